@@ -14,12 +14,9 @@ fn main() {
     // Link node1 -> node 2
     Link::link(&mut node1, &node2, "I link start to end!");
 
-    // Populate the tree
-    tree.nodes.insert(node1.key.clone(), node1);
-    tree.nodes.insert(node2.key.clone(), node2);
-
-    // Set the root node (which also sets current to root since current is not set)
-    tree.set_root_key(root_key).unwrap();
+    // Populate the tree, setting node1 as the root node (which also sets current to root since current is not set)
+    tree.insert_node(node2);
+    tree.set_root_node(node1).unwrap();
 
     // Print the data structure
     println!("\n{:#?}", tree);
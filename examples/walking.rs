@@ -29,13 +29,14 @@ fn walk(mut tree: Tree) {
         println!("{}", current.dialogue);
 
         // End if there's no links to choose
-        if current.links.is_empty() {
+        let links = tree.current_links();
+        if links.is_empty() {
             break 'walk; // Dead end
         }
 
         // Print node links
-        for (id, link) in current.links.iter().enumerate() {
-            println!("[{}] {}", id, link.dialogue);
+        for (id, link) in links.iter().enumerate() {
+            println!("[{}] {}", id, link.text());
         }
 
         // Get user input
@@ -47,7 +48,7 @@ fn walk(mut tree: Tree) {
         if line.trim().eq_ignore_ascii_case("q") {
             break 'walk; // User quit
         } else if let Ok(link_id) = line.parse::<usize>() {
-            if let Some(link) = current.links.get(link_id) {
+            if let Some(link) = links.get(link_id) {
                 let link_key = link.to_key.clone();
                 tree.set_current_key(&link_key).unwrap();
             }
@@ -0,0 +1,124 @@
+//! Relationship meters, or affinities, which track how characters feel about the player.
+//!
+//! The affinity of a character is the integer variable [`AFFINITY_PREFIX`] followed by its id, e.g. `affinity.npc_bob`, which starts at `0`. Links adjust affinities when they are followed with [`Link#affinity`][`crate::Link#structfield.affinity`], conditions test them as `affinity(npc_bob) >= 20`, and hosts adjust them with [`Walker::adjust`][`crate::Walker::adjust`]. Affinity variables need not be declared.
+
+use alloc::{borrow::Cow, collections::BTreeMap, format, string::String};
+
+use crate::value::Value;
+
+/// The prefix of affinity variable names.
+pub const AFFINITY_PREFIX: &str = "affinity.";
+
+/// Returns the name of the variable which holds the affinity of a character.
+///
+/// # Arguments
+///
+/// * `character` - A string slice that holds the id of the character.
+///
+/// # Examples
+///
+/// ```
+/// use convo::affinity;
+/// assert_eq!("affinity.npc_bob", affinity::variable("npc_bob"));
+/// ```
+pub fn variable(character: &str) -> String {
+    format!("{}{}", AFFINITY_PREFIX, character)
+}
+
+/// Returns the id of the character whose affinity a variable holds, or [`None`] if it is not an affinity variable.
+///
+/// # Arguments
+///
+/// * `name` - A string slice that holds the name of a variable.
+///
+/// # Examples
+///
+/// ```
+/// use convo::affinity;
+/// assert_eq!(Some("npc_bob"), affinity::character("affinity.npc_bob"));
+/// assert_eq!(None, affinity::character("gold"));
+/// ```
+pub fn character(name: &str) -> Option<&str> {
+    name.strip_prefix(AFFINITY_PREFIX)
+        .filter(|character| !character.is_empty())
+}
+
+/// Returns the affinity of a character in a set of variable values, which is `0` if it is not set or not an integer.
+///
+/// # Arguments
+///
+/// * `variables` - The values of variables, mapped by name.
+/// * `character` - A string slice that holds the id of the character.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{affinity, Value};
+/// use std::collections::BTreeMap;
+/// let mut variables = BTreeMap::new();
+/// variables.insert("affinity.npc_bob".to_owned(), Value::Int(15));
+/// assert_eq!(15, affinity::get(&variables, "npc_bob"));
+/// assert_eq!(0, affinity::get(&variables, "npc_alice"));
+/// ```
+pub fn get(variables: &BTreeMap<String, Value>, character: &str) -> i64 {
+    match variables.get(&variable(character)) {
+        Some(Value::Int(value)) => *value,
+        _ => 0,
+    }
+}
+
+/// Adjusts the affinity of a character in a set of variable values by an amount, saturating at the bounds of an integer, and returns the new affinity.
+///
+/// # Arguments
+///
+/// * `variables` - The values of variables, mapped by name.
+/// * `character` - A string slice that holds the id of the character.
+/// * `amount` - The amount to add, which is negative to lower the affinity.
+///
+/// # Examples
+///
+/// ```
+/// use convo::affinity;
+/// use std::collections::BTreeMap;
+/// let mut variables = BTreeMap::new();
+/// assert_eq!(5, affinity::adjust(&mut variables, "npc_bob", 5));
+/// assert_eq!(-3, affinity::adjust(&mut variables, "npc_bob", -8));
+/// ```
+pub fn adjust(variables: &mut BTreeMap<String, Value>, character: &str, amount: i64) -> i64 {
+    let value = get(variables, character).saturating_add(amount);
+    variables.insert(variable(character), Value::Int(value));
+    value
+}
+
+// Returns the value of a variable, where affinities which are not set are `0`
+pub(crate) fn lookup<'v>(
+    variables: &'v BTreeMap<String, Value>,
+    name: &str,
+) -> Option<Cow<'v, Value>> {
+    match variables.get(name) {
+        Some(value) => Some(Cow::Borrowed(value)),
+        None => character(name).map(|_| Cow::Owned(Value::Int(0))),
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_adjust() {
+    let mut variables = BTreeMap::new();
+    assert_eq!(
+        Some(Value::Int(0)),
+        lookup(&variables, "affinity.npc_bob").map(Cow::into_owned)
+    );
+    assert_eq!(None, lookup(&variables, "affinity."));
+    assert_eq!(None, lookup(&variables, "gold"));
+
+    // Adjustments saturate
+    assert_eq!(i64::MAX, adjust(&mut variables, "npc_bob", i64::MAX));
+    assert_eq!(i64::MAX, adjust(&mut variables, "npc_bob", 1));
+    assert_eq!(-1, adjust(&mut variables, "npc_bob", i64::MIN));
+
+    // Affinities which are not integers start over
+    variables.insert(variable("npc_alice"), Value::from("fond"));
+    assert_eq!(0, get(&variables, "npc_alice"));
+    assert_eq!(2, adjust(&mut variables, "npc_alice", 2));
+}
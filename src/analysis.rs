@@ -0,0 +1,905 @@
+//! A family of functions which measure the text and structure of [`Tree`]s, e.g. for voice-over budgeting, or to find unintended funnels in branching content.
+
+use alloc::{borrow::ToOwned, collections::BTreeMap, format, string::String, vec, vec::Vec};
+use core::time::Duration;
+
+use crate::{keygen, pacing, tree::Tree};
+
+/// A typical silent reading speed, in words per minute.
+pub const DEFAULT_WORDS_PER_MINUTE: u32 = 200;
+
+/// A [`NodeStats`] measures the text of a single [`crate::Node`] and its links.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct NodeStats {
+    /// The key of the measured node.
+    pub node_key: String,
+
+    /// The number of words in the node dialogue.
+    pub dialogue_words: usize,
+
+    /// The number of words in the dialogue of the node's links.
+    pub link_words: usize,
+
+    /// The length, in characters, of the longest line in the node dialogue or the dialogue of its links.
+    pub longest_line: usize,
+}
+
+impl NodeStats {
+    /// Returns the total number of words in the node dialogue and the dialogue of its links.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{analysis, Link, Node, Tree};
+    /// let mut tree = Tree::new();
+    /// let mut node = Node::new("start", "Hello there!");
+    /// node.links.push(Link::new("start", "Say that again?"));
+    /// tree.insert_node(node);
+    /// assert_eq!(5, analysis::analyze(&tree)[0].words());
+    /// ```
+    pub fn words(&self) -> usize {
+        self.dialogue_words + self.link_words
+    }
+
+    /// Returns the estimated time to read every word of the node and its links.
+    ///
+    /// # Arguments
+    ///
+    /// * `words_per_minute` - The reading speed, e.g. [`DEFAULT_WORDS_PER_MINUTE`]. A speed of 0 is treated as 1.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{analysis, Node, Tree};
+    /// use std::time::Duration;
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "One two three four five."));
+    /// let stats = &analysis::analyze(&tree)[0];
+    /// assert_eq!(Duration::from_secs(3), stats.reading_time(100));
+    /// ```
+    pub fn reading_time(&self, words_per_minute: u32) -> Duration {
+        let millis = self.words() as u64 * 60_000 / u64::from(words_per_minute.max(1));
+        Duration::from_millis(millis)
+    }
+}
+
+/// Returns [`NodeStats`] for every [`crate::Node`] of a [`Tree`], in the order they are stored.
+///
+/// Words are separated by whitespace, and lines by line breaks. Only the text of a node's [`crate::Line`]s is measured, not their speakers.
+///
+/// # Arguments
+///
+/// * `tree` - A [`Tree`] to measure.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{analysis, Node, Tree};
+/// let mut tree = Tree::new();
+/// tree.insert_node(Node::new("start", "Hello there!\nHow are you?"));
+/// let stats = analysis::analyze(&tree);
+/// assert_eq!(5, stats[0].dialogue_words);
+/// assert_eq!(12, stats[0].longest_line);
+/// ```
+pub fn analyze(tree: &Tree) -> Vec<NodeStats> {
+    tree.nodes()
+        .map(|node| {
+            // Speakers of lines are not spoken, so only their text is measured
+            let dialogue = match node.lines.is_empty() {
+                true => vec![&node.dialogue],
+                false => node.lines.iter().map(|line| &line.text).collect(),
+            };
+            let dialogue_words = dialogue.iter().map(|text| count_words(text)).sum();
+            let texts = dialogue
+                .into_iter()
+                .chain(node.links.iter().filter_map(|link| link.dialogue.as_ref()));
+            NodeStats {
+                node_key: node.key.clone(),
+                dialogue_words,
+                link_words: node.links.iter().map(|link| count_words(link.text())).sum(),
+                longest_line: texts
+                    .flat_map(|text| text.lines())
+                    .map(|line| line.chars().count())
+                    .max()
+                    .unwrap_or(0),
+            }
+        })
+        .collect()
+}
+
+/// A [`NodeDominators`] lists the nodes which every path from the root node to a [`crate::Node`] must pass through.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct NodeDominators {
+    /// The key of the dominated node.
+    pub node_key: String,
+
+    /// The keys of the nodes every path from the root node passes through to reach the node, from the root node to its immediate dominator. The node itself is not included, so the root node has none.
+    pub dominators: Vec<String>,
+}
+
+/// Returns [`NodeDominators`] for every [`crate::Node`] of a [`Tree`] which can be reached from the root node, in the order they are stored. Nothing is reachable if the tree has no root set.
+///
+/// Only links between nodes of the tree are followed, so links to missing nodes and jumps to other trees are ignored.
+///
+/// # Arguments
+///
+/// * `tree` - A [`Tree`] to measure.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{analysis, Link, Node, Tree};
+/// let mut tree = Tree::new();
+/// let mut start = Node::new("start", "Hello!");
+/// let mut left = Node::new("left", "Left it is.");
+/// let mut right = Node::new("right", "Right it is.");
+/// let end = Node::new("end", "Goodbye!");
+/// Link::link(&mut start, &left, "Left.");
+/// Link::link(&mut start, &right, "Right.");
+/// Link::link(&mut left, &end, "Bye!");
+/// Link::link(&mut right, &end, "Bye!");
+/// for node in [start, left, right, end] {
+///     tree.insert_node(node);
+/// }
+/// tree.set_root_key("start").unwrap();
+///
+/// // Either branch reaches the end, so only the start must be passed through
+/// let dominators = analysis::dominators(&tree);
+/// assert_eq!("end", dominators[3].node_key);
+/// assert_eq!(vec!["start"], dominators[3].dominators);
+/// ```
+pub fn dominators(tree: &Tree) -> Vec<NodeDominators> {
+    let index = tree.build_index();
+    let root = match tree.root_key().and_then(|key| tree.nodes.get_index_of(key)) {
+        Some(root) => root,
+        None => return vec![],
+    };
+    let children = index.children();
+
+    // Number the reachable nodes in depth-first postorder
+    let mut order = vec![];
+    let mut visited = vec![false; tree.len()];
+    let mut stack = vec![(root, 0)];
+    visited[root] = true;
+    while let Some(&(i, next)) = stack.last() {
+        match children[i].get(next) {
+            Some(&j) => {
+                if let Some(top) = stack.last_mut() {
+                    top.1 += 1;
+                }
+                if !visited[j] {
+                    visited[j] = true;
+                    stack.push((j, 0));
+                }
+            }
+            None => {
+                order.push(i);
+                stack.pop();
+            }
+        }
+    }
+    let mut rank = vec![0; tree.len()];
+    for (r, &i) in order.iter().enumerate() {
+        rank[i] = r;
+    }
+
+    // Find immediate dominators by iterating to a fixed point in reverse postorder, as described by Cooper, Harvey, and Kennedy
+    let mut idom: Vec<Option<usize>> = vec![None; tree.len()];
+    idom[root] = Some(root);
+    let intersect = |idom: &[Option<usize>], mut a: usize, mut b: usize| {
+        while a != b {
+            while rank[a] < rank[b] {
+                a = idom[a].unwrap_or(root);
+            }
+            while rank[b] < rank[a] {
+                b = idom[b].unwrap_or(root);
+            }
+        }
+        a
+    };
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &i in order.iter().rev().filter(|&&i| i != root) {
+            let mut dominator = None;
+            for &parent in &index.parents()[i] {
+                if idom[parent].is_some() {
+                    dominator = Some(match dominator {
+                        Some(dominator) => intersect(&idom, parent, dominator),
+                        None => parent,
+                    });
+                }
+            }
+            if idom[i] != dominator {
+                idom[i] = dominator;
+                changed = true;
+            }
+        }
+    }
+
+    tree.nodes
+        .values()
+        .enumerate()
+        .filter(|(i, _)| visited[*i])
+        .map(|(i, node)| {
+            let mut dominators = vec![];
+            let mut next = i;
+            while next != root {
+                next = idom[next].unwrap_or(root);
+                dominators.push(tree.nodes[next].key.clone());
+            }
+            dominators.reverse();
+            NodeDominators {
+                node_key: node.key.clone(),
+                dominators,
+            }
+        })
+        .collect()
+}
+
+/// Returns the keys of the choke points of a [`Tree`], in the order they are stored: the nodes which can be reached from the root node, and without which the reachable nodes would fall apart into disconnected groups, regardless of the direction of links. These are the articulation points of the link graph.
+///
+/// Content which is meant to branch, but has choke points, funnels every branch through a single node.
+///
+/// # Arguments
+///
+/// * `tree` - A [`Tree`] to measure.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{analysis, Link, Node, Tree};
+/// let mut tree = Tree::new();
+/// let mut start = Node::new("start", "Hello!");
+/// let mut left = Node::new("left", "Left it is.");
+/// let mut right = Node::new("right", "Right it is.");
+/// let mut middle = Node::new("middle", "Anyway...");
+/// let end = Node::new("end", "Goodbye!");
+/// Link::link(&mut start, &left, "Left.");
+/// Link::link(&mut start, &right, "Right.");
+/// Link::link(&mut left, &middle, "Go on.");
+/// Link::link(&mut right, &middle, "Go on.");
+/// Link::link(&mut middle, &end, "Bye!");
+/// for node in [start, left, right, middle, end] {
+///     tree.insert_node(node);
+/// }
+/// tree.set_root_key("start").unwrap();
+/// assert_eq!(vec!["middle"], analysis::choke_points(&tree));
+/// ```
+pub fn choke_points(tree: &Tree) -> Vec<String> {
+    let index = tree.build_index();
+    let root = match tree.root_key().and_then(|key| tree.nodes.get_index_of(key)) {
+        Some(root) => root,
+        None => return vec![],
+    };
+    let reachable = index.reachable();
+
+    // Links are followed in either direction, between reachable nodes
+    let neighbors: Vec<Vec<usize>> = (0..tree.len())
+        .map(|i| {
+            let mut neighbors: Vec<usize> = index.children()[i]
+                .iter()
+                .chain(&index.parents()[i])
+                .copied()
+                .filter(|&j| j != i && reachable[j])
+                .collect();
+            neighbors.sort_unstable();
+            neighbors.dedup();
+            neighbors
+        })
+        .collect();
+
+    // Tarjan's depth-first search for articulation points, comparing the discovery time of each node to the lowest discovery time its subtree can reach
+    const UNDISCOVERED: usize = usize::MAX;
+    let mut discovered = vec![UNDISCOVERED; tree.len()];
+    let mut low = vec![0; tree.len()];
+    let mut is_choke_point = vec![false; tree.len()];
+    let mut root_children = 0;
+    let mut time = 0;
+    discovered[root] = 0;
+    let mut stack = vec![(root, root, 0)];
+    while let Some(&(i, parent, next)) = stack.last() {
+        match neighbors[i].get(next) {
+            Some(&j) => {
+                if let Some(top) = stack.last_mut() {
+                    top.2 += 1;
+                }
+                if discovered[j] == UNDISCOVERED {
+                    time += 1;
+                    discovered[j] = time;
+                    low[j] = time;
+                    stack.push((j, i, 0));
+                    if i == root {
+                        root_children += 1;
+                    }
+                } else if j != parent {
+                    low[i] = low[i].min(discovered[j]);
+                }
+            }
+            None => {
+                stack.pop();
+                if i != root {
+                    low[parent] = low[parent].min(low[i]);
+                    if parent != root && low[i] >= discovered[parent] {
+                        is_choke_point[parent] = true;
+                    }
+                }
+            }
+        }
+    }
+    is_choke_point[root] = root_children > 1;
+
+    tree.nodes
+        .values()
+        .zip(is_choke_point)
+        .filter(|(_, is_choke_point)| *is_choke_point)
+        .map(|(node, _)| node.key.clone())
+        .collect()
+}
+
+/// An [`EndingDepth`] measures how far an ending [`crate::Node`] is from the root node, in links followed, e.g. to find endings which take too many choices to reach.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct EndingDepth {
+    /// The key of the ending node.
+    pub node_key: String,
+
+    /// The fewest links followed from the root node to reach the ending.
+    pub shortest: usize,
+
+    /// The most links followed from the root node to reach the ending without looping.
+    pub longest: usize,
+
+    /// The keys of the nodes along a shortest path, from the root node to the ending.
+    pub path: Vec<String>,
+}
+
+/// Returns an [`EndingDepth`] for every ending of a [`Tree`] which can be reached from the root node, in the order they are stored. Endings are nodes marked [`end`][`crate::Node#structfield.end`], or without links. Nothing is reachable if the tree has no root set.
+///
+/// The longest path never loops: links are explored in authored order, and links back to a node already on the path are not followed. Only links between nodes of the tree are followed, so links to missing nodes and jumps to other trees are ignored.
+///
+/// # Arguments
+///
+/// * `tree` - A [`Tree`] to measure.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{analysis, Link, Node, Tree};
+/// let mut tree = Tree::new();
+/// let mut start = Node::new("start", "Hello!");
+/// let mut small_talk = Node::new("small_talk", "Nice weather.");
+/// let mut end = Node::new("end", "Goodbye!");
+/// end.end = true;
+/// Link::link(&mut start, &small_talk, "Nice weather, huh?");
+/// Link::link(&mut start, &end, "Bye!");
+/// Link::link(&mut small_talk, &end, "Bye!");
+/// for node in [start, small_talk, end] {
+///     tree.insert_node(node);
+/// }
+/// tree.set_root_key("start").unwrap();
+///
+/// let depths = analysis::ending_depths(&tree);
+/// assert_eq!("end", depths[0].node_key);
+/// assert_eq!(1, depths[0].shortest);
+/// assert_eq!(2, depths[0].longest);
+/// assert_eq!(vec!["start", "end"], depths[0].path);
+/// ```
+pub fn ending_depths(tree: &Tree) -> Vec<EndingDepth> {
+    let index = tree.build_index();
+    let root = match tree.root_key().and_then(|key| tree.nodes.get_index_of(key)) {
+        Some(root) => root,
+        None => return vec![],
+    };
+    let children = index.children();
+
+    // Breadth-first search finds the shortest paths
+    let mut previous: Vec<Option<usize>> = vec![None; tree.len()];
+    let mut shortest = vec![usize::MAX; tree.len()];
+    shortest[root] = 0;
+    let mut queue = vec![root];
+    let mut next = 0;
+    while let Some(&i) = queue.get(next) {
+        next += 1;
+        for &j in &children[i] {
+            if shortest[j] == usize::MAX {
+                shortest[j] = shortest[i] + 1;
+                previous[j] = Some(i);
+                queue.push(j);
+            }
+        }
+    }
+
+    // Depth-first search drops the links back into loops, leaving the longest paths to be found in reverse postorder
+    let mut on_path = vec![false; tree.len()];
+    let mut visited = vec![false; tree.len()];
+    let mut forward: Vec<Vec<usize>> = vec![vec![]; tree.len()];
+    let mut order = vec![];
+    let mut stack = vec![(root, 0)];
+    visited[root] = true;
+    on_path[root] = true;
+    while let Some(&(i, next)) = stack.last() {
+        match children[i].get(next) {
+            Some(&j) => {
+                if let Some(top) = stack.last_mut() {
+                    top.1 += 1;
+                }
+                if !on_path[j] {
+                    forward[i].push(j);
+                }
+                if !visited[j] {
+                    visited[j] = true;
+                    on_path[j] = true;
+                    stack.push((j, 0));
+                }
+            }
+            None => {
+                on_path[i] = false;
+                order.push(i);
+                stack.pop();
+            }
+        }
+    }
+    let mut longest = vec![0; tree.len()];
+    for &i in order.iter().rev() {
+        for &j in &forward[i] {
+            longest[j] = longest[j].max(longest[i] + 1);
+        }
+    }
+
+    tree.nodes
+        .values()
+        .enumerate()
+        .filter(|(i, node)| visited[*i] && (node.end || node.links.is_empty()))
+        .map(|(i, node)| {
+            let mut path = vec![node.key.clone()];
+            let mut next = i;
+            while let Some(j) = previous[next] {
+                path.push(tree.nodes[j].key.clone());
+                next = j;
+            }
+            path.reverse();
+            EndingDepth {
+                node_key: node.key.clone(),
+                shortest: shortest[i],
+                longest: longest[i],
+                path,
+            }
+        })
+        .collect()
+}
+
+/// A [`DialogueLocation`] is where a piece of dialogue appears in a [`Tree`].
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum DialogueLocation {
+    /// The dialogue of the node with the given key.
+    Node(String),
+    /// The text of the line with the given index, of the node with the given key.
+    Line(String, usize),
+    /// The dialogue of the link with the given index, of the node with the given key.
+    Link(String, usize),
+}
+
+impl DialogueLocation {
+    /// Returns the key of the node the dialogue belongs to.
+    pub fn node_key(&self) -> &str {
+        match self {
+            DialogueLocation::Node(key)
+            | DialogueLocation::Line(key, _)
+            | DialogueLocation::Link(key, _) => key,
+        }
+    }
+}
+
+/// A [`Consolidation`] suggests how duplicated dialogue could be written once.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum Consolidation {
+    /// Every occurrence is the identical dialogue of a whole node, so links could lead to one shared node instead.
+    SharedNode,
+    /// The occurrences could share one localization key, e.g. an entry of a string table, which is suggested from the text.
+    SharedKey(String),
+}
+
+/// A [`DuplicateDialogue`] groups dialogue which is identical, or identical once normalized with [`normalize_dialogue`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DuplicateDialogue {
+    /// The distinct spellings of the dialogue, in the order they first appear. There is one unless the duplicates only match once normalized.
+    pub texts: Vec<String>,
+
+    /// Where the dialogue appears, in the order nodes are stored.
+    pub locations: Vec<DialogueLocation>,
+
+    /// How the dialogue could be consolidated.
+    pub suggestion: Consolidation,
+}
+
+impl DuplicateDialogue {
+    /// Returns whether every occurrence is spelled identically.
+    pub fn is_exact(&self) -> bool {
+        self.texts.len() == 1
+    }
+}
+
+/// Returns the form of dialogue which near-identical dialogue shares: without pause commands or punctuation, in lowercase, and with words separated by single spaces.
+///
+/// # Arguments
+///
+/// * `text` - A string slice that holds the dialogue.
+///
+/// # Examples
+///
+/// ```
+/// use convo::analysis;
+/// assert_eq!("well no", analysis::normalize_dialogue("Well{pause 500}...  No!"));
+/// ```
+pub fn normalize_dialogue(text: &str) -> String {
+    let words: Vec<String> = pacing::strip(text)
+        .split_whitespace()
+        .map(|word| {
+            word.chars()
+                .filter(|c| c.is_alphanumeric())
+                .flat_map(char::to_lowercase)
+                .collect::<String>()
+        })
+        .filter(|word| !word.is_empty())
+        .collect();
+    words.join(" ")
+}
+
+/// Returns the [`DuplicateDialogue`]s of a [`Tree`], i.e. dialogue of nodes, lines, or links which appears more than once, in the order each first appears. Copies drift apart as they are edited, and each is translated separately, so duplicates are worth writing once.
+///
+/// Dialogue is compared once normalized with [`normalize_dialogue`], so differences of case, whitespace, or punctuation are reported too. Dialogue without words is ignored, and the text of a node's [`crate::Line`]s is compared rather than its dialogue.
+///
+/// # Arguments
+///
+/// * `tree` - A [`Tree`] to search.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{analysis::{self, Consolidation, DialogueLocation}, Link, Node, Tree};
+/// let mut tree = Tree::new();
+/// let mut start = Node::new("start", "Well?");
+/// start.links.push(Link::new("start", "Goodbye."));
+/// let mut shop = Node::new("shop", "Anything else?");
+/// shop.links.push(Link::new("start", "goodbye!"));
+/// tree.insert_node(start);
+/// tree.insert_node(shop);
+///
+/// let duplicates = analysis::duplicates(&tree);
+/// assert_eq!(1, duplicates.len());
+/// assert_eq!(vec!["Goodbye.", "goodbye!"], duplicates[0].texts);
+/// assert_eq!(DialogueLocation::Link("shop".into(), 0), duplicates[0].locations[1]);
+/// assert_eq!(Consolidation::SharedKey("goodbye".into()), duplicates[0].suggestion);
+/// ```
+pub fn duplicates(tree: &Tree) -> Vec<DuplicateDialogue> {
+    // Groups of (texts, locations), in the order they first appear, indexed by normalized dialogue
+    let mut groups: Vec<(Vec<&str>, Vec<DialogueLocation>)> = Vec::new();
+    let mut indices: BTreeMap<String, usize> = BTreeMap::new();
+    for node in tree.nodes() {
+        let texts = match node.lines.is_empty() {
+            true => vec![(DialogueLocation::Node(node.key.clone()), &node.dialogue)],
+            false => node
+                .lines
+                .iter()
+                .enumerate()
+                .map(|(i, line)| (DialogueLocation::Line(node.key.clone(), i), &line.text))
+                .collect(),
+        };
+        let links = node.links.iter().enumerate().filter_map(|(i, link)| {
+            let dialogue = link.dialogue.as_ref()?;
+            Some((DialogueLocation::Link(node.key.clone(), i), dialogue))
+        });
+        for (location, text) in texts.into_iter().chain(links) {
+            let normalized = normalize_dialogue(text);
+            if normalized.is_empty() {
+                continue;
+            }
+            let index = *indices.entry(normalized).or_insert_with(|| {
+                groups.push((Vec::new(), Vec::new()));
+                groups.len() - 1
+            });
+            let (texts, locations) = &mut groups[index];
+            if !texts.contains(&text.as_str()) {
+                texts.push(text);
+            }
+            locations.push(location);
+        }
+    }
+    groups
+        .into_iter()
+        .filter(|(_, locations)| locations.len() > 1)
+        .map(|(texts, locations)| {
+            let whole_nodes = locations
+                .iter()
+                .all(|location| matches!(location, DialogueLocation::Node(_)));
+            let suggestion = match texts.len() == 1 && whole_nodes {
+                true => Consolidation::SharedNode,
+                false => Consolidation::SharedKey(keygen::slugify(texts[0])),
+            };
+            DuplicateDialogue {
+                texts: texts.into_iter().map(ToOwned::to_owned).collect(),
+                locations,
+                suggestion,
+            }
+        })
+        .collect()
+}
+
+/// Returns [`NodeStats`] as CSV, with a header row and one row per node. Reading times are given in seconds.
+///
+/// # Arguments
+///
+/// * `stats` - The [`NodeStats`] to write, e.g. from [`analyze`].
+/// * `words_per_minute` - The reading speed used to estimate reading times.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{analysis, Node, Tree};
+/// let mut tree = Tree::new();
+/// tree.insert_node(Node::new("start", "One two three four five."));
+/// let csv = analysis::to_csv(&analysis::analyze(&tree), 100);
+/// assert_eq!(
+///     "node,dialogue_words,link_words,words,reading_seconds,longest_line\nstart,5,0,5,3.000,24\n",
+///     csv
+/// );
+/// ```
+pub fn to_csv(stats: &[NodeStats], words_per_minute: u32) -> String {
+    let mut csv = "node,dialogue_words,link_words,words,reading_seconds,longest_line\n".to_owned();
+    for stat in stats {
+        let reading_time = stat.reading_time(words_per_minute);
+        csv.push_str(&format!(
+            "{},{},{},{},{}.{:03},{}\n",
+            csv_field(&stat.node_key),
+            stat.dialogue_words,
+            stat.link_words,
+            stat.words(),
+            reading_time.as_secs(),
+            reading_time.subsec_millis(),
+            stat.longest_line
+        ));
+    }
+    csv
+}
+
+fn count_words(text: &str) -> usize {
+    text.split_whitespace().count()
+}
+
+pub(crate) fn csv_field(field: &str) -> String {
+    // Quote fields containing separators, quotes, or line breaks, doubling any quotes
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_analyze() {
+    use crate::{link::Link, node::Node};
+
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "  Hello   there!\nA much longer second line.  ");
+    start.links.push(Link::new("end", "Goodbye."));
+    start.links.push(Link::new("end", "See you later, friend!"));
+    tree.insert_node(start);
+    tree.insert_node(Node::new("end", ""));
+
+    let stats = analyze(&tree);
+    assert_eq!(2, stats.len());
+    assert_eq!("start", stats[0].node_key);
+    assert_eq!(7, stats[0].dialogue_words);
+    assert_eq!(5, stats[0].link_words);
+    assert_eq!(28, stats[0].longest_line);
+    assert_eq!(Duration::from_millis(3600), stats[0].reading_time(200));
+
+    // Empty nodes measure nothing
+    assert_eq!(0, stats[1].words());
+    assert_eq!(0, stats[1].longest_line);
+    assert_eq!(Duration::ZERO, stats[1].reading_time(0));
+}
+
+#[test]
+fn test_to_csv() {
+    let stats = [NodeStats {
+        node_key: "a \"quoted\", key".to_owned(),
+        dialogue_words: 1,
+        link_words: 2,
+        longest_line: 4,
+    }];
+    assert_eq!(
+        "node,dialogue_words,link_words,words,reading_seconds,longest_line\n\"a \"\"quoted\"\", key\",1,2,3,0.900,4\n",
+        to_csv(&stats, DEFAULT_WORDS_PER_MINUTE)
+    );
+}
+
+#[test]
+fn test_dominators() {
+    use crate::{link::Link, node::Node};
+
+    // start -> a -> b -> a, start -> c -> b, b -> end, with an orphan and a dangling link
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "Start");
+    let mut a = Node::new("a", "A");
+    let mut b = Node::new("b", "B");
+    let mut c = Node::new("c", "C");
+    let end = Node::new("end", "End");
+    let mut orphan = Node::new("orphan", "Orphan");
+    Link::link(&mut start, &a, "To A");
+    Link::link(&mut start, &c, "To C");
+    Link::link(&mut a, &b, "To B");
+    Link::link(&mut b, &a, "Back to A");
+    Link::link(&mut c, &b, "To B");
+    Link::link(&mut b, &end, "To end");
+    Link::link(&mut orphan, &end, "To end");
+    start.links.push(Link::new("missing", "Nowhere"));
+    for node in [start, a, b, c, end, orphan] {
+        tree.insert_node(node);
+    }
+
+    // Nothing is reachable without a root
+    assert!(dominators(&tree).is_empty());
+    assert!(choke_points(&tree).is_empty());
+
+    tree.set_root_key("start").unwrap();
+    let found = dominators(&tree);
+    let dominators: Vec<(&str, Vec<&str>)> = found
+        .iter()
+        .map(|d| {
+            (
+                d.node_key.as_str(),
+                d.dominators.iter().map(String::as_str).collect(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        vec![
+            ("start", vec![]),
+            ("a", vec!["start"]),
+            ("b", vec!["start"]),
+            ("c", vec!["start"]),
+            ("end", vec!["start", "b"]),
+        ],
+        dominators
+    );
+
+    // Only `b` holds `end` to the rest, as `a` and `c` are both linked to `b`
+    assert_eq!(vec!["b"], choke_points(&tree));
+}
+
+#[test]
+fn test_choke_points() {
+    use crate::{link::Link, node::Node};
+
+    // A chain passes through every node between its ends
+    let keys = ["start", "a", "b", "end"];
+    let mut nodes: Vec<Node> = keys.iter().map(|key| Node::new(*key, "Hi")).collect();
+    for i in 0..nodes.len() - 1 {
+        let next = nodes[i + 1].clone();
+        Link::link(&mut nodes[i], &next, "Next");
+    }
+    let mut tree = Tree::new();
+    for node in nodes.iter().cloned() {
+        tree.insert_node(node);
+    }
+    tree.set_root_key("start").unwrap();
+    assert_eq!(vec!["a", "b"], choke_points(&tree));
+    assert_eq!(vec!["start", "a", "b"], dominators(&tree)[3].dominators);
+
+    // A root with separate branches is a choke point, and loops back to it are not
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "Hi");
+    let mut left = Node::new("left", "Left");
+    let right = Node::new("right", "Right");
+    Link::link(&mut start, &left, "Left");
+    Link::link(&mut start, &right, "Right");
+    Link::link(&mut left, &start, "Back");
+    for node in [start, left, right] {
+        tree.insert_node(node);
+    }
+    tree.set_root_key("start").unwrap();
+    assert_eq!(vec!["start"], choke_points(&tree));
+}
+
+#[test]
+fn test_ending_depths() {
+    use crate::{link::Link, node::Node};
+
+    // start -> a -> b -> end, start -> b, b -> a, a -> good, where `good` has links but is marked as an ending
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "Start");
+    let mut a = Node::new("a", "A");
+    let mut b = Node::new("b", "B");
+    let end = Node::new("end", "End");
+    let mut good = Node::new("good", "Good");
+    good.end = true;
+    Link::link(&mut start, &a, "To A");
+    Link::link(&mut start, &b, "To B");
+    Link::link(&mut a, &b, "To B");
+    Link::link(&mut a, &good, "To good");
+    Link::link(&mut b, &a, "Back to A");
+    Link::link(&mut b, &end, "To end");
+    Link::link(&mut good, &start, "Again");
+    for node in [start, a, b, end, good] {
+        tree.insert_node(node);
+    }
+    tree.insert_node(Node::new("orphan", "Orphan"));
+
+    // Nothing is reachable without a root
+    assert!(ending_depths(&tree).is_empty());
+
+    // Loops are not followed, and unreachable endings are not reported
+    tree.set_root_key("start").unwrap();
+    let depths = ending_depths(&tree);
+    assert_eq!(2, depths.len());
+    assert_eq!("end", depths[0].node_key);
+    assert_eq!((2, 3), (depths[0].shortest, depths[0].longest));
+    assert_eq!(vec!["start", "b", "end"], depths[0].path);
+    assert_eq!("good", depths[1].node_key);
+    // `b -> a` loops back once `start -> a -> b` is explored, so it is never followed
+    assert_eq!((2, 2), (depths[1].shortest, depths[1].longest));
+    assert_eq!(vec!["start", "a", "good"], depths[1].path);
+
+    // A root without links is an ending of its own
+    let mut tree = Tree::new();
+    tree.insert_node(Node::new("start", "Bye."));
+    tree.set_root_key("start").unwrap();
+    let depths = ending_depths(&tree);
+    assert_eq!((0, 0), (depths[0].shortest, depths[0].longest));
+    assert_eq!(vec!["start"], depths[0].path);
+}
+
+#[test]
+fn test_duplicates() {
+    use crate::{
+        link::Link,
+        node::{Line, Node},
+    };
+
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "The gate is shut.");
+    start.links.push(Link::new("gate", "Open it."));
+    start.links.push(Link::new("gate", "..."));
+    let mut gate = Node::new("gate", "");
+    gate.set_lines(vec![
+        Line::new("guard", "Open it?"),
+        Line::new("you", "The gate is shut."),
+    ]);
+    gate.links.push(Link::new("start", "..."));
+    tree.insert_node(start);
+    tree.insert_node(gate);
+    tree.insert_node(Node::new("again", "The gate is shut."));
+    tree.insert_node(Node::new("shut", "The gate is shut."));
+
+    let found = duplicates(&tree);
+    assert_eq!(2, found.len());
+
+    // Dialogue of lines is compared by text, and is not a whole node
+    assert!(found[0].is_exact());
+    assert_eq!(
+        vec![
+            DialogueLocation::Node("start".into()),
+            DialogueLocation::Line("gate".into(), 1),
+            DialogueLocation::Node("again".into()),
+            DialogueLocation::Node("shut".into()),
+        ],
+        found[0].locations
+    );
+    assert_eq!(
+        Consolidation::SharedKey("the_gate_is_shut".into()),
+        found[0].suggestion
+    );
+
+    // Punctuation is ignored, and dialogue without words is never a duplicate
+    assert!(!found[1].is_exact());
+    assert_eq!(vec!["Open it.", "Open it?"], found[1].texts);
+    assert_eq!("gate", found[1].locations[1].node_key());
+
+    // Identical dialogue of whole nodes could be one node
+    let mut tree = Tree::new();
+    tree.insert_node(Node::new("a", "Farewell."));
+    tree.insert_node(Node::new("b", "Farewell."));
+    assert_eq!(Consolidation::SharedNode, duplicates(&tree)[0].suggestion);
+}
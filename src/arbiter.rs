@@ -0,0 +1,556 @@
+//! Arbitration between concurrent conversations, e.g. ambient barks and scripted scenes, where only the conversation of highest priority is active.
+//!
+//! An [`Arbiter`] holds the [`Walker`]s of several conversations, each started with a priority and an [`Interrupt`] policy. Starting a conversation of higher priority than the active one interrupts it, which is paused until the new conversation ends, or aborted with the reason [`INTERRUPTED`]. A conversation started below the active one's priority waits, or is aborted, likewise. The host reads what happened as [`ArbiterEvent`]s, e.g. to hide a paused conversation's UI.
+
+use alloc::{
+    collections::VecDeque,
+    vec::{self, Vec},
+};
+use core::ops::Deref;
+
+use crate::{
+    tree::Tree,
+    walker::{Status, Walker},
+};
+
+/// The reason walkers are aborted with when their conversation is interrupted and its [`Interrupt`] policy is [`Interrupt::Abort`].
+pub const INTERRUPTED: &str = "interrupted";
+
+/// The id of a conversation held by an [`Arbiter`], unique among the conversations it has started.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct ConversationId(u64);
+
+/// An [`Interrupt`] decides what happens to a conversation which cannot be active, because a conversation of higher priority is.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+pub enum Interrupt {
+    /// The conversation is paused, and resumes when no conversation of higher priority remains, e.g. for scripted scenes.
+    #[default]
+    Pause,
+    /// The conversation's walker is aborted with the reason [`INTERRUPTED`], and the conversation ends, e.g. for ambient barks.
+    Abort,
+}
+
+/// An [`ArbiterEvent`] is a change of a conversation held by an [`Arbiter`], for the host to present. See also: [`Arbiter::events`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum ArbiterEvent {
+    /// The conversation became active for the first time.
+    Started(ConversationId),
+    /// The conversation was interrupted, or could not start, and waits until no conversation of higher priority remains.
+    Paused(ConversationId),
+    /// The paused conversation became active again.
+    Resumed(ConversationId),
+    /// The conversation was interrupted, or could not start, and its walker was aborted.
+    Aborted(ConversationId),
+    /// The conversation finished, or its walker was aborted by the host.
+    Ended(ConversationId),
+}
+
+/// A conversation held by an arbiter.
+#[derive(Debug, Clone)]
+struct Conversation<T>
+where
+    T: Deref<Target = Tree>,
+{
+    id: ConversationId,
+    priority: i32,
+    interrupt: Interrupt,
+
+    /// Whether the conversation has been active.
+    started: bool,
+    walker: Walker<T>,
+}
+
+/// An [`Arbiter`] manages the [`Walker`]s of concurrent conversations, keeping active only the conversation of highest priority. Among conversations of equal priority, the one which was active first keeps the floor, and paused conversations resume in the order they were started.
+///
+/// # Examples
+///
+/// ```
+/// use convo::arbiter::{Arbiter, ArbiterEvent, Interrupt};
+/// use convo::{Node, Tree, Walker};
+/// let mut tree = Tree::new();
+/// tree.insert_node(Node::new("start", "Hello!"));
+/// tree.set_root_key("start").unwrap();
+///
+/// let mut arbiter = Arbiter::new();
+/// let bark = arbiter.start(Walker::new(&tree).unwrap(), 0, Interrupt::Abort);
+/// let scene = arbiter.start(Walker::new(&tree).unwrap(), 10, Interrupt::Pause);
+/// assert_eq!(Some(scene), arbiter.active());
+/// assert_eq!(
+///     vec![
+///         ArbiterEvent::Started(bark),
+///         ArbiterEvent::Aborted(bark),
+///         ArbiterEvent::Started(scene),
+///     ],
+///     arbiter.events().collect::<Vec<_>>()
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct Arbiter<T>
+where
+    T: Deref<Target = Tree>,
+{
+    /// The conversations held, in the order they were started.
+    conversations: Vec<Conversation<T>>,
+
+    /// The id of the active conversation, if any.
+    active: Option<ConversationId>,
+
+    /// The walkers of conversations which ended or were aborted, which the host has not taken.
+    ended: Vec<(ConversationId, Walker<T>)>,
+
+    /// The events the host has not read, oldest first.
+    events: VecDeque<ArbiterEvent>,
+
+    /// The id of the next conversation started.
+    next_id: u64,
+}
+
+impl<T> Default for Arbiter<T>
+where
+    T: Deref<Target = Tree>,
+{
+    fn default() -> Self {
+        Arbiter::new()
+    }
+}
+
+impl<T> Arbiter<T>
+where
+    T: Deref<Target = Tree>,
+{
+    /// Returns an [`Arbiter`] without conversations.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{arbiter::Arbiter, Tree};
+    /// let arbiter: Arbiter<&Tree> = Arbiter::new();
+    /// assert!(arbiter.is_empty());
+    /// ```
+    pub fn new() -> Self {
+        Arbiter {
+            conversations: Vec::new(),
+            active: None,
+            ended: Vec::new(),
+            events: VecDeque::new(),
+            next_id: 0,
+        }
+    }
+
+    /// Start a conversation, returning its id. It becomes active if its priority is higher than the active conversation's, which is interrupted. Otherwise it waits or is aborted, by its [`Interrupt`] policy.
+    ///
+    /// # Arguments
+    ///
+    /// * `walker` - The [`Walker`] of the conversation.
+    /// * `priority` - The priority of the conversation, where higher priorities interrupt lower ones.
+    /// * `interrupt` - What happens to the conversation while one of higher priority is active.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::arbiter::{Arbiter, Interrupt};
+    /// use convo::{Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.set_root_key("start").unwrap();
+    ///
+    /// let mut arbiter = Arbiter::new();
+    /// let scene = arbiter.start(Walker::new(&tree).unwrap(), 10, Interrupt::Pause);
+    /// let bark = arbiter.start(Walker::new(&tree).unwrap(), 0, Interrupt::Abort);
+    /// assert_eq!(Some(scene), arbiter.active());
+    /// assert!(arbiter.walker(bark).is_none());
+    /// ```
+    pub fn start(
+        &mut self,
+        walker: Walker<T>,
+        priority: i32,
+        interrupt: Interrupt,
+    ) -> ConversationId {
+        let id = ConversationId(self.next_id);
+        self.next_id += 1;
+        self.conversations.push(Conversation {
+            id,
+            priority,
+            interrupt,
+            started: false,
+            walker,
+        });
+
+        match self
+            .active_conversation()
+            .map(|active| (active.id, active.priority))
+        {
+            Some((_, active_priority)) if active_priority >= priority => self.interrupt(id),
+            Some((active_id, _)) => {
+                self.active = None;
+                self.interrupt(active_id);
+                self.activate();
+            }
+            None => self.activate(),
+        }
+        id
+    }
+
+    /// Returns the id of the active conversation, or [`None`] if no conversation is held.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{arbiter::Arbiter, Tree};
+    /// let arbiter: Arbiter<&Tree> = Arbiter::new();
+    /// assert!(arbiter.active().is_none());
+    /// ```
+    pub fn active(&self) -> Option<ConversationId> {
+        self.active
+    }
+
+    /// Returns the [`Walker`] of the active conversation, or [`None`] if no conversation is held. Only the active conversation should be walked.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::arbiter::{Arbiter, Interrupt};
+    /// use convo::{Link, Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// let mut start = Node::new("start", "Hello!");
+    /// let end = Node::new("end", "Goodbye!");
+    /// Link::link(&mut start, &end, "Bye!");
+    /// tree.insert_node(start);
+    /// tree.insert_node(end);
+    /// tree.set_root_key("start").unwrap();
+    ///
+    /// let mut arbiter = Arbiter::new();
+    /// arbiter.start(Walker::new(&tree).unwrap(), 0, Interrupt::Pause);
+    /// let walker = arbiter.active_walker().unwrap();
+    /// assert_eq!("Goodbye!", walker.choose(0).unwrap().dialogue);
+    /// ```
+    pub fn active_walker(&mut self) -> Option<&mut Walker<T>> {
+        let id = self.active?;
+        self.walker_mut(id)
+    }
+
+    /// Returns the [`Walker`] of a conversation held, whether active or paused.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the conversation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::arbiter::{Arbiter, Interrupt};
+    /// use convo::{Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let mut arbiter = Arbiter::new();
+    /// let id = arbiter.start(Walker::new(&tree).unwrap(), 0, Interrupt::Pause);
+    /// assert_eq!("start", arbiter.walker(id).unwrap().current_key());
+    /// ```
+    pub fn walker(&self, id: ConversationId) -> Option<&Walker<T>> {
+        self.conversations
+            .iter()
+            .find(|conversation| conversation.id == id)
+            .map(|conversation| &conversation.walker)
+    }
+
+    /// Returns the mutable [`Walker`] of a conversation held, whether active or paused.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the conversation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::arbiter::{Arbiter, Interrupt};
+    /// use convo::{Node, Tree, Value, Walker};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let mut arbiter = Arbiter::new();
+    /// let id = arbiter.start(Walker::new(&tree).unwrap(), 0, Interrupt::Pause);
+    /// let walker = arbiter.walker_mut(id).unwrap();
+    /// walker.variables_mut().insert("met".into(), Value::Bool(true));
+    /// ```
+    pub fn walker_mut(&mut self, id: ConversationId) -> Option<&mut Walker<T>> {
+        self.conversations
+            .iter_mut()
+            .find(|conversation| conversation.id == id)
+            .map(|conversation| &mut conversation.walker)
+    }
+
+    /// Returns whether a conversation is held but not active.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the conversation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::arbiter::{Arbiter, Interrupt};
+    /// use convo::{Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let mut arbiter = Arbiter::new();
+    /// let chatter = arbiter.start(Walker::new(&tree).unwrap(), 0, Interrupt::Pause);
+    /// let scene = arbiter.start(Walker::new(&tree).unwrap(), 1, Interrupt::Pause);
+    /// assert!(arbiter.is_paused(chatter));
+    /// assert!(!arbiter.is_paused(scene));
+    /// ```
+    pub fn is_paused(&self, id: ConversationId) -> bool {
+        self.active != Some(id) && self.walker(id).is_some()
+    }
+
+    /// End a conversation, whether active or paused, returning its [`Walker`], or [`None`] if it is not held. If it was active, the paused conversation of highest priority resumes.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the conversation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::arbiter::{Arbiter, Interrupt};
+    /// use convo::{Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let mut arbiter = Arbiter::new();
+    /// let chatter = arbiter.start(Walker::new(&tree).unwrap(), 0, Interrupt::Pause);
+    /// let scene = arbiter.start(Walker::new(&tree).unwrap(), 1, Interrupt::Pause);
+    /// assert!(arbiter.end(scene).is_some());
+    /// assert_eq!(Some(chatter), arbiter.active());
+    /// ```
+    pub fn end(&mut self, id: ConversationId) -> Option<Walker<T>> {
+        let index = self
+            .conversations
+            .iter()
+            .position(|conversation| conversation.id == id)?;
+        let conversation = self.conversations.remove(index);
+        self.events.push_back(ArbiterEvent::Ended(id));
+        if self.active == Some(id) {
+            self.active = None;
+            self.activate();
+        }
+        Some(conversation.walker)
+    }
+
+    /// End the active conversation if its walker is finished or aborted, and resume the paused conversation of highest priority, e.g. once per frame after the active walker was walked. The walkers of ended conversations are kept for [`take_ended`][`Arbiter#method.take_ended`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::arbiter::{Arbiter, Interrupt};
+    /// use convo::{Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let mut arbiter = Arbiter::new();
+    /// arbiter.start(Walker::new(&tree).unwrap(), 0, Interrupt::Pause);
+    /// arbiter.update();
+    /// assert!(arbiter.is_empty());
+    /// assert_eq!(1, arbiter.take_ended().count());
+    /// ```
+    pub fn update(&mut self) {
+        while let Some(active) = self.active_conversation() {
+            if !matches!(active.walker.status(), Status::Finished | Status::Aborted) {
+                break;
+            }
+            let id = active.id;
+            if let Some(walker) = self.end(id) {
+                self.ended.push((id, walker));
+            }
+        }
+    }
+
+    /// Returns an iterator over the ids and [`Walker`]s of conversations which were ended by [`update`][`Arbiter#method.update`] or aborted when interrupted, in the order they ended, and forgets them. Their walkers may be recorded as [`crate::ResumePoints`], for example.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::arbiter::{Arbiter, Interrupt, INTERRUPTED};
+    /// use convo::{Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let mut arbiter = Arbiter::new();
+    /// let bark = arbiter.start(Walker::new(&tree).unwrap(), 0, Interrupt::Abort);
+    /// arbiter.start(Walker::new(&tree).unwrap(), 1, Interrupt::Pause);
+    /// let (id, walker) = arbiter.take_ended().next().unwrap();
+    /// assert_eq!(bark, id);
+    /// assert_eq!(Some(INTERRUPTED), walker.abort_reason());
+    /// ```
+    pub fn take_ended(&mut self) -> vec::IntoIter<(ConversationId, Walker<T>)> {
+        core::mem::take(&mut self.ended).into_iter()
+    }
+
+    /// Returns an iterator over the [`ArbiterEvent`]s since they were last read, oldest first, and forgets them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::arbiter::{Arbiter, ArbiterEvent, Interrupt};
+    /// use convo::{Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let mut arbiter = Arbiter::new();
+    /// let id = arbiter.start(Walker::new(&tree).unwrap(), 0, Interrupt::Pause);
+    /// assert_eq!(Some(ArbiterEvent::Started(id)), arbiter.events().next());
+    /// assert!(arbiter.events().next().is_none());
+    /// ```
+    pub fn events(&mut self) -> impl Iterator<Item = ArbiterEvent> + '_ {
+        self.events.drain(..)
+    }
+
+    /// Returns the number of conversations held, whether active or paused.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{arbiter::Arbiter, Tree};
+    /// let arbiter: Arbiter<&Tree> = Arbiter::new();
+    /// assert_eq!(0, arbiter.len());
+    /// ```
+    pub fn len(&self) -> usize {
+        self.conversations.len()
+    }
+
+    /// Returns whether no conversations are held.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{arbiter::Arbiter, Tree};
+    /// let arbiter: Arbiter<&Tree> = Arbiter::new();
+    /// assert!(arbiter.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.conversations.is_empty()
+    }
+
+    fn active_conversation(&self) -> Option<&Conversation<T>> {
+        let id = self.active?;
+        self.conversations
+            .iter()
+            .find(|conversation| conversation.id == id)
+    }
+
+    // Pauses a conversation which cannot be active, or aborts and removes it, by its policy
+    fn interrupt(&mut self, id: ConversationId) {
+        let index = match self
+            .conversations
+            .iter()
+            .position(|conversation| conversation.id == id)
+        {
+            Some(index) => index,
+            None => return,
+        };
+        match self.conversations[index].interrupt {
+            Interrupt::Pause => self.events.push_back(ArbiterEvent::Paused(id)),
+            Interrupt::Abort => {
+                let mut conversation = self.conversations.remove(index);
+                conversation.walker.abort(INTERRUPTED);
+                self.events.push_back(ArbiterEvent::Aborted(id));
+                self.ended.push((id, conversation.walker));
+            }
+        }
+    }
+
+    // Makes the conversation of highest priority active if none is, the earliest started among equals
+    fn activate(&mut self) {
+        if self.active.is_some() {
+            return;
+        }
+        let conversation = match self
+            .conversations
+            .iter_mut()
+            .rev()
+            .max_by_key(|conversation| conversation.priority)
+        {
+            Some(conversation) => conversation,
+            None => return,
+        };
+        self.active = Some(conversation.id);
+        let event = match conversation.started {
+            true => ArbiterEvent::Resumed(conversation.id),
+            false => ArbiterEvent::Started(conversation.id),
+        };
+        conversation.started = true;
+        self.events.push_back(event);
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_arbiter() {
+    use crate::{link::Link, node::Node};
+    use ArbiterEvent::*;
+
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "Hello!");
+    let end = Node::new("end", "Goodbye!");
+    Link::link(&mut start, &end, "Bye!");
+    tree.insert_node(start);
+    tree.insert_node(end);
+    tree.set_root_key("start").unwrap();
+    let walker = || Walker::new(&tree).unwrap();
+
+    // Lower and equal priorities wait, higher ones interrupt
+    let mut arbiter = Arbiter::new();
+    let chatter = arbiter.start(walker(), 0, Interrupt::Pause);
+    let gossip = arbiter.start(walker(), 0, Interrupt::Pause);
+    let bark = arbiter.start(walker(), -1, Interrupt::Abort);
+    let scene = arbiter.start(walker(), 5, Interrupt::Pause);
+    let alarm = arbiter.start(walker(), 9, Interrupt::Pause);
+    assert_eq!(Some(alarm), arbiter.active());
+    assert_eq!(4, arbiter.len());
+    assert!(arbiter.is_paused(chatter) && arbiter.is_paused(scene));
+    assert!(!arbiter.is_paused(bark));
+    assert_eq!(
+        vec![
+            Started(chatter),
+            Paused(gossip),
+            Aborted(bark),
+            Paused(chatter),
+            Started(scene),
+            Paused(scene),
+            Started(alarm),
+        ],
+        arbiter.events().collect::<Vec<_>>()
+    );
+
+    // Finished conversations end, resuming the highest priority, then the earliest started
+    arbiter.active_walker().unwrap().choose(0).unwrap();
+    arbiter.update();
+    assert_eq!(Some(scene), arbiter.active());
+    arbiter.walker_mut(chatter).unwrap().abort("player left");
+    arbiter.active_walker().unwrap().abort("cutscene skipped");
+    arbiter.update();
+    assert_eq!(Some(gossip), arbiter.active());
+    assert_eq!(
+        vec![
+            Ended(alarm),
+            Resumed(scene),
+            Ended(scene),
+            Resumed(chatter),
+            Ended(chatter),
+            Started(gossip),
+        ],
+        arbiter.events().collect::<Vec<_>>()
+    );
+    let ended: Vec<ConversationId> = arbiter.take_ended().map(|(id, _)| id).collect();
+    assert_eq!(vec![bark, alarm, scene, chatter], ended);
+    assert_eq!(0, arbiter.take_ended().count());
+
+    // Ending a paused conversation leaves the active one
+    let later = arbiter.start(walker(), 0, Interrupt::Pause);
+    assert!(arbiter.end(later).is_some());
+    assert!(arbiter.end(later).is_none());
+    assert_eq!(Some(gossip), arbiter.active());
+    assert!(arbiter.end(gossip).is_some());
+    assert!(arbiter.active().is_none() && arbiter.is_empty());
+}
@@ -0,0 +1,743 @@
+//! A lower-level parse of dialogue files, which keeps the source position of every value before it is converted to a [`Tree`].
+//!
+//! A [`Document`] is the YAML of a dialogue file as a tree of [`Item`]s, each with the [`Span`] of source it was parsed from. Editor tooling, formatters, and the language server use it to work on exact source positions, and [`Document::to_tree`] converts it to a [`Tree`] exactly as [`crate::importer::source_to_tree`] would.
+
+use crate::{
+    error::{ImportError, TreeError},
+    importer,
+    tree::Tree,
+};
+
+use std::collections::HashMap;
+use yaml_rust::{parser::Parser, scanner::TScalarStyle, yaml, Event, Yaml};
+
+/// A [`Position`] is a location in source.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Position {
+    /// The byte offset from the start of the source.
+    pub offset: usize,
+
+    /// The line, counting from 0.
+    pub line: usize,
+
+    /// The column within the line in characters, counting from 0.
+    pub column: usize,
+}
+
+/// A [`Span`] is a range of source, from its start up to but excluding its end.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash)]
+pub struct Span {
+    /// The first position of the span.
+    pub start: Position,
+
+    /// The position after the span.
+    pub end: Position,
+}
+
+impl Span {
+    /// Returns whether a byte offset falls within this span, including its end, as a cursor after the last character still touches it.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - A byte offset from the start of the source.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::ast;
+    /// let document = ast::parse("root: start").unwrap();
+    /// let root = document.get("root").unwrap();
+    /// assert!(root.span.contains(11));
+    /// assert!(!root.span.contains(3));
+    /// ```
+    pub fn contains(&self, offset: usize) -> bool {
+        self.start.offset <= offset && offset <= self.end.offset
+    }
+
+    /// Returns the source this span covers.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - A string slice that holds the source this span was parsed from.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if the span is outside of the source, or not on character boundaries.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::ast;
+    /// let source = "root: 'start'";
+    /// let document = ast::parse(source).unwrap();
+    /// assert_eq!("'start'", document.get("root").unwrap().span.text(source));
+    /// ```
+    pub fn text<'s>(&self, source: &'s str) -> &'s str {
+        &source[self.start.offset..self.end.offset]
+    }
+}
+
+/// An [`Item`] is a YAML value and the span of source it was parsed from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Item {
+    /// The value of the item.
+    pub kind: ItemKind,
+
+    /// The source of the item. Quoted scalars include their quotes, and collections span from their first to their last character.
+    pub span: Span,
+}
+
+/// An [`ItemKind`] is the resolved value of an [`Item`], as YAML's core schema resolves it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ItemKind {
+    /// A null, written as `~`, `null`, or nothing at all.
+    Null,
+    /// A boolean.
+    Bool(bool),
+    /// An integer.
+    Int(i64),
+    /// A floating point number, as written.
+    Real(String),
+    /// A string, which is any quoted scalar or a plain scalar of no other type.
+    String(String),
+    /// A sequence of items.
+    Sequence(Vec<Item>),
+    /// A mapping of key items to value items, in source order.
+    Mapping(Vec<(Item, Item)>),
+}
+
+impl Item {
+    /// Returns the string of this item, or [`None`] if it is not a string.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::ast;
+    /// let document = ast::parse("root: start").unwrap();
+    /// assert_eq!(Some("start"), document.get("root").unwrap().as_str());
+    /// ```
+    pub fn as_str(&self) -> Option<&str> {
+        match &self.kind {
+            ItemKind::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the items of this sequence, or [`None`] if it is not a sequence.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::ast;
+    /// let document = ast::parse("tags: [shop, forge]").unwrap();
+    /// assert_eq!(2, document.get("tags").unwrap().as_sequence().unwrap().len());
+    /// ```
+    pub fn as_sequence(&self) -> Option<&[Item]> {
+        match &self.kind {
+            ItemKind::Sequence(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    /// Returns the entries of this mapping in source order, or [`None`] if it is not a mapping.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::ast;
+    /// let document = ast::parse("nodes: { start: { dialogue: Hi } }").unwrap();
+    /// let (key, _) = &document.get("nodes").unwrap().as_mapping().unwrap()[0];
+    /// assert_eq!(Some("start"), key.as_str());
+    /// ```
+    pub fn as_mapping(&self) -> Option<&[(Item, Item)]> {
+        match &self.kind {
+            ItemKind::Mapping(entries) => Some(entries),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of this mapping for a string key, or [`None`] if it is not a mapping or has no such key. If a key repeats, its last value is returned, as it is when importing.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - A string slice that holds the key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::ast;
+    /// let document = ast::parse("nodes: { start: { dialogue: Hi } }").unwrap();
+    /// let start = document.get("nodes").unwrap().get("start").unwrap();
+    /// assert_eq!(Some("Hi"), start.get("dialogue").unwrap().as_str());
+    /// ```
+    pub fn get(&self, key: &str) -> Option<&Item> {
+        self.as_mapping()?
+            .iter()
+            .rev()
+            .find(|(item, _)| item.as_str() == Some(key))
+            .map(|(_, value)| value)
+    }
+
+    fn to_yaml(&self) -> Yaml {
+        match &self.kind {
+            ItemKind::Null => Yaml::Null,
+            ItemKind::Bool(value) => Yaml::Boolean(*value),
+            ItemKind::Int(value) => Yaml::Integer(*value),
+            ItemKind::Real(value) => Yaml::Real(value.to_owned()),
+            ItemKind::String(value) => Yaml::String(value.to_owned()),
+            ItemKind::Sequence(items) => Yaml::Array(items.iter().map(Item::to_yaml).collect()),
+            ItemKind::Mapping(entries) => Yaml::Hash(
+                entries
+                    .iter()
+                    .map(|(key, value)| (key.to_yaml(), value.to_yaml()))
+                    .collect::<yaml::Hash>(),
+            ),
+        }
+    }
+}
+
+/// A [`Document`] is the parsed YAML of a dialogue file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Document {
+    /// The top-level item of the document, which is a mapping in dialogue files.
+    pub body: Item,
+}
+
+impl Document {
+    /// Returns the top-level value for a key, or [`None`] if there is no such key.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - A string slice that holds the key, e.g. `root` or `nodes`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::ast;
+    /// let document = ast::parse("root: start").unwrap();
+    /// assert!(document.get("root").is_some());
+    /// assert!(document.get("nodes").is_none());
+    /// ```
+    pub fn get(&self, key: &str) -> Option<&Item> {
+        self.body.get(key)
+    }
+
+    /// Returns the entries of `nodes`, as the items of each node's key and data.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::ast;
+    /// let document = ast::parse("nodes:\n  start:\n    dialogue: Hi\n").unwrap();
+    /// let (key, _) = &document.nodes()[0];
+    /// assert_eq!(1, key.span.start.line);
+    /// ```
+    pub fn nodes(&self) -> &[(Item, Item)] {
+        self.get("nodes")
+            .and_then(Item::as_mapping)
+            .unwrap_or_default()
+    }
+
+    /// Returns the string items which name nodes, in source order: `root`, the targets of links, including links in groups, and the targets of switch nodes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::ast;
+    /// let source = "
+    /// root: start
+    /// nodes:
+    ///   start:
+    ///     dialogue: Hi
+    ///     links:
+    ///       - end: Bye
+    ///       - { to: start, dialogue: Again }
+    /// ";
+    /// let document = ast::parse(source).unwrap();
+    /// let targets: Vec<_> = document
+    ///     .node_references()
+    ///     .iter()
+    ///     .map(|item| item.as_str().unwrap())
+    ///     .collect();
+    /// assert_eq!(vec!["start", "end", "start"], targets);
+    /// ```
+    pub fn node_references(&self) -> Vec<&Item> {
+        let mut references = Vec::new();
+        references.extend(self.get("root"));
+        if let Some(entries) = self.get("entries").and_then(Item::as_mapping) {
+            references.extend(entries.iter().map(|(_, to)| to));
+        }
+        for (_, node) in self.nodes() {
+            if let Some(links) = node.get("links") {
+                link_references(links, &mut references);
+            }
+            if let Some(cases) = node.get("cases").and_then(Item::as_mapping) {
+                references.extend(cases.iter().map(|(_, to)| to));
+            }
+            references.extend(node.get("default"));
+        }
+        references.retain(|item| item.as_str().is_some());
+        references
+    }
+
+    /// Try to return a [`Tree`] which is generated from this document, as [`crate::importer::source_to_tree`] would from its source.
+    ///
+    /// # Errors
+    ///
+    /// * An [`ImportError`] will be returned if the tree is not considered legal.
+    ///   See also: [validation rules](https://github.com/simbleau/convo/blob/dev/FORMATTING.md#validation-rules).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::ast;
+    /// let document = ast::parse("root: start\nnodes:\n  start:\n    dialogue: Hi\n").unwrap();
+    /// let tree = document.to_tree().unwrap();
+    /// assert_eq!("Hi", tree.root_node().unwrap().dialogue);
+    /// ```
+    pub fn to_tree(&self) -> Result<Tree, ImportError> {
+        importer::yaml_to_tree(&self.body.to_yaml())
+    }
+}
+
+// Collects link targets as the importer reads links
+fn link_references<'d>(links: &'d Item, references: &mut Vec<&'d Item>) {
+    for link in links.as_sequence().unwrap_or_default() {
+        let entries = link.as_mapping().unwrap_or_default();
+        let has = |key: &str| link.get(key).is_some();
+        let nested = link
+            .get("links")
+            .filter(|links| links.as_sequence().is_some());
+        match nested {
+            Some(nested) if has("group") && !has("to") => link_references(nested, references),
+            _ if has("to") => references.extend(link.get("to")),
+            _ => references.extend(entries.iter().map(|(to, _)| to)),
+        }
+    }
+}
+
+/// Try to return a [`Document`] which is parsed from YAML source.
+///
+/// # Arguments
+///
+/// * `source` - A string slice that holds YAML.
+///
+/// # Errors
+///
+/// * An [`ImportError`] will be returned if the source is not valid YAML, or does not hold exactly one document.
+///
+/// # Examples
+///
+/// ```
+/// use convo::ast;
+/// let source = "root: start\nnodes:\n  start:\n    dialogue: \"Hi\"\n";
+/// let document = ast::parse(source).unwrap();
+/// let (key, data) = &document.nodes()[0];
+/// assert_eq!("start", key.span.text(source));
+/// assert_eq!("\"Hi\"", data.get("dialogue").unwrap().span.text(source));
+/// ```
+pub fn parse(source: &str) -> Result<Document, ImportError> {
+    let mut events = Vec::new();
+    let mut parser = Parser::new(source.chars());
+    loop {
+        let (event, marker) = parser.next()?;
+        let end = event == Event::StreamEnd;
+        events.push((event, marker.index()));
+        if end {
+            break;
+        }
+    }
+
+    // Exactly one document is expected
+    let documents = events
+        .iter()
+        .filter(|(event, _)| *event == Event::DocumentStart)
+        .count();
+    if documents != 1 {
+        return Err(ImportError::MultipleDocumentsProvided());
+    }
+    let start = events
+        .iter()
+        .position(|(event, _)| *event == Event::DocumentStart)
+        .map_or(0, |start| start + 1);
+
+    let mut builder = Builder::new(source, &events);
+    builder.position = start;
+    let body = builder.item()?;
+    Ok(Document { body })
+}
+
+// Builds items from the events of a document, tracking character indices as yaml-rust marks them
+struct Builder<'s> {
+    source: &'s str,
+    events: &'s [(Event, usize)],
+    position: usize,
+
+    // The byte offset of each character, and of the end of the source
+    offsets: Vec<usize>,
+
+    // The character index at which each line starts
+    line_starts: Vec<usize>,
+
+    // The character index after the last item built
+    last_end: usize,
+
+    anchors: HashMap<usize, Item>,
+}
+
+impl<'s> Builder<'s> {
+    fn new(source: &'s str, events: &'s [(Event, usize)]) -> Self {
+        let mut offsets: Vec<usize> = source.char_indices().map(|(offset, _)| offset).collect();
+        offsets.push(source.len());
+        let line_starts = std::iter::once(0)
+            .chain(
+                source
+                    .chars()
+                    .enumerate()
+                    .filter(|(_, c)| *c == '\n')
+                    .map(|(index, _)| index + 1),
+            )
+            .collect();
+        Builder {
+            source,
+            events,
+            position: 0,
+            offsets,
+            line_starts,
+            last_end: 0,
+            anchors: HashMap::new(),
+        }
+    }
+
+    fn next(&mut self) -> Result<(&'s Event, usize), ImportError> {
+        let (event, index) = self
+            .events
+            .get(self.position)
+            .ok_or_else(|| TreeError::Validation("YAML document ends unexpectedly".into()))?;
+        self.position += 1;
+        Ok((event, *index))
+    }
+
+    fn item(&mut self) -> Result<Item, ImportError> {
+        let (event, mark) = self.next()?;
+        let start = self.skip_properties(mark);
+        let (item, anchor) = match event {
+            Event::Scalar(value, style, anchor, tag) => {
+                let (start, end) = self.scalar_span(start, value, *style);
+                let kind = match importer::scalar_to_yaml(value.to_owned(), *style, tag.clone()) {
+                    Yaml::Boolean(value) => ItemKind::Bool(value),
+                    Yaml::Integer(value) => ItemKind::Int(value),
+                    Yaml::Real(value) => ItemKind::Real(value),
+                    Yaml::String(value) => ItemKind::String(value),
+                    _ => ItemKind::Null,
+                };
+                (self.item_at(kind, start, end), *anchor)
+            }
+            Event::SequenceStart(anchor) => {
+                let mut items = Vec::new();
+                let end = loop {
+                    match self.events.get(self.position) {
+                        Some((Event::SequenceEnd, end)) => break *end,
+                        _ => items.push(self.item()?),
+                    }
+                };
+                self.position += 1;
+                let (start, end) = self.collection_span(start, end, '[', items.first());
+                (self.item_at(ItemKind::Sequence(items), start, end), *anchor)
+            }
+            Event::MappingStart(anchor) => {
+                let mut entries = Vec::new();
+                let end = loop {
+                    match self.events.get(self.position) {
+                        Some((Event::MappingEnd, end)) => break *end,
+                        _ => entries.push((self.item()?, self.item()?)),
+                    }
+                };
+                self.position += 1;
+                let first = entries.first().map(|(key, _)| key);
+                let (start, end) = self.collection_span(start, end, '{', first);
+                (
+                    self.item_at(ItemKind::Mapping(entries), start, end),
+                    *anchor,
+                )
+            }
+            Event::Alias(anchor) => {
+                let aliased = self.anchors.get(anchor).ok_or_else(|| {
+                    TreeError::Validation(format!("YAML alias refers to unknown anchor {}", anchor))
+                })?;
+                let kind = aliased.kind.clone();
+                let end = self.token_end(mark);
+                (self.item_at(kind, mark, end), 0)
+            }
+            event => {
+                return Err(
+                    TreeError::Validation(format!("Unexpected YAML event: {:?}", event)).into(),
+                )
+            }
+        };
+        self.last_end = self.char_index(item.span.end.offset);
+        if anchor > 0 {
+            self.anchors.insert(anchor, item.clone());
+        }
+        Ok(item)
+    }
+
+    fn item_at(&self, kind: ItemKind, start: usize, end: usize) -> Item {
+        Item {
+            kind,
+            span: Span {
+                start: self.position_at(start),
+                end: self.position_at(end),
+            },
+        }
+    }
+
+    fn position_at(&self, index: usize) -> Position {
+        let index = index.min(self.offsets.len() - 1);
+        let line = self.line_starts.partition_point(|&start| start <= index) - 1;
+        Position {
+            offset: self.offsets[index],
+            line,
+            column: index - self.line_starts[line],
+        }
+    }
+
+    fn char_index(&self, offset: usize) -> usize {
+        self.offsets.partition_point(|&start| start < offset)
+    }
+
+    fn char_at(&self, index: usize) -> Option<char> {
+        let offset = *self.offsets.get(index)?;
+        self.source[offset..].chars().next()
+    }
+
+    // Returns the character indices of a scalar, which for empty scalars is where the previous item ended
+    fn scalar_span(&self, start: usize, value: &str, style: TScalarStyle) -> (usize, usize) {
+        let mut index = start + 1;
+        let end = match style {
+            TScalarStyle::DoubleQuoted => loop {
+                match self.char_at(index) {
+                    Some('\\') => index += 2,
+                    Some('"') | None => break index + 1,
+                    Some(_) => index += 1,
+                }
+            },
+            TScalarStyle::SingleQuoted => loop {
+                match (self.char_at(index), self.char_at(index + 1)) {
+                    (Some('\''), Some('\'')) => index += 2,
+                    (Some('\''), _) | (None, _) => break index + 1,
+                    _ => index += 1,
+                }
+            },
+            TScalarStyle::Plain => {
+                let written = &self.source[self.offsets[start]..];
+                if value.is_empty() || value == "~" && !written.starts_with('~') {
+                    return (self.last_end, self.last_end);
+                } else if written.starts_with(value) {
+                    start + value.chars().count()
+                } else {
+                    self.block_end(start)
+                }
+            }
+            _ => {
+                // Block scalars are marked at their content, after their header
+                let mut header = start;
+                while header > 0 && self.char_at(header - 1).is_some_and(char::is_whitespace) {
+                    header -= 1;
+                }
+                while header > 0
+                    && self
+                        .char_at(header - 1)
+                        .is_some_and(|c| "+-0123456789".contains(c))
+                {
+                    header -= 1;
+                }
+                let end = self.block_end(start);
+                if header > 0 && matches!(self.char_at(header - 1), Some('|' | '>')) {
+                    return (header - 1, end);
+                }
+                end
+            }
+        };
+        (start, end.min(self.offsets.len() - 1))
+    }
+
+    // Returns the character index after any anchor and tag written before a value
+    fn skip_properties(&self, mut index: usize) -> usize {
+        while matches!(self.char_at(index), Some('&' | '!')) {
+            index = self.token_end(index);
+            while self.char_at(index).is_some_and(char::is_whitespace) {
+                index += 1;
+            }
+        }
+        index
+    }
+
+    // Returns the character index after a value which spans lines, which is before the next event, less any whitespace, dashes, and comments between them
+    fn block_end(&self, start: usize) -> usize {
+        let next = self
+            .events
+            .get(self.position)
+            .map_or(self.offsets.len() - 1, |(_, index)| *index);
+        let mut end = next;
+        loop {
+            while end > start && self.char_at(end - 1).is_some_and(char::is_whitespace) {
+                end -= 1;
+            }
+            let line = self.line_starts[self.line_starts.partition_point(|&line| line <= end) - 1]
+                .max(start);
+            let text = &self.source[self.offsets[line]..self.offsets[end]];
+            let text = text.trim_start();
+            if line > start && (text == "-" || text.starts_with('#')) {
+                end = line;
+            } else {
+                return end;
+            }
+        }
+    }
+
+    // Returns the character indices of a collection, from its opening bracket or first item, to after its closing bracket or last item
+    fn collection_span(
+        &self,
+        start: usize,
+        end: usize,
+        bracket: char,
+        first: Option<&Item>,
+    ) -> (usize, usize) {
+        if self.char_at(start) == Some(bracket) {
+            return (start, end + 1);
+        }
+        let mut start = first.map_or(start, |first| self.char_index(first.span.start.offset));
+
+        // Block sequences start at the dash of their first item
+        if bracket == '[' {
+            let mut dash = start;
+            while dash > 0 && matches!(self.char_at(dash - 1), Some(' ' | '\t')) {
+                dash -= 1;
+            }
+            if dash > 0 && self.char_at(dash - 1) == Some('-') {
+                start = dash - 1;
+            }
+        }
+        (start, self.last_end.max(start))
+    }
+
+    // Returns the character index after a token such as an alias
+    fn token_end(&self, start: usize) -> usize {
+        let mut end = start + 1;
+        while self
+            .char_at(end)
+            .is_some_and(|c| !c.is_whitespace() && !",]}".contains(c))
+        {
+            end += 1;
+        }
+        end
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_parse() {
+    let source = r#"---
+# A forge
+root: start
+nodes:
+  start:
+    dialogue: "Need a \"blade\"?"
+    tags: [shop, 'smith''s']
+    links:
+      - buy: Yes.
+      - { to: end, dialogue: No. }
+      - group: Ask about...
+        links:
+          - end: |
+              The forge.
+              And more.
+          # Nothing else
+      - &again start: Again?
+  buy:
+    switch: mood
+    cases:
+      calm: end
+    default: *again
+  end:
+    dialogue: Farewell, Ünsal.
+    weight: 2
+"#;
+    let document = parse(source).unwrap();
+    let text = |item: &Item| item.span.text(source);
+
+    // Spans cover the written source
+    let (key, start) = &document.nodes()[0];
+    assert_eq!("start", text(key));
+    assert_eq!(
+        Position {
+            offset: 35,
+            line: 4,
+            column: 2
+        },
+        key.span.start
+    );
+    assert_eq!(
+        r#""Need a \"blade\"?""#,
+        text(start.get("dialogue").unwrap())
+    );
+    assert_eq!("[shop, 'smith''s']", text(start.get("tags").unwrap()));
+    assert_eq!(
+        Some("smith's"),
+        start.get("tags").unwrap().as_sequence().unwrap()[1].as_str()
+    );
+    let links = start.get("links").unwrap().as_sequence().unwrap();
+    assert_eq!("{ to: end, dialogue: No. }", text(&links[1]));
+    let group = links[2].get("links").unwrap();
+    assert_eq!(
+        "|\n              The forge.\n              And more.",
+        text(&group.as_sequence().unwrap()[0].as_mapping().unwrap()[0].1)
+    );
+    assert!(text(start).starts_with("dialogue"));
+    assert!(text(start).ends_with("Again?"));
+
+    // Scalars resolve as the importer resolves them
+    let (_, end) = &document.nodes()[2];
+    assert_eq!(ItemKind::Int(2), end.get("weight").unwrap().kind);
+
+    // Columns count characters, and offsets count bytes
+    let dialogue = end.get("dialogue").unwrap();
+    assert_eq!("Farewell, Ünsal.", text(dialogue));
+    assert_eq!(30, dialogue.span.end.column);
+    assert_eq!(17, dialogue.span.end.offset - dialogue.span.start.offset);
+
+    // References are every item naming a node, with aliases resolved where they are used
+    let references: Vec<(&str, usize)> = document
+        .node_references()
+        .iter()
+        .map(|item| (item.as_str().unwrap(), item.span.start.line))
+        .collect();
+    assert_eq!(
+        vec![
+            ("start", 2),
+            ("buy", 8),
+            ("end", 9),
+            ("end", 12),
+            ("start", 16),
+            ("end", 20),
+            ("start", 21)
+        ],
+        references
+    );
+    assert_eq!("start", text(document.node_references()[4]));
+    assert_eq!("*again", text(document.node_references()[6]));
+
+    // Documents convert to the same tree as their source
+    assert_eq!(
+        importer::source_to_tree(source).unwrap(),
+        document.to_tree().unwrap()
+    );
+
+    // Documents must be valid YAML, and only one
+    assert!(matches!(parse("a: [b"), Err(ImportError::Scan(_))));
+    assert!(matches!(
+        parse("a: b\n---\nc: d"),
+        Err(ImportError::MultipleDocumentsProvided())
+    ));
+}
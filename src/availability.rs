@@ -0,0 +1,179 @@
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+
+/// An [`Availability`] restricts when a [`crate::Tree`] or [`crate::Node`] may be offered, given the [`HostState`] of the game. It is read from and written to the optional `availability` section of a dialogue file or node.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{Availability, HostState};
+/// let availability = Availability {
+///     cooldown: Some(60),
+///     min_day: Some(2),
+///     required_tags: vec!["raining".into()],
+///     ..Availability::default()
+/// };
+/// let mut state = HostState { time: 100, day: 3, ..HostState::default() };
+/// assert!(!availability.allows(&state, "gossip"));
+/// state.tags.push("raining".into());
+/// assert!(availability.allows(&state, "gossip"));
+/// state.last_used.insert("gossip".into(), 50);
+/// assert!(!availability.allows(&state, "gossip"));
+/// ```
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Availability {
+    /// The number of seconds after being used before it may be offered again.
+    pub cooldown: Option<u64>,
+
+    /// The first game day it may be offered on.
+    pub min_day: Option<i64>,
+
+    /// The last game day it may be offered on.
+    pub max_day: Option<i64>,
+
+    /// Tags which the game state must all have for it to be offered, e.g. `act_2`.
+    pub required_tags: Vec<String>,
+}
+
+/// A [`HostState`] is the state of the game which decides [`Availability`], supplied by the host. Its clock is also read by [`Condition`][`crate::Condition`]s which test `day`, `hour`, or `minute` when the conversation has no variable of that name, e.g. `hour in 9..17 && day >= 3`.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct HostState {
+    /// The current time, in seconds, e.g. since the game started.
+    pub time: u64,
+
+    /// The current game day.
+    pub day: i64,
+
+    /// The current hour of the game day, from 0 to 23.
+    pub hour: i64,
+
+    /// The current minute of the hour, from 0 to 59.
+    pub minute: i64,
+
+    /// Tags describing the current game state, e.g. `raining` or `act_2`.
+    pub tags: Vec<String>,
+
+    /// When trees and nodes were last used, in seconds, mapped by tree name or node key. These start cooldowns.
+    pub last_used: BTreeMap<String, u64>,
+
+    /// Tags of content which is not offered, e.g. `mature` for a streamer-safe mode or `spoiler_act3` before act 3. Links carrying any of these tags, or leading to nodes carrying any of them, are hidden.
+    pub excluded_tags: Vec<String>,
+}
+
+impl HostState {
+    /// The names of the clock variables which conditions may test.
+    pub const CLOCK: [&'static str; 3] = ["day", "hour", "minute"];
+
+    /// Returns the value of a clock variable, or [`None`] if the name is not one of [`HostState::CLOCK`].
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - A string slice that holds the name of the clock variable.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::HostState;
+    /// let state = HostState { day: 2, hour: 21, minute: 30, ..HostState::default() };
+    /// assert_eq!(Some(21), state.clock("hour"));
+    /// assert_eq!(None, state.clock("gold"));
+    /// ```
+    pub fn clock(&self, name: &str) -> Option<i64> {
+        match name {
+            "day" => Some(self.day),
+            "hour" => Some(self.hour),
+            "minute" => Some(self.minute),
+            _ => None,
+        }
+    }
+}
+
+impl Availability {
+    /// Returns whether nothing is restricted. Empty availability is not exported.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::Availability;
+    /// assert!(Availability::default().is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.cooldown.is_none()
+            && self.min_day.is_none()
+            && self.max_day.is_none()
+            && self.required_tags.is_empty()
+    }
+
+    /// Returns whether this availability allows being offered in a [`HostState`].
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The [`HostState`] of the game.
+    /// * `key` - A string slice that holds the tree name or node key whose last use starts the cooldown.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Availability, HostState};
+    /// let availability = Availability { max_day: Some(7), ..Availability::default() };
+    /// assert!(availability.allows(&HostState { day: 7, ..HostState::default() }, "festival"));
+    /// assert!(!availability.allows(&HostState { day: 8, ..HostState::default() }, "festival"));
+    /// ```
+    pub fn allows(&self, state: &HostState, key: &str) -> bool {
+        let cooled_down = match (self.cooldown, state.last_used.get(key)) {
+            (Some(cooldown), Some(last_used)) => state.time >= last_used.saturating_add(cooldown),
+            _ => true,
+        };
+        cooled_down
+            && self.min_day.is_none_or(|min_day| state.day >= min_day)
+            && self.max_day.is_none_or(|max_day| state.day <= max_day)
+            && self
+                .required_tags
+                .iter()
+                .all(|tag| state.tags.contains(tag))
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_allows() {
+    let availability = Availability {
+        cooldown: Some(10),
+        min_day: Some(2),
+        max_day: Some(4),
+        required_tags: vec!["a".into(), "b".into()],
+    };
+    let mut state = HostState {
+        time: 100,
+        day: 2,
+        hour: 0,
+        minute: 0,
+        tags: vec!["b".into(), "c".into(), "a".into()],
+        last_used: BTreeMap::new(),
+        excluded_tags: vec![],
+    };
+    assert!(availability.allows(&state, "key"));
+
+    // Cooldowns only apply to the given key, and end after the cooldown
+    state.last_used.insert("key".into(), 91);
+    assert!(!availability.allows(&state, "key"));
+    assert!(availability.allows(&state, "other"));
+    state.last_used.insert("key".into(), 90);
+    assert!(availability.allows(&state, "key"));
+    state.last_used.insert("key".into(), u64::MAX);
+    assert!(!availability.allows(&state, "key"));
+    state.last_used.clear();
+
+    // Days are inclusive
+    for (day, allowed) in [(1, false), (2, true), (4, true), (5, false)] {
+        state.day = day;
+        assert_eq!(allowed, availability.allows(&state, "key"), "{}", day);
+    }
+    state.day = 3;
+
+    // Every tag is required
+    state.tags.retain(|tag| tag != "a");
+    assert!(!availability.allows(&state, "key"));
+
+    // Empty availability allows anything
+    assert!(Availability::default().allows(&HostState::default(), "key"));
+}
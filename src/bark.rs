@@ -0,0 +1,340 @@
+//! Pools of one-liners, or barks, such as ambient NPC chatter, which are drawn at random instead of walked.
+//!
+//! Barks are the [`Node`]s of a [`Tree`] tagged with [`BARK_TAG`]. They need no links, and are not reported as unreachable by [`crate::lint`].
+
+use alloc::{collections::VecDeque, string::String, vec::Vec};
+use core::ops::Deref;
+
+use crate::{availability::HostState, node::Node, rng::Rng, tree::Tree};
+
+/// The tag which marks a [`Node`] as a bark.
+pub const BARK_TAG: &str = "bark";
+
+/// A [`BarkPool`] draws random barks from a [`Tree`], avoiding lines it drew recently. Each pool remembers its own recent lines, so a pool is kept per speaker or session.
+///
+/// Draws are pseudo-random and reproducible for a given seed, see [`set_seed`][`BarkPool#method.set_seed`].
+///
+/// # Examples
+///
+/// ```
+/// use convo::{bark::BarkPool, Node, Tree};
+/// let mut tree = Tree::new();
+/// for (key, dialogue) in [("weather", "Nice weather."), ("arrow", "I took an arrow to the knee.")] {
+///     let mut node = Node::new(key, dialogue);
+///     node.tags.push("bark".into());
+///     tree.insert_node(node);
+/// }
+/// let mut pool = BarkPool::new(&tree);
+/// pool.set_memory(1);
+/// let first = pool.bark().unwrap().key().to_owned();
+/// let second = pool.bark().unwrap().key().to_owned();
+/// assert_ne!(first, second);
+/// ```
+#[derive(Debug, Clone)]
+pub struct BarkPool<T>
+where
+    T: Deref<Target = Tree>,
+{
+    /// The tree holding the barks.
+    tree: T,
+
+    /// The number of recent barks which are not repeated, if set.
+    memory: Option<usize>,
+
+    /// The keys of the most recently drawn barks, oldest first.
+    recent: VecDeque<String>,
+
+    /// The pseudo-random generator.
+    rng: Rng,
+}
+
+impl<T> BarkPool<T>
+where
+    T: Deref<Target = Tree>,
+{
+    /// Returns a [`BarkPool`] which draws the barks of a [`Tree`].
+    ///
+    /// # Arguments
+    ///
+    /// * `tree` - A [`Tree`] holding barks.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{bark::BarkPool, Tree};
+    /// let tree = Tree::new();
+    /// let mut pool = BarkPool::new(&tree);
+    /// assert!(pool.bark().is_none());
+    /// ```
+    pub fn new(tree: T) -> Self {
+        BarkPool {
+            tree,
+            memory: None,
+            recent: VecDeque::new(),
+            rng: Rng::default(),
+        }
+    }
+
+    /// Returns the [`Tree`] holding the barks.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{bark::BarkPool, Tree};
+    /// let tree = Tree::new();
+    /// let pool = BarkPool::new(&tree);
+    /// assert!(pool.tree().is_empty());
+    /// ```
+    pub fn tree(&self) -> &Tree {
+        &self.tree
+    }
+
+    /// Returns an [`Iterator`] over the barks of the tree, in the order they are stored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{bark::BarkPool, Node, Tree};
+    /// let mut tree = Tree::new();
+    /// let mut node = Node::new("weather", "Nice weather.");
+    /// node.tags.push("bark".into());
+    /// tree.insert_node(node);
+    /// tree.insert_node(Node::new("quest", "Help me!"));
+    /// let pool = BarkPool::new(&tree);
+    /// assert_eq!(1, pool.barks().count());
+    /// ```
+    pub fn barks(&self) -> impl Iterator<Item = &Node> {
+        self.tree
+            .nodes()
+            .filter(|node| node.tags.iter().any(|tag| tag == BARK_TAG))
+    }
+
+    /// Set the seed of the pseudo-random generator, e.g. from the clock or a saved game, so draws differ between sessions.
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - The seed, where equal seeds draw equal barks.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{bark::BarkPool, Tree};
+    /// let tree = Tree::new();
+    /// let mut pool = BarkPool::new(&tree);
+    /// pool.set_seed(42);
+    /// ```
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = Rng::new(seed);
+    }
+
+    /// Set the number of recently drawn barks which are not repeated. By default, half of the barks are remembered. A bark can always be drawn, so fewer barks are avoided when too few are available.
+    ///
+    /// # Arguments
+    ///
+    /// * `memory` - The number of recent barks to avoid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{bark::BarkPool, Node, Tree};
+    /// let mut tree = Tree::new();
+    /// let mut node = Node::new("weather", "Nice weather.");
+    /// node.tags.push("bark".into());
+    /// tree.insert_node(node);
+    /// let mut pool = BarkPool::new(&tree);
+    /// pool.set_memory(5);
+    /// assert_eq!("weather", pool.bark().unwrap().key());
+    /// assert_eq!("weather", pool.bark().unwrap().key());
+    /// ```
+    pub fn set_memory(&mut self, memory: usize) {
+        self.memory = Some(memory);
+    }
+
+    /// Returns an [`Iterator`] over the keys of recently drawn barks, oldest first, e.g. to save them with game state.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{bark::BarkPool, Node, Tree};
+    /// let mut tree = Tree::new();
+    /// let mut node = Node::new("weather", "Nice weather.");
+    /// node.tags.push("bark".into());
+    /// tree.insert_node(node);
+    /// let mut pool = BarkPool::new(&tree);
+    /// pool.bark();
+    /// assert_eq!(vec!["weather"], pool.recent().collect::<Vec<_>>());
+    /// ```
+    pub fn recent(&self) -> impl Iterator<Item = &str> {
+        self.recent.iter().map(String::as_str)
+    }
+
+    /// Record a bark as drawn, as if it had been drawn by this pool, e.g. to load recent barks from saved game state.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - A string type that holds the key of the bark.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{bark::BarkPool, Tree};
+    /// let tree = Tree::new();
+    /// let mut pool = BarkPool::new(&tree);
+    /// pool.remember("weather");
+    /// assert_eq!(vec!["weather"], pool.recent().collect::<Vec<_>>());
+    /// ```
+    pub fn remember<K>(&mut self, key: K)
+    where
+        K: Into<String>,
+    {
+        let key = key.into();
+        self.recent.retain(|recent| *recent != key);
+        self.recent.push_back(key);
+    }
+
+    /// Returns a random bark which was not drawn recently, and remembers it, or [`None`] if the tree has no barks.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{bark::BarkPool, Node, Tree};
+    /// let mut tree = Tree::new();
+    /// let mut node = Node::new("weather", "Nice weather.");
+    /// node.tags.push("bark".into());
+    /// tree.insert_node(node);
+    /// let mut pool = BarkPool::new(&tree);
+    /// assert_eq!("Nice weather.", pool.bark().unwrap().dialogue);
+    /// ```
+    pub fn bark(&mut self) -> Option<&Node> {
+        self.draw(None)
+    }
+
+    /// Returns a random bark which was not drawn recently and whose [`Availability`][`crate::Availability`] allows it in a [`HostState`], and remembers it, or [`None`] if no bark is available. Cooldowns of barks are keyed by node key.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The [`HostState`] of the game.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{bark::BarkPool, HostState, Node, Tree};
+    /// let mut tree = Tree::new();
+    /// let mut node = Node::new("rain", "Looks like rain.");
+    /// node.tags.push("bark".into());
+    /// node.availability.required_tags.push("cloudy".into());
+    /// tree.insert_node(node);
+    /// let mut pool = BarkPool::new(&tree);
+    /// let mut state = HostState::default();
+    /// assert!(pool.bark_in(&state).is_none());
+    /// state.tags.push("cloudy".into());
+    /// assert_eq!("rain", pool.bark_in(&state).unwrap().key());
+    /// ```
+    pub fn bark_in(&mut self, state: &HostState) -> Option<&Node> {
+        self.draw(Some(state))
+    }
+
+    fn draw(&mut self, state: Option<&HostState>) -> Option<&Node> {
+        let tree = &*self.tree;
+        let barks: Vec<&Node> = tree
+            .nodes()
+            .filter(|node| node.tags.iter().any(|tag| tag == BARK_TAG))
+            .filter(|node| state.is_none_or(|state| node.availability.allows(state, &node.key)))
+            .collect();
+        if barks.is_empty() {
+            return None;
+        }
+
+        // Avoid the most recent barks, leaving at least one to draw
+        let memory = self.memory.unwrap_or(barks.len() / 2);
+        let avoided = memory.min(barks.len() - 1);
+        let recent: Vec<&String> = self.recent.iter().rev().collect();
+        let mut candidates = barks.clone();
+        let mut skipped = 0;
+        for key in recent {
+            if skipped == avoided {
+                break;
+            }
+            if let Some(i) = candidates.iter().position(|node| node.key == *key) {
+                candidates.remove(i);
+                skipped += 1;
+            }
+        }
+
+        let node = candidates[self.rng.below(candidates.len() as u64) as usize];
+
+        // Recent barks beyond the largest useful memory are forgotten
+        self.recent.retain(|key| *key != node.key);
+        self.recent.push_back(node.key.clone());
+        while self.recent.len() > memory.max(1) {
+            self.recent.pop_front();
+        }
+        Some(node)
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_bark() {
+    use crate::link::Link;
+
+    let mut tree = Tree::new();
+    let mut quest = Node::new("quest", "Help me!");
+    for i in 0..5 {
+        let mut node = Node::new(alloc::format!("bark_{}", i), "Hm.".into());
+        node.tags.push(BARK_TAG.into());
+        Link::link(&mut quest, &node, "Hm?");
+        tree.insert_node(node);
+    }
+    tree.insert_node(quest);
+    let mut pool = BarkPool::new(&tree);
+    assert_eq!(5, pool.barks().count());
+
+    // Barks are never repeated within memory, and only barks are drawn
+    pool.set_memory(4);
+    let mut drawn: Vec<String> = Vec::new();
+    for _ in 0..50 {
+        let key = pool.bark().unwrap().key.clone();
+        assert!(key.starts_with("bark_"));
+        let window = drawn.len().saturating_sub(4);
+        assert!(!drawn[window..].contains(&key), "{} repeated", key);
+        drawn.push(key);
+    }
+    assert_eq!(4, pool.recent().count());
+
+    // Equal seeds draw equal barks
+    let draws = |seed| {
+        let mut pool = BarkPool::new(&tree);
+        pool.set_seed(seed);
+        (0..10)
+            .map(|_| pool.bark().unwrap().key.clone())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(draws(7), draws(7));
+    assert_ne!(draws(7), draws(8));
+    let mut unseeded = BarkPool::new(&tree);
+    let unseeded: Vec<String> = (0..10)
+        .map(|_| unseeded.bark().unwrap().key.clone())
+        .collect();
+    assert_eq!(draws(0), unseeded);
+
+    // Remembered barks are avoided, and unavailable barks are never drawn
+    let mut tree = tree.clone();
+    tree.node_mut("bark_0").unwrap().availability.cooldown = Some(10);
+    let mut state = HostState::default();
+    state.last_used.insert("bark_0".into(), 0);
+    let remembered = || {
+        let mut pool = BarkPool::new(&tree);
+        pool.set_memory(4);
+        for i in 1..5 {
+            pool.remember(alloc::format!("bark_{}", i));
+        }
+        pool
+    };
+    assert_eq!("bark_0", remembered().bark().unwrap().key);
+    let mut pool = remembered();
+    // Only bark_0 is not recent, but it is cooling down, so a recent bark is drawn
+    assert_ne!("bark_0", pool.bark_in(&state).unwrap().key);
+    state.time = 10;
+    assert_eq!("bark_0", pool.bark_in(&state).unwrap().key);
+}
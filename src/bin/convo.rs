@@ -0,0 +1,298 @@
+//! The `convo` command line interface.
+
+use convo::{
+    debugger::{Break, Debugger, Resume},
+    foreign,
+    lint::{self, LintConfig, Severity},
+    project::{self, Project},
+    Value, Walker,
+};
+use std::{
+    env,
+    io::{self, Write},
+    path::Path,
+    process::{self, Command, Stdio},
+};
+
+const USAGE: &str = "Usage: convo <COMMAND> <FILE> [TREE]
+       convo convert <DRAFT> <OUTPUT>
+       convo debug <FILE> <NODE>...
+       convo graph <FILE> -o <IMAGE>
+       convo lint <FILE>...
+       convo lsp
+
+Commands:
+  play    Play a conversation in the terminal
+  convert Convert a draft into a `*.convo.yml` dialogue file
+  debug   Walk a dialogue file line by line, pausing whenever a NODE is
+          entered to show the variables, history and links, change
+          variables, or force a link to follow
+  graph   Draw a dialogue file as a graph of its nodes and links. IMAGE is
+          written as SVG (`.svg`) or Graphviz DOT (`.dot`), or in any other
+          format Graphviz renders, e.g. `.png` or `.pdf`, when `dot` is
+          installed
+  lint    Check dialogue files, or a project, and print the findings as JSON,
+          failing if any is an error. Rules are configured by a `.convolint.yml`
+          file in the working directory, or beside the project manifest
+  lsp     Run a language server over standard input and output, when built
+          with the `lsp` feature
+
+FILE is a dialogue file or a `convo.project.yml` project manifest. For projects,
+TREE names the tree to play, defaulting to the first listed file.
+
+DRAFT is a plain-text screenplay (`.txt`), or when built with the matching
+features, a spreadsheet (`.xlsx`, `.xls`, `.xlsb`, `.ods`) or articy:draft
+XML export (`.xml`).";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let result = match args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        ["play", path] => play(path, None),
+        ["play", path, tree] => play(path, Some(tree)),
+        ["convert", draft, output] => convert(draft, output),
+        ["debug", path, nodes @ ..] if !nodes.is_empty() => debug(path, nodes),
+        ["graph", path, "-o", image] | ["graph", "-o", image, path] => graph(path, image),
+        ["lint", paths @ ..] if !paths.is_empty() => check(paths),
+        #[cfg(feature = "lsp")]
+        ["lsp"] => convo::lsp::LanguageServer::new()
+            .serve(std::io::stdin().lock(), std::io::stdout().lock())
+            .map_err(|e| e.to_string()),
+        _ => Err(USAGE.to_owned()),
+    };
+
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+fn play(path: &str, tree_name: Option<&str>) -> Result<(), String> {
+    if !path.ends_with(project::MANIFEST_FILE_NAME) {
+        if tree_name.is_some() {
+            return Err(USAGE.to_owned());
+        }
+        let tree = convo::importer::import(path).map_err(|e| format!("{:?}", e))?;
+        return convo::tui::play(&tree).map_err(|e| e.to_string());
+    }
+
+    let project = Project::load(path).map_err(|e| format!("{:?}", e))?;
+    let tree = match tree_name {
+        Some(name) => project.tree(name),
+        None => project.trees().next().map(|(_, tree)| tree),
+    }
+    .ok_or_else(|| format!("No tree `{}` in project", tree_name.unwrap_or_default()))?;
+    convo::tui::play(tree).map_err(|e| e.to_string())
+}
+
+fn convert(draft: &str, output: &str) -> Result<(), String> {
+    let extension = Path::new(draft)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let tree = match extension.as_str() {
+        "txt" => foreign::screenplay::import(draft),
+        #[cfg(feature = "spreadsheet")]
+        "xlsx" | "xls" | "xlsb" | "ods" => foreign::spreadsheet::import(draft),
+        #[cfg(feature = "xml")]
+        "xml" => foreign::articy::import(draft),
+        _ => return Err(format!("Unsupported draft format `{}`\n\n{}", draft, USAGE)),
+    }
+    .map_err(|e| format!("{:?}", e))?;
+    convo::exporter::export(&tree, output).map_err(|e| format!("{:?}", e))
+}
+
+fn debug(path: &str, node_keys: &[&str]) -> Result<(), String> {
+    let tree = convo::importer::import(path).map_err(|e| format!("{:?}", e))?;
+    let mut walker = Walker::new(&tree).map_err(|e| format!("{:?}", e))?;
+    walker.set_debugger(Prompt);
+    for key in node_keys {
+        if !tree.contains_node(key) {
+            return Err(format!("Node `{}` does not exist", key));
+        }
+        walker.add_breakpoint(*key);
+    }
+
+    loop {
+        println!("[{}] {}", walker.current_key(), walker.dialogue());
+        let choices = walker.choice_dialogues();
+        if choices.is_empty() {
+            println!("(end)");
+            return Ok(());
+        }
+        for (i, choice) in choices.iter().enumerate() {
+            println!("  {}. {}", i + 1, choice);
+        }
+        let index = loop {
+            let line = match read_line("> ") {
+                Some(line) => line,
+                None => return Ok(()),
+            };
+            match line.parse::<usize>() {
+                Ok(n) if (1..=choices.len()).contains(&n) => break n - 1,
+                _ if line == "q" => return Ok(()),
+                _ => println!("Enter a choice from 1 to {}, or `q` to quit", choices.len()),
+            }
+        };
+        walker.choose(index).map_err(|e| format!("{:?}", e))?;
+    }
+}
+
+/// A [`Debugger`] which prompts for commands on standard input at each breakpoint.
+struct Prompt;
+
+impl Debugger for Prompt {
+    fn on_break(&self, context: Break<'_>) -> Resume {
+        let node = context.node;
+        println!("Breakpoint at `{}`", node.key());
+        match context.history.is_empty() {
+            true => println!("  history: (none)"),
+            false => println!("  history: {}", context.history.join(" > ")),
+        }
+        println!("  variables:");
+        for (name, value) in context.variables.iter() {
+            println!("    {} = {}", name, value);
+        }
+        println!("  links:");
+        for (i, link) in node.links.iter().enumerate() {
+            match &link.condition {
+                Some(condition) => println!(
+                    "    {}. -> {} (if {}: {})",
+                    i + 1,
+                    link.to_key,
+                    condition,
+                    condition.holds(context.variables)
+                ),
+                None => println!("    {}. -> {}", i + 1, link.to_key),
+            }
+        }
+
+        loop {
+            let line = match read_line("(debug) ") {
+                Some(line) => line,
+                None => return Resume::Continue,
+            };
+            let words: Vec<&str> = line.splitn(3, ' ').collect();
+            match words.as_slice() {
+                [""] | ["c"] | ["continue"] => return Resume::Continue,
+                ["f", n] | ["follow", n] => match n.parse::<usize>() {
+                    Ok(n) if (1..=node.links.len()).contains(&n) => return Resume::Follow(n - 1),
+                    _ => println!("No link `{}`", n),
+                },
+                ["set", name, value] => {
+                    context
+                        .variables
+                        .insert((*name).to_owned(), source_to_value(value));
+                }
+                ["unset", name] => {
+                    context.variables.remove(*name);
+                }
+                _ => println!(
+                    "Commands: continue (c), follow (f) <LINK>, set <NAME> <VALUE>, unset <NAME>"
+                ),
+            }
+        }
+    }
+}
+
+// Prints a prompt, and returns the trimmed line read from standard input, or `None` at its end
+fn read_line(prompt: &str) -> Option<String> {
+    print!("{}", prompt);
+    io::stdout().flush().ok()?;
+    let mut line = String::new();
+    match io::stdin().read_line(&mut line) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(line.trim().to_owned()),
+    }
+}
+
+// Parses a value as written in conditions, where anything else is a string
+fn source_to_value(source: &str) -> Value {
+    source
+        .parse()
+        .unwrap_or_else(|_| Value::from(source.trim()))
+}
+
+fn graph(path: &str, image: &str) -> Result<(), String> {
+    let tree = convo::importer::import(path).map_err(|e| format!("{:?}", e))?;
+    let format = Path::new(image)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let source = match format.as_str() {
+        "svg" => convo::graph::to_svg(&tree),
+        "dot" | "gv" => convo::graph::to_dot(&tree),
+        "" => return Err(format!("Missing image format for `{}`\n\n{}", image, USAGE)),
+        // Other formats are rendered by Graphviz
+        _ => return render_graphviz(&convo::graph::to_dot(&tree), &format, image),
+    };
+    std::fs::write(image, source).map_err(|e| e.to_string())
+}
+
+fn render_graphviz(dot: &str, format: &str, image: &str) -> Result<(), String> {
+    let mut child = Command::new("dot")
+        .arg(format!("-T{}", format))
+        .arg("-o")
+        .arg(image)
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| {
+            format!(
+                "Rendering `.{}` images requires Graphviz `dot` ({}), write `.svg` or `.dot` instead",
+                format, e
+            )
+        })?;
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(dot.as_bytes())
+        .map_err(|e| e.to_string())?;
+    let status = child.wait().map_err(|e| e.to_string())?;
+    if !status.success() {
+        return Err(format!("Graphviz `dot` failed to render `{}`", image));
+    }
+    Ok(())
+}
+
+fn check(paths: &[&str]) -> Result<(), String> {
+    let (json, failed) = match paths {
+        [path] if path.ends_with(project::MANIFEST_FILE_NAME) => {
+            let project = Project::load(path).map_err(|e| format!("{:?}", e))?;
+            let diagnostics = project.lint();
+            let failed = diagnostics
+                .iter()
+                .any(|d| d.diagnostic.severity == Severity::Error);
+            (project::to_json(&diagnostics), failed)
+        }
+        _ => {
+            let mut reports = lint::lint_files(paths);
+            if Path::new(lint::CONFIG_FILE_NAME).is_file() {
+                let config =
+                    LintConfig::import(lint::CONFIG_FILE_NAME).map_err(|e| format!("{:?}", e))?;
+                for report in &mut reports {
+                    if let Ok(diagnostics) = &mut report.result {
+                        config.apply(diagnostics);
+                    }
+                }
+            }
+            let failed = reports.iter().any(|report| {
+                !report.result.as_ref().is_ok_and(|diagnostics| {
+                    diagnostics.iter().all(|d| d.severity == Severity::Warning)
+                })
+            });
+            (lint::to_json(&reports), failed)
+        }
+    };
+    println!("{}", json);
+    if failed {
+        return Err("Errors were found".to_owned());
+    }
+    Ok(())
+}
@@ -0,0 +1,457 @@
+//! Registries of the characters who speak in dialogue, shared by every tree of a project.
+//!
+//! The `speaker` of each [`Line`][`crate::Line`] is a character id, so renaming a character only changes its display name in the registry rather than every dialogue file. A [`Characters`] registry is usually loaded from the `characters` file of a [project manifest](https://github.com/simbleau/convo/blob/dev/FORMATTING.md#characters).
+
+use alloc::{collections::BTreeMap, format, string::String, vec::Vec};
+
+#[cfg(feature = "std")]
+use crate::error::{ImportError, TreeError};
+use crate::{
+    lint::{Diagnostic, Rule},
+    suggest,
+    tree::Tree,
+};
+
+#[cfg(feature = "std")]
+use std::{fs, path::Path};
+#[cfg(feature = "std")]
+use yaml_rust::{Yaml, YamlLoader};
+
+/// A [`Character`] is someone who speaks in dialogue.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Character {
+    /// The id of the character, which dialogue lines name as their speaker.
+    pub id: String,
+
+    /// The name shown to players.
+    pub name: String,
+
+    /// The default portrait of the character, e.g. an image path, if it has one.
+    pub portrait: Option<String>,
+
+    /// The default voice of the character, e.g. a voice bank name, if it has one.
+    pub voice: Option<String>,
+}
+
+impl Character {
+    /// Returns a [`Character`] without a portrait or voice.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A string type that holds the id of the character.
+    /// * `name` - A string type that holds the name shown to players.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::characters::Character;
+    /// let guard = Character::new("guard", "Gate Guard");
+    /// assert_eq!("Gate Guard", guard.name);
+    /// ```
+    pub fn new<T>(id: T, name: T) -> Character
+    where
+        T: Into<String>,
+    {
+        Character {
+            id: id.into(),
+            name: name.into(),
+            portrait: None,
+            voice: None,
+        }
+    }
+}
+
+/// A [`Characters`] registry holds the [`Character`]s of a project, mapped by id.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{characters::{Character, Characters}, Line, Node, Tree};
+/// let mut characters = Characters::new();
+/// characters.insert(Character::new("guard", "Gate Guard"));
+///
+/// let mut tree = Tree::new();
+/// let mut node = Node::new("start", "");
+/// node.lines.push(Line::new("guard", "Halt!"));
+/// node.lines.push(Line::new("gaurd", "Who goes there?"));
+/// tree.insert_node(node);
+///
+/// assert_eq!("Gate Guard", characters.display_name("guard"));
+/// assert_eq!(1, characters.lint(&tree).len());
+/// ```
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Characters {
+    characters: BTreeMap<String, Character>,
+}
+
+impl Characters {
+    /// Returns an empty [`Characters`] registry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::characters::Characters;
+    /// assert!(Characters::new().is_empty());
+    /// ```
+    pub fn new() -> Self {
+        Characters::default()
+    }
+
+    /// Try to return a [`Characters`] registry which is generated from importing a file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - A path type that references a characters file to parse from.
+    ///
+    /// # Errors
+    ///
+    /// * An [`ImportError`] will be returned if the source is not valid YAML data or if the registry is not considered legal when parsing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::characters::Characters;
+    /// let characters = Characters::import("examples/characters.yml").unwrap();
+    /// assert_eq!("The Stranger", characters.display_name("stranger"));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn import<P>(path: P) -> Result<Self, ImportError>
+    where
+        P: AsRef<Path>,
+    {
+        let source = fs::read_to_string(path)?;
+        Characters::try_from(&source)
+    }
+
+    /// Try to return a [`Characters`] registry which is generated from parsing a string slice. It is a hash of character ids to a hash with a `name` and an optional `portrait` and `voice`.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - A string slice that holds valid YAML data to parse from.
+    ///
+    /// # Errors
+    ///
+    /// * An [`ImportError`] will be returned if the source is not valid YAML data or if the registry is not considered legal when parsing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::characters::Characters;
+    /// let source = r#"
+    /// ---
+    /// guard:
+    ///   name: Gate Guard
+    ///   portrait: portraits/guard.png
+    /// "#;
+    /// let characters = Characters::try_from(source).unwrap();
+    /// let guard = characters.get("guard").unwrap();
+    /// assert_eq!(Some("portraits/guard.png"), guard.portrait.as_deref());
+    /// assert!(Characters::try_from("guard: Gate Guard").is_err());
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn try_from(source: &str) -> Result<Self, ImportError> {
+        let docs = YamlLoader::load_from_str(source)?;
+        if docs.len() != 1 {
+            return Err(ImportError::MultipleDocumentsProvided());
+        }
+        Ok(yaml_to_characters(&docs[0])?)
+    }
+
+    /// Add a [`Character`], returning the character it replaced with the same id, if any.
+    ///
+    /// # Arguments
+    ///
+    /// * `character` - The [`Character`] to add.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::characters::{Character, Characters};
+    /// let mut characters = Characters::new();
+    /// assert!(characters.insert(Character::new("guard", "Guard")).is_none());
+    /// assert!(characters.insert(Character::new("guard", "Gate Guard")).is_some());
+    /// ```
+    pub fn insert(&mut self, character: Character) -> Option<Character> {
+        self.characters.insert(character.id.clone(), character)
+    }
+
+    /// Remove a [`Character`] by id, returning it if it was registered.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A string slice that holds the id of the character.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::characters::{Character, Characters};
+    /// let mut characters = Characters::new();
+    /// characters.insert(Character::new("guard", "Gate Guard"));
+    /// assert!(characters.remove("guard").is_some());
+    /// assert!(characters.is_empty());
+    /// ```
+    pub fn remove(&mut self, id: &str) -> Option<Character> {
+        self.characters.remove(id)
+    }
+
+    /// Returns the [`Character`] with an id, if it is registered.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A string slice that holds the id of the character.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::characters::{Character, Characters};
+    /// let mut characters = Characters::new();
+    /// characters.insert(Character::new("guard", "Gate Guard"));
+    /// assert!(characters.get("guard").is_some());
+    /// assert!(characters.get("captain").is_none());
+    /// ```
+    pub fn get(&self, id: &str) -> Option<&Character> {
+        self.characters.get(id)
+    }
+
+    /// Returns whether a [`Character`] with an id is registered.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A string slice that holds the id of the character.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::characters::{Character, Characters};
+    /// let mut characters = Characters::new();
+    /// characters.insert(Character::new("guard", "Gate Guard"));
+    /// assert!(characters.contains("guard"));
+    /// ```
+    pub fn contains(&self, id: &str) -> bool {
+        self.characters.contains_key(id)
+    }
+
+    /// Returns the name shown to players for a speaker, or the speaker itself if it is not a registered character id.
+    ///
+    /// # Arguments
+    ///
+    /// * `speaker` - A string slice that holds the speaker of a line.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::characters::{Character, Characters};
+    /// let mut characters = Characters::new();
+    /// characters.insert(Character::new("guard", "Gate Guard"));
+    /// assert_eq!("Gate Guard", characters.display_name("guard"));
+    /// assert_eq!("captain", characters.display_name("captain"));
+    /// ```
+    pub fn display_name<'a>(&'a self, speaker: &'a str) -> &'a str {
+        match self.characters.get(speaker) {
+            Some(character) => &character.name,
+            None => speaker,
+        }
+    }
+
+    /// Returns an [`Iterator`] over the registered [`Character`]s, ordered by id.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::characters::{Character, Characters};
+    /// let mut characters = Characters::new();
+    /// characters.insert(Character::new("guard", "Gate Guard"));
+    /// characters.insert(Character::new("captain", "Captain Reyes"));
+    /// let ids: Vec<&str> = characters.iter().map(|c| c.id.as_str()).collect();
+    /// assert_eq!(vec!["captain", "guard"], ids);
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = &Character> {
+        self.characters.values()
+    }
+
+    /// Returns the number of registered [`Character`]s.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::characters::Characters;
+    /// assert_eq!(0, Characters::new().len());
+    /// ```
+    pub fn len(&self) -> usize {
+        self.characters.len()
+    }
+
+    /// Returns whether no [`Character`]s are registered.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::characters::Characters;
+    /// assert!(Characters::new().is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.characters.is_empty()
+    }
+
+    /// Returns a [`Diagnostic`] under [`Rule::UnknownSpeaker`] for every dialogue line of a [`Tree`] whose speaker is not a registered character id.
+    ///
+    /// # Arguments
+    ///
+    /// * `tree` - The [`Tree`] to check.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{characters::{Character, Characters}, lint::Rule, Line, Node, Tree};
+    /// let mut characters = Characters::new();
+    /// characters.insert(Character::new("guard", "Gate Guard"));
+    /// let mut tree = Tree::new();
+    /// let mut node = Node::new("start", "");
+    /// node.lines.push(Line::new("captain", "At ease."));
+    /// tree.insert_node(node);
+    /// let diagnostics = characters.lint(&tree);
+    /// assert_eq!(Rule::UnknownSpeaker, diagnostics[0].rule);
+    /// ```
+    pub fn lint(&self, tree: &Tree) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for node in tree.nodes() {
+            let mut reported: Vec<&str> = Vec::new();
+            for line in &node.lines {
+                if self.contains(&line.speaker) || reported.contains(&line.speaker.as_str()) {
+                    continue;
+                }
+                reported.push(&line.speaker);
+                diagnostics.push(Diagnostic::new(
+                    Rule::UnknownSpeaker,
+                    &node.key,
+                    format!(
+                        "Speaker `{}` in node `{}` is not a registered character{}",
+                        line.speaker,
+                        node.key,
+                        suggest::did_you_mean(suggest::closest(
+                            &line.speaker,
+                            self.characters.keys().map(String::as_str)
+                        ))
+                    ),
+                ));
+            }
+        }
+        diagnostics
+    }
+}
+
+#[cfg(feature = "std")]
+fn yaml_to_characters(yaml: &Yaml) -> Result<Characters, TreeError> {
+    let mut characters = Characters::new();
+
+    // The registry must be a hash of ids to hashes
+    let fields = yaml.as_hash().ok_or_else(|| {
+        TreeError::Validation(format!("YAML characters is not a hash: '{:?}'", yaml))
+    })?;
+    for (yaml_id, yaml_character) in fields {
+        let id = yaml_id.as_str().ok_or_else(|| {
+            TreeError::Validation(format!(
+                "YAML character id is not a string: '{:?}'",
+                yaml_id
+            ))
+        })?;
+        let character_fields = yaml_character.as_hash().ok_or_else(|| {
+            TreeError::Validation(format!("YAML character `{}` is not a hash", id))
+        })?;
+        let mut character = Character::new(id, "");
+        let mut name = None;
+        for (yaml_key, yaml_value) in character_fields {
+            let value = yaml_value.as_str().map(String::from);
+            let field = match yaml_key.as_str() {
+                Some("name") => &mut name,
+                Some("portrait") => &mut character.portrait,
+                Some("voice") => &mut character.voice,
+                _ => {
+                    return Err(TreeError::Validation(format!(
+                        "YAML character `{}` contains an unknown key: `{:?}`",
+                        id, yaml_key
+                    )))
+                }
+            };
+            *field = Some(value.ok_or_else(|| {
+                TreeError::Validation(format!(
+                    "YAML character `{}` has a value which is not a string: '{:?}'",
+                    id, yaml_value
+                ))
+            })?);
+        }
+        character.name = name.ok_or_else(|| {
+            TreeError::Validation(format!("YAML character `{}` has no `name`", id))
+        })?;
+        characters.insert(character);
+    }
+    Ok(characters)
+}
+
+#[cfg(test)]
+#[test]
+fn test_characters_lint() {
+    use crate::{Line, Node};
+
+    let mut characters = Characters::new();
+    characters.insert(Character::new("guard", "Gate Guard"));
+    characters.insert(Character::new("captain", "Captain Reyes"));
+    let mut tree = Tree::new();
+    let mut gate = Node::new("gate", "");
+    gate.lines.push(Line::new("guard", "Halt!"));
+    gate.lines.push(Line::new("captian", "Let them through."));
+    gate.lines.push(Line::new("captian", "Now!"));
+    gate.lines.push(Line::new("stranger", "Thanks."));
+    tree.insert_node(gate);
+    tree.insert_node(Node::new("end", "Goodbye!"));
+
+    // Each unknown speaker is reported once per node, with a suggestion
+    let diagnostics = characters.lint(&tree);
+    let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+    assert_eq!(
+        vec![
+            "Speaker `captian` in node `gate` is not a registered character, did you mean `captain`?",
+            "Speaker `stranger` in node `gate` is not a registered character",
+        ],
+        messages
+    );
+    assert!(diagnostics.iter().all(|d| d.node_key == "gate"));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_characters_try_from() {
+    let source = r#"
+---
+guard:
+  name: Gate Guard
+  portrait: portraits/guard.png
+  voice: gruff
+captain:
+  name: Captain Reyes
+"#;
+    let characters = Characters::try_from(source).unwrap();
+    assert_eq!(2, characters.len());
+    assert_eq!(
+        &Character {
+            id: "guard".into(),
+            name: "Gate Guard".into(),
+            portrait: Some("portraits/guard.png".into()),
+            voice: Some("gruff".into()),
+        },
+        characters.get("guard").unwrap()
+    );
+    assert_eq!("Captain Reyes", characters.display_name("captain"));
+
+    // Characters must be hashes with a string name, and only known keys
+    for source in [
+        "[guard]",
+        "guard: Gate Guard",
+        "guard: { portrait: guard.png }",
+        "guard: { name: [Gate Guard] }",
+        "guard: { name: Gate Guard, mood: grim }",
+        "--- {}\n--- {}",
+    ] {
+        assert!(Characters::try_from(source).is_err(), "{}", source);
+    }
+}
@@ -0,0 +1,961 @@
+use alloc::{borrow::Cow, collections::BTreeMap, format, string::String, vec, vec::Vec};
+use core::fmt;
+
+use crate::{
+    affinity,
+    availability::HostState,
+    error::TreeError,
+    inventory::{self, InventoryProvider},
+    value::{Type, Value},
+};
+
+/// A [`Condition`] gates a [`crate::Link`] on the values of conversation variables. It holds when all of its [`Clause`]s hold.
+///
+/// Conditions are written as clauses joined by `&&`, e.g. `met_stranger && gold >= 10 && mood != "angry" && hour in 9..17`.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{Clause, Condition, Op, Value};
+/// let condition = Condition::parse("!met_stranger && gold >= 10").unwrap();
+/// assert_eq!(
+///     vec![
+///         Clause::Flag { name: "met_stranger".into(), negated: true },
+///         Clause::Compare { name: "gold".into(), op: Op::Ge, value: Value::Int(10) },
+///     ],
+///     condition.clauses
+/// );
+/// assert_eq!("!met_stranger && gold >= 10", condition.to_string());
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Condition {
+    /// The clauses which must all hold.
+    pub clauses: Vec<Clause>,
+}
+
+/// A [`Clause`] is a single test of a variable in a [`Condition`].
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum Clause {
+    /// A boolean variable is `true`, e.g. `met_stranger`, or `false` when negated, e.g. `!met_stranger`.
+    Flag {
+        /// The name of the variable.
+        name: String,
+        /// Whether the variable must be `false`.
+        negated: bool,
+    },
+    /// A variable compares to a value, e.g. `gold >= 10`.
+    Compare {
+        /// The name of the variable.
+        name: String,
+        /// The comparison operator.
+        op: Op,
+        /// The value the variable is compared to.
+        value: Value,
+    },
+    /// An integer variable is within a range, e.g. `hour in 9..17`. The range includes its start but not its end, and wraps around when its start is greater than its end, e.g. `hour in 22..6` from 10 PM until 6 AM.
+    Within {
+        /// The name of the variable.
+        name: String,
+        /// The first value in the range.
+        start: i64,
+        /// The first value after the range.
+        end: i64,
+    },
+}
+
+/// An [`Op`] is a comparison operator of a [`Clause::Compare`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Op {
+    /// `==`
+    Eq,
+    /// `!=`
+    Ne,
+    /// `<`
+    Lt,
+    /// `<=`
+    Le,
+    /// `>`
+    Gt,
+    /// `>=`
+    Ge,
+}
+
+impl Op {
+    /// Returns the source text of this operator.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::Op;
+    /// assert_eq!(">=", Op::Ge.as_str());
+    /// ```
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Op::Eq => "==",
+            Op::Ne => "!=",
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+        }
+    }
+
+    /// Returns whether this operator orders values, i.e. only applies to integers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::Op;
+    /// assert!(Op::Lt.is_ordering());
+    /// assert!(!Op::Ne.is_ordering());
+    /// ```
+    pub fn is_ordering(&self) -> bool {
+        !matches!(self, Op::Eq | Op::Ne)
+    }
+}
+
+// Two-character operators are matched first
+const OPS: [Op; 6] = [Op::Eq, Op::Ne, Op::Le, Op::Ge, Op::Lt, Op::Gt];
+
+impl Clause {
+    /// Returns the name of the variable this clause tests.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::Condition;
+    /// let condition = Condition::parse("gold > 1").unwrap();
+    /// assert_eq!("gold", condition.clauses[0].name());
+    /// ```
+    pub fn name(&self) -> &str {
+        match self {
+            Clause::Flag { name, .. }
+            | Clause::Compare { name, .. }
+            | Clause::Within { name, .. } => name,
+        }
+    }
+
+    /// Returns whether this clause tests a variable of the declared type in a way the type allows. Flags test booleans, and ordering comparisons and ranges test integers.
+    ///
+    /// # Arguments
+    ///
+    /// * `declared` - The declared [`Type`] of the variable.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Condition, Type};
+    /// let condition = Condition::parse("gold > 1").unwrap();
+    /// assert!(condition.clauses[0].is_allowed(&Type::Int));
+    /// assert!(!condition.clauses[0].is_allowed(&Type::String));
+    /// ```
+    pub fn is_allowed(&self, declared: &Type) -> bool {
+        match self {
+            Clause::Flag { .. } => *declared == Type::Bool,
+            Clause::Compare { op, value, .. } => {
+                declared.accepts(value) && (!op.is_ordering() || *declared == Type::Int)
+            }
+            Clause::Within { .. } => *declared == Type::Int,
+        }
+    }
+
+    // Returns whether this clause holds for the value of its variable, if it is set
+    fn holds(&self, variable: Option<&Value>) -> bool {
+        match self {
+            Clause::Flag { negated, .. } => match variable {
+                Some(value) => *value == Value::Bool(!negated),
+                None => *negated,
+            },
+            Clause::Compare { op, value, .. } => match (variable, value) {
+                (None, _) => *op == Op::Ne,
+                (Some(Value::Int(lhs)), Value::Int(rhs)) => match op {
+                    Op::Eq => lhs == rhs,
+                    Op::Ne => lhs != rhs,
+                    Op::Lt => lhs < rhs,
+                    Op::Le => lhs <= rhs,
+                    Op::Gt => lhs > rhs,
+                    Op::Ge => lhs >= rhs,
+                },
+                (Some(lhs), rhs) => match op {
+                    Op::Eq => lhs == rhs,
+                    Op::Ne => lhs != rhs,
+                    _ => false,
+                },
+            },
+            Clause::Within { start, end, .. } => match variable {
+                Some(Value::Int(value)) if start < end => start <= value && value < end,
+                Some(Value::Int(value)) => value >= start || value < end,
+                _ => false,
+            },
+        }
+    }
+
+    fn parse(source: &str) -> Result<Self, TreeError> {
+        let source = source.trim();
+
+        // Comparisons contain an operator outside of quotes
+        for op in OPS {
+            if let Some(i) = find_unquoted(source, op.as_str()) {
+                let name = parse_name(&source[..i])?;
+                let value = parse_value(&source[i + op.as_str().len()..])?;
+                return Ok(Clause::Compare { name, op, value });
+            }
+        }
+
+        // Ranges are tested with `in`
+        if let Some(i) = find_unquoted(source, " in ") {
+            let name = parse_name(&source[..i])?;
+            let (start, end) = parse_range(&source[i + 4..])?;
+            return Ok(Clause::Within { name, start, end });
+        }
+
+        // Otherwise, the clause is a flag
+        match source.strip_prefix('!') {
+            Some(name) => Ok(Clause::Flag {
+                name: parse_name(name)?,
+                negated: true,
+            }),
+            None => Ok(Clause::Flag {
+                name: parse_name(source)?,
+                negated: false,
+            }),
+        }
+    }
+}
+
+impl Condition {
+    /// Try to return a [`Condition`] parsed from its source text.
+    ///
+    /// Variable names contain letters, digits, `_`, and `.`. The affinity of a character may be named as `affinity(npc_bob)`, for the variable `affinity.npc_bob` (see [`crate::affinity`]), and an item as `item(rusty_key)`, for the variable `item.rusty_key` (see [`crate::inventory`]). Values are `true`, `false`, integers, or strings, which are quoted with `"` or `'`, or bare words. Within a quoted string, `\"`, `\'`, and `\\` stand for a quote or backslash. Ranges are two integers separated by `..`, which must differ.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - A string slice that holds clauses joined by `&&`.
+    ///
+    /// # Errors
+    ///
+    /// * A [`TreeError`] will be returned if the source is not a legal condition.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::Condition;
+    /// assert!(Condition::parse("mood == angry").is_ok());
+    /// assert!(Condition::parse("hour in 9..17").is_ok());
+    /// assert!(Condition::parse("gold >=").is_err());
+    /// assert!(Condition::parse("hour in 9..9").is_err());
+    /// ```
+    pub fn parse(source: &str) -> Result<Self, TreeError> {
+        let mut clauses = Vec::new();
+        let mut rest = source;
+        loop {
+            match find_unquoted(rest, "&&") {
+                Some(i) => {
+                    clauses.push(Clause::parse(&rest[..i])?);
+                    rest = &rest[i + 2..];
+                }
+                None => {
+                    clauses.push(Clause::parse(rest)?);
+                    break;
+                }
+            }
+        }
+        Ok(Condition { clauses })
+    }
+
+    /// Returns whether every clause holds for a set of variable values.
+    ///
+    /// A variable which is not set only satisfies negated flags, e.g. `!met_stranger`, and `!=` comparisons, except for affinities, which are `0`. Ordering comparisons and ranges only hold for integers.
+    ///
+    /// # Arguments
+    ///
+    /// * `variables` - The values of variables, mapped by name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Condition, Value};
+    /// use std::collections::BTreeMap;
+    /// let condition = Condition::parse("!met_stranger && gold >= 10").unwrap();
+    /// let mut variables = BTreeMap::new();
+    /// variables.insert("gold".to_owned(), Value::Int(12));
+    /// assert!(condition.holds(&variables));
+    /// variables.insert("met_stranger".to_owned(), Value::Bool(true));
+    /// assert!(!condition.holds(&variables));
+    /// ```
+    pub fn holds(&self, variables: &BTreeMap<String, Value>) -> bool {
+        self.clauses
+            .iter()
+            .all(|clause| clause.holds(Host::default().input(clause, variables).as_deref()))
+    }
+
+    /// Returns whether every clause holds for a set of variable values, reading the clock variables `day`, `hour`, and `minute` from a [`HostState`] when they are not set.
+    ///
+    /// # Arguments
+    ///
+    /// * `variables` - The values of variables, mapped by name.
+    /// * `clock` - The [`HostState`] of the game, whose clock is read.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Condition, HostState};
+    /// use std::collections::BTreeMap;
+    /// let condition = Condition::parse("hour in 9..17 && day >= 3").unwrap();
+    /// let mut clock = HostState { day: 3, hour: 12, ..HostState::default() };
+    /// assert!(condition.holds_at(&BTreeMap::new(), &clock));
+    /// clock.hour = 17;
+    /// assert!(!condition.holds_at(&BTreeMap::new(), &clock));
+    /// ```
+    pub fn holds_at(&self, variables: &BTreeMap<String, Value>, clock: &HostState) -> bool {
+        self.holds_with(variables, Some(clock), None)
+    }
+
+    /// Returns whether every clause holds for a set of variable values, reading the clock variables from a [`HostState`] and items from an [`InventoryProvider`] when they are supplied and the variables are not set.
+    ///
+    /// Item variables which are not set test whether the item is held as flags, e.g. `item(rusty_key)`, and how many are held otherwise, e.g. `item(arrows) >= 10`. Without an inventory, no items are held.
+    ///
+    /// # Arguments
+    ///
+    /// * `variables` - The values of variables, mapped by name.
+    /// * `clock` - The [`HostState`] of the game, whose clock is read, if any.
+    /// * `inventory` - The [`InventoryProvider`] of the game, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::Condition;
+    /// use std::collections::BTreeMap;
+    /// let condition = Condition::parse("item(rusty_key) && item(arrows) >= 10").unwrap();
+    /// let inventory = |item: &str| if item == "arrows" { 12 } else { 1 };
+    /// assert!(condition.holds_with(&BTreeMap::new(), None, Some(&inventory)));
+    /// assert!(!condition.holds_with(&BTreeMap::new(), None, None));
+    /// ```
+    pub fn holds_with(
+        &self,
+        variables: &BTreeMap<String, Value>,
+        clock: Option<&HostState>,
+        inventory: Option<&dyn InventoryProvider>,
+    ) -> bool {
+        let host = Host {
+            state: clock,
+            inventory,
+        };
+        self.clauses
+            .iter()
+            .all(|clause| clause.holds(host.input(clause, variables).as_deref()))
+    }
+
+    /// Returns the name of the first variable whose clauses contradict each other, such that the condition can never hold, e.g. `gold` in `gold > 5 && gold < 3`.
+    ///
+    /// Only clauses which are allowed by the declared type of their variable are considered.
+    ///
+    /// # Arguments
+    ///
+    /// * `declared` - A function returning the declared [`Type`] of a variable, if any.
+    pub(crate) fn contradiction<'d, F>(&self, declared: F) -> Option<&str>
+    where
+        F: Fn(&str) -> Option<&'d Type>,
+    {
+        let mut domains: Vec<(&str, Domain)> = Vec::new();
+        for clause in &self.clauses {
+            let name = clause.name();
+            let declared = match declared(name) {
+                Some(declared) if clause.is_allowed(declared) => declared,
+                _ => continue,
+            };
+            let i = match domains.iter().position(|(n, _)| *n == name) {
+                Some(i) => i,
+                None => {
+                    domains.push((name, Domain::of(declared)));
+                    domains.len() - 1
+                }
+            };
+            let domain = &mut domains[i].1;
+            domain.restrict(clause);
+            if domain.is_empty() {
+                return Some(name);
+            }
+        }
+        None
+    }
+}
+
+/// What conditions read besides conversation variables, as supplied by the host.
+#[derive(Clone, Copy, Default)]
+pub(crate) struct Host<'h> {
+    /// The state of the game, whose clock is read.
+    pub(crate) state: Option<&'h HostState>,
+
+    /// The items the player holds.
+    pub(crate) inventory: Option<&'h dyn InventoryProvider>,
+}
+
+impl<'h> Host<'h> {
+    // Returns the value a clause tests. Variables take precedence, then affinities, items, and the clock
+    pub(crate) fn input<'v>(
+        &self,
+        clause: &Clause,
+        variables: &'v BTreeMap<String, Value>,
+    ) -> Option<Cow<'v, Value>> {
+        let name = clause.name();
+        if let Some(value) = affinity::lookup(variables, name) {
+            return Some(value);
+        }
+        if let Some(item) = inventory::item(name) {
+            let value = match (clause, self.inventory) {
+                (Clause::Flag { .. }, inventory) => {
+                    Value::Bool(inventory.is_some_and(|inventory| inventory.has_item(item)))
+                }
+                (_, inventory) => {
+                    Value::Int(inventory.map_or(0, |inventory| i64::from(inventory.count(item))))
+                }
+            };
+            return Some(Cow::Owned(value));
+        }
+        let clock = self.state.and_then(|state| state.clock(name));
+        clock.map(|value| Cow::Owned(Value::Int(value)))
+    }
+}
+
+impl fmt::Debug for Host<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Host")
+            .field("state", &self.state)
+            .field("inventory", &self.inventory.map(|_| ".."))
+            .finish()
+    }
+}
+
+/// The values a variable may still hold after restricting it by clauses.
+enum Domain {
+    /// One of a finite set of values, i.e. a boolean or enum.
+    Values(Vec<Value>),
+    /// Any string besides the excluded ones.
+    Strings { excluded: Vec<String> },
+    /// Any integer in an inclusive range, besides the excluded ones.
+    Ints {
+        min: i64,
+        max: i64,
+        excluded: Vec<i64>,
+    },
+}
+
+impl Domain {
+    fn of(declared: &Type) -> Self {
+        match declared {
+            Type::Bool => Domain::Values(vec![Value::Bool(true), Value::Bool(false)]),
+            Type::Int => Domain::Ints {
+                min: i64::MIN,
+                max: i64::MAX,
+                excluded: Vec::new(),
+            },
+            Type::String => Domain::Strings {
+                excluded: Vec::new(),
+            },
+            Type::Enum(variants) => {
+                Domain::Values(variants.iter().cloned().map(Value::String).collect())
+            }
+        }
+    }
+
+    fn restrict(&mut self, clause: &Clause) {
+        let (op, value) = match clause {
+            Clause::Flag { negated, .. } => (Op::Eq, Value::Bool(!negated)),
+            Clause::Compare { op, value, .. } => (*op, value.clone()),
+            Clause::Within { start, end, .. } => {
+                // Ranges which wrap around are not contiguous, so they don't restrict the bounds
+                if let (Domain::Ints { min, max, .. }, true) = (&mut *self, start < end) {
+                    *min = (*min).max(*start);
+                    *max = (*max).min(end - 1);
+                }
+                return;
+            }
+        };
+        match (&mut *self, value) {
+            (Domain::Values(values), value) => match op {
+                Op::Eq => values.retain(|v| *v == value),
+                Op::Ne => values.retain(|v| *v != value),
+                _ => {}
+            },
+            (Domain::Strings { excluded }, Value::String(value)) => match op {
+                Op::Eq if excluded.contains(&value) => *self = Domain::Values(Vec::new()),
+                Op::Eq => *self = Domain::Values(vec![Value::String(value)]),
+                Op::Ne => excluded.push(value),
+                _ => {}
+            },
+            (Domain::Ints { min, max, excluded }, Value::Int(value)) => match op {
+                Op::Eq => {
+                    *min = (*min).max(value);
+                    *max = (*max).min(value);
+                }
+                Op::Ne => excluded.push(value),
+                Op::Lt => match value.checked_sub(1) {
+                    Some(value) => *max = (*max).min(value),
+                    None => *self = Domain::Values(Vec::new()),
+                },
+                Op::Le => *max = (*max).min(value),
+                Op::Gt => match value.checked_add(1) {
+                    Some(value) => *min = (*min).max(value),
+                    None => *self = Domain::Values(Vec::new()),
+                },
+                Op::Ge => *min = (*min).max(value),
+            },
+            _ => {}
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        match self {
+            Domain::Values(values) => values.is_empty(),
+            Domain::Strings { .. } => false,
+            Domain::Ints { min, max, excluded } => {
+                // The range is empty, or every value in it is excluded
+                let mut excluded: Vec<i64> = excluded
+                    .iter()
+                    .copied()
+                    .filter(|value| min <= value && value <= max)
+                    .collect();
+                excluded.sort_unstable();
+                excluded.dedup();
+                min > max || (*max as i128 - *min as i128) < excluded.len() as i128
+            }
+        }
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, clause) in self.clauses.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" && ")?;
+            }
+            match clause {
+                Clause::Flag { name, negated } => write!(
+                    f,
+                    "{}{}",
+                    if *negated { "!" } else { "" },
+                    name_to_source(name)
+                )?,
+                Clause::Compare { name, op, value } => {
+                    write!(f, "{} {} {}", name_to_source(name), op.as_str(), value)?
+                }
+                Clause::Within { name, start, end } => {
+                    write!(f, "{} in {}..{}", name_to_source(name), start, end)?
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn find_unquoted(source: &str, pattern: &str) -> Option<usize> {
+    let mut quote = None;
+    let mut escaped = false;
+    for (i, c) in source.char_indices() {
+        match quote {
+            Some(_) if escaped => escaped = false,
+            Some(_) if c == '\\' => escaped = true,
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if source[i..].starts_with(pattern) => return Some(i),
+            None => {}
+        }
+    }
+    None
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '.'
+}
+
+fn parse_name(source: &str) -> Result<String, TreeError> {
+    let name = source.trim();
+
+    // Affinities and items are written as calls, e.g. `affinity(npc_bob)` or `item(rusty_key)`
+    if let Some(character) = name
+        .strip_prefix("affinity(")
+        .and_then(|rest| rest.strip_suffix(')'))
+    {
+        return Ok(affinity::variable(&parse_name(character)?));
+    }
+    if let Some(item) = name
+        .strip_prefix("item(")
+        .and_then(|rest| rest.strip_suffix(')'))
+    {
+        return Ok(inventory::variable(&parse_name(item)?));
+    }
+
+    if name.is_empty() || !name.chars().all(is_name_char) {
+        return Err(TreeError::Validation(format!(
+            "Condition variable is not a legal name: `{}`",
+            name
+        )));
+    }
+    Ok(name.into())
+}
+
+fn parse_range(source: &str) -> Result<(i64, i64), TreeError> {
+    let source = source.trim();
+    let illegal = || {
+        TreeError::Validation(format!(
+            "Condition range is not two integers separated by `..`: `{}`",
+            source
+        ))
+    };
+    let (start, end) = source.split_once("..").ok_or_else(illegal)?;
+    let start: i64 = start.trim().parse().map_err(|_| illegal())?;
+    let end: i64 = end.trim().parse().map_err(|_| illegal())?;
+    if start == end {
+        return Err(TreeError::Validation(format!(
+            "Condition range is empty: `{}`",
+            source
+        )));
+    }
+    Ok((start, end))
+}
+
+pub(crate) fn parse_value(source: &str) -> Result<Value, TreeError> {
+    let source = source.trim();
+    match (source, unquote(source)) {
+        (_, Some(inner)) => Ok(Value::String(inner)),
+        ("true", _) => Ok(Value::Bool(true)),
+        ("false", _) => Ok(Value::Bool(false)),
+        _ => match source.parse::<i64>() {
+            Ok(value) => Ok(Value::Int(value)),
+            Err(_) if !source.is_empty() && source.chars().all(is_name_char) => {
+                Ok(Value::String(source.into()))
+            }
+            Err(_) => Err(TreeError::Validation(format!(
+                "Condition value is not a boolean, integer, or string: `{}`",
+                source
+            ))),
+        },
+    }
+}
+
+// Returns the contents of a string quoted with `"` or `'`, where a backslash escapes a quote or another backslash
+fn unquote(source: &str) -> Option<String> {
+    let mut chars = source.chars();
+    let quote = chars.next().filter(|c| *c == '"' || *c == '\'')?;
+    let mut inner = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.clone().next() {
+                Some(next @ ('"' | '\'' | '\\')) => {
+                    inner.push(next);
+                    chars.next();
+                }
+                _ => inner.push(c),
+            },
+            _ if c == quote => return Some(inner).filter(|_| chars.as_str().is_empty()),
+            _ => inner.push(c),
+        }
+    }
+    None
+}
+
+fn name_to_source(name: &str) -> Cow<'_, str> {
+    if let Some(character) = affinity::character(name) {
+        return Cow::Owned(format!("affinity({})", character));
+    }
+    match inventory::item(name) {
+        Some(item) => Cow::Owned(format!("item({})", item)),
+        None => Cow::Borrowed(name),
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_parse() {
+    let condition =
+        Condition::parse(" a&&!b && c.d == \"x && y\" && e<-3 && f != 'say \"hi\"' && g == h ")
+            .unwrap();
+    assert_eq!(
+        vec![
+            Clause::Flag {
+                name: "a".into(),
+                negated: false
+            },
+            Clause::Flag {
+                name: "b".into(),
+                negated: true
+            },
+            Clause::Compare {
+                name: "c.d".into(),
+                op: Op::Eq,
+                value: Value::from("x && y")
+            },
+            Clause::Compare {
+                name: "e".into(),
+                op: Op::Lt,
+                value: Value::Int(-3)
+            },
+            Clause::Compare {
+                name: "f".into(),
+                op: Op::Ne,
+                value: Value::from("say \"hi\"")
+            },
+            Clause::Compare {
+                name: "g".into(),
+                op: Op::Eq,
+                value: Value::from("h")
+            },
+        ],
+        condition.clauses
+    );
+
+    // Conditions round-trip through their source text
+    assert_eq!(condition, Condition::parse(&condition.to_string()).unwrap());
+
+    // Quotes and backslashes are escaped within strings
+    let condition =
+        Condition::parse(r#"a == "it's \"fine\"" && b != 'c:\\' && c == "\d""#).unwrap();
+    assert_eq!(
+        vec![
+            Value::from("it's \"fine\""),
+            Value::from("c:\\"),
+            Value::from("\\d")
+        ],
+        condition
+            .clauses
+            .iter()
+            .filter_map(|clause| match clause {
+                Clause::Compare { value, .. } => Some(value.clone()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        r#"a == "it's \"fine\"" && b != "c:\\" && c == "\\d""#,
+        condition.to_string()
+    );
+    assert_eq!(condition, Condition::parse(&condition.to_string()).unwrap());
+
+    // Ranges
+    let condition = Condition::parse("hour in 22 .. 6 && day in -1..3").unwrap();
+    assert_eq!(
+        vec![
+            Clause::Within {
+                name: "hour".into(),
+                start: 22,
+                end: 6
+            },
+            Clause::Within {
+                name: "day".into(),
+                start: -1,
+                end: 3
+            },
+        ],
+        condition.clauses
+    );
+    assert_eq!("hour in 22..6 && day in -1..3", condition.to_string());
+
+    // Affinities and items are written as calls
+    let condition = Condition::parse("affinity( npc_bob ) >= 20 && affinity.x in 1..5").unwrap();
+    assert_eq!("affinity.npc_bob", condition.clauses[0].name());
+    assert_eq!(
+        "affinity(npc_bob) >= 20 && affinity(x) in 1..5",
+        condition.to_string()
+    );
+    let condition =
+        Condition::parse("item(rusty_key) && !item.torch && item(arrows) >= 10").unwrap();
+    assert_eq!("item.rusty_key", condition.clauses[0].name());
+    assert_eq!(
+        "item(rusty_key) && !item(torch) && item(arrows) >= 10",
+        condition.to_string()
+    );
+
+    // Invalid conditions
+    for source in [
+        "",
+        "a &&",
+        "!",
+        "a b",
+        "a == ",
+        "a == b c",
+        "a >= \"b",
+        "== 1",
+        "a in",
+        "a in 1",
+        "a in 1..",
+        "a in x..2",
+        "a in 3..3",
+        "in 1..2",
+        "affinity() > 1",
+        "affinity(a b) > 1",
+        "affinity(a > 1",
+        "item()",
+        "item(a",
+    ] {
+        assert!(Condition::parse(source).is_err(), "{}", source);
+    }
+}
+
+#[test]
+fn test_contradiction() {
+    let declared = |name: &str| match name {
+        "met" => Some(&Type::Bool),
+        "gold" => Some(&Type::Int),
+        "town" => Some(&Type::String),
+        _ => None,
+    };
+    let mood = Type::Enum(vec!["happy".into(), "angry".into()]);
+    let declared = |name: &str| declared(name).or((name == "mood").then_some(&mood));
+
+    // Satisfiable conditions
+    for source in [
+        "met && gold > 2 && gold < 4",
+        "gold >= 3 && gold <= 4 && gold != 3",
+        "town != Springville && town != Shelbyville",
+        "mood != happy",
+        "gold < -9223372036854775807",
+        // Undeclared variables and ill-typed clauses are not considered
+        "missing && !missing",
+        "met > 1 && met < 1",
+        "gold in 1..3 && gold >= 2",
+        // Ranges which wrap around are not restricted
+        "gold in 5..1 && gold == 3",
+    ] {
+        let condition = Condition::parse(source).unwrap();
+        assert_eq!(None, condition.contradiction(declared), "{}", source);
+    }
+
+    // Contradictory conditions
+    for (source, name) in [
+        ("met && !met", "met"),
+        ("met == false && met", "met"),
+        ("met && gold > 4 && gold < 3", "gold"),
+        ("gold >= 3 && gold <= 4 && gold != 3 && gold != 4", "gold"),
+        ("gold == 3 && gold == 4", "gold"),
+        ("gold > 9223372036854775807", "gold"),
+        ("town == Springville && town == Shelbyville", "town"),
+        ("town != Springville && town == Springville", "town"),
+        ("mood != happy && mood != angry", "mood"),
+        ("gold in 1..3 && gold >= 3", "gold"),
+        ("gold in 1..3 && gold in 3..5", "gold"),
+    ] {
+        let condition = Condition::parse(source).unwrap();
+        assert_eq!(Some(name), condition.contradiction(declared), "{}", source);
+    }
+}
+
+#[test]
+fn test_holds() {
+    let mut variables = BTreeMap::new();
+    variables.insert("met".into(), Value::Bool(true));
+    variables.insert("gold".into(), Value::Int(5));
+    variables.insert("town".into(), Value::from("Springville"));
+
+    for source in [
+        "met && gold == 5 && gold != 4 && gold < 6 && gold <= 5 && gold > 4 && gold >= 5",
+        "town == Springville && town != Shelbyville && met == true",
+        "!missing && missing != 1",
+        // Affinities which are not set are 0
+        "affinity(npc_bob) == 0 && affinity(npc_bob) in -5..5",
+    ] {
+        assert!(
+            Condition::parse(source).unwrap().holds(&variables),
+            "{}",
+            source
+        );
+    }
+    for source in [
+        "!met",
+        "gold > 5",
+        "town < 1",
+        "town > Shelbyville",
+        "missing",
+        "missing == false",
+        "gold == \"5\"",
+        "gold in 6..10",
+        "gold in 0..5",
+        "gold in 6..4",
+        "town in 0..5",
+        "missing in 0..5",
+    ] {
+        assert!(
+            !Condition::parse(source).unwrap().holds(&variables),
+            "{}",
+            source
+        );
+    }
+    for source in [
+        "gold in 5..6",
+        "gold in -1..10",
+        "gold in 8..6",
+        "gold in 5..1",
+    ] {
+        assert!(
+            Condition::parse(source).unwrap().holds(&variables),
+            "{}",
+            source
+        );
+    }
+}
+
+#[test]
+fn test_holds_at() {
+    let mut clock = HostState {
+        day: 4,
+        hour: 23,
+        minute: 15,
+        ..HostState::default()
+    };
+    let night = Condition::parse("hour in 22..6 && day >= 3").unwrap();
+    assert!(night.holds_at(&BTreeMap::new(), &clock));
+    assert!(!night.holds(&BTreeMap::new()));
+    clock.hour = 6;
+    assert!(!night.holds_at(&BTreeMap::new(), &clock));
+    clock.hour = 0;
+    assert!(night.holds_at(&BTreeMap::new(), &clock));
+
+    // Conversation variables take precedence over the clock
+    let mut variables = BTreeMap::new();
+    variables.insert("day".into(), Value::Int(1));
+    assert!(!night.holds_at(&variables, &clock));
+
+    // Other names are not read from the clock
+    assert!(!Condition::parse("time >= 0")
+        .unwrap()
+        .holds_at(&variables, &clock));
+    assert!(Condition::parse("minute == 15")
+        .unwrap()
+        .holds_at(&variables, &clock));
+}
+
+#[test]
+fn test_holds_with() {
+    let inventory = |item: &str| match item {
+        "rusty_key" => 1,
+        "arrows" => 12,
+        _ => 0,
+    };
+    let variables = BTreeMap::new();
+    for source in [
+        "item(rusty_key) && !item(torch)",
+        "item(arrows) >= 10 && item(torch) == 0",
+    ] {
+        let condition = Condition::parse(source).unwrap();
+        assert!(
+            condition.holds_with(&variables, None, Some(&inventory)),
+            "{}",
+            source
+        );
+        // No items are held without an inventory
+        assert!(!condition.holds(&variables), "{}", source);
+    }
+    assert!(Condition::parse("!item(rusty_key) && item(arrows) < 1")
+        .unwrap()
+        .holds(&variables));
+
+    // Conversation variables take precedence over the inventory
+    let mut variables = BTreeMap::new();
+    variables.insert("item.rusty_key".into(), Value::Bool(false));
+    assert!(!Condition::parse("item(rusty_key)").unwrap().holds_with(
+        &variables,
+        None,
+        Some(&inventory)
+    ));
+}
@@ -0,0 +1,136 @@
+//! Debuggers which pause a walked conversation at breakpoints on nodes, e.g. to diagnose why a node was reached.
+//!
+//! A [`Debugger`] is attached to a [`Walker`][`crate::Walker`] with [`Walker::set_debugger`][`crate::Walker::set_debugger`], and breakpoints are set on node keys with [`Walker::add_breakpoint`][`crate::Walker::add_breakpoint`]. When the walker enters a node with a breakpoint, by following a link or passing through an automatic node, the debugger may inspect and change the variables of the conversation, and choose which link is followed next.
+
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    string::String,
+    sync::Arc,
+};
+use core::fmt;
+
+use crate::{node::Node, value::Value};
+
+/// A [`Debugger`] is called when a walked conversation enters a [`Node`] with a breakpoint.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{debugger::{Break, Debugger, Resume}, Condition, Link, Node, Tree, Walker};
+///
+/// // Leave the fork through its last link, even though it is locked
+/// struct Last;
+/// impl Debugger for Last {
+///     fn on_break(&self, context: Break<'_>) -> Resume {
+///         context.variables.insert("visited".to_owned(), true.into());
+///         Resume::Follow(context.node.links.len() - 1)
+///     }
+/// }
+///
+/// let mut tree = Tree::new();
+/// let mut fork = Node::new("fork", "Which way?");
+/// fork.links.push(Link::new("left", "Left."));
+/// let mut right = Link::new("right", "Right.");
+/// right.condition = Some(Condition::parse("has_key").unwrap());
+/// fork.links.push(right);
+/// tree.insert_node(Node::new("left", "Left it is."));
+/// tree.insert_node(Node::new("right", "Right it is."));
+/// let mut start = Node::new("start", "Hello!");
+/// start.links.push(Link::new("fork", "Go on."));
+/// tree.insert_node(fork);
+/// tree.set_root_node(start).unwrap();
+///
+/// let mut walker = Walker::new(&tree).unwrap();
+/// walker.set_debugger(Last);
+/// walker.add_breakpoint("fork");
+/// assert_eq!("right", walker.choose(0).unwrap().key());
+/// assert_eq!(&["start", "fork"], walker.history());
+/// assert!(walker.variables().contains_key("visited"));
+/// ```
+pub trait Debugger: Send + Sync {
+    /// Called when the walker enters a [`Node`] with a breakpoint, before its choices are presented or it is passed through. Returns how the walker resumes.
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - The node entered, and the state of the conversation.
+    fn on_break(&self, context: Break<'_>) -> Resume;
+}
+
+/// A [`Break`] is the state of a conversation paused at a breakpoint, given to a [`Debugger`].
+#[derive(Debug)]
+pub struct Break<'b> {
+    /// The [`Node`] entered.
+    pub node: &'b Node,
+
+    /// The values of conversation variables, which may be changed before the walker resumes.
+    pub variables: &'b mut BTreeMap<String, Value>,
+
+    /// The keys of the nodes visited before the node, oldest first. Automatic nodes passed through are not included.
+    pub history: &'b [String],
+}
+
+/// How a walker resumes from a breakpoint. See also: [`Debugger::on_break`].
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum Resume {
+    /// The walker continues as it would have without the breakpoint.
+    #[default]
+    Continue,
+    /// The walker follows a link of the node, by its index in [`Node#links`][`Node#structfield.links`], whether or not its condition holds. Indices of links which do not exist, or which lead to nodes which do not exist, continue instead.
+    Follow(usize),
+}
+
+/// A cloneable handle to a [`Debugger`] held by a [`Walker`][`crate::Walker`].
+#[derive(Clone)]
+struct DebuggerHandle(Arc<dyn Debugger>);
+
+impl fmt::Debug for DebuggerHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("DebuggerHandle(..)")
+    }
+}
+
+/// The breakpoints of a walker, and the debugger called at them.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Breakpoints {
+    pub(crate) keys: BTreeSet<String>,
+    debugger: Option<DebuggerHandle>,
+}
+
+impl Breakpoints {
+    pub(crate) fn set_debugger<D>(&mut self, debugger: D)
+    where
+        D: Debugger + 'static,
+    {
+        self.debugger = Some(DebuggerHandle(Arc::new(debugger)));
+    }
+
+    pub(crate) fn clear_debugger(&mut self) {
+        self.debugger = None;
+    }
+
+    // Calls the debugger if the node has a breakpoint, returning the index of the link it forces
+    pub(crate) fn check(
+        &self,
+        node: &Node,
+        variables: &mut BTreeMap<String, Value>,
+        history: &[String],
+    ) -> Option<usize> {
+        let debugger = self.debugger.as_ref()?;
+        if !self.keys.contains(&node.key) {
+            return None;
+        }
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(node = %node.key, "breakpoint hit");
+
+        let context = Break {
+            node,
+            variables,
+            history,
+        };
+        match debugger.0.on_break(context) {
+            Resume::Continue => None,
+            Resume::Follow(index) => Some(index).filter(|index| *index < node.links.len()),
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+
+use crate::tree::Tree;
+
+/// A [`TreeDiff`] lists the keys of the nodes which differ between two versions of a [`crate::Tree`], e.g. after a dialogue file is saved again. See [`Tree::diff`][`crate::Tree::diff`] and [`Tree::update`][`crate::Tree::update`].
+///
+/// # Examples
+///
+/// ```
+/// use convo::{Node, Tree};
+/// let mut old = Tree::new();
+/// old.insert_node(Node::new("start", "Hello!"));
+/// old.insert_node(Node::new("gone", "Bye!"));
+/// let mut new = Tree::new();
+/// new.insert_node(Node::new("start", "Hi!"));
+/// new.insert_node(Node::new("fresh", "Welcome!"));
+///
+/// let diff = old.diff(&new);
+/// assert_eq!(vec!["fresh"], diff.added);
+/// assert_eq!(vec!["start"], diff.changed);
+/// assert_eq!(vec!["gone"], diff.removed);
+/// assert!(diff.touches("start"));
+/// ```
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct TreeDiff {
+    /// The keys of nodes only in the new version, in the order they are stored.
+    pub added: Vec<String>,
+
+    /// The keys of nodes in both versions whose content differs, in the order they are stored in the new version.
+    pub changed: Vec<String>,
+
+    /// The keys of nodes only in the old version, in the order they were stored.
+    pub removed: Vec<String>,
+}
+
+impl TreeDiff {
+    /// Returns whether no nodes were added, changed, or removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Node, Tree};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// assert!(tree.diff(&tree.clone()).is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+    }
+
+    /// Returns whether a node was added, changed, or removed.
+    ///
+    /// # Arguments
+    ///
+    /// * `node_key` - A string slice that holds the key of the node.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Node, Tree};
+    /// let old = Tree::new();
+    /// let mut new = Tree::new();
+    /// new.insert_node(Node::new("start", "Hello!"));
+    /// assert!(old.diff(&new).touches("start"));
+    /// assert!(!old.diff(&new).touches("end"));
+    /// ```
+    pub fn touches(&self, node_key: &str) -> bool {
+        self.added
+            .iter()
+            .chain(&self.changed)
+            .chain(&self.removed)
+            .any(|key| key == node_key)
+    }
+}
+
+/// A [`Migration`] maps the node keys of an old version of a [`Tree`] to the keys of a new version, following the stable [`id`][`crate::Node#structfield.id`]s of nodes through renames, e.g. to fix up the saved conversations of a live game after its dialogue is patched.
+///
+/// A node is renamed if a node of the new version has its id but another key. It is removed if no node of the new version has its id, or, for nodes without ids, its key. Every other key maps to itself.
+///
+/// Walkers are migrated with [`Walker::migrate`][`crate::Walker::migrate`], and stored sessions with `Store::migrate` when the `storage` feature is enabled.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{Migration, Node, Tree};
+/// let mut old = Tree::new();
+/// let mut node = Node::new("greeting", "Hello!");
+/// node.id = Some(1);
+/// old.insert_node(node);
+/// old.insert_node(Node::new("gone", "Bye!"));
+///
+/// let mut new = Tree::new();
+/// let mut node = Node::new("hello", "Hello!");
+/// node.id = Some(1);
+/// new.insert_node(node);
+///
+/// let migration = Migration::new(&old, &new);
+/// assert_eq!(Some("hello"), migration.key("greeting"));
+/// assert_eq!(None, migration.key("gone"));
+/// assert_eq!(Some("other"), migration.key("other"));
+/// ```
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Migration {
+    /// The new keys of renamed nodes, or [`None`] for removed nodes, mapped by old key.
+    keys: BTreeMap<String, Option<String>>,
+}
+
+impl Migration {
+    /// Returns the [`Migration`] from an old version of a [`Tree`] to a new version.
+    ///
+    /// # Arguments
+    ///
+    /// * `old` - The old version of the tree, e.g. the one saved games were made with.
+    /// * `new` - The new version of the tree.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Migration, Node, Tree};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// assert!(Migration::new(&tree, &tree).is_empty());
+    /// ```
+    pub fn new(old: &Tree, new: &Tree) -> Migration {
+        let mut keys = BTreeMap::new();
+        for node in old.nodes() {
+            let new_key = match node.id {
+                Some(id) => new.node_by_id(id).map(|new_node| &new_node.key),
+                None => new.node(&node.key).map(|new_node| &new_node.key),
+            };
+            if new_key != Some(&node.key) {
+                keys.insert(node.key.clone(), new_key.cloned());
+            }
+        }
+        Migration { keys }
+    }
+
+    /// Returns the key a node of the old version has in the new version, or [`None`] if it was removed.
+    ///
+    /// # Arguments
+    ///
+    /// * `old_key` - A string slice that holds the key of a node in the old version.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Migration, Node, Tree};
+    /// let mut old = Tree::new();
+    /// old.insert_node(Node::new("start", "Hello!"));
+    /// let migration = Migration::new(&old, &Tree::new());
+    /// assert_eq!(None, migration.key("start"));
+    /// ```
+    pub fn key<'m>(&'m self, old_key: &'m str) -> Option<&'m str> {
+        match self.keys.get(old_key) {
+            Some(new_key) => new_key.as_deref(),
+            None => Some(old_key),
+        }
+    }
+
+    /// Returns an iterator over the old and new keys of renamed nodes, ordered by old key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Migration, Node, Tree};
+    /// let mut old = Tree::new();
+    /// let mut node = Node::new("a", "A.");
+    /// node.id = Some(1);
+    /// old.insert_node(node.clone());
+    /// let mut new = Tree::new();
+    /// new.insert_node(node.with_key("b"));
+    /// let migration = Migration::new(&old, &new);
+    /// let renamed: Vec<_> = migration.renamed().collect();
+    /// assert_eq!(vec![("a", "b")], renamed);
+    /// ```
+    pub fn renamed(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.keys
+            .iter()
+            .filter_map(|(old_key, new_key)| Some((old_key.as_str(), new_key.as_deref()?)))
+    }
+
+    /// Returns an iterator over the old keys of removed nodes, in order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Migration, Node, Tree};
+    /// let mut old = Tree::new();
+    /// old.insert_node(Node::new("start", "Hello!"));
+    /// let migration = Migration::new(&old, &Tree::new());
+    /// let removed: Vec<_> = migration.removed().collect();
+    /// assert_eq!(vec!["start"], removed);
+    /// ```
+    pub fn removed(&self) -> impl Iterator<Item = &str> {
+        self.keys
+            .iter()
+            .filter(|(_, new_key)| new_key.is_none())
+            .map(|(old_key, _)| old_key.as_str())
+    }
+
+    /// Returns whether no nodes were renamed or removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::Migration;
+    /// assert!(Migration::default().is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    // Migrates the keys of a conversation, forgetting the history before any step back to a removed node. Returns `None` if the current node was removed
+    pub(crate) fn migrate_keys(
+        &self,
+        current_key: &str,
+        history: &[String],
+    ) -> Option<(String, Vec<String>)> {
+        let current_key = self.key(current_key)?.into();
+        let mut keys = Vec::with_capacity(history.len());
+        for key in history {
+            match self.key(key) {
+                Some(key) => keys.push(key.into()),
+                None => keys.clear(),
+            }
+        }
+        Some((current_key, keys))
+    }
+}
@@ -0,0 +1,347 @@
+//! Draft mode, which lets writers import and export dialogue which is still being written.
+//!
+//! By default, the [importer][`crate::importer`] and [exporter][`crate::exporter`] are strict: links must target existing nodes, and there must be no [`Placeholder`]s, such as dialogue starting with `TODO` or nodes marked `todo: true`, so unfinished content never reaches a build. With `draft: true` in their options, these rules are not enforced, nodes may be written without dialogue or with an empty `links` array, and [`check`] reports the unfinished content as warnings instead. [`placeholders`] lists the outstanding writing work, e.g. for production tracking.
+
+use alloc::{borrow::ToOwned, format, string::String, vec, vec::Vec};
+
+#[cfg(feature = "std")]
+use crate::error::TreeError;
+use crate::{
+    analysis::{csv_field, DialogueLocation},
+    lint::{self, Diagnostic, Rule},
+    node::{Node, NodeKind},
+    tree::Tree,
+};
+
+/// The markers which make dialogue a placeholder when it starts with one, e.g. `TODO` or `TBD: haggling`.
+pub const PLACEHOLDERS: &[&str] = &["TODO", "TBD", "FIXME"];
+
+/// Returns whether dialogue is a placeholder, i.e. it starts with one of the [`PLACEHOLDERS`] as a whole word.
+///
+/// # Arguments
+///
+/// * `text` - A string slice that holds the dialogue.
+///
+/// # Examples
+///
+/// ```
+/// use convo::draft;
+/// assert!(draft::is_placeholder("TODO"));
+/// assert!(draft::is_placeholder(" TBD: the guard haggles"));
+/// assert!(!draft::is_placeholder("TODOS are a fine tradition."));
+/// assert!(!draft::is_placeholder("Nothing left to do."));
+/// ```
+pub fn is_placeholder(text: &str) -> bool {
+    let text = text.trim_start();
+    PLACEHOLDERS.iter().any(|marker| {
+        matches!(text.strip_prefix(marker), Some(rest) if !rest.starts_with(char::is_alphanumeric))
+    })
+}
+
+/// A [`PlaceholderKind`] describes why content is a [`Placeholder`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum PlaceholderKind {
+    /// The node is marked `todo: true`.
+    Todo,
+    /// The dialogue starts with one of the [`PLACEHOLDERS`], e.g. `TODO: prices`.
+    Marker,
+    /// The node has no dialogue or links, and is not marked as an ending.
+    Stub,
+}
+
+impl PlaceholderKind {
+    /// Returns a stable, human-readable identifier for this kind.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::draft::PlaceholderKind;
+    /// assert_eq!("marker", PlaceholderKind::Marker.id());
+    /// ```
+    pub fn id(&self) -> &'static str {
+        match self {
+            PlaceholderKind::Todo => "todo",
+            PlaceholderKind::Marker => "marker",
+            PlaceholderKind::Stub => "stub",
+        }
+    }
+}
+
+/// A [`Placeholder`] is unfinished writing in a [`Tree`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Placeholder {
+    /// Where the placeholder is. Nodes marked `todo: true` and stubs are located by their dialogue.
+    pub location: DialogueLocation,
+
+    /// Why the content is a placeholder.
+    pub kind: PlaceholderKind,
+
+    /// The placeholder text, e.g. `TODO: prices`, or the dialogue of a node marked `todo: true`.
+    pub text: String,
+}
+
+/// Returns the [`Placeholder`]s of a [`Tree`], in the order nodes are stored, so outstanding writing work can be tracked from the dialogue itself.
+///
+/// A node marked `todo: true` is reported once as a whole, rather than for each placeholder in its dialogue, but placeholders in its links are still reported.
+///
+/// # Arguments
+///
+/// * `tree` - A [`Tree`] to search.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{analysis::DialogueLocation, draft::{self, PlaceholderKind}, Link, Node, Tree};
+/// let mut tree = Tree::new();
+/// let mut start = Node::new("start", "Welcome!");
+/// start.links.push(Link::new("shop", "TODO: a better pitch"));
+/// let mut shop = Node::new("shop", "We sell things.");
+/// shop.todo = true;
+/// tree.insert_node(start);
+/// tree.insert_node(shop);
+///
+/// let placeholders = draft::placeholders(&tree);
+/// assert_eq!(DialogueLocation::Link("start".into(), 0), placeholders[0].location);
+/// assert_eq!("TODO: a better pitch", placeholders[0].text);
+/// assert_eq!(PlaceholderKind::Todo, placeholders[1].kind);
+/// ```
+pub fn placeholders(tree: &Tree) -> Vec<Placeholder> {
+    let mut placeholders = vec![];
+    for node in tree.nodes() {
+        let mut report = |location: DialogueLocation, kind: PlaceholderKind, text: &str| {
+            placeholders.push(Placeholder {
+                location,
+                kind,
+                text: text.to_owned(),
+            });
+        };
+        let whole = DialogueLocation::Node(node.key.clone());
+        if node.todo {
+            report(whole, PlaceholderKind::Todo, &node.dialogue);
+        } else if is_stub(node) {
+            report(whole, PlaceholderKind::Stub, &node.dialogue);
+        } else if node.lines.is_empty() {
+            if is_placeholder(&node.dialogue) {
+                report(whole, PlaceholderKind::Marker, &node.dialogue);
+            }
+        } else {
+            for (i, line) in node.lines.iter().enumerate() {
+                if is_placeholder(&line.text) {
+                    let location = DialogueLocation::Line(node.key.clone(), i);
+                    report(location, PlaceholderKind::Marker, &line.text);
+                }
+            }
+        }
+        for (i, link) in node.links.iter().enumerate() {
+            if is_placeholder(link.text()) {
+                let location = DialogueLocation::Link(node.key.clone(), i);
+                report(location, PlaceholderKind::Marker, link.text());
+            }
+        }
+    }
+    placeholders
+}
+
+/// Returns [`Placeholder`]s as CSV, with a header row and one row per placeholder, e.g. to import into a production tracker. The `location` column is `dialogue`, `line N`, or `link N`, counting from 1.
+///
+/// # Arguments
+///
+/// * `placeholders` - The [`Placeholder`]s to write, e.g. from [`placeholders`].
+///
+/// # Examples
+///
+/// ```
+/// use convo::{draft, Node, Tree};
+/// let mut tree = Tree::new();
+/// tree.insert_node(Node::new("start", "TBD: greeting, tone?"));
+/// let csv = draft::to_csv(&draft::placeholders(&tree));
+/// assert_eq!("node,location,kind,text\nstart,dialogue,marker,\"TBD: greeting, tone?\"\n", csv);
+/// ```
+pub fn to_csv(placeholders: &[Placeholder]) -> String {
+    let mut csv = "node,location,kind,text\n".to_owned();
+    for placeholder in placeholders {
+        let location = match &placeholder.location {
+            DialogueLocation::Node(_) => "dialogue".to_owned(),
+            DialogueLocation::Line(_, i) => format!("line {}", i + 1),
+            DialogueLocation::Link(_, i) => format!("link {}", i + 1),
+        };
+        csv.push_str(&format!(
+            "{},{},{},{}\n",
+            csv_field(placeholder.location.node_key()),
+            location,
+            placeholder.kind.id(),
+            csv_field(&placeholder.text)
+        ));
+    }
+    csv
+}
+
+/// Returns warnings for the unfinished content of a [`Tree`]: nodes which cannot be reached from the root node, links to nodes which do not exist, and placeholder dialogue, including nodes without dialogue or links which are not marked as endings.
+///
+/// Links which jump to another tree of a [`crate::project::Project`], e.g. `shop/start`, are not checked.
+///
+/// # Arguments
+///
+/// * `tree` - A [`Tree`] to check.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{draft, lint::Rule, Link, Node, Tree};
+/// let mut tree = Tree::new();
+/// let mut start = Node::new("start", "TODO");
+/// start.links.push(Link::new("haggle", "Too much!"));
+/// tree.set_root_node(start).unwrap();
+/// tree.insert_node(Node::new("idea", ""));
+///
+/// let rules: Vec<Rule> = draft::check(&tree).iter().map(|d| d.rule).collect();
+/// assert_eq!(
+///     vec![Rule::UnreachableNode, Rule::MissingNode, Rule::Placeholder, Rule::Placeholder],
+///     rules
+/// );
+/// ```
+pub fn check(tree: &Tree) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    lint::lint_unreachable_nodes(tree, &mut diagnostics);
+    check_missing_nodes(tree, &mut diagnostics);
+    check_placeholders(tree, &mut diagnostics);
+    diagnostics
+}
+
+// Enforces the rules draft mode relaxes, returning the first violation
+#[cfg(feature = "std")]
+pub(crate) fn validate(tree: &Tree) -> Result<(), TreeError> {
+    let missing = tree
+        .nodes()
+        .flat_map(|node| &node.links)
+        .find(|link| is_missing(tree, &link.to_key));
+    if let Some(link) = missing {
+        return Err(tree.missing_node(&link.to_key));
+    }
+    let mut diagnostics = vec![];
+    check_placeholders(tree, &mut diagnostics);
+    match diagnostics.into_iter().next() {
+        Some(diagnostic) => Err(TreeError::Validation(diagnostic.message)),
+        None => Ok(()),
+    }
+}
+
+// Qualified keys jump to other trees, which are checked by projects
+fn is_missing(tree: &Tree, node_key: &str) -> bool {
+    !node_key.contains('/') && !tree.contains_node(node_key)
+}
+
+fn check_missing_nodes(tree: &Tree, diagnostics: &mut Vec<Diagnostic>) {
+    for node in tree.nodes() {
+        for link in node
+            .links
+            .iter()
+            .filter(|link| is_missing(tree, &link.to_key))
+        {
+            diagnostics.push(Diagnostic::new(
+                Rule::MissingNode,
+                &node.key,
+                format!(
+                    "Link `{}` in node `{}` targets node `{}`, which does not exist",
+                    link.text(),
+                    node.key,
+                    link.to_key
+                ),
+            ));
+        }
+    }
+}
+
+fn check_placeholders(tree: &Tree, diagnostics: &mut Vec<Diagnostic>) {
+    for placeholder in placeholders(tree) {
+        let node_key = placeholder.location.node_key();
+        let message = match (&placeholder.kind, &placeholder.location) {
+            (PlaceholderKind::Todo, _) => format!("Node `{}` is marked as todo", node_key),
+            (PlaceholderKind::Stub, _) => format!(
+                "Node `{}` has placeholder content, without dialogue or links",
+                node_key
+            ),
+            (PlaceholderKind::Marker, DialogueLocation::Node(_)) => {
+                format!("Node `{}` has placeholder dialogue", node_key)
+            }
+            (PlaceholderKind::Marker, DialogueLocation::Line(_, i)) => format!(
+                "Node `{}` has placeholder dialogue in line {}",
+                node_key,
+                i + 1
+            ),
+            (PlaceholderKind::Marker, DialogueLocation::Link(_, i)) => format!(
+                "Node `{}` has placeholder dialogue in its link to `{}`",
+                node_key,
+                link_target(tree, node_key, *i)
+            ),
+        };
+        diagnostics.push(Diagnostic::new(Rule::Placeholder, node_key, message));
+    }
+}
+
+fn link_target<'t>(tree: &'t Tree, node_key: &str, index: usize) -> &'t str {
+    tree.node(node_key)
+        .and_then(|node| node.links.get(index))
+        .map_or("", |link| link.to_key.as_str())
+}
+
+// Switches have no dialogue of their own, and endings may close silently, so only other nodes can be empty stubs
+fn is_stub(node: &Node) -> bool {
+    node.dialogue.trim().is_empty()
+        && node.links.is_empty()
+        && !node.end
+        && !matches!(node.kind, NodeKind::Switch { .. })
+}
+
+#[cfg(test)]
+#[test]
+fn test_check() {
+    use crate::{link::Link, node::Line};
+
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "Welcome.");
+    start.links.push(Link::new("shop", "TBD"));
+    start.links.push(Link::new("inn/start", "To the inn."));
+    let mut shop = Node::new("shop", "");
+    shop.set_lines(vec![
+        Line::new("keeper", "Buying?"),
+        Line::new("keeper", "FIXME: prices"),
+    ]);
+    shop.end = true;
+    tree.insert_node(start);
+    tree.insert_node(shop);
+    tree.set_root_key("start").unwrap();
+
+    // Jumps are not missing, and lines are checked one at a time
+    let diagnostics = check(&tree);
+    assert_eq!(2, diagnostics.len());
+    assert_eq!(
+        "Node `start` has placeholder dialogue in its link to `shop`",
+        diagnostics[0].message
+    );
+    assert_eq!(
+        "Node `shop` has placeholder dialogue in line 2",
+        diagnostics[1].message
+    );
+
+    // Endings may be silent, but other nodes without dialogue or links are stubs
+    tree.node_mut("shop").unwrap().set_lines(vec![]);
+    assert_eq!(1, check(&tree).len());
+    tree.node_mut("shop").unwrap().end = false;
+    assert_eq!(
+        "Node `shop` has placeholder content, without dialogue or links",
+        check(&tree)[1].message
+    );
+
+    // Nodes marked as todo are reported once, but their links are still checked
+    let start = tree.node_mut("start").unwrap();
+    start.dialogue = "TODO".into();
+    start.todo = true;
+    let diagnostics = check(&tree);
+    assert_eq!("Node `start` is marked as todo", diagnostics[0].message);
+    assert_eq!(3, diagnostics.len());
+    assert_eq!(
+        "node,location,kind,text\nstart,dialogue,todo,TODO\nstart,link 1,marker,TBD\nshop,dialogue,stub,\n",
+        to_csv(&placeholders(&tree))
+    );
+}
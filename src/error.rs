@@ -1,6 +1,9 @@
 //! A family of related errors when working with [`convo`][`crate`].
 
+use alloc::string::String;
+
 /// An [`ExportError`] is a category of errors returned by exporter functions that returns [`Result`]s.
+#[cfg(feature = "std")]
 #[derive(Debug)]
 pub enum ExportError {
     /// An error caused when IO issues occur during exporting.
@@ -10,17 +13,22 @@ pub enum ExportError {
     /// An error caused when a tree is not considered legal to export.
     /// See also: [validation rules](https://github.com/simbleau/convo/blob/dev/FORMATTING.md#validation-rules).
     Validation(TreeError),
+    /// An error caused when a string cannot be written with the [`crate::exporter::Quoting`] of the [`crate::exporter::ExportOptions`], given by the string.
+    Unencodable(String),
 }
+#[cfg(feature = "std")]
 impl From<std::io::Error> for ExportError {
     fn from(item: std::io::Error) -> Self {
         ExportError::IO(item)
     }
 }
+#[cfg(feature = "std")]
 impl From<yaml_rust::EmitError> for ExportError {
     fn from(item: yaml_rust::EmitError) -> Self {
         ExportError::Emit(item)
     }
 }
+#[cfg(feature = "std")]
 impl From<TreeError> for ExportError {
     fn from(item: TreeError) -> Self {
         ExportError::Validation(item)
@@ -28,6 +36,7 @@ impl From<TreeError> for ExportError {
 }
 
 /// A [`ImportError`] is a category of errors returned by parser functions that returns [`Result`]s.
+#[cfg(feature = "std")]
 #[derive(Debug)]
 pub enum ImportError {
     /// An error caused when IO issues occur during importing.
@@ -39,23 +48,102 @@ pub enum ImportError {
     Validation(TreeError),
     /// An error caused when the target content contains multiple YAML documents.
     MultipleDocumentsProvided(),
+    /// An error caused when JSON is unable to be parsed, e.g. by [`crate::foreign::chatmapper`].
+    #[cfg(feature = "json")]
+    Json(serde_json::Error),
+    /// An error caused when XML is unable to be parsed, e.g. by [`crate::foreign::articy`].
+    #[cfg(feature = "xml")]
+    Xml(roxmltree::Error),
+    /// An error caused when a spreadsheet is unable to be read, see [`crate::foreign::spreadsheet`].
+    #[cfg(feature = "spreadsheet")]
+    Spreadsheet(calamine::Error),
 }
+#[cfg(feature = "std")]
 impl From<std::io::Error> for ImportError {
     fn from(item: std::io::Error) -> Self {
         ImportError::IO(item)
     }
 }
+#[cfg(feature = "std")]
 impl From<yaml_rust::ScanError> for ImportError {
     fn from(item: yaml_rust::ScanError) -> Self {
         ImportError::Scan(item)
     }
 }
+#[cfg(feature = "json")]
+impl From<serde_json::Error> for ImportError {
+    fn from(item: serde_json::Error) -> Self {
+        ImportError::Json(item)
+    }
+}
+#[cfg(feature = "xml")]
+impl From<roxmltree::Error> for ImportError {
+    fn from(item: roxmltree::Error) -> Self {
+        ImportError::Xml(item)
+    }
+}
+#[cfg(feature = "spreadsheet")]
+impl From<calamine::Error> for ImportError {
+    fn from(item: calamine::Error) -> Self {
+        ImportError::Spreadsheet(item)
+    }
+}
+#[cfg(feature = "std")]
 impl From<TreeError> for ImportError {
     fn from(item: TreeError) -> Self {
         ImportError::Validation(item)
     }
 }
 
+/// A [`ProjectError`] is a category of errors returned when loading a [`crate::project::Project`].
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub enum ProjectError {
+    /// An error caused when the project manifest is unable to be imported.
+    /// See also: [project manifests](https://github.com/simbleau/convo/blob/dev/FORMATTING.md#project-manifests).
+    Manifest(ImportError),
+    /// An error caused when a dialogue file listed by the manifest is unable to be imported, given by its path.
+    File(std::path::PathBuf, ImportError),
+    /// An error caused when the files of a project are not considered legal together.
+    Validation(String),
+    /// An error caused when the lint configuration beside the manifest is unable to be imported.
+    /// See also: [lint configuration](https://github.com/simbleau/convo/blob/dev/FORMATTING.md#lint-configuration).
+    LintConfig(ImportError),
+    /// An error caused when the characters file listed by the manifest is unable to be imported.
+    /// See also: [characters](https://github.com/simbleau/convo/blob/dev/FORMATTING.md#characters).
+    Characters(ImportError),
+}
+#[cfg(feature = "std")]
+impl From<ImportError> for ProjectError {
+    fn from(item: ImportError) -> Self {
+        ProjectError::Manifest(item)
+    }
+}
+
+/// A [`StorageError`] is a category of errors returned when saving or loading with a [`crate::storage::Store`].
+#[cfg(feature = "storage")]
+#[derive(Debug)]
+pub enum StorageError {
+    /// An error caused when the embedded database fails, e.g. when it is unable to be opened.
+    Database(sled::Error),
+    /// An error caused when a stored record is unable to be decoded, given by its description.
+    Corrupt(String),
+    /// An error caused when a stored session does not fit the tree it is loaded with, e.g. its node was removed.
+    Validation(TreeError),
+}
+#[cfg(feature = "storage")]
+impl From<sled::Error> for StorageError {
+    fn from(item: sled::Error) -> Self {
+        StorageError::Database(item)
+    }
+}
+#[cfg(feature = "storage")]
+impl From<TreeError> for StorageError {
+    fn from(item: TreeError) -> Self {
+        StorageError::Validation(item)
+    }
+}
+
 /// A [`TreeError`] is a category of validation errors returned when a tree is not considered legal.
 /// See also: [validation rules](https://github.com/simbleau/convo/blob/dev/FORMATTING.md#validation-rules).
 #[derive(Debug)]
@@ -67,7 +155,17 @@ pub enum TreeError {
     /// See also: [`crate::Tree#current`][`crate::Tree#structfield.current].
     CurrentNotSet(),
     /// An error caused when a [`crate::Tree`] is missing a necessary [`crate::Node`].
-    NodeDNE(String),
+    NodeDNE {
+        /// The key of the missing node.
+        key: String,
+        /// The existing key closest to the missing key, if it is close enough to be a likely typo.
+        suggestion: Option<String>,
+    },
+    /// An error caused when a [`crate::Node`] is missing a necessary [`crate::Link`], given by its index.
+    LinkDNE(usize),
     /// An error caused when validating a family of rules a [`crate::Tree`] must obey.
     Validation(String),
+    /// An error caused when continuing a conversation which was aborted, given the reason it was aborted.
+    /// See also: [`crate::Walker::abort`].
+    Aborted(String),
 }
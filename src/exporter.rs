@@ -1,14 +1,134 @@
 //! A family of functions which export [`Tree`]s into YAML data.
 
 use crate::{
+    availability::Availability,
+    condition::{Clause, Op},
+    draft,
     error::{ExportError, TreeError},
-    link::Link,
-    node::Node,
+    link::{Link, WhenUnmet},
+    meta::Meta,
+    node::{Node, NodeKind},
+    pacing::Pacing,
+    stage::StageDirection,
     tree::Tree,
+    value::{Type, Value},
 };
 
-use std::{fs::File, io::Write, path::Path};
-use yaml_rust::{yaml, Yaml, YamlEmitter};
+use std::{
+    collections::BTreeMap,
+    convert::TryFrom,
+    fs::File,
+    io::Write,
+    path::Path,
+    time::{Duration, Instant},
+};
+use yaml_rust::{yaml, Yaml};
+
+/// A [`Quoting`] determines when the strings of an exported [`Tree`] are quoted.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum Quoting {
+    /// Strings are written plainly, unless they would be read back as another value, such as `true` or `0o17`, or contain characters with meaning in YAML, in which case they are double quoted.
+    #[default]
+    Auto,
+    /// String values are always double quoted. Keys are quoted as with [`Quoting::Auto`].
+    Always,
+    /// Strings are never quoted. Strings which would need quotes are returned as an [`ExportError::Unencodable`].
+    Never,
+}
+
+/// [`ExportOptions`] control how a [`Tree`] is written as YAML.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{exporter::{self, ExportOptions, Quoting}, Node, Tree};
+/// let mut tree = Tree::new();
+/// tree.set_root_node(Node::new("start", "Hello!")).unwrap();
+/// let options = ExportOptions {
+///     quoting: Quoting::Always,
+///     ..Default::default()
+/// };
+/// let source = exporter::tree_to_source_with(&tree, &options).unwrap();
+/// assert!(source.contains(r#"dialogue: "Hello!""#));
+///
+/// // Long dialogue is written as a literal block
+/// tree.insert_node(Node::new("start", "Hello!\nIt's a long way from home."));
+/// let options = ExportOptions {
+///     block_dialogue: Some(16),
+///     ..Default::default()
+/// };
+/// let source = exporter::tree_to_source_with(&tree, &options).unwrap();
+/// assert!(source.ends_with("dialogue: |-\n      Hello!\n      It's a long way from home."));
+///
+/// // Hashes in sequences start on their own line, indented by 4 spaces
+/// tree.insert_node(Node::new("start", "Hello!"));
+/// let options = ExportOptions {
+///     indent: 4,
+///     compact: false,
+///     ..Default::default()
+/// };
+/// let source = exporter::tree_to_source_with(&tree, &options).unwrap();
+/// assert!(source.contains("nodes:\n    start:\n        dialogue: Hello!"));
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ExportOptions {
+    /// When strings are quoted.
+    pub quoting: Quoting,
+    /// The number of characters beyond which dialogue is written as a literal block scalar (`|-`), one line of source per line of dialogue, or `None` to never write blocks.
+    /// Dialogue which cannot be read back exactly from a block, e.g. with leading spaces or control characters, is written as a string.
+    pub block_dialogue: Option<usize>,
+    /// Whether the tree is written in [draft mode][`crate::draft`], where links to missing nodes and placeholder dialogue are allowed.
+    pub draft: bool,
+    /// The number of spaces per level of indentation, which is at least 1. Defaults to 2.
+    pub indent: usize,
+    /// Whether hashes in sequences start on the same line as their `-`, e.g. `- end: Bye`, rather than on the next line. Defaults to `true`.
+    pub compact: bool,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        ExportOptions {
+            quoting: Quoting::default(),
+            block_dialogue: None,
+            draft: false,
+            indent: 2,
+            compact: true,
+        }
+    }
+}
+
+/// [`ExportMetrics`] measure the work of an export, e.g. so asset pipelines can track which dialogue files slow down builds.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{exporter::{self, ExportOptions}, Node, Tree};
+/// let mut tree = Tree::new();
+/// tree.set_root_node(Node::new("start", "Hello!")).unwrap();
+/// let (source, metrics) = exporter::tree_to_source_with_metrics(&tree, &ExportOptions::default()).unwrap();
+/// assert_eq!(1, metrics.nodes);
+/// assert_eq!(source.len(), metrics.bytes);
+/// ```
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct ExportMetrics {
+    /// The size of the written source, in bytes.
+    pub bytes: usize,
+    /// The number of nodes exported.
+    pub nodes: usize,
+    /// The time spent validating the tree.
+    pub validation: Duration,
+    /// The time spent converting the tree to YAML source.
+    pub emit: Duration,
+    /// The time spent writing the file. Sources which are not written to a file take no time.
+    pub write: Duration,
+}
+
+impl ExportMetrics {
+    /// Returns the total time spent exporting.
+    pub fn total(&self) -> Duration {
+        self.validation + self.emit + self.write
+    }
+}
 
 /// Try to save a [`Tree`] as a file.
 ///
@@ -29,17 +149,87 @@ use yaml_rust::{yaml, Yaml, YamlEmitter};
 /// // Make a copy of the file
 /// exporter::export(&tree, "examples/dialogue_files/export.convo.yml").unwrap();
 /// ```
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip_all, fields(path = ?path.as_ref()), err(Debug))
+)]
 pub fn export<P>(tree: &Tree, path: P) -> Result<(), ExportError>
 where
     P: AsRef<Path>,
 {
-    let source = tree_to_source(tree)?;
+    export_with(tree, path, &ExportOptions::default())
+}
+
+/// Try to save a [`Tree`] as a file, written with [`ExportOptions`].
+///
+/// # Arguments
+///
+/// * `tree` - A [`Tree`] that will be saved in a file.
+/// * `path` - A path type that references the file to write.
+/// * `options` - The [`ExportOptions`] which control how the tree is written.
+///
+/// # Errors
+///
+/// * An [`ExportError`] will be returned if the tree is not considered legal, cannot be written with the options, or incurs issues saving.
+///   See also: [validation rules](https://github.com/simbleau/convo/blob/dev/FORMATTING.md#validation-rules).
+///
+/// # Examples
+///
+/// ```
+/// use convo::{importer, exporter::{self, ExportOptions, Quoting}};
+/// let tree = importer::import("examples/dialogue_files/ex_min.convo.yml").unwrap();
+/// let options = ExportOptions {
+///     quoting: Quoting::Always,
+///     ..Default::default()
+/// };
+/// exporter::export_with(&tree, "examples/dialogue_files/export.convo.yml", &options).unwrap();
+/// ```
+pub fn export_with<P>(tree: &Tree, path: P, options: &ExportOptions) -> Result<(), ExportError>
+where
+    P: AsRef<Path>,
+{
+    export_with_metrics(tree, path, options).map(|_| ())
+}
+
+/// Try to save a [`Tree`] as a file, written with [`ExportOptions`], returning the [`ExportMetrics`] of the export.
+///
+/// # Arguments
+///
+/// * `tree` - A [`Tree`] that will be saved in a file.
+/// * `path` - A path type that references the file to write.
+/// * `options` - The [`ExportOptions`] which control how the tree is written.
+///
+/// # Errors
+///
+/// * An [`ExportError`] will be returned as with [`export_with`].
+///
+/// # Examples
+///
+/// ```
+/// use convo::{importer, exporter::{self, ExportOptions}};
+/// let tree = importer::import("examples/dialogue_files/ex_min.convo.yml").unwrap();
+/// let metrics =
+///     exporter::export_with_metrics(&tree, "examples/dialogue_files/export.convo.yml", &ExportOptions::default())
+///         .unwrap();
+/// println!("{} bytes in {:?}", metrics.bytes, metrics.total());
+/// ```
+pub fn export_with_metrics<P>(
+    tree: &Tree,
+    path: P,
+    options: &ExportOptions,
+) -> Result<ExportMetrics, ExportError>
+where
+    P: AsRef<Path>,
+{
+    let (source, metrics) = tree_to_source_with_metrics(tree, options)?;
 
     // Write file
+    let start = Instant::now();
     let mut file = File::create(path)?;
     file.write_all(source.as_bytes())?;
+    let write = start.elapsed();
 
-    Ok(())
+    Ok(ExportMetrics { write, ..metrics })
 }
 
 /// Try to returns a [`String`] which is generated as YAML from a [`Tree`].
@@ -61,6 +251,7 @@ where
 /// root: start
 /// nodes:
 ///   start:
+///     id: 1
 ///     dialogue: I am a recursive node.
 ///     links:
 ///       - start: Recurse!"#;
@@ -68,26 +259,331 @@ where
 /// let source2 = exporter::tree_to_source(&tree).unwrap();
 /// assert_eq!(source, source2);
 /// ```
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip_all, fields(nodes = tree.len()), err(Debug))
+)]
 pub fn tree_to_source(tree: &Tree) -> Result<String, ExportError> {
-    let yaml = tree_to_yaml(tree)?;
+    tree_to_source_with(tree, &ExportOptions::default())
+}
+
+/// Try to returns a [`String`] which is generated as YAML from a [`Tree`], written with [`ExportOptions`].
+///
+/// # Arguments
+///
+/// * `tree` - A [`Tree`] that will be returned as YAML data.
+/// * `options` - The [`ExportOptions`] which control how the tree is written.
+///
+/// # Errors
+///
+/// * An [`ExportError`] will be returned if the tree is not considered legal to export, or cannot be written with the options.
+///   See also: [validation rules](https://github.com/simbleau/convo/blob/dev/FORMATTING.md#validation-rules).
+///
+/// # Examples
+///
+/// ```
+/// use convo::{error::ExportError, exporter::{self, ExportOptions, Quoting}, Node, Tree};
+/// let mut tree = Tree::new();
+/// tree.set_root_node(Node::new("start", "true")).unwrap();
+/// let options = ExportOptions {
+///     quoting: Quoting::Never,
+///     ..Default::default()
+/// };
+/// assert!(matches!(
+///     exporter::tree_to_source_with(&tree, &options),
+///     Err(ExportError::Unencodable(_))
+/// ));
+/// ```
+pub fn tree_to_source_with(tree: &Tree, options: &ExportOptions) -> Result<String, ExportError> {
+    tree_to_source_with_metrics(tree, options).map(|(source, _)| source)
+}
+
+/// Try to returns a [`String`] which is generated as YAML from a [`Tree`], written with [`ExportOptions`], and the [`ExportMetrics`] of the export.
+///
+/// # Arguments
+///
+/// * `tree` - A [`Tree`] that will be returned as YAML data.
+/// * `options` - The [`ExportOptions`] which control how the tree is written.
+///
+/// # Errors
+///
+/// * An [`ExportError`] will be returned as with [`tree_to_source_with`].
+///
+/// # Examples
+///
+/// ```
+/// use convo::{exporter::{self, ExportOptions}, Node, Tree};
+/// let mut tree = Tree::new();
+/// tree.set_root_node(Node::new("start", "Hello!")).unwrap();
+/// let (_, metrics) = exporter::tree_to_source_with_metrics(&tree, &ExportOptions::default()).unwrap();
+/// assert!(metrics.total() >= metrics.validation);
+/// ```
+pub fn tree_to_source_with_metrics(
+    tree: &Tree,
+    options: &ExportOptions,
+) -> Result<(String, ExportMetrics), ExportError> {
+    // Validate the tree, unless it is a draft
+    let start = Instant::now();
+    check_tree(tree, options.draft)?;
+    let validation = start.elapsed();
 
     // Convert to source text
-    let mut writer = String::new();
-    let mut emitter = YamlEmitter::new(&mut writer);
-    emitter.compact(true);
-    emitter.dump(&yaml)?;
+    let start = Instant::now();
+    let yaml = tree_to_yaml(tree)?;
+    let mut emitter = Emitter {
+        source: "---\n".to_owned(),
+        column: 0,
+        quoting: options.quoting,
+        block_dialogue: options.block_dialogue,
+        indent: options.indent.max(1),
+        compact: options.compact,
+        links: false,
+        link: false,
+    };
+    emitter.emit_node(&yaml, false)?;
+    let emit = start.elapsed();
 
-    Ok(writer)
+    let metrics = ExportMetrics {
+        bytes: emitter.source.len(),
+        nodes: tree.len(),
+        validation,
+        emit,
+        write: Duration::ZERO,
+    };
+
+    #[cfg(feature = "tracing")]
+    tracing::debug!(
+        nodes = metrics.nodes,
+        bytes = metrics.bytes,
+        validation = ?metrics.validation,
+        emit = ?metrics.emit,
+        "exported tree"
+    );
+
+    Ok((emitter.source, metrics))
 }
 
-fn tree_to_yaml(tree: &Tree) -> Result<Yaml, TreeError> {
+/// Writes YAML in block style, with compact sequences of hashes by default, as `yaml-rust` does, while controlling how strings are quoted and indented.
+struct Emitter {
+    source: String,
+    // The column at which the current hash or sequence is written
+    column: usize,
+    quoting: Quoting,
+    block_dialogue: Option<usize>,
+    indent: usize,
+    compact: bool,
+    // Whether the next array holds links, and whether the next hash is one of them
+    links: bool,
+    link: bool,
+}
+
+impl Emitter {
+    fn write_indent(&mut self) {
+        for _ in 0..self.column {
+            self.source.push(' ');
+        }
+    }
+
+    fn emit_node(&mut self, node: &Yaml, is_key: bool) -> Result<(), ExportError> {
+        match node {
+            Yaml::Array(array) => self.emit_array(array)?,
+            Yaml::Hash(hash) => self.emit_hash(hash)?,
+            Yaml::String(string) => self.emit_string(string, is_key)?,
+            Yaml::Boolean(value) => self.source.push_str(&value.to_string()),
+            Yaml::Integer(value) => self.source.push_str(&value.to_string()),
+            Yaml::Real(value) => self.source.push_str(value),
+            _ => self.source.push('~'),
+        }
+        Ok(())
+    }
+
+    fn emit_string(&mut self, string: &str, is_key: bool) -> Result<(), ExportError> {
+        let quote = match self.quoting {
+            Quoting::Auto => needs_quotes(string),
+            Quoting::Always => !is_key || needs_quotes(string),
+            Quoting::Never if needs_quotes(string) => {
+                return Err(ExportError::Unencodable(string.to_owned()))
+            }
+            Quoting::Never => false,
+        };
+        if !quote {
+            self.source.push_str(string);
+            return Ok(());
+        }
+
+        // Double quoted strings escape quotes, backslashes, and characters which are not printable
+        self.source.push('"');
+        for c in string.chars() {
+            match c {
+                '"' => self.source.push_str("\\\""),
+                '\\' => self.source.push_str("\\\\"),
+                '\u{8}' => self.source.push_str("\\b"),
+                '\t' => self.source.push_str("\\t"),
+                '\n' => self.source.push_str("\\n"),
+                '\u{c}' => self.source.push_str("\\f"),
+                '\r' => self.source.push_str("\\r"),
+                c if is_unprintable(c) => self.source.push_str(&format!("\\u{:04x}", c as u32)),
+                c => self.source.push(c),
+            }
+        }
+        self.source.push('"');
+        Ok(())
+    }
+
+    // Whether dialogue is long enough to write as a block, and would be read back exactly
+    fn is_block(&self, dialogue: &str) -> bool {
+        self.block_dialogue
+            .is_some_and(|length| dialogue.chars().count() > length)
+            && !dialogue.starts_with([' ', '\t', '\n'])
+            && !dialogue.ends_with('\n')
+            && !dialogue
+                .chars()
+                .any(|c| c != '\n' && c != '\t' && is_unprintable(c))
+            && dialogue
+                .split('\n')
+                .all(|line| line.is_empty() || !line.trim_start().is_empty())
+    }
+
+    fn emit_block(&mut self, dialogue: &str) {
+        self.source.push_str(" |-");
+        self.column += self.indent;
+        for line in dialogue.split('\n') {
+            self.source.push('\n');
+            if !line.is_empty() {
+                self.write_indent();
+                self.source.push_str(line);
+            }
+        }
+        self.column -= self.indent;
+    }
+
+    fn emit_array(&mut self, array: &[Yaml]) -> Result<(), ExportError> {
+        if array.is_empty() {
+            self.source.push_str("[]");
+            return Ok(());
+        }
+        let links = core::mem::take(&mut self.links);
+        for (i, item) in array.iter().enumerate() {
+            if i > 0 {
+                self.source.push('\n');
+                self.write_indent();
+            }
+            self.source.push('-');
+            self.link = links;
+            match item {
+                // Compact collections start after the `-`, and continue in line with their start
+                Yaml::Array(_) | Yaml::Hash(_) if self.compact => {
+                    self.source.push(' ');
+                    self.column += 2;
+                    self.emit_node(item, false)?;
+                    self.column -= 2;
+                }
+                _ => self.emit_value(item)?,
+            }
+            self.link = false;
+        }
+        Ok(())
+    }
+
+    fn emit_hash(&mut self, hash: &yaml::Hash) -> Result<(), ExportError> {
+        if hash.is_empty() {
+            self.source.push_str("{}");
+            return Ok(());
+        }
+        // The dialogue of shorthand links, e.g. `- end: "Bye"`, is keyed by their target
+        let shorthand_link = core::mem::take(&mut self.link) && hash.len() == 1;
+        for (i, (key, value)) in hash.iter().enumerate() {
+            if i > 0 {
+                self.source.push('\n');
+                self.write_indent();
+            }
+            self.emit_node(key, true)?;
+            self.source.push(':');
+            let is_dialogue = shorthand_link || matches!(key.as_str(), Some("dialogue" | "text"));
+            match value {
+                Yaml::String(dialogue) if is_dialogue && self.is_block(dialogue) => {
+                    self.emit_block(dialogue)
+                }
+                Yaml::Array(_) => {
+                    self.links = key.as_str() == Some("links");
+                    self.emit_value(value)?;
+                }
+                _ => self.emit_value(value)?,
+            }
+        }
+        Ok(())
+    }
+
+    // Writes a value after a `:` or `-`, on the same line if it is a scalar or empty, otherwise indented on a new line
+    fn emit_value(&mut self, value: &Yaml) -> Result<(), ExportError> {
+        match value {
+            Yaml::Array(array) if !array.is_empty() => {
+                self.source.push('\n');
+                self.column += self.indent;
+                self.write_indent();
+                let result = self.emit_array(array);
+                self.column -= self.indent;
+                result
+            }
+            Yaml::Hash(hash) if !hash.is_empty() => {
+                self.source.push('\n');
+                self.column += self.indent;
+                self.write_indent();
+                let result = self.emit_hash(hash);
+                self.column -= self.indent;
+                result
+            }
+            _ => {
+                self.source.push(' ');
+                self.emit_node(value, false)
+            }
+        }
+    }
+}
+
+// Whether a string must be quoted to be read back as the same string
+fn needs_quotes(string: &str) -> bool {
+    string.is_empty()
+        || string.starts_with([' ', '\t'])
+        || string.ends_with([' ', '\t'])
+        || string.starts_with(['&', '*', '?', '|', '-', '<', '>', '=', '!', '%', '@', '.'])
+        || string.contains([
+            ':', '{', '}', '[', ']', ',', '#', '`', '"', '\'', '\\', '\n', '\r',
+        ])
+        || string.chars().any(|c| c == '\t' || is_unprintable(c))
+        // Booleans, nulls, and numbers in any form YAML reads them, e.g. `0o17` or `+.inf`
+        || ["yes", "Yes", "YES", "no", "No", "NO", "on", "On", "ON", "off", "Off", "OFF"]
+            .contains(&string)
+        || !matches!(Yaml::from_str(string), Yaml::String(_))
+        || string.parse::<f64>().is_ok()
+}
+
+// Whether a character must be escaped in a double quoted string
+fn is_unprintable(c: char) -> bool {
+    c.is_control()
+        || matches!(
+            c,
+            '\u{2028}' | '\u{2029}' | '\u{feff}' | '\u{fffe}' | '\u{ffff}'
+        )
+}
+
+fn check_tree(tree: &Tree, draft: bool) -> Result<(), TreeError> {
     // Check root key exists
-    let root_key = tree.root_key().ok_or_else(TreeError::RootNotSet)?;
+    tree.root_key().ok_or_else(TreeError::RootNotSet)?;
 
     // Check length of nodes
-    if tree.nodes.is_empty() {
+    if tree.is_empty() {
         return Err(TreeError::Validation("Node map has a length of 0".into()));
     }
+    if !draft {
+        draft::validate(tree)?;
+    }
+    Ok(())
+}
+
+fn tree_to_yaml(tree: &Tree) -> Result<Yaml, TreeError> {
+    // The root key is checked with the tree
+    let root_key = tree.root_key().ok_or_else(TreeError::RootNotSet)?;
 
     // Build node map
     let mut node_map = yaml::Hash::new();
@@ -97,33 +593,305 @@ fn tree_to_yaml(tree: &Tree) -> Result<Yaml, TreeError> {
         node_map.insert(yaml_key, yaml_node);
     }
 
-    // Build the document
+    // Build the document, leading with metadata so it can be read without the nodes
     let mut yaml = yaml::Hash::new();
+    if !tree.meta().is_empty() {
+        let yaml_meta = meta_to_yaml(tree.meta())?;
+        yaml.insert(Yaml::String("meta".to_string()), yaml_meta);
+    }
     yaml.insert(
         Yaml::String("root".to_string()),
         Yaml::String(root_key.to_owned()),
     );
+    if !tree.entries().is_empty() {
+        let yaml_entries = tree
+            .entries()
+            .iter()
+            .map(|(name, node_key)| (Yaml::String(name.clone()), Yaml::String(node_key.clone())))
+            .collect();
+        yaml.insert(
+            Yaml::String("entries".to_string()),
+            Yaml::Hash(yaml_entries),
+        );
+    }
+    if !tree.variables().is_empty() {
+        let yaml_variables = variables_to_yaml(tree.variables());
+        yaml.insert(Yaml::String("variables".to_string()), yaml_variables);
+    }
+    if !tree.availability().is_empty() {
+        let yaml_availability = availability_to_yaml(tree.availability());
+        yaml.insert(Yaml::String("availability".to_string()), yaml_availability);
+    }
     yaml.insert(Yaml::String("nodes".to_string()), Yaml::Hash(node_map));
 
     Ok(Yaml::Hash(yaml))
 }
 
+fn variables_to_yaml(variables: &BTreeMap<String, Type>) -> Yaml {
+    let mut map = yaml::Hash::new();
+    for (name, variable_type) in variables {
+        let yaml_type = match variable_type {
+            Type::Bool => Yaml::String("bool".to_string()),
+            Type::Int => Yaml::String("int".to_string()),
+            Type::String => Yaml::String("string".to_string()),
+            Type::Enum(variants) => {
+                Yaml::Array(variants.iter().cloned().map(Yaml::String).collect())
+            }
+        };
+        map.insert(Yaml::String(name.to_owned()), yaml_type);
+    }
+    Yaml::Hash(map)
+}
+
+fn availability_to_yaml(availability: &Availability) -> Yaml {
+    let mut map = yaml::Hash::new();
+    if let Some(cooldown) = availability.cooldown {
+        // YAML integers are signed, so longer cooldowns saturate
+        let cooldown = i64::try_from(cooldown).unwrap_or(i64::MAX);
+        map.insert(
+            Yaml::String("cooldown".to_string()),
+            Yaml::Integer(cooldown),
+        );
+    }
+    let days = [
+        ("min_day", availability.min_day),
+        ("max_day", availability.max_day),
+    ];
+    for (key, day) in days {
+        if let Some(day) = day {
+            map.insert(Yaml::String(key.to_string()), Yaml::Integer(day));
+        }
+    }
+    if !availability.required_tags.is_empty() {
+        let tags = availability
+            .required_tags
+            .iter()
+            .cloned()
+            .map(Yaml::String)
+            .collect();
+        map.insert(Yaml::String("requires".to_string()), Yaml::Array(tags));
+    }
+    Yaml::Hash(map)
+}
+
+fn pacing_to_yaml(pacing: &Pacing) -> Yaml {
+    let mut map = yaml::Hash::new();
+    let hints = [
+        ("chars_per_second", pacing.chars_per_second),
+        ("pause_before", pacing.pause_before),
+        ("pause_after", pacing.pause_after),
+    ];
+    for (key, hint) in hints {
+        if let Some(hint) = hint {
+            map.insert(
+                Yaml::String(key.to_string()),
+                Yaml::Integer(i64::from(hint)),
+            );
+        }
+    }
+    Yaml::Hash(map)
+}
+
+fn stage_direction_to_yaml(direction: &StageDirection) -> Yaml {
+    // Commands without arguments are bare, and a single argument is not wrapped in an array
+    let command = Yaml::String(direction.command.to_owned());
+    let args = match direction.args.as_slice() {
+        [] => return command,
+        [arg] => value_to_yaml(arg),
+        args => Yaml::Array(args.iter().map(value_to_yaml).collect()),
+    };
+    let mut map = yaml::Hash::new();
+    map.insert(command, args);
+    Yaml::Hash(map)
+}
+
+fn value_to_yaml(value: &Value) -> Yaml {
+    match value {
+        Value::Bool(value) => Yaml::Boolean(*value),
+        Value::Int(value) => Yaml::Integer(*value),
+        Value::String(value) => Yaml::String(value.to_owned()),
+    }
+}
+
+fn meta_to_yaml(meta: &Meta) -> Result<Yaml, TreeError> {
+    let mut map = yaml::Hash::new();
+
+    // Set known fields
+    let fields = [
+        ("title", &meta.title),
+        ("author", &meta.author),
+        ("description", &meta.description),
+    ];
+    for (key, value) in fields {
+        if let Some(value) = value {
+            map.insert(
+                Yaml::String(key.to_string()),
+                Yaml::String(value.to_owned()),
+            );
+        }
+    }
+    if !meta.tags.is_empty() {
+        let tags = meta.tags.iter().cloned().map(Yaml::String).collect();
+        map.insert(Yaml::String("tags".to_string()), Yaml::Array(tags));
+    }
+
+    // Set custom fields, which must not shadow known fields
+    for (key, value) in &meta.custom {
+        if matches!(key.as_str(), "title" | "author" | "description" | "tags") {
+            return Err(TreeError::Validation(format!(
+                "Custom meta field `{}` is reserved",
+                key
+            )));
+        }
+        map.insert(Yaml::String(key.to_owned()), Yaml::String(value.to_owned()));
+    }
+
+    Ok(Yaml::Hash(map))
+}
+
 fn node_to_yaml(node: &Node) -> Result<Yaml, TreeError> {
     // Make node buffer
     let mut map = yaml::Hash::new();
 
-    // Set dialogue
-    map.insert(
-        Yaml::String("dialogue".to_string()),
-        Yaml::String(node.dialogue.to_owned()),
-    );
+    // Set the stable id first, so it reads as part of the node's identity
+    if let Some(id) = node.id {
+        let id = i64::try_from(id).map_err(|_| {
+            TreeError::Validation(format!(
+                "Node `{}` has an id which is too large to export: {}",
+                node.key, id
+            ))
+        })?;
+        map.insert(Yaml::String("id".to_string()), Yaml::Integer(id));
+    }
+
+    // Set dialogue, as a sequence if the node has lines
+    let yaml_dialogue = match node.lines.is_empty() {
+        true => Yaml::String(node.dialogue.to_owned()),
+        false => Yaml::Array(
+            node.lines
+                .iter()
+                .map(|line| {
+                    let mut map = yaml::Hash::new();
+                    map.insert(
+                        Yaml::String("speaker".to_string()),
+                        Yaml::String(line.speaker.to_owned()),
+                    );
+                    map.insert(
+                        Yaml::String("text".to_string()),
+                        Yaml::String(line.text.to_owned()),
+                    );
+                    if let Some(emotion) = &line.emotion {
+                        map.insert(
+                            Yaml::String("emotion".to_string()),
+                            Yaml::String(emotion.to_owned()),
+                        );
+                    }
+                    Yaml::Hash(map)
+                })
+                .collect(),
+        ),
+    };
+    let is_switch = matches!(node.kind, NodeKind::Switch { .. });
+    if !(is_switch && node.dialogue.is_empty() && node.lines.is_empty()) {
+        map.insert(Yaml::String("dialogue".to_string()), yaml_dialogue);
+    }
+
+    // Set tags
+    if !node.tags.is_empty() {
+        let tags = node.tags.iter().cloned().map(Yaml::String).collect();
+        map.insert(Yaml::String("tags".to_string()), Yaml::Array(tags));
+    }
+
+    // Set kind, where switches list their links as cases
+    if let NodeKind::Switch { variable } = &node.kind {
+        switch_to_yaml(node, variable, &mut map)?;
+    }
+    if let NodeKind::Random { avoid_recent } = node.kind {
+        let yaml_random = match avoid_recent {
+            0 => Yaml::Boolean(true),
+            avoid_recent => {
+                let mut options = yaml::Hash::new();
+                options.insert(
+                    Yaml::String("avoid_recent".to_string()),
+                    Yaml::Integer(i64::try_from(avoid_recent).unwrap_or(i64::MAX)),
+                );
+                Yaml::Hash(options)
+            }
+        };
+        map.insert(Yaml::String("random".to_string()), yaml_random);
+    }
+    if let NodeKind::Menu { source } = &node.kind {
+        map.insert(
+            Yaml::String("menu".to_string()),
+            Yaml::String(source.to_owned()),
+        );
+    }
+
+    // Set availability
+    if !node.availability.is_empty() {
+        let yaml_availability = availability_to_yaml(&node.availability);
+        map.insert(Yaml::String("availability".to_string()), yaml_availability);
+    }
+
+    // Set pacing hints
+    if !node.pacing.is_empty() {
+        map.insert(
+            Yaml::String("pacing".to_string()),
+            pacing_to_yaml(&node.pacing),
+        );
+    }
+
+    // Set stage directions
+    if !node.stage.is_empty() {
+        let directions = node.stage.iter().map(stage_direction_to_yaml).collect();
+        map.insert(Yaml::String("stage".to_string()), Yaml::Array(directions));
+    }
+
+    // Mark intentional endings
+    if node.end {
+        map.insert(Yaml::String("end".to_string()), Yaml::Boolean(true));
+    }
+
+    // Mark unfinished writing
+    if node.todo {
+        map.insert(Yaml::String("todo".to_string()), Yaml::Boolean(true));
+    }
 
     // Set links
-    if !node.links.is_empty() {
+    if !node.links.is_empty() && !is_switch {
+        // Grouped links are gathered under their heading, where the group first appears
         let mut links = yaml::Array::new();
+        let mut groups: Vec<(&str, usize)> = Vec::new();
         for link in &node.links {
             let yaml_link = link_to_yaml(link)?;
-            links.push(yaml_link);
+            let heading = match &link.group {
+                Some(heading) => heading,
+                None => {
+                    links.push(yaml_link);
+                    continue;
+                }
+            };
+            let i = match groups.iter().find(|(group, _)| group == heading) {
+                Some((_, i)) => *i,
+                None => {
+                    let mut group = yaml::Hash::new();
+                    group.insert(
+                        Yaml::String("group".to_string()),
+                        Yaml::String(heading.to_owned()),
+                    );
+                    group.insert(Yaml::String("links".to_string()), Yaml::Array(vec![]));
+                    groups.push((heading, links.len()));
+                    links.push(Yaml::Hash(group));
+                    links.len() - 1
+                }
+            };
+            if let Yaml::Hash(group) = &mut links[i] {
+                if let Some(Yaml::Array(group_links)) =
+                    group.get_mut(&Yaml::String("links".to_string()))
+                {
+                    group_links.push(yaml_link);
+                }
+            }
         }
         map.insert(Yaml::String("links".to_string()), Yaml::Array(links));
     }
@@ -133,12 +901,137 @@ fn node_to_yaml(node: &Node) -> Result<Yaml, TreeError> {
     Ok(yaml)
 }
 
+fn switch_to_yaml(node: &Node, variable: &str, map: &mut yaml::Hash) -> Result<(), TreeError> {
+    map.insert(
+        Yaml::String("switch".to_string()),
+        Yaml::String(variable.to_owned()),
+    );
+
+    // Cases compare the variable to a value, and a default may only come last
+    let mut cases = yaml::Hash::new();
+    for (i, link) in node.links.iter().enumerate() {
+        let is_last = i + 1 == node.links.len();
+        let clauses = link.condition.as_ref().map(|c| c.clauses.as_slice());
+        match clauses {
+            Some(
+                [Clause::Compare {
+                    name,
+                    op: Op::Eq,
+                    value,
+                }],
+            ) if name == variable && is_plain_case(link) => {
+                cases.insert(value_to_yaml(value), Yaml::String(link.to_key.to_owned()));
+            }
+            None if is_last && is_plain_case(link) => {
+                map.insert(
+                    Yaml::String("default".to_string()),
+                    Yaml::String(link.to_key.to_owned()),
+                );
+            }
+            _ => {
+                return Err(TreeError::Validation(format!(
+                    "Link to `{}` in switch node `{}` is not a case of `{}`",
+                    link.to_key, node.key, variable
+                )))
+            }
+        }
+    }
+    if !cases.is_empty() {
+        map.insert(Yaml::String("cases".to_string()), Yaml::Hash(cases));
+    }
+    Ok(())
+}
+
+// Returns whether a link carries nothing besides its target and condition, where empty dialogue is as silent as none
+fn is_plain_case(link: &Link) -> bool {
+    let mut plain = Link::silent(link.to_key.as_str());
+    plain.condition = link.condition.clone();
+    plain.dialogue = link.dialogue.clone().filter(String::is_empty);
+    *link == plain
+}
+
+// Silent links are written with null dialogue, e.g. `- end: ~`
+fn link_dialogue_to_yaml(link: &Link) -> Yaml {
+    match &link.dialogue {
+        Some(dialogue) => Yaml::String(dialogue.to_owned()),
+        None => Yaml::Null,
+    }
+}
+
 fn link_to_yaml(link: &Link) -> Result<Yaml, TreeError> {
     let mut map = yaml::Hash::new();
+
+    // Use the shorthand form unless the link carries attributes, or leads to a node keyed `to`, which would read as a structured link
+    if link.to_key != "to"
+        && link.priority.is_none()
+        && link.weight.is_none()
+        && link.condition.is_none()
+        && link.set.is_empty()
+        && link.affinity.is_empty()
+        && link.when_unmet == WhenUnmet::Hidden
+        && link.tags.is_empty()
+    {
+        map.insert(
+            Yaml::String(link.to_key.to_owned()),
+            link_dialogue_to_yaml(link),
+        );
+        return Ok(Yaml::Hash(map));
+    }
+
+    // Structured form
     map.insert(
+        Yaml::String("to".to_string()),
         Yaml::String(link.to_key.to_owned()),
-        Yaml::String(link.dialogue.to_owned()),
     );
+    map.insert(
+        Yaml::String("dialogue".to_string()),
+        link_dialogue_to_yaml(link),
+    );
+    if let Some(priority) = link.priority {
+        map.insert(
+            Yaml::String("priority".to_string()),
+            Yaml::Integer(priority.into()),
+        );
+    }
+    if let Some(weight) = link.weight {
+        map.insert(
+            Yaml::String("weight".to_string()),
+            Yaml::Integer(weight.into()),
+        );
+    }
+    if let Some(condition) = &link.condition {
+        map.insert(
+            Yaml::String("if".to_string()),
+            Yaml::String(condition.to_string()),
+        );
+    }
+    if let WhenUnmet::Disabled(reason) = &link.when_unmet {
+        map.insert(
+            Yaml::String("disabled".to_string()),
+            Yaml::String(reason.to_owned()),
+        );
+    }
+    if !link.set.is_empty() {
+        let mut assignments = yaml::Hash::new();
+        for (name, value) in &link.set {
+            assignments.insert(Yaml::String(name.to_owned()), value_to_yaml(value));
+        }
+        map.insert(Yaml::String("set".to_string()), Yaml::Hash(assignments));
+    }
+    if !link.affinity.is_empty() {
+        let mut adjustments = yaml::Hash::new();
+        for (character, amount) in &link.affinity {
+            adjustments.insert(Yaml::String(character.to_owned()), Yaml::Integer(*amount));
+        }
+        map.insert(
+            Yaml::String("affinity".to_string()),
+            Yaml::Hash(adjustments),
+        );
+    }
+    if !link.tags.is_empty() {
+        let tags = link.tags.iter().cloned().map(Yaml::String).collect();
+        map.insert(Yaml::String("tags".to_string()), Yaml::Array(tags));
+    }
     Ok(Yaml::Hash(map))
 }
 
@@ -148,7 +1041,7 @@ fn test_export() {
     // Test a minimum valid export
     let mut tree = Tree::new();
     let node = Node::new("start", "It's a bad day.");
-    tree.nodes.insert("start".to_owned(), node);
+    tree.insert_node(node);
     tree.set_root_key("start").unwrap();
 
     assert!(export(&tree, "examples/dialogue_files/export.convo.yml").is_ok());
@@ -161,7 +1054,7 @@ fn test_export_path_exists() {
     // Make a valid tree
     let mut tree = Tree::new();
     let node = Node::new("start", "It's a bad day.");
-    tree.nodes.insert("start".to_owned(), node);
+    tree.insert_node(node);
     tree.set_root_key("start").unwrap();
 
     // Should fail because file path is invalid
@@ -173,7 +1066,7 @@ fn test_tree_to_source() {
     // Test a minimum valid export
     let mut tree = Tree::new();
     let node = Node::new("start", "It's a bad day.");
-    tree.nodes.insert("start".to_owned(), node);
+    tree.insert_node(node);
     tree.set_root_key("start").unwrap();
 
     let source = r#"---
@@ -186,6 +1079,38 @@ nodes:
     assert_eq!(source, tree_to_source(&tree).unwrap());
 }
 
+#[test]
+fn test_tree_to_source_structured_links() {
+    // Links with attributes are exported in the structured form
+    let mut tree = Tree::new();
+    let mut node = Node::new("start", "It's a bad day.");
+    node.links.push(Link::new("start", "Is it?"));
+    let mut link = Link::new("start", "Why?");
+    link.priority = Some(2);
+    node.links.push(link);
+    tree.insert_node(node);
+    tree.set_root_key("start").unwrap();
+
+    let source = r#"---
+root: start
+nodes:
+  start:
+    dialogue: "It's a bad day."
+    links:
+      - start: Is it?
+      - to: start
+        dialogue: Why?
+        priority: 2"#;
+
+    // Should be equal
+    assert_eq!(source, tree_to_source(&tree).unwrap());
+
+    // Should round trip, assigning ids
+    let tree2 = crate::importer::source_to_tree(source).unwrap();
+    tree.assign_ids().unwrap();
+    assert_eq!(tree, tree2);
+}
+
 #[test]
 fn test_tree_to_source_root_exists() {
     use crate::error::ExportError::Validation;
@@ -193,7 +1118,7 @@ fn test_tree_to_source_root_exists() {
     // Should fail because root node is never set
     let mut tree = Tree::new();
     let node = Node::new("start", "It's a bad day.");
-    tree.nodes.insert("start".to_owned(), node);
+    tree.insert_node(node);
 
     assert!(matches!(tree_to_source(&tree).unwrap_err(), Validation(_)));
 }
@@ -204,7 +1129,7 @@ fn test_tree_to_source_nodes_exist() {
 
     // Should fail because nodes do not exist
     let mut tree = Tree::new();
-    unsafe { tree.set_root_key_unchecked("start") }
+    tree.raw().set_root_key("start");
 
     assert!(matches!(tree_to_source(&tree).unwrap_err(), Validation(_)));
 }
@@ -218,8 +1143,8 @@ fn test_tree_to_source_unreachable_nodes() {
     let mut tree = Tree::new();
     let node1 = Node::new("1", "It's a bad day.");
     let node2 = Node::new("2", "It's a good day.");
-    tree.nodes.insert("1".to_owned(), node1);
-    tree.nodes.insert("2".to_owned(), node2);
+    tree.insert_node(node1);
+    tree.insert_node(node2);
     tree.set_root_key("1").unwrap();
 
     assert!(matches!(tree_to_source(&tree).unwrap_err(), Validation(_)));
@@ -229,8 +1154,8 @@ fn test_tree_to_source_unreachable_nodes() {
     let mut parent = Node::new("parent", "I am the parent.");
     let child = Node::new("child", "I am the child.");
     Link::link(&mut parent, &child, "I make sure no orphan nodes exist.");
-    tree.nodes.insert("parent".to_owned(), parent);
-    tree.nodes.insert("child".to_owned(), child);
+    tree.insert_node(parent);
+    tree.insert_node(child);
     tree.set_root_key("child").unwrap();
 
     assert!(matches!(tree_to_source(&tree).unwrap_err(), Validation(_)));
@@ -247,9 +1172,231 @@ fn test_tree_to_source_invalid_links() {
     let invalid_link = Link::new("invalid", "I am an invalid link");
     node.links.push(invalid_link);
     // Finish tree
-    tree.nodes.insert("root".to_owned(), node);
+    tree.insert_node(node);
     tree.set_root_key("root").unwrap();
 
     // Should fail because invalid link exists
     assert!(matches!(tree_to_source(&tree).unwrap_err(), Validation(_)));
 }
+
+#[test]
+fn test_tree_to_source_meta() {
+    use crate::importer;
+
+    let mut tree = Tree::new();
+    tree.insert_node(Node::new("start", "It's a bad day."));
+    tree.set_root_key("start").unwrap();
+
+    // Empty metadata is not exported
+    assert!(!tree_to_source(&tree).unwrap().contains("meta"));
+
+    // Metadata round-trips
+    let meta = tree.meta_mut();
+    meta.title = Some("Bad Day".into());
+    meta.tags = vec!["short".into(), "sad".into()];
+    meta.custom.insert("chapter".into(), "1".into());
+    tree.assign_ids().unwrap();
+    let source = tree_to_source(&tree).unwrap();
+    assert!(source.starts_with("---\nmeta:"));
+    assert_eq!(tree, importer::source_to_tree(&source).unwrap());
+
+    // Custom fields cannot shadow known fields
+    tree.meta_mut().custom.insert("tags".into(), "none".into());
+    assert!(matches!(
+        tree_to_source(&tree).unwrap_err(),
+        ExportError::Validation(_)
+    ));
+}
+
+#[test]
+fn test_tree_to_source_quoting() {
+    // Strings which are easily misread as other values, or which contain YAML syntax
+    let strings = [
+        "",
+        " ",
+        "true",
+        "Yes",
+        "off",
+        "~",
+        "null",
+        "0o7",
+        "0o17",
+        "0x1f",
+        "+.inf",
+        "-.inf",
+        ".nan",
+        "1e3",
+        "12",
+        "-3.5",
+        "- item",
+        "key: value",
+        "# comment",
+        "a #b",
+        "'single'",
+        "\"double\"",
+        "back\\slash",
+        "tab\there",
+        "line\nbreak",
+        "\r",
+        "\u{0}",
+        "\u{7}",
+        "\u{1b}[0m",
+        "\u{85}",
+        "\u{2028}",
+        "\u{feff}",
+        "&anchor",
+        "*alias",
+        "!tag",
+        "%directive",
+        "@at",
+        "`tick`",
+        "{}",
+        "[]",
+        "...",
+        "---",
+        "café",
+        "日本語",
+    ];
+    let mut tree = Tree::new();
+    let mut node = Node::new("start", "Hello!");
+    node.end = true;
+    node.id = Some(1);
+    tree.insert_node(node);
+    tree.set_root_key("start").unwrap();
+
+    for (quoting, block_dialogue) in [Quoting::Auto, Quoting::Always, Quoting::Never]
+        .iter()
+        .flat_map(|quoting| [(*quoting, None), (*quoting, Some(0))])
+    {
+        let options = ExportOptions {
+            quoting,
+            block_dialogue,
+            ..Default::default()
+        };
+        for string in strings.iter() {
+            tree.node_mut("start").unwrap().dialogue = (*string).to_owned();
+            match tree_to_source_with(&tree, &options) {
+                Ok(source) => {
+                    assert_eq!(
+                        tree,
+                        crate::importer::source_to_tree(&source).unwrap(),
+                        "{:?}",
+                        string
+                    );
+                }
+                Err(ExportError::Unencodable(unencodable)) => {
+                    assert_eq!(Quoting::Never, quoting);
+                    assert_eq!(*string, unencodable);
+                }
+                Err(e) => panic!("{:?}: {:?}", string, e),
+            }
+        }
+    }
+
+    // Plain strings are only quoted when asked
+    tree.node_mut("start").unwrap().dialogue = "Hello!".to_owned();
+    let never = ExportOptions {
+        quoting: Quoting::Never,
+        ..Default::default()
+    };
+    let always = ExportOptions {
+        quoting: Quoting::Always,
+        ..Default::default()
+    };
+    assert_eq!(
+        tree_to_source(&tree).unwrap(),
+        tree_to_source_with(&tree, &never).unwrap()
+    );
+    assert!(tree_to_source_with(&tree, &always)
+        .unwrap()
+        .contains("root: \"start\"\nnodes:\n  start:\n    id: 1\n    dialogue: \"Hello!\""));
+}
+
+#[test]
+fn test_tree_to_source_block_dialogue() {
+    use crate::{importer, Line};
+
+    let mut tree = Tree::new();
+    let mut node = Node::new("start", "Short.");
+    node.links.push(Link::new("end", "Goodbye,\n\nfriend."));
+    tree.insert_node(node);
+    let mut node = Node::new("end", "");
+    node.set_lines(vec![Line::new("guard", "Halt!\nWho goes there?")]);
+    node.end = true;
+    tree.insert_node(node);
+    tree.set_root_key("start").unwrap();
+
+    // Only dialogue beyond the length is written as blocks, which read back exactly
+    let options = ExportOptions {
+        block_dialogue: Some(8),
+        ..Default::default()
+    };
+    tree.assign_ids().unwrap();
+    let source = tree_to_source_with(&tree, &options).unwrap();
+    assert!(source.contains("dialogue: Short."));
+    assert!(source.contains("- end: |-\n          Goodbye,\n\n          friend."));
+    assert!(source.contains("text: |-\n          Halt!\n          Who goes there?"));
+    assert_eq!(tree, importer::source_to_tree(&source).unwrap());
+    assert_eq!(tree_to_source(&tree).unwrap(), {
+        let options = ExportOptions::default();
+        tree_to_source_with(&tree, &options).unwrap()
+    });
+
+    // Dialogue which a block cannot hold is written as a string
+    for dialogue in [
+        "  Indented,\nlines",
+        "Trailing\n\n",
+        "\nLeading",
+        "Blank\n   \nline",
+        "Bell\u{7}\nrings",
+        "Tabbed\tand: #quoted\n'lines'\t",
+        "- Not\n- a list",
+    ] {
+        tree.node_mut("start").unwrap().dialogue = dialogue.to_owned();
+        let source = tree_to_source_with(&tree, &options).unwrap();
+        let imported = importer::source_to_tree(&source).unwrap();
+        assert_eq!(
+            dialogue.trim_end_matches('\n'),
+            imported.node("start").unwrap().dialogue
+        );
+    }
+}
+
+#[test]
+fn test_tree_to_source_layout() {
+    use crate::importer;
+
+    let tree = importer::import("examples/dialogue_files/ex_1.convo.yml").unwrap();
+
+    // Every layout reads back as the same tree
+    for indent in [0, 1, 2, 4] {
+        for compact in [true, false] {
+            let options = ExportOptions {
+                indent,
+                compact,
+                block_dialogue: Some(24),
+                ..Default::default()
+            };
+            let source = tree_to_source_with(&tree, &options).unwrap();
+            assert_eq!(
+                tree,
+                importer::source_to_tree(&source).unwrap(),
+                "{}",
+                source
+            );
+        }
+    }
+
+    // Sequences of hashes are only compact when asked
+    let options = ExportOptions {
+        indent: 3,
+        compact: false,
+        ..Default::default()
+    };
+    let source = tree_to_source_with(&tree, &options).unwrap();
+    assert!(source.contains("\n      links:\n         -\n            "));
+    assert!(source.contains("\n   tags:\n      - example\n"));
+    assert!(source
+        .lines()
+        .all(|line| !line.trim_start().starts_with("- ") || !line.contains(':')));
+}
@@ -0,0 +1,398 @@
+//! Content filters which screen or mask dialogue before it is displayed, e.g. for user-generated content.
+//!
+//! A [`ContentFilter`] can be applied on the render path of a [`Walker`][`crate::Walker`] with [`Walker::set_filter`][`crate::Walker::set_filter`], or over a whole [`Tree`] at once with [`filter_tree`].
+//!
+//! Whole nodes and links can also be excluded by their tags, such as content ratings, e.g. for regional builds or streamer-safe modes. [`exclude_tags`] returns a copy of a tree without them, and [`HostState#excluded_tags`][`crate::HostState#structfield.excluded_tags`] hides them from walkers at runtime.
+
+use alloc::{
+    borrow::{Cow, ToOwned},
+    collections::BTreeSet,
+    format,
+    string::String,
+    sync::Arc,
+    vec::Vec,
+};
+use core::fmt;
+
+use crate::{error::TreeError, tree::Tree};
+
+/// A [`ContentFilter`] screens dialogue, returning a replacement for text which should not be displayed as-is.
+///
+/// Any `Fn(&str) -> Option<String>` closure which is [`Send`] and [`Sync`] is a content filter.
+///
+/// # Examples
+///
+/// ```
+/// use convo::filter::ContentFilter;
+/// let filter = |text: &str| text.contains("darn").then(|| text.replace("darn", "****"));
+/// assert_eq!(Some("Oh ****!".to_owned()), filter.filter("Oh darn!"));
+/// assert_eq!(None, filter.filter("Oh dear!"));
+/// ```
+pub trait ContentFilter: Send + Sync {
+    /// Returns the text to display in place of `text`, or [`None`] if it may be displayed unchanged.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The dialogue to screen.
+    fn filter(&self, text: &str) -> Option<String>;
+}
+
+impl<F> ContentFilter for F
+where
+    F: Fn(&str) -> Option<String> + Send + Sync,
+{
+    fn filter(&self, text: &str) -> Option<String> {
+        self(text)
+    }
+}
+
+/// A [`MaskWords`] is a [`ContentFilter`] which masks each character of listed words, ignoring ASCII case. Only whole words are masked.
+///
+/// # Examples
+///
+/// ```
+/// use convo::filter::{ContentFilter, MaskWords};
+/// let filter = MaskWords::new(&["darn"], '*');
+/// assert_eq!(Some("**** it, Darnell!".to_owned()), filter.filter("DARN it, Darnell!"));
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct MaskWords {
+    words: Vec<String>,
+    mask: char,
+}
+
+impl MaskWords {
+    /// Returns a [`MaskWords`] filter for a list of words.
+    ///
+    /// # Arguments
+    ///
+    /// * `words` - The words to mask.
+    /// * `mask` - The character which replaces each character of a masked word.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::filter::MaskWords;
+    /// let filter = MaskWords::new(&["darn", "heck"], '#');
+    /// ```
+    pub fn new<S>(words: &[S], mask: char) -> Self
+    where
+        S: AsRef<str>,
+    {
+        MaskWords {
+            words: words.iter().map(|word| word.as_ref().into()).collect(),
+            mask,
+        }
+    }
+}
+
+impl ContentFilter for MaskWords {
+    fn filter(&self, text: &str) -> Option<String> {
+        let mut filtered = String::with_capacity(text.len());
+        let mut altered = false;
+        let mut rest = text;
+        while !rest.is_empty() {
+            // Alternate between runs of word and non-word characters
+            let is_word = |c: char| c.is_alphanumeric() || c == '\'';
+            let first_is_word = rest.chars().next().is_some_and(is_word);
+            let end = rest
+                .find(|c: char| is_word(c) != first_is_word)
+                .unwrap_or(rest.len());
+            let (run, next) = rest.split_at(end);
+            if first_is_word && self.words.iter().any(|word| word.eq_ignore_ascii_case(run)) {
+                filtered.extend(run.chars().map(|_| self.mask));
+                altered = true;
+            } else {
+                filtered.push_str(run);
+            }
+            rest = next;
+        }
+        altered.then_some(filtered)
+    }
+}
+
+/// A cloneable handle to a [`ContentFilter`] held by a [`Walker`][`crate::Walker`].
+#[derive(Clone)]
+pub(crate) struct FilterHandle(Arc<dyn ContentFilter>);
+
+impl FilterHandle {
+    pub(crate) fn new<F>(filter: F) -> Self
+    where
+        F: ContentFilter + 'static,
+    {
+        FilterHandle(Arc::new(filter))
+    }
+
+    pub(crate) fn apply<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        match self.0.filter(text) {
+            Some(filtered) => Cow::Owned(filtered),
+            None => Cow::Borrowed(text),
+        }
+    }
+}
+
+impl fmt::Debug for FilterHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("FilterHandle(..)")
+    }
+}
+
+/// Apply a [`ContentFilter`] to the dialogue of every node and link of a [`Tree`], returning the keys of the nodes which were altered, in the order they are stored.
+///
+/// # Arguments
+///
+/// * `tree` - A [`Tree`] to filter in place.
+/// * `filter` - The [`ContentFilter`] to apply.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{filter::{self, MaskWords}, Link, Node, Tree};
+/// let mut tree = Tree::new();
+/// let mut start = Node::new("start", "Hello!");
+/// let end = Node::new("end", "Goodbye!");
+/// Link::link(&mut start, &end, "Darn, bye!");
+/// tree.insert_node(start);
+/// tree.insert_node(end);
+/// let altered = filter::filter_tree(&mut tree, &MaskWords::new(&["darn"], '*'));
+/// assert_eq!(vec!["start".to_owned()], altered);
+/// assert_eq!("****, bye!", tree.node("start").unwrap().links[0].text());
+/// ```
+pub fn filter_tree(tree: &mut Tree, filter: &dyn ContentFilter) -> Vec<String> {
+    let mut altered = Vec::new();
+    for node in tree.nodes_mut() {
+        let mut node_altered = false;
+
+        // The dialogue of a node with lines follows its lines
+        let has_lines = !node.lines.is_empty();
+        let dialogue = match has_lines {
+            true => None,
+            false => Some(&mut node.dialogue),
+        };
+        let texts = dialogue
+            .into_iter()
+            .chain(node.lines.iter_mut().map(|line| &mut line.text))
+            .chain(
+                node.links
+                    .iter_mut()
+                    .filter_map(|link| link.dialogue.as_mut()),
+            );
+        for text in texts {
+            if let Some(filtered) = filter.filter(text) {
+                *text = filtered;
+                node_altered = true;
+            }
+        }
+        if has_lines {
+            node.sync_dialogue();
+        }
+        if node_altered {
+            altered.push(node.key.clone());
+        }
+    }
+    altered
+}
+
+/// Try to return a copy of a [`Tree`] without the nodes and links carrying any of a set of tags, e.g. content ratings such as `mature`, for regional builds or streamer-safe modes.
+///
+/// Links to an excluded node are rerouted past it when it leads on through a single link which is kept, so flagged asides can be cut from linear content. Other links to excluded nodes are removed.
+///
+/// # Arguments
+///
+/// * `tree` - A [`Tree`] to filter.
+/// * `tags` - The tags of the content to exclude.
+///
+/// # Errors
+///
+/// * A [`TreeError`] will be returned if the tree has no root, the root node is excluded, or a node which is kept can no longer be reached from the root.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{filter, Link, Node, Tree};
+/// let mut tree = Tree::new();
+/// let mut start = Node::new("start", "Hello!");
+/// start.links.push(Link::new("joke", "Tell me a joke."));
+/// let mut joke = Node::new("joke", "A rude joke.");
+/// joke.tags.push("mature".to_owned());
+/// joke.links.push(Link::new("end", "Ha!"));
+/// tree.insert_node(start);
+/// tree.insert_node(joke);
+/// tree.insert_node(Node::new("end", "Goodbye!"));
+/// tree.set_root_key("start").unwrap();
+///
+/// let safe = filter::exclude_tags(&tree, &["mature"]).unwrap();
+/// assert!(!safe.contains_node("joke"));
+/// assert_eq!("end", safe.node("start").unwrap().links[0].to_key);
+/// ```
+pub fn exclude_tags(tree: &Tree, tags: &[&str]) -> Result<Tree, TreeError> {
+    let is_excluded =
+        |item_tags: &[String]| item_tags.iter().any(|tag| tags.contains(&tag.as_str()));
+    let root_key = tree
+        .root_key()
+        .ok_or_else(|| TreeError::Validation("Tree has no root to filter from".into()))?;
+    let excluded: BTreeSet<&str> = tree
+        .nodes()
+        .filter(|node| is_excluded(&node.tags))
+        .map(|node| node.key.as_str())
+        .collect();
+    if excluded.contains(root_key.as_str()) {
+        return Err(TreeError::Validation(format!(
+            "Root node `{}` is excluded by its tags",
+            root_key
+        )));
+    }
+
+    // Follows excluded nodes which lead on through a single kept link, giving up on cycles
+    let reroute = |key: &str| -> Option<String> {
+        let mut key = key.to_owned();
+        for _ in 0..=excluded.len() {
+            if !excluded.contains(key.as_str()) {
+                return Some(key);
+            }
+            let mut links = tree
+                .node(&key)?
+                .links
+                .iter()
+                .filter(|link| !is_excluded(&link.tags));
+            key = match (links.next(), links.next()) {
+                (Some(link), None) => link.to_key.clone(),
+                _ => return None,
+            };
+        }
+        None
+    };
+
+    let mut filtered = tree.clone();
+    for key in &excluded {
+        filtered.remove_node(key);
+    }
+    for node in filtered.nodes_mut() {
+        node.links = core::mem::take(&mut node.links)
+            .into_iter()
+            .filter(|link| !is_excluded(&link.tags))
+            .filter_map(|mut link| {
+                link.to_key = reroute(&link.to_key)?;
+                Some(link)
+            })
+            .collect();
+    }
+
+    // Content which is kept must stay connected
+    let before = tree.build_index();
+    let after = filtered.build_index();
+    let disconnected: Vec<String> = filtered
+        .nodes()
+        .filter(|node| before.is_reachable(&node.key) && !after.is_reachable(&node.key))
+        .map(|node| format!("`{}`", node.key))
+        .collect();
+    if !disconnected.is_empty() {
+        return Err(TreeError::Validation(format!(
+            "Excluding tags disconnects {} from the root",
+            disconnected.join(", ")
+        )));
+    }
+
+    Ok(filtered)
+}
+
+#[cfg(test)]
+#[test]
+fn test_mask_words() {
+    let filter = MaskWords::new(&["darn", "o'clock"], '*');
+
+    // Whole words are masked regardless of case
+    assert_eq!(
+        Some("****! It's ******* and ****.".into()),
+        filter.filter("Darn! It's o'clock and DARN.")
+    );
+    assert_eq!(Some("ü ****".into()), filter.filter("ü darn"));
+
+    // Partial words and clean text are left alone
+    assert_eq!(None, filter.filter("Darnell darned it"));
+    assert_eq!(None, filter.filter(""));
+}
+
+#[test]
+fn test_filter_tree() {
+    use crate::{
+        link::Link,
+        node::{Line, Node},
+    };
+
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "Darn it.");
+    let mut middle = Node::new("middle", "Fine.");
+    let end = Node::new("end", "Bye.");
+    Link::link(&mut start, &middle, "Okay.");
+    Link::link(&mut middle, &end, "Darn.");
+    for node in [start, middle, end] {
+        tree.insert_node(node);
+    }
+
+    let altered = filter_tree(&mut tree, &MaskWords::new(&["darn"], '#'));
+    assert_eq!(vec!["start", "middle"], altered);
+    assert_eq!("#### it.", tree.node("start").unwrap().dialogue);
+    assert_eq!("####.", tree.node("middle").unwrap().links[0].text());
+    assert_eq!("Bye.", tree.node("end").unwrap().dialogue);
+
+    // Filtering again alters nothing
+    assert!(filter_tree(&mut tree, &MaskWords::new(&["darn"], '#')).is_empty());
+
+    // Lines are filtered, but not their speakers, and the dialogue follows them
+    let mut lines = Node::new("lines", "");
+    lines.set_lines(vec![Line::new("Darn", "Darn it."), Line::new("Bob", "Hi.")]);
+    tree.insert_node(lines);
+    let altered = filter_tree(&mut tree, &MaskWords::new(&["darn"], '#'));
+    assert_eq!(vec!["lines"], altered);
+    let lines = tree.node("lines").unwrap();
+    assert_eq!("#### it.", lines.lines[0].text);
+    assert_eq!("Darn: #### it.\nBob: Hi.", lines.dialogue);
+}
+
+#[test]
+fn test_exclude_tags() {
+    use crate::{link::Link, node::Node};
+
+    // start -> (joke -> aside -> end | spoiler -> end | end)
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "Hello!");
+    start.links.push(Link::new("joke", "Tell me a joke."));
+    let mut spoiler = Link::new("spoiler", "How does it end?");
+    spoiler.tags.push("spoiler".into());
+    start.links.push(spoiler);
+    start.links.push(Link::new("end", "Bye."));
+    let mut joke = Node::new("joke", "A rude joke.");
+    joke.tags.push("mature".into());
+    joke.links.push(Link::new("aside", "Ha!"));
+    let mut aside = Node::new("aside", "And another.");
+    aside.tags.push("mature".into());
+    aside.links.push(Link::new("end", "Stop."));
+    let mut spoiler = Node::new("spoiler", "Everyone dies.");
+    spoiler.links.push(Link::new("end", "Oh."));
+    for node in [start, joke, aside, spoiler, Node::new("end", "Goodbye!")] {
+        tree.insert_node(node);
+    }
+    tree.set_root_key("start").unwrap();
+
+    // Chains of excluded nodes are rerouted past, and excluded links are removed
+    let filtered = exclude_tags(&tree, &["mature"]).unwrap();
+    assert_eq!(3, filtered.len());
+    let targets: Vec<&str> = filtered
+        .node("start")
+        .unwrap()
+        .links
+        .iter()
+        .map(|link| link.to_key.as_str())
+        .collect();
+    assert_eq!(vec!["end", "spoiler", "end"], targets);
+    assert_eq!(tree, exclude_tags(&tree, &[]).unwrap());
+
+    // Kept content must stay reachable
+    assert!(matches!(
+        exclude_tags(&tree, &["spoiler"]).unwrap_err(),
+        TreeError::Validation(message) if message == "Excluding tags disconnects `spoiler` from the root"
+    ));
+    tree.node_mut("start").unwrap().tags.push("mature".into());
+    assert!(exclude_tags(&tree, &["mature"]).is_err());
+}
@@ -0,0 +1,497 @@
+//! Import trees from the XML export of [articy:draft](https://www.articy.com).
+//!
+//! Every `DialogueFragment` becomes a [`Node`], keyed by its technical name, or its ID if it has none, and spoken by the display name of its speaker entity. Every connection out of a fragment becomes a [`Link`] whose dialogue is the target fragment's menu text, or its text if the menu text is empty. The root node is the first fragment connected from the first `Dialogue`, whose display name becomes the tree's title.
+//!
+//! `Hub`, `Jump`, `Condition`, and `Instruction` nodes are followed through to the fragments they lead to:
+//!
+//! * Condition expressions are read as [`Condition`]s on the links through their `true` pin. Links through the `false` pin are gated by the negated condition, which must be a single clause.
+//! * Instruction expressions are read as variable assignments, e.g. `GameState.met = true;`, which links through them [`set`][`Link#structfield.set`].
+//!
+//! Text is read in the `en` language if present, otherwise in the first language. Other node types are not supported.
+
+use crate::{
+    condition::{parse_value, Clause, Condition, Op},
+    error::{ImportError, TreeError},
+    link::Link,
+    node::{Line, Node},
+    tree::Tree,
+    value::Value,
+};
+
+use roxmltree::{Document, Node as Element};
+use std::{collections::HashMap, fs, path::Path};
+
+/// The language whose text is read, when present.
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+/// Try to return a [`Tree`] which is generated from importing an articy:draft XML file.
+///
+/// # Arguments
+///
+/// * `path` - A path type that references a file to parse from.
+///
+/// # Errors
+///
+/// * An [`ImportError`] will be returned if the file cannot be read, is not valid XML, or holds objects which cannot be converted.
+///
+/// # Examples
+///
+/// ```no_run
+/// use convo::foreign::articy;
+/// let tree = articy::import("export.xml").unwrap();
+/// ```
+pub fn import<P>(path: P) -> Result<Tree, ImportError>
+where
+    P: AsRef<Path>,
+{
+    source_to_tree(&fs::read_to_string(path)?)
+}
+
+/// Try to return a [`Tree`] which is generated from articy:draft XML.
+///
+/// # Arguments
+///
+/// * `source` - A string slice that holds the exported XML.
+///
+/// # Errors
+///
+/// * An [`ImportError`] will be returned if the source is not valid XML, or holds objects which cannot be converted.
+///
+/// # Examples
+///
+/// ```
+/// use convo::foreign::articy;
+/// let source = r#"<Export><Content>
+///     <DialogueFragment Id="0x01">
+///         <TechnicalName>DFr_Halt</TechnicalName>
+///         <Text><LocalizedString Lang="en">Halt!</LocalizedString></Text>
+///     </DialogueFragment>
+/// </Content></Export>"#;
+/// let tree = articy::source_to_tree(source).unwrap();
+/// assert_eq!("Halt!", tree.node("DFr_Halt").unwrap().dialogue);
+/// ```
+pub fn source_to_tree(source: &str) -> Result<Tree, ImportError> {
+    let document = Document::parse(source)?;
+    let content = document
+        .descendants()
+        .find(|element| element.has_tag_name("Content"))
+        .ok_or_else(|| TreeError::Validation("articy:draft XML has no `Content`".into()))?;
+    let export = Export::new(content)?;
+
+    let mut tree = Tree::new();
+    let mut root_key = None;
+    let mut has_dialogue = false;
+    for element in content.children().filter(Element::is_element) {
+        let id = match element.attribute("Id") {
+            Some(id) => id,
+            None => continue,
+        };
+        match element.tag_name().name() {
+            "DialogueFragment" => {
+                let dialogue = text(element, "Text");
+                let mut node = Node::new(export.key(id), dialogue.clone());
+                let speaker = child(element, "Speaker")
+                    .and_then(|speaker| speaker.attribute("IdRef"))
+                    .and_then(|speaker| export.objects.get(speaker))
+                    .map(|speaker| text(*speaker, "DisplayName"))
+                    .filter(|speaker| !speaker.is_empty());
+                if let Some(speaker) = speaker {
+                    node.set_lines(vec![Line::new(speaker, dialogue)]);
+                }
+                node.links = export.links_from(id)?;
+                root_key.get_or_insert_with(|| node.key.clone());
+                tree.insert_node(node);
+            }
+            "Dialogue" if !has_dialogue => {
+                has_dialogue = true;
+                if let Some(first) = export.links_from(id)?.first() {
+                    root_key = Some(first.to_key.clone());
+                }
+                let title = text(element, "DisplayName");
+                if !title.is_empty() {
+                    tree.meta_mut().title = Some(title);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let root_key = root_key.ok_or_else(|| {
+        TreeError::Validation("articy:draft XML has no dialogue fragments".into())
+    })?;
+    tree.set_root_key(&root_key)?;
+    Ok(tree)
+}
+
+// The objects of an export, and the connections between them
+struct Export<'a, 'input> {
+    objects: HashMap<&'a str, Element<'a, 'input>>,
+
+    // Target IDs, mapped by source ID, along with the index of the source pin
+    outgoing: HashMap<&'a str, Vec<(usize, &'a str)>>,
+}
+
+impl<'a, 'input> Export<'a, 'input> {
+    fn new(content: Element<'a, 'input>) -> Result<Self, ImportError> {
+        let mut objects = HashMap::new();
+        let mut pins = HashMap::new();
+        let mut connections = Vec::new();
+        for element in content.children().filter(Element::is_element) {
+            if element.has_tag_name("Connection") {
+                let end = |name| {
+                    child(element, name)
+                        .and_then(|end| Some((end.attribute("IdRef")?, end.attribute("PinRef"))))
+                        .ok_or_else(|| {
+                            TreeError::Validation(format!(
+                                "articy:draft connection has no `{}` reference",
+                                name
+                            ))
+                        })
+                };
+                let (source, pin) = end("Source")?;
+                let (target, _) = end("Target")?;
+                connections.push((source, pin, target));
+            } else if let Some(id) = element.attribute("Id") {
+                objects.insert(id, element);
+                for pin in element.descendants().filter(|pin| pin.has_tag_name("Pin")) {
+                    if let (Some(id), Some(index)) = (pin.attribute("Id"), pin.attribute("Index")) {
+                        pins.insert(id, index.parse().unwrap_or_default());
+                    }
+                }
+            }
+        }
+
+        let mut outgoing: HashMap<&str, Vec<(usize, &str)>> = HashMap::new();
+        for (source, pin, target) in connections {
+            let index = pin
+                .and_then(|pin| pins.get(pin))
+                .copied()
+                .unwrap_or_default();
+            outgoing.entry(source).or_default().push((index, target));
+        }
+        Ok(Export { objects, outgoing })
+    }
+
+    // Returns the technical name of an object, or its ID
+    fn key(&self, id: &str) -> String {
+        self.objects
+            .get(id)
+            .and_then(|object| child(*object, "TechnicalName"))
+            .and_then(|name| name.text())
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .unwrap_or(id)
+            .to_owned()
+    }
+
+    fn links_from(&self, id: &str) -> Result<Vec<Link>, ImportError> {
+        let mut links = Vec::new();
+        let mut through = Vec::new();
+        for (_, target) in self.outgoing.get(id).into_iter().flatten() {
+            self.follow(
+                target,
+                Link::new(String::new(), String::new()),
+                &mut through,
+                &mut links,
+            )?;
+        }
+        Ok(links)
+    }
+
+    // Follows a connection through to the fragments it leads to, collecting conditions and assignments on the way
+    fn follow(
+        &self,
+        id: &'a str,
+        link: Link,
+        through: &mut Vec<&'a str>,
+        links: &mut Vec<Link>,
+    ) -> Result<(), ImportError> {
+        let element = self.objects.get(id).ok_or_else(|| {
+            TreeError::Validation(format!("articy:draft connection leads to missing `{}`", id))
+        })?;
+        let kind = element.tag_name().name();
+        match kind {
+            "DialogueFragment" => {
+                let menu_text = text(*element, "MenuText");
+                let dialogue = match menu_text.is_empty() {
+                    true => text(*element, "Text"),
+                    false => menu_text,
+                };
+                links.push(Link {
+                    to_key: self.key(id),
+                    dialogue: Some(dialogue),
+                    ..link
+                });
+                return Ok(());
+            }
+            // Leaving the dialogue ends the conversation
+            "Dialogue" => return Ok(()),
+            "Hub" | "Jump" | "Condition" | "Instruction" => {}
+            kind => {
+                return Err(TreeError::Validation(format!(
+                    "articy:draft `{}` is an unsupported `{}`",
+                    id, kind
+                ))
+                .into())
+            }
+        }
+
+        if through.contains(&id) {
+            return Err(TreeError::Validation(format!(
+                "articy:draft `{}` leads back to itself without a dialogue fragment",
+                id
+            ))
+            .into());
+        }
+        through.push(id);
+        let outgoing = self.outgoing.get(id).map(Vec::as_slice).unwrap_or_default();
+        match kind {
+            "Jump" => {
+                let target = child(*element, "Target")
+                    .and_then(|target| target.attribute("IdRef"))
+                    .ok_or_else(|| {
+                        TreeError::Validation(format!("articy:draft jump `{}` has no target", id))
+                    })?;
+                self.follow(target, link, through, links)?;
+            }
+            "Condition" => {
+                let condition = expression_to_condition(*element, id)?;
+                let negated = negate(&condition);
+                for (pin, target) in outgoing {
+                    let clauses = match pin {
+                        0 => condition.clauses.clone(),
+                        _ => vec![negated.clone().ok_or_else(|| {
+                            TreeError::Validation(format!(
+                                "articy:draft condition `{}` has a false pin but more than one clause",
+                                id
+                            ))
+                        })?],
+                    };
+                    let mut link = link.clone();
+                    link.condition
+                        .get_or_insert_with(|| Condition { clauses: vec![] })
+                        .clauses
+                        .extend(clauses);
+                    self.follow(target, link, through, links)?;
+                }
+            }
+            _ => {
+                let mut link = link;
+                if kind == "Instruction" {
+                    link.set.extend(expression_to_assignments(*element, id)?);
+                }
+                for (_, target) in outgoing {
+                    self.follow(target, link.clone(), through, links)?;
+                }
+            }
+        }
+        through.pop();
+        Ok(())
+    }
+}
+
+fn child<'a, 'input>(element: Element<'a, 'input>, name: &str) -> Option<Element<'a, 'input>> {
+    element.children().find(|child| child.has_tag_name(name))
+}
+
+// Returns the text of a child, in the default language if it is localized, or the first language
+fn text(element: Element, name: &str) -> String {
+    let child = match child(element, name) {
+        Some(child) => child,
+        None => return String::new(),
+    };
+    let localized: Vec<Element> = child
+        .children()
+        .filter(|string| string.has_tag_name("LocalizedString"))
+        .collect();
+    let text = match localized.first() {
+        Some(first) => localized
+            .iter()
+            .find(|string| string.attribute("Lang") == Some(DEFAULT_LANGUAGE))
+            .unwrap_or(first)
+            .text(),
+        None => child.text(),
+    };
+    text.unwrap_or_default().trim().to_owned()
+}
+
+fn expression_to_condition(element: Element, id: &str) -> Result<Condition, ImportError> {
+    let expression = text(element, "Expression");
+    Condition::parse(&expression).map_err(|_| {
+        TreeError::Validation(format!(
+            "articy:draft condition `{}` is not a legal condition: `{}`",
+            id, expression
+        ))
+        .into()
+    })
+}
+
+// Returns the assignments of an instruction, e.g. `GameState.met = true; GameState.gold = 10;`
+fn expression_to_assignments(
+    element: Element,
+    id: &str,
+) -> Result<Vec<(String, Value)>, ImportError> {
+    let expression = text(element, "Expression");
+    let illegal = || {
+        TreeError::Validation(format!(
+            "articy:draft instruction `{}` is not a list of assignments: `{}`",
+            id, expression
+        ))
+    };
+    let mut assignments = Vec::new();
+    for statement in expression.split([';', '\n']) {
+        if statement.trim().is_empty() {
+            continue;
+        }
+        let (name, value) = statement.split_once('=').ok_or_else(illegal)?;
+        let name = name.trim();
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == '.')
+        {
+            return Err(illegal().into());
+        }
+        let value = parse_value(value).map_err(|_| illegal())?;
+        assignments.push((name.to_owned(), value));
+    }
+    Ok(assignments)
+}
+
+// Returns the negation of a condition with a single clause
+fn negate(condition: &Condition) -> Option<Clause> {
+    match condition.clauses.as_slice() {
+        [Clause::Flag { name, negated }] => Some(Clause::Flag {
+            name: name.to_owned(),
+            negated: !negated,
+        }),
+        [Clause::Compare { name, op, value }] => Some(Clause::Compare {
+            name: name.to_owned(),
+            op: match op {
+                Op::Eq => Op::Ne,
+                Op::Ne => Op::Eq,
+                Op::Lt => Op::Ge,
+                Op::Ge => Op::Lt,
+                Op::Gt => Op::Le,
+                Op::Le => Op::Gt,
+            },
+            value: value.clone(),
+        }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_source_to_tree() {
+    use crate::error::ImportError::{Validation, Xml};
+
+    let source = r#"<?xml version="1.0" encoding="utf-8"?>
+<Export>
+  <Content>
+    <Entity Id="0x10"><DisplayName><LocalizedString Lang="en">Smith</LocalizedString></DisplayName></Entity>
+    <Dialogue Id="0x20">
+      <DisplayName><LocalizedString Lang="en">Forge</LocalizedString></DisplayName>
+      <Pins><Pin Id="0x21" Index="0" Semantic="Input" /></Pins>
+    </Dialogue>
+    <DialogueFragment Id="0x30">
+      <TechnicalName>DFr_Greeting</TechnicalName>
+      <Speaker IdRef="0x10" />
+      <Text><LocalizedString Lang="de">Brauchst du eine Klinge?</LocalizedString><LocalizedString Lang="en">Need a blade?</LocalizedString></Text>
+      <Pins><Pin Id="0x31" Index="0" Semantic="Output" /></Pins>
+    </DialogueFragment>
+    <Condition Id="0x40">
+      <Expression>GameState.gold &gt;= 10</Expression>
+      <Pins><Pin Id="0x41" Index="0" Semantic="Output" /><Pin Id="0x42" Index="1" Semantic="Output" /></Pins>
+    </Condition>
+    <Instruction Id="0x50">
+      <Expression>GameState.bought = true; GameState.blade = "short";</Expression>
+    </Instruction>
+    <Hub Id="0x60" />
+    <DialogueFragment Id="0x70">
+      <MenuText><LocalizedString Lang="en">Yes.</LocalizedString></MenuText>
+      <Text><LocalizedString Lang="en">Yes, I'll take one.</LocalizedString></Text>
+    </DialogueFragment>
+    <DialogueFragment Id="0x80">
+      <Text>Maybe later.</Text>
+    </DialogueFragment>
+    <Jump Id="0x90"><Target IdRef="0x30" /></Jump>
+    <Connection Id="0xA0"><Source IdRef="0x20" PinRef="0x21" /><Target IdRef="0x30" /></Connection>
+    <Connection Id="0xA1"><Source IdRef="0x30" PinRef="0x31" /><Target IdRef="0x40" /></Connection>
+    <Connection Id="0xA2"><Source IdRef="0x40" PinRef="0x41" /><Target IdRef="0x50" /></Connection>
+    <Connection Id="0xA3"><Source IdRef="0x40" PinRef="0x42" /><Target IdRef="0x60" /></Connection>
+    <Connection Id="0xA4"><Source IdRef="0x50" /><Target IdRef="0x70" /></Connection>
+    <Connection Id="0xA5"><Source IdRef="0x60" /><Target IdRef="0x80" /></Connection>
+    <Connection Id="0xA6"><Source IdRef="0x70" /><Target IdRef="0x20" /></Connection>
+    <Connection Id="0xA7"><Source IdRef="0x80" /><Target IdRef="0x90" /></Connection>
+  </Content>
+</Export>"#;
+    let tree = source_to_tree(source).unwrap();
+    assert_eq!("DFr_Greeting", tree.root_key().unwrap());
+    assert_eq!(Some("Forge".to_owned()), tree.meta().title);
+
+    // Fragments keep their speaker, and links gather conditions and assignments on the way
+    let greeting = tree.node("DFr_Greeting").unwrap();
+    assert_eq!(vec![Line::new("Smith", "Need a blade?")], greeting.lines);
+    assert_eq!(
+        vec![("0x70", "Yes."), ("0x80", "Maybe later.")],
+        greeting
+            .links
+            .iter()
+            .map(|link| (link.to_key.as_str(), link.text()))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        Some(Condition::parse("GameState.gold >= 10").unwrap()),
+        greeting.links[0].condition
+    );
+    assert_eq!(
+        vec![
+            ("GameState.bought".to_owned(), Value::Bool(true)),
+            ("GameState.blade".to_owned(), Value::String("short".into()))
+        ],
+        greeting.links[0].set
+    );
+    assert_eq!(
+        Some(Condition::parse("GameState.gold < 10").unwrap()),
+        greeting.links[1].condition
+    );
+
+    // Leaving the dialogue ends the conversation, and jumps are followed
+    assert!(tree.node("0x70").unwrap().links.is_empty());
+    assert_eq!("DFr_Greeting", tree.node("0x80").unwrap().links[0].to_key);
+
+    // Sources must be articy:draft XML of supported, acyclic objects
+    assert!(matches!(source_to_tree("<Export>").unwrap_err(), Xml(_)));
+    let fragment = |objects: &str| {
+        format!(
+            r#"<Export><Content>
+                <DialogueFragment Id="1" />
+                <Connection><Source IdRef="1" /><Target IdRef="2" /></Connection>
+                {}
+            </Content></Export>"#,
+            objects
+        )
+    };
+    for source in [
+        "<Export />".to_owned(),
+        "<Export><Content /></Export>".to_owned(),
+        fragment(""),
+        fragment(r#"<FlowFragment Id="2" />"#),
+        fragment(
+            r#"<Hub Id="2" /><Connection><Source IdRef="2" /><Target IdRef="2" /></Connection>"#,
+        ),
+        fragment(r#"<Condition Id="2"><Expression>a || b</Expression></Condition>"#),
+        fragment(r#"<Instruction Id="2"><Expression>gold += 1;</Expression></Instruction>"#),
+        fragment(
+            r#"<Condition Id="2"><Expression>a &amp;&amp; b</Expression><Pins><Pin Id="3" Index="1" /></Pins></Condition>
+               <Connection><Source IdRef="2" PinRef="3" /><Target IdRef="1" /></Connection>"#,
+        ),
+    ] {
+        assert!(
+            matches!(source_to_tree(&source).unwrap_err(), Validation(_)),
+            "{}",
+            source
+        );
+    }
+}
@@ -0,0 +1,318 @@
+//! Import trees from the JSON export of [Chat Mapper](https://www.chatmapper.com).
+//!
+//! Every conversation of a project becomes a [`Tree`], titled by its `Title` field. Every dialog node becomes a [`Node`] keyed by its ID, spoken by its actor, and every outgoing link becomes a [`Link`] whose dialogue is the destination node's `Menu Text`, or its `Dialogue Text` if the menu text is empty. The root node is the node marked `IsRoot`, or the node with ID 0.
+//!
+//! Conditions of a node gate the links into it, and are read as [`Condition`]s after rewriting the Lua forms `Variable["name"]`, `~=` and `and`. User scripts are not imported, and links to other conversations are not supported.
+
+use crate::{
+    condition::Condition,
+    error::{ImportError, TreeError},
+    link::Link,
+    node::{Line, Node},
+    tree::Tree,
+};
+
+use serde_json::Value;
+use std::{collections::BTreeMap, fs, path::Path};
+
+/// Try to return every conversation of a Chat Mapper JSON file as a titled [`Tree`], in the order they are exported.
+///
+/// # Arguments
+///
+/// * `path` - A path type that references a file to parse from.
+///
+/// # Errors
+///
+/// * An [`ImportError`] will be returned if the file cannot be read, is not valid JSON, or holds conversations which cannot be converted.
+///
+/// # Examples
+///
+/// ```no_run
+/// use convo::foreign::chatmapper;
+/// for (title, tree) in chatmapper::import("project.json").unwrap() {
+///     println!("{}: {} nodes", title, tree.len());
+/// }
+/// ```
+pub fn import<P>(path: P) -> Result<Vec<(String, Tree)>, ImportError>
+where
+    P: AsRef<Path>,
+{
+    source_to_trees(&fs::read_to_string(path)?)
+}
+
+/// Try to return every conversation of Chat Mapper JSON as a titled [`Tree`], in the order they are exported.
+///
+/// # Arguments
+///
+/// * `source` - A string slice that holds the exported JSON.
+///
+/// # Errors
+///
+/// * An [`ImportError`] will be returned if the source is not valid JSON, or holds conversations which cannot be converted.
+///
+/// # Examples
+///
+/// ```
+/// use convo::foreign::chatmapper;
+/// let source = r#"{ "Assets": {
+///     "Actors": [{ "ID": 1, "Fields": { "Name": "Guard" } }],
+///     "Conversations": [{ "ID": 1, "Fields": { "Title": "Gate" }, "DialogNodes": [
+///         { "ID": 0, "IsRoot": true, "Fields": { "Actor": "1", "Dialogue Text": "Halt!" },
+///           "OutgoingLinks": [] }
+///     ] }]
+/// } }"#;
+/// let trees = chatmapper::source_to_trees(source).unwrap();
+/// assert_eq!("Gate", trees[0].0);
+/// assert_eq!("Guard: Halt!", trees[0].1.root_node().unwrap().dialogue);
+/// ```
+pub fn source_to_trees(source: &str) -> Result<Vec<(String, Tree)>, ImportError> {
+    let json: Value = serde_json::from_str(source)?;
+    let assets = json
+        .get("Assets")
+        .ok_or_else(|| TreeError::Validation("Chat Mapper JSON has no `Assets`".into()))?;
+
+    // Actors are referred to by ID
+    let mut actors = BTreeMap::new();
+    for actor in array(assets, "Actors") {
+        if let (Some(id), Some(name)) = (id(actor), field(actor, "Name")) {
+            actors.insert(id, name);
+        }
+    }
+
+    let mut trees = Vec::new();
+    for conversation in array(assets, "Conversations") {
+        let conversation_id = id(conversation).ok_or_else(|| {
+            TreeError::Validation("Chat Mapper conversation has no integer `ID`".into())
+        })?;
+        let title = field(conversation, "Title").unwrap_or_else(|| conversation_id.to_string());
+        trees.push((
+            title.clone(),
+            conversation_to_tree(conversation, conversation_id, &title, &actors)?,
+        ));
+    }
+    Ok(trees)
+}
+
+fn conversation_to_tree(
+    conversation: &Value,
+    conversation_id: i64,
+    title: &str,
+    actors: &BTreeMap<i64, String>,
+) -> Result<Tree, ImportError> {
+    let dialog_nodes: BTreeMap<i64, &Value> = array(conversation, "DialogNodes")
+        .iter()
+        .filter_map(|node| Some((id(node)?, node)))
+        .collect();
+
+    let mut tree = Tree::new();
+    let mut root_key = None;
+    for (node_id, dialog_node) in &dialog_nodes {
+        let dialogue = field(dialog_node, "Dialogue Text").unwrap_or_default();
+        let mut node = Node::new(node_id.to_string(), dialogue.clone());
+        let speaker = field(dialog_node, "Actor")
+            .and_then(|actor| actor.parse().ok())
+            .and_then(|actor: i64| actors.get(&actor));
+        if let Some(speaker) = speaker.filter(|_| !dialogue.is_empty()) {
+            node.set_lines(vec![Line::new(speaker.to_owned(), dialogue)]);
+        }
+        if dialog_node.get("IsRoot").and_then(Value::as_bool) == Some(true) {
+            root_key.get_or_insert_with(|| node.key.clone());
+        }
+
+        for outgoing in array(dialog_node, "OutgoingLinks") {
+            let destination_conversation = outgoing
+                .get("DestinationConvoID")
+                .and_then(Value::as_i64)
+                .unwrap_or(conversation_id);
+            if destination_conversation != conversation_id {
+                return Err(TreeError::Validation(format!(
+                    "Chat Mapper node {} of `{}` links to another conversation",
+                    node_id, title
+                ))
+                .into());
+            }
+            let destination_id = outgoing
+                .get("DestinationDialogID")
+                .and_then(Value::as_i64)
+                .ok_or_else(|| {
+                    TreeError::Validation(format!(
+                        "Chat Mapper node {} of `{}` has a link without a destination",
+                        node_id, title
+                    ))
+                })?;
+            let destination = dialog_nodes.get(&destination_id).ok_or_else(|| {
+                TreeError::Validation(format!(
+                    "Chat Mapper node {} of `{}` links to missing node {}",
+                    node_id, title, destination_id
+                ))
+            })?;
+            let choice = field(destination, "Menu Text")
+                .filter(|text| !text.is_empty())
+                .or_else(|| field(destination, "Dialogue Text"))
+                .unwrap_or_default();
+            let mut link = Link::new(destination_id.to_string(), choice);
+            link.condition = condition(destination, title)?;
+            node.links.push(link);
+        }
+        tree.insert_node(node);
+    }
+
+    let root_key = root_key.unwrap_or_else(|| "0".to_owned());
+    tree.set_root_key(&root_key)?;
+    Ok(tree)
+}
+
+// Returns the condition of a node, rewritten from Lua
+fn condition(dialog_node: &Value, title: &str) -> Result<Option<Condition>, ImportError> {
+    let lua = dialog_node
+        .get("ConditionsString")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    if lua.trim().is_empty() {
+        return Ok(None);
+    }
+    let mut source = lua.replace("~=", "!=").replace(" and ", " && ");
+    while let Some(start) = source.find("Variable[") {
+        let end = source[start..]
+            .find(']')
+            .map(|end| start + end)
+            .ok_or_else(|| {
+                TreeError::Validation(format!("Chat Mapper condition is not closed: `{}`", lua))
+            })?;
+        let name = source[start + "Variable[".len()..end]
+            .trim_matches(|c| c == '"' || c == '\'')
+            .to_owned();
+        source.replace_range(start..=end, &name);
+    }
+    Condition::parse(&source).map(Some).map_err(|_| {
+        TreeError::Validation(format!(
+            "Chat Mapper condition in `{}` is not a legal condition: `{}`",
+            title, lua
+        ))
+        .into()
+    })
+}
+
+// Returns the elements of an array property, or nothing
+fn array<'v>(value: &'v Value, name: &str) -> &'v [Value] {
+    value
+        .get(name)
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+fn id(value: &Value) -> Option<i64> {
+    value.get("ID").and_then(Value::as_i64)
+}
+
+// Returns a field, where fields are exported as an object, or an array of titled values
+fn field(value: &Value, title: &str) -> Option<String> {
+    let fields = value.get("Fields")?;
+    let field = match fields {
+        Value::Array(fields) => fields
+            .iter()
+            .find(|field| field.get("Title").and_then(Value::as_str) == Some(title))?
+            .get("Value")?,
+        fields => fields.get(title)?,
+    };
+    match field {
+        Value::String(field) => Some(field.to_owned()),
+        Value::Null => None,
+        field => Some(field.to_string()),
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_source_to_trees() {
+    use crate::error::ImportError::{Json, Validation};
+
+    let source = r#"{ "Title": "Demo", "Assets": {
+        "Actors": [
+            { "ID": 1, "Fields": [{ "Title": "Name", "Value": "Player", "Type": "Text" }] },
+            { "ID": 2, "Fields": [{ "Title": "Name", "Value": "Smith", "Type": "Text" }] }
+        ],
+        "Conversations": [
+            { "ID": 1, "Fields": [{ "Title": "Title", "Value": "Forge" }], "DialogNodes": [
+                { "ID": 0, "IsRoot": true, "Fields": [{ "Title": "Title", "Value": "START" }],
+                  "OutgoingLinks": [{ "DestinationConvoID": 1, "DestinationDialogID": 1 }] },
+                { "ID": 1, "Fields": [
+                    { "Title": "Actor", "Value": "2" },
+                    { "Title": "Dialogue Text", "Value": "Need a blade?" }
+                  ],
+                  "OutgoingLinks": [
+                    { "DestinationConvoID": 1, "DestinationDialogID": 2 },
+                    { "DestinationConvoID": 1, "DestinationDialogID": 3 }
+                  ] },
+                { "ID": 2, "ConditionsString": "Variable[\"gold\"] >= 10 and Variable['mood'] ~= \"angry\"",
+                  "Fields": [
+                    { "Title": "Actor", "Value": "1" },
+                    { "Title": "Menu Text", "Value": "Yes." },
+                    { "Title": "Dialogue Text", "Value": "Yes, I'll take one." }
+                  ], "OutgoingLinks": [] },
+                { "ID": 3, "Fields": [
+                    { "Title": "Actor", "Value": "1" },
+                    { "Title": "Menu Text", "Value": "" },
+                    { "Title": "Dialogue Text", "Value": "No." }
+                  ], "OutgoingLinks": [] }
+            ] },
+            { "ID": 2, "Fields": { "Title": "Empty" }, "DialogNodes": [{ "ID": 0, "Fields": {} }] }
+        ]
+    } }"#;
+    let trees = source_to_trees(source).unwrap();
+    assert_eq!(
+        vec!["Forge", "Empty"],
+        trees
+            .iter()
+            .map(|(title, _)| title.as_str())
+            .collect::<Vec<_>>()
+    );
+
+    // Nodes are keyed by ID, and links are labelled by their destination's menu or dialogue text
+    let forge = &trees[0].1;
+    assert_eq!("0", forge.root_key().unwrap());
+    assert_eq!("", forge.node("0").unwrap().dialogue);
+    let smith = forge.node("1").unwrap();
+    assert_eq!(vec![Line::new("Smith", "Need a blade?")], smith.lines);
+    assert_eq!(
+        vec![("2", "Yes."), ("3", "No.")],
+        smith
+            .links
+            .iter()
+            .map(|link| (link.to_key.as_str(), link.text()))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        Some(Condition::parse("gold >= 10 && mood != \"angry\"").unwrap()),
+        smith.links[0].condition
+    );
+    assert!(smith.links[1].condition.is_none());
+    assert_eq!("0", trees[1].1.root_key().unwrap());
+
+    // Sources must be Chat Mapper JSON whose links stay within their conversation
+    assert!(matches!(source_to_trees("{").unwrap_err(), Json(_)));
+    let node = |node: &str| {
+        format!(
+            r#"{{ "Assets": {{ "Conversations": [{{ "ID": 1, "DialogNodes": [
+                {{ "ID": 0, "OutgoingLinks": [{{ "DestinationConvoID": 1, "DestinationDialogID": 1 }}] }},
+                {} ] }}] }} }}"#,
+            node
+        )
+    };
+    for source in [
+        "[]".to_owned(),
+        node(r#"{ "ID": 2 }"#),
+        node(r#"{ "ID": 1, "ConditionsString": "Variable[\"gold\" > 1" }"#),
+        node(r#"{ "ID": 1, "ConditionsString": "gold > 1 or gold < 0" }"#),
+        node(
+            r#"{ "ID": 1, "OutgoingLinks": [{ "DestinationConvoID": 2, "DestinationDialogID": 0 }] }"#,
+        ),
+    ] {
+        assert!(
+            matches!(source_to_trees(&source).unwrap_err(), Validation(_)),
+            "{}",
+            source
+        );
+    }
+}
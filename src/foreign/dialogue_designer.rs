@@ -0,0 +1,297 @@
+//! Import trees from the JSON export of [Dialogue Designer](https://radmatt.itch.io/dialogue-designer).
+//!
+//! An export is an array of nodes, named by `node_name`. The first `start` node's `next` node becomes the root. `show_message` nodes become [`Node`]s spoken by their `character`, whose `choices` become [`Link`]s, or whose `next` node becomes a single link with empty dialogue. `random_branch` nodes become [random nodes][`NodeKind::Random`], and `comment` nodes are skipped. Other node types are not supported.
+//!
+//! Text is read in the `ENG` language if present, otherwise in the first language. Choice conditions are read as [`Condition`]s, where `{{name}}` refers to the variable `name`. Choices which lead nowhere end the conversation at an empty node keyed `<node_name>.<choice index>`.
+
+use crate::{
+    condition::Condition,
+    error::{ImportError, TreeError},
+    link::Link,
+    node::{Line, Node, NodeKind},
+    tree::Tree,
+};
+
+use serde_json::{Map, Value};
+use std::{fs, path::Path};
+
+/// The language whose text is read, when present.
+pub const DEFAULT_LANGUAGE: &str = "ENG";
+
+/// Try to return a [`Tree`] which is generated from importing a Dialogue Designer JSON file.
+///
+/// # Arguments
+///
+/// * `path` - A path type that references a file to parse from.
+///
+/// # Errors
+///
+/// * An [`ImportError`] will be returned if the file cannot be read, is not valid JSON, or holds nodes which cannot be converted.
+///
+/// # Examples
+///
+/// ```no_run
+/// use convo::foreign::dialogue_designer;
+/// let tree = dialogue_designer::import("intro.json").unwrap();
+/// ```
+pub fn import<P>(path: P) -> Result<Tree, ImportError>
+where
+    P: AsRef<Path>,
+{
+    source_to_tree(&fs::read_to_string(path)?)
+}
+
+/// Try to return a [`Tree`] which is generated from Dialogue Designer JSON.
+///
+/// # Arguments
+///
+/// * `source` - A string slice that holds the exported JSON.
+///
+/// # Errors
+///
+/// * An [`ImportError`] will be returned if the source is not valid JSON, or holds nodes which cannot be converted.
+///
+/// # Examples
+///
+/// ```
+/// use convo::foreign::dialogue_designer;
+/// let source = r#"[
+///     { "node_name": "START", "node_type": "start", "title": "START", "next": "1" },
+///     { "node_name": "1", "node_type": "show_message", "character": ["Guard", 0],
+///       "text": { "ENG": "Halt!" }, "choices": [] }
+/// ]"#;
+/// let tree = dialogue_designer::source_to_tree(source).unwrap();
+/// assert_eq!("Guard: Halt!", tree.root_node().unwrap().dialogue);
+/// ```
+pub fn source_to_tree(source: &str) -> Result<Tree, ImportError> {
+    let json: Value = serde_json::from_str(source)?;
+    let entries = json.as_array().ok_or_else(|| {
+        TreeError::Validation("Dialogue Designer JSON is not an array of nodes".into())
+    })?;
+
+    let mut tree = Tree::new();
+    let mut root_key = None;
+    for entry in entries {
+        // Entries without a name hold editor settings rather than nodes
+        let name = match entry.get("node_name").and_then(Value::as_str) {
+            Some(name) => name,
+            None => continue,
+        };
+        let node_type = entry
+            .get("node_type")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        match node_type {
+            "start" => {
+                if root_key.is_none() {
+                    root_key = Some(next_key(entry, name)?.ok_or_else(|| {
+                        TreeError::Validation(format!(
+                            "Dialogue Designer start node `{}` leads nowhere",
+                            name
+                        ))
+                    })?);
+                }
+            }
+            "show_message" => {
+                for node in message_to_nodes(entry, name)? {
+                    tree.insert_node(node);
+                }
+            }
+            "random_branch" => {
+                let mut node = Node::new(name, "");
+                node.kind = NodeKind::Random { avoid_recent: 0 };
+                let branches = entry
+                    .get("branches")
+                    .and_then(Value::as_object)
+                    .ok_or_else(|| {
+                        TreeError::Validation(format!(
+                            "Dialogue Designer random branch `{}` has no branches",
+                            name
+                        ))
+                    })?;
+                for to_key in branches.values().filter_map(Value::as_str) {
+                    node.links.push(Link::silent(to_key));
+                }
+                tree.insert_node(node);
+            }
+            "comment" => {}
+            node_type => {
+                return Err(TreeError::Validation(format!(
+                    "Dialogue Designer node `{}` has unsupported type `{}`",
+                    name, node_type
+                ))
+                .into())
+            }
+        }
+    }
+
+    let root_key = root_key
+        .ok_or_else(|| TreeError::Validation("Dialogue Designer JSON has no start node".into()))?;
+    tree.set_root_key(&root_key)?;
+    Ok(tree)
+}
+
+// Returns the message node, followed by an end node for each choice which leads nowhere
+fn message_to_nodes(entry: &Value, name: &str) -> Result<Vec<Node>, ImportError> {
+    let dialogue = text(entry.get("text"));
+    let speaker = entry
+        .get("character")
+        .and_then(|character| character.get(0))
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let mut node = Node::new(name, dialogue.as_str());
+    if !speaker.is_empty() {
+        node.set_lines(vec![Line::new(speaker, dialogue.as_str())]);
+    }
+
+    let mut ends = Vec::new();
+    let choices = entry
+        .get("choices")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    for (i, choice) in choices.iter().enumerate() {
+        let to_key = match next_key(choice, name)? {
+            Some(to_key) => to_key,
+            None => {
+                let end = Node::new(format!("{}.{}", name, i), String::new());
+                let to_key = end.key.clone();
+                ends.push(end);
+                to_key
+            }
+        };
+        let mut link = Link::new(to_key, text(choice.get("text")));
+        let is_condition = choice
+            .get("is_condition")
+            .and_then(Value::as_bool)
+            .unwrap_or_default();
+        let condition = choice
+            .get("condition")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        if is_condition && !condition.trim().is_empty() {
+            let source = condition.replace("{{", "").replace("}}", "");
+            link.condition = Some(Condition::parse(&source).map_err(|_| {
+                TreeError::Validation(format!(
+                    "Dialogue Designer condition of choice {} in `{}` is not a legal condition: `{}`",
+                    i, name, condition
+                ))
+            })?);
+        }
+        node.links.push(link);
+    }
+    if choices.is_empty() {
+        if let Some(to_key) = next_key(entry, name)? {
+            node.links.push(Link::silent(to_key));
+        }
+    }
+
+    let mut nodes = vec![node];
+    nodes.extend(ends);
+    Ok(nodes)
+}
+
+// Returns the key of the `next` node, which is null when leading nowhere
+fn next_key(entry: &Value, name: &str) -> Result<Option<String>, ImportError> {
+    match entry.get("next") {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(next)) if next.is_empty() => Ok(None),
+        Some(Value::String(next)) => Ok(Some(next.to_owned())),
+        Some(next) => Err(TreeError::Validation(format!(
+            "Dialogue Designer node `{}` has a next node which is not a string: `{}`",
+            name, next
+        ))
+        .into()),
+    }
+}
+
+// Returns the text in the default language, or the first language
+fn text(value: Option<&Value>) -> String {
+    let pick = |languages: &Map<String, Value>| {
+        languages
+            .get(DEFAULT_LANGUAGE)
+            .or_else(|| languages.values().next())
+            .and_then(Value::as_str)
+            .map(str::to_owned)
+    };
+    match value {
+        Some(Value::String(text)) => text.to_owned(),
+        Some(Value::Object(languages)) => pick(languages).unwrap_or_default(),
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_source_to_tree() {
+    use crate::error::ImportError::{Json, Validation};
+
+    let source = r#"[
+        { "node_name": "START", "node_type": "start", "title": "START", "next": "1" },
+        { "node_name": "1", "node_type": "show_message", "character": ["Merchant", 0],
+          "text": { "ENG": "Buying or selling?", "FRA": "Vous achetez ?" },
+          "choices": [
+            { "text": { "ENG": "Buying." }, "next": "2", "is_condition": false, "condition": "" },
+            { "text": { "ENG": "Selling." }, "next": "3", "is_condition": true, "condition": "{{has_loot}} == true" },
+            { "text": { "ENG": "Nothing." }, "next": null, "is_condition": false, "condition": "" }
+          ] },
+        { "node_name": "2", "node_type": "show_message", "character": ["", 0],
+          "text": { "FRA": "Voici." }, "choices": [], "next": "4" },
+        { "node_name": "3", "node_type": "show_message", "text": "Let's see.", "choices": [] },
+        { "node_name": "4", "node_type": "random_branch", "possibilities": 2,
+          "branches": { "1": "3", "2": "1" } },
+        { "node_name": "5", "node_type": "comment", "text": "TODO" },
+        { "editor_version": "1.0", "languages": ["ENG", "FRA"] }
+    ]"#;
+    let tree = source_to_tree(source).unwrap();
+    assert_eq!("1", tree.root_key().unwrap());
+    assert_eq!(5, tree.len());
+
+    // Messages keep their speaker, and choices keep their conditions
+    let merchant = tree.node("1").unwrap();
+    assert_eq!(
+        vec![Line::new("Merchant", "Buying or selling?")],
+        merchant.lines
+    );
+    assert_eq!(
+        vec![("2", "Buying."), ("3", "Selling."), ("1.2", "Nothing.")],
+        merchant
+            .links
+            .iter()
+            .map(|link| (link.to_key.as_str(), link.text()))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        Some(Condition::parse("has_loot == true").unwrap()),
+        merchant.links[1].condition
+    );
+    assert!(tree.node("1.2").unwrap().links.is_empty());
+
+    // Messages without choices continue to their next node
+    let voici = tree.node("2").unwrap();
+    assert_eq!("Voici.", voici.dialogue);
+    assert!(voici.lines.is_empty());
+    assert_eq!(vec![Link::silent("4")], voici.links);
+    assert_eq!(
+        NodeKind::Random { avoid_recent: 0 },
+        tree.node("4").unwrap().kind
+    );
+
+    // Sources must be JSON arrays of supported nodes with a start node
+    assert!(matches!(source_to_tree("[").unwrap_err(), Json(_)));
+    for source in [
+        "{}",
+        "[]",
+        r#"[{ "node_name": "START", "node_type": "start", "next": null }]"#,
+        r#"[{ "node_name": "1", "node_type": "wait", "time": 1 }]"#,
+        r#"[{ "node_name": "START", "node_type": "start", "next": "1" },
+            { "node_name": "1", "node_type": "show_message", "text": "Hi",
+              "choices": [{ "text": "Go", "next": "1", "is_condition": true, "condition": "a b" }] }]"#,
+    ] {
+        assert!(
+            matches!(source_to_tree(source).unwrap_err(), Validation(_)),
+            "{}",
+            source
+        );
+    }
+}
@@ -0,0 +1,15 @@
+//! Importers and exporters for the formats of other dialogue tools, so existing content can be migrated to and from convo.
+//!
+//! Each format lives in its own module, behind the feature which provides its parser, if it needs one.
+
+#[cfg(feature = "xml")]
+pub mod articy;
+#[cfg(feature = "json")]
+pub mod chatmapper;
+#[cfg(feature = "json")]
+pub mod dialogue_designer;
+pub mod screenplay;
+#[cfg(feature = "spreadsheet")]
+pub mod spreadsheet;
+#[cfg(feature = "json")]
+pub mod unity;
@@ -0,0 +1,461 @@
+//! Import trees drafted as indented plain text, so writers can draft in any text editor and convert to `*.convo.yml` later, e.g. with `convo convert`.
+//!
+//! Each line is a prompt, such as `Guard: Halt!`, or a choice starting with `>`. Consecutive prompts form one node, and the choices below them are its links. Lines indented under a choice are where the choice leads. A branch which runs out of lines, or a choice with nothing indented under it, continues at the next prompt after the choices, or ends the conversation if there is none.
+//!
+//! ```text
+//! # Comments start with `#`, and blank lines are ignored
+//! [gate] Guard: Halt! Who goes there?
+//!   > A friend.
+//!     Guard: Pass, friend.
+//!   > Nobody.
+//!     Guard: Nobody, eh?
+//!     > Nobody at all. -> gate
+//!   > ...
+//! Guard: Move along.
+//! ```
+//!
+//! Keys are inferred from the first words of a node's dialogue, e.g. `pass_friend` (see [`crate::keygen`]), unless a prompt is labelled with `[key]`. A choice ending with `-> key` leads to the labelled node. A prompt's speaker is the text before its first `: `, if that is a short name. Indentation may use any mix of spaces and tabs, as long as nested lines are indented further than their choice.
+
+use crate::{
+    error::{ImportError, TreeError},
+    keygen,
+    link::Link,
+    node::{Line, Node},
+    tree::Tree,
+};
+
+use std::{collections::HashMap, fs, path::Path};
+
+/// Try to return a [`Tree`] which is generated from importing a plain-text screenplay file.
+///
+/// # Arguments
+///
+/// * `path` - A path type that references a file to parse from.
+///
+/// # Errors
+///
+/// * An [`ImportError`] will be returned if the file cannot be read, or its text cannot be converted.
+///
+/// # Examples
+///
+/// ```no_run
+/// use convo::foreign::screenplay;
+/// let tree = screenplay::import("draft.txt").unwrap();
+/// ```
+pub fn import<P>(path: P) -> Result<Tree, ImportError>
+where
+    P: AsRef<Path>,
+{
+    source_to_tree(&fs::read_to_string(path)?)
+}
+
+/// Try to return a [`Tree`] which is generated from a plain-text screenplay.
+///
+/// # Arguments
+///
+/// * `source` - A string slice that holds the screenplay.
+///
+/// # Errors
+///
+/// * An [`ImportError`] will be returned if the screenplay has no prompts, a choice has no prompt above it, labels are repeated, or a choice jumps to a missing label. Errors name the line, counting from 1.
+///
+/// # Examples
+///
+/// ```
+/// use convo::foreign::screenplay;
+/// let source = "
+/// Guard: Halt!
+///   > Sorry.
+/// Guard: Move along.
+/// ";
+/// let tree = screenplay::source_to_tree(source).unwrap();
+/// assert_eq!("Guard: Halt!", tree.root_node().unwrap().dialogue);
+/// assert_eq!("move_along", tree.root_node().unwrap().links[0].to_key);
+/// ```
+pub fn source_to_tree(source: &str) -> Result<Tree, ImportError> {
+    // Read lines, with their number and indentation
+    let mut lines = Vec::new();
+    for (i, text) in source.lines().enumerate() {
+        let content = text.trim();
+        if content.is_empty() || content.starts_with('#') {
+            continue;
+        }
+        let indent = text.len() - text.trim_start().len();
+        lines.push((i + 1, indent, content));
+    }
+    let mut position = 0;
+    let block = read_block(&lines, &mut position, 0)?;
+    if block.is_empty() {
+        return Err(TreeError::Validation("Screenplay has no prompts".into()).into());
+    }
+
+    let mut builder = Builder::default();
+    let root_key = builder
+        .build(&block, None)?
+        .ok_or_else(|| TreeError::Validation("Screenplay has no prompts".into()))?;
+
+    // Jumps are resolved once every label is known
+    for (node_key, index, label, number) in builder.jumps {
+        let to_key = builder.labels.get(&label).ok_or_else(|| {
+            TreeError::Validation(format!(
+                "Line {}: choice jumps to missing label `{}`",
+                number, label
+            ))
+        })?;
+        if let Some(node) = builder.tree.node_mut(&node_key) {
+            node.links[index].to_key = to_key.to_owned();
+        }
+    }
+    builder.tree.set_root_key(&root_key)?;
+    Ok(builder.tree)
+}
+
+enum Item<'s> {
+    Prompt {
+        number: usize,
+        label: Option<&'s str>,
+        text: &'s str,
+    },
+    Choice {
+        number: usize,
+        text: &'s str,
+        jump: Option<&'s str>,
+        branch: Vec<Item<'s>>,
+    },
+}
+
+// Reads the lines indented at least as far as the first, with choices holding the lines indented further under them
+fn read_block<'s>(
+    lines: &[(usize, usize, &'s str)],
+    position: &mut usize,
+    min_indent: usize,
+) -> Result<Vec<Item<'s>>, ImportError> {
+    let mut items = Vec::new();
+    while let Some(&(number, indent, content)) = lines.get(*position) {
+        if indent < min_indent {
+            break;
+        }
+        *position += 1;
+        match content.strip_prefix('>') {
+            Some(choice) => {
+                let (text, jump) = match choice.rsplit_once("->") {
+                    Some((text, label)) if is_label(label.trim()) => (text, Some(label.trim())),
+                    _ => (choice, None),
+                };
+                let branch = match lines.get(*position) {
+                    Some(&(_, next_indent, _)) if next_indent > indent => {
+                        read_block(lines, position, next_indent)?
+                    }
+                    _ => Vec::new(),
+                };
+                items.push(Item::Choice {
+                    number,
+                    text: text.trim(),
+                    jump,
+                    branch,
+                });
+            }
+            None => {
+                let (label, text) = match content
+                    .strip_prefix('[')
+                    .and_then(|rest| rest.split_once(']'))
+                {
+                    Some((label, text)) if is_label(label.trim()) => {
+                        (Some(label.trim()), text.trim())
+                    }
+                    _ => (None, content),
+                };
+                items.push(Item::Prompt {
+                    number,
+                    label,
+                    text,
+                });
+            }
+        }
+    }
+    Ok(items)
+}
+
+fn is_label(label: &str) -> bool {
+    !label.is_empty()
+        && label
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '.')
+}
+
+#[derive(Default)]
+struct Builder {
+    tree: Tree,
+
+    // Node keys, mapped by label
+    labels: HashMap<String, String>,
+
+    // Links which jump to labels, as node key, link index, label, and line number
+    jumps: Vec<(String, usize, String, usize)>,
+}
+
+impl Builder {
+    // Builds the nodes of a block, whose last node continues at `next`, returning the key of its first node
+    fn build(&mut self, block: &[Item], next: Option<&str>) -> Result<Option<String>, ImportError> {
+        // A node is a run of prompts, followed by a run of choices
+        let mut segments: Vec<(&[Item], &[Item])> = Vec::new();
+        let mut start = 0;
+        while start < block.len() {
+            let choices = start
+                + block[start..]
+                    .iter()
+                    .position(|item| matches!(item, Item::Choice { .. }))
+                    .unwrap_or(block.len() - start);
+            let end = choices
+                + block[choices..]
+                    .iter()
+                    .position(|item| matches!(item, Item::Prompt { .. }))
+                    .unwrap_or(block.len() - choices);
+            if choices == start {
+                if let Item::Choice { number, .. } = &block[start] {
+                    return Err(TreeError::Validation(format!(
+                        "Line {}: choice has no prompt above it",
+                        number
+                    ))
+                    .into());
+                }
+            }
+            segments.push((&block[start..choices], &block[choices..end]));
+            start = end;
+        }
+
+        // Keys are reserved in order, so the first prompts get the plainest keys
+        let mut keys = Vec::new();
+        for (prompts, _) in &segments {
+            keys.push(self.reserve_key(prompts)?);
+        }
+
+        for (i, (prompts, choices)) in segments.iter().enumerate() {
+            let continuation = keys.get(i + 1).map(String::as_str).or(next);
+            let mut node = prompts_to_node(&keys[i], prompts);
+            if choices.is_empty() {
+                if let Some(continuation) = continuation {
+                    node.links.push(Link::silent(continuation));
+                }
+            }
+            for choice in choices.iter() {
+                if let Item::Choice {
+                    number,
+                    text,
+                    jump,
+                    branch,
+                } = choice
+                {
+                    let to_key = match jump {
+                        Some(label) => {
+                            self.jumps.push((
+                                node.key.clone(),
+                                node.links.len(),
+                                (*label).to_owned(),
+                                *number,
+                            ));
+                            String::new()
+                        }
+                        None => match self.build(branch, continuation)? {
+                            Some(key) => key,
+                            None => match continuation {
+                                Some(key) => key.to_owned(),
+                                None => self.end_key(),
+                            },
+                        },
+                    };
+                    node.links.push(Link::new(to_key, (*text).to_owned()));
+                }
+            }
+            self.tree.insert_node(node);
+        }
+        Ok(keys.into_iter().next())
+    }
+
+    // Returns the label of the prompts, or a key inferred from their first words
+    fn reserve_key(&mut self, prompts: &[Item]) -> Result<String, ImportError> {
+        for prompt in prompts {
+            if let Item::Prompt {
+                number,
+                label: Some(label),
+                ..
+            } = prompt
+            {
+                if self.labels.contains_key(*label) {
+                    return Err(TreeError::Validation(format!(
+                        "Line {}: label `{}` is already used",
+                        number, label
+                    ))
+                    .into());
+                }
+                let key = self.unique_key(label);
+                self.labels.insert((*label).to_owned(), key.clone());
+                return Ok(key);
+            }
+        }
+        let texts: Vec<&str> = prompts
+            .iter()
+            .filter_map(|prompt| match prompt {
+                Item::Prompt { text, .. } => Some(split_speaker(text).1),
+                Item::Choice { .. } => None,
+            })
+            .collect();
+        Ok(self.unique_key(&keygen::slugify(&texts.join(" "))))
+    }
+
+    fn unique_key(&mut self, stem: &str) -> String {
+        let key = keygen::unique_key(stem, |key| {
+            self.tree.contains_node(key) || self.labels.values().any(|used| used == key)
+        });
+        // Reserve the key until its node is built
+        self.tree.insert_node(Node::new(key.as_str(), ""));
+        key
+    }
+
+    // Returns the key of an empty node which ends the conversation
+    fn end_key(&mut self) -> String {
+        match self.tree.node("end") {
+            Some(end) if end.end => "end".to_owned(),
+            _ => {
+                let key = self.unique_key("end");
+                if let Some(end) = self.tree.node_mut(&key) {
+                    end.end = true;
+                }
+                key
+            }
+        }
+    }
+}
+
+fn prompts_to_node(key: &str, prompts: &[Item]) -> Node {
+    let texts: Vec<&str> = prompts
+        .iter()
+        .filter_map(|prompt| match prompt {
+            Item::Prompt { text, .. } => Some(*text),
+            Item::Choice { .. } => None,
+        })
+        .collect();
+    let mut node = Node::new(key, &texts.join("\n"));
+    let lines: Vec<Line> = texts
+        .iter()
+        .filter_map(|text| match split_speaker(text) {
+            (Some(speaker), text) => Some(Line::new(speaker, text)),
+            (None, _) => None,
+        })
+        .collect();
+    if lines.len() == texts.len() {
+        node.set_lines(lines);
+    }
+    node
+}
+
+// Splits `Speaker: text`, where the speaker is a name of at most three words
+fn split_speaker(text: &str) -> (Option<&str>, &str) {
+    match text.split_once(": ") {
+        Some((speaker, line))
+            if speaker.split_whitespace().count() <= 3
+                && speaker
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || " _-'.".contains(c)) =>
+        {
+            (Some(speaker.trim()), line.trim())
+        }
+        _ => (None, text),
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_source_to_tree() {
+    use crate::error::ImportError::Validation;
+
+    let source = "
+# The gate
+[gate] Guard: Halt! Who goes there?
+\t> A friend.
+\t\tGuard: Pass, friend.
+\t\tGuard: Mind the mud.
+\t> Nobody.
+\t\tGuard: Nobody, eh?
+\t\t  > Nobody at all. -> gate
+\t\t  > Just a traveller.
+\t> ...
+
+Guard: Move along.
+  > Fine.
+  > A friend.
+    Guard: Pass, friend.
+";
+    let tree = source_to_tree(source).unwrap();
+    assert_eq!("gate", tree.root_key().unwrap());
+    let links = |key: &str| {
+        tree.node(key)
+            .unwrap()
+            .links
+            .iter()
+            .map(|link| (link.to_key.as_str(), link.text()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        vec![
+            ("pass_friend_mind_the", "A friend."),
+            ("nobody_eh", "Nobody."),
+            ("move_along", "...")
+        ],
+        links("gate")
+    );
+
+    // Prompts form one node, and branches continue at the next prompt after their choices
+    let pass = tree.node("pass_friend_mind_the").unwrap();
+    assert_eq!(
+        vec![
+            Line::new("Guard", "Pass, friend."),
+            Line::new("Guard", "Mind the mud.")
+        ],
+        pass.lines
+    );
+    assert_eq!(vec![("move_along", "")], links("pass_friend_mind_the"));
+    assert_eq!(
+        vec![
+            ("gate", "Nobody at all."),
+            ("move_along", "Just a traveller.")
+        ],
+        links("nobody_eh")
+    );
+
+    // The last prompts end the conversation, and inferred keys are unique
+    assert_eq!(
+        vec![("end", "Fine."), ("pass_friend", "A friend.")],
+        links("move_along")
+    );
+    assert!(tree.node("end").unwrap().dialogue.is_empty());
+    assert!(tree.node("end").unwrap().end);
+    assert!(links("pass_friend").is_empty());
+
+    // Lines without a short speaker name are plain dialogue
+    let tree = source_to_tree("It was a dark night: stormy, too.\n> Go on.").unwrap();
+    let root = tree.root_node().unwrap();
+    assert!(root.lines.is_empty());
+    assert_eq!("It was a dark night: stormy, too.", root.dialogue);
+    assert_eq!("it_was_a_dark", root.key);
+
+    // Malformed screenplays are reported with their line number
+    for (line, source) in [
+        (1, "> Hello?"),
+        (3, "A\n> B\n  > C"),
+        (3, "[a] A\n> B\n  [a] C"),
+        (2, "A\n> B -> missing"),
+    ] {
+        match source_to_tree(source).unwrap_err() {
+            Validation(TreeError::Validation(message)) => {
+                assert!(
+                    message.starts_with(&format!("Line {}:", line)),
+                    "{}",
+                    message
+                )
+            }
+            error => panic!("{:?}", error),
+        }
+    }
+    assert!(source_to_tree("# Nothing yet\n\n").is_err());
+}
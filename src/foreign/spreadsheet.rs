@@ -0,0 +1,371 @@
+//! Import trees drafted in spreadsheets, such as Excel (`.xlsx`, `.xls`, `.xlsb`) or OpenDocument (`.ods`) workbooks.
+//!
+//! The first row of a sheet is a header naming its columns, in any order and case. Every other row is a line of dialogue:
+//!
+//! | Column | Required | Description |
+//! |---|---|---|
+//! | `key` | No | The key of the node the line belongs to. Rows with an empty key, or the key of the row above, continue its node. If the first row has no key, or there is no `key` column, its node is keyed from its text, e.g. `need_a_blade` (see [`crate::keygen`]). |
+//! | `text` | Yes | The text of the line. |
+//! | `speaker` | No | The speaker of the line. If one line of a node has a speaker, every line must. |
+//! | `links` | No | Links of the node, one per line of the cell or separated by `\|`, written as `target: choice text`. |
+//!
+//! Other columns, e.g. for writer notes, are ignored, as are empty rows. The first node is the root.
+//!
+//! | key | speaker | text | links |
+//! |---|---|---|---|
+//! | start | Smith | Need a blade? | buy: Yes. \| end: No. |
+//! | | Smith | They're sharp. | |
+//! | buy | Smith | Here you go. | |
+//! | end | Smith | Suit yourself. | |
+
+use crate::{
+    error::{ImportError, TreeError},
+    keygen,
+    link::Link,
+    node::{Line, Node},
+    tree::Tree,
+};
+
+use calamine::{open_workbook_auto, Reader};
+use std::path::Path;
+
+/// Try to return a [`Tree`] which is generated from importing the first sheet of a spreadsheet.
+///
+/// # Arguments
+///
+/// * `path` - A path type that references a spreadsheet to parse from.
+///
+/// # Errors
+///
+/// * An [`ImportError`] will be returned if the spreadsheet cannot be read, has no sheets, or holds malformed rows.
+///
+/// # Examples
+///
+/// ```no_run
+/// use convo::foreign::spreadsheet;
+/// let tree = spreadsheet::import("dialogue.xlsx").unwrap();
+/// ```
+pub fn import<P>(path: P) -> Result<Tree, ImportError>
+where
+    P: AsRef<Path>,
+{
+    let mut workbook = open_workbook_auto(path)?;
+    let sheet = workbook
+        .sheet_names()
+        .first()
+        .cloned()
+        .ok_or_else(|| TreeError::Validation("Spreadsheet has no sheets".into()))?;
+    import_sheet_of(&mut workbook, &sheet)
+}
+
+/// Try to return a [`Tree`] which is generated from importing a named sheet of a spreadsheet.
+///
+/// # Arguments
+///
+/// * `path` - A path type that references a spreadsheet to parse from.
+/// * `sheet` - A string slice that holds the name of the sheet.
+///
+/// # Errors
+///
+/// * An [`ImportError`] will be returned if the spreadsheet cannot be read, has no such sheet, or holds malformed rows.
+///
+/// # Examples
+///
+/// ```no_run
+/// use convo::foreign::spreadsheet;
+/// let tree = spreadsheet::import_sheet("dialogue.ods", "Act 1").unwrap();
+/// ```
+pub fn import_sheet<P>(path: P, sheet: &str) -> Result<Tree, ImportError>
+where
+    P: AsRef<Path>,
+{
+    let mut workbook = open_workbook_auto(path)?;
+    import_sheet_of(&mut workbook, sheet)
+}
+
+fn import_sheet_of<R>(workbook: &mut R, sheet: &str) -> Result<Tree, ImportError>
+where
+    R: Reader<std::io::BufReader<std::fs::File>>,
+    ImportError: From<R::Error>,
+{
+    let range = workbook.worksheet_range(sheet)?;
+    let rows = range
+        .rows()
+        .map(|row| row.iter().map(ToString::to_string).collect::<Vec<_>>());
+    rows_to_tree(rows).map_err(|error| match error {
+        ImportError::Validation(TreeError::Validation(message)) => {
+            TreeError::Validation(format!("Sheet `{}`: {}", sheet, message)).into()
+        }
+        error => error,
+    })
+}
+
+/// Try to return a [`Tree`] which is generated from the rows of a sheet, as text cells. The first row is the header.
+///
+/// # Arguments
+///
+/// * `rows` - An iterator over the rows of a sheet.
+///
+/// # Errors
+///
+/// * An [`ImportError`] will be returned if the header lacks a `text` column, or a row is malformed. Errors name the row, counting the header as row 1.
+///
+/// # Examples
+///
+/// ```
+/// use convo::foreign::spreadsheet;
+/// let rows = vec![
+///     vec!["Key", "Speaker", "Text", "Links"],
+///     vec!["start", "Smith", "Need a blade?", "end: No thanks."],
+///     vec!["end", "Smith", "Suit yourself.", ""],
+/// ];
+/// let tree = spreadsheet::rows_to_tree(rows).unwrap();
+/// assert_eq!("Smith: Need a blade?", tree.root_node().unwrap().dialogue);
+/// ```
+pub fn rows_to_tree<I, R, C>(rows: I) -> Result<Tree, ImportError>
+where
+    I: IntoIterator<Item = R>,
+    R: IntoIterator<Item = C>,
+    C: AsRef<str>,
+{
+    let mut rows = rows.into_iter().map(|row| {
+        row.into_iter()
+            .map(|cell| cell.as_ref().trim().to_owned())
+            .collect::<Vec<_>>()
+    });
+
+    // Find the columns by header
+    let header = rows
+        .next()
+        .ok_or_else(|| TreeError::Validation("Spreadsheet has no header row".into()))?;
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|title| title.eq_ignore_ascii_case(name))
+    };
+    let required = |name: &str| {
+        column(name).ok_or_else(|| {
+            TreeError::Validation(format!("Spreadsheet header has no `{}` column", name))
+        })
+    };
+    let text_column = required("text")?;
+    let (key_column, speaker_column, links_column) =
+        (column("key"), column("speaker"), column("links"));
+
+    let mut tree = Tree::new();
+    let mut root_key = None;
+    let mut current: Option<Draft> = None;
+    for (i, row) in rows.enumerate() {
+        let number = i + 2;
+        let cell = |column: Option<usize>| {
+            column
+                .and_then(|column| row.get(column))
+                .map(String::as_str)
+                .unwrap_or_default()
+        };
+        if row.iter().all(String::is_empty) {
+            continue;
+        }
+        let malformed =
+            |message: String| TreeError::Validation(format!("Row {}: {}", number, message));
+
+        // A new key starts a new node, and an empty or repeated key continues the node above, if there is one
+        let generated;
+        let mut key = cell(key_column);
+        if key.is_empty() && current.is_none() {
+            generated = keygen::slugify(cell(Some(text_column)));
+            key = &generated;
+        }
+        if !key.is_empty() && current.as_ref().is_none_or(|draft| draft.key != key) {
+            if let Some(draft) = current.take() {
+                tree.insert_node(draft.into_node()?);
+            }
+            if let Some(existing) = tree.node(key) {
+                return Err(
+                    malformed(format!("node `{}` is already defined", existing.key)).into(),
+                );
+            }
+            root_key.get_or_insert_with(|| key.to_owned());
+            current = Some(Draft {
+                key: key.to_owned(),
+                lines: Vec::new(),
+                links: Vec::new(),
+            });
+        }
+        let draft = current.as_mut().expect("the first line starts a node");
+
+        let text = cell(Some(text_column));
+        if text.is_empty() {
+            return Err(malformed(format!("line of `{}` has no text", draft.key)).into());
+        }
+        draft
+            .lines
+            .push((number, cell(speaker_column).to_owned(), text.to_owned()));
+
+        for entry in cell(links_column).split(['\n', '|']) {
+            let entry = entry.trim();
+            if entry.is_empty() {
+                continue;
+            }
+            let (to_key, dialogue) = entry.split_once(':').ok_or_else(|| {
+                malformed(format!(
+                    "link `{}` of `{}` is not written as `target: choice text`",
+                    entry, draft.key
+                ))
+            })?;
+            let (to_key, dialogue) = (to_key.trim(), dialogue.trim());
+            if to_key.is_empty() || dialogue.is_empty() {
+                return Err(malformed(format!(
+                    "link `{}` of `{}` needs both a target and choice text",
+                    entry, draft.key
+                ))
+                .into());
+            }
+            draft.links.push(Link::new(to_key, dialogue));
+        }
+    }
+    if let Some(draft) = current.take() {
+        tree.insert_node(draft.into_node()?);
+    }
+
+    let root_key =
+        root_key.ok_or_else(|| TreeError::Validation("Spreadsheet has no dialogue rows".into()))?;
+    tree.set_root_key(&root_key)?;
+    Ok(tree)
+}
+
+// A node being read, whose lines are numbered by row, and which has at least one line
+struct Draft {
+    key: String,
+    lines: Vec<(usize, String, String)>,
+    links: Vec<Link>,
+}
+
+impl Draft {
+    fn into_node(self) -> Result<Node, TreeError> {
+        let has_speakers = self.lines.iter().any(|(_, speaker, _)| !speaker.is_empty());
+        let mut node = Node::new(self.key.as_str(), "");
+        if has_speakers {
+            let mut lines = Vec::new();
+            for (number, speaker, text) in self.lines {
+                if speaker.is_empty() {
+                    return Err(TreeError::Validation(format!(
+                        "Row {}: line of `{}` has no speaker, but other lines do",
+                        number, self.key
+                    )));
+                }
+                lines.push(Line::new(speaker, text));
+            }
+            node.set_lines(lines);
+        } else {
+            let texts: Vec<String> = self.lines.into_iter().map(|(_, _, text)| text).collect();
+            node.dialogue = texts.join("\n");
+        }
+        node.links = self.links;
+        Ok(node)
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_rows_to_tree() {
+    use crate::error::ImportError::Validation;
+
+    let rows = vec![
+        vec!["Notes", "KEY", "Text", "Speaker", "Links"],
+        vec![
+            "",
+            "start",
+            "Need a blade?",
+            "Smith",
+            "buy: Yes. | end: No.",
+        ],
+        vec!["draft", "", "They're sharp.", "Smith", "end: Maybe later."],
+        vec!["", "", "", "", ""],
+        vec!["", "buy", "Here you go.", "", ""],
+        vec!["", "buy", "Mind the edge.", "", ""],
+        vec!["", "end", "Suit yourself.", "", "start: Wait!\nbuy: Fine."],
+    ];
+    let tree = rows_to_tree(rows).unwrap();
+    assert_eq!("start", tree.root_key().unwrap());
+    let start = tree.node("start").unwrap();
+    assert_eq!(
+        vec![
+            Line::new("Smith", "Need a blade?"),
+            Line::new("Smith", "They're sharp.")
+        ],
+        start.lines
+    );
+    assert_eq!(
+        vec![
+            Link::new("buy", "Yes."),
+            Link::new("end", "No."),
+            Link::new("end", "Maybe later.")
+        ],
+        start.links
+    );
+    assert_eq!(
+        "Here you go.\nMind the edge.",
+        tree.node("buy").unwrap().dialogue
+    );
+    assert_eq!(2, tree.node("end").unwrap().links.len());
+
+    // Without a key, the first node is keyed from its text
+    let rows = vec![
+        vec!["Text", "Links"],
+        vec!["Halt! Who goes there?", "end: A friend."],
+        vec!["Speak up.", ""],
+    ];
+    let tree = rows_to_tree(rows).unwrap();
+    assert_eq!("halt_who_goes_there", tree.root_key().unwrap());
+    assert_eq!(
+        "Halt! Who goes there?\nSpeak up.",
+        tree.root_node().unwrap().dialogue
+    );
+
+    // Malformed sheets and rows are reported with their row number
+    let error = |rows: Vec<Vec<&str>>| match rows_to_tree(rows).unwrap_err() {
+        Validation(TreeError::Validation(message)) => message,
+        error => panic!("{:?}", error),
+    };
+    assert_eq!(
+        "Spreadsheet header has no `text` column",
+        error(vec![vec!["key", "line"]])
+    );
+    assert_eq!(
+        "Spreadsheet has no dialogue rows",
+        error(vec![vec!["key", "text"]])
+    );
+    for (row, rows) in [
+        (2, vec![vec!["key", "text", "notes"], vec!["", "", "draft"]]),
+        (
+            3,
+            vec![vec!["key", "text"], vec!["a", "Hi."], vec!["b", ""]],
+        ),
+        (
+            4,
+            vec![
+                vec!["key", "text"],
+                vec!["a", "Hi."],
+                vec!["b", "Hi."],
+                vec!["a", "Hi."],
+            ],
+        ),
+        (2, vec![vec!["key", "text", "links"], vec!["a", "Hi.", "b"]]),
+        (
+            2,
+            vec![vec!["key", "text", "links"], vec!["a", "Hi.", "b: "]],
+        ),
+        (
+            3,
+            vec![
+                vec!["key", "text", "speaker"],
+                vec!["a", "Hi.", "Smith"],
+                vec!["", "Hi.", ""],
+            ],
+        ),
+    ] {
+        let message = error(rows);
+        assert!(message.starts_with(&format!("Row {}:", row)), "{}", message);
+    }
+}
@@ -0,0 +1,367 @@
+//! Export trees as flat interchange JSON, laid out for `ScriptableObject`-based Unity dialogue assets.
+//!
+//! Unity's `JsonUtility` reads fixed fields of arrays and objects, without maps or nulls, so the layout is flat: nodes and edges are arrays of objects which refer to nodes by integer id, and absent values are empty strings or arrays. Node ids are the order of the tree's nodes, starting at 0.
+//!
+//! # Schema
+//!
+//! The top-level object holds:
+//!
+//! | Field | Type | Description |
+//! |---|---|---|
+//! | `schema` | *string* | The schema version, [`SCHEMA_VERSION`]. |
+//! | `root` | *int* | The id of the root node. |
+//! | `title` | *string* | The title of the tree, or empty. |
+//! | `variables` | *array* of variables | The declared variables of the tree. |
+//! | `nodes` | *array* of nodes | Every node, ordered by id. |
+//! | `edges` | *array* of edges | Every link, ordered by source node, then by link order. |
+//!
+//! A variable holds `name` (*string*), `type` (*string*, one of `bool`, `int`, `string`, or `enum`), and `variants` (*array* of *strings*, the values of an `enum`).
+//!
+//! A node holds `id` (*int*), `key` (*string*), `dialogue` (*string*), `lines` (*array* of objects with `speaker` and `text` *strings*), `tags` (*array* of *strings*), `kind` (*string*, one of `choice`, `random`, `switch`, or `menu`), `source` (*string*, the source of a menu node's entries, see [`crate::menu`], or empty), `stage` (*array* of stage directions, see [`crate::stage`]), and `end` (*bool*, whether the node is an intentional ending).
+//!
+//! An edge holds:
+//!
+//! | Field | Type | Description |
+//! |---|---|---|
+//! | `id` | *int* | The order of the edge among all edges. |
+//! | `from` | *int* | The id of the node the link belongs to. |
+//! | `to` | *int* | The id of the node the link leads to. |
+//! | `index` | *int* | The order of the link among the links of its node. |
+//! | `text` | *string* | The dialogue of the link. |
+//! | `priority` | *int* | The priority of the link, or `0`. |
+//! | `weight` | *int* | The weight of the link for random nodes, or `1`. |
+//! | `group` | *string* | The group heading of the link, or empty. |
+//! | `condition` | *string* | The condition source, or empty. |
+//! | `clauses` | *array* of clauses | The clauses of the condition, which must all hold. |
+//! | `set` | *array* of assignments | The variables assigned when the link is followed. |
+//! | `affinity` | *array* of adjustments | The affinities adjusted when the link is followed, after its variables are assigned. |
+//! | `disabled` | *string* | The reason shown when the link is presented disabled, or empty when it is hidden. |
+//!
+//! A clause holds `name` (*string*), `op` (*string*, one of `==`, `!=`, `<`, `<=`, `>`, `>=`, or `!` for a negated flag, empty for a flag, and `in` for a range), `type` (*string*, `bool`, `int`, or `string`), and `value` (*string*, the value as text, which for flags is the value the variable must have, and for ranges is `start..end`, where the end is excluded and a start greater than the end wraps around). An assignment holds `name`, `type`, and `value` likewise. An adjustment holds `character` (*string*, the character id) and `amount` (*int*), which is added to the `int` variable `affinity.` followed by the character id, see [`crate::affinity`]. A stage direction holds `command` (*string*) and `args` (*array* of arguments, each holding `type` and `value` like an assignment).
+
+use crate::{
+    condition::Clause,
+    error::{ExportError, TreeError},
+    link::WhenUnmet,
+    node::NodeKind,
+    tree::Tree,
+    value::{Type, Value},
+};
+
+use serde_json::{json, Value as Json};
+use std::{collections::HashMap, fs, path::Path};
+
+/// The version of the interchange schema, written to the `schema` field.
+pub const SCHEMA_VERSION: &str = "convo-unity/1";
+
+/// Try to save a [`Tree`] as an interchange JSON file.
+///
+/// # Arguments
+///
+/// * `tree` - A [`Tree`] that will be saved in a file.
+/// * `path` - A path type that references the file to write.
+///
+/// # Errors
+///
+/// * An [`ExportError`] will be returned if the tree has no root, links to missing nodes, or incurs issues saving.
+///
+/// # Examples
+///
+/// ```no_run
+/// use convo::{foreign::unity, importer};
+/// let tree = importer::import("examples/dialogue_files/ex_min.convo.yml").unwrap();
+/// unity::export(&tree, "Assets/Dialogue/ex_min.json").unwrap();
+/// ```
+pub fn export<P>(tree: &Tree, path: P) -> Result<(), ExportError>
+where
+    P: AsRef<Path>,
+{
+    fs::write(path, tree_to_json(tree)?)?;
+    Ok(())
+}
+
+/// Try to return interchange JSON which is generated from a [`Tree`].
+///
+/// # Arguments
+///
+/// * `tree` - A [`Tree`] that will be returned as JSON.
+///
+/// # Errors
+///
+/// * An [`ExportError`] will be returned if the tree has no root or links to missing nodes.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{foreign::unity, Link, Node, Tree};
+/// let mut tree = Tree::new();
+/// let mut start = Node::new("start", "Hello!");
+/// let end = Node::new("end", "Bye!");
+/// Link::link(&mut start, &end, "Bye.");
+/// tree.insert_node(start);
+/// tree.insert_node(end);
+/// tree.set_root_key("start").unwrap();
+/// let json = unity::tree_to_json(&tree).unwrap();
+/// assert!(json.contains(r#""from": 0,"#));
+/// assert!(json.contains(r#""to": 1,"#));
+/// ```
+pub fn tree_to_json(tree: &Tree) -> Result<String, ExportError> {
+    let ids: HashMap<&str, usize> = tree
+        .nodes()
+        .enumerate()
+        .map(|(id, node)| (node.key.as_str(), id))
+        .collect();
+    let root = tree
+        .root_key()
+        .and_then(|key| ids.get(key.as_str()))
+        .ok_or(TreeError::RootNotSet())?;
+
+    let variables: Vec<Json> = tree
+        .variables()
+        .iter()
+        .map(|(name, declared)| {
+            let (kind, variants) = match declared {
+                Type::Enum(variants) => ("enum".to_owned(), variants.clone()),
+                declared => (declared.to_string(), Vec::new()),
+            };
+            json!({ "name": name, "type": kind, "variants": variants })
+        })
+        .collect();
+
+    let mut nodes = Vec::new();
+    let mut edges = Vec::new();
+    for (id, node) in tree.nodes().enumerate() {
+        let (kind, source) = match &node.kind {
+            NodeKind::Choice => ("choice", ""),
+            NodeKind::Random { .. } => ("random", ""),
+            NodeKind::Switch { .. } => ("switch", ""),
+            NodeKind::Menu { source } => ("menu", source.as_str()),
+        };
+        let lines: Vec<Json> = node
+            .lines
+            .iter()
+            .map(|line| json!({ "speaker": line.speaker, "text": line.text }))
+            .collect();
+        let stage: Vec<Json> = node
+            .stage
+            .iter()
+            .map(|direction| {
+                let args: Vec<Json> = direction
+                    .args
+                    .iter()
+                    .map(|arg| {
+                        let (kind, value) = value_to_json(arg);
+                        json!({ "type": kind, "value": value })
+                    })
+                    .collect();
+                json!({ "command": direction.command, "args": args })
+            })
+            .collect();
+        nodes.push(json!({
+            "id": id,
+            "key": node.key,
+            "dialogue": node.dialogue,
+            "lines": lines,
+            "tags": node.tags,
+            "kind": kind,
+            "source": source,
+            "stage": stage,
+            "end": node.end,
+        }));
+
+        for (index, link) in node.links.iter().enumerate() {
+            let to = ids.get(link.to_key.as_str()).ok_or_else(|| {
+                TreeError::Validation(format!(
+                    "Link {} of `{}` leads to missing node `{}`",
+                    index, node.key, link.to_key
+                ))
+            })?;
+            let clauses: Vec<Json> = link
+                .condition
+                .iter()
+                .flat_map(|condition| &condition.clauses)
+                .map(|clause| match clause {
+                    Clause::Flag { name, negated } => json!({
+                        "name": name,
+                        "op": if *negated { "!" } else { "" },
+                        "type": "bool",
+                        "value": (!negated).to_string(),
+                    }),
+                    Clause::Compare { name, op, value } => {
+                        let (kind, value) = value_to_json(value);
+                        json!({ "name": name, "op": op.as_str(), "type": kind, "value": value })
+                    }
+                    Clause::Within { name, start, end } => json!({
+                        "name": name,
+                        "op": "in",
+                        "type": "int",
+                        "value": format!("{}..{}", start, end),
+                    }),
+                })
+                .collect();
+            let set: Vec<Json> = link
+                .set
+                .iter()
+                .map(|(name, value)| {
+                    let (kind, value) = value_to_json(value);
+                    json!({ "name": name, "type": kind, "value": value })
+                })
+                .collect();
+            let affinity: Vec<Json> = link
+                .affinity
+                .iter()
+                .map(|(character, amount)| json!({ "character": character, "amount": amount }))
+                .collect();
+            edges.push(json!({
+                "id": edges.len(),
+                "from": id,
+                "to": to,
+                "index": index,
+                "text": link.text(),
+                "priority": link.priority.unwrap_or_default(),
+                "weight": link.weight.unwrap_or(1),
+                "group": link.group.clone().unwrap_or_default(),
+                "condition": link.condition.as_ref().map(ToString::to_string).unwrap_or_default(),
+                "clauses": clauses,
+                "set": set,
+                "affinity": affinity,
+                "disabled": match &link.when_unmet {
+                    WhenUnmet::Hidden => "",
+                    WhenUnmet::Disabled(reason) => reason.as_str(),
+                },
+            }));
+        }
+    }
+
+    let document = json!({
+        "schema": SCHEMA_VERSION,
+        "root": root,
+        "title": tree.meta().title.clone().unwrap_or_default(),
+        "variables": variables,
+        "nodes": nodes,
+        "edges": edges,
+    });
+    Ok(serde_json::to_string_pretty(&document).expect("JSON values always serialize"))
+}
+
+// Returns the type and text of a value
+fn value_to_json(value: &Value) -> (String, String) {
+    let text = match value {
+        Value::Bool(value) => value.to_string(),
+        Value::Int(value) => value.to_string(),
+        Value::String(value) => value.to_owned(),
+    };
+    (Type::of(value).to_string(), text)
+}
+
+#[cfg(test)]
+#[test]
+fn test_tree_to_json() {
+    use crate::importer;
+
+    let source = r#"---
+    root: start
+    variables:
+        gold: int
+        mood: [calm, angry]
+    meta:
+        title: Forge
+    nodes:
+        end:
+            dialogue:
+                - speaker: Smith
+                  text: "Bye."
+        start:
+            dialogue: "Need a blade?"
+            tags: [shop]
+            stage:
+                - camera: [close_up, smith]
+            links:
+                - to: end
+                  dialogue: "Yes."
+                  if: gold >= 10 && !broke
+                  disabled: "Not enough gold"
+                  set: { gold: 0, mood: calm }
+                  affinity: { smith: 2 }
+                - group: "Ask about..."
+                  links:
+                      - end: "The forge."
+    "#;
+    let tree = importer::source_to_tree(source).unwrap();
+    let json: Json = serde_json::from_str(&tree_to_json(&tree).unwrap()).unwrap();
+    assert_eq!(SCHEMA_VERSION, json["schema"]);
+    assert_eq!(1, json["root"]);
+    assert_eq!("Forge", json["title"]);
+    assert_eq!(
+        json!([
+            { "name": "gold", "type": "int", "variants": [] },
+            { "name": "mood", "type": "enum", "variants": ["calm", "angry"] },
+        ]),
+        json["variables"]
+    );
+
+    // Nodes are ordered by id, and edges refer to them by id
+    assert_eq!(
+        json!({
+            "id": 0,
+            "key": "end",
+            "dialogue": "Smith: Bye.",
+            "lines": [{ "speaker": "Smith", "text": "Bye." }],
+            "tags": [],
+            "kind": "choice",
+            "source": "",
+            "stage": [],
+            "end": false,
+        }),
+        json["nodes"][0]
+    );
+    assert_eq!(json!(["shop"]), json["nodes"][1]["tags"]);
+    assert_eq!(
+        json!([{
+            "command": "camera",
+            "args": [
+                { "type": "string", "value": "close_up" },
+                { "type": "string", "value": "smith" },
+            ],
+        }]),
+        json["nodes"][1]["stage"]
+    );
+    assert_eq!(
+        json!({
+            "id": 0,
+            "from": 1,
+            "to": 0,
+            "index": 0,
+            "text": "Yes.",
+            "priority": 0,
+            "weight": 1,
+            "group": "",
+            "condition": "gold >= 10 && !broke",
+            "clauses": [
+                { "name": "gold", "op": ">=", "type": "int", "value": "10" },
+                { "name": "broke", "op": "!", "type": "bool", "value": "false" },
+            ],
+            "set": [
+                { "name": "gold", "type": "int", "value": "0" },
+                { "name": "mood", "type": "string", "value": "calm" },
+            ],
+            "affinity": [{ "character": "smith", "amount": 2 }],
+            "disabled": "Not enough gold",
+        }),
+        json["edges"][0]
+    );
+    assert_eq!("Ask about...", json["edges"][1]["group"]);
+    assert_eq!("", json["edges"][1]["condition"]);
+
+    // Trees must have a root, and links must lead to existing nodes
+    let mut tree = tree.clone();
+    tree.node_mut("start").unwrap().links[1].to_key = "missing".into();
+    assert!(matches!(
+        tree_to_json(&tree).unwrap_err(),
+        ExportError::Validation(_)
+    ));
+    assert!(matches!(
+        tree_to_json(&Tree::new()).unwrap_err(),
+        ExportError::Validation(TreeError::RootNotSet())
+    ));
+}
@@ -0,0 +1,477 @@
+//! A family of functions which draw a [`Tree`] as a graph of its nodes and links, as [Graphviz](https://graphviz.org) DOT or as SVG, e.g. so writers can review the shape of a conversation.
+//!
+//! SVG is laid out without Graphviz. Nodes are drawn in layers by their distance from the root node and entry points, top to bottom, and ordered within each layer to keep links short. Links which lead back up are drawn curving around the right of the graph.
+
+use alloc::{
+    collections::VecDeque,
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+
+use crate::{node::Node, tree::Tree};
+
+// Dimensions of the SVG layout, in pixels
+const CHAR_WIDTH: i64 = 7;
+const NODE_HEIGHT: i64 = 40;
+const NODE_PADDING: i64 = 12;
+const NODE_GAP: i64 = 40;
+const LAYER_GAP: i64 = 70;
+const MARGIN: i64 = 20;
+const BACK_LINK_OFFSET: i64 = 40;
+
+// The number of characters of dialogue shown for nodes and links
+const NODE_DIALOGUE_LENGTH: usize = 32;
+const LINK_DIALOGUE_LENGTH: usize = 24;
+
+/// Returns the [Graphviz](https://graphviz.org) DOT source of a [`Tree`], with a box for each node labelled by its key and the start of its dialogue, and an arrow for each link labelled by the start of its dialogue. The root node is drawn in bold, and endings with a double border. Links to nodes which do not exist, such as jumps to other trees, are not drawn.
+///
+/// # Arguments
+///
+/// * `tree` - A [`Tree`] to draw.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{graph, Link, Node, Tree};
+/// let mut tree = Tree::new();
+/// let mut start = Node::new("start", "Hello!");
+/// let mut end = Node::new("end", "Goodbye!");
+/// end.end = true;
+/// Link::link(&mut start, &end, "Bye!");
+/// tree.insert_node(end);
+/// tree.set_root_node(start).unwrap();
+/// let dot = graph::to_dot(&tree);
+/// assert!(dot.starts_with("digraph {"));
+/// assert!(dot.contains(r#""start" -> "end" [label="Bye!"];"#));
+/// ```
+pub fn to_dot(tree: &Tree) -> String {
+    let mut dot = String::from("digraph {\n");
+    dot.push_str("    node [shape=box, fontname=\"sans-serif\"];\n");
+    dot.push_str("    edge [fontname=\"sans-serif\", fontsize=10];\n");
+    for node in tree.nodes() {
+        let mut attributes = vec![format!(
+            "label=\"{}\\n{}\"",
+            dot_escape(&node.key),
+            dot_escape(&snippet(&node.dialogue, NODE_DIALOGUE_LENGTH))
+        )];
+        if tree.root_key() == Some(&node.key) {
+            attributes.push("penwidth=2".to_string());
+        }
+        if is_ending(node) {
+            attributes.push("peripheries=2".to_string());
+        }
+        dot.push_str(&format!(
+            "    \"{}\" [{}];\n",
+            dot_escape(&node.key),
+            attributes.join(", ")
+        ));
+    }
+    for node in tree.nodes() {
+        for link in node.links.iter() {
+            if !tree.contains_node(&link.to_key) {
+                continue;
+            }
+            dot.push_str(&format!(
+                "    \"{}\" -> \"{}\" [label=\"{}\"];\n",
+                dot_escape(&node.key),
+                dot_escape(&link.to_key),
+                dot_escape(&snippet(link.text(), LINK_DIALOGUE_LENGTH))
+            ));
+        }
+    }
+    dot.push('}');
+    dot
+}
+
+/// Returns a standalone SVG image of a [`Tree`], laid out without Graphviz, with a box for each node labelled by its key and the start of its dialogue, and an arrow for each link labelled by the start of its dialogue. Hovering a node or link shows its full dialogue. The root node is drawn in bold, and endings with a double border. Links to nodes which do not exist, such as jumps to other trees, are not drawn.
+///
+/// # Arguments
+///
+/// * `tree` - A [`Tree`] to draw.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{graph, Link, Node, Tree};
+/// let mut tree = Tree::new();
+/// let mut start = Node::new("start", "Hello!");
+/// let mut end = Node::new("end", "Goodbye!");
+/// end.end = true;
+/// Link::link(&mut start, &end, "Bye!");
+/// tree.insert_node(end);
+/// tree.set_root_node(start).unwrap();
+/// let svg = graph::to_svg(&tree);
+/// assert!(svg.starts_with("<svg"));
+///
+/// // A group for each node and link
+/// assert_eq!(3, svg.matches("<g>").count());
+/// ```
+pub fn to_svg(tree: &Tree) -> String {
+    let layout = Layout::new(tree);
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\" font-family=\"sans-serif\" font-size=\"12\">\n",
+        layout.width, layout.height
+    );
+    svg.push_str("  <defs>\n    <marker id=\"arrow\" viewBox=\"0 0 10 10\" refX=\"10\" refY=\"5\" markerWidth=\"8\" markerHeight=\"8\" orient=\"auto\">\n      <path d=\"M 0 0 L 10 5 L 0 10 z\" fill=\"#555\"/>\n    </marker>\n  </defs>\n");
+    svg.push_str("  <rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n");
+
+    // Links are drawn first, so nodes are drawn over them
+    for (i, node) in tree.nodes().enumerate() {
+        for link in node.links.iter() {
+            let j = match tree.nodes.get_index_of(&link.to_key) {
+                Some(j) => j,
+                None => continue,
+            };
+            let (path, label_x, label_y) = layout.link_path(i, j);
+            svg.push_str(&format!(
+                "  <g>\n    <title>{}</title>\n    <path d=\"{}\" fill=\"none\" stroke=\"#555\" marker-end=\"url(#arrow)\"/>\n",
+                xml_escape(link.text()),
+                path
+            ));
+            svg.push_str(&format!(
+                "    <text x=\"{}\" y=\"{}\" text-anchor=\"middle\" font-size=\"10\" fill=\"#333\" stroke=\"white\" stroke-width=\"3\" paint-order=\"stroke\">{}</text>\n  </g>\n",
+                label_x,
+                label_y,
+                xml_escape(&snippet(link.text(), LINK_DIALOGUE_LENGTH))
+            ));
+        }
+    }
+
+    for (i, node) in tree.nodes().enumerate() {
+        let (x, y, width) = (layout.x[i], layout.y[i], layout.widths[i]);
+        let stroke_width = match tree.root_key() == Some(&node.key) {
+            true => 3,
+            false => 1,
+        };
+        svg.push_str(&format!(
+            "  <g>\n    <title>{}</title>\n    <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"4\" fill=\"#f4f4f8\" stroke=\"#333\" stroke-width=\"{}\"/>\n",
+            xml_escape(&node.dialogue),
+            x,
+            y,
+            width,
+            NODE_HEIGHT,
+            stroke_width
+        ));
+        if is_ending(node) {
+            svg.push_str(&format!(
+                "    <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"2\" fill=\"none\" stroke=\"#333\"/>\n",
+                x + 3,
+                y + 3,
+                width - 6,
+                NODE_HEIGHT - 6
+            ));
+        }
+        svg.push_str(&format!(
+            "    <text x=\"{0}\" y=\"{1}\" text-anchor=\"middle\" font-weight=\"bold\">{2}</text>\n    <text x=\"{0}\" y=\"{3}\" text-anchor=\"middle\" font-size=\"10\" fill=\"#444\">{4}</text>\n  </g>\n",
+            x + width / 2,
+            y + 16,
+            xml_escape(&node.key),
+            y + 31,
+            xml_escape(&snippet(&node.dialogue, NODE_DIALOGUE_LENGTH))
+        ));
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// The positions of the nodes of a tree, by the index of the node.
+struct Layout {
+    layers: Vec<usize>,
+    x: Vec<i64>,
+    y: Vec<i64>,
+    widths: Vec<i64>,
+    right: i64,
+    width: i64,
+    height: i64,
+}
+
+impl Layout {
+    fn new(tree: &Tree) -> Self {
+        let index = tree.build_index();
+        let len = tree.len();
+
+        // Layers are distances from the root node and entry points, then from each node left unvisited, in storage order
+        let mut layers = vec![usize::MAX; len];
+        let starts: Vec<usize> = tree
+            .root_key()
+            .into_iter()
+            .chain(tree.entries().values())
+            .filter_map(|key| tree.nodes.get_index_of(key))
+            .chain(0..len)
+            .collect();
+        let mut order = Vec::with_capacity(len);
+        for start in starts {
+            if layers[start] != usize::MAX {
+                continue;
+            }
+            layers[start] = 0;
+            let mut queue = VecDeque::from(vec![start]);
+            while let Some(i) = queue.pop_front() {
+                order.push(i);
+                for &j in &index.children()[i] {
+                    if layers[j] == usize::MAX {
+                        layers[j] = layers[i] + 1;
+                        queue.push_back(j);
+                    }
+                }
+            }
+        }
+        let layer_count = layers.iter().map(|layer| layer + 1).max().unwrap_or(0);
+        let mut rows: Vec<Vec<usize>> = vec![vec![]; layer_count];
+        for i in order {
+            rows[layers[i]].push(i);
+        }
+
+        // Nodes are ordered by the average position of their parents in earlier layers, to shorten links
+        let mut rank = vec![0.0; len];
+        for row in rows.iter() {
+            for (position, &i) in row.iter().enumerate() {
+                rank[i] = position as f64;
+            }
+        }
+        for row in rows.iter_mut().skip(1) {
+            let barycenter = |i: usize| {
+                let parents: Vec<f64> = index.parents()[i]
+                    .iter()
+                    .filter(|&&j| layers[j] < layers[i])
+                    .map(|&j| rank[j])
+                    .collect();
+                match parents.is_empty() {
+                    true => rank[i],
+                    false => parents.iter().sum::<f64>() / parents.len() as f64,
+                }
+            };
+            let mut keyed: Vec<(f64, usize)> = row.iter().map(|&i| (barycenter(i), i)).collect();
+            keyed.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(core::cmp::Ordering::Equal));
+            *row = keyed.into_iter().map(|(_, i)| i).collect();
+            for (position, &i) in row.iter().enumerate() {
+                rank[i] = position as f64;
+            }
+        }
+
+        // Nodes are as wide as their labels, and each layer is centered
+        let widths: Vec<i64> = tree
+            .nodes()
+            .map(|node| {
+                let characters = node.key.chars().count().max(
+                    snippet(&node.dialogue, NODE_DIALOGUE_LENGTH)
+                        .chars()
+                        .count(),
+                );
+                characters as i64 * CHAR_WIDTH + 2 * NODE_PADDING
+            })
+            .collect();
+        let row_width =
+            |row: &[usize]| row.iter().map(|&i| widths[i] + NODE_GAP).sum::<i64>() - NODE_GAP;
+        let content_width = rows.iter().map(|row| row_width(row)).max().unwrap_or(0);
+        let (mut x, mut y) = (vec![0; len], vec![0; len]);
+        for (layer, row) in rows.iter().enumerate() {
+            let mut left = MARGIN + (content_width - row_width(row)) / 2;
+            for &i in row {
+                x[i] = left;
+                y[i] = MARGIN + layer as i64 * (NODE_HEIGHT + LAYER_GAP);
+                left += widths[i] + NODE_GAP;
+            }
+        }
+
+        // Links which lead back up curve around the right of the graph, further out the more layers they span, and self links loop beside their node
+        let right = MARGIN + content_width;
+        let mut width = right + MARGIN;
+        for (i, node) in tree.nodes().enumerate() {
+            for j in node
+                .links
+                .iter()
+                .filter_map(|link| tree.nodes.get_index_of(&link.to_key))
+            {
+                let outside = match layers[j] < layers[i] {
+                    true => right + BACK_LINK_OFFSET + (layers[i] - layers[j]) as i64 * 8,
+                    false if i == j => x[i] + widths[i] + BACK_LINK_OFFSET,
+                    false => continue,
+                };
+                width = width.max(outside + MARGIN);
+            }
+        }
+        let height = layer_count as i64 * (NODE_HEIGHT + LAYER_GAP) - LAYER_GAP + 2 * MARGIN;
+
+        Layout {
+            layers,
+            x,
+            y,
+            widths,
+            right,
+            width: width.max(2 * MARGIN),
+            height: height.max(2 * MARGIN) + NODE_GAP,
+        }
+    }
+
+    // Returns the SVG path of a link between two nodes, and where to place its label
+    fn link_path(&self, from: usize, to: usize) -> (String, i64, i64) {
+        let (from_x, from_y, from_width) = (self.x[from], self.y[from], self.widths[from]);
+        let (to_x, to_y, to_width) = (self.x[to], self.y[to], self.widths[to]);
+
+        // Links down leave the bottom of a node for the top of the next
+        if self.layers[to] > self.layers[from] {
+            let (x1, y1) = (from_x + from_width / 2, from_y + NODE_HEIGHT);
+            let (x2, y2) = (to_x + to_width / 2, to_y);
+            let middle = (y1 + y2) / 2;
+            let path = format!(
+                "M {} {} C {} {} {} {} {} {}",
+                x1, y1, x1, middle, x2, middle, x2, y2
+            );
+            return (path, (x1 + x2) / 2, middle + 4);
+        }
+
+        // Self links loop beside their node
+        let (x1, y1) = (from_x + from_width, from_y + NODE_HEIGHT / 2);
+        if from == to {
+            let bend = x1 + BACK_LINK_OFFSET;
+            let path = format!(
+                "M {} {} C {} {} {} {} {} {}",
+                x1,
+                y1 - 8,
+                bend,
+                y1 - 30,
+                bend,
+                y1 + 30,
+                x1,
+                y1 + 8
+            );
+            return (path, bend, y1 + 4);
+        }
+
+        // Links across a layer arc beneath it, from the bottom of one node to the bottom of the other
+        if self.layers[to] == self.layers[from] {
+            let (x1, x2) = (from_x + from_width / 2, to_x + to_width / 2);
+            let y1 = from_y + NODE_HEIGHT;
+            let below = y1 + NODE_GAP;
+            let path = format!(
+                "M {} {} C {} {} {} {} {} {}",
+                x1, y1, x1, below, x2, below, x2, y1
+            );
+            return (path, (x1 + x2) / 2, below - 4);
+        }
+
+        // Links back up leave and enter the right of nodes, around the right of the graph
+        let (x2, y2) = (to_x + to_width, to_y + NODE_HEIGHT / 2);
+        let span = (self.layers[from] - self.layers[to]) as i64;
+        let bend = self.right + BACK_LINK_OFFSET + span * 8;
+        let path = format!(
+            "M {} {} C {} {} {} {} {} {}",
+            x1, y1, bend, y1, bend, y2, x2, y2
+        );
+        (path, bend - BACK_LINK_OFFSET / 4, (y1 + y2) / 2 + 4)
+    }
+}
+
+// Nodes are endings if they are marked as one, or have no links
+fn is_ending(node: &Node) -> bool {
+    node.end || node.links.is_empty()
+}
+
+// Returns the first line of some dialogue, shortened to a number of characters with an ellipsis
+fn snippet(dialogue: &str, length: usize) -> String {
+    let line = dialogue.lines().next().unwrap_or_default();
+    match line.chars().count() > length || dialogue.contains('\n') {
+        true => {
+            let mut snippet: String = line.chars().take(length.saturating_sub(1)).collect();
+            snippet.push('…');
+            snippet
+        }
+        false => line.to_string(),
+    }
+}
+
+fn dot_escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn xml_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c if c.is_control() && c != '\n' && c != '\t' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+#[test]
+fn test_to_dot() {
+    use crate::link::Link;
+
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "Say \"hello\" to the \\ guard.");
+    let mut end = Node::new("end", "A very long farewell which will not fit in a box.");
+    end.end = true;
+    Link::link(&mut start, &end, "Bye!");
+    start.links.push(Link::new("start", "Again?"));
+    start.links.push(Link::new("other#start", "Jump."));
+    tree.insert_node(end);
+    tree.set_root_node(start).unwrap();
+
+    let dot = to_dot(&tree);
+    assert!(dot.contains(r#""start" [label="start\nSay \"hello\" to the \\ guard.", penwidth=2];"#));
+    assert!(
+        dot.contains(r#""end" [label="end\nA very long farewell which will…", peripheries=2];"#)
+    );
+    assert!(dot.contains(r#""start" -> "end" [label="Bye!"];"#));
+    assert!(dot.contains(r#""start" -> "start" [label="Again?"];"#));
+
+    // Links to missing nodes are not drawn
+    assert_eq!(2, dot.matches(" -> ").count());
+    assert!(dot.ends_with('}'));
+}
+
+#[test]
+fn test_to_svg() {
+    use crate::link::Link;
+
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "<Hello> & welcome!");
+    let mut left = Node::new("left", "Left.");
+    let mut right = Node::new("right", "Right.");
+    let mut end = Node::new("end", "Goodbye!");
+    end.end = true;
+    Link::link(&mut start, &left, "Left.");
+    Link::link(&mut start, &right, "Right.");
+    Link::link(&mut left, &end, "Onward.");
+    Link::link(&mut right, &start, "Back.");
+    tree.insert_node(left);
+    tree.insert_node(right);
+    tree.insert_node(end);
+    tree.set_root_node(start).unwrap();
+    tree.insert_node(Node::new("orphan", "Hm."));
+
+    let svg = to_svg(&tree);
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+    assert!(svg.ends_with("</svg>\n"));
+    assert!(svg.contains("&lt;Hello&gt; &amp; welcome!"));
+    assert!(!svg.contains("<Hello>"));
+
+    // A box for each node, a double border for each ending, and a path for each link
+    assert_eq!(1 + 5 + 2, svg.matches("<rect").count());
+    assert_eq!(4 + 1, svg.matches("<path").count());
+
+    // Layers descend from the root node, and unreachable nodes start their own
+    let layout = Layout::new(&tree);
+    assert_eq!(vec![1, 1, 2, 0, 0], layout.layers);
+    assert!(layout.y[0] > layout.y[3]);
+    assert_eq!(layout.y[0], layout.y[1]);
+    assert!(layout.x[0] < layout.x[1]);
+
+    // Links back up curve around the right
+    let (path, _, _) = layout.link_path(1, 3);
+    assert!(path.starts_with(&format!("M {} ", layout.x[1] + layout.widths[1])));
+
+    // Empty trees are drawn as empty images
+    assert!(to_svg(&Tree::new()).contains("<rect width=\"100%\""));
+}
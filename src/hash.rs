@@ -0,0 +1,38 @@
+use core::hash::Hasher;
+
+/// A [`FnvHasher`] implements the 64-bit [FNV-1a](http://www.isthe.com/chongo/tech/comp/fnv/) hash, used to index nodes when `std`'s randomized hasher is unavailable.
+#[derive(Debug, Clone, Copy)]
+pub struct FnvHasher(u64);
+
+impl Default for FnvHasher {
+    fn default() -> Self {
+        FnvHasher(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for FnvHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_fnv_hasher() {
+    // Known FNV-1a test vectors
+    let hash = |bytes: &[u8]| {
+        let mut hasher = FnvHasher::default();
+        hasher.write(bytes);
+        hasher.finish()
+    };
+    assert_eq!(0xcbf2_9ce4_8422_2325, hash(b""));
+    assert_eq!(0xaf63_dc4c_8601_ec8c, hash(b"a"));
+    assert_eq!(0x8594_4171_f739_67e8, hash(b"foobar"));
+}
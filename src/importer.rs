@@ -1,26 +1,105 @@
 //! A family of functions which parse YAML into [`Tree`]s.
 
 use crate::{
+    availability::Availability,
+    condition::{Clause, Condition, Op},
+    draft,
     error::{ImportError, TreeError},
-    link::Link,
-    node::Node,
+    inventory,
+    link::{Link, WhenUnmet},
+    lint::{self, Diagnostic, MaxLength, Rule},
+    meta::Meta,
+    node::{Line, Node, NodeKind},
+    pacing::Pacing,
+    stage::StageDirection,
     tree::Tree,
+    value::{Type, Value},
 };
 
-use std::{fs::File, io::Read, path::Path};
-use yaml_rust::{Yaml, YamlLoader};
+use std::{
+    collections::BTreeMap,
+    convert::TryFrom,
+    fs::File,
+    io::{self, BufRead, BufReader, Read},
+    path::Path,
+    time::{Duration, Instant},
+};
+use yaml_rust::{
+    parser::Parser,
+    scanner::{TScalarStyle, TokenType},
+    yaml, Event, Yaml, YamlLoader,
+};
+
+/// [`ImportOptions`] control how YAML is read into a [`Tree`].
+///
+/// # Examples
+///
+/// ```
+/// use convo::importer::{self, ImportOptions};
+/// let source = "---\nroot: cafe\u{301}\nnodes:\n  cafe\u{301}:\n    dialogue: Hi\n    end: true\n";
+/// let options = ImportOptions {
+///     normalize_keys: true,
+///     ..Default::default()
+/// };
+/// let tree = importer::source_to_tree_with(source, &options).unwrap();
+/// assert_eq!("caf\u{e9}", tree.root_key().unwrap());
+/// ```
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct ImportOptions {
+    /// Whether node keys, and the keys linked to, are normalized to Unicode Normalization Form C (NFC). See [`Tree::normalize_keys`].
+    pub normalize_keys: bool,
+    /// Whether the source is read in [draft mode][`crate::draft`], where links to missing nodes, placeholder dialogue, nodes without dialogue, and empty `links` arrays are allowed.
+    pub draft: bool,
+    /// The limits beyond which dialogue is reported as a warning by [`source_to_tree_with_warnings`] and [`import_with_warnings`]. By default, no dialogue is too long.
+    pub max_length: MaxLength,
+}
+
+/// [`ImportMetrics`] measure the work of an import, e.g. so asset pipelines can track which dialogue files slow down builds.
+///
+/// # Examples
+///
+/// ```
+/// use convo::importer::{self, ImportOptions};
+/// let (tree, metrics) =
+///     importer::import_with_metrics("examples/dialogue_files/ex_1.convo.yml", &ImportOptions::default())
+///         .unwrap();
+/// assert_eq!(tree.len(), metrics.nodes);
+/// assert!(metrics.total() >= metrics.parse);
+/// ```
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct ImportMetrics {
+    /// The size of the source, in bytes.
+    pub bytes: usize,
+    /// The number of nodes imported.
+    pub nodes: usize,
+    /// The time spent reading the file. Sources which are not read from a file take no time.
+    pub read: Duration,
+    /// The time spent parsing the YAML.
+    pub parse: Duration,
+    /// The time spent building the tree from the YAML.
+    pub build: Duration,
+    /// The time spent validating the tree, and applying the [`ImportOptions`].
+    pub validation: Duration,
+}
+
+impl ImportMetrics {
+    /// Returns the total time spent importing.
+    pub fn total(&self) -> Duration {
+        self.read + self.parse + self.build + self.validation
+    }
+}
 
 /// Try to returns a [`Tree`] which is generated from importing a file.
 ///
 /// # Arguments
 ///
 /// * `path` - A path type that references a file to parse from.
-/// See also: [example dialogue files](https://github.com/simbleau/convo/tree/main/examples/dialogue_files).
+///   See also: [example dialogue files](https://github.com/simbleau/convo/tree/main/examples/dialogue_files).
 ///
 /// # Errors
 ///
 /// * An [`ImportError`] will be returned if the source is not valid YAML data or if the tree is not considered legal when parsing.
-/// See also: [validation rules](https://github.com/simbleau/convo/blob/dev/FORMATTING.md#validation-rules).
+///   See also: [validation rules](https://github.com/simbleau/convo/blob/dev/FORMATTING.md#validation-rules).
 ///
 /// # Examples
 ///
@@ -28,6 +107,10 @@ use yaml_rust::{Yaml, YamlLoader};
 /// use convo::importer;
 /// let tree = importer::import("examples/dialogue_files/ex_min.convo.yml").unwrap();
 /// ```
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip_all, fields(path = ?path.as_ref()), err(Debug))
+)]
 pub fn import<P>(path: P) -> Result<Tree, ImportError>
 where
     P: AsRef<Path>,
@@ -39,17 +122,80 @@ where
     Ok(convo_tree)
 }
 
+/// Try to returns a [`Tree`] which is generated from importing a file, read with [`ImportOptions`].
+///
+/// # Arguments
+///
+/// * `path` - A path type that references a file to parse from.
+/// * `options` - The [`ImportOptions`] which control how the file is read.
+///
+/// # Errors
+///
+/// * An [`ImportError`] will be returned if the source is not valid YAML data or if the tree is not considered legal when parsing, including when node keys are identical once normalized.
+///   See also: [validation rules](https://github.com/simbleau/convo/blob/dev/FORMATTING.md#validation-rules).
+///
+/// # Examples
+///
+/// ```
+/// use convo::importer::{self, ImportOptions};
+/// let options = ImportOptions {
+///     normalize_keys: true,
+///     ..Default::default()
+/// };
+/// let tree = importer::import_with("examples/dialogue_files/ex_min.convo.yml", &options).unwrap();
+/// ```
+pub fn import_with<P>(path: P, options: &ImportOptions) -> Result<Tree, ImportError>
+where
+    P: AsRef<Path>,
+{
+    import_with_metrics(path, options).map(|(tree, _)| tree)
+}
+
+/// Try to returns a [`Tree`] which is generated from importing a file, read with [`ImportOptions`], and the [`ImportMetrics`] of the import.
+///
+/// # Arguments
+///
+/// * `path` - A path type that references a file to parse from.
+/// * `options` - The [`ImportOptions`] which control how the file is read.
+///
+/// # Errors
+///
+/// * An [`ImportError`] will be returned as with [`import_with`].
+///
+/// # Examples
+///
+/// ```
+/// use convo::importer::{self, ImportOptions};
+/// let (_, metrics) =
+///     importer::import_with_metrics("examples/dialogue_files/ex_min.convo.yml", &ImportOptions::default())
+///         .unwrap();
+/// println!("{} nodes in {:?}", metrics.nodes, metrics.total());
+/// ```
+pub fn import_with_metrics<P>(
+    path: P,
+    options: &ImportOptions,
+) -> Result<(Tree, ImportMetrics), ImportError>
+where
+    P: AsRef<Path>,
+{
+    let start = Instant::now();
+    let source = get_file_source(path)?;
+    let read = start.elapsed();
+    let (tree, metrics) = source_to_tree_with_metrics(&source, options)?;
+    Ok((tree, ImportMetrics { read, ..metrics }))
+}
+
 /// Try to returns a [`Tree`] which is generated from parsing a string slice.
 ///
 /// # Arguments
 ///
 /// * `source` - A string slice that holds valid YAML data to parse from.
-/// See also: [example dialogue files](https://github.com/simbleau/convo/tree/main/examples/dialogue_files).
+///   See also: [example dialogue files](https://github.com/simbleau/convo/tree/main/examples/dialogue_files).
 ///
 /// # Errors
 ///
 /// * A [`ImportError`] will be returned if the source is not valid YAML data or if the tree is not considered legal when parsing.
-/// See also: [validation rules](https://github.com/simbleau/convo/blob/dev/FORMATTING.md#validation-rules).
+///   See also: [validation rules](https://github.com/simbleau/convo/blob/dev/FORMATTING.md#validation-rules).
 ///
 /// # Examples
 ///
@@ -66,257 +212,3527 @@ where
 /// "#;
 /// let tree = importer::source_to_tree(source).unwrap();
 /// ```
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip_all, err(Debug))
+)]
 pub fn source_to_tree(source: &str) -> Result<Tree, ImportError> {
+    source_to_tree_with(source, &ImportOptions::default())
+}
+
+/// Try to returns a [`Tree`] which is generated from parsing a string slice, read with [`ImportOptions`].
+///
+/// # Arguments
+///
+/// * `source` - A string slice that holds valid YAML data to parse from.
+/// * `options` - The [`ImportOptions`] which control how the source is read.
+///
+/// # Errors
+///
+/// * A [`ImportError`] will be returned if the source is not valid YAML data or if the tree is not considered legal when parsing, including when node keys are identical once normalized.
+///   See also: [validation rules](https://github.com/simbleau/convo/blob/dev/FORMATTING.md#validation-rules).
+///
+/// # Examples
+///
+/// ```
+/// use convo::importer::{self, ImportOptions};
+/// let source = "---\nroot: start\nnodes:\n  start:\n    dialogue: Hi\n    end: true\n";
+/// let tree = importer::source_to_tree_with(source, &ImportOptions::default()).unwrap();
+/// ```
+pub fn source_to_tree_with(source: &str, options: &ImportOptions) -> Result<Tree, ImportError> {
+    source_to_tree_with_metrics(source, options).map(|(tree, _)| tree)
+}
+
+/// Try to returns a [`Tree`] which is generated from parsing a string slice, read with [`ImportOptions`], and the [`ImportMetrics`] of the import.
+///
+/// # Arguments
+///
+/// * `source` - A string slice that holds valid YAML data to parse from.
+/// * `options` - The [`ImportOptions`] which control how the source is read.
+///
+/// # Errors
+///
+/// * A [`ImportError`] will be returned as with [`source_to_tree_with`].
+///
+/// # Examples
+///
+/// ```
+/// use convo::importer::{self, ImportOptions};
+/// let source = "---\nroot: start\nnodes:\n  start:\n    dialogue: Hi\n    end: true\n";
+/// let (_, metrics) = importer::source_to_tree_with_metrics(source, &ImportOptions::default()).unwrap();
+/// assert_eq!(1, metrics.nodes);
+/// assert_eq!(source.len(), metrics.bytes);
+/// ```
+pub fn source_to_tree_with_metrics(
+    source: &str,
+    options: &ImportOptions,
+) -> Result<(Tree, ImportMetrics), ImportError> {
+    load_source(source, options, None)
+}
+
+/// Try to returns a [`Tree`] which is generated from importing a file, read with [`ImportOptions`], and the warnings found while importing it.
+///
+/// # Arguments
+///
+/// * `path` - A path type that references a file to parse from.
+/// * `options` - The [`ImportOptions`] which control how the file is read.
+///
+/// # Errors
+///
+/// * An [`ImportError`] will be returned as with [`import_with`].
+///
+/// # Examples
+///
+/// ```
+/// use convo::importer::{self, ImportOptions};
+/// let (_, warnings) =
+///     importer::import_with_warnings("examples/dialogue_files/ex_1.convo.yml", &ImportOptions::default())
+///         .unwrap();
+/// for warning in warnings {
+///     println!("{}: {}", warning.rule.id(), warning.message);
+/// }
+/// ```
+pub fn import_with_warnings<P>(
+    path: P,
+    options: &ImportOptions,
+) -> Result<(Tree, Vec<Diagnostic>), ImportError>
+where
+    P: AsRef<Path>,
+{
+    let source = get_file_source(path)?;
+    source_to_tree_with_warnings(&source, options)
+}
+
+/// Try to returns a [`Tree`] which is generated from parsing a string slice, read with [`ImportOptions`], and the warnings found while importing it.
+///
+/// Warnings are problems which do not fail the import, but should not be silently dropped: fields which are not part of the format and are ignored, under the [`Rule::UnknownField`] rule, followed by the [lints][`crate::lint::lint`] of the tree, such as duplicate links, and dialogue beyond the [`ImportOptions#max_length`][`ImportOptions#structfield.max_length`].
+///
+/// # Arguments
+///
+/// * `source` - A string slice that holds valid YAML data to parse from.
+/// * `options` - The [`ImportOptions`] which control how the source is read.
+///
+/// # Errors
+///
+/// * An [`ImportError`] will be returned as with [`source_to_tree_with`].
+///
+/// # Examples
+///
+/// ```
+/// use convo::{importer::{self, ImportOptions}, lint::{MaxLength, Rule}};
+/// let source = "---\nroot: start\nnodes:\n  start:\n    dialogue: Hello there!\n    mood: happy\n    end: true\n";
+/// let options = ImportOptions {
+///     max_length: MaxLength {
+///         dialogue: Some(8),
+///         link_dialogue: None,
+///     },
+///     ..Default::default()
+/// };
+/// let (_, warnings) = importer::source_to_tree_with_warnings(source, &options).unwrap();
+/// assert_eq!(Rule::UnknownField, warnings[0].rule);
+/// assert_eq!("max-length", warnings[1].rule.id());
+/// ```
+pub fn source_to_tree_with_warnings(
+    source: &str,
+    options: &ImportOptions,
+) -> Result<(Tree, Vec<Diagnostic>), ImportError> {
+    let mut warnings = Vec::new();
+    let (tree, _) = load_source(source, options, Some(&mut warnings))?;
+    warnings.extend(lint::lint_with(&tree, &[&options.max_length]));
+    Ok((tree, warnings))
+}
+
+fn load_source(
+    source: &str,
+    options: &ImportOptions,
+    warnings: Option<&mut Vec<Diagnostic>>,
+) -> Result<(Tree, ImportMetrics), ImportError> {
     // Parse the YAML
+    let start = Instant::now();
     let docs = YamlLoader::load_from_str(source)?;
     if docs.len() != 1 {
         return Err(ImportError::MultipleDocumentsProvided());
     }
     let yaml = &docs[0];
+    let parse = start.elapsed();
+    if let Some(warnings) = warnings {
+        unknown_fields(yaml, warnings);
+    }
 
     // Convert YAML to Tree
-    let tree = yaml_to_tree(yaml)?;
+    let start = Instant::now();
+    let mut tree = match options.draft {
+        true => build_tree(&draft_yaml(yaml))?,
+        false => build_tree(yaml)?,
+    };
+    let build = start.elapsed();
+
+    // Validate the tree, unless it is a draft
+    let start = Instant::now();
+    if !options.draft {
+        draft::validate(&tree)?;
+    }
+    apply_options(&mut tree, options)?;
+    let validation = start.elapsed();
+
+    let metrics = ImportMetrics {
+        bytes: source.len(),
+        nodes: tree.len(),
+        read: Duration::ZERO,
+        parse,
+        build,
+        validation,
+    };
+
+    #[cfg(feature = "tracing")]
+    tracing::debug!(
+        nodes = metrics.nodes,
+        bytes = metrics.bytes,
+        parse = ?metrics.parse,
+        build = ?metrics.build,
+        validation = ?metrics.validation,
+        "imported tree"
+    );
+
+    Ok((tree, metrics))
+}
+
+// The fields of documents, nodes, dialogue lines, link groups, and structured links, which are read by the importer
+const DOCUMENT_FIELDS: [&str; 7] = [
+    "root",
+    "nodes",
+    "generate",
+    "entries",
+    "meta",
+    "variables",
+    "availability",
+];
+const NODE_FIELDS: [&str; 14] = [
+    "dialogue",
+    "links",
+    "tags",
+    "switch",
+    "cases",
+    "default",
+    "random",
+    "menu",
+    "availability",
+    "pacing",
+    "stage",
+    "end",
+    "todo",
+    "id",
+];
+const LINE_FIELDS: [&str; 3] = ["speaker", "text", "emotion"];
+const GROUP_FIELDS: [&str; 2] = ["group", "links"];
+const LINK_FIELDS: [&str; 11] = [
+    "to",
+    "dialogue",
+    "text",
+    "priority",
+    "weight",
+    "if",
+    "disabled",
+    "set",
+    "affinity",
+    "requires_item",
+    "tags",
+];
+
+// Reports the fields which are ignored, since they are not part of the format
+fn unknown_fields(yaml: &Yaml, warnings: &mut Vec<Diagnostic>) {
+    let is_unknown = |fields: &[&str], field: &Yaml| {
+        !field.as_str().is_some_and(|field| fields.contains(&field))
+    };
+    let name = |field: &Yaml| match field.as_str() {
+        Some(field) => field.to_owned(),
+        None => format!("{:?}", field),
+    };
+    let root_key = yaml["root"].as_str().unwrap_or_default();
+    for field in yaml.as_hash().into_iter().flat_map(yaml::Hash::keys) {
+        if is_unknown(&DOCUMENT_FIELDS, field) {
+            let message = format!(
+                "The document has the unknown field `{}`, which is ignored",
+                name(field)
+            );
+            warnings.push(Diagnostic::new(Rule::UnknownField, root_key, message));
+        }
+    }
+    for (key, data) in yaml["nodes"].as_hash().into_iter().flatten() {
+        let key = key.as_str().unwrap_or_default();
+        let data = match data.as_hash() {
+            Some(data) => data,
+            None => continue,
+        };
+        for field in data.keys().filter(|field| is_unknown(&NODE_FIELDS, field)) {
+            let message = format!(
+                "Node `{}` has the unknown field `{}`, which is ignored",
+                key,
+                name(field)
+            );
+            warnings.push(Diagnostic::new(Rule::UnknownField, key, message));
+        }
+
+        // Dialogue may be an array of lines
+        let lines = data.get(&Yaml::from_str("dialogue")).and_then(Yaml::as_vec);
+        for (i, line) in lines.into_iter().flatten().enumerate() {
+            let fields = line.as_hash().into_iter().flat_map(yaml::Hash::keys);
+            for field in fields.filter(|field| is_unknown(&LINE_FIELDS, field)) {
+                let message = format!(
+                    "Line {} of node `{}` has the unknown field `{}`, which is ignored",
+                    i + 1,
+                    key,
+                    name(field)
+                );
+                warnings.push(Diagnostic::new(Rule::UnknownField, key, message));
+            }
+        }
+
+        // Structured links may be grouped, where each group holds its own links
+        let mut links: Vec<&Yaml> = Vec::new();
+        for link in data
+            .get(&Yaml::from_str("links"))
+            .and_then(Yaml::as_vec)
+            .into_iter()
+            .flatten()
+        {
+            match link.as_hash() {
+                Some(hash) if is_link_group(hash) => {
+                    for field in hash.keys().filter(|field| is_unknown(&GROUP_FIELDS, field)) {
+                        let message = format!(
+                            "Link group `{}` of node `{}` has the unknown field `{}`, which is ignored",
+                            hash[&Yaml::from_str("group")].as_str().unwrap_or_default(),
+                            key,
+                            name(field)
+                        );
+                        warnings.push(Diagnostic::new(Rule::UnknownField, key, message));
+                    }
+                    links.extend(
+                        hash[&Yaml::from_str("links")]
+                            .as_vec()
+                            .into_iter()
+                            .flatten(),
+                    )
+                }
+                _ => links.push(link),
+            }
+        }
+        for (i, link) in links.into_iter().enumerate() {
+            let hash = match link.as_hash() {
+                Some(hash) if is_structured_link(hash) => hash,
+                _ => continue,
+            };
+            for field in hash.keys().filter(|field| is_unknown(&LINK_FIELDS, field)) {
+                let message = format!(
+                    "Link {} of node `{}` has the unknown field `{}`, which is ignored",
+                    i + 1,
+                    key,
+                    name(field)
+                );
+                warnings.push(Diagnostic::new(Rule::UnknownField, key, message));
+            }
+        }
+    }
+}
+
+fn apply_options(tree: &mut Tree, options: &ImportOptions) -> Result<(), ImportError> {
+    if options.normalize_keys {
+        tree.normalize_keys()?;
+    }
+    Ok(())
+}
+
+/// Try to returns a [`Tree`] which is generated from importing a file incrementally.
+///
+/// Unlike [`import`], the file is never read into memory as a whole and its YAML is never materialized as a whole document. Nodes are built one at a time while the file is parsed, which substantially reduces peak memory for very large trees.
+///
+/// # Arguments
+///
+/// * `path` - A path type that references a file to parse from.
+///   See also: [example dialogue files](https://github.com/simbleau/convo/tree/main/examples/dialogue_files).
+///
+/// # Errors
+///
+/// * An [`ImportError`] will be returned if the source is not valid YAML data or if the tree is not considered legal when parsing. Errors are returned as soon as they are found, so a file with several problems may report a different one than [`import`].
+///   See also: [validation rules](https://github.com/simbleau/convo/blob/dev/FORMATTING.md#validation-rules).
+///
+/// # Examples
+///
+/// ```
+/// use convo::importer;
+/// let tree = importer::import_streaming("examples/dialogue_files/ex_1.convo.yml").unwrap();
+/// assert_eq!(tree, importer::import("examples/dialogue_files/ex_1.convo.yml").unwrap());
+/// ```
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip_all, fields(path = ?path.as_ref()), err(Debug))
+)]
+pub fn import_streaming<P>(path: P) -> Result<Tree, ImportError>
+where
+    P: AsRef<Path>,
+{
+    let file = File::open(path)?;
+    reader_to_tree(file)
+}
+
+/// Try to returns a [`Tree`] which is generated from parsing a reader incrementally.
+///
+/// Nodes are built one at a time as the YAML is read. YAML aliases are not supported in this mode.
+///
+/// # Arguments
+///
+/// * `reader` - A reader that yields valid UTF-8 YAML data to parse from.
+///   See also: [example dialogue files](https://github.com/simbleau/convo/tree/main/examples/dialogue_files).
+///
+/// # Errors
+///
+/// * A [`ImportError`] will be returned if the reader fails, if the source is not valid YAML data, or if the tree is not considered legal when parsing.
+///   See also: [validation rules](https://github.com/simbleau/convo/blob/dev/FORMATTING.md#validation-rules).
+///
+/// # Examples
+///
+/// ```
+/// use convo::importer;
+/// let source = r#"
+/// ---
+/// root: start
+/// nodes:
+///     start:
+///         dialogue: I am a recursive node.
+///         links:
+///             - start: Recurse!
+/// "#;
+/// let tree = importer::reader_to_tree(source.as_bytes()).unwrap();
+/// ```
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip_all, err(Debug))
+)]
+pub fn reader_to_tree<R>(reader: R) -> Result<Tree, ImportError>
+where
+    R: Read,
+{
+    let mut chars = ReaderChars::new(BufReader::new(reader));
+    let result = EventStream::new(&mut chars).read_tree();
+
+    // Read errors end the character stream early, so they take precedence over the parse errors they cause
+    if let Some(err) = chars.error.take() {
+        return Err(err.into());
+    }
+    let tree = result?;
+
+    #[cfg(feature = "tracing")]
+    tracing::debug!(nodes = tree.len(), "imported tree");
 
     Ok(tree)
 }
 
-fn get_file_source<P>(path: P) -> Result<String, ImportError>
+/// Try to returns the [`Meta`] of a dialogue file without building its nodes.
+///
+/// The file is parsed incrementally, as with [`import_streaming`], but everything besides the top-level `meta` section is skipped, so tools can list conversations cheaply. A file without a `meta` section has empty metadata.
+///
+/// # Arguments
+///
+/// * `path` - A path type that references a file to parse from.
+///   See also: [example dialogue files](https://github.com/simbleau/convo/tree/main/examples/dialogue_files).
+///
+/// # Errors
+///
+/// * An [`ImportError`] will be returned if the file cannot be read, if the source is not valid YAML data, or if the `meta` section is not considered legal when parsing. The nodes are not validated.
+///   See also: [validation rules](https://github.com/simbleau/convo/blob/dev/FORMATTING.md#validation-rules).
+///
+/// # Examples
+///
+/// ```
+/// use convo::importer;
+/// let meta = importer::import_meta("examples/dialogue_files/ex_1.convo.yml").unwrap();
+/// assert_eq!(Some("Stranger Danger"), meta.title.as_deref());
+/// ```
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip_all, fields(path = ?path.as_ref()), err(Debug))
+)]
+pub fn import_meta<P>(path: P) -> Result<Meta, ImportError>
 where
     P: AsRef<Path>,
 {
-    // Read the file contents
-    let mut file = File::open(path)?;
-    let mut buf = String::new();
-    file.read_to_string(&mut buf)?;
+    let file = File::open(path)?;
+    let mut chars = ReaderChars::new(BufReader::new(file));
+    let result = EventStream::new(&mut chars).read_meta();
+
+    // Read errors end the character stream early, so they take precedence over the parse errors they cause
+    if let Some(err) = chars.error.take() {
+        return Err(err.into());
+    }
+    result
+}
+
+/// An iterator over the characters of a buffered reader, one line at a time.
+struct ReaderChars<R> {
+    reader: R,
+    line: String,
+    pos: usize,
+    error: Option<io::Error>,
+}
+
+impl<R> ReaderChars<R>
+where
+    R: BufRead,
+{
+    fn new(reader: R) -> Self {
+        ReaderChars {
+            reader,
+            line: String::new(),
+            pos: 0,
+            error: None,
+        }
+    }
+}
+
+impl<R> Iterator for ReaderChars<R>
+where
+    R: BufRead,
+{
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        if self.pos >= self.line.len() {
+            self.line.clear();
+            self.pos = 0;
+            match self.reader.read_line(&mut self.line) {
+                Ok(0) => return None,
+                Ok(_) => {}
+                Err(err) => {
+                    self.error = Some(err);
+                    return None;
+                }
+            }
+        }
+        let c = self.line[self.pos..].chars().next()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+}
+
+/// A pull parser which builds a [`Tree`] from YAML events, materializing at most one node's YAML at a time.
+struct EventStream<I> {
+    parser: Parser<I>,
+}
+
+impl<I> EventStream<I>
+where
+    I: Iterator<Item = char>,
+{
+    fn new(chars: I) -> Self {
+        EventStream {
+            parser: Parser::new(chars),
+        }
+    }
+
+    fn next(&mut self) -> Result<Event, ImportError> {
+        Ok(self.parser.next()?.0)
+    }
+
+    fn read_document<F>(&mut self, mut read_value: F) -> Result<(), ImportError>
+    where
+        F: FnMut(&mut Self, &Yaml) -> Result<(), ImportError>,
+    {
+        // Exactly one document is expected
+        self.next()?; // StreamStart
+        if self.next()? != Event::DocumentStart {
+            return Err(ImportError::MultipleDocumentsProvided());
+        }
+
+        // Each top-level value is read by the caller, so the document must be a mapping
+        if !matches!(self.next()?, Event::MappingStart(_)) {
+            return Err(TreeError::Validation(
+                "YAML does not contain top-level string key for `root`".into(),
+            )
+            .into());
+        }
+        loop {
+            let key = match self.next()? {
+                Event::MappingEnd => break,
+                event => self.load(event)?,
+            };
+            read_value(self, &key)?;
+        }
+        self.next()?; // DocumentEnd
+        if self.next()? != Event::StreamEnd {
+            return Err(ImportError::MultipleDocumentsProvided());
+        }
+
+        Ok(())
+    }
+
+    fn read_meta(&mut self) -> Result<Meta, ImportError> {
+        let mut meta = Meta::default();
+        self.read_document(|stream, key| {
+            match key.as_str() {
+                Some("meta") => meta = yaml_to_meta(&stream.load_next()?)?,
+                _ => stream.skip_next()?,
+            }
+            Ok(())
+        })?;
+        Ok(meta)
+    }
+
+    fn read_tree(&mut self) -> Result<Tree, ImportError> {
+        let mut root_key = None;
+        let mut tree = None;
+        let mut meta = Meta::default();
+        let mut availability = Availability::default();
+        let mut variables = BTreeMap::new();
+        let mut generators = Yaml::Null;
+        let mut entries = Yaml::Null;
+        self.read_document(|stream, key| {
+            match key.as_str() {
+                Some("root") => root_key = stream.load_next()?.into_string(),
+                Some("entries") => entries = stream.load_next()?,
+                Some("nodes") => tree = Some(stream.nodes()?),
+                Some("meta") => meta = yaml_to_meta(&stream.load_next()?)?,
+                Some("variables") => variables = yaml_to_variables(&stream.load_next()?)?,
+                Some("availability") => {
+                    availability = yaml_to_availability(&stream.load_next()?, "the tree")?
+                }
+                Some("generate") => generators = stream.load_next()?,
+                _ => stream.skip_next()?,
+            }
+            Ok(())
+        })?;
+
+        let root_key = root_key.ok_or_else(|| {
+            TreeError::Validation("YAML does not contain top-level string key for `root`".into())
+        })?;
+        let mut tree = tree.ok_or_else(|| {
+            TreeError::Validation("YAML does not contain top-level hash for `nodes`".into())
+        })?;
+        generate_nodes(&mut tree, &generators)?;
+        tree.assign_ids()?;
+        set_root(&mut tree, &root_key)?;
+        set_entries(&mut tree, &entries)?;
+        *tree.meta_mut() = meta;
+        *tree.variables_mut() = variables;
+        *tree.availability_mut() = availability;
+        draft::validate(&tree)?;
+
+        Ok(tree)
+    }
+
+    fn nodes(&mut self) -> Result<Tree, ImportError> {
+        if !matches!(self.next()?, Event::MappingStart(_)) {
+            return Err(TreeError::Validation(
+                "YAML does not contain top-level hash for `nodes`".into(),
+            )
+            .into());
+        }
+
+        // Insert nodes as they are parsed
+        let mut tree = Tree::new();
+        loop {
+            let key = match self.next()? {
+                Event::MappingEnd => break,
+                event => self.load(event)?,
+            };
+            let value = self.load_next()?;
+            let node = yaml_to_node(&key, &value)?;
+            tree.insert_node(node);
+        }
+
+        // Check length of nodes
+        if tree.is_empty() {
+            return Err(TreeError::Validation("Node map has a length of 0".into()).into());
+        }
+
+        Ok(tree)
+    }
+
+    fn load_next(&mut self) -> Result<Yaml, ImportError> {
+        let event = self.next()?;
+        self.load(event)
+    }
+
+    fn load(&mut self, event: Event) -> Result<Yaml, ImportError> {
+        match event {
+            Event::Scalar(value, style, _, tag) => Ok(scalar_to_yaml(value, style, tag)),
+            Event::SequenceStart(_) => {
+                let mut array = yaml::Array::new();
+                loop {
+                    match self.next()? {
+                        Event::SequenceEnd => return Ok(Yaml::Array(array)),
+                        event => array.push(self.load(event)?),
+                    }
+                }
+            }
+            Event::MappingStart(_) => {
+                let mut hash = yaml::Hash::new();
+                loop {
+                    let key = match self.next()? {
+                        Event::MappingEnd => return Ok(Yaml::Hash(hash)),
+                        event => self.load(event)?,
+                    };
+                    let value = self.load_next()?;
+                    hash.insert(key, value);
+                }
+            }
+            Event::Alias(_) => Err(TreeError::Validation(
+                "YAML aliases are not supported when importing incrementally".into(),
+            )
+            .into()),
+            event => {
+                Err(TreeError::Validation(format!("Unexpected YAML event: {:?}", event)).into())
+            }
+        }
+    }
+
+    fn skip_next(&mut self) -> Result<(), ImportError> {
+        let mut depth = 0usize;
+        loop {
+            match self.next()? {
+                Event::SequenceStart(_) | Event::MappingStart(_) => depth += 1,
+                Event::SequenceEnd | Event::MappingEnd => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 {
+                return Ok(());
+            }
+        }
+    }
+}
+
+pub(crate) fn scalar_to_yaml(value: String, style: TScalarStyle, tag: Option<TokenType>) -> Yaml {
+    // Quoted and tagged scalars are strings, as with `YamlLoader`, except for core schema tags
+    match (style, tag) {
+        (TScalarStyle::Plain, None) => Yaml::from_str(&value),
+        (TScalarStyle::Plain, Some(TokenType::Tag(handle, suffix)))
+            if handle == "!!" && suffix != "str" =>
+        {
+            Yaml::from_str(&value)
+        }
+        _ => Yaml::String(value),
+    }
+}
+
+pub(crate) fn get_file_source<P>(path: P) -> Result<String, ImportError>
+where
+    P: AsRef<Path>,
+{
+    // Read the file contents
+    let mut file = File::open(path)?;
+    let mut buf = String::new();
+    file.read_to_string(&mut buf)?;
+
+    Ok(buf)
+}
+
+pub(crate) fn yaml_to_tree(yaml: &Yaml) -> Result<Tree, ImportError> {
+    let tree = build_tree(yaml)?;
+    draft::validate(&tree)?;
+    Ok(tree)
+}
+
+// Fills in what draft mode allows to be left out, leaving anything which is not a node map for the importer to reject
+fn draft_yaml(yaml: &Yaml) -> Yaml {
+    let mut yaml = yaml.clone();
+    let nodes_key = Yaml::String("nodes".to_owned());
+    let node_map = match &mut yaml {
+        Yaml::Hash(document) => document.get_mut(&nodes_key),
+        _ => None,
+    };
+    if let Some(Yaml::Hash(node_map)) = node_map {
+        for (_, yaml_data) in node_map.iter_mut() {
+            if yaml_data.is_null() {
+                *yaml_data = Yaml::Hash(yaml::Hash::new());
+            }
+            if let Yaml::Hash(data) = yaml_data {
+                let links_key = Yaml::String("links".to_owned());
+                let no_links = match data.get(&links_key) {
+                    Some(Yaml::Null) => true,
+                    Some(Yaml::Array(links)) => links.is_empty(),
+                    _ => false,
+                };
+                if no_links {
+                    data.remove(&links_key);
+                }
+                let dialogue_key = Yaml::String("dialogue".to_owned());
+                let no_dialogue = match data.get(&dialogue_key) {
+                    Some(yaml_dialogue) => yaml_dialogue.is_null(),
+                    None => !data.contains_key(&Yaml::String("switch".to_owned())),
+                };
+                if no_dialogue {
+                    data.insert(dialogue_key, Yaml::String(String::new()));
+                }
+            }
+        }
+    }
+    yaml
+}
+
+fn build_tree(yaml: &Yaml) -> Result<Tree, ImportError> {
+    // This needs some major cleanup
+
+    let root_key = yaml["root"].as_str().ok_or_else(|| {
+        TreeError::Validation("YAML does not contain top-level string key for `root`".into())
+    })?;
+
+    let node_map = yaml["nodes"].as_hash().ok_or_else(|| {
+        TreeError::Validation("YAML does not contain top-level hash for `nodes`".into())
+    })?;
+
+    // Check length of nodes
+    if node_map.is_empty() {
+        return Err(TreeError::Validation("Node map has a length of 0".into()).into());
+    }
+
+    // Insert nodes
+    let mut tree = Tree::with_capacity(node_map.len());
+    for (key, value) in node_map.iter() {
+        let node = yaml_to_node(key, value)?;
+        tree.insert_node(node);
+    }
+    let yaml_generators = &yaml["generate"];
+    if !yaml_generators.is_badvalue() {
+        generate_nodes(&mut tree, yaml_generators)?;
+    }
+    tree.assign_ids()?;
+
+    set_root(&mut tree, root_key)?;
+
+    // Entry points, metadata, variables, and availability are optional
+    let yaml_entries = &yaml["entries"];
+    if !yaml_entries.is_badvalue() {
+        set_entries(&mut tree, yaml_entries)?;
+    }
+    let yaml_meta = &yaml["meta"];
+    if !yaml_meta.is_badvalue() {
+        *tree.meta_mut() = yaml_to_meta(yaml_meta)?;
+    }
+    let yaml_variables = &yaml["variables"];
+    if !yaml_variables.is_badvalue() {
+        *tree.variables_mut() = yaml_to_variables(yaml_variables)?;
+    }
+    let yaml_availability = &yaml["availability"];
+    if !yaml_availability.is_badvalue() {
+        *tree.availability_mut() = yaml_to_availability(yaml_availability, "the tree")?;
+    }
+
+    Ok(tree)
+}
+
+fn yaml_to_meta(yaml: &Yaml) -> Result<Meta, ImportError> {
+    let mut meta = Meta::default();
+
+    // An empty `meta` section holds no metadata
+    if yaml.is_null() {
+        return Ok(meta);
+    }
+
+    // Unwrap fields
+    let fields = yaml
+        .as_hash()
+        .ok_or_else(|| TreeError::Validation(format!("YAML meta is not a hash: '{:?}'", yaml)))?;
+    for (yaml_key, yaml_value) in fields {
+        let key = yaml_key.as_str().ok_or_else(|| {
+            TreeError::Validation(format!("YAML meta key is not a string: `{:?}`", yaml_key))
+        })?;
+
+        // Tags are an array of strings, every other field is a string
+        if key == "tags" {
+            let tags = yaml_value.as_vec().ok_or_else(|| {
+                TreeError::Validation(format!(
+                    "YAML meta tags are not an array: '{:?}'",
+                    yaml_value
+                ))
+            })?;
+            for tag in tags {
+                let tag = tag.as_str().ok_or_else(|| {
+                    TreeError::Validation(format!("YAML meta tag is not a string: '{:?}'", tag))
+                })?;
+                meta.tags.push(tag.into());
+            }
+            continue;
+        }
+        let value = yaml_value
+            .as_str()
+            .ok_or_else(|| {
+                TreeError::Validation(format!("YAML meta value is not a string for `{:?}`", key))
+            })?
+            .to_owned();
+        match key {
+            "title" => meta.title = Some(value),
+            "author" => meta.author = Some(value),
+            "description" => meta.description = Some(value),
+            _ => {
+                meta.custom.insert(key.into(), value);
+            }
+        }
+    }
+
+    Ok(meta)
+}
+
+fn set_root(tree: &mut Tree, root_key: &str) -> Result<(), ImportError> {
+    // Set root and current
+    if !tree.contains_node(root_key) {
+        return Err(tree.missing_node(root_key).into());
+    }
+
+    // The root node is guaranteed to exist, per above
+    tree.raw().set_root_key(root_key).set_current_key(root_key);
+
+    Ok(())
+}
+
+fn set_entries(tree: &mut Tree, yaml: &Yaml) -> Result<(), ImportError> {
+    // An empty `entries` section names no entry points
+    if yaml.is_null() {
+        return Ok(());
+    }
+
+    // Each entry point names the node it starts at, which must exist like the root node
+    let entries = yaml.as_hash().ok_or_else(|| {
+        TreeError::Validation(format!("YAML entries are not a hash: '{:?}'", yaml))
+    })?;
+    for (yaml_name, yaml_key) in entries {
+        let name = yaml_name.as_str().ok_or_else(|| {
+            TreeError::Validation(format!(
+                "YAML entry point name is not a string: `{:?}`",
+                yaml_name
+            ))
+        })?;
+        let node_key = yaml_key.as_str().ok_or_else(|| {
+            TreeError::Validation(format!(
+                "YAML entry point is not a string key for `{:?}`",
+                name
+            ))
+        })?;
+        tree.set_entry(name, node_key)?;
+    }
+
+    Ok(())
+}
+
+fn yaml_to_variables(yaml: &Yaml) -> Result<BTreeMap<String, Type>, ImportError> {
+    let mut variables = BTreeMap::new();
+
+    // An empty `variables` section declares nothing
+    if yaml.is_null() {
+        return Ok(variables);
+    }
+
+    // Unwrap declarations
+    let declarations = yaml.as_hash().ok_or_else(|| {
+        TreeError::Validation(format!("YAML variables are not a hash: '{:?}'", yaml))
+    })?;
+    for (yaml_name, yaml_type) in declarations {
+        let name = yaml_name.as_str().ok_or_else(|| {
+            TreeError::Validation(format!(
+                "YAML variable name is not a string: `{:?}`",
+                yaml_name
+            ))
+        })?;
+
+        // Types are named, except enums which list their variants
+        let variable_type = match yaml_type {
+            Yaml::String(type_name) if type_name == "bool" => Type::Bool,
+            Yaml::String(type_name) if type_name == "int" => Type::Int,
+            Yaml::String(type_name) if type_name == "string" => Type::String,
+            Yaml::Array(yaml_variants) if !yaml_variants.is_empty() => {
+                let variants = yaml_variants
+                    .iter()
+                    .map(|variant| variant.as_str().map(str::to_owned))
+                    .collect::<Option<Vec<String>>>()
+                    .ok_or_else(|| {
+                        TreeError::Validation(format!(
+                            "YAML enum variants are not strings for `{:?}`",
+                            name
+                        ))
+                    })?;
+                Type::Enum(variants)
+            }
+            _ => {
+                return Err(TreeError::Validation(format!(
+                    "YAML variable type is not `bool`, `int`, `string`, or an array of variants for `{:?}`",
+                    name
+                ))
+                .into())
+            }
+        };
+        variables.insert(name.to_owned(), variable_type);
+    }
+
+    Ok(variables)
+}
+
+fn yaml_to_availability(yaml: &Yaml, owner: &str) -> Result<Availability, ImportError> {
+    let mut availability = Availability::default();
+
+    // An empty `availability` section restricts nothing
+    if yaml.is_null() {
+        return Ok(availability);
+    }
+
+    // Unwrap fields
+    let fields = yaml.as_hash().ok_or_else(|| {
+        TreeError::Validation(format!(
+            "YAML availability is not a hash for {}: '{:?}'",
+            owner, yaml
+        ))
+    })?;
+    for (yaml_key, yaml_value) in fields {
+        let day = || {
+            yaml_value.as_i64().ok_or_else(|| {
+                TreeError::Validation(format!(
+                    "YAML availability `{:?}` is not an integer for {}",
+                    yaml_key, owner
+                ))
+            })
+        };
+        match yaml_key.as_str() {
+            Some("cooldown") => {
+                let cooldown = yaml_value
+                    .as_i64()
+                    .and_then(|cooldown| u64::try_from(cooldown).ok())
+                    .ok_or_else(|| {
+                        TreeError::Validation(format!(
+                            "YAML availability cooldown is not a non-negative integer for {}",
+                            owner
+                        ))
+                    })?;
+                availability.cooldown = Some(cooldown);
+            }
+            Some("min_day") => availability.min_day = Some(day()?),
+            Some("max_day") => availability.max_day = Some(day()?),
+            Some("requires") => {
+                let tags = yaml_value.as_vec().ok_or_else(|| {
+                    TreeError::Validation(format!(
+                        "YAML availability requires is not an array for {}",
+                        owner
+                    ))
+                })?;
+                for tag in tags {
+                    let tag = tag.as_str().ok_or_else(|| {
+                        TreeError::Validation(format!(
+                            "YAML required tag is not a string for {}",
+                            owner
+                        ))
+                    })?;
+                    availability.required_tags.push(tag.into());
+                }
+            }
+            _ => {
+                return Err(TreeError::Validation(format!(
+                    "YAML availability key is not `cooldown`, `min_day`, `max_day`, or `requires` for {}: `{:?}`",
+                    owner, yaml_key
+                ))
+                .into())
+            }
+        }
+    }
+
+    // Days are inclusive, so a range may hold a single day
+    if let (Some(min_day), Some(max_day)) = (availability.min_day, availability.max_day) {
+        if min_day > max_day {
+            return Err(TreeError::Validation(format!(
+                "YAML availability min_day is after max_day for {}",
+                owner
+            ))
+            .into());
+        }
+    }
+
+    Ok(availability)
+}
+
+fn yaml_to_pacing(yaml: &Yaml, key: &str) -> Result<Pacing, ImportError> {
+    let mut pacing = Pacing::default();
+
+    // An empty `pacing` section hints nothing
+    if yaml.is_null() {
+        return Ok(pacing);
+    }
+
+    // Unwrap fields
+    let fields = yaml.as_hash().ok_or_else(|| {
+        TreeError::Validation(format!(
+            "YAML pacing is not a hash for `{:?}`: '{:?}'",
+            key, yaml
+        ))
+    })?;
+    for (yaml_key, yaml_value) in fields {
+        let hint = match yaml_key.as_str() {
+            Some("chars_per_second") => &mut pacing.chars_per_second,
+            Some("pause_before") => &mut pacing.pause_before,
+            Some("pause_after") => &mut pacing.pause_after,
+            _ => {
+                return Err(TreeError::Validation(format!(
+                    "YAML pacing key is not `chars_per_second`, `pause_before`, or `pause_after` for `{:?}`: `{:?}`",
+                    key, yaml_key
+                ))
+                .into())
+            }
+        };
+        let value = yaml_value
+            .as_i64()
+            .and_then(|value| u32::try_from(value).ok())
+            .ok_or_else(|| {
+                TreeError::Validation(format!(
+                    "YAML pacing `{:?}` is not a non-negative integer for `{:?}`",
+                    yaml_key, key
+                ))
+            })?;
+        *hint = Some(value);
+    }
+
+    Ok(pacing)
+}
+
+fn yaml_to_stage(yaml: &Yaml, key: &str) -> Result<Vec<StageDirection>, ImportError> {
+    let yaml_directions = yaml.as_vec().ok_or_else(|| {
+        TreeError::Validation(format!("YAML stage is not an array for `{:?}`", key))
+    })?;
+
+    let mut directions = Vec::new();
+    for yaml_direction in yaml_directions {
+        // Directions are a bare command, e.g. `- fade_out`, or a command and its arguments, e.g. `- camera: [close_up, npc_bob]`
+        let (command, yaml_args) = match yaml_direction {
+            Yaml::String(command) => (command.as_str(), None),
+            Yaml::Hash(map) if map.len() == 1 => {
+                let (yaml_command, yaml_args) = map.front().unwrap();
+                let command = yaml_command.as_str().ok_or_else(|| {
+                    TreeError::Validation(format!(
+                        "YAML stage command is not a string for `{:?}`: `{:?}`",
+                        key, yaml_command
+                    ))
+                })?;
+                (command, Some(yaml_args))
+            }
+            _ => {
+                return Err(TreeError::Validation(format!(
+                    "YAML stage direction is not a command or a hash of one command to its arguments for `{:?}`: '{:?}'",
+                    key, yaml_direction
+                ))
+                .into())
+            }
+        };
+        if command.is_empty() {
+            return Err(TreeError::Validation(format!(
+                "YAML stage command is empty for `{:?}`",
+                key
+            ))
+            .into());
+        }
+
+        // Arguments are empty, a single value, or an array of values
+        let yaml_args = match yaml_args {
+            None | Some(Yaml::Null) => &[][..],
+            Some(Yaml::Array(yaml_args)) => yaml_args.as_slice(),
+            Some(yaml_arg) => std::slice::from_ref(yaml_arg),
+        };
+        let args = yaml_args
+            .iter()
+            .map(|yaml_arg| {
+                yaml_to_value(yaml_arg).ok_or_else(|| {
+                    TreeError::Validation(format!(
+                        "YAML stage argument of `{}` is not a boolean, integer, or string for `{:?}`: '{:?}'",
+                        command, key, yaml_arg
+                    ))
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        directions.push(StageDirection::new(command, args));
+    }
+
+    Ok(directions)
+}
+
+fn generate_nodes(tree: &mut Tree, yaml: &Yaml) -> Result<(), ImportError> {
+    // An empty `generate` section generates nothing
+    if yaml.is_null() {
+        return Ok(());
+    }
+
+    let generators = yaml.as_vec().ok_or_else(|| {
+        TreeError::Validation(format!("YAML generate is not an array: '{:?}'", yaml))
+    })?;
+    for generator in generators {
+        let generator = generator.as_hash().ok_or_else(|| {
+            TreeError::Validation(format!("YAML generator is not a hash: '{:?}'", generator))
+        })?;
+        if let Some(key) = generator
+            .keys()
+            .find(|key| !matches!(key.as_str(), Some("each" | "nodes" | "links")))
+        {
+            return Err(TreeError::Validation(format!(
+                "YAML generator key is not `each`, `nodes`, or `links`: `{:?}`",
+                key
+            ))
+            .into());
+        }
+        let entries = generator
+            .get(&Yaml::from_str("each"))
+            .and_then(Yaml::as_vec)
+            .ok_or_else(|| {
+                TreeError::Validation(format!(
+                    "YAML generator does not contain an array for `each`: '{:?}'",
+                    generator
+                ))
+            })?;
+        let empty = yaml::Hash::new();
+        let template_hash = |name: &str| match generator.get(&Yaml::from_str(name)) {
+            None => Ok(&empty),
+            Some(template) => template.as_hash().ok_or_else(|| {
+                TreeError::Validation(format!(
+                    "YAML generator `{}` is not a hash: '{:?}'",
+                    name, template
+                ))
+            }),
+        };
+        let node_templates = template_hash("nodes")?;
+        let link_templates = template_hash("links")?;
+        if node_templates.is_empty() && link_templates.is_empty() {
+            return Err(TreeError::Validation(format!(
+                "YAML generator generates no nodes or links: '{:?}'",
+                generator
+            ))
+            .into());
+        }
+
+        // Stamp out every node before links, so links may be added to generated nodes
+        let mut bindings = Vec::new();
+        for entry in entries {
+            bindings.push(yaml_to_bindings(entry)?);
+        }
+        for bindings in &bindings {
+            for (yaml_key, yaml_data) in node_templates {
+                let node = yaml_to_node(
+                    &substitute_key(yaml_key, bindings)?,
+                    &substitute(yaml_data, bindings)?,
+                )?;
+                if tree.contains_node(&node.key) {
+                    return Err(TreeError::Validation(format!(
+                        "YAML generator generates existing node `{}`",
+                        node.key
+                    ))
+                    .into());
+                }
+                tree.insert_node(node);
+            }
+        }
+        for bindings in &bindings {
+            for (yaml_key, yaml_links) in link_templates {
+                let key = substitute_key(yaml_key, bindings)?;
+                let node = key
+                    .as_str()
+                    .and_then(|key| tree.node_mut(key))
+                    .ok_or_else(|| {
+                        TreeError::Validation(format!(
+                            "YAML generator adds links to a node which does not exist: `{:?}`",
+                            key
+                        ))
+                    })?;
+                node.links
+                    .extend(yaml_to_links(&substitute(yaml_links, bindings)?)?);
+                resolve_affinity(node)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn yaml_to_bindings(yaml: &Yaml) -> Result<BTreeMap<String, Yaml>, ImportError> {
+    let entry = yaml.as_hash().ok_or_else(|| {
+        TreeError::Validation(format!("YAML generator entry is not a hash: '{:?}'", yaml))
+    })?;
+    let mut bindings = BTreeMap::new();
+    for (yaml_name, yaml_value) in entry {
+        let name = yaml_name.as_str().ok_or_else(|| {
+            TreeError::Validation(format!(
+                "YAML placeholder name is not a string: `{:?}`",
+                yaml_name
+            ))
+        })?;
+        if yaml_to_value(yaml_value).is_none() {
+            return Err(TreeError::Validation(format!(
+                "YAML placeholder `{}` is not a boolean, integer, or string",
+                name
+            ))
+            .into());
+        }
+        bindings.insert(name.to_owned(), yaml_value.clone());
+    }
+    Ok(bindings)
+}
+
+fn substitute(yaml: &Yaml, bindings: &BTreeMap<String, Yaml>) -> Result<Yaml, ImportError> {
+    match yaml {
+        Yaml::String(template) => {
+            // A lone placeholder keeps the type of its value, e.g. for `set`
+            let lone = template
+                .strip_prefix('{')
+                .and_then(|name| name.strip_suffix('}'))
+                .and_then(|name| bindings.get(name));
+            if let Some(value) = lone {
+                return Ok(value.clone());
+            }
+            Ok(Yaml::String(fill_placeholders(template, bindings)?))
+        }
+        Yaml::Array(array) => array
+            .iter()
+            .map(|yaml| substitute(yaml, bindings))
+            .collect::<Result<_, _>>()
+            .map(Yaml::Array),
+        Yaml::Hash(hash) => {
+            let mut filled = yaml::Hash::new();
+            for (key, value) in hash {
+                filled.insert(substitute_key(key, bindings)?, substitute(value, bindings)?);
+            }
+            Ok(Yaml::Hash(filled))
+        }
+        yaml => Ok(yaml.clone()),
+    }
+}
+
+fn substitute_key(yaml: &Yaml, bindings: &BTreeMap<String, Yaml>) -> Result<Yaml, ImportError> {
+    // Keys are always filled in as strings, e.g. `buy_{id}` or `{id}`
+    match yaml {
+        Yaml::String(template) => Ok(Yaml::String(fill_placeholders(template, bindings)?)),
+        yaml => Ok(yaml.clone()),
+    }
+}
+
+fn fill_placeholders(
+    template: &str,
+    bindings: &BTreeMap<String, Yaml>,
+) -> Result<String, ImportError> {
+    // `{name}` is replaced by the value of `name`, and `{{` and `}}` are literal braces
+    let mut filled = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        filled.push_str(&rest[..start].replace("}}", "}"));
+        rest = &rest[start + 1..];
+        if let Some(escaped) = rest.strip_prefix('{') {
+            filled.push('{');
+            rest = escaped;
+            continue;
+        }
+        let end = rest.find('}').ok_or_else(|| {
+            TreeError::Validation(format!("YAML placeholder is not closed in `{}`", template))
+        })?;
+        let name = &rest[..end];
+        let value = match bindings.get(name) {
+            Some(Yaml::String(value)) => value.to_owned(),
+            Some(Yaml::Integer(value)) => value.to_string(),
+            Some(Yaml::Boolean(value)) => value.to_string(),
+            _ => {
+                return Err(TreeError::Validation(format!(
+                    "YAML placeholder `{}` has no value in `{}`",
+                    name, template
+                ))
+                .into())
+            }
+        };
+        filled.push_str(&value);
+        rest = &rest[end + 1..];
+    }
+    filled.push_str(&rest.replace("}}", "}"));
+    Ok(filled)
+}
+
+pub(crate) fn yaml_to_value(yaml: &Yaml) -> Option<Value> {
+    match yaml {
+        Yaml::Boolean(value) => Some(Value::Bool(*value)),
+        Yaml::Integer(value) => Some(Value::Int(*value)),
+        Yaml::String(value) => Some(Value::String(value.to_owned())),
+        _ => None,
+    }
+}
+
+/// The known fields of a node's hash, gathered in a single pass over it.
+#[derive(Default)]
+struct NodeFields<'y> {
+    dialogue: Option<&'y Yaml>,
+    links: Option<&'y Yaml>,
+    tags: Option<&'y Yaml>,
+    switch: Option<&'y Yaml>,
+    cases: Option<&'y Yaml>,
+    default: Option<&'y Yaml>,
+    random: Option<&'y Yaml>,
+    menu: Option<&'y Yaml>,
+    availability: Option<&'y Yaml>,
+    pacing: Option<&'y Yaml>,
+    stage: Option<&'y Yaml>,
+    end: Option<&'y Yaml>,
+    todo: Option<&'y Yaml>,
+    id: Option<&'y Yaml>,
+}
+
+impl<'y> NodeFields<'y> {
+    fn new(data: &'y yaml::Hash) -> Self {
+        let mut fields = NodeFields::default();
+        for (yaml_field, yaml_value) in data {
+            let field = match yaml_field.as_str() {
+                Some("dialogue") => &mut fields.dialogue,
+                Some("links") => &mut fields.links,
+                Some("tags") => &mut fields.tags,
+                Some("switch") => &mut fields.switch,
+                Some("cases") => &mut fields.cases,
+                Some("default") => &mut fields.default,
+                Some("random") => &mut fields.random,
+                Some("menu") => &mut fields.menu,
+                Some("availability") => &mut fields.availability,
+                Some("pacing") => &mut fields.pacing,
+                Some("stage") => &mut fields.stage,
+                Some("end") => &mut fields.end,
+                Some("todo") => &mut fields.todo,
+                Some("id") => &mut fields.id,
+                // Unknown fields are ignored
+                _ => continue,
+            };
+            *field = Some(yaml_value);
+        }
+        fields
+    }
+}
+
+fn yaml_to_node(yaml_key: &Yaml, yaml_data: &Yaml) -> Result<Node, ImportError> {
+    // Unwrap name
+    let key = yaml_key.as_str().ok_or_else(|| {
+        TreeError::Validation(format!("YAML key is not a string: `{:?}`", yaml_key))
+    })?;
+
+    // Unwrap data, visiting each field once
+    let data = yaml_data.as_hash().ok_or_else(|| {
+        TreeError::Validation(format!("YAML data is not a hash: '{:?}'", yaml_data))
+    })?;
+    let fields = NodeFields::new(data);
+
+    // Unwrap dialogue, which is a string or a sequence of lines, and is optional for switches
+    let no_dialogue = Yaml::String(String::new());
+    let yaml_dialogue = match (fields.dialogue, fields.switch) {
+        (Some(yaml_dialogue), _) => yaml_dialogue,
+        (None, Some(_)) => &no_dialogue,
+        (None, None) => {
+            return Err(TreeError::Validation(format!(
+                "YAML does not contain dialogue for `{:?}`",
+                key
+            ))
+            .into())
+        }
+    };
+    let mut node = match yaml_dialogue {
+        Yaml::Array(yaml_lines) => {
+            let mut node = Node::new(key, "");
+            node.set_lines(yaml_to_lines(key, yaml_lines)?);
+            node
+        }
+        _ => {
+            let dialogue = yaml_dialogue.as_str().ok_or_else(|| {
+                TreeError::Validation(format!(
+                    "YAML dialogue is not a string or an array of lines for `{:?}`",
+                    key
+                ))
+            })?;
+            Node::new(key, trim_dialogue(dialogue))
+        }
+    };
+
+    // Check if any links exist
+    if let Some(yaml_links) = fields.links {
+        // Unwrap links
+        let links = yaml_to_links(yaml_links)?;
+        node.links.extend(links);
+    };
+
+    // Check if any tags exist
+    if let Some(yaml_tags) = fields.tags {
+        let tags = yaml_tags.as_vec().ok_or_else(|| {
+            TreeError::Validation(format!("YAML tags are not an array for `{:?}`", key))
+        })?;
+        for tag in tags {
+            let tag = tag.as_str().ok_or_else(|| {
+                TreeError::Validation(format!("YAML tag is not a string for `{:?}`", key))
+            })?;
+            node.tags.push(tag.into());
+        }
+    }
+
+    // Check if the node switches on a variable, listing `cases` and a `default` rather than links
+    if let Some(yaml_variable) = fields.switch {
+        let variable = yaml_to_switch(key, yaml_variable, &fields, &mut node.links)?;
+        node.kind = NodeKind::Switch { variable };
+    }
+
+    // Check if links are followed at random, e.g. `random: true` or `random: { avoid_recent: 2 }`
+    if let Some(yaml_random) = fields.random {
+        let avoid_recent = match yaml_random {
+            Yaml::Boolean(true) => Some(0),
+            Yaml::Boolean(false) => None,
+            Yaml::Hash(options) => {
+                let avoid_recent = match options.get(&Yaml::from_str("avoid_recent")) {
+                    Some(yaml_avoid) => yaml_avoid
+                        .as_i64()
+                        .and_then(|avoid| usize::try_from(avoid).ok())
+                        .ok_or_else(|| {
+                            TreeError::Validation(format!(
+                                "YAML random `avoid_recent` is not a non-negative integer for `{:?}`",
+                                key
+                            ))
+                        })?,
+                    None => 0,
+                };
+                if options
+                    .keys()
+                    .any(|option| option.as_str() != Some("avoid_recent"))
+                {
+                    return Err(TreeError::Validation(format!(
+                        "YAML random options contain a key besides `avoid_recent` for `{:?}`",
+                        key
+                    ))
+                    .into());
+                }
+                Some(avoid_recent)
+            }
+            _ => {
+                return Err(TreeError::Validation(format!(
+                    "YAML random is not a boolean or a hash for `{:?}`",
+                    key
+                ))
+                .into())
+            }
+        };
+        if let Some(avoid_recent) = avoid_recent {
+            if node.kind.is_automatic() {
+                return Err(TreeError::Validation(format!(
+                    "YAML random is not allowed with `switch` for `{:?}`",
+                    key
+                ))
+                .into());
+            }
+            node.kind = NodeKind::Random { avoid_recent };
+        }
+    }
+
+    // Check if the node is a menu, whose first link is presented once per entry of a source the host supplies, e.g. `menu: shop_stock`
+    if let Some(yaml_menu) = fields.menu {
+        let source = yaml_menu
+            .as_str()
+            .filter(|source| !source.is_empty())
+            .ok_or_else(|| {
+                TreeError::Validation(format!("YAML menu is not a string for `{:?}`", key))
+            })?;
+        if node.kind.is_automatic() {
+            return Err(TreeError::Validation(format!(
+                "YAML menu is not allowed with `switch` or `random` for `{:?}`",
+                key
+            ))
+            .into());
+        }
+        if node.links.is_empty() {
+            return Err(TreeError::Validation(format!(
+                "YAML menu has no template link for `{:?}`",
+                key
+            ))
+            .into());
+        }
+        node.kind = NodeKind::Menu {
+            source: source.to_owned(),
+        };
+    }
+
+    // Check if availability is restricted
+    if let Some(yaml_availability) = fields.availability {
+        node.availability = yaml_to_availability(yaml_availability, &format!("`{}`", key))?;
+    }
+
+    // Check if the dialogue has pacing hints
+    if let Some(yaml_pacing) = fields.pacing {
+        node.pacing = yaml_to_pacing(yaml_pacing, key)?;
+    }
+
+    // Check if the node has stage directions
+    if let Some(yaml_stage) = fields.stage {
+        node.stage = yaml_to_stage(yaml_stage, key)?;
+    }
+
+    // Check if the node is an intentional ending, e.g. `end: true`
+    if let Some(yaml_end) = fields.end {
+        node.end = yaml_end.as_bool().ok_or_else(|| {
+            TreeError::Validation(format!("YAML end is not a boolean for `{:?}`", key))
+        })?;
+    }
+
+    // Check if the node is unfinished writing, e.g. `todo: true`
+    if let Some(yaml_todo) = fields.todo {
+        node.todo = yaml_todo.as_bool().ok_or_else(|| {
+            TreeError::Validation(format!("YAML todo is not a boolean for `{:?}`", key))
+        })?;
+    }
+
+    // Check if the node has a stable id, e.g. `id: 42`
+    if let Some(yaml_id) = fields.id {
+        let id = yaml_id
+            .as_i64()
+            .and_then(|id| u64::try_from(id).ok())
+            .ok_or_else(|| {
+                TreeError::Validation(format!(
+                    "YAML id is not a non-negative integer for `{:?}`",
+                    key
+                ))
+            })?;
+        node.id = Some(id);
+    }
+
+    resolve_affinity(&mut node)?;
+    Ok(node)
+}
+
+fn yaml_to_switch(
+    key: &str,
+    yaml_variable: &Yaml,
+    fields: &NodeFields<'_>,
+    links: &mut Vec<Link>,
+) -> Result<String, ImportError> {
+    // Variable names are checked by parsing them as a flag
+    let variable = yaml_variable
+        .as_str()
+        .filter(|variable| {
+            Condition::parse(variable).is_ok_and(|condition| {
+                matches!(
+                    condition.clauses.as_slice(),
+                    [Clause::Flag { negated: false, .. }]
+                )
+            })
+        })
+        .ok_or_else(|| {
+            TreeError::Validation(format!(
+                "YAML switch is not a variable name for `{:?}`",
+                key
+            ))
+        })?;
+    if fields.links.is_some() {
+        return Err(TreeError::Validation(format!(
+            "YAML switch contains `links` rather than `cases` for `{:?}`",
+            key
+        ))
+        .into());
+    }
+
+    // Each case becomes a link whose condition compares the variable to its value
+    if let Some(yaml_cases) = fields.cases {
+        let cases = yaml_cases.as_hash().ok_or_else(|| {
+            TreeError::Validation(format!("YAML switch cases are not a hash for `{:?}`", key))
+        })?;
+        for (yaml_value, yaml_target) in cases {
+            let value = yaml_to_value(yaml_value).ok_or_else(|| {
+                TreeError::Validation(format!(
+                    "YAML switch case is not a boolean, integer, or string for `{:?}`",
+                    key
+                ))
+            })?;
+            let target = yaml_target.as_str().ok_or_else(|| {
+                TreeError::Validation(format!(
+                    "YAML switch case target is not a string for `{:?}`",
+                    key
+                ))
+            })?;
+            let mut link = Link::silent(target);
+            link.condition = Some(Condition {
+                clauses: vec![Clause::Compare {
+                    name: variable.to_owned(),
+                    op: Op::Eq,
+                    value,
+                }],
+            });
+            links.push(link);
+        }
+    }
+    if let Some(yaml_default) = fields.default {
+        let target = yaml_default.as_str().ok_or_else(|| {
+            TreeError::Validation(format!(
+                "YAML switch default is not a string for `{:?}`",
+                key
+            ))
+        })?;
+        links.push(Link::silent(target));
+    }
+    if links.is_empty() {
+        return Err(TreeError::Validation(format!(
+            "YAML switch has no cases or default for `{:?}`",
+            key
+        ))
+        .into());
+    }
+
+    Ok(variable.to_owned())
+}
+
+// Dialogue keeps no trailing line breaks, so `|` and `|-` block scalars import alike
+fn trim_dialogue(dialogue: &str) -> &str {
+    dialogue.trim_end_matches('\n')
+}
+
+fn resolve_affinity(node: &mut Node) -> Result<(), TreeError> {
+    // Links which adjust an affinity without naming a character adjust the last speaker of their node
+    let key = &node.key;
+    let speaker = node.lines.last().map(|line| &line.speaker);
+    for link in &mut node.links {
+        for (character, _) in &mut link.affinity {
+            if character.is_empty() {
+                *character = speaker.cloned().ok_or_else(|| {
+                    TreeError::Validation(format!(
+                        "YAML link `affinity` names no character for `{:?}`, whose dialogue has no speaker",
+                        key
+                    ))
+                })?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn yaml_to_lines(key: &str, yaml_lines: &[Yaml]) -> Result<Vec<Line>, ImportError> {
+    if yaml_lines.is_empty() {
+        return Err(TreeError::Validation(format!(
+            "YAML dialogue lines have a length of 0 for `{:?}`",
+            key
+        ))
+        .into());
+    }
+
+    // Each line names its speaker and text, and may annotate its emotion
+    let mut lines = Vec::new();
+    for yaml_line in yaml_lines {
+        let field = |name: &str| {
+            yaml_line[name].as_str().ok_or_else(|| {
+                TreeError::Validation(format!(
+                    "YAML dialogue line does not contain a string `{}` for `{:?}`",
+                    name, key
+                ))
+            })
+        };
+        let line_hash = yaml_line.as_hash().ok_or_else(|| {
+            TreeError::Validation(format!(
+                "YAML dialogue line is not a hash for `{:?}`: '{:?}'",
+                key, yaml_line
+            ))
+        })?;
+        // Unknown fields are ignored
+        let yaml_emotion = line_hash.get(&Yaml::String("emotion".to_string()));
+        let mut line = Line::new(field("speaker")?, trim_dialogue(field("text")?));
+        if yaml_emotion.is_some() {
+            let emotion = field("emotion")?;
+            if emotion.is_empty() {
+                return Err(TreeError::Validation(format!(
+                    "YAML dialogue line emotion is empty for `{:?}`",
+                    key
+                ))
+                .into());
+            }
+            line.emotion = Some(emotion.to_owned());
+        }
+        lines.push(line);
+    }
+
+    Ok(lines)
+}
+
+fn yaml_to_links(yaml: &Yaml) -> Result<Vec<Link>, ImportError> {
+    // Unwrap link array
+    let links = yaml.as_vec().ok_or_else(|| {
+        TreeError::Validation(format!("YAML link data is not an array: '{:?}'", yaml))
+    })?;
+
+    if links.is_empty() {
+        return Err(TreeError::Validation("Links array has a length of 0".into()).into());
+    }
+
+    // Collect links
+    let mut link_buf = Vec::<Link>::new();
+    for yaml_link in links {
+        let yaml_link_hash = yaml_link.as_hash().ok_or_else(|| {
+            TreeError::Validation(format!("YAML link is not a hash: '{:?}'", yaml))
+        })?;
+
+        // Group form, e.g. `- group: "Ask about..."` with nested `links`
+        if is_link_group(yaml_link_hash) {
+            let heading = yaml_link_hash[&Yaml::from_str("group")]
+                .as_str()
+                .ok_or_else(|| {
+                    TreeError::Validation(format!(
+                        "YAML link group heading is not a string: '{:?}'",
+                        yaml_link_hash
+                    ))
+                })?;
+            for mut link in yaml_to_links(&yaml_link_hash[&Yaml::from_str("links")])? {
+                if link.group.is_some() {
+                    return Err(TreeError::Validation(format!(
+                        "YAML link groups are nested in `{:?}`",
+                        heading
+                    ))
+                    .into());
+                }
+                link.group = Some(heading.to_owned());
+                link_buf.push(link);
+            }
+            continue;
+        }
+
+        // Structured form, e.g. `- to: end` with `dialogue: "Bye"`
+        if is_structured_link(yaml_link_hash) {
+            let link = yaml_to_structured_link(yaml_link_hash)?;
+            link_buf.push(link);
+            continue;
+        }
+
+        // Shorthand form, e.g. `- end: "Bye"`, which holds exactly one link, since a hash of several would hide a missing `-`
+        let (yaml_to, yaml_dialogue) = match yaml_link_hash.iter().next() {
+            Some(entry) if yaml_link_hash.len() == 1 => entry,
+            _ => {
+                let keys: Vec<String> = yaml_link_hash
+                    .keys()
+                    .map(|key| format!("{:?}", key.as_str().unwrap_or("?")))
+                    .collect();
+                return Err(TreeError::Validation(format!(
+                    "YAML shorthand link must map exactly one target to its dialogue, but maps [{}]; write each link as its own `- target: dialogue` item, or give its attributes with `to` and `dialogue`",
+                    keys.join(", ")
+                ))
+                .into());
+            }
+        };
+        let to = yaml_to.as_str().ok_or_else(|| {
+            TreeError::Validation(format!("YAML link name is not a string:  '{:?}'", yaml))
+        })?;
+        let link = Link {
+            dialogue: yaml_to_link_dialogue(yaml_dialogue, to)?,
+            ..Link::silent(to)
+        };
+        link_buf.push(link);
+    }
+
+    Ok(link_buf)
+}
+
+fn yaml_to_link_dialogue(yaml: &Yaml, to: &str) -> Result<Option<String>, ImportError> {
+    // Null dialogue, e.g. `- end: ~`, makes a silent link
+    if yaml.is_null() {
+        return Ok(None);
+    }
+    let dialogue = yaml.as_str().ok_or_else(|| {
+        TreeError::Validation(format!("YAML link dialogue is not a string for `{:?}`", to))
+    })?;
+    Ok(Some(trim_dialogue(dialogue).to_owned()))
+}
+
+fn is_link_group(hash: &yaml::Hash) -> bool {
+    // A group names its heading with `group`, and holds an array of `links`, where unknown fields are ignored
+    !hash.contains_key(&Yaml::from_str("to"))
+        && hash.contains_key(&Yaml::from_str("group"))
+        && hash
+            .get(&Yaml::from_str("links"))
+            .is_some_and(|links| links.as_vec().is_some())
+}
+
+fn is_structured_link(hash: &yaml::Hash) -> bool {
+    // A structured link names its target with `to`, and its dialogue, if it has any, with `dialogue` (or `text`)
+    hash.contains_key(&Yaml::from_str("to"))
+}
+
+fn yaml_to_structured_link(hash: &yaml::Hash) -> Result<Link, ImportError> {
+    // Unwrap target
+    let to = hash[&Yaml::from_str("to")].as_str().ok_or_else(|| {
+        TreeError::Validation(format!("YAML link `to` is not a string: '{:?}'", hash))
+    })?;
+
+    // Unwrap dialogue, which may be given as `dialogue` or `text`, but not both, and is silent if neither is given
+    let dialogue_key = Yaml::from_str("dialogue");
+    let text_key = Yaml::from_str("text");
+    let yaml_dialogue = match (hash.get(&dialogue_key), hash.get(&text_key)) {
+        (Some(_), Some(_)) => {
+            return Err(TreeError::Validation(format!(
+                "YAML link specifies both `dialogue` and `text` for `{:?}`",
+                to
+            ))
+            .into())
+        }
+        (Some(dialogue), None) | (None, Some(dialogue)) => dialogue,
+        (None, None) => &Yaml::Null,
+    };
+    let mut link = Link {
+        dialogue: yaml_to_link_dialogue(yaml_dialogue, to)?,
+        ..Link::silent(to)
+    };
+
+    // Unwrap priority
+    if let Some(yaml_priority) = hash.get(&Yaml::from_str("priority")) {
+        let priority = yaml_priority
+            .as_i64()
+            .and_then(|priority| i32::try_from(priority).ok())
+            .ok_or_else(|| {
+                TreeError::Validation(format!(
+                    "YAML link priority is not an integer for `{:?}`",
+                    to
+                ))
+            })?;
+        link.priority = Some(priority);
+    }
+
+    // Unwrap weight
+    if let Some(yaml_weight) = hash.get(&Yaml::from_str("weight")) {
+        let weight = yaml_weight
+            .as_i64()
+            .and_then(|weight| u32::try_from(weight).ok())
+            .ok_or_else(|| {
+                TreeError::Validation(format!(
+                    "YAML link weight is not a non-negative integer for `{:?}`",
+                    to
+                ))
+            })?;
+        link.weight = Some(weight);
+    }
+
+    // Unwrap condition
+    if let Some(yaml_condition) = hash.get(&Yaml::from_str("if")) {
+        let condition = yaml_condition.as_str().ok_or_else(|| {
+            TreeError::Validation(format!(
+                "YAML link condition is not a string for `{:?}`",
+                to
+            ))
+        })?;
+        link.condition = Some(Condition::parse(condition)?);
+    }
+
+    // Unwrap required items, which are an item or an array of items, each adding a flag to the condition
+    if let Some(yaml_items) = hash.get(&Yaml::from_str("requires_item")) {
+        let yaml_items = match yaml_items {
+            Yaml::Array(yaml_items) => yaml_items.as_slice(),
+            _ => core::slice::from_ref(yaml_items),
+        };
+        for yaml_item in yaml_items {
+            let item = yaml_item
+                .as_str()
+                .filter(|item| !item.is_empty())
+                .ok_or_else(|| {
+                    TreeError::Validation(format!(
+                        "YAML link `requires_item` is not a string for `{:?}`",
+                        to
+                    ))
+                })?;
+            link.condition
+                .get_or_insert_with(|| Condition {
+                    clauses: Vec::new(),
+                })
+                .clauses
+                .push(Clause::Flag {
+                    name: inventory::variable(item),
+                    negated: false,
+                });
+        }
+    }
+
+    // Unwrap the reason the link is disabled, if it is presented when its condition fails
+    if let Some(yaml_disabled) = hash.get(&Yaml::from_str("disabled")) {
+        let reason = yaml_disabled.as_str().ok_or_else(|| {
+            TreeError::Validation(format!(
+                "YAML link `disabled` reason is not a string for `{:?}`",
+                to
+            ))
+        })?;
+        link.when_unmet = WhenUnmet::Disabled(reason.to_owned());
+    }
+
+    // Unwrap assignments
+    if let Some(yaml_set) = hash.get(&Yaml::from_str("set")) {
+        let assignments = yaml_set.as_hash().ok_or_else(|| {
+            TreeError::Validation(format!("YAML link `set` is not a hash for `{:?}`", to))
+        })?;
+        for (yaml_name, yaml_value) in assignments {
+            let name = yaml_name.as_str().ok_or_else(|| {
+                TreeError::Validation(format!(
+                    "YAML link `set` variable is not a string for `{:?}`",
+                    to
+                ))
+            })?;
+            let value = yaml_to_value(yaml_value).ok_or_else(|| {
+                TreeError::Validation(format!(
+                    "YAML link `set` value is not a boolean, integer, or string for `{:?}`",
+                    name
+                ))
+            })?;
+            link.set.push((name.to_owned(), value));
+        }
+    }
+
+    // Unwrap affinity adjustments, which are a hash of characters to amounts, or an amount for the speaker of the node
+    if let Some(yaml_affinity) = hash.get(&Yaml::from_str("affinity")) {
+        let amount = |yaml_amount: &Yaml| {
+            yaml_amount.as_i64().ok_or_else(|| {
+                TreeError::Validation(format!(
+                    "YAML link `affinity` amount is not an integer for `{:?}`",
+                    to
+                ))
+            })
+        };
+        match yaml_affinity {
+            Yaml::Hash(adjustments) => {
+                for (yaml_character, yaml_amount) in adjustments {
+                    let character = yaml_character
+                        .as_str()
+                        .filter(|character| !character.is_empty())
+                        .ok_or_else(|| {
+                            TreeError::Validation(format!(
+                                "YAML link `affinity` character is not a string for `{:?}`",
+                                to
+                            ))
+                        })?;
+                    link.affinity
+                        .push((character.to_owned(), amount(yaml_amount)?));
+                }
+            }
+            // The speaker is filled in once the node's lines are known
+            _ => link.affinity.push((String::new(), amount(yaml_affinity)?)),
+        }
+    }
+
+    // Unwrap tags
+    if let Some(yaml_tags) = hash.get(&Yaml::from_str("tags")) {
+        let tags = yaml_tags.as_vec().ok_or_else(|| {
+            TreeError::Validation(format!("YAML link tags are not an array for `{:?}`", to))
+        })?;
+        for tag in tags {
+            let tag = tag.as_str().ok_or_else(|| {
+                TreeError::Validation(format!("YAML link tag is not a string for `{:?}`", to))
+            })?;
+            link.tags.push(tag.into());
+        }
+    }
+
+    // Remaining fields are reserved for link attributes and are ignored for now
+    Ok(link)
+}
+
+#[cfg(test)]
+#[test]
+fn test_import() {
+    let bad_file = "examples/dialogue_files/ex_bad.convo.yml";
+    assert!(import(bad_file).is_err());
+
+    let good_file = "examples/dialogue_files/ex_min.convo.yml";
+    assert!(import(good_file).is_ok());
+}
+
+#[test]
+fn test_source_to_tree() {
+    // Test a minimal valid source
+    let source = r#"---
+    root: start
+    nodes:
+        start:
+            dialogue: "It's a bad day."
+    "#;
+    assert!(source_to_tree(source).is_ok());
+}
+
+#[test]
+fn test_source_to_tree_root_exists() {
+    use crate::error::ImportError::Validation;
+
+    // Invalid: YAML must contain a top-level element called `root`.
+    let source = r#"---
+    nodes:
+        start:
+            dialogue: "It's a bad day."
+    "#;
+    assert!(matches!(source_to_tree(source).unwrap_err(), Validation(_)));
+
+    // Invalid: YAML must contain a top-level element called `root` which points to a real node
+    let source = r#"---
+    root: abc_123
+    nodes:
+        start:
+            dialogue: "It's a bad day."
+    "#;
+    assert!(matches!(source_to_tree(source).unwrap_err(), Validation(_)));
+}
+
+#[test]
+fn test_source_to_tree_nodes_exist() {
+    use crate::error::ImportError::Validation;
+
+    // Invalid: `nodes` must contain at least 1 node.
+    let source = r#"---
+    root: start
+    nodes:
+    "#;
+    assert!(matches!(source_to_tree(source).unwrap_err(), Validation(_)));
+}
+
+#[test]
+fn test_source_to_tree_attributes() {
+    use crate::error::ImportError::Validation;
+
+    // `start` does not contain dialogue.
+    let source = r#"---
+    root: start
+    nodes:
+        start:
+            links:
+            - end: "I'm rudely in a hurry."
+        end:
+            dialogue: "Ok, let's talk some other time."
+    "#;
+    assert!(matches!(source_to_tree(source).unwrap_err(), Validation(_)));
+}
+
+#[test]
+#[ignore = "Waiting on issue #3"]
+fn test_source_to_tree_unreachable_nodes() {
+    use crate::error::ImportError::Validation;
+
+    // `end` is an orphan node. It is not reachable.
+    let source = r#"---
+    root: start
+    nodes:
+        start:
+            dialogue: "Hello, how are you?"
+        end:
+            dialogue: "Ok, let's talk some other time."
+    "#;
+    assert!(matches!(source_to_tree(source).unwrap_err(), Validation(_)));
+
+    // `end` and `fork` are orphans because the root node (`start`) is a leaf node.
+    let source = r#"---
+    root: start
+    nodes:
+        fork:
+            dialogue: "I make sure no one is an orphan by being the parent to all."
+            links:
+                - start: "I link to start"
+                - end: "I link to the end"
+                - fork: "I even link to myself!"
+        start:
+            dialogue: "Hello, how are you?"
+        end:
+            dialogue: "Ok, let's talk some other time."
+    "#;
+    assert!(matches!(source_to_tree(source).unwrap_err(), Validation(_)));
+}
+
+#[test]
+#[ignore = "Waiting on issue #10"]
+fn test_source_to_tree_invalid_links() {
+    use crate::error::ImportError::Validation;
+
+    // `not_a_real_key` is an invalid reference key.
+    let source = r#"---
+    root: start
+    nodes:
+        start:
+            dialogue: "I am the start node"
+            links:
+                - start: "I am valid and link to myself"
+                - not_a_real_key: "I do not link to a valid key"
+    "#;
+
+    assert!(matches!(source_to_tree(source).unwrap_err(), Validation(_)));
+}
+
+#[test]
+fn test_source_to_tree_shorthand_links() {
+    use crate::error::ImportError::Validation;
+
+    // Shorthand links hold one target each, rather than fanning out
+    let source = r#"---
+    root: start
+    nodes:
+        start:
+            dialogue: "I am the start node"
+            links:
+                - start: "Again."
+                  end: "Goodbye."
+        end:
+            dialogue: "I am the end node"
+    "#;
+    match source_to_tree(source).unwrap_err() {
+        Validation(TreeError::Validation(message)) => {
+            assert!(message.contains(r#"maps ["start", "end"]"#), "{}", message)
+        }
+        e => panic!("{:?}", e),
+    }
+    assert!(source_to_tree(&source.replace("  end: \"Goodbye.\"", "- end: \"Goodbye.\"")).is_ok());
+}
+
+#[test]
+fn test_source_to_tree_silent_links() {
+    // Links with null dialogue are silent, in either form
+    let source = r#"---
+    root: start
+    nodes:
+        start:
+            dialogue: "The lights go out."
+            links:
+                - end: ~
+                - to: end
+                  dialogue: ~
+                  priority: 1
+                - end: ""
+        end:
+            dialogue: "Morning comes."
+    "#;
+    let tree = source_to_tree(source).unwrap();
+    let links = &tree.node("start").unwrap().links;
+    assert_eq!(Link::silent("end"), links[0]);
+    assert!(links[1].is_silent());
+    assert_eq!(Some(String::new()), links[2].dialogue);
+
+    // Silent links are written with null dialogue
+    let exported = crate::exporter::tree_to_source(&tree).unwrap();
+    assert!(exported.contains("- end: ~"));
+    assert!(exported.contains("dialogue: ~"));
+    assert!(exported.contains("- end: \"\""));
+    assert_eq!(tree, source_to_tree(&exported).unwrap());
+
+    // Structured links without dialogue are silent, with or without attributes
+    let source = r#"---
+    root: start
+    nodes:
+        start:
+            dialogue: "The lights go out."
+            links:
+                - to: end
+                - to: end
+                  weight: 2
+        end:
+            dialogue: "Morning comes."
+    "#;
+    let tree = source_to_tree(source).unwrap();
+    let links = &tree.node("start").unwrap().links;
+    assert_eq!(Link::silent("end"), links[0]);
+    assert_eq!(
+        Link {
+            weight: Some(2),
+            ..Link::silent("end")
+        },
+        links[1]
+    );
+    assert_eq!(tree, reader_to_tree(source.as_bytes()).unwrap());
+
+    // Links to a node keyed `to` are written in structured form, so they round trip
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "The lights go out.");
+    start.links.push(Link::new("to", "Go on"));
+    start.links.push(Link::silent("to"));
+    tree.insert_node(start);
+    tree.insert_node(Node::new("to", "Morning comes."));
+    tree.set_root_key("start").unwrap();
+    let exported = crate::exporter::tree_to_source(&tree).unwrap();
+    assert!(exported.contains("- to: to"), "{}", exported);
+    let imported = source_to_tree(&exported).unwrap();
+    assert_eq!(
+        tree.node("start").unwrap().links,
+        imported.node("start").unwrap().links
+    );
+}
+
+#[test]
+fn test_source_to_tree_structured_links() {
+    use crate::error::ImportError::Validation;
+
+    // Structured and shorthand links may be mixed
+    let source = r#"---
+    root: start
+    nodes:
+        start:
+            dialogue: "Hello, how are you?"
+            links:
+                - to: end
+                  dialogue: "I'm rudely in a hurry."
+                - to: end
+                  text: "Goodbye."
+                - end: "See you."
+        end:
+            dialogue: "Ok, let's talk some other time."
+    "#;
+    let tree = source_to_tree(source).unwrap();
+    let links = &tree.node("start").unwrap().links;
+    assert_eq!(3, links.len());
+    assert_eq!(Link::new("end", "I'm rudely in a hurry."), links[0]);
+    assert_eq!(Link::new("end", "Goodbye."), links[1]);
+    assert_eq!(Link::new("end", "See you."), links[2]);
+
+    // Structured links may carry a priority
+    let source = r#"---
+    root: start
+    nodes:
+        start:
+            dialogue: "Hello, how are you?"
+            links:
+                - to: start
+                  dialogue: "Again."
+                  priority: -3
+    "#;
+    let tree = source_to_tree(source).unwrap();
+    assert_eq!(Some(-3), tree.node("start").unwrap().links[0].priority);
+
+    // Structured links may be presented disabled when their condition fails
+    let source = r#"---
+    root: start
+    nodes:
+        start:
+            dialogue: "What'll it be?"
+            links:
+                - to: start
+                  dialogue: "The sword."
+                  if: "gold >= 10"
+                  disabled: "Requires 10 gold"
+    "#;
+    let tree = source_to_tree(source).unwrap();
+    assert_eq!(
+        WhenUnmet::Disabled("Requires 10 gold".into()),
+        tree.node("start").unwrap().links[0].when_unmet
+    );
+    let exported = crate::exporter::tree_to_source(&tree).unwrap();
+    assert_eq!(tree, source_to_tree(&exported).unwrap());
+
+    // Invalid: `disabled` must be a string
+    let source = r#"---
+    root: start
+    nodes:
+        start:
+            dialogue: "What'll it be?"
+            links:
+                - to: start
+                  dialogue: "The sword."
+                  disabled: true
+    "#;
+    assert!(matches!(source_to_tree(source).unwrap_err(), Validation(_)));
+
+    // Invalid: `dialogue` and `text` are mutually exclusive
+    let source = r#"---
+    root: start
+    nodes:
+        start:
+            dialogue: "Hello, how are you?"
+            links:
+                - to: start
+                  dialogue: "Again."
+                  text: "Again?"
+    "#;
+    assert!(matches!(source_to_tree(source).unwrap_err(), Validation(_)));
+
+    // Invalid: `priority` must be an integer
+    let source = r#"---
+    root: start
+    nodes:
+        start:
+            dialogue: "Hello, how are you?"
+            links:
+                - to: start
+                  dialogue: "Again."
+                  priority: high
+    "#;
+    assert!(matches!(source_to_tree(source).unwrap_err(), Validation(_)));
+
+    // Invalid: `to` must be a string
+    let source = r#"---
+    root: start
+    nodes:
+        start:
+            dialogue: "Hello, how are you?"
+            links:
+                - to: [start]
+                  dialogue: "Again."
+    "#;
+    assert!(matches!(source_to_tree(source).unwrap_err(), Validation(_)));
+}
+
+#[test]
+fn test_reader_to_tree() {
+    use crate::error::ImportError::{MultipleDocumentsProvided, Validation, IO};
+
+    // Should match the document importer
+    for path in [
+        "examples/dialogue_files/ex_1.convo.yml",
+        "examples/dialogue_files/ex_min.convo.yml",
+    ] {
+        assert_eq!(import(path).unwrap(), import_streaming(path).unwrap());
+    }
+    let source = r#"---
+    nodes:
+        start:
+            dialogue: "Hello"
+            extra: [1, { a: b }]
+            links:
+                - end: "Bye"
+                - to: end
+                  text: "Later"
+                  priority: 2
+        end:
+            dialogue: 'Goodbye'
+    unknown: { nested: [true] }
+    root: start
+    "#;
+    assert_eq!(
+        source_to_tree(source).unwrap(),
+        reader_to_tree(source.as_bytes()).unwrap()
+    );
+
+    // Should reject the same invalid sources
+    assert!(matches!(
+        import_streaming("examples/dialogue_files/ex_bad.convo.yml").unwrap_err(),
+        Validation(_)
+    ));
+    for source in [
+        "---\nnodes:\n    start:\n        dialogue: Hi\n",
+        "---\nroot: start\nnodes:\n",
+        "---\nroot: end\nnodes:\n    start:\n        dialogue: Hi\n",
+        "---\nroot: start\nnodes:\n    start:\n        links:\n            - start: Again\n",
+        "---\nroot: start\nnodes:\n    start:\n        dialogue: 1\n",
+    ] {
+        assert!(matches!(
+            reader_to_tree(source.as_bytes()).unwrap_err(),
+            Validation(_)
+        ));
+    }
+    let source = "---\nroot: start\nnodes:\n    start:\n        dialogue: Hi\n---\nroot: start\n";
+    assert!(matches!(
+        reader_to_tree(source.as_bytes()).unwrap_err(),
+        MultipleDocumentsProvided()
+    ));
+
+    // Documents which are not mappings have no root
+    for source in ["---\n- root\n- start\n", "---\nroot\n"] {
+        match reader_to_tree(source.as_bytes()).unwrap_err() {
+            Validation(TreeError::Validation(message)) => {
+                assert!(
+                    message.contains("top-level string key for `root`"),
+                    "{}",
+                    message
+                )
+            }
+            e => panic!("{:?}", e),
+        }
+        assert!(matches!(
+            source_to_tree(source).unwrap_err(),
+            Validation(TreeError::Validation(_))
+        ));
+    }
+
+    // Aliases are not supported
+    let source = "---\nroot: start\nnodes:\n    start:\n        dialogue: &hi Hi\n    end:\n        dialogue: *hi\n";
+    assert!(source_to_tree(source).is_ok());
+    assert!(matches!(
+        reader_to_tree(source.as_bytes()).unwrap_err(),
+        Validation(_)
+    ));
+
+    // Read errors take precedence
+    struct Broken;
+    impl Read for Broken {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::other("broken"))
+        }
+    }
+    assert!(matches!(reader_to_tree(Broken).unwrap_err(), IO(_)));
+}
+
+#[test]
+fn test_source_to_tree_meta() {
+    use crate::error::ImportError::Validation;
+
+    // Known and custom fields are parsed, in either importer
+    let source = r#"---
+    root: start
+    meta:
+        title: "A Bad Day"
+        tags: [short, "sad"]
+        chapter: "1"
+    nodes:
+        start:
+            dialogue: "It's a bad day."
+    "#;
+    let tree = source_to_tree(source).unwrap();
+    let meta = tree.meta();
+    assert_eq!(Some("A Bad Day"), meta.title.as_deref());
+    assert!(meta.author.is_none());
+    assert_eq!(vec!["short", "sad"], meta.tags);
+    assert_eq!(Some("1"), meta.custom.get("chapter").map(String::as_str));
+    assert_eq!(tree, reader_to_tree(source.as_bytes()).unwrap());
+
+    // An empty section holds no metadata
+    let source = "---\nmeta:\nroot: start\nnodes:\n    start:\n        dialogue: Hi\n";
+    assert!(source_to_tree(source).unwrap().meta().is_empty());
 
-    Ok(buf)
+    // Invalid: values must be strings, and tags an array of strings
+    for meta in [
+        "[a]",
+        "{ title: 1 }",
+        "{ tags: a }",
+        "{ tags: [[a]] }",
+        "{ 1: a }",
+    ] {
+        let source = format!(
+            "---\nmeta: {}\nroot: start\nnodes:\n    start:\n        dialogue: Hi\n",
+            meta
+        );
+        assert!(matches!(
+            source_to_tree(&source).unwrap_err(),
+            Validation(_)
+        ));
+        assert!(matches!(
+            reader_to_tree(source.as_bytes()).unwrap_err(),
+            Validation(_)
+        ));
+    }
 }
 
-fn yaml_to_tree(yaml: &Yaml) -> Result<Tree, ImportError> {
-    // This needs some major cleanup
+#[test]
+fn test_import_meta() {
+    let meta = import_meta("examples/dialogue_files/ex_1.convo.yml").unwrap();
+    assert_eq!(
+        &meta,
+        import("examples/dialogue_files/ex_1.convo.yml")
+            .unwrap()
+            .meta()
+    );
+    assert_eq!(vec!["example", "branching"], meta.tags);
 
-    let root_key = yaml["root"].as_str().ok_or_else(|| {
-        TreeError::Validation("YAML does not contain top-level string key for `root`".into())
-    })?;
+    // Nodes are not validated, and missing metadata is empty
+    let meta = import_meta("examples/dialogue_files/ex_bad.convo.yml").unwrap();
+    assert!(meta.is_empty());
+}
 
-    let node_map = yaml["nodes"].as_hash().ok_or_else(|| {
-        TreeError::Validation("YAML does not contain top-level hash for `nodes`".into())
-    })?;
+#[test]
+fn test_source_to_tree_variables() {
+    use crate::{
+        condition::{Clause, Op},
+        error::ImportError::Validation,
+        exporter,
+    };
 
-    // Check length of nodes
-    if node_map.is_empty() {
-        return Err(TreeError::Validation("Node map has a length of 0".into()).into());
-    }
+    // Declarations, conditions, and assignments are parsed, in either importer
+    let source = r#"---
+    root: start
+    variables:
+        met: bool
+        gold: int
+        mood: [happy, angry]
+    nodes:
+        start:
+            dialogue: "Hello."
+            links:
+                - to: start
+                  dialogue: "Pay up."
+                  if: "!met && gold > 2"
+                  set: { met: true, gold: 0, mood: angry }
+    "#;
+    let tree = source_to_tree(source).unwrap();
+    assert_eq!(Some(&Type::Int), tree.variables().get("gold"));
+    assert_eq!(
+        Some(&Type::Enum(vec!["happy".into(), "angry".into()])),
+        tree.variables().get("mood")
+    );
+    let link = &tree.node("start").unwrap().links[0];
+    assert_eq!(
+        Clause::Compare {
+            name: "gold".into(),
+            op: Op::Gt,
+            value: Value::Int(2)
+        },
+        link.condition.as_ref().unwrap().clauses[1]
+    );
+    assert_eq!(
+        vec![
+            ("met".into(), Value::Bool(true)),
+            ("gold".into(), Value::Int(0)),
+            ("mood".into(), Value::from("angry"))
+        ],
+        link.set
+    );
+    assert_eq!(tree, reader_to_tree(source.as_bytes()).unwrap());
 
-    // Insert nodes
-    let mut tree = Tree::new();
-    for (key, value) in node_map.iter() {
-        let node = yaml_to_node(key, value)?;
-        tree.nodes.insert(node.key.clone(), node);
-    }
+    // They round-trip through the exporter
+    let exported = exporter::tree_to_source(&tree).unwrap();
+    assert_eq!(tree, source_to_tree(&exported).unwrap());
 
-    // Set root and current
-    if !tree.nodes.contains_key(root_key) {
-        return Err(TreeError::NodeDNE(root_key.into()).into());
+    // Invalid declarations, conditions, and assignments
+    let link = "                - to: start\n                  dialogue: Again.\n";
+    for (variables, attribute) in [
+        ("[gold]", ""),
+        ("{ gold: float }", ""),
+        ("{ mood: [] }", ""),
+        ("{ mood: [1] }", ""),
+        ("{}", "                  if: \"gold >\"\n"),
+        ("{}", "                  if: [gold]\n"),
+        ("{}", "                  set: [gold]\n"),
+        ("{}", "                  set: { gold: [1] }\n"),
+    ] {
+        let source = format!(
+            "---\nroot: start\nvariables: {}\nnodes:\n    start:\n        dialogue: Hi\n        links:\n{}{}",
+            variables, link, attribute
+        );
+        assert!(
+            matches!(source_to_tree(&source).unwrap_err(), Validation(_)),
+            "{}",
+            source
+        );
     }
+}
 
-    // Safety : Sound code - root node guaranteed to exist, per above
-    unsafe {
-        tree.set_root_key_unchecked(root_key);
-        tree.set_current_key_unchecked(root_key);
-    }
+#[test]
+fn test_source_to_tree_node_tags() {
+    use crate::{error::ImportError::Validation, exporter};
 
-    Ok(tree)
+    let source = r#"---
+    root: start
+    nodes:
+        start:
+            dialogue: "The end."
+            tags: [ending, good]
+    "#;
+    let tree = source_to_tree(source).unwrap();
+    assert_eq!(vec!["ending", "good"], tree.node("start").unwrap().tags);
+    assert_eq!(tree, reader_to_tree(source.as_bytes()).unwrap());
+    let exported = exporter::tree_to_source(&tree).unwrap();
+    assert_eq!(tree, source_to_tree(&exported).unwrap());
+
+    // Tags must be an array of strings
+    for tags in ["ending", "[[ending]]"] {
+        let source = format!(
+            "---\nroot: start\nnodes:\n    start:\n        dialogue: Hi\n        tags: {}\n",
+            tags
+        );
+        assert!(matches!(
+            source_to_tree(&source).unwrap_err(),
+            Validation(_)
+        ));
+    }
 }
 
-fn yaml_to_node(yaml_key: &Yaml, yaml_data: &Yaml) -> Result<Node, ImportError> {
-    // Unwrap name
-    let key = yaml_key.as_str().ok_or_else(|| {
-        TreeError::Validation(format!("YAML key is not a string: `{:?}`", yaml_key))
-    })?;
+#[test]
+fn test_source_to_tree_end() {
+    use crate::{error::ImportError::Validation, exporter};
 
-    // Unwrap data
-    let data = yaml_data.as_hash().ok_or_else(|| {
-        TreeError::Validation(format!("YAML data is not a hash: '{:?}'", yaml_data))
-    })?;
+    let source = r#"---
+    root: start
+    nodes:
+        start:
+            dialogue: "Leaving so soon?"
+            links:
+                - end: "Yes."
+        end:
+            dialogue: "Farewell."
+            end: true
+    "#;
+    let tree = source_to_tree(source).unwrap();
+    assert!(!tree.node("start").unwrap().end);
+    assert!(tree.node("end").unwrap().end);
+    assert_eq!(tree, reader_to_tree(source.as_bytes()).unwrap());
+    let exported = exporter::tree_to_source(&tree).unwrap();
+    assert!(exported.contains("end: true"));
+    assert_eq!(tree, source_to_tree(&exported).unwrap());
 
-    // Unwrap dialogue
-    let dialogue = data
-        .get(&Yaml::from_str("dialogue"))
-        .ok_or_else(|| {
-            TreeError::Validation(format!("YAML does not contain dialogue for `{:?}`", key))
-        })?
-        .as_str()
-        .ok_or_else(|| {
-            TreeError::Validation(format!("YAML dialogue is not a string for `{:?}`", key))
-        })?;
+    // Endings must be booleans
+    let source = "---\nroot: start\nnodes:\n    start:\n        dialogue: Hi\n        end: yes\n";
+    assert!(matches!(source_to_tree(source).unwrap_err(), Validation(_)));
+}
 
-    let mut node = Node::new(key, dialogue);
+#[test]
+fn test_source_to_tree_link_tags() {
+    use crate::{error::ImportError::Validation, exporter};
 
-    // Check if any links exist
-    if let Some(yaml_links) = data.get(&Yaml::from_str("links")) {
-        // Unwrap links
-        let links = yaml_to_links(yaml_links)?;
-        node.links.extend(links);
-    };
+    let source = r#"---
+    root: start
+    nodes:
+        start:
+            dialogue: "Hello."
+            links:
+                - to: start
+                  dialogue: "How does it end?"
+                  tags: [spoiler_act3, mature]
+                - start: "Again."
+    "#;
+    let tree = source_to_tree(source).unwrap();
+    let links = &tree.node("start").unwrap().links;
+    assert_eq!(vec!["spoiler_act3", "mature"], links[0].tags);
+    assert!(links[1].tags.is_empty());
+    let exported = exporter::tree_to_source(&tree).unwrap();
+    assert!(exported.contains("tags:\n"));
+    assert_eq!(tree, source_to_tree(&exported).unwrap());
 
-    Ok(node)
+    // Tags must be an array of strings
+    let source = "---\nroot: a\nnodes:\n    a:\n        dialogue: Hi\n        links:\n            - to: a\n              dialogue: Again\n              tags: mature\n";
+    assert!(matches!(source_to_tree(source).unwrap_err(), Validation(_)));
 }
 
-fn yaml_to_links(yaml: &Yaml) -> Result<Vec<Link>, ImportError> {
-    // Unwrap link array
-    let links = yaml.as_vec().ok_or_else(|| {
-        TreeError::Validation(format!("YAML link data is not an array: '{:?}'", yaml))
-    })?;
+#[test]
+fn test_source_to_tree_affinity() {
+    use crate::{error::ImportError::Validation, exporter};
 
-    if links.is_empty() {
-        return Err(TreeError::Validation("Links array has a length of 0".into()).into());
+    let source = r#"---
+    root: start
+    nodes:
+        start:
+            dialogue:
+                - speaker: guard
+                  text: "Halt!"
+                - speaker: npc_bob
+                  text: "Let them through."
+            links:
+                - to: start
+                  dialogue: "Thanks, Bob."
+                  affinity: +5
+                - to: start
+                  dialogue: "Out of my way."
+                  affinity: { npc_bob: -3, guard: -10 }
+    "#;
+    let tree = source_to_tree(source).unwrap();
+    let links = &tree.node("start").unwrap().links;
+    assert_eq!(vec![("npc_bob".to_owned(), 5)], links[0].affinity);
+    assert_eq!(
+        vec![("npc_bob".to_owned(), -3), ("guard".to_owned(), -10)],
+        links[1].affinity
+    );
+    let exported = exporter::tree_to_source(&tree).unwrap();
+    assert!(exported.contains("affinity:\n"));
+    assert_eq!(tree, source_to_tree(&exported).unwrap());
+
+    // Amounts must be integers, and links without a character adjust the speaker of their node
+    for affinity in ["five", "{ npc_bob: five }", "{ '': 1 }", "1"] {
+        let source = format!(
+            "---\nroot: a\nnodes:\n    a:\n        dialogue: Hi\n        links:\n            - to: a\n              dialogue: Again\n              affinity: {}\n",
+            affinity
+        );
+        assert!(
+            matches!(source_to_tree(&source).unwrap_err(), Validation(_)),
+            "{}",
+            affinity
+        );
     }
+}
 
-    // Collect links
-    let mut link_buf = Vec::<Link>::new();
-    for yaml_link in links {
-        let yaml_link_hash = yaml_link.as_hash().ok_or_else(|| {
-            TreeError::Validation(format!("YAML link is not a hash: '{:?}'", yaml))
-        })?;
+#[test]
+fn test_source_to_tree_requires_item() {
+    use crate::{error::ImportError::Validation, exporter};
 
-        for (yaml_to, yaml_dialogue) in yaml_link_hash {
-            let to = yaml_to.as_str().ok_or_else(|| {
-                TreeError::Validation(format!("YAML link name is not a string:  '{:?}'", yaml))
-            })?;
-            let dialogue = yaml_dialogue.as_str().ok_or_else(|| {
-                TreeError::Validation(format!("YAML link dialogue is not a string for `{:?}`", to))
-            })?;
-            let link = Link::new(to, dialogue);
-            link_buf.push(link);
-        }
+    let source = r#"---
+    root: start
+    nodes:
+        start:
+            dialogue: "A locked door."
+            links:
+                - to: start
+                  dialogue: "Unlock it."
+                  requires_item: rusty_key
+                - to: start
+                  dialogue: "Pick the lock."
+                  if: "!alarmed"
+                  requires_item: [lockpick, torch]
+    "#;
+    let tree = source_to_tree(source).unwrap();
+    let links = &tree.node("start").unwrap().links;
+    assert_eq!(
+        "item(rusty_key)",
+        links[0].condition.as_ref().unwrap().to_string()
+    );
+    assert_eq!(
+        "!alarmed && item(lockpick) && item(torch)",
+        links[1].condition.as_ref().unwrap().to_string()
+    );
+    let exported = exporter::tree_to_source(&tree).unwrap();
+    assert_eq!(tree, source_to_tree(&exported).unwrap());
+
+    // Items must be non-empty strings
+    for items in ["''", "1", "[key, 1]", "{ key: 1 }"] {
+        let source = format!(
+            "---\nroot: a\nnodes:\n    a:\n        dialogue: Hi\n        links:\n            - to: a\n              dialogue: Again\n              requires_item: {}\n",
+            items
+        );
+        assert!(
+            matches!(source_to_tree(&source).unwrap_err(), Validation(_)),
+            "{}",
+            items
+        );
     }
+}
 
-    Ok(link_buf)
+#[test]
+fn test_source_to_tree_ids() {
+    use crate::{error::ImportError::Validation, exporter};
+
+    let source = r#"---
+    root: start
+    nodes:
+        start:
+            dialogue: "Hello."
+            links:
+                - middle: "Go on."
+        middle:
+            id: 5
+            dialogue: "And then?"
+            links:
+                - end: "Finish."
+        end:
+            dialogue: "The end."
+    "#;
+    let tree = source_to_tree(source).unwrap();
+    assert_eq!(Some(6), tree.node("start").unwrap().id);
+    assert_eq!(Some(5), tree.node("middle").unwrap().id);
+    assert_eq!(Some(7), tree.node("end").unwrap().id);
+    assert_eq!("middle", tree.node_by_id(5).unwrap().key);
+    assert_eq!(tree, reader_to_tree(source.as_bytes()).unwrap());
+
+    // Ids survive renaming a key once exported
+    let exported = exporter::tree_to_source(&tree).unwrap();
+    let renamed = source_to_tree(&exported.replace("middle", "center")).unwrap();
+    assert_eq!("center", renamed.node_by_id(5).unwrap().key);
+    assert_eq!(Some(6), renamed.node("start").unwrap().id);
+
+    // Ids must be unique non-negative integers
+    let source = "---\nroot: a\nnodes:\n    a:\n        id: 1\n        dialogue: Hi\n    b:\n        id: 1\n        dialogue: Bye\n";
+    assert!(matches!(source_to_tree(source).unwrap_err(), Validation(_)));
+    let source = "---\nroot: a\nnodes:\n    a:\n        id: -1\n        dialogue: Hi\n";
+    assert!(matches!(source_to_tree(source).unwrap_err(), Validation(_)));
 }
 
-#[cfg(test)]
 #[test]
-fn test_import() {
-    let bad_file = "examples/dialogue_files/ex_bad.convo.yml";
-    assert!(import(bad_file).is_err());
+fn test_source_to_tree_entries() {
+    use crate::{error::ImportError::Validation, exporter, Walker};
 
-    let good_file = "examples/dialogue_files/ex_min.convo.yml";
-    assert!(import(good_file).is_ok());
+    let source = r#"---
+    root: greet
+    entries:
+        greeting: greet
+        angry: snarl
+    nodes:
+        greet:
+            dialogue: "Well met!"
+            end: true
+        snarl:
+            dialogue: "What do you want?"
+            end: true
+    "#;
+    let tree = source_to_tree(source).unwrap();
+    assert_eq!(2, tree.entries().len());
+    assert_eq!("snarl", tree.entries()["angry"]);
+    assert_eq!(tree, reader_to_tree(source.as_bytes()).unwrap());
+    assert_eq!(
+        "snarl",
+        Walker::start_at(&tree, "angry").unwrap().current_key()
+    );
+    assert!(tree.lint().is_empty());
+    let exported = exporter::tree_to_source(&tree).unwrap();
+    assert!(exported.contains("entries:\n  angry: snarl\n  greeting: greet"));
+    assert_eq!(tree, source_to_tree(&exported).unwrap());
+
+    // Entry points must name existing nodes, like the root
+    let source = "---\nroot: greet\nentries:\n  angry: snral\nnodes:\n  greet:\n    dialogue: Hi\n  snarl:\n    dialogue: Hm\n";
+    assert!(matches!(
+        source_to_tree(source).unwrap_err(),
+        ImportError::Validation(TreeError::NodeDNE { suggestion: Some(suggestion), .. })
+            if suggestion == "snarl"
+    ));
+    let source = "---\nroot: greet\nentries: [greet]\nnodes:\n  greet:\n    dialogue: Hi\n";
+    assert!(matches!(source_to_tree(source).unwrap_err(), Validation(_)));
 }
 
 #[test]
-fn test_source_to_tree() {
-    // Test a minimal valid source
+fn test_source_to_tree_block_dialogue() {
+    // Block scalars keep no trailing line breaks, whether clipped, stripped, or kept
+    let source = "---\nroot: start\nnodes:\n  start:\n    dialogue: |\n      Hello,\n\n      traveler.\n    links:\n      - to: end\n        dialogue: |+\n          Bye.\n\n  end:\n    dialogue: |-\n      Farewell.\n    end: true\n";
+    let tree = source_to_tree(source).unwrap();
+    assert_eq!("Hello,\n\ntraveler.", tree.node("start").unwrap().dialogue);
+    assert_eq!("Bye.", tree.node("start").unwrap().links[0].text());
+    assert_eq!("Farewell.", tree.node("end").unwrap().dialogue);
+    assert_eq!(tree, reader_to_tree(source.as_bytes()).unwrap());
+
+    // Line breaks are read alike from files with Windows line endings
+    let source = source.replace('\n', "\r\n");
+    assert_eq!(tree, source_to_tree(&source).unwrap());
+}
+
+#[test]
+fn test_source_to_tree_link_groups() {
+    use crate::{error::ImportError::Validation, exporter};
+
     let source = r#"---
     root: start
     nodes:
         start:
-            dialogue: "It's a bad day."
+            dialogue: "What do you want?"
+            links:
+                - group: "Ask about..."
+                  links:
+                      - town: "The town."
+                      - to: king
+                        dialogue: "The king."
+                        priority: 1
+                - end: "Nothing."
+                - group: "Trade"
+                  links:
+                      - end: "Buy."
+        town:
+            dialogue: "It's quiet."
+        king:
+            dialogue: "He's dead."
+        end:
+            dialogue: "Bye."
     "#;
-    assert!(source_to_tree(source).is_ok());
+    let tree = source_to_tree(source).unwrap();
+    let groups: Vec<Option<&str>> = tree
+        .node("start")
+        .unwrap()
+        .links
+        .iter()
+        .map(|link| link.group.as_deref())
+        .collect();
+    assert_eq!(
+        vec![
+            Some("Ask about..."),
+            Some("Ask about..."),
+            None,
+            Some("Trade")
+        ],
+        groups
+    );
+    assert_eq!(tree, reader_to_tree(source.as_bytes()).unwrap());
+    let exported = exporter::tree_to_source(&tree).unwrap();
+    assert_eq!(tree, source_to_tree(&exported).unwrap());
+
+    // Groups must have a string heading and cannot be nested or empty
+    for group in [
+        "{ group: [Ask], links: [{ end: Bye }] }",
+        "{ group: Ask, links: [{ group: About, links: [{ end: Bye }] }] }",
+        "{ group: Ask, links: [] }",
+    ] {
+        let source = format!(
+            "---\nroot: end\nnodes:\n    end:\n        dialogue: Hi\n        links: [{}]\n",
+            group
+        );
+        assert!(matches!(
+            source_to_tree(&source).unwrap_err(),
+            Validation(_)
+        ));
+    }
 }
 
 #[test]
-fn test_source_to_tree_root_exists() {
-    use crate::error::ImportError::Validation;
+fn test_source_to_tree_random() {
+    use crate::{error::ImportError::Validation, exporter};
 
-    // Invalid: YAML must contain a top-level element called `root`.
     let source = r#"---
+    root: start
     nodes:
         start:
-            dialogue: "It's a bad day."
+            dialogue: "Hello."
+            random: { avoid_recent: 2 }
+            links:
+                - to: end
+                  dialogue: "Common."
+                  weight: 3
+                - end: "Rare."
+        end:
+            dialogue: "Bye."
+            random: false
+        again:
+            dialogue: "Again."
+            random: true
     "#;
-    assert!(matches!(source_to_tree(source).unwrap_err(), Validation(_)));
+    let tree = source_to_tree(source).unwrap();
+    let start = tree.node("start").unwrap();
+    assert_eq!(NodeKind::Random { avoid_recent: 2 }, start.kind);
+    assert_eq!(
+        vec![Some(3), None],
+        start
+            .links
+            .iter()
+            .map(|link| link.weight)
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(NodeKind::Choice, tree.node("end").unwrap().kind);
+    assert_eq!(
+        NodeKind::Random { avoid_recent: 0 },
+        tree.node("again").unwrap().kind
+    );
+    assert_eq!(tree, reader_to_tree(source.as_bytes()).unwrap());
+    let exported = exporter::tree_to_source(&tree).unwrap();
+    assert_eq!(tree, source_to_tree(&exported).unwrap());
+
+    // Random must be a boolean or options, and weights must be non-negative integers
+    for node in [
+        "random: yes please",
+        "random: { avoid_recent: -1 }",
+        "random: { seed: 1 }",
+        "links: [{ to: start, dialogue: Hi, weight: -1 }]",
+    ] {
+        let source = format!(
+            "---\nroot: start\nnodes:\n    start:\n        dialogue: Hi\n        {}\n",
+            node
+        );
+        assert!(matches!(
+            source_to_tree(&source).unwrap_err(),
+            Validation(_)
+        ));
+    }
+}
+
+#[test]
+fn test_source_to_tree_menu() {
+    use crate::{error::ImportError::Validation, exporter};
 
-    // Invalid: YAML must contain a top-level element called `root` which points to a real node
     let source = r#"---
-    root: abc_123
+    root: shop
+    nodes:
+        shop:
+            dialogue: "What'll it be?"
+            menu: shop_stock
+            links:
+                - to: bought
+                  dialogue: "Buy"
+                - leave: "Nothing, thanks."
+        bought:
+            switch: menu.shop_stock
+            cases:
+                iron_sword: sword
+            default: leave
+        sword:
+            dialogue: "A fine blade."
+        leave:
+            dialogue: "Come again."
+    "#;
+    let tree = source_to_tree(source).unwrap();
+    assert_eq!(
+        NodeKind::Menu {
+            source: "shop_stock".into()
+        },
+        tree.node("shop").unwrap().kind
+    );
+    assert_eq!(tree, reader_to_tree(source.as_bytes()).unwrap());
+    let exported = exporter::tree_to_source(&tree).unwrap();
+    assert!(exported.contains("menu: shop_stock"));
+    assert_eq!(tree, source_to_tree(&exported).unwrap());
+
+    // Menus name a source, have a template link, and are not automatic
+    for node in [
+        "menu: 1\n        links: [{ to: start, dialogue: Buy }]",
+        "menu: ''\n        links: [{ to: start, dialogue: Buy }]",
+        "menu: shop_stock",
+        "menu: shop_stock\n        random: true\n        links: [{ to: start, dialogue: Buy }]",
+    ] {
+        let source = format!(
+            "---\nroot: start\nnodes:\n    start:\n        dialogue: Hi\n        {}\n",
+            node
+        );
+        assert!(
+            matches!(source_to_tree(&source).unwrap_err(), Validation(_)),
+            "{}",
+            node
+        );
+    }
+}
+
+#[test]
+fn test_source_to_tree_switch() {
+    use crate::{error::ImportError::Validation, exporter};
+
+    let source = r#"---
+    root: start
     nodes:
         start:
-            dialogue: "It's a bad day."
+            switch: faction
+            cases:
+                rebels: rebel
+                3: empire
+            default: neutral
+        rebel:
+            dialogue: "Welcome, friend."
+        empire:
+            dialogue: "Halt!"
+        neutral:
+            dialogue: "Who are you?"
     "#;
-    assert!(matches!(source_to_tree(source).unwrap_err(), Validation(_)));
+    let tree = source_to_tree(source).unwrap();
+    let start = tree.node("start").unwrap();
+    assert_eq!(
+        NodeKind::Switch {
+            variable: "faction".into()
+        },
+        start.kind
+    );
+    assert_eq!("", start.dialogue);
+    assert_eq!(
+        vec!["rebel", "empire", "neutral"],
+        start
+            .links
+            .iter()
+            .map(|link| link.to_key.as_str())
+            .collect::<Vec<_>>()
+    );
+    assert!(start.links[2].condition.is_none());
+    assert_eq!(tree, reader_to_tree(source.as_bytes()).unwrap());
+    let exported = exporter::tree_to_source(&tree).unwrap();
+    assert_eq!(tree, source_to_tree(&exported).unwrap());
+
+    // Switches need a variable name and cases or a default, take no other links, and are not random
+    for node in [
+        "switch: \"!faction\"\n        default: start",
+        "switch: faction\n        default: start\n        random: true",
+        "switch: faction\n        default: start\n        links: [{ start: Hi }]",
+        "switch: faction",
+        "switch: faction\n        cases: { [1]: start }",
+        "switch: faction\n        cases: { rebels: [start] }",
+    ] {
+        let source = format!("---\nroot: start\nnodes:\n    start:\n        {}\n", node);
+        assert!(
+            matches!(source_to_tree(&source).unwrap_err(), Validation(_)),
+            "{}",
+            node
+        );
+    }
 }
 
 #[test]
-fn test_source_to_tree_nodes_exist() {
+fn test_source_to_tree_generate() {
     use crate::error::ImportError::Validation;
 
-    // Invalid: `nodes` must contain at least 1 node.
+    let source = r#"---
+    root: shop
+    nodes:
+        shop:
+            dialogue: "What will it be?"
+    generate:
+        - each:
+            - { item: sword, price: 10 }
+            - { item: shield, price: 15 }
+          nodes:
+              buy_{item}:
+                  dialogue: "A {item} costs {price} gold, {{haggling}} aside."
+                  links:
+                      - to: shop
+                        dialogue: "Back"
+                        set: { spent: "{price}" }
+          links:
+              shop:
+                  - buy_{item}: "A {item}, please."
+    "#;
+    let tree = source_to_tree(source).unwrap();
+    assert_eq!(3, tree.len());
+    let sword = tree.node("buy_sword").unwrap();
+    assert_eq!("A sword costs 10 gold, {haggling} aside.", sword.dialogue);
+    assert_eq!(
+        vec![("spent".to_owned(), Value::Int(10))],
+        sword.links[0].set
+    );
+    assert_eq!(
+        vec![
+            ("buy_sword", "A sword, please."),
+            ("buy_shield", "A shield, please.")
+        ],
+        tree.node("shop")
+            .unwrap()
+            .links
+            .iter()
+            .map(|link| (link.to_key.as_str(), link.text()))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(tree, reader_to_tree(source.as_bytes()).unwrap());
+
+    // Entries bind scalars, placeholders must be bound, and generated nodes must be new
+    for generator in [
+        "each: [{ item: [a] }]\n      nodes: { \"n_{item}\": { dialogue: Hi } }",
+        "each: [{ item: a }]\n      nodes: { \"n_{price}\": { dialogue: Hi } }",
+        "each: [{ item: a }]\n      nodes: { \"n_{item\": { dialogue: Hi } }",
+        "each: [{ item: start }]\n      nodes: { \"{item}\": { dialogue: Hi } }",
+        "each: [{ item: a }]\n      links: { \"n_{item}\": [{ start: Hi }] }",
+        "each: [{ item: a }]",
+        "each: [{ item: a }]\n      nodes: {}\n      repeat: 2",
+    ] {
+        let source = format!(
+            "---\nroot: start\nnodes:\n    start:\n        dialogue: Hi\ngenerate:\n    - {}\n",
+            generator
+        );
+        assert!(
+            matches!(source_to_tree(&source).unwrap_err(), Validation(_)),
+            "{}",
+            generator
+        );
+        assert!(reader_to_tree(source.as_bytes()).is_err(), "{}", generator);
+    }
+}
+
+#[test]
+fn test_source_to_tree_lines() {
+    use crate::{error::ImportError::Validation, exporter};
+
     let source = r#"---
     root: start
     nodes:
+        start:
+            dialogue:
+                - speaker: guard
+                  text: "Halt!"
+                - speaker: captain
+                  text: "Let them through."
+                  emotion: weary
     "#;
-    assert!(matches!(source_to_tree(source).unwrap_err(), Validation(_)));
+    let tree = source_to_tree(source).unwrap();
+    let node = tree.node("start").unwrap();
+    assert_eq!(
+        vec![
+            Line::new("guard", "Halt!"),
+            Line {
+                emotion: Some("weary".to_owned()),
+                ..Line::new("captain", "Let them through.")
+            }
+        ],
+        node.lines
+    );
+    assert_eq!("guard: Halt!\ncaptain: Let them through.", node.dialogue);
+    assert_eq!(tree, reader_to_tree(source.as_bytes()).unwrap());
+    let exported = exporter::tree_to_source(&tree).unwrap();
+    assert_eq!(tree, source_to_tree(&exported).unwrap());
+
+    // Lines must be a non-empty array of hashes of a string speaker and text, and optionally a non-empty string emotion
+    for dialogue in [
+        "[]",
+        "[Halt!]",
+        "[{ speaker: guard }]",
+        "[{ speaker: guard, text: [Halt!] }]",
+        "[{ speaker: guard, text: Halt!, emotion: [angry] }]",
+        "[{ speaker: guard, text: Halt!, emotion: '' }]",
+        "[{ speaker: guard, emotion: angry }]",
+        "{ speaker: guard, text: Halt! }",
+    ] {
+        let source = format!(
+            "---\nroot: start\nnodes:\n    start:\n        dialogue: {}\n",
+            dialogue
+        );
+        assert!(matches!(
+            source_to_tree(&source).unwrap_err(),
+            Validation(_)
+        ));
+    }
 }
 
 #[test]
-fn test_source_to_tree_attributes() {
-    use crate::error::ImportError::Validation;
+fn test_source_to_tree_availability() {
+    use crate::{error::ImportError::Validation, exporter};
 
-    // `start` does not contain dialogue.
     let source = r#"---
     root: start
+    availability:
+        cooldown: 600
+        requires: [act_2]
     nodes:
         start:
-            links:
-            - end: "I'm rudely in a hurry."
-        end:
-            dialogue: "Ok, let's talk some other time."
+            dialogue: "Happy festival!"
+            availability:
+                min_day: 7
+                max_day: 7
     "#;
-    assert!(matches!(source_to_tree(source).unwrap_err(), Validation(_)));
+    let tree = source_to_tree(source).unwrap();
+    assert_eq!(Some(600), tree.availability().cooldown);
+    assert_eq!(vec!["act_2"], tree.availability().required_tags);
+    let availability = &tree.node("start").unwrap().availability;
+    assert_eq!(
+        (Some(7), Some(7)),
+        (availability.min_day, availability.max_day)
+    );
+    assert_eq!(tree, reader_to_tree(source.as_bytes()).unwrap());
+    let exported = exporter::tree_to_source(&tree).unwrap();
+    assert_eq!(tree, source_to_tree(&exported).unwrap());
+
+    // Availability must be a hash of known, well-typed fields, with days in order
+    for availability in [
+        "[]",
+        "{ cooldown: -1 }",
+        "{ min_day: tomorrow }",
+        "{ requires: act_2 }",
+        "{ requires: [[act_2]] }",
+        "{ weather: rain }",
+        "{ min_day: 2, max_day: 1 }",
+    ] {
+        let source = format!(
+            "---\nroot: start\nnodes:\n    start:\n        dialogue: Hi\n        availability: {}\n",
+            availability
+        );
+        assert!(matches!(
+            source_to_tree(&source).unwrap_err(),
+            Validation(_)
+        ));
+    }
 }
 
 #[test]
-#[ignore = "Waiting on issue #3"]
-fn test_source_to_tree_unreachable_nodes() {
-    use crate::error::ImportError::Validation;
+fn test_source_to_tree_stage() {
+    use crate::{error::ImportError::Validation, exporter, value::Value};
 
-    // `end` is an orphan node. It is not reachable.
     let source = r#"---
     root: start
     nodes:
         start:
-            dialogue: "Hello, how are you?"
-        end:
-            dialogue: "Ok, let's talk some other time."
+            dialogue: "The door creaks open."
+            stage:
+                - camera: [close_up, npc_bob]
+                - animate: [npc_bob, wave, 2, true]
+                - shake: 3
+                - fade_out
+                - wait:
     "#;
-    assert!(matches!(source_to_tree(source).unwrap_err(), Validation(_)));
+    let tree = source_to_tree(source).unwrap();
+    assert_eq!(
+        vec![
+            StageDirection::new("camera", vec!["close_up".into(), "npc_bob".into()]),
+            StageDirection::new(
+                "animate",
+                vec![
+                    "npc_bob".into(),
+                    "wave".into(),
+                    Value::Int(2),
+                    Value::Bool(true)
+                ]
+            ),
+            StageDirection::new("shake", vec![Value::Int(3)]),
+            StageDirection::new("fade_out", vec![]),
+            StageDirection::new("wait", vec![]),
+        ],
+        tree.node("start").unwrap().stage
+    );
+    assert_eq!(tree, reader_to_tree(source.as_bytes()).unwrap());
+    let exported = exporter::tree_to_source(&tree).unwrap();
+    assert_eq!(tree, source_to_tree(&exported).unwrap());
+
+    // Stage must be an array of commands, or hashes of one command to scalar arguments
+    for stage in [
+        "camera",
+        "[{ camera: close_up, shake: 3 }]",
+        "[{ camera: [[close_up]] }]",
+        "[{ camera: { zoom: 2 } }]",
+        "[{ 3: close_up }]",
+        "['']",
+        "[[camera]]",
+    ] {
+        let source = format!(
+            "---\nroot: start\nnodes:\n    start:\n        dialogue: Hi\n        stage: {}\n",
+            stage
+        );
+        assert!(matches!(
+            source_to_tree(&source).unwrap_err(),
+            Validation(_)
+        ));
+    }
+}
+
+#[test]
+fn test_source_to_tree_pacing() {
+    use crate::{error::ImportError::Validation, exporter};
 
-    // `end` and `fork` are orphans because the root node (`start`) is a leaf node.
     let source = r#"---
     root: start
     nodes:
-        fork:
-            dialogue: "I make sure no one is an orphan by being the parent to all."
-            links:
-                - start: "I link to start"
-                - end: "I link to the end"
-                - fork: "I even link to myself!"
         start:
-            dialogue: "Hello, how are you?"
+            dialogue: "Well{pause 500}... no."
+            pacing:
+                chars_per_second: 30
+                pause_after: 1000
+            links:
+                - end: "I see."
         end:
-            dialogue: "Ok, let's talk some other time."
+            dialogue: "Goodbye."
+            pacing:
     "#;
-    assert!(matches!(source_to_tree(source).unwrap_err(), Validation(_)));
+    let tree = source_to_tree(source).unwrap();
+    assert_eq!(
+        Pacing {
+            chars_per_second: Some(30),
+            pause_before: None,
+            pause_after: Some(1000),
+        },
+        tree.node("start").unwrap().pacing
+    );
+    assert!(tree.node("end").unwrap().pacing.is_empty());
+    assert_eq!(tree, reader_to_tree(source.as_bytes()).unwrap());
+    let exported = exporter::tree_to_source(&tree).unwrap();
+    assert_eq!(tree, source_to_tree(&exported).unwrap());
+
+    // Pacing must be a hash of known hints, each a non-negative integer which fits in 32 bits
+    for pacing in [
+        "[]",
+        "{ chars_per_second: -1 }",
+        "{ pause_before: soon }",
+        "{ pause_after: 4294967296 }",
+        "{ speed: 30 }",
+    ] {
+        let source = format!(
+            "---\nroot: start\nnodes:\n    start:\n        dialogue: Hi\n        pacing: {}\n",
+            pacing
+        );
+        assert!(matches!(
+            source_to_tree(&source).unwrap_err(),
+            Validation(_)
+        ));
+    }
 }
 
 #[test]
-#[ignore = "Waiting on issue #10"]
-fn test_source_to_tree_invalid_links() {
-    use crate::error::ImportError::Validation;
+fn test_source_to_tree_draft() {
+    use crate::exporter::{self, ExportOptions};
 
-    // `not_a_real_key` is an invalid reference key.
+    let source = r#"---
+root: start
+nodes:
+  start:
+    dialogue: "TBD: greeting"
+    links:
+      - shop: Browse.
+      - inn/start: To the inn.
+  stub:
+  empty:
+    dialogue: ~
+    links: []
+  later:
+    dialogue: Come back later.
+    todo: true
+    end: true
+"#;
+
+    // Strict mode rejects nodes without dialogue, links to missing nodes, and placeholders
+    assert!(source_to_tree(source).is_err());
+    let strict = &source[..source.find("  stub:").unwrap()];
+    assert!(matches!(
+        source_to_tree(strict),
+        Err(ImportError::Validation(TreeError::NodeDNE { .. }))
+    ));
+    assert!(matches!(
+        source_to_tree(&strict.replace("- shop: Browse.", "- start: Again.")),
+        Err(ImportError::Validation(TreeError::Validation(_)))
+    ));
+    let draft = ImportOptions {
+        draft: true,
+        ..Default::default()
+    };
+    let tree = source_to_tree_with(source, &draft).unwrap();
+    assert_eq!("", tree.node("stub").unwrap().dialogue);
+    assert!(tree.node("empty").unwrap().links.is_empty());
+    assert!(tree.node("later").unwrap().todo);
+    assert_eq!(4, draft::placeholders(&tree).len());
+
+    // Drafts only round trip in draft mode
+    assert!(exporter::tree_to_source(&tree).is_err());
+    let options = ExportOptions {
+        draft: true,
+        ..Default::default()
+    };
+    let exported = exporter::tree_to_source_with(&tree, &options).unwrap();
+    assert_eq!(tree, source_to_tree_with(&exported, &draft).unwrap());
+}
+
+#[test]
+fn test_source_to_tree_with_metrics() {
+    use crate::exporter::{self, ExportOptions};
+
+    let source = get_file_source("examples/dialogue_files/ex_1.convo.yml").unwrap();
+    let (tree, metrics) = source_to_tree_with_metrics(&source, &ImportOptions::default()).unwrap();
+    assert_eq!(source.len(), metrics.bytes);
+    assert_eq!(tree.len(), metrics.nodes);
+    assert_eq!(Duration::ZERO, metrics.read);
+    assert_eq!(
+        metrics.parse + metrics.build + metrics.validation,
+        metrics.total()
+    );
+
+    let (exported, metrics) =
+        exporter::tree_to_source_with_metrics(&tree, &ExportOptions::default()).unwrap();
+    assert_eq!(exported.len(), metrics.bytes);
+    assert_eq!(tree.len(), metrics.nodes);
+    assert_eq!(Duration::ZERO, metrics.write);
+}
+
+#[test]
+fn test_source_to_tree_with_warnings() {
     let source = r#"---
     root: start
+    version: 2
     nodes:
         start:
-            dialogue: "I am the start node"
+            dialogue: "Hello"
+            mood: happy
             links:
-                - start: "I am valid and link to myself"
-                - not_a_real_key: "I do not link to a valid key"
+                - end: "Bye"
+                - end: "Bye"
+                - group: "Ask about..."
+                  collapsed: true
+                  links:
+                    - to: end
+                      text: "The weather."
+                      colour: blue
+        end:
+            dialogue:
+                - speaker: guard
+                  text: "Goodbye"
+                  volume: loud
+            end: true
     "#;
 
-    assert!(matches!(source_to_tree(source).unwrap_err(), Validation(_)));
+    // Warnings do not fail the import, and plain imports drop them
+    let (tree, warnings) = source_to_tree_with_warnings(source, &ImportOptions::default()).unwrap();
+    assert_eq!(source_to_tree(source).unwrap(), tree);
+    let found: Vec<(&str, &str, &str)> = warnings
+        .iter()
+        .map(|warning| {
+            (
+                warning.rule.id(),
+                warning.node_key.as_str(),
+                warning.message.as_str(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        vec![
+            (
+                "unknown-field",
+                "start",
+                "The document has the unknown field `version`, which is ignored"
+            ),
+            (
+                "unknown-field",
+                "start",
+                "Node `start` has the unknown field `mood`, which is ignored"
+            ),
+            (
+                "unknown-field",
+                "start",
+                "Link group `Ask about...` of node `start` has the unknown field `collapsed`, which is ignored"
+            ),
+            (
+                "unknown-field",
+                "start",
+                "Link 3 of node `start` has the unknown field `colour`, which is ignored"
+            ),
+            (
+                "unknown-field",
+                "end",
+                "Line 1 of node `end` has the unknown field `volume`, which is ignored"
+            ),
+            (
+                "duplicate-link",
+                "start",
+                "Link `Bye` to `end` is duplicated in node `start`"
+            ),
+        ],
+        found
+    );
+
+    // Long dialogue is only reported beyond the configured limits
+    let options = ImportOptions {
+        max_length: MaxLength {
+            dialogue: None,
+            link_dialogue: Some(8),
+        },
+        ..Default::default()
+    };
+    let (_, warnings) = source_to_tree_with_warnings(source, &options).unwrap();
+    assert_eq!(7, warnings.len());
+    assert_eq!(Rule::Text("max-length"), warnings[6].rule);
 }
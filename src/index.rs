@@ -0,0 +1,374 @@
+use alloc::{collections::VecDeque, vec, vec::Vec};
+
+use crate::{node::Node, tree::Tree};
+
+/// A [`TreeIndex`] is a precomputed snapshot of the link graph of a [`Tree`], answering reachability, parent, and path queries without re-walking the whole tree.
+///
+/// The index borrows its tree, so the tree cannot change while the index is alive. Rebuild it with [`Tree::build_index`] after editing.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{Link, Node, Tree};
+/// let mut tree = Tree::new();
+/// let mut start = Node::new("start", "Hello!");
+/// let end = Node::new("end", "Goodbye!");
+/// Link::link(&mut start, &end, "Bye!");
+/// tree.insert_node(start);
+/// tree.insert_node(end);
+/// tree.insert_node(Node::new("orphan", "Anyone?"));
+/// tree.set_root_key("start").unwrap();
+///
+/// let index = tree.build_index();
+/// assert!(index.is_reachable("end"));
+/// assert!(!index.is_reachable("orphan"));
+/// assert_eq!("start", index.links_to("end")[0].key());
+/// ```
+#[derive(Debug, Clone)]
+pub struct TreeIndex<'t> {
+    tree: &'t Tree,
+
+    /// The indices of the nodes each node links to, without duplicates or missing targets.
+    children: Vec<Vec<usize>>,
+
+    /// The indices of the nodes which link to each node, in the order they are stored.
+    parents: Vec<Vec<usize>>,
+
+    /// Whether each node is reachable from the root node or an entry point.
+    reachable: Vec<bool>,
+}
+
+impl<'t> TreeIndex<'t> {
+    pub(crate) fn new(tree: &'t Tree) -> Self {
+        let len = tree.len();
+        let mut children = vec![Vec::new(); len];
+        let mut parents = vec![Vec::new(); len];
+        for (i, node) in tree.nodes.values().enumerate() {
+            for link in &node.links {
+                if let Some(j) = tree.nodes.get_index_of(&link.to_key) {
+                    if !children[i].contains(&j) {
+                        children[i].push(j);
+                        parents[j].push(i);
+                    }
+                }
+            }
+        }
+
+        // Nodes are visited in storage order, so parents are already sorted
+        let mut index = TreeIndex {
+            tree,
+            children,
+            parents,
+            reachable: vec![false; len],
+        };
+        let starts = tree.root_key().into_iter().chain(tree.entries().values());
+        for start in starts.filter_map(|key| tree.nodes.get_index_of(key)) {
+            if index.reachable[start] {
+                continue;
+            }
+            for i in index.search(start, |index, i| &index.children[i]).0 {
+                index.reachable[i] = true;
+            }
+        }
+        index
+    }
+
+    /// Returns the [`Tree`] this index was built from.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::Tree;
+    /// let tree = Tree::new();
+    /// let index = tree.build_index();
+    /// assert!(core::ptr::eq(&tree, index.tree()));
+    /// ```
+    pub fn tree(&self) -> &'t Tree {
+        self.tree
+    }
+
+    /// Returns the [`Node`]s which have at least one link to a node key, in the order they are stored. See also: [`Tree::links_to`].
+    ///
+    /// # Arguments
+    ///
+    /// * `node_key` - A string slice that holds the key of the linked node.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Link, Node, Tree};
+    /// let mut tree = Tree::new();
+    /// let mut start = Node::new("start", "Hello!");
+    /// let end = Node::new("end", "Goodbye!");
+    /// Link::link(&mut start, &end, "Bye!");
+    /// tree.insert_node(start);
+    /// tree.insert_node(end);
+    /// let index = tree.build_index();
+    /// assert_eq!(tree.links_to("end"), index.links_to("end"));
+    /// ```
+    pub fn links_to(&self, node_key: &str) -> Vec<&'t Node> {
+        match self.tree.nodes.get_index_of(node_key) {
+            Some(i) => self.nodes(&self.parents[i]),
+            // Links to missing nodes are not indexed, and are rare enough to search for
+            None => self.tree.links_to(node_key),
+        }
+    }
+
+    /// Returns whether a node key indexes a [`Node`] which can be reached from the root node, or an entry point (see [`Tree::entries`]), by following links. Nothing is reachable if the tree has no root or entry points set.
+    ///
+    /// # Arguments
+    ///
+    /// * `node_key` - A string slice that holds the key of the node.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Node, Tree};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// assert!(!tree.build_index().is_reachable("start"));
+    /// tree.set_root_key("start").unwrap();
+    /// assert!(tree.build_index().is_reachable("start"));
+    /// ```
+    pub fn is_reachable(&self, node_key: &str) -> bool {
+        self.tree
+            .nodes
+            .get_index_of(node_key)
+            .is_some_and(|i| self.reachable[i])
+    }
+
+    /// Returns the [`Node`]s which cannot be reached from the root node or an entry point, in the order they are stored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Node, Tree};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.insert_node(Node::new("orphan", "Anyone?"));
+    /// tree.set_root_key("start").unwrap();
+    /// assert_eq!("orphan", tree.build_index().unreachable_nodes()[0].key());
+    /// ```
+    pub fn unreachable_nodes(&self) -> Vec<&'t Node> {
+        self.tree
+            .nodes()
+            .zip(&self.reachable)
+            .filter(|(_, reachable)| !**reachable)
+            .map(|(node, _)| node)
+            .collect()
+    }
+
+    /// Returns the [`Node`]s from which a node key can be reached by following links, nearest first. The node itself is only included if it is part of a cycle.
+    ///
+    /// # Arguments
+    ///
+    /// * `node_key` - A string slice that holds the key of the node.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Link, Node, Tree};
+    /// let mut tree = Tree::new();
+    /// let mut start = Node::new("start", "Hello!");
+    /// let mut middle = Node::new("middle", "How are you?");
+    /// let end = Node::new("end", "Goodbye!");
+    /// Link::link(&mut start, &middle, "Hi!");
+    /// Link::link(&mut middle, &end, "Bye!");
+    /// tree.insert_node(start);
+    /// tree.insert_node(middle);
+    /// tree.insert_node(end);
+    /// let index = tree.build_index();
+    /// let ancestors: Vec<&str> = index.ancestors("end").iter().map(|node| node.key()).collect();
+    /// assert_eq!(vec!["middle", "start"], ancestors);
+    /// ```
+    pub fn ancestors(&self, node_key: &str) -> Vec<&'t Node> {
+        let start = match self.tree.nodes.get_index_of(node_key) {
+            Some(i) => i,
+            None => return vec![],
+        };
+        let (order, visited) = self.search(start, |index, i| &index.parents[i]);
+
+        // The search always begins with the node itself, which is its own ancestor if one of its children is
+        let mut ancestors = self.nodes(&order[1..]);
+        if self.children[start].iter().any(|&child| visited[child]) {
+            ancestors.push(&self.tree.nodes[start]);
+        }
+        ancestors
+    }
+
+    /// Returns the shortest sequence of [`Node`]s which leads from one node key to another by following links, including both ends. A path from a node to itself is just that node. Returns [`None`] if either node does not exist or there is no such path.
+    ///
+    /// # Arguments
+    ///
+    /// * `from_key` - A string slice that holds the key of the first node.
+    /// * `to_key` - A string slice that holds the key of the last node.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Link, Node, Tree};
+    /// let mut tree = Tree::new();
+    /// let mut start = Node::new("start", "Hello!");
+    /// let mut middle = Node::new("middle", "How are you?");
+    /// let end = Node::new("end", "Goodbye!");
+    /// Link::link(&mut start, &middle, "Hi!");
+    /// Link::link(&mut middle, &end, "Bye!");
+    /// tree.insert_node(start);
+    /// tree.insert_node(middle);
+    /// tree.insert_node(end);
+    /// let index = tree.build_index();
+    /// assert_eq!(3, index.path("start", "end").unwrap().len());
+    /// assert!(index.path("end", "start").is_none());
+    /// ```
+    pub fn path(&self, from_key: &str, to_key: &str) -> Option<Vec<&'t Node>> {
+        let from = self.tree.nodes.get_index_of(from_key)?;
+        let to = self.tree.nodes.get_index_of(to_key)?;
+
+        // Breadth-first search, remembering how each node was first reached
+        let mut previous = vec![None; self.children.len()];
+        let mut queue = VecDeque::from(vec![from]);
+        let mut found = from == to;
+        while let Some(i) = queue.pop_front() {
+            if found {
+                break;
+            }
+            for &child in &self.children[i] {
+                if child != from && previous[child].is_none() {
+                    previous[child] = Some(i);
+                    if child == to {
+                        found = true;
+                    }
+                    queue.push_back(child);
+                }
+            }
+        }
+        if !found {
+            return None;
+        }
+
+        let mut path = vec![to];
+        while let Some(i) = previous[*path.last()?] {
+            path.push(i);
+        }
+        path.reverse();
+        Some(self.nodes(&path))
+    }
+
+    /// The indices of the nodes each node links to, by the index of the node.
+    pub(crate) fn children(&self) -> &[Vec<usize>] {
+        &self.children
+    }
+
+    /// The indices of the nodes which link to each node, by the index of the node.
+    pub(crate) fn parents(&self) -> &[Vec<usize>] {
+        &self.parents
+    }
+
+    /// Whether each node is reachable from the root node, by the index of the node.
+    pub(crate) fn reachable(&self) -> &[bool] {
+        &self.reachable
+    }
+
+    /// Breadth-first search from a node, returning the visit order and which nodes were visited.
+    fn search<F>(&self, start: usize, edges: F) -> (Vec<usize>, Vec<bool>)
+    where
+        F: Fn(&Self, usize) -> &Vec<usize>,
+    {
+        let mut visited = vec![false; self.children.len()];
+        let mut order = vec![start];
+        visited[start] = true;
+        let mut next = 0;
+        while let Some(&i) = order.get(next) {
+            next += 1;
+            for &j in edges(self, i) {
+                if !visited[j] {
+                    visited[j] = true;
+                    order.push(j);
+                }
+            }
+        }
+        (order, visited)
+    }
+
+    fn nodes(&self, indices: &[usize]) -> Vec<&'t Node> {
+        indices.iter().map(|&i| &*self.tree.nodes[i]).collect()
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_reachability() {
+    use crate::link::Link;
+
+    // start -> a -> b -> a, with an orphan and a dangling link
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "Start");
+    let mut a = Node::new("a", "A");
+    let mut b = Node::new("b", "B");
+    let mut orphan = Node::new("orphan", "Orphan");
+    Link::link(&mut start, &a, "To A");
+    Link::link(&mut start, &a, "To A again");
+    Link::link(&mut a, &b, "To B");
+    Link::link(&mut b, &a, "Back to A");
+    Link::link(&mut orphan, &b, "To B");
+    orphan.links.push(Link::new("missing", "Nowhere"));
+    for node in [start, a, b, orphan] {
+        tree.insert_node(node);
+    }
+
+    // Nothing is reachable without a root
+    let index = tree.build_index();
+    assert!(!index.is_reachable("start"));
+    assert_eq!(4, index.unreachable_nodes().len());
+
+    tree.set_root_key("start").unwrap();
+    let index = tree.build_index();
+    assert!(index.is_reachable("start"));
+    assert!(index.is_reachable("b"));
+    assert!(!index.is_reachable("orphan"));
+    assert!(!index.is_reachable("missing"));
+    assert_eq!(1, index.unreachable_nodes().len());
+
+    // Parents should match the unindexed query
+    for key in ["start", "a", "b", "orphan", "missing"] {
+        assert_eq!(tree.links_to(key), index.links_to(key));
+    }
+}
+
+#[test]
+fn test_ancestors_and_paths() {
+    use crate::link::Link;
+
+    // start -> a -> b -> a, b -> end
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "Start");
+    let mut a = Node::new("a", "A");
+    let mut b = Node::new("b", "B");
+    let end = Node::new("end", "End");
+    Link::link(&mut start, &a, "To A");
+    Link::link(&mut a, &b, "To B");
+    Link::link(&mut b, &a, "Back to A");
+    Link::link(&mut b, &end, "To the end");
+    for node in [start, a, b, end] {
+        tree.insert_node(node);
+    }
+    let index = tree.build_index();
+    let keys =
+        |nodes: Vec<&Node>| -> Vec<String> { nodes.iter().map(|node| node.key.clone()).collect() };
+
+    // Cycles include the node itself
+    assert_eq!(vec!["b", "a", "start"], keys(index.ancestors("end")));
+    assert_eq!(vec!["start", "b", "a"], keys(index.ancestors("a")));
+    assert!(index.ancestors("start").is_empty());
+    assert!(index.ancestors("missing").is_empty());
+
+    // Paths are shortest and inclusive
+    assert_eq!(
+        vec!["start", "a", "b", "end"],
+        keys(index.path("start", "end").unwrap())
+    );
+    assert_eq!(vec!["a"], keys(index.path("a", "a").unwrap()));
+    assert_eq!(vec!["end"], keys(index.path("end", "end").unwrap()));
+    assert!(index.path("end", "start").is_none());
+    assert!(index.path("start", "missing").is_none());
+}
@@ -0,0 +1,183 @@
+//! A dialogue inspector widget for [`egui`] debug UIs.
+
+use crate::{tree::Tree, walker::Walker};
+
+use core::ops::Deref;
+use egui::{Align2, Button, FontId, Painter, Pos2, Response, Sense, Stroke, Ui, Vec2, Widget};
+
+/// An [`Inspector`] is an [`egui`] widget which shows the current [`crate::Node`] of a [`Walker`], its choices, and a mini graph of its neighbourhood. Clicking a choice follows it.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{importer, inspector::Inspector, Walker};
+/// let tree = importer::import("examples/dialogue_files/ex_1.convo.yml").unwrap();
+/// let mut walker = Walker::new(&tree).unwrap();
+/// let ctx = egui::Context::default();
+/// let _ = ctx.run(egui::RawInput::default(), |ctx| {
+///     egui::CentralPanel::default().show(ctx, |ui| {
+///         ui.add(Inspector::new(&mut walker));
+///     });
+/// });
+/// ```
+#[derive(Debug)]
+pub struct Inspector<'w, T>
+where
+    T: Deref<Target = Tree>,
+{
+    walker: &'w mut Walker<T>,
+}
+
+impl<'w, T> Inspector<'w, T>
+where
+    T: Deref<Target = Tree>,
+{
+    /// Returns an [`Inspector`] for a [`Walker`].
+    ///
+    /// # Arguments
+    ///
+    /// * `walker` - A [`Walker`] to inspect. Choices clicked in the inspector are made on this walker.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{importer, inspector::Inspector, Walker};
+    /// let tree = importer::import("examples/dialogue_files/ex_1.convo.yml").unwrap();
+    /// let mut walker = Walker::new(&tree).unwrap();
+    /// let inspector = Inspector::new(&mut walker);
+    /// ```
+    pub fn new(walker: &'w mut Walker<T>) -> Self {
+        Inspector { walker }
+    }
+}
+
+impl<'w, T> Widget for Inspector<'w, T>
+where
+    T: Deref<Target = Tree>,
+{
+    fn ui(self, ui: &mut Ui) -> Response {
+        let tree = self.walker.tree();
+        let node = self.walker.current_node();
+
+        let mut chosen = None;
+        let response = ui
+            .vertical(|ui| {
+                // Current node
+                ui.heading(format!("[{}]", node.key));
+                ui.label(self.walker.dialogue());
+                ui.separator();
+
+                // Choices, where links to missing nodes cannot be followed
+                if self.walker.is_finished() {
+                    ui.weak("No choices. The conversation has ended.");
+                }
+                let dialogues = self.walker.choice_dialogues();
+                for (i, (link, dialogue)) in self.walker.choices().iter().zip(dialogues).enumerate()
+                {
+                    let exists = tree.contains_node(&link.to_key);
+                    let text = format!("{}. {} → {}", i + 1, dialogue, link.to_key);
+                    if ui.add_enabled(exists, Button::new(text)).clicked() {
+                        chosen = Some(i);
+                    }
+                }
+                ui.separator();
+
+                // Neighbourhood graph
+                ui.collapsing("Graph", |ui| {
+                    let parents: Vec<&str> = tree
+                        .links_to(&node.key)
+                        .iter()
+                        .map(|parent| parent.key.as_str())
+                        .collect();
+                    let children: Vec<&str> =
+                        node.links.iter().map(|link| link.to_key.as_str()).collect();
+                    draw_graph(ui, &parents, &node.key, &children);
+                });
+            })
+            .response;
+
+        // Choices are applied once the current node is no longer displayed. The target was checked to exist above.
+        if let Some(i) = chosen {
+            let _ = self.walker.choose(i);
+        }
+        response
+    }
+}
+
+fn draw_graph(ui: &mut Ui, parents: &[&str], current: &str, children: &[&str]) {
+    const ROW_HEIGHT: f32 = 24.0;
+    let rows = parents.len().max(children.len()).max(1);
+    let size = Vec2::new(ui.available_width(), rows as f32 * ROW_HEIGHT);
+    let (response, painter) = ui.allocate_painter(size, Sense::hover());
+    let rect = response.rect;
+
+    // Parents on the left, the current node in the middle, children on the right
+    let column = |x: f32, count: usize| -> Vec<Pos2> {
+        (0..count)
+            .map(|i| {
+                let y = rect.top() + rect.height() * (i as f32 + 0.5) / count as f32;
+                Pos2::new(rect.left() + rect.width() * x, y)
+            })
+            .collect()
+    };
+    let center = rect.center();
+    let parent_positions = column(1.0 / 6.0, parents.len());
+    let child_positions = column(5.0 / 6.0, children.len());
+
+    let stroke = Stroke::new(1.0, ui.visuals().weak_text_color());
+    for position in parent_positions.iter().chain(child_positions.iter()) {
+        painter.line_segment([*position, center], stroke);
+    }
+    for (position, key) in parent_positions.iter().zip(parents) {
+        draw_node(ui, &painter, *position, key, false);
+    }
+    for (position, key) in child_positions.iter().zip(children) {
+        draw_node(ui, &painter, *position, key, false);
+    }
+    draw_node(ui, &painter, center, current, true);
+}
+
+fn draw_node(ui: &Ui, painter: &Painter, position: Pos2, key: &str, current: bool) {
+    let visuals = ui.visuals();
+    let fill = if current {
+        visuals.selection.bg_fill
+    } else {
+        visuals.extreme_bg_color
+    };
+    painter.circle_filled(position, 5.0, fill);
+    painter.text(
+        position + Vec2::new(0.0, -8.0),
+        Align2::CENTER_BOTTOM,
+        key,
+        FontId::proportional(11.0),
+        visuals.text_color(),
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_inspector() {
+    let tree = crate::importer::import("examples/dialogue_files/ex_1.convo.yml").unwrap();
+    let mut walker = Walker::new(&tree).unwrap();
+    walker.choose(0).unwrap();
+
+    // Should render every part of the inspector without making choices
+    let ctx = egui::Context::default();
+    for _ in 0..2 {
+        let _ = ctx.run(egui::RawInput::default(), |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                ui.add(Inspector::new(&mut walker));
+            });
+        });
+    }
+    assert_eq!("bowler", walker.current_key());
+
+    // Should render an ending
+    walker.choose(0).unwrap();
+    let _ = ctx.run(egui::RawInput::default(), |ctx| {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.add(Inspector::new(&mut walker));
+        });
+    });
+    assert!(walker.is_finished());
+}
@@ -0,0 +1,105 @@
+//! Inventories which link conditions can test for items, e.g. to offer a choice only while the player carries a key.
+//!
+//! The host supplies an [`InventoryProvider`] with [`Walker::set_inventory`][`crate::Walker::set_inventory`]. Conditions test whether an item is held as `item(rusty_key)`, and how many are held as `item(arrows) >= 10`, for the variable [`ITEM_PREFIX`] followed by the item id, e.g. `item.rusty_key`. Structured links may require items with `requires_item`, which adds these flags to their condition. Item variables need not be declared.
+
+use alloc::{format, string::String, sync::Arc};
+use core::fmt;
+
+/// The prefix of item variable names.
+pub const ITEM_PREFIX: &str = "item.";
+
+/// An [`InventoryProvider`] reports the items the player holds, as kept by the game.
+///
+/// Any `Fn(&str) -> u32` closure which is [`Send`] and [`Sync`] is an inventory provider, returning the count of an item.
+///
+/// # Examples
+///
+/// ```
+/// use convo::inventory::InventoryProvider;
+/// let inventory = |item: &str| if item == "arrows" { 12 } else { 0 };
+/// assert_eq!(12, inventory.count("arrows"));
+/// assert!(!inventory.has_item("rusty_key"));
+/// ```
+pub trait InventoryProvider: Send + Sync {
+    /// Returns how many of an item the player holds.
+    ///
+    /// # Arguments
+    ///
+    /// * `item` - A string slice that holds the id of the item.
+    fn count(&self, item: &str) -> u32;
+
+    /// Returns whether the player holds at least one of an item.
+    ///
+    /// # Arguments
+    ///
+    /// * `item` - A string slice that holds the id of the item.
+    fn has_item(&self, item: &str) -> bool {
+        self.count(item) > 0
+    }
+}
+
+impl<F> InventoryProvider for F
+where
+    F: Fn(&str) -> u32 + Send + Sync,
+{
+    fn count(&self, item: &str) -> u32 {
+        self(item)
+    }
+}
+
+/// Returns the name of the variable which conditions test for an item.
+///
+/// # Arguments
+///
+/// * `item` - A string slice that holds the id of the item.
+///
+/// # Examples
+///
+/// ```
+/// use convo::inventory;
+/// assert_eq!("item.rusty_key", inventory::variable("rusty_key"));
+/// ```
+pub fn variable(item: &str) -> String {
+    format!("{}{}", ITEM_PREFIX, item)
+}
+
+/// Returns the id of the item a variable tests, or [`None`] if it is not an item variable.
+///
+/// # Arguments
+///
+/// * `name` - A string slice that holds the name of a variable.
+///
+/// # Examples
+///
+/// ```
+/// use convo::inventory;
+/// assert_eq!(Some("rusty_key"), inventory::item("item.rusty_key"));
+/// assert_eq!(None, inventory::item("gold"));
+/// ```
+pub fn item(name: &str) -> Option<&str> {
+    name.strip_prefix(ITEM_PREFIX)
+        .filter(|item| !item.is_empty())
+}
+
+/// A cloneable handle to an [`InventoryProvider`] held by a [`Walker`][`crate::Walker`].
+#[derive(Clone)]
+pub(crate) struct InventoryHandle(Arc<dyn InventoryProvider>);
+
+impl InventoryHandle {
+    pub(crate) fn new<I>(inventory: I) -> Self
+    where
+        I: InventoryProvider + 'static,
+    {
+        InventoryHandle(Arc::new(inventory))
+    }
+
+    pub(crate) fn provider(&self) -> &dyn InventoryProvider {
+        &*self.0
+    }
+}
+
+impl fmt::Debug for InventoryHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("InventoryHandle(..)")
+    }
+}
@@ -0,0 +1,102 @@
+//! Helpers which generate unique, human-readable node keys from dialogue, e.g. for formats without keys, or for editors creating new nodes.
+//!
+//! A key is slugified from the first [`SLUG_WORDS`] words of its dialogue, lowercased and joined by `_`, e.g. `pass_friend` from `Pass, friend.`, and made unique by a numeric suffix, e.g. `pass_friend_2`.
+
+use alloc::{borrow::ToOwned, format, string::String, vec::Vec};
+
+use crate::tree::Tree;
+
+/// The number of words of dialogue a slug is made from.
+pub const SLUG_WORDS: usize = 4;
+
+/// The slug of dialogue which has no words.
+pub const EMPTY_SLUG: &str = "node";
+
+/// Returns the slug of dialogue: its first [`SLUG_WORDS`] words, lowercased and without punctuation, joined by `_`, or [`EMPTY_SLUG`] if it has no words.
+///
+/// # Arguments
+///
+/// * `text` - A string slice that holds the dialogue.
+///
+/// # Examples
+///
+/// ```
+/// use convo::keygen;
+/// assert_eq!("pass_friend", keygen::slugify("Pass, friend."));
+/// assert_eq!("who_goes_there_stranger", keygen::slugify("Who goes there, stranger? Speak!"));
+/// assert_eq!("node", keygen::slugify("..."));
+/// ```
+pub fn slugify(text: &str) -> String {
+    let words: Vec<String> = text
+        .split_whitespace()
+        .map(|word| {
+            word.chars()
+                .filter(|c| c.is_alphanumeric())
+                .flat_map(char::to_lowercase)
+                .collect::<String>()
+        })
+        .filter(|word| !word.is_empty())
+        .take(SLUG_WORDS)
+        .collect();
+    match words.is_empty() {
+        true => EMPTY_SLUG.to_owned(),
+        false => words.join("_"),
+    }
+}
+
+/// Returns a stem if it is not taken, or else the stem with the first numeric suffix which is not, starting from `_2`.
+///
+/// # Arguments
+///
+/// * `stem` - A string slice that holds the preferred key.
+/// * `is_taken` - A closure which returns whether a key is already used.
+///
+/// # Examples
+///
+/// ```
+/// use convo::keygen;
+/// let taken = ["gate", "gate_2"];
+/// assert_eq!("gate_3", keygen::unique_key("gate", |key| taken.contains(&key)));
+/// assert_eq!("yard", keygen::unique_key("yard", |key| taken.contains(&key)));
+/// ```
+pub fn unique_key<F>(stem: &str, is_taken: F) -> String
+where
+    F: Fn(&str) -> bool,
+{
+    let mut key = stem.to_owned();
+    let mut n = 1;
+    while is_taken(&key) {
+        n += 1;
+        key = format!("{}_{}", stem, n);
+    }
+    key
+}
+
+/// Returns a key for a new node of a [`Tree`], slugified from its dialogue and unique among the tree's nodes.
+///
+/// # Arguments
+///
+/// * `tree` - The [`Tree`] the node is added to.
+/// * `text` - A string slice that holds the dialogue of the node.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{keygen, Node, Tree};
+/// let mut tree = Tree::new();
+/// tree.insert_node(Node::new("hello", "Hello!"));
+/// assert_eq!("hello_2", keygen::key_for(&tree, "Hello?"));
+/// assert_eq!("goodbye", keygen::key_for(&tree, "Goodbye."));
+/// ```
+pub fn key_for(tree: &Tree, text: &str) -> String {
+    unique_key(&slugify(text), |key| tree.contains_node(key))
+}
+
+#[cfg(test)]
+#[test]
+fn test_slugify() {
+    assert_eq!("café_au_lait", slugify("  Café au LAIT!"));
+    assert_eq!("im_not_sure_why", slugify("I'm not sure why you ask."));
+    assert_eq!("node", slugify(""));
+    assert_eq!("node_2", unique_key(&slugify("?!"), |key| key == "node"));
+}
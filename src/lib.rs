@@ -3,19 +3,96 @@
 #![warn(rustdoc::missing_doc_code_examples)]
 #![deny(rustdoc::missing_crate_level_docs)]
 #![deny(rustdoc::broken_intra_doc_links)]
+// `See also` notes continue the error they follow
+#![allow(clippy::doc_lazy_continuation)]
+// Invariant-bypassing operations are safe, see `RawTree`
+#![forbid(unsafe_code)]
 // Doc attributes
 #![doc(issue_tracker_base_url = "https://github.com/simbleau/convo/issues/")]
+// Without the `std` feature, only `alloc` is required
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 
 //! A modern dialogue executor and tree parser using YAML, focusing on ease-of-use and speed.
 
+extern crate alloc;
+
+pub mod affinity;
+pub mod analysis;
+pub mod arbiter;
+#[cfg(feature = "std")]
+pub mod ast;
+pub mod bark;
+pub mod characters;
+pub mod debugger;
+pub mod draft;
 pub mod error;
+#[cfg(feature = "std")]
 pub mod exporter;
+pub mod filter;
+#[cfg(feature = "std")]
+pub mod foreign;
+pub mod graph;
+#[cfg(feature = "std")]
 pub mod importer;
+#[cfg(feature = "inspector")]
+pub mod inspector;
+pub mod inventory;
+pub mod keygen;
+pub mod lint;
+#[cfg(feature = "lsp")]
+pub mod lsp;
+pub mod menu;
+pub mod observer;
+pub mod pacing;
+pub mod prelude;
+#[cfg(feature = "std")]
+pub mod project;
+pub mod requirements;
+#[cfg(feature = "std")]
+pub mod sanitize;
+pub mod scheduler;
+pub mod script;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod simplify;
+pub mod stage;
+#[cfg(feature = "storage")]
+pub mod storage;
+#[cfg(feature = "tui")]
+pub mod tui;
 
+mod availability;
+mod condition;
+mod diff;
+#[cfg(not(feature = "std"))]
+mod hash;
+mod index;
 mod link;
+mod meta;
 mod node;
+#[cfg(feature = "async")]
+mod provider;
+mod raw;
+mod resume;
+mod rng;
+mod shared;
+mod suggest;
 mod tree;
+mod value;
+mod walker;
 
-pub use link::Link;
-pub use node::Node;
+pub use availability::{Availability, HostState};
+pub use condition::{Clause, Condition, Op};
+pub use diff::{Migration, TreeDiff};
+pub use index::TreeIndex;
+pub use link::{Link, LinkOrder, WhenUnmet};
+pub use meta::Meta;
+pub use node::{Line, Node, NodeKind};
+#[cfg(feature = "async")]
+pub use provider::{ChoiceFuture, ChoiceProvider, SendChoiceFuture, SendChoiceProvider};
+pub use raw::RawTree;
+pub use resume::ResumePoints;
+pub use shared::SharedTree;
 pub use tree::Tree;
+pub use value::{Type, Value};
+pub use walker::{MenuItem, Narrate, Outcome, PresentedChoice, Status, Walker};
@@ -1,13 +1,59 @@
-use crate::node::Node;
+use alloc::{string::String, vec::Vec};
+
+use crate::{condition::Condition, node::Node, value::Value};
 
 /// A [`Link`] is a uni-directional path to a [`Node`] with descriptor [`dialogue`][`Link#structfield.dialogue`].
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct Link {
-    /// A key to the node being linked. This should be identical to an existing [`Node#key`][`Node#structfield.key`].
+    /// A key to the node being linked. This should be identical to an existing [`Node::key`].
     pub to_key: String,
 
-    /// The dialogue used to describe this link.
-    pub dialogue: String,
+    /// The dialogue used to describe this link, or [`None`] if it is a silent transition, e.g. an automatic or narrative one, which has no dialogue to present.
+    pub dialogue: Option<String>,
+
+    /// The priority of this link. Links with a higher priority are presented first when ordering by [`LinkOrder::Priority`]. A priority of [`None`] is treated as `0`.
+    pub priority: Option<i32>,
+
+    /// The relative chance of following this link from a [`NodeKind::Random`][`crate::NodeKind::Random`] node. A weight of [`None`] is treated as `1`, and links with a weight of `0` are never picked.
+    pub weight: Option<u32>,
+
+    /// The condition which must hold for this link to be followed, or [`None`] if it can always be followed.
+    pub condition: Option<Condition>,
+
+    /// The variables assigned when this link is followed, in authored order.
+    pub set: Vec<(String, Value)>,
+
+    /// The amounts the affinities of characters are adjusted by when this link is followed, after its variables are assigned, mapped by character id in authored order. See [`crate::affinity`].
+    pub affinity: Vec<(String, i64)>,
+
+    /// How this link is presented when its [`condition`][`Link#structfield.condition`] does not hold.
+    pub when_unmet: WhenUnmet,
+
+    /// The heading of the group this link belongs to, e.g. `Ask about...`, or [`None`] if it is not grouped. See [`Walker::menu`][`crate::Walker::menu`].
+    pub group: Option<String>,
+
+    /// Tags which categorize this link, in authored order, e.g. content ratings such as `mature` or `spoiler_act3`. See [`HostState#excluded_tags`][`crate::HostState#structfield.excluded_tags`].
+    pub tags: Vec<String>,
+}
+
+/// A [`LinkOrder`] determines the order in which a [`Node`]'s [`Link`]s are presented.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub enum LinkOrder {
+    /// Links are presented in the order they were authored.
+    #[default]
+    Authored,
+    /// Links are presented by descending [`Link#priority`][`Link#structfield.priority`], then in the order they were authored.
+    Priority,
+}
+
+/// A [`WhenUnmet`] determines how a [`Link`] whose condition does not hold is presented. See also: [`Walker::presented_choices`][`crate::Walker::presented_choices`].
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+pub enum WhenUnmet {
+    /// The link is not presented.
+    #[default]
+    Hidden,
+    /// The link is presented, but cannot be chosen, with the reason why, e.g. `Requires 10 gold`.
+    Disabled(String),
 }
 
 impl Link {
@@ -15,7 +61,7 @@ impl Link {
     ///
     /// # Arguments
     ///
-    /// * `to_key` - A string type that holds an identical [`Node#key`][`Node#structfield.key`] to which this [`Link`] corresponds.
+    /// * `to_key` - A string type that holds an identical [`Node::key`] to which this [`Link`] corresponds.
     /// * `dialogue` - A string type that holds associated descriptor dialogue.
     ///
     /// # Examples
@@ -30,10 +76,67 @@ impl Link {
     {
         Link {
             to_key: to_key.into(),
-            dialogue: dialogue.into(),
+            dialogue: Some(dialogue.into()),
+            priority: None,
+            weight: None,
+            condition: None,
+            set: Vec::new(),
+            affinity: Vec::new(),
+            when_unmet: WhenUnmet::Hidden,
+            group: None,
+            tags: Vec::new(),
         }
     }
 
+    /// Returns a silent [`Link`] which maps to a [`Node`] without dialogue, e.g. for an automatic or narrative transition.
+    ///
+    /// # Arguments
+    ///
+    /// * `to_key` - A string type that holds an identical [`Node::key`] to which this [`Link`] corresponds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::Link;
+    /// let link = Link::silent("end");
+    /// assert!(link.is_silent());
+    /// ```
+    pub fn silent<T>(to_key: T) -> Link
+    where
+        T: Into<String>,
+    {
+        Link {
+            dialogue: None,
+            ..Link::new(to_key.into(), String::new())
+        }
+    }
+
+    /// Returns whether this link is silent, having no dialogue to present.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::Link;
+    /// assert!(!Link::new("end", "Goodbye!").is_silent());
+    /// assert!(Link::silent("end").is_silent());
+    /// ```
+    pub fn is_silent(&self) -> bool {
+        self.dialogue.is_none()
+    }
+
+    /// Returns the dialogue of this link, or an empty string slice if it is silent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::Link;
+    /// assert_eq!("Goodbye!", Link::new("end", "Goodbye!").text());
+    /// assert_eq!("", Link::silent("end").text());
+    /// ```
+    pub fn text(&self) -> &str {
+        self.dialogue.as_deref().unwrap_or_default()
+    }
+
     /// Link two [`Node`]s together by creating a [`Link`] with descriptor dialogue.
     ///
     /// # Arguments
@@ -54,10 +157,7 @@ impl Link {
     where
         T: Into<String>,
     {
-        let link = Link {
-            to_key: to.key.clone(),
-            dialogue: dialogue.into(),
-        };
+        let link = Link::new(to.key.clone(), dialogue.into());
         from.links.push(link);
     }
 }
@@ -72,5 +172,5 @@ fn test_link() {
     let link_dialogue = "A simple link.";
     Link::link(&mut start_node, &end_node, link_dialogue);
     assert_eq!(1, start_node.links.len());
-    assert_eq!(link_dialogue, start_node.links.first().unwrap().dialogue);
+    assert_eq!(link_dialogue, start_node.links.first().unwrap().text());
 }
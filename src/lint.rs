@@ -0,0 +1,1612 @@
+//! A family of functions which check [`Tree`]s for likely authoring mistakes.
+//!
+//! Unlike [validation rules](https://github.com/simbleau/convo/blob/dev/FORMATTING.md#validation-rules), lints never prevent a [`Tree`] from being imported or exported. They are reported as [`Diagnostic`]s for authors to review.
+
+use alloc::{collections::BTreeMap, format, string::String, vec, vec::Vec};
+
+use crate::{
+    affinity,
+    availability::HostState,
+    bark::BARK_TAG,
+    condition::Clause,
+    inventory,
+    link::Link,
+    menu,
+    node::Node,
+    suggest,
+    tree::Tree,
+    value::{Type, Value},
+};
+#[cfg(feature = "std")]
+use crate::{
+    ast::{self, Span},
+    error::{ImportError, TreeError},
+    requirements::json_string,
+};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+#[cfg(feature = "std")]
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+#[cfg(feature = "std")]
+use yaml_rust::{Yaml, YamlLoader};
+
+/// The conventional file name of a [`LintConfig`], kept beside a project manifest or in the directory `convo lint` runs in.
+pub const CONFIG_FILE_NAME: &str = ".convolint.yml";
+
+/// A [`Severity`] describes how seriously a [`Diagnostic`] should be taken.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Severity {
+    /// The diagnostic is likely, but not certainly, an authoring mistake.
+    Warning,
+    /// The diagnostic is an authoring mistake.
+    Error,
+}
+
+/// A [`Rule`] identifies the lint check which produced a [`Diagnostic`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Rule {
+    /// A link targets the node it belongs to.
+    SelfLink,
+    /// A node has multiple links to the same target with identical dialogue.
+    DuplicateLink,
+    /// A node cannot be reached from the root node by following links.
+    UnreachableNode,
+    /// A [`TextLinter`], identified by its [`TextLinter::id`], reported a problem with some dialogue.
+    Text(&'static str),
+    /// A link jumps to a tree or node which does not exist in its [`crate::project::Project`].
+    BrokenJump,
+    /// A tree of a [`crate::project::Project`] is never jumped to, and is not the entry tree.
+    UnusedTree,
+    /// A link condition or assignment references a variable which is not declared.
+    UndeclaredVariable,
+    /// A link condition or assignment uses a variable in a way its declared type does not allow.
+    VariableType,
+    /// A link condition can never hold, because its clauses contradict each other.
+    ImpossibleCondition,
+    /// A node can only be reached from the root node through links whose conditions can never hold.
+    ImpossibleNode,
+    /// A node has no links, but is not marked as an intentional ending with [`Node#end`][`Node#structfield.end`].
+    DeadEnd,
+    /// A node key, link target, or dialogue contains an invisible or bidirectional control character, e.g. a zero-width space, so that different text looks identical.
+    InvisibleCharacter,
+    /// A dialogue line's speaker is not a registered character id. See also: [`crate::characters::Characters::lint`].
+    UnknownSpeaker,
+    /// A link targets a node which does not exist in its tree. Only reported in draft mode, see [`crate::draft::check`].
+    MissingNode,
+    /// A node's dialogue is a placeholder, e.g. `TODO`. Only reported in draft mode, see [`crate::draft::check`].
+    Placeholder,
+    /// A document, node, dialogue line, link group, or link field is not part of the format, and is ignored. Only reported on import, see [`crate::importer::source_to_tree_with_warnings`].
+    UnknownField,
+}
+
+impl Rule {
+    /// Returns a stable, human-readable identifier for this rule.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::lint::Rule;
+    /// assert_eq!("self-link", Rule::SelfLink.id());
+    /// ```
+    pub fn id(&self) -> &'static str {
+        match self {
+            Rule::SelfLink => "self-link",
+            Rule::DuplicateLink => "duplicate-link",
+            Rule::UnreachableNode => "unreachable-node",
+            Rule::Text(id) => id,
+            Rule::BrokenJump => "broken-jump",
+            Rule::UnusedTree => "unused-tree",
+            Rule::UndeclaredVariable => "undeclared-variable",
+            Rule::VariableType => "variable-type",
+            Rule::ImpossibleCondition => "impossible-condition",
+            Rule::ImpossibleNode => "impossible-node",
+            Rule::DeadEnd => "dead-end",
+            Rule::InvisibleCharacter => "invisible-character",
+            Rule::UnknownSpeaker => "unknown-speaker",
+            Rule::MissingNode => "missing-node",
+            Rule::Placeholder => "placeholder",
+            Rule::UnknownField => "unknown-field",
+        }
+    }
+
+    /// Returns the [`Severity`] this rule reports with.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::lint::{Rule, Severity};
+    /// assert_eq!(Severity::Warning, Rule::SelfLink.severity());
+    /// ```
+    pub fn severity(&self) -> Severity {
+        match self {
+            Rule::SelfLink => Severity::Warning,
+            Rule::DuplicateLink => Severity::Warning,
+            Rule::UnreachableNode => Severity::Warning,
+            Rule::Text(_) => Severity::Warning,
+            Rule::BrokenJump => Severity::Error,
+            Rule::UnusedTree => Severity::Warning,
+            Rule::UndeclaredVariable => Severity::Error,
+            Rule::VariableType => Severity::Error,
+            Rule::ImpossibleCondition => Severity::Error,
+            Rule::ImpossibleNode => Severity::Warning,
+            Rule::DeadEnd => Severity::Warning,
+            Rule::InvisibleCharacter => Severity::Warning,
+            Rule::UnknownSpeaker => Severity::Error,
+            Rule::MissingNode => Severity::Warning,
+            Rule::Placeholder => Severity::Warning,
+            Rule::UnknownField => Severity::Warning,
+        }
+    }
+}
+
+/// A [`Diagnostic`] is a single finding reported by a lint check.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Diagnostic {
+    /// The rule which produced this diagnostic.
+    pub rule: Rule,
+
+    /// The severity of this diagnostic.
+    pub severity: Severity,
+
+    /// The key of the [`crate::Node`] this diagnostic concerns.
+    pub node_key: String,
+
+    /// A human-readable description of the finding.
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub(crate) fn new<T>(rule: Rule, node_key: T, message: String) -> Self
+    where
+        T: Into<String>,
+    {
+        Diagnostic {
+            rule,
+            severity: rule.severity(),
+            node_key: node_key.into(),
+            message,
+        }
+    }
+}
+
+/// A [`LintConfig`] selects which lint rules run and the severity they report with, since different projects have different strictness needs.
+///
+/// Rules are identified by their [`Rule::id`], including the ids of [`TextLinter`]s. Rules which are not configured run with their default severity.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{lint::{LintConfig, Rule, Severity}, Link, Node, Tree};
+/// let mut tree = Tree::new();
+/// let mut start = Node::new("start", "Hello?");
+/// start.links.push(Link::new("start", "Hello?"));
+/// tree.insert_node(start);
+/// tree.set_root_key("start").unwrap();
+///
+/// let mut config = LintConfig::default();
+/// config.rules.insert("self-link".into(), Some(Severity::Error));
+/// let mut diagnostics = tree.lint();
+/// config.apply(&mut diagnostics);
+/// assert_eq!(Severity::Error, diagnostics[0].severity);
+///
+/// config.rules.insert("self-link".into(), None);
+/// config.apply(&mut diagnostics);
+/// assert!(diagnostics.is_empty());
+/// ```
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct LintConfig {
+    /// The configured rules, mapping rule ids to the severity to report with, or [`None`] if the rule is turned off.
+    pub rules: BTreeMap<String, Option<Severity>>,
+}
+
+impl LintConfig {
+    /// Try to return a [`LintConfig`] which is generated from importing a file, e.g. a [`CONFIG_FILE_NAME`] file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - A path type that references a lint configuration file to parse from.
+    ///
+    /// # Errors
+    ///
+    /// * An [`ImportError`] will be returned if the source is not valid YAML data or if the configuration is not considered legal when parsing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::lint::{LintConfig, Severity};
+    /// let config = LintConfig::import("examples/.convolint.yml").unwrap();
+    /// assert_eq!(Some(&Some(Severity::Error)), config.rules.get("unreachable-node"));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn import<P>(path: P) -> Result<Self, ImportError>
+    where
+        P: AsRef<Path>,
+    {
+        let source = fs::read_to_string(path)?;
+        LintConfig::try_from(&source)
+    }
+
+    /// Try to return a [`LintConfig`] which is generated from parsing a string slice. Its `rules` hash maps rule ids to `off`, `warning`, or `error`.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - A string slice that holds valid YAML data to parse from.
+    ///
+    /// # Errors
+    ///
+    /// * An [`ImportError`] will be returned if the source is not valid YAML data or if the configuration is not considered legal when parsing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::lint::{LintConfig, Severity};
+    /// let source = r#"
+    /// ---
+    /// rules:
+    ///   self-link: off
+    ///   max-length: error
+    /// "#;
+    /// let config = LintConfig::try_from(source).unwrap();
+    /// assert_eq!(Some(&None), config.rules.get("self-link"));
+    /// assert_eq!(Some(&Some(Severity::Error)), config.rules.get("max-length"));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn try_from(source: &str) -> Result<Self, ImportError> {
+        let docs = YamlLoader::load_from_str(source)?;
+        if docs.len() != 1 {
+            return Err(ImportError::MultipleDocumentsProvided());
+        }
+        Ok(yaml_to_config(&docs[0])?)
+    }
+
+    /// Removes the [`Diagnostic`]s of rules which are turned off, and sets the configured severity of the rest.
+    ///
+    /// # Arguments
+    ///
+    /// * `diagnostics` - The [`Diagnostic`]s to configure, e.g. from [`lint`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{lint::LintConfig, Node, Tree};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.insert_node(Node::new("orphan", "Hello?"));
+    /// tree.set_root_key("start").unwrap();
+    ///
+    /// let mut config = LintConfig::default();
+    /// config.rules.insert("unreachable-node".into(), None);
+    /// config.rules.insert("dead-end".into(), None);
+    /// let mut diagnostics = tree.lint();
+    /// config.apply(&mut diagnostics);
+    /// assert!(diagnostics.is_empty());
+    /// ```
+    pub fn apply(&self, diagnostics: &mut Vec<Diagnostic>) {
+        diagnostics.retain_mut(|diagnostic| match self.severity(diagnostic.rule) {
+            Some(severity) => {
+                diagnostic.severity = severity;
+                true
+            }
+            None => false,
+        });
+    }
+
+    /// Returns the [`Severity`] a [`Rule`] reports with under this configuration, or [`None`] if it is turned off.
+    ///
+    /// # Arguments
+    ///
+    /// * `rule` - The [`Rule`] to look up.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::lint::{LintConfig, Rule, Severity};
+    /// let mut config = LintConfig::default();
+    /// assert_eq!(Some(Severity::Warning), config.severity(Rule::SelfLink));
+    /// config.rules.insert("self-link".into(), None);
+    /// assert_eq!(None, config.severity(Rule::SelfLink));
+    /// ```
+    pub fn severity(&self, rule: Rule) -> Option<Severity> {
+        match self.rules.get(rule.id()) {
+            Some(severity) => *severity,
+            None => Some(rule.severity()),
+        }
+    }
+}
+
+/// A [`TextContext`] describes where a string checked by a [`TextLinter`] comes from.
+#[derive(Debug, Clone, Copy)]
+pub struct TextContext<'t> {
+    /// The node which owns the string.
+    pub node: &'t Node,
+
+    /// The link which owns the string, or [`None`] if the string is the node dialogue.
+    pub link: Option<&'t Link>,
+}
+
+/// A [`TextLinter`] checks dialogue strings, e.g. with a spell checker, a banned-word list, or house-style rules. Text linters are run over the dialogue of every node and link by [`lint_with`].
+///
+/// # Examples
+///
+/// ```
+/// use convo::{lint::{self, TextContext, TextLinter}, Node, Tree};
+///
+/// struct NoShouting;
+/// impl TextLinter for NoShouting {
+///     fn id(&self) -> &'static str {
+///         "no-shouting"
+///     }
+///
+///     fn check(&self, text: &str, _context: TextContext<'_>) -> Vec<String> {
+///         text.split_whitespace()
+///             .filter(|word| word.len() > 1 && word.chars().all(|c| c.is_ascii_uppercase()))
+///             .map(|word| format!("`{}` is shouted", word))
+///             .collect()
+///     }
+/// }
+///
+/// let mut tree = Tree::new();
+/// let mut start = Node::new("start", "HELLO there!");
+/// start.end = true;
+/// tree.insert_node(start);
+/// let diagnostics = lint::lint_with(&tree, &[&NoShouting]);
+/// assert_eq!(lint::Rule::Text("no-shouting"), diagnostics[0].rule);
+/// ```
+pub trait TextLinter {
+    /// Returns a stable, human-readable identifier for this linter, reported as [`Rule::Text`].
+    fn id(&self) -> &'static str;
+
+    /// Returns the [`Severity`] this linter reports with. Defaults to [`Severity::Warning`].
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    /// Returns a message for each problem found in a string. An empty [`Vec`] means the string is fine.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The dialogue to check.
+    /// * `context` - The node and link the dialogue belongs to.
+    fn check(&self, text: &str, context: TextContext<'_>) -> Vec<String>;
+}
+
+/// A [`MaxLength`] is a [`TextLinter`] which reports dialogue longer than a UI can display, e.g. 70 characters for choice buttons. Lengths are counted in characters. It reports under the `max-length` rule.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{lint::{self, MaxLength}, Link, Node, Tree};
+/// let mut tree = Tree::new();
+/// let mut start = Node::new("start", "Hello!");
+/// let mut end = Node::new("end", "Goodbye!");
+/// end.end = true;
+/// Link::link(&mut start, &end, "Could you say that once more, but a little slower this time?");
+/// tree.insert_node(start);
+/// tree.insert_node(end);
+/// let max_length = MaxLength {
+///     dialogue: None,
+///     link_dialogue: Some(40),
+/// };
+/// let diagnostics = lint::lint_with(&tree, &[&max_length]);
+/// assert_eq!("max-length", diagnostics[0].rule.id());
+/// ```
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct MaxLength {
+    /// The maximum length of node dialogue, or [`None`] for no limit.
+    pub dialogue: Option<usize>,
+
+    /// The maximum length of link dialogue, or [`None`] for no limit.
+    pub link_dialogue: Option<usize>,
+}
+
+impl TextLinter for MaxLength {
+    fn id(&self) -> &'static str {
+        "max-length"
+    }
+
+    fn check(&self, text: &str, context: TextContext<'_>) -> Vec<String> {
+        let (max, kind) = match context.link {
+            Some(_) => (self.link_dialogue, "Link dialogue"),
+            None => (self.dialogue, "Dialogue"),
+        };
+        let length = text.chars().count();
+        match max {
+            Some(max) if length > max => vec![format!(
+                "{} `{}` is {} characters long, exceeding the maximum of {}",
+                kind, text, length, max
+            )],
+            _ => vec![],
+        }
+    }
+}
+
+/// Returns the [`Diagnostic`]s found by running every lint check over a [`Tree`].
+///
+/// # Arguments
+///
+/// * `tree` - A [`Tree`] to check.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{lint, Link, Node, Tree};
+/// let mut tree = Tree::new();
+/// let mut node = Node::new("start", "Hello?");
+/// node.links.push(Link::new("start", "Hello?"));
+/// tree.insert_node(node);
+/// let diagnostics = lint::lint(&tree);
+/// assert_eq!(lint::Rule::SelfLink, diagnostics[0].rule);
+/// ```
+pub fn lint(tree: &Tree) -> Vec<Diagnostic> {
+    lint_with(tree, &[])
+}
+
+/// Returns the [`Diagnostic`]s found by running every lint check, followed by every [`TextLinter`], over a [`Tree`].
+///
+/// # Arguments
+///
+/// * `tree` - A [`Tree`] to check.
+/// * `text_linters` - The [`TextLinter`]s to run over the dialogue of every node and link.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{lint::{self, TextContext, TextLinter}, Node, Tree};
+///
+/// struct Banned(&'static str);
+/// impl TextLinter for Banned {
+///     fn id(&self) -> &'static str {
+///         "banned-word"
+///     }
+///
+///     fn check(&self, text: &str, _context: TextContext<'_>) -> Vec<String> {
+///         match text.contains(self.0) {
+///             true => vec![format!("`{}` is banned", self.0)],
+///             false => vec![],
+///         }
+///     }
+/// }
+///
+/// let mut tree = Tree::new();
+/// let mut start = Node::new("start", "Darn it!");
+/// start.end = true;
+/// tree.insert_node(start);
+/// let diagnostics = lint::lint_with(&tree, &[&Banned("Darn")]);
+/// assert_eq!("banned-word", diagnostics[0].rule.id());
+/// ```
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(name = "lint", level = "debug", skip_all, fields(nodes = tree.len()))
+)]
+pub fn lint_with(tree: &Tree, text_linters: &[&dyn TextLinter]) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    lint_self_links(tree, &mut diagnostics);
+    lint_duplicate_links(tree, &mut diagnostics);
+    lint_unreachable_nodes(tree, &mut diagnostics);
+    lint_dead_ends(tree, &mut diagnostics);
+    lint_invisible_characters(tree, &mut diagnostics);
+    let names: Vec<&str> = tree.variables().keys().map(String::as_str).collect();
+    lint_variables(
+        tree,
+        |name| tree.variables().get(name),
+        &names,
+        &mut diagnostics,
+    );
+    lint_conditions(tree, |name| tree.variables().get(name), &mut diagnostics);
+    lint_text(tree, text_linters, &mut diagnostics);
+
+    #[cfg(feature = "tracing")]
+    tracing::debug!(diagnostics = diagnostics.len(), "linted tree");
+
+    diagnostics
+}
+
+/// A [`FileReport`] is the outcome of importing and linting a single dialogue file.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct FileReport {
+    /// The path of the dialogue file.
+    pub path: PathBuf,
+
+    /// The [`Diagnostic`]s found in the file, or the [`ImportError`] which stopped it from being imported.
+    pub result: Result<Vec<Diagnostic>, ImportError>,
+
+    /// The spans of the node keys defined in the file, by key, if its YAML could be parsed.
+    pub spans: HashMap<String, Span>,
+}
+
+#[cfg(feature = "std")]
+impl FileReport {
+    fn check<P>(path: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let mut spans = HashMap::new();
+        let result = fs::read_to_string(path)
+            .map_err(ImportError::from)
+            .and_then(|source| {
+                let document = ast::parse(&source)?;
+                for (key, _) in document.nodes() {
+                    if let Some(name) = key.as_str() {
+                        spans.insert(name.to_owned(), key.span);
+                    }
+                }
+                document.to_tree()
+            })
+            .map(|tree| lint(&tree));
+        FileReport {
+            path: path.to_path_buf(),
+            result,
+            spans,
+        }
+    }
+
+    /// Returns whether the file was imported with no [`Diagnostic`]s.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::lint;
+    /// let reports = lint::lint_files(&["examples/dialogue_files/ex_1.convo.yml"]);
+    /// assert!(reports[0].is_clean());
+    /// ```
+    pub fn is_clean(&self) -> bool {
+        matches!(&self.result, Ok(diagnostics) if diagnostics.is_empty())
+    }
+}
+
+/// Returns a [`FileReport`] for each dialogue file, in the order given, by importing (and thereby validating) and linting them one at a time.
+///
+/// # Arguments
+///
+/// * `paths` - The paths of the dialogue files to check.
+///
+/// # Examples
+///
+/// ```
+/// use convo::lint;
+/// let reports = lint::lint_files(&[
+///     "examples/dialogue_files/ex_1.convo.yml",
+///     "examples/dialogue_files/ex_bad.convo.yml",
+/// ]);
+/// assert!(reports[0].result.is_ok());
+/// assert!(reports[1].result.is_err());
+/// ```
+#[cfg(feature = "std")]
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip_all, fields(files = paths.len()))
+)]
+pub fn lint_files<P>(paths: &[P]) -> Vec<FileReport>
+where
+    P: AsRef<Path>,
+{
+    paths.iter().map(FileReport::check).collect()
+}
+
+/// Returns a [`FileReport`] for each dialogue file, in the order given, by importing (and thereby validating) and linting them in parallel on the [`rayon`] thread pool.
+///
+/// This produces the same reports as [`lint_files`], and is considerably faster for large projects.
+///
+/// # Arguments
+///
+/// * `paths` - The paths of the dialogue files to check.
+///
+/// # Examples
+///
+/// ```
+/// use convo::lint;
+/// let reports = lint::par_lint_files(&[
+///     "examples/dialogue_files/ex_1.convo.yml",
+///     "examples/dialogue_files/ex_bad.convo.yml",
+/// ]);
+/// assert!(reports[0].result.is_ok());
+/// assert!(reports[1].result.is_err());
+/// ```
+#[cfg(feature = "rayon")]
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip_all, fields(files = paths.len()))
+)]
+pub fn par_lint_files<P>(paths: &[P]) -> Vec<FileReport>
+where
+    P: AsRef<Path> + Sync,
+{
+    paths.par_iter().map(FileReport::check).collect()
+}
+
+/// Returns [`FileReport`]s as a JSON array with one object per finding, so CI systems and editor plugins can consume them.
+///
+/// Each object has a `rule` id, a `severity` of `"warning"` or `"error"`, the `file` path, the `node` key it concerns or `null`, the `span` of that node's key or `null`, and a `message`. Spans have a `start` and an `end`, each with a `line` and a `column` in characters, counting from 1. Files which could not be imported report a single finding with the rule id `import`, located where possible.
+///
+/// # Arguments
+///
+/// * `reports` - The [`FileReport`]s to write, e.g. from [`lint_files`].
+///
+/// # Examples
+///
+/// ```
+/// use convo::lint;
+/// let reports = lint::lint_files(&["examples/dialogue_files/ex_bad.convo.yml"]);
+/// let json = lint::to_json(&reports);
+/// assert!(json.starts_with(r#"[{"rule":"import","severity":"error","file":"examples/dialogue_files/ex_bad.convo.yml","#));
+/// ```
+#[cfg(feature = "std")]
+pub fn to_json(reports: &[FileReport]) -> String {
+    let mut findings = vec![];
+    for report in reports {
+        match &report.result {
+            Ok(diagnostics) => findings.extend(diagnostics.iter().map(|diagnostic| {
+                json_finding(
+                    &report.path,
+                    diagnostic.rule.id(),
+                    diagnostic.severity,
+                    Some(diagnostic.node_key.as_str()),
+                    report.spans.get(&diagnostic.node_key).map(json_span),
+                    &diagnostic.message,
+                )
+            })),
+            Err(error) => {
+                let (message, node, span) = match error {
+                    ImportError::Scan(e) => {
+                        let marker = e.marker();
+                        let position = json_position(marker.line(), marker.col() + 1);
+                        let span = format!("{{\"start\":{},\"end\":{}}}", position, position);
+                        (e.to_string(), None, Some(span))
+                    }
+                    ImportError::Validation(TreeError::Validation(message)) => {
+                        // Locate the first node the message names
+                        let node = message
+                            .split('`')
+                            .skip(1)
+                            .step_by(2)
+                            .find(|name| report.spans.contains_key(*name));
+                        (message.clone(), node, None)
+                    }
+                    ImportError::Validation(TreeError::NodeDNE { key, suggestion }) => (
+                        format!(
+                            "Node `{}` does not exist{}",
+                            key,
+                            suggest::did_you_mean(suggestion.as_deref())
+                        ),
+                        None,
+                        None,
+                    ),
+                    error => (format!("{:?}", error), None, None),
+                };
+                let span =
+                    span.or_else(|| node.and_then(|node| report.spans.get(node)).map(json_span));
+                findings.push(json_finding(
+                    &report.path,
+                    "import",
+                    Severity::Error,
+                    node,
+                    span,
+                    &message,
+                ));
+            }
+        }
+    }
+    format!("[{}]", findings.join(","))
+}
+
+// Writes a finding of `to_json`, where the span is already written as JSON
+#[cfg(feature = "std")]
+pub(crate) fn json_finding(
+    path: &Path,
+    rule: &str,
+    severity: Severity,
+    node_key: Option<&str>,
+    span: Option<String>,
+    message: &str,
+) -> String {
+    let severity = match severity {
+        Severity::Warning => "warning",
+        Severity::Error => "error",
+    };
+    format!(
+        "{{\"rule\":{},\"severity\":\"{}\",\"file\":{},\"node\":{},\"span\":{},\"message\":{}}}",
+        json_string(rule),
+        severity,
+        json_string(&path.to_string_lossy()),
+        node_key.map_or_else(|| "null".to_owned(), json_string),
+        span.unwrap_or_else(|| "null".to_owned()),
+        json_string(message)
+    )
+}
+
+#[cfg(feature = "std")]
+pub(crate) fn json_span(span: &Span) -> String {
+    format!(
+        "{{\"start\":{},\"end\":{}}}",
+        json_position(span.start.line + 1, span.start.column + 1),
+        json_position(span.end.line + 1, span.end.column + 1)
+    )
+}
+
+#[cfg(feature = "std")]
+fn json_position(line: usize, column: usize) -> String {
+    format!("{{\"line\":{},\"column\":{}}}", line, column)
+}
+
+#[cfg(feature = "std")]
+fn yaml_to_config(yaml: &Yaml) -> Result<LintConfig, TreeError> {
+    let mut config = LintConfig::default();
+
+    // The configuration must be a hash
+    let fields = yaml.as_hash().ok_or_else(|| {
+        TreeError::Validation(format!(
+            "YAML lint configuration is not a hash: '{:?}'",
+            yaml
+        ))
+    })?;
+    for (yaml_key, yaml_value) in fields {
+        if yaml_key.as_str() != Some("rules") {
+            return Err(TreeError::Validation(format!(
+                "YAML lint configuration contains an unknown key: `{:?}`",
+                yaml_key
+            )));
+        }
+        let rules = yaml_value.as_hash().ok_or_else(|| {
+            TreeError::Validation(format!(
+                "YAML lint rules are not a hash: '{:?}'",
+                yaml_value
+            ))
+        })?;
+        for (rule, level) in rules {
+            let rule = rule.as_str().ok_or_else(|| {
+                TreeError::Validation(format!("YAML lint rule is not a string: `{:?}`", rule))
+            })?;
+            let severity = match level.as_str() {
+                Some("off") => None,
+                Some("warning") => Some(Severity::Warning),
+                Some("error") => Some(Severity::Error),
+                _ => {
+                    return Err(TreeError::Validation(format!(
+                        "YAML lint rule `{}` is not `off`, `warning`, or `error`: '{:?}'",
+                        rule, level
+                    )))
+                }
+            };
+            config.rules.insert(rule.into(), severity);
+        }
+    }
+
+    Ok(config)
+}
+
+fn lint_self_links(tree: &Tree, diagnostics: &mut Vec<Diagnostic>) {
+    for (key, node) in &tree.nodes {
+        for link in node.links.iter().filter(|link| &link.to_key == key) {
+            diagnostics.push(Diagnostic::new(
+                Rule::SelfLink,
+                key,
+                format!("Link `{}` targets its own node `{}`", link.text(), key),
+            ));
+        }
+    }
+}
+
+fn lint_duplicate_links(tree: &Tree, diagnostics: &mut Vec<Diagnostic>) {
+    for (key, node) in &tree.nodes {
+        for (i, link) in node.links.iter().enumerate() {
+            // Only report the later duplicates
+            let is_duplicate = node.links[..i]
+                .iter()
+                .any(|prev| prev.to_key == link.to_key && prev.dialogue == link.dialogue);
+            if is_duplicate {
+                diagnostics.push(Diagnostic::new(
+                    Rule::DuplicateLink,
+                    key,
+                    format!(
+                        "Link `{}` to `{}` is duplicated in node `{}`",
+                        link.text(),
+                        link.to_key,
+                        key
+                    ),
+                ));
+            }
+        }
+    }
+}
+
+pub(crate) fn lint_unreachable_nodes(tree: &Tree, diagnostics: &mut Vec<Diagnostic>) {
+    // Without a root, nothing is reachable and every node would be reported
+    let root_key = match tree.root_key() {
+        Some(root_key) => root_key,
+        None => return,
+    };
+    // Barks are drawn from pools rather than reached by links
+    let mut unreachable = tree.build_index().unreachable_nodes();
+    unreachable.retain(|node| !node.tags.iter().any(|tag| tag == BARK_TAG));
+    let entry_points = match tree.entries().is_empty() {
+        true => "",
+        false => " or an entry point",
+    };
+    for node in unreachable {
+        diagnostics.push(Diagnostic::new(
+            Rule::UnreachableNode,
+            &node.key,
+            format!(
+                "Node `{}` cannot be reached from the root node `{}`{}",
+                node.key, root_key, entry_points
+            ),
+        ));
+    }
+}
+
+fn lint_dead_ends(tree: &Tree, diagnostics: &mut Vec<Diagnostic>) {
+    // Barks are one-liners, which never need links
+    let dead_ends = tree.nodes().filter(|node| {
+        node.links.is_empty() && !node.end && !node.tags.iter().any(|tag| tag == BARK_TAG)
+    });
+    for node in dead_ends {
+        diagnostics.push(Diagnostic::new(
+            Rule::DeadEnd,
+            &node.key,
+            format!(
+                "Node `{}` has no links, but is not marked as an ending with `end: true`",
+                node.key
+            ),
+        ));
+    }
+}
+
+fn lint_invisible_characters(tree: &Tree, diagnostics: &mut Vec<Diagnostic>) {
+    for node in tree.nodes() {
+        let mut check = |text: &str, is_key: bool, location: &str| {
+            if let Some((c, name)) = invisible_character(text, is_key) {
+                diagnostics.push(Diagnostic::new(
+                    Rule::InvisibleCharacter,
+                    &node.key,
+                    format!(
+                        "Node `{}` has an invisible character (U+{:04X} {}) in {}",
+                        node.key, c as u32, name, location
+                    ),
+                ));
+            }
+        };
+        check(&node.key, true, "its key");
+        check(&node.dialogue, false, "its dialogue");
+        for link in &node.links {
+            check(
+                &link.to_key,
+                true,
+                &format!("the key of its link to `{}`", link.to_key),
+            );
+            check(
+                link.text(),
+                false,
+                &format!("the dialogue of its link to `{}`", link.to_key),
+            );
+        }
+    }
+}
+
+// Returns the first invisible or bidirectional control character of some text, and its name. Joiners are
+// only reported in keys, since dialogue needs them for emoji sequences and some scripts
+fn invisible_character(text: &str, is_key: bool) -> Option<(char, &'static str)> {
+    text.chars().find_map(|c| {
+        let name = match c {
+            '\u{061c}' => "ARABIC LETTER MARK",
+            '\u{180e}' => "MONGOLIAN VOWEL SEPARATOR",
+            '\u{200b}' => "ZERO WIDTH SPACE",
+            '\u{200c}' if is_key => "ZERO WIDTH NON-JOINER",
+            '\u{200d}' if is_key => "ZERO WIDTH JOINER",
+            '\u{200e}' => "LEFT-TO-RIGHT MARK",
+            '\u{200f}' => "RIGHT-TO-LEFT MARK",
+            '\u{202a}' => "LEFT-TO-RIGHT EMBEDDING",
+            '\u{202b}' => "RIGHT-TO-LEFT EMBEDDING",
+            '\u{202c}' => "POP DIRECTIONAL FORMATTING",
+            '\u{202d}' => "LEFT-TO-RIGHT OVERRIDE",
+            '\u{202e}' => "RIGHT-TO-LEFT OVERRIDE",
+            '\u{2060}' => "WORD JOINER",
+            '\u{2061}'..='\u{2064}' => "INVISIBLE OPERATOR",
+            '\u{2066}' => "LEFT-TO-RIGHT ISOLATE",
+            '\u{2067}' => "RIGHT-TO-LEFT ISOLATE",
+            '\u{2068}' => "FIRST STRONG ISOLATE",
+            '\u{2069}' => "POP DIRECTIONAL ISOLATE",
+            '\u{3164}' => "HANGUL FILLER",
+            '\u{feff}' => "ZERO WIDTH NO-BREAK SPACE",
+            _ => return None,
+        };
+        Some((c, name))
+    })
+}
+
+// Clock variables, affinities, and item counts are integers which need not be declared
+static BUILTIN_TYPE: Type = Type::Int;
+
+// The entries chosen from menus are strings which need not be declared
+static MENU_TYPE: Type = Type::String;
+
+fn or_builtin<'d, F>(declared: F) -> impl Fn(&str) -> Option<&'d Type>
+where
+    F: Fn(&str) -> Option<&'d Type>,
+{
+    move |name| {
+        declared(name).or_else(|| {
+            let builtin = HostState::CLOCK.contains(&name)
+                || affinity::character(name).is_some()
+                || inventory::item(name).is_some();
+            match menu::source(name) {
+                Some(_) => Some(&MENU_TYPE),
+                None => builtin.then_some(&BUILTIN_TYPE),
+            }
+        })
+    }
+}
+
+pub(crate) fn lint_variables<'d, F>(
+    tree: &Tree,
+    declared: F,
+    names: &[&str],
+    diagnostics: &mut Vec<Diagnostic>,
+) where
+    F: Fn(&str) -> Option<&'d Type>,
+{
+    let declared = or_builtin(declared);
+    for node in tree.nodes() {
+        for link in &node.links {
+            let mut check = |name: &str, usage: &str, allowed: &dyn Fn(&Type) -> bool| {
+                let (rule, problem) = match declared(name) {
+                    None => (
+                        Rule::UndeclaredVariable,
+                        format!(
+                            "is not declared{}",
+                            suggest::did_you_mean(suggest::closest(name, names.iter().copied()))
+                        ),
+                    ),
+                    Some(declared) if !allowed(declared) => {
+                        (Rule::VariableType, format!("is declared as `{}`", declared))
+                    }
+                    Some(_) => return,
+                };
+                diagnostics.push(Diagnostic::new(
+                    rule,
+                    &node.key,
+                    format!(
+                        "Link `{}` in node `{}` {}, but `{}` {}",
+                        link.text(),
+                        node.key,
+                        usage,
+                        name,
+                        problem
+                    ),
+                ));
+            };
+
+            // Conditions
+            let clauses = link
+                .condition
+                .iter()
+                .flat_map(|condition| &condition.clauses);
+            for clause in clauses {
+                // Flags on items test whether any are held
+                if matches!(clause, Clause::Flag { name, .. } if inventory::item(name).is_some()) {
+                    continue;
+                }
+                let usage = match clause {
+                    Clause::Flag { name, .. } => format!("tests the flag `{}`", name),
+                    Clause::Compare { name, op, value } => format!(
+                        "compares `{} {} {}`",
+                        name,
+                        op.as_str(),
+                        value_source(value)
+                    ),
+                    Clause::Within { name, start, end } => {
+                        format!("tests the range `{} in {}..{}`", name, start, end)
+                    }
+                };
+                check(clause.name(), &usage, &|declared| {
+                    clause.is_allowed(declared)
+                });
+            }
+
+            // Assignments
+            for (name, value) in &link.set {
+                let usage = format!("assigns `{} = {}`", name, value_source(value));
+                check(name, &usage, &|declared| declared.accepts(value));
+            }
+        }
+    }
+}
+
+pub(crate) fn lint_conditions<'d, F>(tree: &Tree, declared: F, diagnostics: &mut Vec<Diagnostic>)
+where
+    F: Fn(&str) -> Option<&'d Type>,
+{
+    let declared = or_builtin(declared);
+
+    // Links whose conditions can never hold
+    let mut impossible: Vec<(&str, usize)> = vec![];
+    for node in tree.nodes() {
+        for (i, link) in node.links.iter().enumerate() {
+            let condition = match &link.condition {
+                Some(condition) => condition,
+                None => continue,
+            };
+            if let Some(name) = condition.contradiction(&declared) {
+                impossible.push((&node.key, i));
+                diagnostics.push(Diagnostic::new(
+                    Rule::ImpossibleCondition,
+                    &node.key,
+                    format!(
+                        "Link `{}` in node `{}` has the condition `{}`, which can never hold for `{}`",
+                        link.text(), node.key, condition, name
+                    ),
+                ));
+            }
+        }
+    }
+    if impossible.is_empty() {
+        return;
+    }
+
+    // Nodes which are only reachable by following those links
+    let root_key = match tree.root_key() {
+        Some(root_key) => root_key,
+        None => return,
+    };
+    let mut reached: Vec<&str> = vec![root_key];
+    for entry_key in tree.entries().values() {
+        if !reached.contains(&entry_key.as_str()) {
+            reached.push(entry_key);
+        }
+    }
+    let mut i = 0;
+    while i < reached.len() {
+        let key = reached[i];
+        i += 1;
+        let links = tree
+            .node(key)
+            .map(|node| &node.links[..])
+            .unwrap_or_default();
+        for (j, link) in links.iter().enumerate() {
+            let followable = !impossible.contains(&(key, j)) && tree.contains_node(&link.to_key);
+            if followable && !reached.contains(&link.to_key.as_str()) {
+                reached.push(&link.to_key);
+            }
+        }
+    }
+    let index = tree.build_index();
+    for node in tree.nodes() {
+        if index.is_reachable(&node.key) && !reached.contains(&node.key.as_str()) {
+            diagnostics.push(Diagnostic::new(
+                Rule::ImpossibleNode,
+                &node.key,
+                format!(
+                    "Node `{}` can only be reached from the root node `{}` through conditions which can never hold",
+                    node.key, root_key
+                ),
+            ));
+        }
+    }
+}
+
+fn value_source(value: &Value) -> String {
+    match value {
+        Value::Bool(value) => format!("{}", value),
+        Value::Int(value) => format!("{}", value),
+        Value::String(value) => format!("{:?}", value),
+    }
+}
+
+fn lint_text(tree: &Tree, text_linters: &[&dyn TextLinter], diagnostics: &mut Vec<Diagnostic>) {
+    for linter in text_linters {
+        for node in tree.nodes() {
+            // Lines are checked individually, rather than as the dialogue they make up
+            let dialogue = match node.lines.is_empty() {
+                true => vec![&node.dialogue],
+                false => node.lines.iter().map(|line| &line.text).collect(),
+            };
+            let texts = dialogue.into_iter().map(|text| (text, None)).chain(
+                node.links
+                    .iter()
+                    .filter_map(|link| Some((link.dialogue.as_ref()?, Some(link)))),
+            );
+            for (text, link) in texts {
+                for message in linter.check(text, TextContext { node, link }) {
+                    let mut diagnostic =
+                        Diagnostic::new(Rule::Text(linter.id()), &node.key, message);
+                    diagnostic.severity = linter.severity();
+                    diagnostics.push(diagnostic);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_lint_self_links() {
+    use crate::{link::Link, node::Node};
+
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "The start node.");
+    let mut end = Node::new("end", "The end node.");
+    end.end = true;
+    Link::link(&mut start, &end, "Go to the end.");
+    tree.insert_node(start.clone());
+    tree.insert_node(end);
+
+    // Should be clean
+    assert!(lint(&tree).is_empty());
+
+    // Should warn once about the self link
+    start.links.push(Link::new("start", "Stay here."));
+    tree.insert_node(start);
+    let diagnostics = lint(&tree);
+    assert_eq!(1, diagnostics.len());
+    assert_eq!(Rule::SelfLink, diagnostics[0].rule);
+    assert_eq!(Severity::Warning, diagnostics[0].severity);
+    assert_eq!("start", diagnostics[0].node_key);
+}
+
+#[test]
+fn test_lint_duplicate_links() {
+    use crate::{link::Link, node::Node};
+
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "The start node.");
+    start.links.push(Link::new("end", "Goodbye."));
+    start.links.push(Link::new("end", "Farewell."));
+    tree.insert_node(start.clone());
+    let mut end = Node::new("end", "The end node.");
+    end.end = true;
+    tree.insert_node(end);
+
+    // Same target with different dialogue is fine
+    assert!(lint(&tree).is_empty());
+
+    // Should warn once per duplicate
+    start.links.push(Link::new("end", "Goodbye."));
+    start.links.push(Link::new("end", "Goodbye."));
+    tree.insert_node(start);
+    let diagnostics = lint(&tree);
+    assert_eq!(2, diagnostics.len());
+    assert!(diagnostics
+        .iter()
+        .all(|diagnostic| diagnostic.rule == Rule::DuplicateLink));
+}
+
+#[test]
+fn test_lint_unreachable_nodes() {
+    use crate::{link::Link, node::Node};
+
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "The start node.");
+    let mut end = Node::new("end", "The end node.");
+    end.end = true;
+    Link::link(&mut start, &end, "Go to the end.");
+    tree.insert_node(start);
+    tree.insert_node(end);
+    let mut orphan = Node::new("orphan", "The orphan node.");
+    orphan.end = true;
+    tree.insert_node(orphan);
+
+    // Should not report anything without a root
+    assert!(lint(&tree).is_empty());
+
+    // Should warn once about the orphan
+    tree.set_root_key("start").unwrap();
+    let diagnostics = lint(&tree);
+    assert_eq!(1, diagnostics.len());
+    assert_eq!(Rule::UnreachableNode, diagnostics[0].rule);
+    assert_eq!("orphan", diagnostics[0].node_key);
+
+    // Barks are not reported
+    let mut bark = Node::new("bark", "Hm.");
+    bark.tags.push(BARK_TAG.into());
+    tree.insert_node(bark);
+    assert_eq!(1, lint(&tree).len());
+
+    // Entry points are reachable
+    tree.set_entry("lonely", "orphan").unwrap();
+    assert!(lint(&tree).is_empty());
+}
+
+#[test]
+fn test_lint_invisible_characters() {
+    use crate::{link::Link, node::Node};
+
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "Hello\u{200b} there.");
+    start.links.push(Link::new("end\u{202e}", "Bye."));
+    start.links.push(Link::new(
+        "family",
+        "\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}",
+    ));
+    tree.insert_node(start);
+    let mut end = Node::new("end\u{202e}", "Farewell.");
+    end.end = true;
+    tree.insert_node(end);
+    let mut family = Node::new("family", "Welcome home.");
+    family.end = true;
+    tree.insert_node(family);
+    tree.set_root_key("start").unwrap();
+
+    // Joiners are allowed in dialogue, but not in keys
+    let diagnostics = lint(&tree);
+    assert_eq!(3, diagnostics.len());
+    assert!(diagnostics
+        .iter()
+        .all(|diagnostic| diagnostic.rule == Rule::InvisibleCharacter));
+    assert_eq!(
+        "Node `start` has an invisible character (U+200B ZERO WIDTH SPACE) in its dialogue",
+        diagnostics[0].message
+    );
+    assert!(diagnostics[1]
+        .message
+        .contains("U+202E RIGHT-TO-LEFT OVERRIDE"));
+    assert!(diagnostics[1]
+        .message
+        .ends_with("in the key of its link to `end\u{202e}`"));
+    assert_eq!("end\u{202e}", diagnostics[2].node_key);
+    assert!(diagnostics[2].message.ends_with("in its key"));
+
+    tree.node_mut("family").unwrap().key = "fam\u{200d}ily".into();
+    assert!(lint(&tree)[3].message.contains("U+200D ZERO WIDTH JOINER"));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_lint_files() {
+    let paths = [
+        "examples/dialogue_files/ex_1.convo.yml",
+        "examples/dialogue_files/ex_bad.convo.yml",
+        "examples/dialogue_files/does_not_exist.convo.yml",
+    ];
+    let reports = lint_files(&paths);
+    assert_eq!(3, reports.len());
+    assert_eq!(Path::new(paths[0]), reports[0].path);
+    assert!(reports[0].is_clean());
+    assert!(matches!(reports[1].result, Err(ImportError::Validation(_))));
+    assert!(matches!(reports[2].result, Err(ImportError::IO(_))));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_to_json() {
+    let dir = std::env::temp_dir();
+    let path = dir.join(format!("convo_lint_{}.convo.yml", std::process::id()));
+    let bad_path = dir.join(format!("convo_lint_{}_bad.convo.yml", std::process::id()));
+    let invalid_path = dir.join(format!(
+        "convo_lint_{}_invalid.convo.yml",
+        std::process::id()
+    ));
+    let source = "---\nroot: start\nnodes:\n  start:\n    dialogue: \"Hi \\\"you\\\"\"\n    links:\n      - start: Again\n";
+    fs::write(&path, source).unwrap();
+    fs::write(&bad_path, "---\nroot: [start\n").unwrap();
+    let invalid = "---\nroot: start\nnodes:\n  start:\n    dialogue: Hi\n    availability: 3\n";
+    fs::write(&invalid_path, invalid).unwrap();
+    let reports = lint_files(&[&path, &bad_path, &invalid_path]);
+    for path in [&path, &bad_path, &invalid_path] {
+        fs::remove_file(path).unwrap();
+    }
+
+    // Lints are located at the key of the node they concern
+    let file = json_string(&path.to_string_lossy());
+    assert_eq!(
+        format!(
+            concat!(
+                r#"{{"rule":"self-link","severity":"warning","file":{},"node":"start","#,
+                r#""span":{{"start":{{"line":4,"column":3}},"end":{{"line":4,"column":8}}}},"#,
+                r#""message":"Link `Again` targets its own node `start`"}}"#
+            ),
+            file
+        ),
+        to_json(&reports[..1])[1..].trim_end_matches(']')
+    );
+
+    // Scan errors are located where they occur
+    let json = to_json(&reports[1..2]);
+    assert!(json.starts_with(r#"[{"rule":"import","severity":"error","file":"#));
+    assert!(json.contains(r#""node":null,"span":{"start":{"line":3,"#));
+
+    // Validation errors are located at the first node they name
+    let json = to_json(&reports[2..]);
+    assert!(json.contains(r#""node":"start","span":{"start":{"line":4,"column":3}"#));
+    assert_eq!("[]", to_json(&[]));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_lint_config() {
+    let config = LintConfig::try_from("rules:\n  self-link: off\n  recorder: error\n").unwrap();
+    assert_eq!(None, config.severity(Rule::SelfLink));
+    assert_eq!(
+        Some(Severity::Error),
+        config.severity(Rule::Text("recorder"))
+    );
+    assert_eq!(
+        Some(Severity::Warning),
+        config.severity(Rule::UnreachableNode)
+    );
+
+    let mut diagnostics = vec![
+        Diagnostic::new(Rule::SelfLink, "a", "".into()),
+        Diagnostic::new(Rule::Text("recorder"), "b", "".into()),
+        Diagnostic::new(Rule::UnreachableNode, "c", "".into()),
+    ];
+    config.apply(&mut diagnostics);
+    let severities: Vec<(&str, Severity)> = diagnostics
+        .iter()
+        .map(|d| (d.node_key.as_str(), d.severity))
+        .collect();
+    assert_eq!(
+        vec![("b", Severity::Error), ("c", Severity::Warning)],
+        severities
+    );
+
+    // Levels and keys are checked
+    for source in [
+        "rules:\n  self-link: loud\n",
+        "rules: [self-link]\n",
+        "rule:\n  self-link: off\n",
+    ] {
+        assert!(matches!(
+            LintConfig::try_from(source),
+            Err(ImportError::Validation(TreeError::Validation(_)))
+        ));
+    }
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_par_lint_files() {
+    // Many files should be reported in the order given
+    let paths: Vec<&str> = (0..64)
+        .map(|i| match i % 3 {
+            0 => "examples/dialogue_files/ex_1.convo.yml",
+            1 => "examples/dialogue_files/ex_min.convo.yml",
+            _ => "examples/dialogue_files/ex_bad.convo.yml",
+        })
+        .collect();
+    let sequential = lint_files(&paths);
+    let parallel = par_lint_files(&paths);
+    assert_eq!(paths.len(), parallel.len());
+    for (expected, actual) in sequential.iter().zip(&parallel) {
+        assert_eq!(expected.path, actual.path);
+        assert_eq!(expected.result.is_ok(), actual.result.is_ok());
+        if let (Ok(expected), Ok(actual)) = (&expected.result, &actual.result) {
+            assert_eq!(expected, actual);
+        }
+    }
+}
+
+#[test]
+fn test_lint_text() {
+    use crate::node::Line;
+    use alloc::borrow::ToOwned;
+
+    struct Recorder;
+    impl TextLinter for Recorder {
+        fn id(&self) -> &'static str {
+            "recorder"
+        }
+
+        fn severity(&self) -> Severity {
+            Severity::Error
+        }
+
+        fn check(&self, text: &str, context: TextContext<'_>) -> Vec<String> {
+            let to = context.link.map_or("-", |link| link.to_key.as_str());
+            vec![format!("{}:{}:{}", context.node.key, to, text)]
+        }
+    }
+
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "Hello!");
+    let mut end = Node::new("end", "Goodbye!");
+    end.end = true;
+    Link::link(&mut start, &end, "Bye!");
+    tree.insert_node(start);
+    tree.insert_node(end);
+
+    // Should check every dialogue and link string, with context
+    let diagnostics = lint_with(&tree, &[&Recorder]);
+    let messages: Vec<String> = diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.message.to_owned())
+        .collect();
+    assert_eq!(
+        vec!["start:-:Hello!", "start:end:Bye!", "end:-:Goodbye!"],
+        messages
+    );
+    assert!(diagnostics.iter().all(|diagnostic| {
+        diagnostic.rule == Rule::Text("recorder") && diagnostic.severity == Severity::Error
+    }));
+    assert_eq!("end", diagnostics[2].node_key);
+
+    // Should not run without text linters
+    assert!(lint(&tree).is_empty());
+
+    // Should check lines individually, without their speakers
+    tree.node_mut("end")
+        .unwrap()
+        .set_lines(vec![Line::new("a", "Bye."), Line::new("b", "Later.")]);
+    let diagnostics = lint_with(&tree, &[&Recorder]);
+    assert_eq!("end:-:Bye.", diagnostics[2].message);
+    assert_eq!("end:-:Later.", diagnostics[3].message);
+}
+
+#[test]
+fn test_lint_conditions() {
+    use crate::{condition::Condition, link::Link, node::Node};
+
+    // start -> a (impossible) -> end, start -> b -> end
+    let mut tree = Tree::new();
+    tree.variables_mut().insert("gold".into(), Type::Int);
+    let mut start = Node::new("start", "Start");
+    let mut a = Node::new("a", "A");
+    let mut b = Node::new("b", "B");
+    let mut end = Node::new("end", "End");
+    end.end = true;
+    Link::link(&mut start, &a, "To A");
+    Link::link(&mut start, &b, "To B");
+    Link::link(&mut a, &end, "To the end");
+    Link::link(&mut b, &end, "To the end");
+    start.links[0].condition = Some(Condition::parse("gold > 5 && gold < 3").unwrap());
+    start.links[1].condition = Some(Condition::parse("gold > 5").unwrap());
+    let mut start_b = start.clone();
+    for node in [start, a, b, end] {
+        tree.insert_node(node);
+    }
+    tree.set_root_key("start").unwrap();
+
+    // Should report the impossible link and the node behind it
+    let diagnostics = lint(&tree);
+    assert_eq!(2, diagnostics.len());
+    assert_eq!(Rule::ImpossibleCondition, diagnostics[0].rule);
+    assert_eq!(Severity::Error, diagnostics[0].severity);
+    assert_eq!("start", diagnostics[0].node_key);
+    assert_eq!(
+        "Link `To A` in node `start` has the condition `gold > 5 && gold < 3`, which can never hold for `gold`",
+        diagnostics[0].message
+    );
+    assert_eq!(Rule::ImpossibleNode, diagnostics[1].rule);
+    assert_eq!(Severity::Warning, diagnostics[1].severity);
+    assert_eq!("a", diagnostics[1].node_key);
+
+    // Nodes only reachable past impossible nodes are reported too
+    start_b.links[1].condition = Some(Condition::parse("gold == 1 && gold == 2").unwrap());
+    tree.insert_node(start_b);
+    let impossible: Vec<String> = lint(&tree)
+        .into_iter()
+        .filter(|diagnostic| diagnostic.rule == Rule::ImpossibleNode)
+        .map(|diagnostic| diagnostic.node_key)
+        .collect();
+    assert_eq!(vec!["a", "b", "end"], impossible);
+}
+
+#[test]
+fn test_max_length() {
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "Héllo!");
+    let mut end = Node::new("end", "Bye.");
+    end.end = true;
+    Link::link(&mut start, &end, "Bye now!");
+    tree.insert_node(start);
+    tree.insert_node(end);
+
+    // Lengths are counted in characters, inclusively
+    let max_length = MaxLength {
+        dialogue: Some(6),
+        link_dialogue: Some(8),
+    };
+    assert!(lint_with(&tree, &[&max_length]).is_empty());
+
+    // Node and link dialogue are limited separately
+    let max_length = MaxLength {
+        dialogue: Some(5),
+        link_dialogue: Some(4),
+    };
+    let diagnostics = lint_with(&tree, &[&max_length]);
+    assert_eq!(2, diagnostics.len());
+    assert_eq!(
+        "Dialogue `Héllo!` is 6 characters long, exceeding the maximum of 5",
+        diagnostics[0].message
+    );
+    assert_eq!(
+        "Link dialogue `Bye now!` is 8 characters long, exceeding the maximum of 4",
+        diagnostics[1].message
+    );
+    assert!(diagnostics.iter().all(|diagnostic| {
+        diagnostic.node_key == "start" && diagnostic.rule == Rule::Text("max-length")
+    }));
+
+    // No limits report nothing
+    assert!(lint_with(&tree, &[&MaxLength::default()]).is_empty());
+}
+
+#[test]
+fn test_lint_variables() {
+    use crate::condition::Condition;
+
+    let mut tree = Tree::new();
+    let variables = tree.variables_mut();
+    variables.insert("met".into(), Type::Bool);
+    variables.insert("gold".into(), Type::Int);
+    variables.insert(
+        "mood".into(),
+        Type::Enum(vec!["happy".into(), "angry".into()]),
+    );
+    let mut start = Node::new("start", "Hello!");
+    let mut end = Node::new("end", "Goodbye!");
+    end.end = true;
+    let mut link = Link::new("end", "Bye!");
+    link.condition = Some(Condition::parse("!met && gold >= 10 && mood == happy").unwrap());
+    link.set = vec![
+        ("met".into(), Value::Bool(true)),
+        ("mood".into(), "angry".into()),
+    ];
+    start.links.push(link);
+    tree.insert_node(start);
+    tree.insert_node(end);
+
+    // Should be clean
+    assert!(lint(&tree).is_empty());
+
+    // Should report undeclared variables and type mismatches
+    let link = &mut tree.node_mut("start").unwrap().links[0];
+    link.condition =
+        Some(Condition::parse("gold && mood > happy && mood == sad && gld == 1").unwrap());
+    link.set = vec![
+        ("met".into(), Value::Int(1)),
+        ("gold".into(), Value::Int(1)),
+    ];
+    let diagnostics = lint(&tree);
+    let rules: Vec<Rule> = diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.rule)
+        .collect();
+    assert_eq!(
+        vec![
+            Rule::VariableType,
+            Rule::VariableType,
+            Rule::VariableType,
+            Rule::UndeclaredVariable,
+            Rule::VariableType,
+        ],
+        rules
+    );
+    assert_eq!(
+        "Link `Bye!` in node `start` compares `mood == \"sad\"`, but `mood` is declared as `[happy, angry]`",
+        diagnostics[2].message
+    );
+    assert_eq!(
+        "Link `Bye!` in node `start` compares `gld == 1`, but `gld` is not declared, did you mean `gold`?",
+        diagnostics[3].message
+    );
+    assert!(diagnostics
+        .iter()
+        .all(|diagnostic| diagnostic.severity == Severity::Error));
+
+    // Clock variables, affinities, items, and menu entries need not be declared
+    let link = &mut tree.node_mut("start").unwrap().links[0];
+    link.condition = Some(
+        Condition::parse(
+            "hour in 9..17 && day >= 3 && affinity(npc_bob) > 2 && item(key) && item(arrows) >= 10 && menu.stock == potion && minute",
+        )
+        .unwrap(),
+    );
+    link.set = vec![("affinity.npc_alice".into(), Value::Int(1))];
+    let diagnostics = lint(&tree);
+    assert_eq!(1, diagnostics.len());
+    assert_eq!(
+        "Link `Bye!` in node `start` tests the flag `minute`, but `minute` is declared as `int`",
+        diagnostics[0].message
+    );
+
+    // Contradictory clock ranges are reported too
+    let link = &mut tree.node_mut("start").unwrap().links[0];
+    link.condition = Some(Condition::parse("hour in 9..12 && hour >= 14").unwrap());
+    let rules: Vec<Rule> = lint(&tree).iter().map(|d| d.rule).collect();
+    assert!(rules.contains(&Rule::ImpossibleCondition));
+}
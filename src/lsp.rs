@@ -0,0 +1,722 @@
+//! A [Language Server Protocol](https://microsoft.github.io/language-server-protocol/) server for `*.convo.yml` files, so editors such as VS Code report problems while dialogue is written.
+//!
+//! The server speaks JSON-RPC over a stream, usually the standard input and output of `convo lsp`, and keeps open documents in memory with full text synchronization. The following methods are supported:
+//!
+//! | Method | Behavior |
+//! | --- | --- |
+//! | `textDocument/publishDiagnostics` | Import errors, links to missing nodes, and [lints](crate::lint) are reported whenever a document is opened or changed. |
+//! | `textDocument/definition` | A link target, `root`, or switch target leads to the node it names. |
+//! | `textDocument/rename` | A node key is renamed along with every reference to it in the document. |
+//! | `textDocument/completion` | Node keys are completed inside `links` and switch `cases`, and after `root` and `default`. |
+//!
+//! Node keys and references are located with the spans of [`crate::ast`], so the document is parsed once per change.
+
+use crate::{
+    ast::{self, Document, Item},
+    error::{ImportError, TreeError},
+    lint::{self, Severity},
+    suggest,
+};
+
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, Write},
+};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const REQUEST_FAILED: i64 = -32803;
+
+const SEVERITY_ERROR: u8 = 1;
+const SEVERITY_WARNING: u8 = 2;
+const COMPLETION_REFERENCE: u8 = 18;
+
+/// A [`LanguageServer`] answers the requests of an editor about the documents it has open.
+#[derive(Debug, Default)]
+pub struct LanguageServer {
+    documents: HashMap<String, String>,
+    shutdown: bool,
+    exited: bool,
+}
+
+impl LanguageServer {
+    /// Returns a [`LanguageServer`] with no open documents.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::lsp::LanguageServer;
+    /// let server = LanguageServer::new();
+    /// ```
+    pub fn new() -> Self {
+        LanguageServer::default()
+    }
+
+    /// Try to serve messages read from a stream, writing responses and notifications to another, until the editor sends `exit` or the input ends.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - A stream of messages, each preceded by a `Content-Length` header.
+    /// * `writer` - A stream which messages are written to.
+    ///
+    /// # Errors
+    ///
+    /// * An [`io::Error`] will be returned if the streams cannot be read or written.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use convo::lsp::LanguageServer;
+    /// use std::io;
+    /// LanguageServer::new()
+    ///     .serve(io::stdin().lock(), io::stdout().lock())
+    ///     .unwrap();
+    /// ```
+    pub fn serve<R, W>(&mut self, mut reader: R, mut writer: W) -> io::Result<()>
+    where
+        R: BufRead,
+        W: Write,
+    {
+        while !self.exited {
+            // Read the headers
+            let mut content_length = None;
+            loop {
+                let mut header = String::new();
+                if reader.read_line(&mut header)? == 0 {
+                    return Ok(());
+                }
+                if header.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = header.split_once(':') {
+                    if name.trim().eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().ok();
+                    }
+                }
+            }
+
+            // Messages without a length cannot be read
+            let content_length = match content_length {
+                Some(content_length) => content_length,
+                None => continue,
+            };
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body)?;
+            for message in self.handle(&String::from_utf8_lossy(&body)) {
+                write!(
+                    writer,
+                    "Content-Length: {}\r\n\r\n{}",
+                    message.len(),
+                    message
+                )?;
+            }
+            writer.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Returns the messages to send in answer to a message from the editor, which are a response for requests, and any diagnostics to publish.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - A string slice that holds a JSON-RPC request or notification.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::lsp::LanguageServer;
+    /// let mut server = LanguageServer::new();
+    /// let messages = server.handle(
+    ///     r#"{"jsonrpc": "2.0", "method": "textDocument/didOpen", "params": {"textDocument": {"uri": "file:///a.convo.yml", "text": "root: start\nnodes:\n  start:\n    dialogue: Hi\n    links:\n      - end: Bye\n"}}}"#,
+    /// );
+    /// assert!(messages[0].contains("Node `end` does not exist"));
+    /// ```
+    pub fn handle(&mut self, message: &str) -> Vec<String> {
+        let message: Value = match serde_json::from_str(message) {
+            Ok(message) => message,
+            Err(e) => return vec![error_response(Value::Null, PARSE_ERROR, e.to_string())],
+        };
+        let method = message
+            .get("method")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+        // Requests are answered, and notifications are not
+        let id = match message.get("id") {
+            Some(id) => id.clone(),
+            None => return self.notify(method, &params),
+        };
+        let result = match method {
+            _ if self.shutdown => Err((INVALID_REQUEST, "The server is shut down".to_owned())),
+            "initialize" => Ok(json!({
+                "capabilities": {
+                    "textDocumentSync": 1,
+                    "definitionProvider": true,
+                    "renameProvider": true,
+                    "completionProvider": { "triggerCharacters": ["-", " "] },
+                },
+                "serverInfo": { "name": "convo", "version": env!("CARGO_PKG_VERSION") },
+            })),
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Value::Null)
+            }
+            "textDocument/definition" => self.definition(&params),
+            "textDocument/rename" => self.rename(&params),
+            "textDocument/completion" => self.completion(&params),
+            _ => Err((METHOD_NOT_FOUND, format!("Unknown method `{}`", method))),
+        };
+        let response = match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "result": result, "id": id }).to_string(),
+            Err((code, message)) => error_response(id, code, message),
+        };
+        vec![response]
+    }
+
+    fn notify(&mut self, method: &str, params: &Value) -> Vec<String> {
+        let uri = match params["textDocument"]["uri"].as_str() {
+            Some(uri) => uri.to_owned(),
+            None => {
+                if method == "exit" {
+                    self.exited = true;
+                }
+                return Vec::new();
+            }
+        };
+        match method {
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                self.documents.insert(uri.clone(), text.to_owned());
+                vec![publish_diagnostics(&uri, diagnose(text))]
+            }
+            "textDocument/didChange" => {
+                // Changes hold the full text, so only the last matters
+                let changes = params["contentChanges"].as_array();
+                let text = match changes.and_then(|changes| changes.last()) {
+                    Some(change) => change["text"].as_str().unwrap_or_default(),
+                    None => return Vec::new(),
+                };
+                self.documents.insert(uri.clone(), text.to_owned());
+                vec![publish_diagnostics(&uri, diagnose(text))]
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                vec![publish_diagnostics(&uri, Vec::new())]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn definition(&self, params: &Value) -> Result<Value, (i64, String)> {
+        let (uri, source) = self.document(params)?;
+        let outline = Outline::new(source, ast::parse(source).ok().as_ref());
+        let definition = outline
+            .symbol_at(position(params)?)
+            .and_then(|symbol| outline.definition(&symbol.key));
+        Ok(match definition {
+            Some(definition) => json!({ "uri": uri, "range": definition.range() }),
+            None => Value::Null,
+        })
+    }
+
+    fn rename(&self, params: &Value) -> Result<Value, (i64, String)> {
+        let (uri, source) = self.document(params)?;
+        let new_name = params["newName"]
+            .as_str()
+            .ok_or_else(|| (INVALID_PARAMS, "Missing string `newName`".to_owned()))?;
+        let outline = Outline::new(source, ast::parse(source).ok().as_ref());
+        let key = match outline.symbol_at(position(params)?) {
+            Some(symbol) => &symbol.key,
+            None => return Ok(Value::Null),
+        };
+        if !is_plain_key(new_name) {
+            return Err((
+                REQUEST_FAILED,
+                format!(
+                    "Node key `{}` may only hold letters, digits, `_`, `-`, and `.`",
+                    new_name
+                ),
+            ));
+        }
+        if outline.definition(new_name).is_some() {
+            return Err((
+                REQUEST_FAILED,
+                format!("Node `{}` already exists", new_name),
+            ));
+        }
+        let edits: Vec<Value> = outline
+            .definitions
+            .iter()
+            .chain(&outline.references)
+            .filter(|symbol| symbol.key == *key)
+            .map(|symbol| json!({ "range": symbol.range(), "newText": new_name }))
+            .collect();
+        Ok(json!({ "changes": { uri: edits } }))
+    }
+
+    fn completion(&self, params: &Value) -> Result<Value, (i64, String)> {
+        let (_, source) = self.document(params)?;
+        let outline = Outline::new(source, ast::parse(source).ok().as_ref());
+        let (line, _) = position(params)?;
+        if !outline.completes(line) {
+            return Ok(json!([]));
+        }
+        let items: Vec<Value> = outline
+            .definitions
+            .iter()
+            .map(|symbol| json!({ "label": symbol.key, "kind": COMPLETION_REFERENCE }))
+            .collect();
+        Ok(Value::Array(items))
+    }
+
+    fn document<'p>(&self, params: &'p Value) -> Result<(&'p str, &str), (i64, String)> {
+        let uri = params["textDocument"]["uri"].as_str().ok_or_else(|| {
+            (
+                INVALID_PARAMS,
+                "Missing string `textDocument.uri`".to_owned(),
+            )
+        })?;
+        let source = self
+            .documents
+            .get(uri)
+            .ok_or_else(|| (INVALID_PARAMS, format!("Unknown document `{}`", uri)))?;
+        Ok((uri, source))
+    }
+}
+
+fn position(params: &Value) -> Result<(usize, usize), (i64, String)> {
+    let field = |name: &str| {
+        params["position"][name]
+            .as_u64()
+            .map(|value| value as usize)
+            .ok_or_else(|| {
+                (
+                    INVALID_PARAMS,
+                    format!("Missing unsigned integer `position.{}`", name),
+                )
+            })
+    };
+    Ok((field("line")?, field("character")?))
+}
+
+fn is_plain_key(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '.')
+}
+
+// Returns the diagnostics of a document, which are its import errors, links to missing nodes, and lints
+fn diagnose(source: &str) -> Vec<Value> {
+    let document = ast::parse(source);
+    let outline = Outline::new(source, document.as_ref().ok());
+    let mut diagnostics = Vec::new();
+    let tree = document.and_then(|document| document.to_tree());
+
+    // Links to missing nodes are imported, but fail when walked
+    let keys: Vec<&str> = match &tree {
+        Ok(tree) => tree.nodes().map(|node| node.key.as_str()).collect(),
+        Err(_) => outline
+            .definitions
+            .iter()
+            .map(|symbol| symbol.key.as_str())
+            .collect(),
+    };
+    for reference in &outline.references {
+        if !keys.contains(&reference.key.as_str()) {
+            let suggestion = suggest::closest(&reference.key, keys.iter().copied());
+            diagnostics.push(diagnostic(
+                reference.range(),
+                SEVERITY_ERROR,
+                None,
+                format!(
+                    "Node `{}` does not exist{}",
+                    reference.key,
+                    suggest::did_you_mean(suggestion)
+                ),
+            ));
+        }
+    }
+
+    match tree {
+        Ok(tree) => {
+            for finding in lint::lint(&tree) {
+                let range = outline
+                    .definition(&finding.node_key)
+                    .map(Symbol::range)
+                    .unwrap_or_else(|| point(0, 0));
+                let severity = match finding.severity {
+                    Severity::Error => SEVERITY_ERROR,
+                    Severity::Warning => SEVERITY_WARNING,
+                };
+                diagnostics.push(diagnostic(
+                    range,
+                    severity,
+                    Some(finding.rule.id()),
+                    finding.message,
+                ));
+            }
+        }
+        Err(error) => {
+            let (range, message) = match error {
+                ImportError::Scan(e) => (
+                    point(e.marker().line().saturating_sub(1), e.marker().col()),
+                    e.to_string(),
+                ),
+                ImportError::Validation(TreeError::Validation(message)) => {
+                    (outline.locate(&message), message)
+                }
+                // Missing nodes were reported where they are referenced
+                ImportError::Validation(TreeError::NodeDNE { .. }) => return diagnostics,
+                error => (point(0, 0), format!("{:?}", error)),
+            };
+            diagnostics.push(diagnostic(range, SEVERITY_ERROR, None, message));
+        }
+    }
+    diagnostics
+}
+
+fn diagnostic(range: Value, severity: u8, code: Option<&str>, message: String) -> Value {
+    let mut diagnostic = json!({
+        "range": range,
+        "severity": severity,
+        "source": "convo",
+        "message": message,
+    });
+    if let Some(code) = code {
+        diagnostic["code"] = json!(code);
+    }
+    diagnostic
+}
+
+fn point(line: usize, character: usize) -> Value {
+    let position = json!({ "line": line, "character": character });
+    json!({ "start": position, "end": position })
+}
+
+fn publish_diagnostics(uri: &str, diagnostics: Vec<Value>) -> String {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": { "uri": uri, "diagnostics": diagnostics },
+    })
+    .to_string()
+}
+
+fn error_response(id: Value, code: i64, message: String) -> String {
+    json!({
+        "jsonrpc": "2.0",
+        "error": { "code": code, "message": message },
+        "id": id,
+    })
+    .to_string()
+}
+
+// A node key where it is written, without quotes, with columns counted in UTF-16 code units as editors do
+#[derive(Debug, PartialEq)]
+struct Symbol {
+    key: String,
+    line: usize,
+    start: usize,
+    end: usize,
+}
+
+impl Symbol {
+    fn new(source: &str, item: &Item) -> Option<Self> {
+        let key = item.as_str()?;
+        let (mut start, mut end) = (item.span.start.offset, item.span.end.offset);
+        if source[start..end].starts_with(['"', '\'']) {
+            start += 1;
+            end -= 1;
+        }
+        let line_start = source[..start].rfind('\n').map_or(0, |newline| newline + 1);
+        let column = source[line_start..start].encode_utf16().count();
+        Some(Symbol {
+            key: key.to_owned(),
+            line: item.span.start.line,
+            start: column,
+            end: column + source[start..end].encode_utf16().count(),
+        })
+    }
+
+    fn range(&self) -> Value {
+        json!({
+            "start": { "line": self.line, "character": self.start },
+            "end": { "line": self.line, "character": self.end },
+        })
+    }
+}
+// The node keys of a document, where they are defined and referenced, and the lines where they are completed
+#[derive(Debug, Default)]
+struct Outline {
+    definitions: Vec<Symbol>,
+    references: Vec<Symbol>,
+    completion_lines: Vec<(usize, usize)>,
+}
+
+impl Outline {
+    fn new(source: &str, document: Option<&Document>) -> Self {
+        let mut outline = Outline::default();
+        let document = match document {
+            Some(document) => document,
+            None => return outline,
+        };
+        outline.definitions = document
+            .nodes()
+            .iter()
+            .filter_map(|(key, _)| Symbol::new(source, key))
+            .collect();
+        outline.references = document
+            .node_references()
+            .into_iter()
+            .filter_map(|item| Symbol::new(source, item))
+            .collect();
+
+        // Keys are completed inside links, switch cases, and entry points, and as the value of `root` and `default`
+        let mut completed: Vec<&Item> = ["root", "entries"]
+            .iter()
+            .filter_map(|key| document.get(key))
+            .collect();
+        for (_, node) in document.nodes() {
+            completed.extend(
+                ["links", "cases", "default"]
+                    .iter()
+                    .filter_map(|key| node.get(key)),
+            );
+        }
+        outline.completion_lines = completed
+            .iter()
+            .map(|item| (item.span.start.line, item.span.end.line))
+            .collect();
+        outline
+    }
+
+    fn completes(&self, line: usize) -> bool {
+        self.completion_lines
+            .iter()
+            .any(|&(start, end)| start <= line && line <= end)
+    }
+
+    fn definition(&self, key: &str) -> Option<&Symbol> {
+        self.definitions.iter().find(|symbol| symbol.key == key)
+    }
+
+    fn symbol_at(&self, (line, character): (usize, usize)) -> Option<&Symbol> {
+        self.definitions
+            .iter()
+            .chain(&self.references)
+            .find(|symbol| {
+                symbol.line == line && symbol.start <= character && character <= symbol.end
+            })
+    }
+
+    // Returns the range of the first node key named in backticks by a message
+    fn locate(&self, message: &str) -> Value {
+        message
+            .split('`')
+            .skip(1)
+            .step_by(2)
+            .map(|name| name.trim_matches('"'))
+            .find_map(|name| {
+                self.definitions
+                    .iter()
+                    .chain(&self.references)
+                    .find(|symbol| symbol.key == name)
+            })
+            .map(Symbol::range)
+            .unwrap_or_else(|| point(0, 0))
+    }
+}
+
+#[cfg(test)]
+const TEST_SOURCE: &str = r#"---
+root: start
+nodes:
+  start:
+    dialogue: "Need a blade?"
+    links:
+      - buy: "Yes."
+      - to: "end"
+        dialogue: "No."
+        set: { gold: 0 }
+      - group: "Ask about..."
+        links:
+          - ends: "The forge."
+  buy:
+    switch: mood
+    cases:
+      calm: end
+    default: start
+  end:
+    dialogue: "Farewell, Ünsal."
+    end: true
+variables:
+  gold: int
+  mood: [calm, angry]
+"#;
+
+#[cfg(test)]
+#[test]
+fn test_outline() {
+    let outline = Outline::new(TEST_SOURCE, Some(&ast::parse(TEST_SOURCE).unwrap()));
+    fn keys(symbols: &[Symbol]) -> Vec<(&str, usize)> {
+        symbols
+            .iter()
+            .map(|symbol| (symbol.key.as_str(), symbol.line))
+            .collect()
+    }
+    assert_eq!(
+        vec![("start", 3), ("buy", 13), ("end", 18)],
+        keys(&outline.definitions)
+    );
+    assert_eq!(
+        vec![
+            ("start", 1),
+            ("buy", 6),
+            ("end", 7),
+            ("ends", 12),
+            ("end", 16),
+            ("start", 17)
+        ],
+        keys(&outline.references)
+    );
+
+    // Quoted targets are located without their quotes
+    assert_eq!((13, 16), {
+        let end = &outline.references[2];
+        (end.start, end.end)
+    });
+
+    // Keys are completed inside links and cases, and after `root` and `default`
+    assert!(outline.completes(1));
+    assert!(!outline.completes(4));
+    assert!(outline.completes(6));
+    assert!(outline.completes(9));
+    assert!(!outline.completes(14));
+    assert!(outline.completes(16));
+    assert!(outline.completes(17));
+
+    // Documents which cannot be parsed have no keys
+    let outline = Outline::new("nodes: [", None);
+    assert!(outline.definitions.is_empty() && !outline.completes(0));
+}
+
+#[test]
+fn test_handle() {
+    let mut server = LanguageServer::new();
+    let mut call = |request: Value| -> Vec<Value> {
+        server
+            .handle(&request.to_string())
+            .iter()
+            .map(|message| serde_json::from_str(message).unwrap())
+            .collect()
+    };
+    let uri = "file:///forge.convo.yml";
+    let at = |line: usize, character: usize| json!({ "textDocument": { "uri": uri }, "position": { "line": line, "character": character } });
+
+    let response = call(json!({ "jsonrpc": "2.0", "method": "initialize", "params": {}, "id": 1 }));
+    assert_eq!(
+        true,
+        response[0]["result"]["capabilities"]["renameProvider"]
+    );
+
+    // Opening a document publishes its diagnostics
+    let messages = call(json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didOpen",
+        "params": { "textDocument": { "uri": uri, "text": TEST_SOURCE } },
+    }));
+    let diagnostics = &messages[0]["params"]["diagnostics"];
+    assert_eq!("textDocument/publishDiagnostics", messages[0]["method"]);
+    assert_eq!(1, diagnostics.as_array().unwrap().len());
+    assert_eq!(
+        "Node `ends` does not exist, did you mean `end`?",
+        diagnostics[0]["message"]
+    );
+    assert_eq!(12, diagnostics[0]["range"]["start"]["line"]);
+
+    // Link targets lead to their nodes
+    let mut params = at(16, 15);
+    let response = call(
+        json!({ "jsonrpc": "2.0", "method": "textDocument/definition", "params": params, "id": 2 }),
+    );
+    assert_eq!(18, response[0]["result"]["range"]["start"]["line"]);
+    assert_eq!(2, response[0]["result"]["range"]["start"]["character"]);
+
+    // Renaming a key renames every reference to it
+    params["newName"] = json!("farewell");
+    let response = call(
+        json!({ "jsonrpc": "2.0", "method": "textDocument/rename", "params": params, "id": 3 }),
+    );
+    assert_eq!(
+        3,
+        response[0]["result"]["changes"][uri]
+            .as_array()
+            .unwrap()
+            .len()
+    );
+    params["newName"] = json!("buy");
+    let response = call(
+        json!({ "jsonrpc": "2.0", "method": "textDocument/rename", "params": params, "id": 4 }),
+    );
+    assert_eq!(REQUEST_FAILED, response[0]["error"]["code"]);
+
+    // Keys are completed inside links
+    let response = call(
+        json!({ "jsonrpc": "2.0", "method": "textDocument/completion", "params": at(6, 8), "id": 5 }),
+    );
+    assert_eq!(3, response[0]["result"].as_array().unwrap().len());
+    let response = call(
+        json!({ "jsonrpc": "2.0", "method": "textDocument/completion", "params": at(4, 8), "id": 6 }),
+    );
+    assert_eq!(0, response[0]["result"].as_array().unwrap().len());
+
+    // Import errors are located by the keys they name
+    let messages = call(json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didChange",
+        "params": {
+            "textDocument": { "uri": uri },
+            "contentChanges": [{ "text": "root: start\nnodes:\n  start:\n    tags: 5\n" }],
+        },
+    }));
+    let diagnostics = &messages[0]["params"]["diagnostics"];
+    assert_eq!(1, diagnostics.as_array().unwrap().len());
+    assert_eq!(2, diagnostics[0]["range"]["start"]["line"]);
+
+    // Requests fail after shutting down
+    call(json!({ "jsonrpc": "2.0", "method": "shutdown", "id": 7 }));
+    let response = call(
+        json!({ "jsonrpc": "2.0", "method": "textDocument/completion", "params": at(0, 0), "id": 8 }),
+    );
+    assert_eq!(INVALID_REQUEST, response[0]["error"]["code"]);
+}
+
+#[test]
+fn test_serve() {
+    use std::io::Cursor;
+
+    let frame = |body: &str| format!("Content-Length: {}\r\n\r\n{}", body.len(), body);
+    let input = [
+        frame(r#"{"jsonrpc": "2.0", "method": "initialize", "params": {}, "id": 1}"#),
+        frame(r#"{"jsonrpc": "2.0", "method": "initialized", "params": {}}"#),
+        frame(r#"{"jsonrpc": "2.0", "method": "shutdown", "id": 2}"#),
+        frame(r#"{"jsonrpc": "2.0", "method": "exit"}"#),
+        frame(r#"{"jsonrpc": "2.0", "method": "shutdown", "id": 3}"#),
+    ]
+    .concat();
+    let mut output = Vec::new();
+    LanguageServer::new()
+        .serve(Cursor::new(input), &mut output)
+        .unwrap();
+
+    // Requests are answered until the editor exits
+    let output = String::from_utf8(output).unwrap();
+    assert_eq!(2, output.matches("Content-Length: ").count());
+    assert!(output.contains(r#""id":2"#));
+    assert!(!output.contains(r#""id":3"#));
+}
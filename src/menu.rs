@@ -0,0 +1,135 @@
+//! Menus whose choices are generated at runtime from entries the host supplies, e.g. a shop's stock, alongside authored choices.
+//!
+//! A node of kind [`NodeKind::Menu`][`crate::NodeKind::Menu`] names a source of entries, which the host supplies with a [`MenuProvider`] set by [`Walker::set_menu`][`crate::Walker::set_menu`]. The first link of the node is a template, which is presented once per entry with the entry's dialogue, followed by the node's other links as authored. Following an entry follows the template, and assigns the entry's id to the string variable [`MENU_PREFIX`] followed by the source, e.g. `menu.shop_stock`, so the node it leads to can branch on what was chosen. Menu variables need not be declared.
+
+use alloc::{format, string::String, sync::Arc, vec::Vec};
+use core::fmt;
+
+/// The prefix of menu variable names.
+pub const MENU_PREFIX: &str = "menu.";
+
+/// A [`MenuEntry`] is a choice the host supplies for a menu, e.g. an item in stock.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct MenuEntry {
+    /// The id of the entry, assigned to the menu variable when it is chosen.
+    pub id: String,
+
+    /// The dialogue presented for the entry, e.g. `Iron sword (50 gold)`.
+    pub dialogue: String,
+}
+
+impl MenuEntry {
+    /// Returns a [`MenuEntry`] with an id and the dialogue presented for it.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A string type that holds the id of the entry.
+    /// * `dialogue` - A string type that holds the dialogue presented for the entry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::menu::MenuEntry;
+    /// let entry = MenuEntry::new("iron_sword", "Iron sword (50 gold)");
+    /// assert_eq!("iron_sword", entry.id);
+    /// ```
+    pub fn new<T>(id: T, dialogue: T) -> MenuEntry
+    where
+        T: Into<String>,
+    {
+        MenuEntry {
+            id: id.into(),
+            dialogue: dialogue.into(),
+        }
+    }
+}
+
+/// A [`MenuProvider`] supplies the entries of menus, as kept by the game.
+///
+/// Any `Fn(&str) -> Vec<MenuEntry>` closure which is [`Send`] and [`Sync`] is a menu provider, returning the entries of a source.
+///
+/// # Examples
+///
+/// ```
+/// use convo::menu::{MenuEntry, MenuProvider};
+/// let shop = |source: &str| match source {
+///     "shop_stock" => vec![MenuEntry::new("potion", "Potion (5 gold)")],
+///     _ => Vec::new(),
+/// };
+/// assert_eq!(1, shop.entries("shop_stock").len());
+/// assert!(shop.entries("bounties").is_empty());
+/// ```
+pub trait MenuProvider: Send + Sync {
+    /// Returns the entries of a menu, in the order they are presented.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - A string slice that holds the source named by the menu node, e.g. `shop_stock`.
+    fn entries(&self, source: &str) -> Vec<MenuEntry>;
+}
+
+impl<F> MenuProvider for F
+where
+    F: Fn(&str) -> Vec<MenuEntry> + Send + Sync,
+{
+    fn entries(&self, source: &str) -> Vec<MenuEntry> {
+        self(source)
+    }
+}
+
+/// Returns the name of the variable which is assigned the id of the entry chosen from a menu.
+///
+/// # Arguments
+///
+/// * `source` - A string slice that holds the source named by the menu node.
+///
+/// # Examples
+///
+/// ```
+/// use convo::menu;
+/// assert_eq!("menu.shop_stock", menu::variable("shop_stock"));
+/// ```
+pub fn variable(source: &str) -> String {
+    format!("{}{}", MENU_PREFIX, source)
+}
+
+/// Returns the source whose chosen entry a variable holds, or [`None`] if it is not a menu variable.
+///
+/// # Arguments
+///
+/// * `name` - A string slice that holds the name of a variable.
+///
+/// # Examples
+///
+/// ```
+/// use convo::menu;
+/// assert_eq!(Some("shop_stock"), menu::source("menu.shop_stock"));
+/// assert_eq!(None, menu::source("gold"));
+/// ```
+pub fn source(name: &str) -> Option<&str> {
+    name.strip_prefix(MENU_PREFIX)
+        .filter(|source| !source.is_empty())
+}
+
+/// A cloneable handle to a [`MenuProvider`] held by a [`Walker`][`crate::Walker`].
+#[derive(Clone)]
+pub(crate) struct MenuHandle(Arc<dyn MenuProvider>);
+
+impl MenuHandle {
+    pub(crate) fn new<M>(menu: M) -> Self
+    where
+        M: MenuProvider + 'static,
+    {
+        MenuHandle(Arc::new(menu))
+    }
+
+    pub(crate) fn provider(&self) -> &dyn MenuProvider {
+        &*self.0
+    }
+}
+
+impl fmt::Debug for MenuHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("MenuHandle(..)")
+    }
+}
@@ -0,0 +1,51 @@
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+
+/// A [`Meta`] describes a conversation [`crate::Tree`] as a whole, so files are self-describing. It is read from and written to the optional top-level `meta` section of a dialogue file.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{Meta, Tree};
+/// let mut tree = Tree::new();
+/// tree.meta_mut().title = Some("The Stranger".into());
+/// tree.meta_mut().tags.push("intro".into());
+/// assert_eq!(Some("The Stranger"), tree.meta().title.as_deref());
+/// ```
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Meta {
+    /// The title of the conversation.
+    pub title: Option<String>,
+
+    /// The author of the conversation.
+    pub author: Option<String>,
+
+    /// A description of the conversation.
+    pub description: Option<String>,
+
+    /// Tags which categorize the conversation, in authored order.
+    pub tags: Vec<String>,
+
+    /// Any other fields, mapping field names to string values.
+    pub custom: BTreeMap<String, String>,
+}
+
+impl Meta {
+    /// Returns whether no metadata is set. Empty metadata is not exported.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::Meta;
+    /// let mut meta = Meta::default();
+    /// assert!(meta.is_empty());
+    /// meta.custom.insert("chapter".into(), "1".into());
+    /// assert!(!meta.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none()
+            && self.author.is_none()
+            && self.description.is_none()
+            && self.tags.is_empty()
+            && self.custom.is_empty()
+    }
+}
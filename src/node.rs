@@ -1,16 +1,50 @@
-use crate::link::Link;
+use alloc::{format, string::String, vec, vec::Vec};
+
+use crate::{
+    availability::Availability,
+    link::{Link, LinkOrder},
+    pacing::Pacing,
+    stage::StageDirection,
+};
 
 /// A [`Node`] is a node in a conversation tree. It canonically acts as a fork of decisions by wrapping prompting [`dialogue`][`Node#structfield.dialogue`] and a list of path options (called [`Link`]s).
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct Node {
-    /// The key of this node. Must be unique.
-    pub key: String,
+    /// The key of this node. Must be unique. A [`Tree`][`crate::Tree`] indexes its nodes by key, so the key is only changed by [`with_key`][`Node#method.with_key`], on nodes outside a tree.
+    pub(crate) key: String,
+
+    /// The stable id of this node, if it has one, which external systems such as save games, analytics, or localization can reference even when the key is renamed. Imported trees assign an id to every node without one, see [`Tree::assign_ids`][`crate::Tree::assign_ids`].
+    pub id: Option<u64>,
 
-    /// The dialogue of this node.
+    /// The dialogue of this node. If the node has [`lines`][`Node#structfield.lines`], it holds each line as `speaker: text`, separated by newlines.
     pub dialogue: String,
 
+    /// The [`Line`]s of a back-and-forth played in order before the choices of this node, if its dialogue is a sequence. See [`set_lines`][`Node#method.set_lines`].
+    pub lines: Vec<Line>,
+
     /// A container of [`Link`]s, which connect to other [`Node`]s.
     pub links: Vec<Link>,
+
+    /// How this node leaves to its links, i.e. by presenting them as choices, or automatically.
+    pub kind: NodeKind,
+
+    /// Tags which categorize this node, in authored order, e.g. to mark the kind of ending it is.
+    pub tags: Vec<String>,
+
+    /// When this node may be offered as a choice, given the [`crate::HostState`] of the game.
+    pub availability: Availability,
+
+    /// How the dialogue of this node is revealed, e.g. its speed and pauses, for typewriter-style presentation.
+    pub pacing: Pacing,
+
+    /// Directions for the engine when this node is entered, e.g. camera moves and animation cues, in authored order.
+    pub stage: Vec<StageDirection>,
+
+    /// Whether this node is an intentional ending of the conversation, so it is not linted as a dead end when it has no links.
+    pub end: bool,
+
+    /// Whether this node is unfinished writing, e.g. marked `todo: true` by a writer, so it is tracked as a placeholder. See [`crate::draft`].
+    pub todo: bool,
 }
 
 impl Node {
@@ -33,8 +67,237 @@ impl Node {
     {
         Node {
             key: key.into(),
+            id: None,
             dialogue: dialogue.into(),
+            lines: vec![],
             links: vec![],
+            kind: NodeKind::Choice,
+            tags: vec![],
+            availability: Availability::default(),
+            pacing: Pacing::default(),
+            stage: vec![],
+            end: false,
+            todo: false,
+        }
+    }
+
+    /// Returns the key of this node, which indexes it in a [`Tree`][`crate::Tree`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::Node;
+    /// let node = Node::new("start", "How are you?");
+    /// assert_eq!("start", node.key());
+    /// ```
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Returns this node with another key, e.g. to rename a node removed from a [`Tree`][`crate::Tree`] before inserting it again. Nodes borrowed from a tree cannot be re-keyed, so every node stays indexed by its own key.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - A string type that holds the new key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Node, Tree};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("shop", "Welcome!"));
+    /// let store = tree.remove_node("shop").unwrap().with_key("store");
+    /// tree.insert_node(store);
+    /// assert_eq!("store", tree.node("store").unwrap().key());
+    /// ```
+    pub fn with_key<T>(self, key: T) -> Node
+    where
+        T: Into<String>,
+    {
+        Node {
+            key: key.into(),
+            ..self
         }
     }
+
+    /// Set the [`Line`]s of this node, played in order before its choices, and set its dialogue to match. Setting no lines leaves the dialogue empty.
+    ///
+    /// # Arguments
+    ///
+    /// * `lines` - The [`Line`]s of the node, in the order they are spoken.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Line, Node};
+    /// let mut node = Node::new("start", "");
+    /// node.set_lines(vec![
+    ///     Line::new("guard", "Halt!"),
+    ///     Line::new("captain", "Let them through."),
+    /// ]);
+    /// assert_eq!("guard: Halt!\ncaptain: Let them through.", node.dialogue);
+    /// ```
+    pub fn set_lines(&mut self, lines: Vec<Line>) {
+        self.lines = lines;
+        self.sync_dialogue();
+    }
+
+    // Sets the dialogue to the lines, if there are any
+    pub(crate) fn sync_dialogue(&mut self) {
+        let lines: Vec<String> = self
+            .lines
+            .iter()
+            .map(|line| format!("{}: {}", line.speaker, line.text))
+            .collect();
+        self.dialogue = lines.join("\n");
+    }
+
+    /// Returns the [`Link`]s of this node in the given [`LinkOrder`]. Sorting is stable, so links of equal priority keep the order they were authored in.
+    ///
+    /// # Arguments
+    ///
+    /// * `order` - A [`LinkOrder`] which determines the order of the returned links.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Link, LinkOrder, Node};
+    /// let mut node = Node::new("start", "How are you?");
+    /// node.links.push(Link::new("end", "Goodbye."));
+    /// let mut important = Link::new("quest", "About that quest...");
+    /// important.priority = Some(10);
+    /// node.links.push(important);
+    /// let links = node.ordered_links(LinkOrder::Priority);
+    /// assert_eq!("quest", links[0].to_key);
+    /// assert_eq!("end", links[1].to_key);
+    /// ```
+    pub fn ordered_links(&self, order: LinkOrder) -> Vec<&Link> {
+        let mut links: Vec<&Link> = self.links.iter().collect();
+        if order == LinkOrder::Priority {
+            links.sort_by_key(|link| core::cmp::Reverse(link.priority.unwrap_or(0)));
+        }
+        links
+    }
+}
+
+/// A [`NodeKind`] determines how a [`Node`] leaves to one of its [`Link`]s when walked.
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+pub enum NodeKind {
+    /// The links are presented as choices.
+    #[default]
+    Choice,
+    /// One of the available links is followed automatically, at random, weighted by [`Link#weight`][`Link#structfield.weight`]. Links picked among the `avoid_recent` most recent picks of the node are avoided, while another link is available.
+    Random {
+        /// The number of recent picks to avoid.
+        avoid_recent: usize,
+    },
+    /// The first available link, in authored order, is followed automatically. Each link is a case whose [`Condition`][`crate::Condition`] compares the variable to a value, e.g. `reputation == hero`, and a link without a condition is the default.
+    Switch {
+        /// The name of the variable switched on.
+        variable: String,
+    },
+    /// The links are presented as choices, where the first link is a template presented once per entry the host supplies for the source, e.g. a shop's stock. See [`crate::menu`].
+    Menu {
+        /// The name of the source of entries, passed to the [`MenuProvider`][`crate::menu::MenuProvider`].
+        source: String,
+    },
+}
+
+impl NodeKind {
+    /// Returns whether nodes of this kind are left automatically, rather than by choosing a link.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::NodeKind;
+    /// assert!(!NodeKind::Choice.is_automatic());
+    /// assert!(!NodeKind::Menu { source: "shop_stock".into() }.is_automatic());
+    /// assert!(NodeKind::Random { avoid_recent: 0 }.is_automatic());
+    /// assert!(NodeKind::Switch { variable: "reputation".into() }.is_automatic());
+    /// ```
+    pub fn is_automatic(&self) -> bool {
+        !matches!(self, NodeKind::Choice | NodeKind::Menu { .. })
+    }
+}
+
+/// A [`Line`] is a single line of a [`Node`] whose dialogue is a sequence, spoken by one of several speakers.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Line {
+    /// The name of the speaker.
+    pub speaker: String,
+
+    /// The text spoken.
+    pub text: String,
+
+    /// The emotion the line is spoken with, e.g. `angry`, if it is annotated.
+    pub emotion: Option<String>,
+}
+
+impl Line {
+    /// Returns a [`Line`] spoken by a speaker.
+    ///
+    /// # Arguments
+    ///
+    /// * `speaker` - A string type that holds the name of the speaker.
+    /// * `text` - A string type that holds the text spoken.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::Line;
+    /// let line = Line::new("guard", "Halt!");
+    /// assert_eq!("guard", line.speaker);
+    /// ```
+    pub fn new<T>(speaker: T, text: T) -> Line
+    where
+        T: Into<String>,
+    {
+        Line {
+            speaker: speaker.into(),
+            text: text.into(),
+            emotion: None,
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_ordered_links() {
+    let mut node = Node::new("start", "The start node.");
+    let mut low = Link::new("low", "Low priority.");
+    low.priority = Some(-1);
+    let unset = Link::new("unset", "No priority.");
+    let mut high_1 = Link::new("high_1", "High priority.");
+    high_1.priority = Some(5);
+    let mut high_2 = Link::new("high_2", "High priority, authored later.");
+    high_2.priority = Some(5);
+    node.links.extend(vec![low, unset, high_1, high_2]);
+
+    // Authored order is untouched
+    let keys: Vec<&str> = node
+        .ordered_links(LinkOrder::Authored)
+        .iter()
+        .map(|link| link.to_key.as_str())
+        .collect();
+    assert_eq!(vec!["low", "unset", "high_1", "high_2"], keys);
+
+    // Priority order is descending and stable
+    let keys: Vec<&str> = node
+        .ordered_links(LinkOrder::Priority)
+        .iter()
+        .map(|link| link.to_key.as_str())
+        .collect();
+    assert_eq!(vec!["high_1", "high_2", "unset", "low"], keys);
+}
+
+#[test]
+fn test_set_lines() {
+    let mut node = Node::new("start", "Hello!");
+    node.set_lines(vec![Line::new("a", "Hi."), Line::new("b", "Hey.")]);
+    assert_eq!("a: Hi.\nb: Hey.", node.dialogue);
+    assert_eq!(2, node.lines.len());
+
+    // Without lines, the dialogue is empty
+    node.set_lines(vec![]);
+    assert_eq!("", node.dialogue);
 }
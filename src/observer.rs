@@ -0,0 +1,211 @@
+//! Observers which are notified of conversation events, and transcripts which record what happened in a conversation, e.g. for analytics.
+//!
+//! An [`Observer`] is attached to a [`Walker`][`crate::Walker`] with [`Walker::set_observer`][`crate::Walker::set_observer`], and the transcript of a walker is read with [`Walker::transcript`][`crate::Walker::transcript`]. When enabled with [`Walker::set_condition_trace`][`crate::Walker::set_condition_trace`], the conditions evaluated at each step are read with [`Walker::condition_trace`][`crate::Walker::condition_trace`].
+
+use alloc::{borrow::Cow, collections::BTreeMap, string::String, sync::Arc, vec, vec::Vec};
+use core::fmt;
+
+use crate::{
+    condition::{Condition, Host},
+    link::Link,
+    node::Node,
+    stage::StageDirection,
+    value::Value,
+};
+
+/// An [`Observer`] is notified of events as a conversation is walked. Every method does nothing by default, so observers only implement the events they need.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{observer::{Observer, TranscriptEntry}, Node, Tree, Walker};
+/// use std::sync::{Arc, Mutex};
+///
+/// struct Interruptions(Arc<Mutex<Vec<String>>>);
+/// impl Observer for Interruptions {
+///     fn on_conversation_aborted(&self, reason: &str, _transcript: &[TranscriptEntry]) {
+///         self.0.lock().unwrap().push(reason.to_owned());
+///     }
+/// }
+///
+/// let mut tree = Tree::new();
+/// tree.insert_node(Node::new("start", "Hello!"));
+/// tree.set_root_key("start").unwrap();
+/// let interruptions = Arc::new(Mutex::new(vec![]));
+/// let mut walker = Walker::new(&tree).unwrap();
+/// walker.set_observer(Interruptions(interruptions.clone()));
+/// walker.abort("combat started");
+/// assert_eq!(vec!["combat started"], *interruptions.lock().unwrap());
+/// ```
+pub trait Observer: Send + Sync {
+    /// Called when the walker enters a [`Node`], by following a link or by returning to it.
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The [`Node`] entered.
+    fn on_node_entered(&self, node: &Node) {
+        let _ = node;
+    }
+
+    /// Called for each [`StageDirection`] of a [`Node`] the walker enters, in order, after [`on_node_entered`][`Observer::on_node_entered`], e.g. to move the camera.
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The [`Node`] entered.
+    /// * `direction` - The [`StageDirection`] of the node.
+    fn on_stage_direction(&self, node: &Node, direction: &StageDirection) {
+        let _ = (node, direction);
+    }
+
+    /// Called when the conversation is aborted, after the transcript is finalized.
+    ///
+    /// # Arguments
+    ///
+    /// * `reason` - Why the conversation was aborted.
+    /// * `transcript` - The transcript of the conversation, whose last entry records the abort.
+    fn on_conversation_aborted(&self, reason: &str, transcript: &[TranscriptEntry]) {
+        let _ = (reason, transcript);
+    }
+}
+
+/// A [`TranscriptEntry`] records a visit to a single [`Node`] of a walked conversation.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TranscriptEntry {
+    /// The key of the visited [`Node`].
+    pub node_key: String,
+
+    /// The dialogue of the [`Link`] followed from the node, if one was followed and it was not silent.
+    pub choice: Option<String>,
+
+    /// The reason the conversation was aborted at the node, if it was. This finalizes the transcript, unless the conversation is resumed.
+    pub aborted: Option<String>,
+}
+
+impl TranscriptEntry {
+    fn new(node_key: &str) -> Self {
+        TranscriptEntry {
+            node_key: node_key.into(),
+            choice: None,
+            aborted: None,
+        }
+    }
+}
+
+/// An [`Evaluation`] records a [`Condition`] of a [`Link`] evaluated when the walker decided which links of a [`Node`] could be followed, e.g. to find out why a choice was hidden.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Evaluation {
+    /// The index of the [`TranscriptEntry`] of the visit to the node during which the condition was evaluated.
+    pub step: usize,
+
+    /// The key of the [`Node`] whose link was evaluated.
+    pub node_key: String,
+
+    /// The index of the link in [`Node#links`][`Node#structfield.links`].
+    pub link_index: usize,
+
+    /// The evaluated condition.
+    pub condition: Condition,
+
+    /// The values of the variables the condition tests, mapped by name, or [`None`] for variables which were not set. Clock variables, affinities, and items which were not set hold the value read in their place, e.g. from the walker's [`HostState`][`crate::HostState`].
+    pub inputs: BTreeMap<String, Option<Value>>,
+
+    /// Whether the condition held.
+    pub holds: bool,
+}
+
+/// A cloneable handle to an [`Observer`] held by a [`Walker`][`crate::Walker`].
+#[derive(Clone)]
+pub(crate) struct ObserverHandle(Arc<dyn Observer>);
+
+impl ObserverHandle {
+    pub(crate) fn new<O>(observer: O) -> Self
+    where
+        O: Observer + 'static,
+    {
+        ObserverHandle(Arc::new(observer))
+    }
+}
+
+impl fmt::Debug for ObserverHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ObserverHandle(..)")
+    }
+}
+
+/// Records the transcript of a walker and notifies its observer.
+#[derive(Debug, Clone)]
+pub(crate) struct Journal {
+    pub(crate) transcript: Vec<TranscriptEntry>,
+    pub(crate) observer: Option<ObserverHandle>,
+    pub(crate) evaluations: Option<Vec<Evaluation>>,
+}
+
+impl Journal {
+    pub(crate) fn new(root_key: &str) -> Self {
+        Journal {
+            transcript: vec![TranscriptEntry::new(root_key)],
+            observer: None,
+            evaluations: None,
+        }
+    }
+
+    // Records the evaluation of every condition of a node's links, if conditions are traced
+    pub(crate) fn trace(
+        &mut self,
+        node: &Node,
+        variables: &BTreeMap<String, Value>,
+        host: Host<'_>,
+    ) {
+        let evaluations = match &mut self.evaluations {
+            Some(evaluations) => evaluations,
+            None => return,
+        };
+        let step = self.transcript.len() - 1;
+        for (link_index, link) in node.links.iter().enumerate() {
+            if let Some(condition) = &link.condition {
+                let inputs = condition
+                    .clauses
+                    .iter()
+                    .map(|clause| {
+                        let value = host.input(clause, variables).map(Cow::into_owned);
+                        (clause.name().into(), value)
+                    })
+                    .collect();
+                evaluations.push(Evaluation {
+                    step,
+                    node_key: node.key.clone(),
+                    link_index,
+                    condition: condition.clone(),
+                    inputs,
+                    holds: condition.holds_with(variables, host.state, host.inventory),
+                });
+            }
+        }
+    }
+
+    pub(crate) fn follow(&mut self, link: &Link, node: &Node) {
+        if let Some(entry) = self.transcript.last_mut() {
+            entry.choice = link.dialogue.clone();
+        }
+        self.enter(node);
+    }
+
+    pub(crate) fn enter(&mut self, node: &Node) {
+        self.transcript.push(TranscriptEntry::new(&node.key));
+        if let Some(observer) = &self.observer {
+            observer.0.on_node_entered(node);
+            for direction in &node.stage {
+                observer.0.on_stage_direction(node, direction);
+            }
+        }
+    }
+
+    pub(crate) fn abort(&mut self, reason: &str) {
+        if let Some(entry) = self.transcript.last_mut() {
+            entry.aborted = Some(reason.into());
+        }
+        if let Some(observer) = &self.observer {
+            observer.0.on_conversation_aborted(reason, &self.transcript);
+        }
+    }
+}
@@ -0,0 +1,211 @@
+//! Pacing hints for typewriter-style presentation of dialogue, so the speed and pauses of revealed text are authored rather than hardcoded per game.
+//!
+//! A [`Node`][`crate::Node`] may carry a [`Pacing`], read from its optional `pacing` section, and its dialogue may contain inline commands such as `{pause 500}`, which pause the reveal for 500 milliseconds. [`spans`] splits dialogue into text and pauses, and [`strip`] removes the commands for hosts which present text at once.
+
+use alloc::{borrow::Cow, string::String, vec::Vec};
+use core::time::Duration;
+
+/// The name of the inline command which pauses the reveal, e.g. `{pause 500}`.
+pub const PAUSE_COMMAND: &str = "pause";
+
+/// A [`Pacing`] hints how the dialogue of a node is revealed. Hints which are [`None`] are left to the host.
+///
+/// # Examples
+///
+/// ```
+/// use convo::pacing::Pacing;
+/// use std::time::Duration;
+/// let pacing = Pacing {
+///     chars_per_second: Some(20),
+///     pause_after: Some(500),
+///     ..Pacing::default()
+/// };
+/// assert_eq!(Some(Duration::from_millis(1150)), pacing.duration("Hello.{pause 250}.."));
+/// ```
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash)]
+pub struct Pacing {
+    /// The number of characters revealed per second.
+    pub chars_per_second: Option<u32>,
+
+    /// The number of milliseconds to wait before the dialogue is revealed.
+    pub pause_before: Option<u32>,
+
+    /// The number of milliseconds to wait after the dialogue is revealed, e.g. before the conversation advances on its own.
+    pub pause_after: Option<u32>,
+}
+
+impl Pacing {
+    /// Returns whether no hints are set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::pacing::Pacing;
+    /// assert!(Pacing::default().is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.chars_per_second.is_none() && self.pause_before.is_none() && self.pause_after.is_none()
+    }
+
+    /// Returns how long revealing text takes with these hints, including its pauses, or [`None`] if no speed is set.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - A string slice that holds the text, which may contain pause commands.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::pacing::Pacing;
+    /// use std::time::Duration;
+    /// let pacing = Pacing { chars_per_second: Some(10), ..Pacing::default() };
+    /// assert_eq!(Some(Duration::from_millis(800)), pacing.duration("Hi{pause 500}!"));
+    /// assert_eq!(None, Pacing::default().duration("Hi!"));
+    /// ```
+    pub fn duration(&self, text: &str) -> Option<Duration> {
+        let chars_per_second = u64::from(self.chars_per_second.filter(|cps| *cps > 0)?);
+        let mut millis =
+            u64::from(self.pause_before.unwrap_or(0)) + u64::from(self.pause_after.unwrap_or(0));
+        let mut chars = 0;
+        for span in spans(text) {
+            match span {
+                Span::Text(text) => chars += text.chars().count() as u64,
+                Span::Pause(pause) => millis += u64::from(pause),
+            }
+        }
+        Some(Duration::from_millis(
+            millis + chars * 1000 / chars_per_second,
+        ))
+    }
+}
+
+/// A [`Span`] is a part of dialogue, as split by [`spans`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Span<'t> {
+    /// Text to reveal.
+    Text(&'t str),
+    /// A pause in the reveal, in milliseconds, from a command such as `{pause 500}`.
+    Pause(u32),
+}
+
+/// Returns the [`Span`]s of dialogue, in order, where each pause command is a [`Span::Pause`] and the text around them is a [`Span::Text`]. Braces which do not hold a pause command, e.g. `{name}` or `{pause}`, are kept as text.
+///
+/// # Arguments
+///
+/// * `text` - A string slice that holds the dialogue.
+///
+/// # Examples
+///
+/// ```
+/// use convo::pacing::{self, Span};
+/// assert_eq!(
+///     vec![Span::Text("Well"), Span::Pause(500), Span::Text("... no.")],
+///     pacing::spans("Well{pause 500}... no.")
+/// );
+/// ```
+pub fn spans(text: &str) -> Vec<Span<'_>> {
+    let mut spans = Vec::new();
+    let mut rest = text;
+    let mut start = 0;
+    while let Some(open) = rest[start..].find('{').map(|i| start + i) {
+        let (pause, len) = match command(&rest[open..]) {
+            Some(command) => command,
+            None => {
+                start = open + 1;
+                continue;
+            }
+        };
+        if open > 0 {
+            spans.push(Span::Text(&rest[..open]));
+        }
+        spans.push(Span::Pause(pause));
+        rest = &rest[open + len..];
+        start = 0;
+    }
+    if !rest.is_empty() {
+        spans.push(Span::Text(rest));
+    }
+    spans
+}
+
+/// Returns dialogue without its pause commands, e.g. for hosts which present text at once.
+///
+/// # Arguments
+///
+/// * `text` - A string slice that holds the dialogue.
+///
+/// # Examples
+///
+/// ```
+/// use convo::pacing;
+/// assert_eq!("Well... no.", pacing::strip("Well{pause 500}... no."));
+/// assert_eq!("Hi, {name}.", pacing::strip("Hi, {name}."));
+/// ```
+pub fn strip(text: &str) -> Cow<'_, str> {
+    match spans(text).as_slice() {
+        [] => Cow::Borrowed(""),
+        [Span::Text(text)] => Cow::Borrowed(text),
+        spans => Cow::Owned(
+            spans
+                .iter()
+                .filter_map(|span| match span {
+                    Span::Text(text) => Some(*text),
+                    Span::Pause(_) => None,
+                })
+                .collect::<String>(),
+        ),
+    }
+}
+
+// Returns the milliseconds of the pause command at the start of text, and its length, if there is one
+fn command(text: &str) -> Option<(u32, usize)> {
+    let close = text.find('}')?;
+    let mut words = text[1..close].split_whitespace();
+    let (name, millis) = (words.next()?, words.next()?);
+    if name != PAUSE_COMMAND
+        || words.next().is_some()
+        || !millis.bytes().all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+    Some((millis.parse().ok()?, close + 1))
+}
+
+#[cfg(test)]
+#[test]
+fn test_spans() {
+    assert!(spans("").is_empty());
+    assert_eq!(
+        vec![Span::Pause(1), Span::Pause(20), Span::Text("a")],
+        spans("{pause 1}{ pause  20 }a")
+    );
+    assert_eq!(
+        vec![
+            Span::Text("{x} {pause} {pause -1} {pause 1 2} {"),
+            Span::Pause(3)
+        ],
+        spans("{x} {pause} {pause -1} {pause 1 2} {{pause 3}")
+    );
+    assert_eq!(
+        vec![Span::Text("{pause 99999999999}")],
+        spans("{pause 99999999999}")
+    );
+    assert_eq!("", strip("{pause 5}"));
+    assert_eq!("héllo", strip("hé{pause 5}llo"));
+
+    // Characters are counted rather than bytes
+    let pacing = Pacing {
+        chars_per_second: Some(2),
+        pause_before: Some(100),
+        ..Pacing::default()
+    };
+    assert_eq!(Some(Duration::from_millis(1100)), pacing.duration("hé"));
+    assert_eq!(
+        None,
+        Pacing {
+            chars_per_second: Some(0),
+            ..pacing
+        }
+        .duration("hé")
+    );
+}
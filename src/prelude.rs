@@ -0,0 +1,47 @@
+//! A convenience module which re-exports the most commonly used types and functions of [`convo`][`crate`].
+//!
+//! # Examples
+//!
+//! ```
+//! use convo::prelude::*;
+//! let mut tree = Tree::new();
+//! let mut start = Node::new("start", "Hello!");
+//! let end = Node::new("end", "Goodbye!");
+//! Link::link(&mut start, &end, "Bye!");
+//! tree.insert_node(start);
+//! tree.insert_node(end);
+//! tree.set_root_key("start").unwrap();
+//! let walker = Walker::new(&tree).unwrap();
+//! assert_eq!("start", walker.current_key());
+//! ```
+//!
+//! With the `std` feature, importer and exporter entry points are included:
+//!
+//! ```
+//! # #[cfg(feature = "std")]
+//! # {
+//! use convo::prelude::*;
+//! let tree = import("examples/dialogue_files/ex_1.convo.yml").unwrap();
+//! let source = tree_to_source(&tree).unwrap();
+//! assert_eq!(tree, source_to_tree(&source).unwrap());
+//! # }
+//! ```
+
+pub use crate::error::TreeError;
+#[cfg(feature = "std")]
+pub use crate::error::{ExportError, ImportError};
+#[cfg(feature = "std")]
+pub use crate::exporter::{
+    export, export_with, tree_to_source, tree_to_source_with, ExportOptions, Quoting,
+};
+#[cfg(feature = "std")]
+pub use crate::importer::{
+    import, import_meta, import_streaming, import_with, reader_to_tree, source_to_tree,
+    source_to_tree_with, ImportOptions,
+};
+#[cfg(feature = "async")]
+pub use crate::{ChoiceFuture, ChoiceProvider};
+pub use crate::{
+    Condition, Link, LinkOrder, Meta, Node, RawTree, SharedTree, Tree, TreeIndex, Type, Value,
+    Walker,
+};
@@ -0,0 +1,1224 @@
+//! Multi-file projects, described by a `convo.project.yml` manifest.
+//!
+//! A [`Manifest`] lists the dialogue files of a project along with shared variables, localization settings, and per-file overrides. A [`Project`] loads every file of a manifest, giving multi-file projects a single entry point.
+//! See also: [project manifests](https://github.com/simbleau/convo/blob/dev/FORMATTING.md#project-manifests).
+
+use crate::{
+    availability::HostState,
+    characters::Characters,
+    diff::TreeDiff,
+    error::{ImportError, ProjectError, TreeError},
+    importer::yaml_to_value,
+    link::LinkOrder,
+    lint::{self, Diagnostic, LintConfig, Rule},
+    node::Node,
+    suggest,
+    tree::Tree,
+    value::{Type, Value},
+};
+
+use indexmap::IndexMap;
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+use yaml_rust::{Yaml, YamlLoader};
+
+/// The conventional file name of a project manifest.
+pub const MANIFEST_FILE_NAME: &str = "convo.project.yml";
+
+/// A [`Manifest`] describes a multi-file project. Paths are relative to the directory of the manifest.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Manifest {
+    /// The name of the project.
+    pub name: Option<String>,
+
+    /// The dialogue files of the project, in the order they are loaded.
+    pub files: Vec<PathBuf>,
+
+    /// Variables shared by every file, mapping names to initial values.
+    pub variables: BTreeMap<String, Value>,
+
+    /// The file registering the characters who speak in the project's dialogue, if there is one.
+    /// See also: [characters](https://github.com/simbleau/convo/blob/dev/FORMATTING.md#characters).
+    pub characters: Option<PathBuf>,
+
+    /// The localization settings of the project.
+    pub localization: Localization,
+
+    /// Overrides for individual dialogue files, keyed by their path as listed in [`files`][`Manifest#structfield.files`].
+    pub overrides: BTreeMap<PathBuf, FileOverride>,
+}
+
+/// A [`Localization`] holds the localization settings of a [`Manifest`].
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Localization {
+    /// The locale the dialogue files are authored in, e.g. `en`.
+    pub default_locale: Option<String>,
+
+    /// The locales the project is translated to.
+    pub locales: Vec<String>,
+
+    /// The directory holding translations.
+    pub directory: Option<PathBuf>,
+}
+
+/// A [`FileOverride`] replaces settings of a single dialogue file when it is loaded into a [`Project`].
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct FileOverride {
+    /// The key of the root node to use instead of the file's `root`.
+    pub root: Option<String>,
+
+    /// The [`LinkOrder`] to walk the file's tree with.
+    pub link_order: Option<LinkOrder>,
+}
+
+impl Manifest {
+    /// Try to return a [`Manifest`] which is generated from importing a file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - A path type that references a manifest file to parse from.
+    ///
+    /// # Errors
+    ///
+    /// * An [`ImportError`] will be returned if the source is not valid YAML data or if the manifest is not considered legal when parsing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::project::Manifest;
+    /// let manifest = Manifest::import("examples/convo.project.yml").unwrap();
+    /// assert_eq!(2, manifest.files.len());
+    /// ```
+    pub fn import<P>(path: P) -> Result<Self, ImportError>
+    where
+        P: AsRef<Path>,
+    {
+        let source = fs::read_to_string(path)?;
+        Manifest::try_from(&source)
+    }
+
+    /// Try to return a [`Manifest`] which is generated from parsing a string slice.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - A string slice that holds valid YAML data to parse from.
+    ///
+    /// # Errors
+    ///
+    /// * An [`ImportError`] will be returned if the source is not valid YAML data or if the manifest is not considered legal when parsing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{project::Manifest, LinkOrder, Value};
+    /// let source = r#"
+    /// ---
+    /// files:
+    ///   - intro.convo.yml
+    /// variables:
+    ///   gold: 10
+    /// overrides:
+    ///   intro.convo.yml:
+    ///     link_order: priority
+    /// "#;
+    /// let manifest = Manifest::try_from(source).unwrap();
+    /// assert_eq!(Some(&Value::Int(10)), manifest.variables.get("gold"));
+    /// let overrides = &manifest.overrides[&manifest.files[0]];
+    /// assert_eq!(Some(LinkOrder::Priority), overrides.link_order);
+    /// ```
+    pub fn try_from(source: &str) -> Result<Self, ImportError> {
+        let docs = YamlLoader::load_from_str(source)?;
+        if docs.len() != 1 {
+            return Err(ImportError::MultipleDocumentsProvided());
+        }
+        Ok(yaml_to_manifest(&docs[0])?)
+    }
+}
+
+/// A [`ProjectDiagnostic`] is a [`Diagnostic`] found in a dialogue file of a [`Project`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ProjectDiagnostic {
+    /// The path of the dialogue file the diagnostic concerns.
+    pub path: PathBuf,
+
+    /// The name of the tree the diagnostic concerns.
+    pub tree: String,
+
+    /// The diagnostic, naming the node it concerns.
+    pub diagnostic: Diagnostic,
+}
+
+/// Returns [`ProjectDiagnostic`]s as a JSON array with one object per diagnostic, in the layout of [`lint::to_json`]. Projects hold trees rather than sources, so each `span` is `null`.
+///
+/// # Arguments
+///
+/// * `diagnostics` - The [`ProjectDiagnostic`]s to write, e.g. from [`Project::lint`].
+///
+/// # Examples
+///
+/// ```
+/// use convo::project::{self, Project};
+/// let project = Project::load("examples/convo.project.yml").unwrap();
+/// let json = project::to_json(&project.lint());
+/// assert!(json.starts_with(r#"[{"rule":"unused-tree","severity":"warning","#));
+/// ```
+pub fn to_json(diagnostics: &[ProjectDiagnostic]) -> String {
+    let findings: Vec<String> = diagnostics
+        .iter()
+        .map(|d| {
+            lint::json_finding(
+                &d.path,
+                d.diagnostic.rule.id(),
+                d.diagnostic.severity,
+                Some(&d.diagnostic.node_key),
+                None,
+                &d.diagnostic.message,
+            )
+        })
+        .collect();
+    format!("[{}]", findings.join(","))
+}
+
+/// A [`Project`] holds the [`Tree`]s of every dialogue file listed by a [`Manifest`], with per-file overrides applied. Each tree is named after its file name, without the `.convo.yml` extension.
+///
+/// # Examples
+///
+/// ```
+/// use convo::project::Project;
+/// let project = Project::load("examples/convo.project.yml").unwrap();
+/// let tree = project.tree("ex_1").unwrap();
+/// assert_eq!("start", tree.root_key().unwrap());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Project {
+    manifest: Manifest,
+    trees: IndexMap<String, (PathBuf, Tree)>,
+    characters: Characters,
+    lint_config: LintConfig,
+}
+
+impl Project {
+    /// Try to return a [`Project`] by importing a manifest and every dialogue file it lists.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - A path type that references a manifest file, conventionally named [`MANIFEST_FILE_NAME`].
+    ///
+    /// # Errors
+    ///
+    /// * A [`ProjectError`] will be returned if the manifest or any dialogue file cannot be imported, if an override cannot be applied, or if two files share a tree name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::project::Project;
+    /// let project = Project::load("examples/convo.project.yml").unwrap();
+    /// assert_eq!(2, project.len());
+    /// ```
+    pub fn load<P>(path: P) -> Result<Self, ProjectError>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let manifest = Manifest::import(path)?;
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        Project::from_manifest(manifest, dir)
+    }
+
+    /// Try to return a [`Project`] by importing every dialogue file listed by a [`Manifest`].
+    ///
+    /// # Arguments
+    ///
+    /// * `manifest` - The [`Manifest`] of the project.
+    /// * `dir` - A path type that references the directory which the manifest's paths are relative to.
+    ///
+    /// # Errors
+    ///
+    /// * A [`ProjectError`] will be returned if any dialogue file cannot be imported, if an override cannot be applied, or if two files share a tree name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::project::{Manifest, Project};
+    /// let mut manifest = Manifest::default();
+    /// manifest.files.push("ex_min.convo.yml".into());
+    /// let project = Project::from_manifest(manifest, "examples/dialogue_files").unwrap();
+    /// assert!(project.tree("ex_min").is_some());
+    /// ```
+    pub fn from_manifest<P>(manifest: Manifest, dir: P) -> Result<Self, ProjectError>
+    where
+        P: AsRef<Path>,
+    {
+        let mut trees = IndexMap::with_capacity(manifest.files.len());
+        for file in &manifest.files {
+            let path = dir.as_ref().join(file);
+            let tree = import_file(&manifest, file, &path)?;
+            let name = tree_name(file);
+            if trees.contains_key(&name) {
+                return Err(ProjectError::Validation(format!(
+                    "Multiple files are named `{}`",
+                    name
+                )));
+            }
+            trees.insert(name, (path, tree));
+        }
+
+        // Projects may register their characters
+        let characters = match &manifest.characters {
+            Some(file) => {
+                Characters::import(dir.as_ref().join(file)).map_err(ProjectError::Characters)?
+            }
+            None => Characters::new(),
+        };
+
+        // Projects may configure their lint rules
+        let config_path = dir.as_ref().join(lint::CONFIG_FILE_NAME);
+        let lint_config = if config_path.is_file() {
+            LintConfig::import(&config_path).map_err(ProjectError::LintConfig)?
+        } else {
+            LintConfig::default()
+        };
+
+        Ok(Project {
+            manifest,
+            trees,
+            characters,
+            lint_config,
+        })
+    }
+
+    /// Returns the [`Manifest`] this project was loaded from.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::project::Project;
+    /// let project = Project::load("examples/convo.project.yml").unwrap();
+    /// assert_eq!(Some("Examples"), project.manifest().name.as_deref());
+    /// ```
+    pub fn manifest(&self) -> &Manifest {
+        &self.manifest
+    }
+
+    /// Returns the [`Tree`] with a name, if it exists.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - A string slice that holds the name of a tree, i.e. its file name without the `.convo.yml` extension.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::project::Project;
+    /// let project = Project::load("examples/convo.project.yml").unwrap();
+    /// assert!(project.tree("ex_min").is_some());
+    /// assert!(project.tree("ex_min.convo.yml").is_none());
+    /// ```
+    pub fn tree(&self, name: &str) -> Option<&Tree> {
+        self.trees.get(name).map(|(_, tree)| tree)
+    }
+
+    /// Returns the [`Node`] indexed by a qualified key of the form `tree/node`, if it exists.
+    ///
+    /// # Arguments
+    ///
+    /// * `qualified_key` - A string slice that holds the name of a tree and the key of one of its nodes, separated by `/`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::project::Project;
+    /// let project = Project::load("examples/convo.project.yml").unwrap();
+    /// let node = project.node("ex_min/end").unwrap();
+    /// assert_eq!("end", node.key());
+    /// assert!(project.node("end").is_none());
+    /// ```
+    pub fn node(&self, qualified_key: &str) -> Option<&Node> {
+        let (tree_name, node_key) = qualified_key.split_once('/')?;
+        self.tree(tree_name)?.node(node_key)
+    }
+
+    /// Returns the name of the [`Tree`] and the [`Node`] which a link target resolves to from within a tree, if it exists.
+    ///
+    /// Unqualified keys index nodes of the same tree. Qualified keys of the form `tree/node` index nodes of any tree, so trees authored separately can reference each other without coordinating their keys.
+    ///
+    /// # Arguments
+    ///
+    /// * `tree_name` - A string slice that holds the name of the tree the key is used in.
+    /// * `key` - A string slice that holds an unqualified or qualified node key, e.g. a [`crate::Link#to_key`][`crate::Link#structfield.to_key`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::project::Project;
+    /// let project = Project::load("examples/convo.project.yml").unwrap();
+    /// let (tree_name, node) = project.resolve("ex_1", "ex_min/start").unwrap();
+    /// assert_eq!("ex_min", tree_name);
+    /// let (tree_name, node) = project.resolve("ex_1", "start").unwrap();
+    /// assert_eq!("ex_1", tree_name);
+    /// ```
+    pub fn resolve<'p>(&'p self, tree_name: &'p str, key: &str) -> Option<(&'p str, &'p Node)> {
+        match key.split_once('/') {
+            Some((target, node_key)) => {
+                let (name, (_, tree)) = self.trees.get_key_value(target)?;
+                Some((name.as_str(), tree.node(node_key)?))
+            }
+            None => Some((tree_name, self.tree(tree_name)?.node(key)?)),
+        }
+    }
+
+    /// Re-import the dialogue file of a [`Tree`] after it changed, e.g. when an editor saves it, and update the tree in place with [`Tree::update`], returning the [`TreeDiff`] applied. Walkers of the old version can continue on the new one with [`Walker::reload`][`crate::Walker::reload`].
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - A string slice that holds the name of a tree.
+    ///
+    /// # Errors
+    ///
+    /// * A [`ProjectError`] will be returned if the tree does not exist, or if its file cannot be imported or its override cannot be applied. The tree is left unchanged on error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::project::Project;
+    /// let mut project = Project::load("examples/convo.project.yml").unwrap();
+    /// assert!(project.reload("ex_min").unwrap().is_empty());
+    /// assert!(project.reload("missing").is_err());
+    /// ```
+    pub fn reload(&mut self, name: &str) -> Result<TreeDiff, ProjectError> {
+        let file = self
+            .manifest
+            .files
+            .iter()
+            .find(|file| tree_name(file) == name);
+        let (file, (path, tree)) = match (file, self.trees.get_mut(name)) {
+            (Some(file), Some(entry)) => (file, entry),
+            _ => {
+                return Err(ProjectError::Validation(format!(
+                    "Tree `{}` does not exist{}",
+                    name,
+                    suggest::did_you_mean(suggest::closest(
+                        name,
+                        self.trees.keys().map(String::as_str)
+                    ))
+                )))
+            }
+        };
+        let new = import_file(&self.manifest, file, path)?;
+        Ok(tree.update(new))
+    }
+
+    /// Returns the path a [`Tree`] was imported from, if it exists.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - A string slice that holds the name of a tree.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::project::Project;
+    /// use std::path::Path;
+    /// let project = Project::load("examples/convo.project.yml").unwrap();
+    /// let path = Path::new("examples/dialogue_files/ex_1.convo.yml");
+    /// assert_eq!(Some(path), project.path("ex_1"));
+    /// ```
+    pub fn path(&self, name: &str) -> Option<&Path> {
+        self.trees.get(name).map(|(path, _)| path.as_path())
+    }
+
+    /// Returns an iterator over the names and [`Tree`]s of the project, in the order their files are listed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::project::Project;
+    /// let project = Project::load("examples/convo.project.yml").unwrap();
+    /// let names: Vec<&str> = project.trees().map(|(name, _)| name).collect();
+    /// assert_eq!(vec!["ex_1", "ex_min"], names);
+    /// ```
+    pub fn trees(&self) -> impl DoubleEndedIterator<Item = (&str, &Tree)> + ExactSizeIterator {
+        self.trees
+            .iter()
+            .map(|(name, (_, tree))| (name.as_str(), tree))
+    }
+
+    /// Returns an [`Iterator`] over the trees whose [`Availability`][`crate::Availability`] allows them to be offered in a [`HostState`], in the order they are loaded. Cooldowns of trees are keyed by tree name.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The [`HostState`] of the game.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{project::Project, HostState};
+    /// let project = Project::load("examples/convo.project.yml").unwrap();
+    /// let state = HostState::default();
+    /// assert_eq!(2, project.available_trees(&state).count());
+    /// ```
+    pub fn available_trees<'p>(
+        &'p self,
+        state: &'p HostState,
+    ) -> impl Iterator<Item = (&'p str, &'p Tree)> + 'p {
+        self.trees()
+            .filter(move |(name, tree)| tree.availability().allows(state, name))
+    }
+
+    /// Returns the [`ProjectDiagnostic`]s found by linting every [`Tree`] of the project, followed by the cross-tree checks.
+    ///
+    /// Variables shared by the [`Manifest`] are declared in every tree, with the type of their initial value.
+    ///
+    /// A link jumps to another tree when its target is a qualified key of the form `tree/node`, see [`resolve`][`Project#method.resolve`]. Jumps must target existing trees and nodes, and every tree besides the entry tree (the first listed file) should be jumped to. Nodes reached by jumps are not reported as unreachable.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{lint::Rule, project::Project};
+    /// let project = Project::load("examples/convo.project.yml").unwrap();
+    /// let diagnostics = project.lint();
+    /// assert_eq!(Rule::UnusedTree, diagnostics[0].diagnostic.rule);
+    /// assert_eq!("ex_min", diagnostics[0].tree);
+    /// ```
+    pub fn lint(&self) -> Vec<ProjectDiagnostic> {
+        let mut diagnostics = vec![];
+
+        // Follow jumps, recording the name of the jumping tree with each target
+        let mut jumps: HashSet<(&str, &str, &str)> = HashSet::new();
+        for (name, (path, tree)) in &self.trees {
+            for node in tree.nodes() {
+                for link in &node.links {
+                    let (tree_name, node_key) = match link.to_key.split_once('/') {
+                        Some(jump) => jump,
+                        None => continue,
+                    };
+                    let message = match self.tree(tree_name) {
+                        Some(target) if target.contains_node(node_key) => {
+                            jumps.insert((name, tree_name, node_key));
+                            continue;
+                        }
+                        Some(target) => format!(
+                            "Link `{}` in node `{}` jumps to node `{}`, which does not exist in tree `{}`{}",
+                            link.text(),
+                            node.key,
+                            node_key,
+                            tree_name,
+                            suggest::did_you_mean(suggest::closest(
+                                node_key,
+                                target.nodes().map(|node| node.key.as_str())
+                            ))
+                        ),
+                        None => format!(
+                            "Link `{}` in node `{}` jumps to tree `{}`, which does not exist{}",
+                            link.text(),
+                            node.key,
+                            tree_name,
+                            suggest::did_you_mean(suggest::closest(
+                                tree_name,
+                                self.trees.keys().map(String::as_str)
+                            ))
+                        ),
+                    };
+                    diagnostics.push(ProjectDiagnostic {
+                        path: path.clone(),
+                        tree: name.clone(),
+                        diagnostic: Diagnostic::new(Rule::BrokenJump, &node.key, message),
+                    });
+                }
+            }
+        }
+
+        // Lint each tree, where nodes reached by jumps are reachable and shared variables are declared
+        let shared: BTreeMap<&str, Type> = self
+            .manifest
+            .variables
+            .iter()
+            .map(|(name, value)| (name.as_str(), Type::of(value)))
+            .collect();
+        let mut tree_diagnostics = vec![];
+        for (name, (path, tree)) in &self.trees {
+            let index = tree.build_index();
+            let targets: Vec<&str> = jumps
+                .iter()
+                .filter(|(_, tree_name, _)| tree_name == name)
+                .map(|(_, _, node_key)| *node_key)
+                .collect();
+            let mut diagnostics = tree.lint();
+            diagnostics.retain(|diagnostic| {
+                !matches!(
+                    diagnostic.rule,
+                    Rule::UndeclaredVariable
+                        | Rule::VariableType
+                        | Rule::ImpossibleCondition
+                        | Rule::ImpossibleNode
+                )
+            });
+            let declared = |name: &str| tree.variables().get(name).or_else(|| shared.get(name));
+            let names: Vec<&str> = tree
+                .variables()
+                .keys()
+                .map(String::as_str)
+                .chain(shared.keys().copied())
+                .collect();
+            lint::lint_variables(tree, declared, &names, &mut diagnostics);
+            lint::lint_conditions(tree, declared, &mut diagnostics);
+            if self.manifest.characters.is_some() {
+                diagnostics.extend(self.characters.lint(tree));
+            }
+            for diagnostic in diagnostics {
+                let jumped_to = matches!(
+                    diagnostic.rule,
+                    Rule::UnreachableNode | Rule::ImpossibleNode
+                ) && targets
+                    .iter()
+                    .any(|target| index.path(target, &diagnostic.node_key).is_some());
+                if !jumped_to {
+                    tree_diagnostics.push(ProjectDiagnostic {
+                        path: path.clone(),
+                        tree: name.clone(),
+                        diagnostic,
+                    });
+                }
+            }
+        }
+
+        // Every tree besides the entry tree should be jumped to
+        for (name, (path, tree)) in self.trees.iter().skip(1) {
+            let used = jumps
+                .iter()
+                .any(|(from, tree_name, _)| from != name && tree_name == name);
+            if !used {
+                let root_key = tree.root_key().map(String::as_str).unwrap_or_default();
+                diagnostics.push(ProjectDiagnostic {
+                    path: path.clone(),
+                    tree: name.clone(),
+                    diagnostic: Diagnostic::new(
+                        Rule::UnusedTree,
+                        root_key,
+                        format!("Tree `{}` is never jumped to", name),
+                    ),
+                });
+            }
+        }
+
+        tree_diagnostics.extend(diagnostics);
+        tree_diagnostics.retain_mut(|d| match self.lint_config.severity(d.diagnostic.rule) {
+            Some(severity) => {
+                d.diagnostic.severity = severity;
+                true
+            }
+            None => false,
+        });
+        tree_diagnostics
+    }
+
+    /// Returns the [`Characters`] registered by the manifest's characters file, which is empty if it has none.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::project::Project;
+    /// let project = Project::load("examples/convo.project.yml").unwrap();
+    /// assert_eq!("The Stranger", project.characters().display_name("stranger"));
+    /// ```
+    pub fn characters(&self) -> &Characters {
+        &self.characters
+    }
+
+    /// Returns the [`LintConfig`] which [`lint`][`Project#method.lint`] applies, read from a [`lint::CONFIG_FILE_NAME`] file beside the manifest if there is one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{lint::Severity, project::Project};
+    /// let project = Project::load("examples/convo.project.yml").unwrap();
+    /// let rules = &project.lint_config().rules;
+    /// assert_eq!(Some(&Some(Severity::Error)), rules.get("unreachable-node"));
+    /// ```
+    pub fn lint_config(&self) -> &LintConfig {
+        &self.lint_config
+    }
+
+    /// Sets the [`LintConfig`] which [`lint`][`Project#method.lint`] applies.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The [`LintConfig`] to apply.
+    pub fn set_lint_config(&mut self, config: LintConfig) {
+        self.lint_config = config;
+    }
+
+    /// Returns the number of [`Tree`]s in the project.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::project::{Manifest, Project};
+    /// let project = Project::from_manifest(Manifest::default(), ".").unwrap();
+    /// assert_eq!(0, project.len());
+    /// ```
+    pub fn len(&self) -> usize {
+        self.trees.len()
+    }
+
+    /// Returns whether the project has no [`Tree`]s.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::project::{Manifest, Project};
+    /// let project = Project::from_manifest(Manifest::default(), ".").unwrap();
+    /// assert!(project.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.trees.is_empty()
+    }
+}
+
+// Imports a dialogue file listed by a manifest, applying its overrides
+fn import_file(manifest: &Manifest, file: &Path, path: &Path) -> Result<Tree, ProjectError> {
+    let mut tree =
+        crate::importer::import(path).map_err(|err| ProjectError::File(path.to_owned(), err))?;
+
+    // Apply overrides
+    if let Some(file_override) = manifest.overrides.get(file) {
+        if let Some(root_key) = &file_override.root {
+            tree.set_root_key(root_key)
+                .map_err(|err| ProjectError::File(path.to_owned(), err.into()))?;
+        }
+        if let Some(order) = file_override.link_order {
+            tree.set_link_order(order);
+        }
+    }
+
+    // Node keys cannot be mistaken for qualified keys
+    if let Some(node) = tree.nodes().find(|node| node.key.contains('/')) {
+        let err = TreeError::Validation(format!(
+            "Node key `{}` contains `/`, which is reserved for qualified keys",
+            node.key
+        ));
+        return Err(ProjectError::File(path.to_owned(), err.into()));
+    }
+
+    Ok(tree)
+}
+
+fn tree_name(file: &Path) -> String {
+    let file_name = file
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    let name = file_name
+        .strip_suffix(".convo.yml")
+        .or_else(|| file_name.strip_suffix(".yml"))
+        .unwrap_or(&file_name);
+    name.to_owned()
+}
+
+fn yaml_to_manifest(yaml: &Yaml) -> Result<Manifest, TreeError> {
+    let mut manifest = Manifest::default();
+
+    // The manifest must be a hash
+    let fields = yaml.as_hash().ok_or_else(|| {
+        TreeError::Validation(format!("YAML manifest is not a hash: '{:?}'", yaml))
+    })?;
+    for (yaml_key, yaml_value) in fields {
+        match yaml_key.as_str() {
+            Some("name") => manifest.name = Some(yaml_to_string(yaml_value, "name")?),
+            Some("files") => {
+                for file in yaml_to_vec(yaml_value, "files")? {
+                    manifest.files.push(yaml_to_string(file, "files")?.into());
+                }
+            }
+            Some("variables") => {
+                for (name, value) in yaml_to_hash(yaml_value, "variables")? {
+                    let name = yaml_to_string(name, "variables")?;
+                    let value = yaml_to_value(value).ok_or_else(|| {
+                        TreeError::Validation(format!(
+                            "YAML variable is not a boolean, integer, or string for `{:?}`",
+                            name
+                        ))
+                    })?;
+                    manifest.variables.insert(name, value);
+                }
+            }
+            Some("characters") => {
+                manifest.characters = Some(yaml_to_string(yaml_value, "characters")?.into())
+            }
+            Some("localization") => {
+                manifest.localization = yaml_to_localization(yaml_value)?;
+            }
+            Some("overrides") => {
+                for (file, value) in yaml_to_hash(yaml_value, "overrides")? {
+                    let file = yaml_to_string(file, "overrides")?;
+                    let file_override = yaml_to_override(value)?;
+                    manifest.overrides.insert(file.into(), file_override);
+                }
+            }
+            _ => {
+                return Err(TreeError::Validation(format!(
+                    "YAML manifest contains an unknown key: `{:?}`",
+                    yaml_key
+                )))
+            }
+        }
+    }
+
+    // Overrides must refer to listed files
+    for file in manifest.overrides.keys() {
+        if !manifest.files.contains(file) {
+            return Err(TreeError::Validation(format!(
+                "YAML override is for an unlisted file: `{}`",
+                file.display()
+            )));
+        }
+    }
+
+    Ok(manifest)
+}
+
+fn yaml_to_localization(yaml: &Yaml) -> Result<Localization, TreeError> {
+    let mut localization = Localization::default();
+    for (yaml_key, yaml_value) in yaml_to_hash(yaml, "localization")? {
+        match yaml_key.as_str() {
+            Some("default") => {
+                localization.default_locale = Some(yaml_to_string(yaml_value, "default")?)
+            }
+            Some("locales") => {
+                for locale in yaml_to_vec(yaml_value, "locales")? {
+                    localization
+                        .locales
+                        .push(yaml_to_string(locale, "locales")?);
+                }
+            }
+            Some("directory") => {
+                localization.directory = Some(yaml_to_string(yaml_value, "directory")?.into())
+            }
+            _ => {
+                return Err(TreeError::Validation(format!(
+                    "YAML localization contains an unknown key: `{:?}`",
+                    yaml_key
+                )))
+            }
+        }
+    }
+    Ok(localization)
+}
+
+fn yaml_to_override(yaml: &Yaml) -> Result<FileOverride, TreeError> {
+    let mut file_override = FileOverride::default();
+    for (yaml_key, yaml_value) in yaml_to_hash(yaml, "overrides")? {
+        match yaml_key.as_str() {
+            Some("root") => file_override.root = Some(yaml_to_string(yaml_value, "root")?),
+            Some("link_order") => {
+                let order = match yaml_value.as_str() {
+                    Some("authored") => LinkOrder::Authored,
+                    Some("priority") => LinkOrder::Priority,
+                    _ => {
+                        return Err(TreeError::Validation(format!(
+                            "YAML link order is not `authored` or `priority`: '{:?}'",
+                            yaml_value
+                        )))
+                    }
+                };
+                file_override.link_order = Some(order);
+            }
+            _ => {
+                return Err(TreeError::Validation(format!(
+                    "YAML override contains an unknown key: `{:?}`",
+                    yaml_key
+                )))
+            }
+        }
+    }
+    Ok(file_override)
+}
+
+fn yaml_to_string(yaml: &Yaml, field: &str) -> Result<String, TreeError> {
+    yaml.as_str().map(str::to_owned).ok_or_else(|| {
+        TreeError::Validation(format!(
+            "YAML `{}` value is not a string: '{:?}'",
+            field, yaml
+        ))
+    })
+}
+
+fn yaml_to_vec<'y>(yaml: &'y Yaml, field: &str) -> Result<&'y Vec<Yaml>, TreeError> {
+    yaml.as_vec().ok_or_else(|| {
+        TreeError::Validation(format!("YAML `{}` is not an array: '{:?}'", field, yaml))
+    })
+}
+
+fn yaml_to_hash<'y>(yaml: &'y Yaml, field: &str) -> Result<&'y yaml_rust::yaml::Hash, TreeError> {
+    yaml.as_hash().ok_or_else(|| {
+        TreeError::Validation(format!("YAML `{}` is not a hash: '{:?}'", field, yaml))
+    })
+}
+
+#[cfg(test)]
+#[test]
+fn test_manifest_try_from() {
+    use crate::error::ImportError::Validation;
+
+    let source = r#"---
+    name: Example
+    files:
+        - intro.convo.yml
+        - shop/outro.convo.yml
+    variables:
+        met: false
+        gold: 10
+        town: Springville
+    characters: characters.yml
+    localization:
+        default: en
+        locales: [en, fr]
+        directory: locales
+    overrides:
+        shop/outro.convo.yml:
+            root: end
+            link_order: authored
+    "#;
+    let manifest = Manifest::try_from(source).unwrap();
+    assert_eq!(Some("Example"), manifest.name.as_deref());
+    assert_eq!(
+        vec![
+            PathBuf::from("intro.convo.yml"),
+            PathBuf::from("shop/outro.convo.yml")
+        ],
+        manifest.files
+    );
+    assert_eq!(Some(&Value::Bool(false)), manifest.variables.get("met"));
+    assert_eq!(Some(&Value::Int(10)), manifest.variables.get("gold"));
+    assert_eq!(
+        Some(&Value::from("Springville")),
+        manifest.variables.get("town")
+    );
+    assert_eq!(
+        Some(Path::new("characters.yml")),
+        manifest.characters.as_deref()
+    );
+    assert_eq!(
+        Localization {
+            default_locale: Some("en".into()),
+            locales: vec!["en".into(), "fr".into()],
+            directory: Some("locales".into()),
+        },
+        manifest.localization
+    );
+    assert_eq!(
+        FileOverride {
+            root: Some("end".into()),
+            link_order: Some(LinkOrder::Authored),
+        },
+        manifest.overrides[Path::new("shop/outro.convo.yml")]
+    );
+
+    // Invalid manifests
+    for source in [
+        "---\n[a]\n",
+        "---\nfiles: a.convo.yml\n",
+        "---\nvariables:\n    ratio: 1.5\n",
+        "---\ncharacters: [characters.yml]\n",
+        "---\nlocalization:\n    fallback: en\n",
+        "---\nfiles: [a.convo.yml]\noverrides:\n    b.convo.yml:\n        root: start\n",
+        "---\nfiles: [a.convo.yml]\noverrides:\n    a.convo.yml:\n        link_order: random\n",
+        "---\nunknown: true\n",
+    ] {
+        assert!(matches!(
+            Manifest::try_from(source).unwrap_err(),
+            Validation(_)
+        ));
+    }
+}
+
+#[test]
+fn test_project_from_manifest() {
+    let dir = "examples/dialogue_files";
+
+    // Overrides are applied
+    let mut manifest = Manifest::default();
+    manifest.files.push("ex_min.convo.yml".into());
+    manifest.overrides.insert(
+        "ex_min.convo.yml".into(),
+        FileOverride {
+            root: Some("end".into()),
+            link_order: Some(LinkOrder::Priority),
+        },
+    );
+    let project = Project::from_manifest(manifest.clone(), dir).unwrap();
+    let tree = project.tree("ex_min").unwrap();
+    assert_eq!("end", tree.root_key().unwrap());
+    assert_eq!(LinkOrder::Priority, tree.link_order());
+
+    // Invalid overrides name the file
+    manifest
+        .overrides
+        .get_mut(Path::new("ex_min.convo.yml"))
+        .unwrap()
+        .root = Some("none".into());
+    assert!(matches!(
+        Project::from_manifest(manifest, dir).unwrap_err(),
+        ProjectError::File(path, ImportError::Validation(TreeError::NodeDNE { .. }))
+            if path == Path::new(dir).join("ex_min.convo.yml")
+    ));
+
+    // Characters files must import
+    let manifest = Manifest {
+        characters: Some("ex_1.convo.yml".into()),
+        ..Default::default()
+    };
+    assert!(matches!(
+        Project::from_manifest(manifest, dir).unwrap_err(),
+        ProjectError::Characters(_)
+    ));
+
+    // Files which fail to import name the file
+    let mut manifest = Manifest::default();
+    manifest.files.push("ex_bad.convo.yml".into());
+    assert!(matches!(
+        Project::from_manifest(manifest, dir).unwrap_err(),
+        ProjectError::File(..)
+    ));
+
+    // Tree names must be unique
+    let mut manifest = Manifest::default();
+    manifest.files.push("ex_min.convo.yml".into());
+    manifest
+        .files
+        .push("../dialogue_files/ex_min.convo.yml".into());
+    assert!(matches!(
+        Project::from_manifest(manifest, dir).unwrap_err(),
+        ProjectError::Validation(_)
+    ));
+
+    // Node keys cannot contain `/`
+    let dir = std::env::temp_dir();
+    let file = format!("convo_project_{}.convo.yml", std::process::id());
+    let source = "---\nroot: a/b\nnodes:\n    a/b:\n        dialogue: Hi\n";
+    fs::write(dir.join(&file), source).unwrap();
+    let mut manifest = Manifest::default();
+    manifest.files.push(file.as_str().into());
+    let result = Project::from_manifest(manifest, &dir);
+    fs::remove_file(dir.join(&file)).unwrap();
+    assert!(matches!(
+        result.unwrap_err(),
+        ProjectError::File(_, ImportError::Validation(TreeError::Validation(_)))
+    ));
+}
+
+#[test]
+fn test_project_lint() {
+    use crate::{link::Link, node::Node};
+
+    let tree = |root_key: &str, links: &[(&str, &str)]| {
+        let mut tree = Tree::new();
+        let mut root = Node::new(root_key, "Hello!");
+        for (from, to) in links {
+            if *from == root_key {
+                root.links.push(Link::new(*to, "Go."));
+            } else {
+                let mut node = Node::new(*from, "Hmm.");
+                node.links.push(Link::new(*to, "Go."));
+                tree.insert_node(node);
+            }
+        }
+        root.end = root.links.is_empty();
+        tree.insert_node(root);
+        tree.set_root_key(root_key).unwrap();
+        tree
+    };
+    let project = |trees: Vec<(&str, Tree)>| Project {
+        manifest: Manifest::default(),
+        trees: trees
+            .into_iter()
+            .map(|(name, tree)| (name.into(), (format!("{}.convo.yml", name).into(), tree)))
+            .collect(),
+        characters: Characters::new(),
+        lint_config: LintConfig::default(),
+    };
+
+    // Jumps to other trees use them, and reach their nodes
+    let diagnostics = project(vec![
+        ("intro", tree("start", &[("start", "shop/haggle")])),
+        ("shop", tree("welcome", &[("haggle", "intro/start")])),
+    ])
+    .lint();
+    assert!(diagnostics.is_empty());
+
+    // Broken jumps and unused trees, which only jump to themselves, name the file, tree, and node
+    let diagnostics = project(vec![
+        (
+            "intro",
+            tree("start", &[("start", "shop/none"), ("start", "none/start")]),
+        ),
+        ("shop", tree("welcome", &[("welcome", "shop/welcome")])),
+    ])
+    .lint();
+    let rules: Vec<(&str, &str, Rule)> = diagnostics
+        .iter()
+        .map(|d| {
+            (
+                d.tree.as_str(),
+                d.diagnostic.node_key.as_str(),
+                d.diagnostic.rule,
+            )
+        })
+        .collect();
+    assert_eq!(
+        vec![
+            ("intro", "start", Rule::BrokenJump),
+            ("intro", "start", Rule::BrokenJump),
+            ("shop", "welcome", Rule::UnusedTree),
+        ],
+        rules
+    );
+    assert_eq!(Path::new("intro.convo.yml"), diagnostics[0].path);
+    assert_eq!(
+        concat!(
+            r#"[{"rule":"unused-tree","severity":"warning","file":"shop.convo.yml","#,
+            r#""node":"welcome","span":null,"message":"Tree `shop` is never jumped to"}]"#
+        ),
+        to_json(&diagnostics[2..])
+    );
+
+    // Broken jumps suggest the closest tree or node
+    let diagnostics = project(vec![
+        (
+            "intro",
+            tree(
+                "start",
+                &[("start", "shpo/welcome"), ("start", "shop/welcom")],
+            ),
+        ),
+        ("shop", tree("welcome", &[("welcome", "intro/start")])),
+    ])
+    .lint();
+    assert!(diagnostics[0]
+        .diagnostic
+        .message
+        .ends_with("which does not exist, did you mean `shop`?"));
+    assert!(diagnostics[1]
+        .diagnostic
+        .message
+        .ends_with("in tree `shop`, did you mean `welcome`?"));
+
+    // Shared variables are declared in every tree
+    let mut intro = tree("start", &[("start", "start")]);
+    intro.node_mut("start").unwrap().links[0].set = vec![("gold".into(), Value::Int(5))];
+    let mut project = project(vec![("intro", intro)]);
+    let rules = |project: &Project| -> Vec<Rule> {
+        project.lint().iter().map(|d| d.diagnostic.rule).collect()
+    };
+    assert_eq!(
+        vec![Rule::SelfLink, Rule::UndeclaredVariable],
+        rules(&project)
+    );
+    project
+        .manifest
+        .variables
+        .insert("gold".into(), Value::Int(0));
+    assert_eq!(vec![Rule::SelfLink], rules(&project));
+    project
+        .manifest
+        .variables
+        .insert("gold".into(), Value::Bool(false));
+    assert_eq!(vec![Rule::SelfLink, Rule::VariableType], rules(&project));
+
+    // Lint configurations turn rules off and change their severity
+    let mut config = LintConfig::default();
+    config.rules.insert("self-link".into(), None);
+    config
+        .rules
+        .insert("variable-type".into(), Some(lint::Severity::Warning));
+    project.set_lint_config(config);
+    let diagnostics = project.lint();
+    assert_eq!(1, diagnostics.len());
+    assert_eq!(lint::Severity::Warning, diagnostics[0].diagnostic.severity);
+
+    // Speakers are only checked when the manifest registers characters
+    let mut intro = tree("start", &[]);
+    let line = crate::node::Line::new("guard", "Halt!");
+    intro.node_mut("start").unwrap().lines.push(line);
+    project
+        .trees
+        .insert("intro".into(), ("intro.convo.yml".into(), intro));
+    assert!(project.lint().is_empty());
+    project.manifest.characters = Some("characters.yml".into());
+    assert_eq!(vec![Rule::UnknownSpeaker], rules(&project));
+    project
+        .characters
+        .insert(crate::characters::Character::new("guard", "Gate Guard"));
+    assert!(project.lint().is_empty());
+}
+
+#[test]
+fn test_project_resolve() {
+    use crate::node::Node;
+
+    let mut intro = Tree::new();
+    intro.insert_node(Node::new("start", "Hello!"));
+    let mut shop = Tree::new();
+    shop.insert_node(Node::new("start", "Welcome!"));
+    let project = Project {
+        manifest: Manifest::default(),
+        trees: vec![("intro", intro), ("shop", shop)]
+            .into_iter()
+            .map(|(name, tree)| (name.into(), (PathBuf::new(), tree)))
+            .collect(),
+        characters: Characters::new(),
+        lint_config: LintConfig::default(),
+    };
+
+    // Unqualified keys resolve within their tree, and qualified keys within any tree
+    let resolve = |tree_name, key| {
+        project
+            .resolve(tree_name, key)
+            .map(|(name, node)| (name, node.dialogue.as_str()))
+    };
+    assert_eq!(Some(("intro", "Hello!")), resolve("intro", "start"));
+    assert_eq!(Some(("shop", "Welcome!")), resolve("intro", "shop/start"));
+    assert_eq!(Some(("intro", "Hello!")), resolve("shop", "intro/start"));
+    assert_eq!(None, resolve("intro", "shop/end"));
+    assert_eq!(None, resolve("intro", "outro/start"));
+    assert_eq!(None, resolve("outro", "start"));
+    assert_eq!("Welcome!", project.node("shop/start").unwrap().dialogue);
+    assert!(project.node("start").is_none());
+}
+
+#[test]
+fn test_project_reload() {
+    let dir = std::env::temp_dir();
+    let file = format!("convo_reload_{}.convo.yml", std::process::id());
+    let path = dir.join(&file);
+    let source = "---\nroot: start\nnodes:\n    start:\n        dialogue: Hi\n        links:\n            - end: Bye\n    end:\n        dialogue: Bye\n";
+    fs::write(&path, source).unwrap();
+    let mut manifest = Manifest::default();
+    manifest.files.push(file.as_str().into());
+    let mut project = Project::from_manifest(manifest, &dir).unwrap();
+    let name = tree_name(Path::new(&file));
+
+    // Only edited nodes are reported
+    fs::write(&path, source.replace("dialogue: Bye", "dialogue: Farewell")).unwrap();
+    let diff = project.reload(&name).unwrap();
+    assert_eq!(vec!["end"], diff.changed);
+    assert!(diff.added.is_empty() && diff.removed.is_empty());
+    assert_eq!(
+        "Farewell",
+        project.tree(&name).unwrap().node("end").unwrap().dialogue
+    );
+
+    // Files which no longer import leave the tree unchanged
+    fs::write(&path, "---\nroot: missing\nnodes: {}\n").unwrap();
+    let result = project.reload(&name);
+    fs::remove_file(&path).unwrap();
+    assert!(matches!(result.unwrap_err(), ProjectError::File(..)));
+    assert_eq!(2, project.tree(&name).unwrap().len());
+    assert!(matches!(
+        project.reload("missing").unwrap_err(),
+        ProjectError::Validation(_)
+    ));
+}
@@ -0,0 +1,268 @@
+use alloc::boxed::Box;
+use core::{future::Future, ops::Deref, pin::Pin};
+
+use crate::{
+    error::TreeError,
+    link::Link,
+    node::Node,
+    tree::Tree,
+    walker::{Status, Walker},
+};
+
+/// A [`ChoiceFuture`] is a boxed [`Future`] resolving to the index of a chosen [`Link`], or [`None`] to stop walking.
+pub type ChoiceFuture<'p> = Pin<Box<dyn Future<Output = Option<usize>> + 'p>>;
+
+/// A [`SendChoiceFuture`] is a [`ChoiceFuture`] which may be sent between threads.
+pub type SendChoiceFuture<'p> = Pin<Box<dyn Future<Output = Option<usize>> + Send + 'p>>;
+
+/// A [`ChoiceProvider`] supplies choices to a [`Walker`] asynchronously, e.g. from a network client or a UI. See also: [`Walker::run`].
+pub trait ChoiceProvider {
+    /// Returns a [`ChoiceFuture`] which resolves to the index of the chosen [`Link`] in `choices`, or [`None`] to stop walking.
+    ///
+    /// This is called for every [`Node`] the walker enters which awaits a choice, i.e. which has choices and is not an ending, so `choices` is never empty.
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The [`Node`] the walker is currently at.
+    /// * `choices` - The [`Link`]s which may be chosen, as ordered by [`Walker::choices`].
+    fn choose<'p>(&'p mut self, node: &'p Node, choices: &'p [&'p Link]) -> ChoiceFuture<'p>;
+}
+
+/// A [`SendChoiceProvider`] is a [`ChoiceProvider`] whose futures may be sent between threads, so that a walk may be spawned on a multi-threaded executor. See also: [`Walker::run_send`].
+pub trait SendChoiceProvider: Send {
+    /// Returns a [`SendChoiceFuture`] which resolves to the index of the chosen [`Link`] in `choices`, or [`None`] to stop walking.
+    ///
+    /// This is called for every [`Node`] the walker enters which awaits a choice, i.e. which has choices and is not an ending, so `choices` is never empty.
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The [`Node`] the walker is currently at.
+    /// * `choices` - The [`Link`]s which may be chosen, as ordered by [`Walker::choices`].
+    fn choose<'p>(&'p mut self, node: &'p Node, choices: &'p [&'p Link]) -> SendChoiceFuture<'p>;
+}
+
+impl<T> Walker<T>
+where
+    T: Deref<Target = Tree>,
+{
+    /// Try to walk the conversation to its end, asking a [`ChoiceProvider`] for every choice.
+    ///
+    /// Walking stops when the walker's [`status`][`Walker#method.status`] is [`Status::Finished`], [`Status::Aborted`], or [`Status::Blocked`], or when the provider resolves to [`None`]. The provider is not asked about the last node, including an [`end`][`Node#structfield.end`] node with links.
+    ///
+    /// # Arguments
+    ///
+    /// * `provider` - A [`ChoiceProvider`] which supplies choices.
+    ///
+    /// # Errors
+    ///
+    /// * A [`TreeError`] will be returned if the provider chooses a link which does not exist, or a link leads to a node which does not exist.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use convo::{ChoiceFuture, ChoiceProvider, Link, Node, Tree, Walker};
+    ///
+    /// struct FirstChoice;
+    ///
+    /// impl ChoiceProvider for FirstChoice {
+    ///     fn choose<'p>(&'p mut self, node: &'p Node, _choices: &'p [&'p Link]) -> ChoiceFuture<'p> {
+    ///         Box::pin(async move {
+    ///             println!("{}", node.dialogue);
+    ///             Some(0)
+    ///         })
+    ///     }
+    /// }
+    ///
+    /// async fn play(walker: &mut Walker<&Tree>) {
+    ///     walker.run(&mut FirstChoice).await.unwrap();
+    /// }
+    /// ```
+    pub async fn run<P>(&mut self, provider: &mut P) -> Result<(), TreeError>
+    where
+        P: ChoiceProvider + ?Sized,
+    {
+        // Only a pending choice is asked for, so finished, aborted, and blocked conversations stop
+        while matches!(self.status(), Status::AwaitingChoice | Status::Narrating) {
+            let node = self.current_node();
+            let choices = self.choices();
+            match provider.choose(node, &choices).await {
+                Some(index) => self.choose(index)?,
+                None => return Ok(()),
+            };
+        }
+        Ok(())
+    }
+
+    /// Try to walk the conversation to its end, asking a [`SendChoiceProvider`] for every choice.
+    ///
+    /// This behaves like [`Walker::run`], but the returned future is [`Send`] whenever the walker is, so it may be spawned on a multi-threaded executor.
+    ///
+    /// # Arguments
+    ///
+    /// * `provider` - A [`SendChoiceProvider`] which supplies choices.
+    ///
+    /// # Errors
+    ///
+    /// * A [`TreeError`] will be returned if the provider chooses a link which does not exist, or a link leads to a node which does not exist.
+    pub async fn run_send<P>(&mut self, provider: &mut P) -> Result<(), TreeError>
+    where
+        P: SendChoiceProvider + ?Sized,
+    {
+        // Only a pending choice is asked for, so finished, aborted, and blocked conversations stop
+        while matches!(self.status(), Status::AwaitingChoice | Status::Narrating) {
+            let node = self.current_node();
+            let choices = self.choices();
+            match provider.choose(node, &choices).await {
+                Some(index) => self.choose(index)?,
+                None => return Ok(()),
+            };
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+fn block_on<F: Future>(future: F) -> F::Output {
+    use std::task::{Context, Poll, Waker};
+
+    let mut future = Box::pin(future);
+    let mut context = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+    }
+}
+
+#[cfg(test)]
+struct ScriptedProvider {
+    script: Vec<Option<usize>>,
+    visited: Vec<String>,
+}
+
+#[cfg(test)]
+impl ChoiceProvider for ScriptedProvider {
+    fn choose<'p>(&'p mut self, node: &'p Node, choices: &'p [&'p Link]) -> ChoiceFuture<'p> {
+        Box::pin(async move {
+            assert!(!choices.is_empty());
+            self.visited.push(node.key().to_owned());
+            self.script.remove(0)
+        })
+    }
+}
+
+#[cfg(test)]
+impl SendChoiceProvider for ScriptedProvider {
+    fn choose<'p>(&'p mut self, node: &'p Node, choices: &'p [&'p Link]) -> SendChoiceFuture<'p> {
+        Box::pin(async move {
+            assert!(!choices.is_empty());
+            self.visited.push(node.key().to_owned());
+            self.script.remove(0)
+        })
+    }
+}
+
+#[test]
+fn test_run() {
+    // start -> (start | end)
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "Again?");
+    let end = Node::new("end", "Done.");
+    start.links.push(Link::new("start", "Again!"));
+    Link::link(&mut start, &end, "Stop.");
+    tree.insert_node(start);
+    tree.insert_node(end);
+    tree.set_root_key("start").unwrap();
+
+    // Should walk to the end
+    let mut walker = Walker::new(&tree).unwrap();
+    let mut provider = ScriptedProvider {
+        script: vec![Some(0), Some(0), Some(1)],
+        visited: vec![],
+    };
+    block_on(walker.run(&mut provider)).unwrap();
+    assert_eq!(vec!["start", "start", "start"], provider.visited);
+    assert!(walker.is_finished());
+
+    // Should stop when the provider resolves to `None`
+    let mut walker = Walker::new(&tree).unwrap();
+    let mut provider = ScriptedProvider {
+        script: vec![Some(0), None],
+        visited: vec![],
+    };
+    block_on(walker.run(&mut provider)).unwrap();
+    assert_eq!(vec!["start", "start"], provider.visited);
+    assert_eq!("start", walker.current_key());
+
+    // Should fail when the provider chooses a link which does not exist
+    let mut walker = Walker::new(&tree).unwrap();
+    let mut provider = ScriptedProvider {
+        script: vec![Some(5)],
+        visited: vec![],
+    };
+    assert!(matches!(
+        block_on(walker.run(&mut provider)).unwrap_err(),
+        TreeError::LinkDNE(5)
+    ));
+}
+
+#[test]
+fn test_run_stops() {
+    // start -> ending -> epilogue, where the ending is marked as one
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "The dragon falls.");
+    let mut ending = Node::new("ending", "The kingdom is saved.");
+    let epilogue = Node::new("epilogue", "Years later...");
+    Link::link(&mut start, &ending, "Cheer.");
+    Link::link(&mut ending, &epilogue, "Continue");
+    ending.end = true;
+    tree.extend_nodes(vec![start, ending, epilogue]).unwrap();
+    tree.set_root_key("start").unwrap();
+
+    // Should stop at an ending with links, without asking about it
+    let mut walker = Walker::new(&tree).unwrap();
+    let mut provider = ScriptedProvider {
+        script: vec![Some(0), Some(0)],
+        visited: vec![],
+    };
+    block_on(walker.run(&mut provider)).unwrap();
+    assert_eq!(vec!["start"], provider.visited);
+    assert_eq!("ending", walker.current_key());
+
+    // Should stop without asking when aborted
+    let mut walker = Walker::new(&tree).unwrap();
+    walker.abort("combat started");
+    let mut provider = ScriptedProvider {
+        script: vec![Some(0)],
+        visited: vec![],
+    };
+    block_on(walker.run(&mut provider)).unwrap();
+    assert!(provider.visited.is_empty());
+}
+
+#[test]
+fn test_run_send() {
+    fn assert_send<F: Send>(future: F) -> F {
+        future
+    }
+
+    // start -> (start | end)
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "Again?");
+    let end = Node::new("end", "Done.");
+    start.links.push(Link::new("start", "Again!"));
+    Link::link(&mut start, &end, "Stop.");
+    tree.insert_node(start);
+    tree.insert_node(end);
+    tree.set_root_key("start").unwrap();
+
+    // Should walk to the end with a future which may be sent between threads
+    let mut walker = Walker::new(&tree).unwrap();
+    let mut provider = ScriptedProvider {
+        script: vec![Some(0), Some(1)],
+        visited: vec![],
+    };
+    block_on(assert_send(walker.run_send(&mut provider))).unwrap();
+    assert_eq!(vec!["start", "start"], provider.visited);
+    assert!(walker.is_finished());
+}
@@ -0,0 +1,131 @@
+use alloc::borrow::ToOwned;
+
+use crate::tree::Tree;
+
+/// A [`RawTree`] is a capability to edit a [`Tree`] without [validation checks](https://github.com/simbleau/convo/blob/dev/FORMATTING.md#validation-rules). It is returned by [`Tree::raw`].
+///
+/// Every operation is memory-safe. Bypassing validation can only leave keys which index no [`crate::Node`], in which case queries such as [`Tree::root_node`] return [`None`] and walkers fail with a [`crate::error::TreeError`]. Prefer the checked methods on [`Tree`] unless the invariants are already known to hold, e.g. while building a tree in bulk.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{Node, Tree};
+/// let mut tree = Tree::new();
+/// tree.raw().set_root_key("root");
+/// assert_eq!("root", tree.root_key().unwrap());
+/// assert!(tree.root_node().is_none());
+/// ```
+#[derive(Debug)]
+pub struct RawTree<'t> {
+    tree: &'t mut Tree,
+}
+
+impl<'t> RawTree<'t> {
+    pub(crate) fn new(tree: &'t mut Tree) -> Self {
+        RawTree { tree }
+    }
+
+    /// Set the root node key. Unlike [`Tree::set_root_key`], this method does not check the node exists and will **not** incur side effects to the current node key in any way.
+    ///
+    /// # Arguments
+    ///
+    /// * `node_key` - A string slice that holds a unique identifier which should index a [`crate::Node`] in the [`Tree::nodes`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Tree, Node};
+    /// let mut tree = Tree::new();
+    /// let root_node = Node::new("root", "The only node.");
+    /// tree.insert_node(root_node);
+    /// tree.raw().set_root_key("root");
+    /// assert!(tree.current_key().is_none());
+    /// ```
+    pub fn set_root_key(&mut self, node_key: &str) -> &mut Self {
+        self.tree.root_key = Some(node_key.to_owned());
+        self
+    }
+
+    /// Set the current node key. Unlike [`Tree::set_current_key`], this method does not check the node exists.
+    ///
+    /// # Arguments
+    ///
+    /// * `node_key` - A string slice that holds a unique identifier which should index a [`crate::Node`] in the [`Tree::nodes`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Tree, Node};
+    /// let mut tree = Tree::new();
+    /// let current_node = Node::new("x", "Some node.");
+    /// tree.insert_node(current_node);
+    /// tree.raw().set_current_key("x");
+    /// assert_eq!("x", tree.current_key().unwrap());
+    /// ```
+    pub fn set_current_key(&mut self, node_key: &str) -> &mut Self {
+        self.tree.current_key = Some(node_key.to_owned());
+        self
+    }
+
+    /// Rewind the current node key back to the root key by cloning the root key. Unlike [`Tree::rewind`], this method does not check a root is set, and clears the current node key if it is not.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Tree, Node};
+    /// let mut tree = Tree::new();
+    /// let current_node = Node::new("x", "Some node.");
+    /// tree.insert_node(current_node);
+    /// tree.set_current_key("x").unwrap();
+    /// tree.raw().rewind();
+    /// assert!(tree.current_key().is_none()); // Because the root was `None`.
+    /// ```
+    pub fn rewind(&mut self) -> &mut Self {
+        self.tree.current_key = self.tree.root_key.clone();
+        self
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_set_root_key() {
+    let mut tree = Tree::new();
+    tree.raw().set_root_key("root");
+
+    // Ensure root key was set, but not current
+    assert_eq!("root", tree.root_key().unwrap());
+    assert!(tree.current_key().is_none());
+}
+
+#[test]
+fn test_set_current_key() {
+    let mut tree = Tree::new();
+    tree.raw().set_current_key("current");
+
+    // Ensure current key was set
+    assert_eq!("current", tree.current_key().unwrap());
+}
+
+#[test]
+fn test_rewind() {
+    use crate::node::Node;
+
+    // Set up tree with a root and additional node
+    let mut tree = Tree::new();
+    let root_node = Node::new("root", "The root node.");
+    let current_node = Node::new("current", "A node.");
+    tree.insert_node(root_node);
+    tree.insert_node(current_node);
+
+    tree.set_current_key("current").unwrap();
+
+    // Test rewind
+    assert_eq!("current", tree.current_key().unwrap());
+    tree.raw().rewind();
+    assert!(tree.root_node().is_none());
+    assert!(tree.current_key().is_none());
+
+    // Operations chain
+    tree.raw().set_root_key("root").rewind();
+    assert_eq!("root", tree.current_key().unwrap());
+}
@@ -0,0 +1,365 @@
+//! A family of functions which report the variable values required to reach each node of a [`Tree`], e.g. to verify content gating against a quest design.
+
+use alloc::{
+    borrow::{Cow, ToOwned},
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+
+use crate::{
+    affinity,
+    analysis::csv_field,
+    condition::{Clause, Condition, Op},
+    menu,
+    node::NodeKind,
+    tree::Tree,
+    value::Value,
+};
+
+/// A [`NodeRequirements`] lists the clauses which hold on every path from the root node to a single [`crate::Node`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct NodeRequirements {
+    /// The key of the node.
+    pub node_key: String,
+
+    /// Whether the node can be reached from the root node by following links.
+    pub reachable: bool,
+
+    /// The clauses of link conditions which are tested on every path to the node, in the order they are first tested. Clauses on variables which a link assigns earlier on the path are not required, since the conversation itself decides them.
+    pub clauses: Vec<Clause>,
+}
+
+// The clauses required, and the variables which may have been assigned, on arriving at a node
+type Arrival<'t> = (Vec<Clause>, Vec<Cow<'t, str>>);
+
+/// Returns the [`NodeRequirements`] of every [`crate::Node`] of a [`Tree`], in the order they are stored.
+///
+/// Unreachable nodes, and every node of a tree without a root, have no requirements.
+///
+/// # Arguments
+///
+/// * `tree` - A [`Tree`] to report on.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{requirements, Condition, Link, Node, Tree};
+/// let mut tree = Tree::new();
+/// let mut start = Node::new("start", "Got the gold?");
+/// let mut shop = Node::new("shop", "Welcome!");
+/// let end = Node::new("end", "Goodbye!");
+/// Link::link(&mut start, &shop, "Here you go.");
+/// Link::link(&mut shop, &end, "Bye!");
+/// start.links[0].condition = Some(Condition::parse("gold >= 10").unwrap());
+/// tree.insert_node(start);
+/// tree.insert_node(shop);
+/// tree.insert_node(end);
+/// tree.set_root_key("start").unwrap();
+/// let requirements = requirements::requirements(&tree);
+/// assert!(requirements[0].clauses.is_empty());
+/// assert_eq!(Condition::parse("gold >= 10").unwrap().clauses, requirements[2].clauses);
+/// ```
+pub fn requirements(tree: &Tree) -> Vec<NodeRequirements> {
+    let mut states: Vec<Option<Arrival<'_>>> = vec![None; tree.len()];
+    let keys: Vec<&str> = tree.nodes().map(|node| node.key.as_str()).collect();
+    let position = |key: &str| keys.iter().position(|k| *k == key);
+
+    if let Some(root) = tree.root_key().and_then(|key| position(key)) {
+        states[root] = Some((vec![], vec![]));
+        let mut queue = vec![root];
+        while let Some(i) = queue.pop() {
+            let (required, assigned) = states[i].clone().unwrap_or_default();
+            let node = tree.node(keys[i]);
+            let links = node.map(|node| &node.links[..]);
+
+            // The template of a menu node assigns the entry chosen
+            let chosen = node.and_then(|node| match &node.kind {
+                NodeKind::Menu { source } => Some(menu::variable(source)),
+                _ => None,
+            });
+            for (k, link) in links.unwrap_or_default().iter().enumerate() {
+                let j = match position(&link.to_key) {
+                    Some(j) => j,
+                    None => continue,
+                };
+
+                // Conditions are tested before the link assigns its variables
+                let mut link_required = required.clone();
+                let tested = link.condition.iter().flat_map(|c| &c.clauses);
+                for clause in tested {
+                    let name = Cow::Borrowed(clause.name());
+                    if !assigned.contains(&name) && !link_required.contains(clause) {
+                        link_required.push(clause.clone());
+                    }
+                }
+                let mut link_assigned = assigned.clone();
+                let names = link
+                    .set
+                    .iter()
+                    .map(|(name, _)| Cow::Borrowed(name.as_str()));
+                let adjusted = link
+                    .affinity
+                    .iter()
+                    .map(|(character, _)| Cow::Owned(affinity::variable(character)));
+                let chosen = chosen
+                    .iter()
+                    .filter(|_| k == 0)
+                    .map(|name| Cow::Owned(name.clone()));
+                for name in names.chain(adjusted).chain(chosen) {
+                    if !link_assigned.contains(&name) {
+                        link_assigned.push(name);
+                    }
+                }
+
+                // Merge with other paths, requiring what all require and assigning what any assign
+                let merged = match &states[j] {
+                    None => (link_required, link_assigned),
+                    Some((prev_required, prev_assigned)) => {
+                        let mut required = prev_required.clone();
+                        required.retain(|clause| link_required.contains(clause));
+                        let mut assigned = prev_assigned.clone();
+                        for name in link_assigned {
+                            if !assigned.contains(&name) {
+                                assigned.push(name);
+                            }
+                        }
+                        (required, assigned)
+                    }
+                };
+                if states[j].as_ref() != Some(&merged) {
+                    states[j] = Some(merged);
+                    queue.push(j);
+                }
+            }
+        }
+    }
+
+    keys.iter()
+        .zip(states)
+        .map(|(key, state)| NodeRequirements {
+            node_key: (*key).to_owned(),
+            reachable: state.is_some(),
+            clauses: state.map(|(required, _)| required).unwrap_or_default(),
+        })
+        .collect()
+}
+
+/// Returns [`NodeRequirements`] as CSV, with a header row and one row per node. Requirements are written as a condition, e.g. `met_stranger && gold >= 10`.
+///
+/// # Arguments
+///
+/// * `requirements` - The [`NodeRequirements`] to write, e.g. from [`requirements()`].
+///
+/// # Examples
+///
+/// ```
+/// use convo::{requirements::{self, NodeRequirements}, Condition};
+/// let rows = [NodeRequirements {
+///     node_key: "shop".into(),
+///     reachable: true,
+///     clauses: Condition::parse("met && gold >= 10").unwrap().clauses,
+/// }];
+/// assert_eq!(
+///     "node,reachable,requirements\nshop,true,met && gold >= 10\n",
+///     requirements::to_csv(&rows)
+/// );
+/// ```
+pub fn to_csv(requirements: &[NodeRequirements]) -> String {
+    let mut csv = "node,reachable,requirements\n".to_owned();
+    for row in requirements {
+        let condition = match row.clauses.is_empty() {
+            true => String::new(),
+            false => Condition {
+                clauses: row.clauses.clone(),
+            }
+            .to_string(),
+        };
+        csv.push_str(&format!(
+            "{},{},{}\n",
+            csv_field(&row.node_key),
+            row.reachable,
+            csv_field(&condition)
+        ));
+    }
+    csv
+}
+
+/// Returns [`NodeRequirements`] as a JSON array with one object per node. Each clause is an object with a `variable`, an `op`, and a `value`, where flags compare to `true` or `false` with `==`, and ranges have the `in` op and a value of their start and end, e.g. `[9,17]`.
+///
+/// # Arguments
+///
+/// * `requirements` - The [`NodeRequirements`] to write, e.g. from [`requirements()`].
+///
+/// # Examples
+///
+/// ```
+/// use convo::{requirements::{self, NodeRequirements}, Condition};
+/// let rows = [NodeRequirements {
+///     node_key: "shop".into(),
+///     reachable: true,
+///     clauses: Condition::parse("!met").unwrap().clauses,
+/// }];
+/// assert_eq!(
+///     r#"[{"node":"shop","reachable":true,"requirements":[{"variable":"met","op":"==","value":false}]}]"#,
+///     requirements::to_json(&rows)
+/// );
+/// ```
+pub fn to_json(requirements: &[NodeRequirements]) -> String {
+    let rows: Vec<String> = requirements
+        .iter()
+        .map(|row| {
+            let clauses: Vec<String> = row
+                .clauses
+                .iter()
+                .map(|clause| {
+                    let (name, op, value) = match clause {
+                        Clause::Flag { name, negated } => (name, Op::Eq, Value::Bool(!negated)),
+                        Clause::Compare { name, op, value } => (name, *op, value.clone()),
+                        Clause::Within { name, start, end } => {
+                            return format!(
+                                "{{\"variable\":{},\"op\":\"in\",\"value\":[{},{}]}}",
+                                json_string(name),
+                                start,
+                                end
+                            )
+                        }
+                    };
+                    let value = match value {
+                        Value::Bool(value) => format!("{}", value),
+                        Value::Int(value) => format!("{}", value),
+                        Value::String(value) => json_string(&value),
+                    };
+                    format!(
+                        "{{\"variable\":{},\"op\":\"{}\",\"value\":{}}}",
+                        json_string(name),
+                        op.as_str(),
+                        value
+                    )
+                })
+                .collect();
+            format!(
+                "{{\"node\":{},\"reachable\":{},\"requirements\":[{}]}}",
+                json_string(&row.node_key),
+                row.reachable,
+                clauses.join(",")
+            )
+        })
+        .collect();
+    format!("[{}]", rows.join(","))
+}
+
+pub(crate) fn json_string(value: &str) -> String {
+    let mut json = String::with_capacity(value.len() + 2);
+    json.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+#[cfg(test)]
+#[test]
+fn test_requirements() {
+    use crate::{link::Link, node::Node};
+
+    // start -> a [met] -> b [gold > 1] -> end, start -> b [met && !quest] (sets gold), b -> start
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "Start");
+    let mut a = Node::new("a", "A");
+    let mut b = Node::new("b", "B");
+    let end = Node::new("end", "End");
+    Link::link(&mut start, &a, "To A");
+    Link::link(&mut start, &b, "To B");
+    Link::link(&mut a, &b, "To B");
+    Link::link(&mut b, &end, "To the end");
+    Link::link(&mut b, &start, "Back");
+    start.links[0].condition = Some(Condition::parse("met").unwrap());
+    start.links[1].condition = Some(Condition::parse("met && !quest").unwrap());
+    start.links[1].set.push(("gold".into(), Value::Int(5)));
+    a.links[0].condition = Some(Condition::parse("gold > 1").unwrap());
+    b.links[0].condition = Some(Condition::parse("gold > 1").unwrap());
+    for node in [start, a, b, end, Node::new("orphan", "Orphan")] {
+        tree.insert_node(node);
+    }
+
+    // Without a root, nothing is reachable
+    assert!(requirements(&tree).iter().all(|row| !row.reachable));
+
+    tree.set_root_key("start").unwrap();
+    let requirements = requirements(&tree);
+    let clauses = |source: &str| match source {
+        "" => vec![],
+        source => Condition::parse(source).unwrap().clauses,
+    };
+    assert_eq!(5, requirements.len());
+    assert_eq!(clauses(""), requirements[0].clauses);
+    assert_eq!(clauses("met"), requirements[1].clauses);
+    assert_eq!(clauses("met"), requirements[2].clauses);
+    // Gold may have been assigned on the way to the end
+    assert_eq!(clauses("met"), requirements[3].clauses);
+    assert_eq!("orphan", requirements[4].node_key);
+    assert!(!requirements[4].reachable);
+    assert!(requirements[3].reachable);
+
+    // The template of a menu node assigns the entry chosen
+    let mut tree = Tree::new();
+    let mut shop = Node::new("shop", "Shop");
+    let mut sold = Node::new("sold", "Sold");
+    let thanks = Node::new("thanks", "Thanks");
+    shop.kind = NodeKind::Menu {
+        source: "stock".into(),
+    };
+    Link::link(&mut shop, &sold, "Buy");
+    Link::link(&mut sold, &thanks, "Enjoy your potion.");
+    sold.links[0].condition = Some(Condition::parse("menu.stock == potion").unwrap());
+    for node in [shop, sold, thanks] {
+        tree.insert_node(node);
+    }
+    tree.set_root_key("shop").unwrap();
+    assert!(crate::requirements::requirements(&tree)
+        .iter()
+        .all(|row| row.reachable && row.clauses.is_empty()));
+}
+
+#[test]
+fn test_to_csv_and_json() {
+    let rows = [
+        NodeRequirements {
+            node_key: "a, \"b\"".into(),
+            reachable: true,
+            clauses: Condition::parse("gold >= 10 && town == \"Spring, ville\"")
+                .unwrap()
+                .clauses,
+        },
+        NodeRequirements {
+            node_key: "orphan".into(),
+            reachable: false,
+            clauses: vec![],
+        },
+    ];
+    assert_eq!(
+        "node,reachable,requirements\n\"a, \"\"b\"\"\",true,\"gold >= 10 && town == \"\"Spring, ville\"\"\"\norphan,false,\n",
+        to_csv(&rows)
+    );
+    assert_eq!(
+        concat!(
+            r#"[{"node":"a, \"b\"","reachable":true,"requirements":["#,
+            r#"{"variable":"gold","op":">=","value":10},"#,
+            r#"{"variable":"town","op":"==","value":"Spring, ville"}]},"#,
+            r#"{"node":"orphan","reachable":false,"requirements":[]}]"#
+        ),
+        to_json(&rows)
+    );
+    assert_eq!("\"\\u0001\\n\\\\\"", json_string("\u{1}\n\\"));
+}
@@ -0,0 +1,283 @@
+use alloc::{collections::BTreeMap, string::String};
+use core::ops::Deref;
+
+use crate::{
+    tree::Tree,
+    walker::{Status, Walker},
+};
+
+/// A [`ResumePoints`] remembers where aborted conversations left off, per tree and speaker, so they can be continued later with [`Walker::resume_or_start`]. This is how most ambient NPC chatter behaves.
+///
+/// Trees and speakers are named by the host, e.g. by the tree's name in a [`crate::project::Project`] and the speaking character's id. Resume points are plain strings, so they can be saved with the rest of the game state.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{Link, Node, ResumePoints, Tree, Walker};
+/// let mut tree = Tree::new();
+/// let mut start = Node::new("start", "Have you heard the news?");
+/// let end = Node::new("end", "The king is dead!");
+/// Link::link(&mut start, &end, "What news?");
+/// tree.insert_node(start);
+/// tree.insert_node(end);
+/// tree.set_root_key("start").unwrap();
+///
+/// let mut points = ResumePoints::new();
+/// let mut walker = Walker::resume_or_start(&tree, &points, "gossip", "baker").unwrap();
+/// walker.choose(0).unwrap();
+/// walker.abort("player walked away");
+/// points.record(&walker);
+///
+/// let walker = Walker::resume_or_start(&tree, &points, "gossip", "baker").unwrap();
+/// assert_eq!("end", walker.current_key());
+/// ```
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct ResumePoints {
+    /// Node keys, mapped by tree name and speaker.
+    points: BTreeMap<(String, String), String>,
+}
+
+impl ResumePoints {
+    /// Returns an empty [`ResumePoints`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::ResumePoints;
+    /// let points = ResumePoints::new();
+    /// assert!(points.is_empty());
+    /// ```
+    pub fn new() -> Self {
+        ResumePoints::default()
+    }
+
+    /// Record the outcome of a conversation started with [`Walker::resume_or_start`]. An aborted conversation records a resume point at its current node, and a finished conversation removes its resume point, so it starts over next time. Otherwise, and for walkers which were not started with [`Walker::resume_or_start`], nothing is recorded.
+    ///
+    /// # Arguments
+    ///
+    /// * `walker` - The [`Walker`] of the conversation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Node, ResumePoints, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let mut points = ResumePoints::new();
+    /// let mut walker = Walker::resume_or_start(&tree, &points, "greeting", "guard").unwrap();
+    /// walker.abort("combat started");
+    /// points.record(&walker);
+    /// assert_eq!(Some("start"), points.get("greeting", "guard"));
+    /// ```
+    pub fn record<T>(&mut self, walker: &Walker<T>)
+    where
+        T: Deref<Target = Tree>,
+    {
+        let (tree_name, speaker) = match walker.resume_key() {
+            Some(key) => key,
+            None => return,
+        };
+        match walker.status() {
+            Status::Aborted => {
+                self.insert(tree_name, speaker, walker.current_key());
+            }
+            Status::Finished => {
+                self.remove(tree_name, speaker);
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns the key of the [`crate::Node`] a conversation resumes at, or [`None`] if it has no resume point.
+    ///
+    /// # Arguments
+    ///
+    /// * `tree_name` - A string slice that holds the name of the tree.
+    /// * `speaker` - A string slice that holds the name of the speaker.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::ResumePoints;
+    /// let mut points = ResumePoints::new();
+    /// points.insert("gossip", "baker", "news");
+    /// assert_eq!(Some("news"), points.get("gossip", "baker"));
+    /// assert_eq!(None, points.get("gossip", "smith"));
+    /// ```
+    pub fn get(&self, tree_name: &str, speaker: &str) -> Option<&str> {
+        self.points
+            .get(&(tree_name.into(), speaker.into()))
+            .map(String::as_str)
+    }
+
+    /// Set the node a conversation resumes at, returning the previous node key, if any. This is useful to load resume points from saved game state.
+    ///
+    /// # Arguments
+    ///
+    /// * `tree_name` - A string type that holds the name of the tree.
+    /// * `speaker` - A string type that holds the name of the speaker.
+    /// * `node_key` - A string type that holds the key of the node to resume at.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::ResumePoints;
+    /// let mut points = ResumePoints::new();
+    /// assert_eq!(None, points.insert("gossip", "baker", "news"));
+    /// assert_eq!(Some("news".to_owned()), points.insert("gossip", "baker", "rumors"));
+    /// ```
+    pub fn insert<T>(&mut self, tree_name: T, speaker: T, node_key: T) -> Option<String>
+    where
+        T: Into<String>,
+    {
+        self.points
+            .insert((tree_name.into(), speaker.into()), node_key.into())
+    }
+
+    /// Remove the resume point of a conversation, returning its node key, if any.
+    ///
+    /// # Arguments
+    ///
+    /// * `tree_name` - A string slice that holds the name of the tree.
+    /// * `speaker` - A string slice that holds the name of the speaker.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::ResumePoints;
+    /// let mut points = ResumePoints::new();
+    /// points.insert("gossip", "baker", "news");
+    /// assert_eq!(Some("news".to_owned()), points.remove("gossip", "baker"));
+    /// assert!(points.is_empty());
+    /// ```
+    pub fn remove(&mut self, tree_name: &str, speaker: &str) -> Option<String> {
+        self.points.remove(&(tree_name.into(), speaker.into()))
+    }
+
+    /// Returns an [`Iterator`] over every resume point as `(tree_name, speaker, node_key)`, e.g. to save them with game state.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::ResumePoints;
+    /// let mut points = ResumePoints::new();
+    /// points.insert("gossip", "baker", "news");
+    /// assert_eq!(vec![("gossip", "baker", "news")], points.iter().collect::<Vec<_>>());
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str, &str)> {
+        self.points.iter().map(|((tree_name, speaker), node_key)| {
+            (tree_name.as_str(), speaker.as_str(), node_key.as_str())
+        })
+    }
+
+    /// Returns the number of resume points.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::ResumePoints;
+    /// assert_eq!(0, ResumePoints::new().len());
+    /// ```
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    /// Returns whether there are no resume points.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::ResumePoints;
+    /// assert!(ResumePoints::new().is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_resume_points() {
+    use crate::{link::Link, node::Node};
+
+    // start -> middle -> end
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "Start");
+    let mut middle = Node::new("middle", "Middle");
+    let end = Node::new("end", "End");
+    Link::link(&mut start, &middle, "Continue");
+    Link::link(&mut middle, &end, "Continue");
+    for node in [start, middle, end] {
+        tree.insert_node(node);
+    }
+    tree.set_root_key("start").unwrap();
+    let mut points = ResumePoints::new();
+
+    // Conversations in progress, and walkers without a resume key, record nothing
+    let mut walker = Walker::resume_or_start(&tree, &points, "chatter", "baker").unwrap();
+    walker.choose(0).unwrap();
+    points.record(&walker);
+    let mut plain = Walker::new(&tree).unwrap();
+    plain.abort("combat started");
+    points.record(&plain);
+    assert!(points.is_empty());
+
+    // Aborting records a resume point per tree and speaker
+    walker.abort("combat started");
+    points.record(&walker);
+    assert_eq!(Some("middle"), points.get("chatter", "baker"));
+    assert_eq!(None, points.get("chatter", "smith"));
+    let walker = Walker::resume_or_start(&tree, &points, "chatter", "smith").unwrap();
+    assert_eq!("start", walker.current_key());
+
+    // Resuming continues at the saved node, and finishing starts over next time
+    let mut walker = Walker::resume_or_start(&tree, &points, "chatter", "baker").unwrap();
+    assert_eq!("middle", walker.current_key());
+    assert!(walker.history().is_empty());
+    assert_eq!("middle", walker.transcript()[0].node_key);
+    walker.choose(0).unwrap();
+    points.record(&walker);
+    assert!(points.is_empty());
+
+    // Resume points to nodes which no longer exist start over
+    points.insert("chatter", "baker", "removed");
+    let walker = Walker::resume_or_start(&tree, &points, "chatter", "baker").unwrap();
+    assert_eq!("start", walker.current_key());
+}
+
+#[test]
+fn test_resume_automatic_nodes() {
+    use crate::{link::Link, node::Node, value::Value, walker::Status, NodeKind};
+
+    // greet (random) -> hello, and news (random) -> rumor
+    let mut tree = Tree::new();
+    let mut greet = Node::new("greet", "");
+    greet.kind = NodeKind::Random { avoid_recent: 0 };
+    let mut news = Node::new("news", "");
+    news.kind = NodeKind::Random { avoid_recent: 0 };
+    let hello = Node::new("hello", "Hello!");
+    let rumor = Node::new("rumor", "Did you hear?");
+    Link::link(&mut greet, &hello, "");
+    greet.links[0]
+        .set
+        .push(("greeted".into(), Value::Bool(true)));
+    Link::link(&mut news, &rumor, "");
+    for node in [greet, news, hello, rumor] {
+        tree.insert_node(node);
+    }
+    tree.set_root_key("greet").unwrap();
+    let mut points = ResumePoints::new();
+
+    // Starting passes through the root
+    let walker = Walker::resume_or_start(&tree, &points, "chatter", "baker").unwrap();
+    assert_eq!("hello", walker.current_key());
+    assert_eq!(Some(&Value::Bool(true)), walker.variables().get("greeted"));
+
+    // Resuming starts afresh, passing through the resume point rather than the root
+    points.insert("chatter", "baker", "news");
+    let walker = Walker::resume_or_start(&tree, &points, "chatter", "baker").unwrap();
+    assert_eq!("rumor", walker.current_key());
+    assert!(walker.variables().is_empty());
+    assert_ne!(Status::Narrating, walker.status());
+}
@@ -0,0 +1,34 @@
+/// The seed of a [`Rng`] whose seed is not set.
+const DEFAULT_SEED: u64 = 0x2545_f491_4f6c_dd1d;
+
+/// A small, seedable pseudo-random generator (xorshift64*), so draws are reproducible without a dependency.
+#[derive(Debug, Clone)]
+pub(crate) struct Rng(u64);
+
+impl Default for Rng {
+    fn default() -> Self {
+        Rng(DEFAULT_SEED)
+    }
+}
+
+impl Rng {
+    pub(crate) fn new(seed: u64) -> Self {
+        // The generator never leaves the zero state, so it is avoided
+        match seed {
+            0 => Rng::default(),
+            seed => Rng(seed),
+        }
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    // Returns a number in `0..bound`, where `bound` is not 0
+    pub(crate) fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+}
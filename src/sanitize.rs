@@ -0,0 +1,402 @@
+//! A sanitizing import for generated dialogue, e.g. nodes written by a language model, which repairs what it can rather than rejecting the whole file, and reports every repair it made.
+//!
+//! Before the YAML is read into a [`Tree`] like [`importer::source_to_tree`], the dialogue of every node, line, and link has its disallowed control characters removed and its whitespace normalized, and is truncated to the length limit of the [`SanitizeOptions`], if there is one. Nodes without a key, or given as an array rather than a hash, are keyed `node_1`, `node_2`, and so on. Anything else which is not legal is still rejected.
+
+use crate::{
+    error::ImportError,
+    importer::{self, get_file_source},
+    tree::Tree,
+};
+
+use std::{fmt, path::Path};
+use yaml_rust::{yaml, Yaml, YamlLoader};
+
+/// [`SanitizeOptions`] control how generated dialogue is repaired.
+///
+/// # Examples
+///
+/// ```
+/// use convo::sanitize::SanitizeOptions;
+/// let options = SanitizeOptions {
+///     max_chars: Some(280),
+///     ..SanitizeOptions::default()
+/// };
+/// assert_eq!("node_", options.key_prefix);
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SanitizeOptions {
+    /// The maximum number of characters of any dialogue, beyond which it is truncated, if there is one.
+    pub max_chars: Option<usize>,
+
+    /// The prefix of the keys generated for nodes without one, followed by a number.
+    pub key_prefix: String,
+}
+
+impl Default for SanitizeOptions {
+    fn default() -> Self {
+        SanitizeOptions {
+            max_chars: None,
+            key_prefix: "node_".to_owned(),
+        }
+    }
+}
+
+/// A [`Repair`] is a change made to generated dialogue so it could be imported.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Repair {
+    /// The key of the repaired node, which for a generated key is the key generated.
+    pub node_key: String,
+
+    /// The repaired field of the node, e.g. `dialogue`, `dialogue[1].text`, or `links[0].dialogue`.
+    pub field: String,
+
+    /// What was repaired.
+    pub kind: RepairKind,
+}
+
+impl fmt::Display for Repair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}` {}: ", self.node_key, self.field)?;
+        match self.kind {
+            RepairKind::GeneratedKey => f.write_str("generated a key for an unnamed node"),
+            RepairKind::ControlCharacters(count) => {
+                write!(f, "removed {} control character(s)", count)
+            }
+            RepairKind::Whitespace => f.write_str("normalized whitespace"),
+            RepairKind::Truncated(chars) => write!(f, "truncated from {} characters", chars),
+        }
+    }
+}
+
+/// A [`RepairKind`] is the kind of a [`Repair`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum RepairKind {
+    /// The node had no key, so one was generated.
+    GeneratedKey,
+    /// Control characters other than line breaks and tabs were removed, with the number removed.
+    ControlCharacters(usize),
+    /// Line breaks were normalized to `\n`, runs of whitespace within lines were collapsed to a single space, or whitespace was trimmed from the ends of lines or of the dialogue.
+    Whitespace,
+    /// The dialogue was longer than the length limit, with its number of characters before it was truncated.
+    Truncated(usize),
+}
+
+/// Try to return a [`Tree`] which is generated from importing a file of generated dialogue, and the [`Repair`]s made to it.
+///
+/// # Arguments
+///
+/// * `path` - A path type that references a file to parse from.
+/// * `options` - The [`SanitizeOptions`] which control how dialogue is repaired.
+///
+/// # Errors
+///
+/// * An [`ImportError`] will be returned if the source is not valid YAML data or if the repaired tree is not considered legal when parsing.
+///   See also: [validation rules](https://github.com/simbleau/convo/blob/dev/FORMATTING.md#validation-rules).
+///
+/// # Examples
+///
+/// ```
+/// use convo::sanitize::{self, SanitizeOptions};
+/// let (tree, repairs) = sanitize::import("examples/dialogue_files/ex_min.convo.yml", &SanitizeOptions::default()).unwrap();
+/// assert!(repairs.is_empty());
+/// ```
+pub fn import<P>(path: P, options: &SanitizeOptions) -> Result<(Tree, Vec<Repair>), ImportError>
+where
+    P: AsRef<Path>,
+{
+    let source = get_file_source(path)?;
+    source_to_tree(&source, options)
+}
+
+/// Try to return a [`Tree`] which is generated from parsing a string slice of generated dialogue, and the [`Repair`]s made to it, in the order the nodes are written.
+///
+/// # Arguments
+///
+/// * `source` - A string slice that holds YAML data to parse from.
+/// * `options` - The [`SanitizeOptions`] which control how dialogue is repaired.
+///
+/// # Errors
+///
+/// * An [`ImportError`] will be returned if the source is not valid YAML data or if the repaired tree is not considered legal when parsing.
+///   See also: [validation rules](https://github.com/simbleau/convo/blob/dev/FORMATTING.md#validation-rules).
+///
+/// # Examples
+///
+/// ```
+/// use convo::sanitize::{self, RepairKind, SanitizeOptions};
+/// let source = "---\nroot: start\nnodes:\n  start:\n    dialogue: \"  Hello,\\u0007   traveler. \"\n";
+/// let (tree, repairs) = sanitize::source_to_tree(source, &SanitizeOptions::default()).unwrap();
+/// assert_eq!("Hello, traveler.", tree.node("start").unwrap().dialogue);
+/// assert_eq!(RepairKind::ControlCharacters(1), repairs[0].kind);
+/// assert_eq!(RepairKind::Whitespace, repairs[1].kind);
+/// ```
+pub fn source_to_tree(
+    source: &str,
+    options: &SanitizeOptions,
+) -> Result<(Tree, Vec<Repair>), ImportError> {
+    // Parse the YAML
+    let mut docs = YamlLoader::load_from_str(source)?;
+    if docs.len() != 1 {
+        return Err(ImportError::MultipleDocumentsProvided());
+    }
+    let mut yaml = docs.remove(0);
+
+    // Repair nodes, leaving anything which is not a node map for the importer to reject
+    let mut repairs = Vec::new();
+    if let Yaml::Hash(document) = &mut yaml {
+        let nodes_key = Yaml::String("nodes".to_owned());
+        if let Some(yaml_nodes) = document.get_mut(&nodes_key) {
+            sanitize_nodes(yaml_nodes, options, &mut repairs);
+        }
+    }
+
+    let tree = importer::yaml_to_tree(&yaml)?;
+    Ok((tree, repairs))
+}
+
+fn sanitize_nodes(yaml_nodes: &mut Yaml, options: &SanitizeOptions, repairs: &mut Vec<Repair>) {
+    // Nodes given as an array have no keys
+    let entries: Vec<(Option<Yaml>, Yaml)> = match std::mem::replace(yaml_nodes, Yaml::Null) {
+        Yaml::Hash(map) => map
+            .into_iter()
+            .map(|(key, data)| match &key {
+                Yaml::Null => (None, data),
+                Yaml::String(name) if name.trim().is_empty() => (None, data),
+                _ => (Some(key), data),
+            })
+            .collect(),
+        Yaml::Array(array) => array.into_iter().map(|data| (None, data)).collect(),
+        other => {
+            *yaml_nodes = other;
+            return;
+        }
+    };
+
+    // Generated keys skip any key already taken
+    let taken: Vec<&str> = entries
+        .iter()
+        .filter_map(|(key, _)| key.as_ref().and_then(Yaml::as_str))
+        .collect();
+    let mut generated = Vec::new();
+    let mut number = 0;
+    for _ in entries.iter().filter(|(key, _)| key.is_none()) {
+        let key = loop {
+            number += 1;
+            let key = format!("{}{}", options.key_prefix, number);
+            if !taken.contains(&key.as_str()) {
+                break key;
+            }
+        };
+        generated.push(key);
+    }
+
+    let mut generated = generated.into_iter();
+    let mut map = yaml::Hash::new();
+    for (key, mut data) in entries {
+        let key = match key {
+            Some(key) => key,
+            None => {
+                let key = generated
+                    .next()
+                    .expect("a key is generated for every unnamed node");
+                repairs.push(Repair {
+                    node_key: key.clone(),
+                    field: "key".to_owned(),
+                    kind: RepairKind::GeneratedKey,
+                });
+                Yaml::String(key)
+            }
+        };
+        let node_key = match &key {
+            Yaml::String(name) => name.clone(),
+            other => format!("{:?}", other),
+        };
+        sanitize_node(&node_key, &mut data, options, repairs);
+        map.insert(key, data);
+    }
+    *yaml_nodes = Yaml::Hash(map);
+}
+
+fn sanitize_node(
+    node_key: &str,
+    data: &mut Yaml,
+    options: &SanitizeOptions,
+    repairs: &mut Vec<Repair>,
+) {
+    let mut repair = |yaml: &mut Yaml, field: String| {
+        if let Yaml::String(text) = yaml {
+            let (sanitized, kinds) = sanitize_text(text, options);
+            *text = sanitized;
+            repairs.extend(kinds.into_iter().map(|kind| Repair {
+                node_key: node_key.to_owned(),
+                field: field.clone(),
+                kind,
+            }));
+        }
+    };
+    let map = match data {
+        Yaml::Hash(map) => map,
+        _ => return,
+    };
+
+    // Dialogue is a string, or a sequence of lines
+    match map.get_mut(&Yaml::String("dialogue".to_owned())) {
+        Some(Yaml::Array(lines)) => {
+            for (index, line) in lines.iter_mut().enumerate() {
+                if let Yaml::Hash(line) = line {
+                    if let Some(text) = line.get_mut(&Yaml::String("text".to_owned())) {
+                        repair(text, format!("dialogue[{}].text", index));
+                    }
+                }
+            }
+        }
+        Some(dialogue) => repair(dialogue, "dialogue".to_owned()),
+        None => {}
+    }
+
+    if let Some(Yaml::Array(links)) = map.get_mut(&Yaml::String("links".to_owned())) {
+        sanitize_links(links, "links", &mut repair);
+    }
+}
+
+fn sanitize_links<F>(links: &mut [Yaml], path: &str, repair: &mut F)
+where
+    F: FnMut(&mut Yaml, String),
+{
+    for (index, link) in links.iter_mut().enumerate() {
+        let link = match link {
+            Yaml::Hash(link) => link,
+            _ => continue,
+        };
+        let path = format!("{}[{}]", path, index);
+
+        // Groups hold links, structured links hold their dialogue or text, and shorthand links are their dialogue
+        if let Some(Yaml::Array(links)) = link.get_mut(&Yaml::String("links".to_owned())) {
+            sanitize_links(links, &format!("{}.links", path), repair);
+        } else if link.contains_key(&Yaml::String("to".to_owned())) {
+            for field in ["dialogue", "text"] {
+                if let Some(text) = link.get_mut(&Yaml::String(field.to_owned())) {
+                    repair(text, format!("{}.{}", path, field));
+                }
+            }
+        } else if link.len() == 1 {
+            for (_, text) in link.iter_mut() {
+                repair(text, path.clone());
+            }
+        }
+    }
+}
+
+// Returns repaired text, and the kinds of repairs made to it, in the order they were made
+fn sanitize_text(text: &str, options: &SanitizeOptions) -> (String, Vec<RepairKind>) {
+    let mut kinds = Vec::new();
+
+    // Line breaks are normalized first, so carriage returns are not counted as control characters
+    let unified = text.replace("\r\n", "\n").replace('\r', "\n");
+    let controls = unified.chars().filter(|c| is_disallowed(*c)).count();
+    if controls > 0 {
+        kinds.push(RepairKind::ControlCharacters(controls));
+    }
+    let stripped: String = unified.chars().filter(|c| !is_disallowed(*c)).collect();
+
+    // Each line has its runs of whitespace collapsed, and blank lines at either end are dropped
+    let lines: Vec<String> = stripped
+        .split('\n')
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .collect();
+    let first = lines.iter().position(|line| !line.is_empty());
+    let last = lines.iter().rposition(|line| !line.is_empty());
+    let mut sanitized = match (first, last) {
+        (Some(first), Some(last)) => lines[first..=last].join("\n"),
+        _ => String::new(),
+    };
+    if unified != text || sanitized != stripped {
+        kinds.push(RepairKind::Whitespace);
+    }
+
+    if let Some(max_chars) = options.max_chars {
+        let chars = sanitized.chars().count();
+        if chars > max_chars {
+            sanitized = sanitized.chars().take(max_chars).collect::<String>();
+            sanitized.truncate(sanitized.trim_end().len());
+            kinds.push(RepairKind::Truncated(chars));
+        }
+    }
+
+    (sanitized, kinds)
+}
+
+fn is_disallowed(c: char) -> bool {
+    c.is_control() && c != '\n' && c != '\t'
+}
+
+#[cfg(test)]
+#[test]
+fn test_source_to_tree() {
+    use crate::error::ImportError::Validation;
+
+    let source = "---
+root: start
+nodes:
+    start:
+        dialogue: \"Welcome,\\u0000 traveler.\\r\\nStay   a while.\\n\\n\"
+        links:
+            - start: \" Thanks. \"
+            - group: Ask about...
+              links:
+                  - to: start
+                    dialogue: \"\\tThe inn\"
+    ~:
+        dialogue:
+            - speaker: keeper
+              text: Fine.
+    node_1:
+        dialogue: This node is far too long for the limit, by a long way.
+    \"  \":
+        dialogue: Blank.
+";
+    let options = SanitizeOptions {
+        max_chars: Some(40),
+        ..SanitizeOptions::default()
+    };
+    let (tree, repairs) = source_to_tree(source, &options).unwrap();
+    assert_eq!(
+        "Welcome, traveler.\nStay a while.",
+        tree.node("start").unwrap().dialogue
+    );
+    assert_eq!(
+        "This node is far too long for the limit,",
+        tree.node("node_1").unwrap().dialogue
+    );
+    assert_eq!("Fine.", tree.node("node_2").unwrap().lines[0].text);
+    assert_eq!("Blank.", tree.node("node_3").unwrap().dialogue);
+    let repairs: Vec<String> = repairs.iter().map(ToString::to_string).collect();
+    assert_eq!(
+        vec![
+            "`start` dialogue: removed 1 control character(s)",
+            "`start` dialogue: normalized whitespace",
+            "`start` links[0]: normalized whitespace",
+            "`start` links[1].links[0].dialogue: normalized whitespace",
+            "`node_2` key: generated a key for an unnamed node",
+            "`node_1` dialogue: truncated from 55 characters",
+            "`node_3` key: generated a key for an unnamed node",
+        ],
+        repairs
+    );
+
+    // Nodes given as an array are all keyed, and legal content needs no repairs
+    let source = "---\nroot: node_1\nnodes:\n    - dialogue: One.\n    - dialogue: Two.\n";
+    let (tree, repairs) = source_to_tree(source, &SanitizeOptions::default()).unwrap();
+    let keys: Vec<&str> = tree.nodes().map(|node| node.key.as_str()).collect();
+    assert_eq!(vec!["node_1", "node_2"], keys);
+    assert_eq!(2, repairs.len());
+    let source = "---\nroot: start\nnodes:\n    start:\n        dialogue: Hi.\n";
+    assert!(source_to_tree(source, &options).unwrap().1.is_empty());
+
+    // What can't be repaired is still rejected
+    let source = "---\nroot: start\nnodes:\n    start:\n        links: []\n";
+    assert!(matches!(
+        source_to_tree(source, &options).unwrap_err(),
+        Validation(_)
+    ));
+}
@@ -0,0 +1,365 @@
+//! Scheduling of queued conversations, which play one at a time after delays and when their conditions hold, e.g. "play this tree 5 seconds after the last one ends, if `met_stranger`".
+//!
+//! A [`Scheduler`] is ticked by the host each frame with the time elapsed and the game's variables. It plays the first queued conversation whose delay has passed since the last conversation ended, and whose [`Condition`] holds, then holds its [`Walker`] until it is finished or aborted.
+
+use alloc::{
+    collections::BTreeMap,
+    string::String,
+    vec::{self, Vec},
+};
+use core::{ops::Deref, time::Duration};
+
+use crate::{
+    condition::Condition,
+    tree::Tree,
+    value::Value,
+    walker::{Status, Walker},
+};
+
+/// A conversation waiting in a scheduler's queue.
+#[derive(Debug, Clone)]
+struct Scheduled<T>
+where
+    T: Deref<Target = Tree>,
+{
+    walker: Walker<T>,
+
+    /// How long no conversation must have played for before this one plays.
+    delay: Duration,
+
+    /// The condition which must hold for this one to play, if any.
+    condition: Option<Condition>,
+}
+
+/// A [`Scheduler`] plays queued conversations one at a time, each once its delay has passed since the last conversation ended, and its [`Condition`] holds. Queued conversations which cannot play yet wait, while later ones which can play first.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{scheduler::Scheduler, Condition, Node, Tree, Value, Walker};
+/// use std::{collections::BTreeMap, time::Duration};
+/// let mut tree = Tree::new();
+/// tree.insert_node(Node::new("start", "Psst, over here."));
+/// tree.set_root_key("start").unwrap();
+///
+/// let mut scheduler = Scheduler::new();
+/// let condition = Condition::parse("met_stranger").unwrap();
+/// scheduler.schedule(Walker::new(&tree).unwrap(), Duration::from_secs(5), Some(condition));
+///
+/// let mut flags = BTreeMap::new();
+/// assert!(scheduler.tick(Duration::from_secs(6), &flags).is_none());
+/// flags.insert("met_stranger".to_owned(), Value::Bool(true));
+/// let walker = scheduler.tick(Duration::from_millis(16), &flags).unwrap();
+/// assert_eq!("Psst, over here.", walker.current_node().dialogue);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Scheduler<T>
+where
+    T: Deref<Target = Tree>,
+{
+    /// The conversations waiting to play, in the order they were scheduled.
+    queue: Vec<Scheduled<T>>,
+
+    /// The conversation playing, if any.
+    current: Option<Walker<T>>,
+
+    /// How long no conversation has played for.
+    idle: Duration,
+
+    /// The walkers of conversations which ended, which the host has not taken.
+    ended: Vec<Walker<T>>,
+}
+
+impl<T> Default for Scheduler<T>
+where
+    T: Deref<Target = Tree>,
+{
+    fn default() -> Self {
+        Scheduler::new()
+    }
+}
+
+impl<T> Scheduler<T>
+where
+    T: Deref<Target = Tree>,
+{
+    /// Returns a [`Scheduler`] with an empty queue, which has been idle for no time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{scheduler::Scheduler, Tree};
+    /// let scheduler: Scheduler<&Tree> = Scheduler::new();
+    /// assert!(scheduler.is_empty());
+    /// ```
+    pub fn new() -> Self {
+        Scheduler {
+            queue: Vec::new(),
+            current: None,
+            idle: Duration::ZERO,
+            ended: Vec::new(),
+        }
+    }
+
+    /// Queue a conversation, which plays once no conversation has played for its delay, and its condition holds.
+    ///
+    /// # Arguments
+    ///
+    /// * `walker` - The [`Walker`] of the conversation.
+    /// * `delay` - How long after the last conversation ended the conversation may play.
+    /// * `condition` - The [`Condition`] on the variables passed to [`tick`][`Scheduler#method.tick`] which must hold for the conversation to play, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{scheduler::Scheduler, Node, Tree, Walker};
+    /// use std::{collections::BTreeMap, time::Duration};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let mut scheduler = Scheduler::new();
+    /// scheduler.schedule(Walker::new(&tree).unwrap(), Duration::ZERO, None);
+    /// assert_eq!(1, scheduler.len());
+    /// ```
+    pub fn schedule(&mut self, walker: Walker<T>, delay: Duration, condition: Option<Condition>) {
+        self.queue.push(Scheduled {
+            walker,
+            delay,
+            condition,
+        });
+    }
+
+    /// Advance the scheduler by the time elapsed, e.g. once per frame, returning the [`Walker`] of a conversation which started playing. The conversation playing ends once its walker is finished or aborted, and is kept for [`take_ended`][`Scheduler#method.take_ended`].
+    ///
+    /// # Arguments
+    ///
+    /// * `elapsed` - The time elapsed since the last tick.
+    /// * `variables` - The values of variables which queued conditions test, mapped by name, e.g. the game's flags.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{scheduler::Scheduler, Node, Tree, Walker};
+    /// use std::{collections::BTreeMap, time::Duration};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let mut scheduler = Scheduler::new();
+    /// scheduler.schedule(Walker::new(&tree).unwrap(), Duration::from_secs(5), None);
+    ///
+    /// let flags = BTreeMap::new();
+    /// assert!(scheduler.tick(Duration::from_secs(3), &flags).is_none());
+    /// assert!(scheduler.tick(Duration::from_secs(3), &flags).is_some());
+    /// ```
+    pub fn tick(
+        &mut self,
+        elapsed: Duration,
+        variables: &BTreeMap<String, Value>,
+    ) -> Option<&mut Walker<T>> {
+        match &self.current {
+            Some(walker) if matches!(walker.status(), Status::Finished | Status::Aborted) => {
+                self.end();
+            }
+            Some(_) => return None,
+            None => self.idle = self.idle.saturating_add(elapsed),
+        }
+
+        let idle = self.idle;
+        let index = self.queue.iter().position(|scheduled| {
+            scheduled.delay <= idle
+                && scheduled
+                    .condition
+                    .as_ref()
+                    .is_none_or(|condition| condition.holds(variables))
+        })?;
+        self.current = Some(self.queue.remove(index).walker);
+        self.current.as_mut()
+    }
+
+    /// Returns the [`Walker`] of the conversation playing, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{scheduler::Scheduler, Tree};
+    /// let scheduler: Scheduler<&Tree> = Scheduler::new();
+    /// assert!(scheduler.current().is_none());
+    /// ```
+    pub fn current(&self) -> Option<&Walker<T>> {
+        self.current.as_ref()
+    }
+
+    /// Returns the mutable [`Walker`] of the conversation playing, if any, e.g. to choose a link.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{scheduler::Scheduler, Link, Node, Tree, Walker};
+    /// use std::{collections::BTreeMap, time::Duration};
+    /// let mut tree = Tree::new();
+    /// let mut start = Node::new("start", "Hello!");
+    /// let end = Node::new("end", "Goodbye!");
+    /// Link::link(&mut start, &end, "Bye!");
+    /// tree.insert_node(start);
+    /// tree.insert_node(end);
+    /// tree.set_root_key("start").unwrap();
+    /// let mut scheduler = Scheduler::new();
+    /// scheduler.schedule(Walker::new(&tree).unwrap(), Duration::ZERO, None);
+    /// scheduler.tick(Duration::ZERO, &BTreeMap::new());
+    /// scheduler.current_mut().unwrap().choose(0).unwrap();
+    /// ```
+    pub fn current_mut(&mut self) -> Option<&mut Walker<T>> {
+        self.current.as_mut()
+    }
+
+    /// End the conversation playing, if any, e.g. when the player walks away, and keep its [`Walker`] for [`take_ended`][`Scheduler#method.take_ended`]. Delays are counted from now.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{scheduler::Scheduler, Node, Tree, Walker};
+    /// use std::{collections::BTreeMap, time::Duration};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let mut scheduler = Scheduler::new();
+    /// scheduler.schedule(Walker::new(&tree).unwrap(), Duration::ZERO, None);
+    /// scheduler.tick(Duration::ZERO, &BTreeMap::new());
+    /// scheduler.end();
+    /// assert!(scheduler.current().is_none());
+    /// assert_eq!(1, scheduler.take_ended().count());
+    /// ```
+    pub fn end(&mut self) {
+        if let Some(walker) = self.current.take() {
+            self.ended.push(walker);
+            self.idle = Duration::ZERO;
+        }
+    }
+
+    /// Returns an iterator over the [`Walker`]s of conversations which ended, in the order they ended, and forgets them. Their walkers may be recorded as [`crate::ResumePoints`], for example.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{scheduler::Scheduler, Tree};
+    /// let mut scheduler: Scheduler<&Tree> = Scheduler::new();
+    /// assert_eq!(0, scheduler.take_ended().count());
+    /// ```
+    pub fn take_ended(&mut self) -> vec::IntoIter<Walker<T>> {
+        core::mem::take(&mut self.ended).into_iter()
+    }
+
+    /// Returns how long no conversation has played for, since the last ended or the scheduler was created.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{scheduler::Scheduler, Tree};
+    /// use std::{collections::BTreeMap, time::Duration};
+    /// let mut scheduler: Scheduler<&Tree> = Scheduler::new();
+    /// scheduler.tick(Duration::from_secs(2), &BTreeMap::new());
+    /// assert_eq!(Duration::from_secs(2), scheduler.idle());
+    /// ```
+    pub fn idle(&self) -> Duration {
+        self.idle
+    }
+
+    /// Remove every queued conversation, leaving the conversation playing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{scheduler::Scheduler, Node, Tree, Walker};
+    /// use std::time::Duration;
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let mut scheduler = Scheduler::new();
+    /// scheduler.schedule(Walker::new(&tree).unwrap(), Duration::ZERO, None);
+    /// scheduler.clear();
+    /// assert!(scheduler.is_empty());
+    /// ```
+    pub fn clear(&mut self) {
+        self.queue.clear();
+    }
+
+    /// Returns the number of queued conversations, not counting the conversation playing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{scheduler::Scheduler, Tree};
+    /// let scheduler: Scheduler<&Tree> = Scheduler::new();
+    /// assert_eq!(0, scheduler.len());
+    /// ```
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Returns whether no conversations are queued, not counting the conversation playing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{scheduler::Scheduler, Tree};
+    /// let scheduler: Scheduler<&Tree> = Scheduler::new();
+    /// assert!(scheduler.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_scheduler() {
+    use crate::{link::Link, node::Node};
+
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "Hello!");
+    let end = Node::new("end", "Goodbye!");
+    Link::link(&mut start, &end, "Bye!");
+    tree.insert_node(start);
+    tree.insert_node(end);
+    tree.set_root_key("start").unwrap();
+    let tree_at = |key: &str| {
+        let mut tree = tree.clone();
+        tree.set_root_key(key).unwrap();
+        tree
+    };
+    let (first, second, third) = (tree_at("start"), tree_at("end"), tree_at("start"));
+    let seconds = Duration::from_secs;
+
+    let mut scheduler = Scheduler::new();
+    scheduler.schedule(
+        Walker::new(&first).unwrap(),
+        seconds(0),
+        Some(Condition::parse("met").unwrap()),
+    );
+    scheduler.schedule(Walker::new(&second).unwrap(), seconds(5), None);
+    scheduler.schedule(Walker::new(&third).unwrap(), seconds(0), None);
+    let mut flags = BTreeMap::new();
+
+    // Conversations whose condition does not hold wait, while later ones play
+    let walker = scheduler.tick(seconds(1), &flags).unwrap();
+    assert!(core::ptr::eq(&third, walker.tree()));
+    assert!(scheduler.tick(seconds(10), &flags).is_none());
+    assert_eq!(seconds(1), scheduler.idle());
+
+    // Delays are counted from the end of the last conversation
+    scheduler.current_mut().unwrap().choose(0).unwrap();
+    assert!(scheduler.tick(seconds(10), &flags).is_none());
+    assert_eq!(1, scheduler.take_ended().count());
+    assert!(scheduler.tick(seconds(4), &flags).is_none());
+    let walker = scheduler.tick(seconds(1), &flags).unwrap();
+    assert!(core::ptr::eq(&second, walker.tree()));
+
+    // Conditions test the variables passed on each tick
+    flags.insert("met".to_owned(), Value::Bool(true));
+    assert!(scheduler.tick(seconds(1), &flags).is_some());
+    assert!(core::ptr::eq(&first, scheduler.current().unwrap().tree()));
+    assert!(scheduler.is_empty());
+    scheduler.end();
+    assert_eq!(Duration::ZERO, scheduler.idle());
+    assert!(scheduler.tick(seconds(1), &flags).is_none());
+}
@@ -0,0 +1,394 @@
+//! A family of functions which extract the dialogue lines of [`Tree`]s and projects by speaker, e.g. to prepare scripts for voice-over recording sessions, or annotated datasets for training speech and dialogue models.
+//!
+//! Only the [`Line`][`crate::Line`]s of nodes are extracted, since they name their speakers. The dialogue of nodes without lines has no speaker, so it is not included.
+
+use alloc::{borrow::ToOwned, collections::BTreeMap, format, string::String, vec::Vec};
+
+#[cfg(feature = "json")]
+use serde_json::json;
+
+use crate::{analysis::csv_field, characters::Characters, tree::Tree};
+
+#[cfg(feature = "std")]
+use crate::project::Project;
+
+/// A [`ScriptLine`] is a single line of dialogue to record, with the context an actor needs to perform it.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ScriptLine {
+    /// The key of the node the line is spoken in, qualified as `tree/node` when it is extracted from a project.
+    pub node_key: String,
+
+    /// The stable [`id`][`crate::Node#structfield.id`] of the node the line is spoken in, if it has one.
+    pub node_id: Option<u64>,
+
+    /// The index of the line in the [`lines`][`crate::Node#structfield.lines`] of its node.
+    pub index: usize,
+
+    /// The speaker of the line.
+    pub speaker: String,
+
+    /// The text spoken.
+    pub text: String,
+
+    /// The emotion the line is spoken with, if it is annotated.
+    pub emotion: Option<String>,
+
+    /// The [`tags`][`crate::Node#structfield.tags`] of the node the line is spoken in.
+    pub tags: Vec<String>,
+
+    /// What is said before the line: the previous line of its node as `speaker: text`, or for the first line of a node, the dialogue of the link which leads to the node, if exactly one link does.
+    pub context: Option<String>,
+}
+
+/// Returns the [`ScriptLine`]s of a [`Tree`], in the order its nodes are stored and their lines are spoken.
+///
+/// # Arguments
+///
+/// * `tree` - The [`Tree`] to extract lines from.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{script, Line, Link, Node, Tree};
+/// let mut tree = Tree::new();
+/// let mut start = Node::new("start", "Hello.");
+/// start.links.push(Link::new("gate", "Open the gate!"));
+/// tree.insert_node(start);
+/// let mut gate = Node::new("gate", "");
+/// gate.set_lines(vec![Line::new("guard", "Halt!"), Line::new("captain", "Let them through.")]);
+/// tree.insert_node(gate);
+///
+/// let lines = script::extract(&tree);
+/// assert_eq!(2, lines.len());
+/// assert_eq!(Some("Open the gate!"), lines[0].context.as_deref());
+/// assert_eq!(Some("guard: Halt!"), lines[1].context.as_deref());
+/// ```
+pub fn extract(tree: &Tree) -> Vec<ScriptLine> {
+    extract_with(tree, |key| key.to_owned())
+}
+
+/// Returns the [`ScriptLine`]s of every [`Tree`] of a [`Project`], in the order its files are listed. Node keys are qualified as `tree/node`.
+///
+/// # Arguments
+///
+/// * `project` - The [`Project`] to extract lines from.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{project::Project, script};
+/// let project = Project::load("examples/convo.project.yml").unwrap();
+/// let lines = script::extract_project(&project);
+/// assert!(lines.iter().all(|line| line.node_key.contains('/')));
+/// ```
+#[cfg(feature = "std")]
+pub fn extract_project(project: &Project) -> Vec<ScriptLine> {
+    project
+        .trees()
+        .flat_map(|(name, tree)| extract_with(tree, |key| format!("{}/{}", name, key)))
+        .collect()
+}
+
+fn extract_with<F>(tree: &Tree, qualify: F) -> Vec<ScriptLine>
+where
+    F: Fn(&str) -> String,
+{
+    let mut script = Vec::new();
+    for node in tree.nodes().filter(|node| !node.lines.is_empty()) {
+        // The first line follows whatever the player chose to get here, if there is only one way in
+        let mut links = tree
+            .nodes()
+            .flat_map(|parent| &parent.links)
+            .filter(|link| link.to_key == node.key);
+        let mut context = match (links.next(), links.next()) {
+            (Some(link), None) => link.dialogue.clone(),
+            _ => None,
+        };
+        for (index, line) in node.lines.iter().enumerate() {
+            script.push(ScriptLine {
+                node_key: qualify(&node.key),
+                node_id: node.id,
+                index,
+                speaker: line.speaker.clone(),
+                text: line.text.clone(),
+                emotion: line.emotion.clone(),
+                tags: node.tags.clone(),
+                context: context.take(),
+            });
+            context = Some(format!("{}: {}", line.speaker, line.text));
+        }
+    }
+    script
+}
+
+/// Returns [`ScriptLine`]s grouped by speaker, keeping the order of each speaker's lines.
+///
+/// # Arguments
+///
+/// * `lines` - The [`ScriptLine`]s to group.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{script, Line, Node, Tree};
+/// let mut tree = Tree::new();
+/// let mut node = Node::new("start", "");
+/// node.set_lines(vec![Line::new("guard", "Halt!"), Line::new("captain", "Hold."), Line::new("guard", "Yes, sir.")]);
+/// tree.insert_node(node);
+///
+/// let speakers = script::by_speaker(script::extract(&tree));
+/// assert_eq!(vec!["captain", "guard"], speakers.keys().collect::<Vec<_>>());
+/// assert_eq!(2, speakers["guard"].len());
+/// ```
+pub fn by_speaker(lines: Vec<ScriptLine>) -> BTreeMap<String, Vec<ScriptLine>> {
+    let mut speakers: BTreeMap<String, Vec<ScriptLine>> = BTreeMap::new();
+    for line in lines {
+        speakers.entry(line.speaker.clone()).or_default().push(line);
+    }
+    speakers
+}
+
+/// Returns [`ScriptLine`]s as CSV, with a header row. Speakers are named by their display names in a [`Characters`] registry, or by id if they are not registered. Group lines with [`by_speaker`] first to write one file per character.
+///
+/// # Arguments
+///
+/// * `lines` - The [`ScriptLine`]s to export.
+/// * `characters` - The [`Characters`] registry which names speakers.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{characters::{Character, Characters}, script, Line, Node, Tree};
+/// let mut tree = Tree::new();
+/// let mut node = Node::new("start", "");
+/// node.set_lines(vec![Line::new("guard", "Halt, traveler!")]);
+/// tree.insert_node(node);
+///
+/// let mut characters = Characters::new();
+/// characters.insert(Character::new("guard", "Gate Guard"));
+/// let csv = script::to_csv(&script::extract(&tree), &characters);
+/// assert_eq!(
+///     "speaker,character,node,line,context,text\nguard,Gate Guard,start,1,,\"Halt, traveler!\"\n",
+///     csv
+/// );
+/// ```
+pub fn to_csv(lines: &[ScriptLine], characters: &Characters) -> String {
+    let mut csv = "speaker,character,node,line,context,text\n".to_owned();
+    for line in lines {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{}\n",
+            csv_field(&line.speaker),
+            csv_field(characters.display_name(&line.speaker)),
+            csv_field(&line.node_key),
+            line.index + 1,
+            csv_field(line.context.as_deref().unwrap_or_default()),
+            csv_field(&line.text)
+        ));
+    }
+    csv
+}
+
+/// Returns [`ScriptLine`]s as a Markdown document, with a section per speaker holding a table of their lines. Sections are titled by display names in a [`Characters`] registry, or by id if speakers are not registered, and list the default voice of characters which have one.
+///
+/// # Arguments
+///
+/// * `lines` - The [`ScriptLine`]s to export.
+/// * `characters` - The [`Characters`] registry which names speakers.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{characters::Characters, script, Line, Node, Tree};
+/// let mut tree = Tree::new();
+/// let mut node = Node::new("start", "");
+/// node.set_lines(vec![Line::new("guard", "Halt!")]);
+/// tree.insert_node(node);
+///
+/// let markdown = script::to_markdown(&script::extract(&tree), &Characters::new());
+/// assert!(markdown.starts_with("# guard\n"));
+/// assert!(markdown.contains("| start | 1 |  | Halt! |"));
+/// ```
+pub fn to_markdown(lines: &[ScriptLine], characters: &Characters) -> String {
+    let mut speakers: BTreeMap<&str, Vec<&ScriptLine>> = BTreeMap::new();
+    for line in lines {
+        speakers.entry(&line.speaker).or_default().push(line);
+    }
+
+    let mut markdown = String::new();
+    for (speaker, lines) in speakers {
+        if !markdown.is_empty() {
+            markdown.push('\n');
+        }
+        let name = characters.display_name(speaker);
+        match name == speaker {
+            true => markdown.push_str(&format!("# {}\n\n", name)),
+            false => markdown.push_str(&format!("# {} (`{}`)\n\n", name, speaker)),
+        }
+        if let Some(voice) = characters.get(speaker).and_then(|c| c.voice.as_ref()) {
+            markdown.push_str(&format!("Voice: {}\n\n", voice));
+        }
+        markdown.push_str("| Node | Line | Context | Text |\n| --- | --- | --- | --- |\n");
+        for line in lines {
+            markdown.push_str(&format!(
+                "| {} | {} | {} | {} |\n",
+                markdown_cell(&line.node_key),
+                line.index + 1,
+                markdown_cell(line.context.as_deref().unwrap_or_default()),
+                markdown_cell(&line.text)
+            ));
+        }
+    }
+    markdown
+}
+
+/// Returns [`ScriptLine`]s as JSON Lines, with one object per line holding its `id` (the stable id of its node, or `null`), `node`, `line` (starting at 1), `speaker`, `text`, `emotion` (or `null`), and `tags`, e.g. to train speech or dialogue summarization models on annotated lines which trace back to their nodes.
+///
+/// # Arguments
+///
+/// * `lines` - The [`ScriptLine`]s to export.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{script, Line, Node, Tree};
+/// let mut tree = Tree::new();
+/// let mut node = Node::new("start", "");
+/// node.id = Some(7);
+/// node.tags.push("act_1".into());
+/// let mut line = Line::new("guard", "Halt!");
+/// line.emotion = Some("angry".into());
+/// node.set_lines(vec![line]);
+/// tree.insert_node(node);
+///
+/// assert_eq!(
+///     "{\"emotion\":\"angry\",\"id\":7,\"line\":1,\"node\":\"start\",\"speaker\":\"guard\",\"tags\":[\"act_1\"],\"text\":\"Halt!\"}\n",
+///     script::to_jsonl(&script::extract(&tree))
+/// );
+/// ```
+#[cfg(feature = "json")]
+pub fn to_jsonl(lines: &[ScriptLine]) -> String {
+    let mut jsonl = String::new();
+    for line in lines {
+        let object = json!({
+            "id": line.node_id,
+            "node": line.node_key,
+            "line": line.index + 1,
+            "speaker": line.speaker,
+            "text": line.text,
+            "emotion": line.emotion,
+            "tags": line.tags,
+        });
+        jsonl.push_str(&object.to_string());
+        jsonl.push('\n');
+    }
+    jsonl
+}
+
+fn markdown_cell(text: &str) -> String {
+    // Table cells can't hold pipes or line breaks
+    text.replace('|', "\\|")
+        .lines()
+        .collect::<Vec<&str>>()
+        .join("<br>")
+}
+
+#[cfg(test)]
+#[test]
+fn test_extract() {
+    use crate::{
+        link::Link,
+        node::{Line, Node},
+    };
+
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "No speaker here.");
+    start.links.push(Link::new("gate", "Open up!"));
+    start.links.push(Link::new("yard", "Wait."));
+    tree.insert_node(start);
+    let mut gate = Node::new("gate", "");
+    gate.set_lines(vec![
+        Line::new("guard", "Halt!"),
+        Line::new("captain", "Hold."),
+    ]);
+    gate.links.push(Link::new("yard", "Onwards."));
+    tree.insert_node(gate);
+    let mut yard = Node::new("yard", "");
+    yard.set_lines(vec![Line::new("guard", "Move along.")]);
+    tree.insert_node(yard);
+
+    let lines = extract(&tree);
+    assert_eq!(3, lines.len());
+    assert_eq!(
+        ScriptLine {
+            node_key: "gate".to_owned(),
+            node_id: None,
+            index: 0,
+            speaker: "guard".to_owned(),
+            text: "Halt!".to_owned(),
+            emotion: None,
+            tags: vec![],
+            context: Some("Open up!".to_owned()),
+        },
+        lines[0]
+    );
+    assert_eq!(1, lines[1].index);
+    assert_eq!(Some("guard: Halt!"), lines[1].context.as_deref());
+
+    // Nodes reached by several links have no single preceding line
+    assert_eq!("yard", lines[2].node_key);
+    assert_eq!(None, lines[2].context);
+
+    let speakers = by_speaker(lines);
+    assert_eq!(2, speakers.len());
+    let texts: Vec<&str> = speakers["guard"]
+        .iter()
+        .map(|line| line.text.as_str())
+        .collect();
+    assert_eq!(vec!["Halt!", "Move along."], texts);
+}
+
+#[test]
+fn test_to_csv_markdown_and_jsonl() {
+    use crate::characters::Character;
+
+    let lines = [
+        ScriptLine {
+            node_key: "a/start".to_owned(),
+            node_id: Some(1),
+            index: 0,
+            speaker: "guard".to_owned(),
+            text: "Halt!\nWho | goes there?".to_owned(),
+            emotion: Some("stern".to_owned()),
+            tags: vec!["gate".to_owned()],
+            context: Some("Hello, \"friend\".".to_owned()),
+        },
+        ScriptLine {
+            node_key: "a/end".to_owned(),
+            node_id: None,
+            index: 2,
+            speaker: "local".to_owned(),
+            text: "Bye.".to_owned(),
+            emotion: None,
+            tags: vec![],
+            context: None,
+        },
+    ];
+    let mut characters = Characters::new();
+    let mut guard = Character::new("guard", "Gate Guard");
+    guard.voice = Some("gruff".to_owned());
+    characters.insert(guard);
+
+    assert_eq!(
+        "speaker,character,node,line,context,text\nguard,Gate Guard,a/start,1,\"Hello, \"\"friend\"\".\",\"Halt!\nWho | goes there?\"\nlocal,local,a/end,3,,Bye.\n",
+        to_csv(&lines, &characters)
+    );
+    assert_eq!(
+        "# Gate Guard (`guard`)\n\nVoice: gruff\n\n| Node | Line | Context | Text |\n| --- | --- | --- | --- |\n| a/start | 1 | Hello, \"friend\". | Halt!<br>Who \\| goes there? |\n\n# local\n\n| Node | Line | Context | Text |\n| --- | --- | --- | --- |\n| a/end | 3 |  | Bye. |\n",
+        to_markdown(&lines, &characters)
+    );
+    #[cfg(feature = "json")]
+    assert_eq!(
+        "{\"emotion\":\"stern\",\"id\":1,\"line\":1,\"node\":\"a/start\",\"speaker\":\"guard\",\"tags\":[\"gate\"],\"text\":\"Halt!\\nWho | goes there?\"}\n{\"emotion\":null,\"id\":null,\"line\":3,\"node\":\"a/end\",\"speaker\":\"local\",\"tags\":[],\"text\":\"Bye.\"}\n",
+        to_jsonl(&lines)
+    );
+}
@@ -0,0 +1,782 @@
+//! A small [JSON-RPC 2.0](https://www.jsonrpc.org/specification) server over HTTP which lets remote clients walk a [`Tree`].
+//!
+//! Every request is an HTTP `POST` whose body is a JSON-RPC request. The following methods are supported:
+//!
+//! | Method | Params | Result |
+//! | --- | --- | --- |
+//! | `start` | *none* | `{ "session": <id>, "node": <node> }` |
+//! | `current` | `{ "session": <id> }` | `<node>` |
+//! | `choose` | `{ "session": <id>, "choice": <index> }` | `<node>` |
+//! | `end` | `{ "session": <id> }` | `null` |
+//!
+//! A session `<id>` is a string of 32 hexadecimal digits, drawn at random from the operating system, so clients cannot guess the sessions of other clients.
+//!
+//! Requests must give `"jsonrpc": "2.0"`. Notifications, i.e. requests without an `id`, are carried out but not answered, so their HTTP response is `204 No Content`.
+//!
+//! Request bodies larger than [`Server::set_max_body_size`] are answered with `413 Payload Too Large`, connections which take longer than [`Server::set_timeout`] in all are dropped, and at most [`Server::set_max_sessions`] sessions are kept, evicting the least recently used session when a new one starts.
+//!
+//! A `<node>` is an object of the form `{ "key": <string>, "dialogue": <string>, "lines": [{ "speaker": <string>, "text": <string> }], "choices": [{ "to": <string>, "dialogue": <string>, "group": <string or null> }], "finished": <bool> }`, where lines are empty unless the node's dialogue is a sequence, as by [`Walker::lines`], and choices are ordered as by [`Walker::choices`].
+
+use crate::{
+    error::TreeError,
+    filter::{ContentFilter, FilterHandle},
+    tree::Tree,
+    walker::Walker,
+};
+
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    time::{Duration, Instant},
+};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+const TREE_ERROR: i64 = -32000;
+
+// The largest request line and headers read, in bytes
+const MAX_HEAD_SIZE: u64 = 8 * 1024;
+
+/// A [`Server`] serves conversation sessions over a borrowed [`Tree`]. Each session is an independent [`Walker`].
+#[derive(Debug)]
+pub struct Server<'a> {
+    tree: &'a Tree,
+    sessions: HashMap<String, Session<'a>>,
+    filter: Option<FilterHandle>,
+    max_body_size: usize,
+    max_sessions: usize,
+    timeout: Option<Duration>,
+
+    /// Counts requests to sessions, so the least recently used session can be evicted.
+    clock: u64,
+}
+
+#[derive(Debug)]
+struct Session<'a> {
+    walker: Walker<&'a Tree>,
+    last_used: u64,
+}
+
+impl<'a> Server<'a> {
+    /// Returns a [`Server`] with no sessions.
+    ///
+    /// # Arguments
+    ///
+    /// * `tree` - A [`Tree`] which sessions will walk.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{server::Server, Tree};
+    /// let tree = Tree::new();
+    /// let server = Server::new(&tree);
+    /// ```
+    pub fn new(tree: &'a Tree) -> Self {
+        Server {
+            tree,
+            sessions: HashMap::new(),
+            filter: None,
+            max_body_size: 64 * 1024,
+            max_sessions: 1024,
+            timeout: Some(Duration::from_secs(5)),
+            clock: 0,
+        }
+    }
+
+    /// Set the largest request body accepted, in bytes. Larger requests are answered with `413 Payload Too Large`. The default is 64 KiB.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - The largest body size, in bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{server::Server, Tree};
+    /// let tree = Tree::new();
+    /// let mut server = Server::new(&tree);
+    /// server.set_max_body_size(1024);
+    /// ```
+    pub fn set_max_body_size(&mut self, size: usize) {
+        self.max_body_size = size;
+    }
+
+    /// Set the most sessions kept at once. When a session starts and the limit is reached, the least recently used session is ended. The default is 1024.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - The most sessions kept, at least 1.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{server::Server, Tree};
+    /// let tree = Tree::new();
+    /// let mut server = Server::new(&tree);
+    /// server.set_max_sessions(16);
+    /// ```
+    pub fn set_max_sessions(&mut self, count: usize) {
+        self.max_sessions = count.max(1);
+    }
+
+    /// Set how long [`Server::serve`] spends on a connection in all, reading its request and writing its response, before dropping it, or [`None`] to wait forever. The default is 5 seconds.
+    ///
+    /// Since connections are handled one at a time, this bounds how long a slow client can keep others waiting.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - The time limit of each connection.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{server::Server, Tree};
+    /// use std::time::Duration;
+    /// let tree = Tree::new();
+    /// let mut server = Server::new(&tree);
+    /// server.set_timeout(Some(Duration::from_secs(1)));
+    /// ```
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    /// Set a [`ContentFilter`] which screens the dialogue sent to sessions started afterwards. See [`Walker::set_filter`].
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` - The [`ContentFilter`] to apply, replacing any previous filter.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{filter::MaskWords, server::Server, Tree};
+    /// let tree = Tree::new();
+    /// let mut server = Server::new(&tree);
+    /// server.set_filter(MaskWords::new(&["darn"], '*'));
+    /// ```
+    pub fn set_filter<F>(&mut self, filter: F)
+    where
+        F: ContentFilter + 'static,
+    {
+        self.filter = Some(FilterHandle::new(filter));
+    }
+
+    /// Try to serve HTTP requests on an address, forever. Connections are handled one at a time, and dropped when they take longer than [`Server::set_timeout`].
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - An address to listen on.
+    ///
+    /// # Errors
+    ///
+    /// * An [`io::Error`] will be returned if the address cannot be bound.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use convo::{importer, server::Server};
+    /// let tree = importer::import("examples/dialogue_files/ex_1.convo.yml").unwrap();
+    /// Server::new(&tree).serve("127.0.0.1:8080").unwrap();
+    /// ```
+    pub fn serve<A>(&mut self, addr: A) -> io::Result<()>
+    where
+        A: ToSocketAddrs,
+    {
+        let listener = TcpListener::bind(addr)?;
+        for stream in listener.incoming() {
+            // A broken connection should not take the server down
+            let _ = stream.and_then(|stream| {
+                let deadline = self.timeout.map(|timeout| Instant::now() + timeout);
+                self.handle_connection(&mut Deadline { stream, deadline })
+            });
+        }
+        Ok(())
+    }
+
+    /// Try to handle a single HTTP request on a stream, writing the HTTP response back to it.
+    ///
+    /// Requests with a body larger than [`Server::set_max_body_size`] are answered with `413 Payload Too Large`, requests whose body is shorter than their `Content-Length` with `400 Bad Request`, and notifications with `204 No Content`.
+    ///
+    /// # Arguments
+    ///
+    /// * `stream` - A stream holding an HTTP request.
+    ///
+    /// # Errors
+    ///
+    /// * An [`io::Error`] will be returned if the stream cannot be read or written.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{server::Server, Tree};
+    /// use std::io::Cursor;
+    /// let tree = Tree::new();
+    /// let mut server = Server::new(&tree);
+    /// let mut stream = Cursor::new(b"GET / HTTP/1.1\r\n\r\n".to_vec());
+    /// server.handle_connection(&mut stream).unwrap();
+    /// ```
+    pub fn handle_connection<S>(&mut self, stream: &mut S) -> io::Result<()>
+    where
+        S: Read + Write,
+    {
+        let mut reader = BufReader::new(&mut *stream);
+
+        // Read the request line and headers
+        let mut head = (&mut reader).take(MAX_HEAD_SIZE);
+        let mut request_line = String::new();
+        head.read_line(&mut request_line)?;
+        let mut content_length = Some(0);
+        loop {
+            let mut header = String::new();
+            if head.read_line(&mut header)? == 0 || header.trim().is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                if name.trim().eq_ignore_ascii_case("content-length") {
+                    content_length = value.trim().parse().ok();
+                }
+            }
+        }
+
+        // Only JSON-RPC requests are accepted
+        let (status, body) = match content_length {
+            _ if !request_line.starts_with("POST ") => ("405 Method Not Allowed", String::new()),
+            None => ("400 Bad Request", String::new()),
+            Some(length) if length > self.max_body_size => ("413 Payload Too Large", String::new()),
+            Some(length) => {
+                let mut body = vec![0; length];
+                match reader.read_exact(&mut body) {
+                    Ok(()) => match self.handle(&String::from_utf8_lossy(&body)) {
+                        Some(response) => ("200 OK", response),
+                        None => ("204 No Content", String::new()),
+                    },
+                    Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                        ("400 Bad Request", String::new())
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+        };
+
+        write!(
+            stream,
+            "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        )?;
+        stream.flush()
+    }
+
+    /// Returns the JSON-RPC response to a JSON-RPC request, or [`None`] if the request is a notification, i.e. it has no `id`.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - A string slice that holds a JSON-RPC request or notification.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{server::Server, Node, Tree};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let mut server = Server::new(&tree);
+    /// let response = server.handle(r#"{"jsonrpc": "2.0", "method": "start", "id": 1}"#);
+    /// assert!(response.unwrap().contains("Hello!"));
+    /// assert!(server.handle(r#"{"jsonrpc": "2.0", "method": "start"}"#).is_none());
+    /// ```
+    pub fn handle(&mut self, request: &str) -> Option<String> {
+        let request: Value = match serde_json::from_str(request) {
+            Ok(request) => request,
+            Err(e) => return Some(error_response(Value::Null, PARSE_ERROR, e.to_string())),
+        };
+        let id = request.get("id").cloned();
+        if request.get("jsonrpc").and_then(Value::as_str) != Some("2.0") {
+            return Some(error_response(
+                id.unwrap_or(Value::Null),
+                INVALID_REQUEST,
+                "Missing `\"jsonrpc\": \"2.0\"`".into(),
+            ));
+        }
+        let method = match request.get("method").and_then(Value::as_str) {
+            Some(method) => method,
+            None => {
+                return Some(error_response(
+                    id.unwrap_or(Value::Null),
+                    INVALID_REQUEST,
+                    "Missing method".into(),
+                ))
+            }
+        };
+        let params = request.get("params").cloned().unwrap_or(Value::Null);
+
+        let result = match method {
+            "start" => self.start(),
+            "current" => self.current(&params),
+            "choose" => self.choose(&params),
+            "end" => self.end(&params),
+            _ => Err((METHOD_NOT_FOUND, format!("Unknown method `{}`", method))),
+        };
+
+        // Requests are answered, and notifications are not
+        let id = id?;
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "result": result, "id": id }).to_string(),
+            Err((code, message)) => error_response(id, code, message),
+        })
+    }
+
+    fn start(&mut self) -> Result<Value, (i64, String)> {
+        let mut walker = Walker::new(self.tree).map_err(tree_error)?;
+        walker.set_filter_handle(self.filter.clone());
+        let session = session_token()?;
+        let node = node_to_json(&walker);
+
+        // Make room by ending the least recently used session
+        if self.sessions.len() >= self.max_sessions {
+            if let Some(oldest) = self
+                .sessions
+                .iter()
+                .min_by_key(|(_, session)| session.last_used)
+                .map(|(id, _)| id.clone())
+            {
+                self.sessions.remove(&oldest);
+            }
+        }
+        self.clock += 1;
+        self.sessions.insert(
+            session.clone(),
+            Session {
+                walker,
+                last_used: self.clock,
+            },
+        );
+        Ok(json!({ "session": session, "node": node }))
+    }
+
+    fn current(&mut self, params: &Value) -> Result<Value, (i64, String)> {
+        let walker = self.session(params)?;
+        Ok(node_to_json(walker))
+    }
+
+    fn choose(&mut self, params: &Value) -> Result<Value, (i64, String)> {
+        let choice = params
+            .get("choice")
+            .and_then(Value::as_u64)
+            .ok_or_else(|| {
+                (
+                    INVALID_PARAMS,
+                    "Missing unsigned integer `choice`".to_owned(),
+                )
+            })?;
+        let walker = self.session(params)?;
+        walker.choose(choice as usize).map_err(tree_error)?;
+        Ok(node_to_json(walker))
+    }
+
+    fn end(&mut self, params: &Value) -> Result<Value, (i64, String)> {
+        let session = session_id(params)?;
+        self.sessions
+            .remove(session)
+            .map(|_| Value::Null)
+            .ok_or_else(|| (INVALID_PARAMS, format!("Unknown session `{}`", session)))
+    }
+
+    fn session(&mut self, params: &Value) -> Result<&mut Walker<&'a Tree>, (i64, String)> {
+        let session = session_id(params)?;
+        self.clock += 1;
+        let clock = self.clock;
+        self.sessions
+            .get_mut(session)
+            .map(|session| {
+                session.last_used = clock;
+                &mut session.walker
+            })
+            .ok_or_else(|| (INVALID_PARAMS, format!("Unknown session `{}`", session)))
+    }
+}
+
+fn session_id(params: &Value) -> Result<&str, (i64, String)> {
+    params
+        .get("session")
+        .and_then(Value::as_str)
+        .ok_or_else(|| (INVALID_PARAMS, "Missing string `session`".to_owned()))
+}
+
+// Returns a random 128-bit session id in hexadecimal, which cannot be guessed from other ids
+fn session_token() -> Result<String, (i64, String)> {
+    let mut bytes = [0u8; 16];
+    getrandom::fill(&mut bytes).map_err(|e| {
+        (
+            INTERNAL_ERROR,
+            format!("Could not generate a session id: {}", e),
+        )
+    })?;
+    Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+fn node_to_json(walker: &Walker<&Tree>) -> Value {
+    let node = walker.current_node();
+    let choices: Vec<Value> = walker
+        .choices()
+        .iter()
+        .zip(walker.choice_dialogues())
+        .map(|(link, dialogue)| {
+            json!({ "to": link.to_key, "dialogue": dialogue, "group": link.group })
+        })
+        .collect();
+    let lines: Vec<Value> = walker
+        .lines()
+        .into_iter()
+        .map(|(speaker, text)| json!({ "speaker": speaker, "text": text }))
+        .collect();
+    json!({
+        "key": node.key,
+        "dialogue": walker.dialogue(),
+        "lines": lines,
+        "choices": choices,
+        "finished": walker.is_finished(),
+    })
+}
+
+fn tree_error(error: TreeError) -> (i64, String) {
+    (TREE_ERROR, format!("{:?}", error))
+}
+
+// A TCP stream which fails reads and writes once its deadline passes, however slowly the client sends
+struct Deadline {
+    stream: TcpStream,
+    deadline: Option<Instant>,
+}
+
+impl Deadline {
+    // Returns the time left, or an error once none is left
+    fn remaining(&self) -> io::Result<Option<Duration>> {
+        match self.deadline {
+            None => Ok(None),
+            Some(deadline) => deadline
+                .checked_duration_since(Instant::now())
+                .filter(|remaining| !remaining.is_zero())
+                .map(Some)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::TimedOut, "connection took too long")),
+        }
+    }
+}
+
+impl Read for Deadline {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.remaining()?;
+        self.stream.set_read_timeout(remaining)?;
+        self.stream.read(buf)
+    }
+}
+
+impl Write for Deadline {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let remaining = self.remaining()?;
+        self.stream.set_write_timeout(remaining)?;
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+fn error_response(id: Value, code: i64, message: String) -> String {
+    json!({
+        "jsonrpc": "2.0",
+        "error": { "code": code, "message": message },
+        "id": id,
+    })
+    .to_string()
+}
+
+#[cfg(test)]
+fn test_tree() -> Tree {
+    use crate::{link::Link, node::Node};
+
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "Hello!");
+    let end = Node::new("end", "Goodbye!");
+    Link::link(&mut start, &end, "Bye!");
+    tree.insert_node(start);
+    tree.insert_node(end);
+    tree.set_root_key("start").unwrap();
+    tree
+}
+
+#[test]
+fn test_handle() {
+    let tree = test_tree();
+    let mut server = Server::new(&tree);
+    let mut call = |request: &str| -> Value {
+        serde_json::from_str(&server.handle(request).unwrap()).unwrap()
+    };
+
+    // Start a session
+    let response = call(r#"{"jsonrpc": "2.0", "method": "start", "id": 1}"#);
+    assert_eq!(1, response["id"]);
+    let session = response["result"]["session"].clone();
+    assert_eq!("start", response["result"]["node"]["key"]);
+    assert_eq!("Bye!", response["result"]["node"]["choices"][0]["dialogue"]);
+
+    // Get the current node
+    let response = call(
+        &json!({ "jsonrpc": "2.0", "method": "current", "params": { "session": session }, "id": 2 })
+            .to_string(),
+    );
+    assert_eq!("Hello!", response["result"]["dialogue"]);
+
+    // Make a choice
+    let response = call(
+        &json!({ "jsonrpc": "2.0", "method": "choose", "params": { "session": session, "choice": 0 }, "id": 3 })
+            .to_string(),
+    );
+    assert_eq!("end", response["result"]["key"]);
+    assert_eq!(true, response["result"]["finished"]);
+
+    // End the session
+    let response = call(
+        &json!({ "jsonrpc": "2.0", "method": "end", "params": { "session": session }, "id": 4 })
+            .to_string(),
+    );
+    assert_eq!(Value::Null, response["result"]);
+}
+
+#[test]
+fn test_handle_errors() {
+    let tree = test_tree();
+    let mut server = Server::new(&tree);
+    let mut call = |request: &str| -> Value {
+        serde_json::from_str(&server.handle(request).unwrap()).unwrap()
+    };
+
+    // Invalid JSON
+    assert_eq!(PARSE_ERROR, call("not json")["error"]["code"]);
+
+    // Unknown method
+    let response = call(r#"{"jsonrpc": "2.0", "method": "dance", "id": 1}"#);
+    assert_eq!(METHOD_NOT_FOUND, response["error"]["code"]);
+
+    // Unknown session
+    let response =
+        call(r#"{"jsonrpc": "2.0", "method": "current", "params": {"session": "7"}, "id": 2}"#);
+    assert_eq!(INVALID_PARAMS, response["error"]["code"]);
+
+    // Invalid choice
+    let session =
+        call(r#"{"jsonrpc": "2.0", "method": "start", "id": 3}"#)["result"]["session"].clone();
+    let response = call(
+        &json!({ "jsonrpc": "2.0", "method": "choose", "params": { "session": session, "choice": 9 }, "id": 4 })
+            .to_string(),
+    );
+    assert_eq!(TREE_ERROR, response["error"]["code"]);
+
+    // Missing or unsupported version
+    let response = call(r#"{"method": "start", "id": 5}"#);
+    assert_eq!(INVALID_REQUEST, response["error"]["code"]);
+    assert_eq!(5, response["id"]);
+    let response = call(r#"{"jsonrpc": "1.0", "method": "start", "id": 6}"#);
+    assert_eq!(INVALID_REQUEST, response["error"]["code"]);
+}
+
+#[test]
+fn test_notifications() {
+    use std::io::Cursor;
+
+    let tree = test_tree();
+    let mut server = Server::new(&tree);
+
+    // Should carry out notifications without answering them
+    assert!(server
+        .handle(r#"{"jsonrpc": "2.0", "method": "start"}"#)
+        .is_none());
+    assert_eq!(1, server.sessions.len());
+    assert!(server
+        .handle(r#"{"jsonrpc": "2.0", "method": "dance"}"#)
+        .is_none());
+
+    // Should answer notifications over HTTP without a body
+    let body = r#"{"jsonrpc": "2.0", "method": "start"}"#;
+    let request = format!(
+        "POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}",
+        body.len(),
+        body
+    );
+    let mut stream = Cursor::new(request.into_bytes());
+    server.handle_connection(&mut stream).unwrap();
+    let response = String::from_utf8(stream.into_inner()).unwrap();
+    assert!(response.contains("HTTP/1.1 204 No Content"));
+    assert!(response.ends_with("\r\n\r\n"));
+}
+
+#[test]
+fn test_deadline() {
+    use std::{net::TcpListener, thread, time::Duration};
+
+    let tree = test_tree();
+    let mut server = Server::new(&tree);
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    // A client which sends a byte at a time, each well within a read timeout
+    let client = thread::spawn(move || {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        for byte in b"POST / HTTP/1.1\r\nContent-Length: 2\r\n\r\n{}" {
+            if stream.write_all(&[*byte]).is_err() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+    });
+
+    // Should be dropped once the connection takes longer than the timeout in all
+    let (stream, _) = listener.accept().unwrap();
+    let started = Instant::now();
+    let deadline = Some(started + Duration::from_millis(100));
+    let error = server
+        .handle_connection(&mut Deadline { stream, deadline })
+        .unwrap_err();
+    assert!(matches!(
+        error.kind(),
+        io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock
+    ));
+    assert!(started.elapsed() < Duration::from_millis(500));
+    client.join().unwrap();
+}
+
+#[test]
+fn test_session_ids() {
+    let tree = test_tree();
+    let mut server = Server::new(&tree);
+    let mut start = || -> u128 {
+        let response: Value = serde_json::from_str(
+            &server
+                .handle(r#"{"jsonrpc": "2.0", "method": "start", "id": 1}"#)
+                .unwrap(),
+        )
+        .unwrap();
+        let session = response["result"]["session"].as_str().unwrap();
+        assert_eq!(32, session.len());
+        u128::from_str_radix(session, 16).unwrap()
+    };
+
+    // Should not hand out sequential ids
+    let first = start();
+    let second = start();
+    assert_ne!(first, second);
+    assert_ne!(first.wrapping_add(1), second);
+}
+
+#[test]
+fn test_handle_connection() {
+    use std::io::Cursor;
+
+    let tree = test_tree();
+    let mut server = Server::new(&tree);
+
+    // Should answer a JSON-RPC request
+    let body = r#"{"jsonrpc": "2.0", "method": "start", "id": 1}"#;
+    let request = format!(
+        "POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}",
+        body.len(),
+        body
+    );
+    let mut stream = Cursor::new(request.into_bytes());
+    server.handle_connection(&mut stream).unwrap();
+    let response = String::from_utf8(stream.into_inner()).unwrap();
+    assert!(response.contains("HTTP/1.1 200 OK"));
+    assert!(response.contains(r#""key":"start""#));
+
+    // Should reject anything else
+    let mut stream = Cursor::new(b"GET / HTTP/1.1\r\n\r\n".to_vec());
+    server.handle_connection(&mut stream).unwrap();
+    let response = String::from_utf8(stream.into_inner()).unwrap();
+    assert!(response.contains("HTTP/1.1 405 Method Not Allowed"));
+}
+
+#[test]
+fn test_handle_connection_limits() {
+    use std::io::Cursor;
+
+    let tree = test_tree();
+    let mut server = Server::new(&tree);
+    server.set_max_body_size(64);
+
+    // Should reject a body larger than the limit without reading it
+    let request = "POST / HTTP/1.1\r\nContent-Length: 18446744073709551615\r\n\r\n";
+    let mut stream = Cursor::new(request.as_bytes().to_vec());
+    server.handle_connection(&mut stream).unwrap();
+    let response = String::from_utf8(stream.into_inner()).unwrap();
+    assert!(response.contains("HTTP/1.1 413 Payload Too Large"));
+
+    // Should reject a body shorter than its length
+    let body = r#"{"jsonrpc": "2.0", "method": "start", "id": 1}"#;
+    let request = format!(
+        "POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}",
+        body.len(),
+        &body[..10]
+    );
+    let mut stream = Cursor::new(request.into_bytes());
+    server.handle_connection(&mut stream).unwrap();
+    let response = String::from_utf8(stream.into_inner()).unwrap();
+    assert!(response.contains("HTTP/1.1 400 Bad Request"));
+    assert!(server.sessions.is_empty());
+
+    // Should reject a length which is not a number
+    let request = "POST / HTTP/1.1\r\nContent-Length: many\r\n\r\n{}";
+    let mut stream = Cursor::new(request.as_bytes().to_vec());
+    server.handle_connection(&mut stream).unwrap();
+    let response = String::from_utf8(stream.into_inner()).unwrap();
+    assert!(response.contains("HTTP/1.1 400 Bad Request"));
+}
+
+#[test]
+fn test_max_sessions() {
+    let tree = test_tree();
+    let mut server = Server::new(&tree);
+    server.set_max_sessions(2);
+    let mut call = |request: &str| -> Value {
+        serde_json::from_str(&server.handle(request).unwrap()).unwrap()
+    };
+    let start = r#"{"jsonrpc": "2.0", "method": "start", "id": 1}"#;
+    let current = |session: &Value| {
+        json!({ "jsonrpc": "2.0", "method": "current", "params": { "session": session }, "id": 2 })
+            .to_string()
+    };
+
+    // Should evict the least recently used session
+    let first = call(start)["result"]["session"].clone();
+    let second = call(start)["result"]["session"].clone();
+    call(&current(&first));
+    let third = call(start)["result"]["session"].clone();
+    let response = call(&current(&second));
+    assert_eq!(INVALID_PARAMS, response["error"]["code"]);
+    for session in [first, third] {
+        assert_eq!("start", call(&current(&session))["result"]["key"]);
+    }
+}
+
+#[test]
+fn test_filter() {
+    use crate::filter::MaskWords;
+
+    let tree = test_tree();
+    let mut server = Server::new(&tree);
+    server.set_filter(MaskWords::new(&["hello", "bye"], '*'));
+    let response: Value = serde_json::from_str(
+        &server
+            .handle(r#"{"jsonrpc": "2.0", "method": "start", "id": 1}"#)
+            .unwrap(),
+    )
+    .unwrap();
+    assert_eq!("*****!", response["result"]["node"]["dialogue"]);
+    assert_eq!("***!", response["result"]["node"]["choices"][0]["dialogue"]);
+}
@@ -0,0 +1,205 @@
+use alloc::sync::Arc;
+use core::ops::Deref;
+
+use crate::{error::TreeError, tree::Tree, walker::Walker};
+
+/// A [`SharedTree`] is a cheaply clonable, thread-safe handle to an immutable [`Tree`].
+///
+/// Walking never requires mutable access to a tree, so one [`SharedTree`] can be cloned across threads, each clone driving any number of independent [`Walker`]s.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{Node, SharedTree, Tree};
+/// use std::thread;
+///
+/// let mut tree = Tree::new();
+/// tree.insert_node(Node::new("start", "Hello!"));
+/// tree.set_root_key("start").unwrap();
+/// let shared = SharedTree::new(tree);
+///
+/// let handles: Vec<_> = (0..4)
+///     .map(|_| {
+///         let shared = shared.clone();
+///         thread::spawn(move || shared.walker().unwrap().current_node().dialogue.clone())
+///     })
+///     .collect();
+/// for handle in handles {
+///     assert_eq!("Hello!", handle.join().unwrap());
+/// }
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SharedTree(Arc<Tree>);
+
+impl SharedTree {
+    /// Returns a [`SharedTree`] which takes ownership of a [`Tree`].
+    ///
+    /// # Arguments
+    ///
+    /// * `tree` - A [`Tree`] to share.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{SharedTree, Tree};
+    /// let shared = SharedTree::new(Tree::new());
+    /// ```
+    pub fn new(tree: Tree) -> Self {
+        SharedTree(Arc::new(tree))
+    }
+
+    /// Try to return a [`Walker`] which owns a clone of this handle and starts at the root [`crate::Node`].
+    ///
+    /// # Errors
+    ///
+    /// * A [`TreeError`] will be returned if the tree has no root set or the root node does not exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Node, SharedTree, Tree};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let walker = SharedTree::new(tree).walker().unwrap();
+    /// assert_eq!("start", walker.current_key());
+    /// ```
+    pub fn walker(&self) -> Result<Walker<SharedTree>, TreeError> {
+        Walker::new(self.clone())
+    }
+
+    /// Edit the shared [`Tree`] copy-on-write, e.g. to apply a content hotfix to a live service.
+    ///
+    /// If other handles (including those owned by [`Walker`]s) still share the current version, the tree is cloned first and this handle moves to the new version. Existing handles keep their old snapshot untouched. The versions share their nodes, so only the nodes the closure edits are copied. If this handle is the only one, the tree is edited in place.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - A closure which edits the tree. Its result is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Node, SharedTree, Tree};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let mut shared = SharedTree::new(tree);
+    /// let walker = shared.walker().unwrap();
+    ///
+    /// shared.edit(|tree| tree.node_mut("start").unwrap().dialogue = "Hi!".to_owned());
+    /// assert_eq!("Hello!", walker.current_node().dialogue);
+    /// assert_eq!("Hi!", shared.walker().unwrap().current_node().dialogue);
+    /// ```
+    pub fn edit<F, R>(&mut self, f: F) -> R
+    where
+        F: FnOnce(&mut Tree) -> R,
+    {
+        f(Arc::make_mut(&mut self.0))
+    }
+}
+
+impl Deref for SharedTree {
+    type Target = Tree;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<Tree> for SharedTree {
+    fn from(tree: Tree) -> Self {
+        SharedTree::new(tree)
+    }
+}
+
+impl From<Arc<Tree>> for SharedTree {
+    fn from(tree: Arc<Tree>) -> Self {
+        SharedTree(tree)
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_shared_tree() {
+    use crate::{link::Link, node::Node};
+    use std::thread;
+
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<SharedTree>();
+    assert_send_sync::<Walker<SharedTree>>();
+
+    // start -> end
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "Hello!");
+    let end = Node::new("end", "Goodbye!");
+    Link::link(&mut start, &end, "Bye!");
+    tree.insert_node(start);
+    tree.insert_node(end);
+    tree.set_root_key("start").unwrap();
+    let shared = SharedTree::from(tree);
+
+    // Walkers on other threads are independent
+    let mut walker = shared.walker().unwrap();
+    let handle = thread::spawn(move || {
+        walker.choose(0).unwrap();
+        walker
+    });
+    let moved = handle.join().unwrap();
+    assert_eq!("end", moved.current_key());
+    assert_eq!("start", shared.walker().unwrap().current_key());
+
+    // Walkers do not copy the tree
+    assert!(core::ptr::eq(&*shared, moved.tree()));
+}
+
+#[test]
+fn test_edit() {
+    use crate::node::Node;
+
+    let mut tree = Tree::new();
+    tree.insert_node(Node::new("start", "Hello!"));
+    tree.set_root_key("start").unwrap();
+    let mut shared = SharedTree::new(tree);
+
+    // Unshared handles are edited in place
+    let before: *const Tree = &*shared;
+    shared.edit(|tree| tree.node_mut("start").unwrap().dialogue = "Hi!".to_owned());
+    assert!(core::ptr::eq(before, &*shared));
+
+    // Shared snapshots are left untouched
+    let walker = shared.walker().unwrap();
+    let snapshot = shared.clone();
+    let key = shared.edit(|tree| {
+        tree.insert_node(Node::new("new", "New!"));
+        tree.set_root_key("new").unwrap();
+        tree.root_key().cloned()
+    });
+    assert_eq!(Some("new".to_owned()), key);
+    assert_eq!("start", walker.current_key());
+    assert_eq!(1, walker.tree().len());
+    assert_eq!(1, snapshot.len());
+    assert_eq!("new", shared.walker().unwrap().current_key());
+    assert!(!core::ptr::eq(walker.tree(), &*shared));
+}
+
+#[test]
+fn test_edit_shares_nodes() {
+    use crate::node::Node;
+
+    let mut tree = Tree::new();
+    tree.insert_node(Node::new("start", "Hello!"));
+    tree.insert_node(Node::new("end", "Goodbye!"));
+    tree.set_root_key("start").unwrap();
+    let mut shared = SharedTree::new(tree);
+    let snapshot = shared.clone();
+
+    // Only the edited node is copied
+    shared.edit(|tree| tree.node_mut("start").unwrap().dialogue = "Hi!".to_owned());
+    assert_eq!("Hello!", snapshot.node("start").unwrap().dialogue);
+    assert_eq!("Hi!", shared.node("start").unwrap().dialogue);
+    assert!(!Arc::ptr_eq(
+        &snapshot.nodes["start"],
+        &shared.nodes["start"]
+    ));
+    assert!(Arc::ptr_eq(&snapshot.nodes["end"], &shared.nodes["end"]));
+}
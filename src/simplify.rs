@@ -0,0 +1,389 @@
+//! Transforms which simplify the shape of a [`Tree`] without changing what is said, e.g. to clean up content imported from other tools.
+//!
+//! [`merge_chains`] collapses chains of nodes which only lead on to the next, i.e. those a [`Walker`][`crate::Walker`] narrates through, into single nodes. [`split_sentences`] does the inverse, splitting a node into a chain with one sentence per node.
+
+use alloc::{borrow::ToOwned, collections::BTreeMap, string::String, vec, vec::Vec};
+
+use crate::{
+    error::TreeError,
+    keygen,
+    link::Link,
+    node::{Node, NodeKind},
+    tree::Tree,
+};
+
+/// Collapse every chain of nodes which lead on to the next through a single link into one node, whose dialogue is the dialogue of the chain joined by a separator, and return the keys of the removed nodes, mapped to the key of the node they were merged into.
+///
+/// A node is merged into the node before it when:
+/// * The node before it is a choice node which is not an ending, whose only link leads to it, and that link is silent or has empty dialogue, and has no condition, assignments, affinity adjustments, group, or tags. A single link with dialogue is a choice the player makes, so it is kept.
+/// * No other link leads to it, and it is not the root, an entry point, or the current node.
+/// * It has no tags, availability, or stage directions, and the same pacing as the node before it, and is marked [`todo`][`Node#structfield.todo`] only if the node before it is too.
+/// * Either both nodes have [`Line`][`crate::Line`]s, which are concatenated, or neither does.
+///
+/// The merged node keeps the key, id, tags, availability, and stage directions of the first node of the chain, and takes the kind, links, and ending of the last.
+///
+/// # Arguments
+///
+/// * `tree` - A [`Tree`] to simplify.
+/// * `separator` - A string slice that holds the separator between the dialogue of merged nodes, e.g. `" "` or `"\n"`.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{simplify, Link, Node, Tree};
+/// let mut tree = Tree::new();
+/// let mut start = Node::new("start", "The door creaks.");
+/// start.links.push(Link::silent("next"));
+/// tree.insert_node(start);
+/// tree.insert_node(Node::new("next", "Someone is here."));
+/// tree.set_root_key("start").unwrap();
+///
+/// let merged = simplify::merge_chains(&mut tree, " ");
+/// assert_eq!("start", merged["next"]);
+/// assert_eq!("The door creaks. Someone is here.", tree.node("start").unwrap().dialogue);
+/// assert!(!tree.contains_node("next"));
+/// ```
+pub fn merge_chains(tree: &mut Tree, separator: &str) -> BTreeMap<String, String> {
+    // Merging a node moves its links to the node before it, so the number of links to each node holds
+    let mut parents: BTreeMap<String, usize> = BTreeMap::new();
+    for link in tree.nodes().flat_map(|node| &node.links) {
+        *parents.entry(link.to_key.clone()).or_default() += 1;
+    }
+
+    let mut merged = BTreeMap::new();
+    let keys: Vec<String> = tree.nodes().map(|node| node.key.clone()).collect();
+    for key in keys {
+        // Nodes already merged into another are gone
+        while let Some(next_key) = tree
+            .node(&key)
+            .and_then(|node| mergeable(tree, node, &parents))
+        {
+            let next = tree
+                .remove_node(&next_key)
+                .expect("a mergeable node exists");
+            let node = tree.node_mut(&key).expect("the node merged into exists");
+            match node.lines.is_empty() {
+                true => match (node.dialogue.is_empty(), next.dialogue.is_empty()) {
+                    (_, true) => {}
+                    (true, false) => node.dialogue = next.dialogue,
+                    (false, false) => {
+                        node.dialogue.push_str(separator);
+                        node.dialogue.push_str(&next.dialogue);
+                    }
+                },
+                false => {
+                    let mut lines = core::mem::take(&mut node.lines);
+                    lines.extend(next.lines);
+                    node.set_lines(lines);
+                }
+            }
+            node.kind = next.kind;
+            node.links = next.links;
+            node.end = next.end;
+
+            // Nodes merged into the next node are now merged into this one
+            for into in merged.values_mut() {
+                if *into == next_key {
+                    *into = key.clone();
+                }
+            }
+            merged.insert(next_key, key.clone());
+        }
+    }
+    merged
+}
+
+// Returns the key of the node which can be merged into a node, if there is one
+fn mergeable(tree: &Tree, node: &Node, parents: &BTreeMap<String, usize>) -> Option<String> {
+    let link = match node.links.as_slice() {
+        [link] => link,
+        _ => return None,
+    };
+    let plain = link.dialogue.as_deref().is_none_or(str::is_empty)
+        && link.condition.is_none()
+        && link.set.is_empty()
+        && link.affinity.is_empty()
+        && link.group.is_none()
+        && link.tags.is_empty();
+    if node.kind != NodeKind::Choice || node.end || !plain || link.to_key == node.key {
+        return None;
+    }
+
+    let next = tree.node(&link.to_key)?;
+    let anchored = tree.root_key() == Some(&next.key)
+        || tree.current_key() == Some(&next.key)
+        || tree.entries().values().any(|entry| *entry == next.key);
+    let annotated =
+        !next.tags.is_empty() || !next.availability.is_empty() || !next.stage.is_empty();
+    if anchored
+        || annotated
+        || parents.get(&next.key) != Some(&1)
+        || next.pacing != node.pacing
+        || next.todo != node.todo
+        || next.lines.is_empty() != node.lines.is_empty()
+    {
+        return None;
+    }
+    Some(next.key.clone())
+}
+
+/// Try to split a [`Node`] into a chain of nodes with one sentence of its dialogue each, or one [`Line`][`crate::Line`] each if it has lines, and return the keys of the chain in order. Each node of the chain leads on to the next through a single link, and the last takes the kind, links, and ending of the node.
+///
+/// The first node of the chain keeps the key, id, tags, availability, and stage directions of the node. The others are keyed by the node's key with a numeric suffix, e.g. `start_2`, and every node of the chain has the node's pacing. A node with a single sentence is left as it is.
+///
+/// # Arguments
+///
+/// * `tree` - A [`Tree`] holding the node.
+/// * `node_key` - A string slice that holds the key of the node to split.
+/// * `dialogue` - A string slice that holds the dialogue of the links between the nodes of the chain, e.g. `Continue`, or an empty string for links which [`merge_chains`] collapses again.
+///
+/// # Errors
+///
+/// * A [`TreeError`] will be returned if the node does not exist.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{simplify, Node, Tree};
+/// let mut tree = Tree::new();
+/// tree.insert_node(Node::new("start", "The door creaks. Someone is here!"));
+///
+/// let keys = simplify::split_sentences(&mut tree, "start", "Continue").unwrap();
+/// assert_eq!(vec!["start", "start_2"], keys);
+/// assert_eq!("The door creaks.", tree.node("start").unwrap().dialogue);
+/// assert_eq!("Someone is here!", tree.node("start_2").unwrap().dialogue);
+/// ```
+pub fn split_sentences(
+    tree: &mut Tree,
+    node_key: &str,
+    dialogue: &str,
+) -> Result<Vec<String>, TreeError> {
+    let node = tree
+        .node(node_key)
+        .ok_or_else(|| tree.missing_node(node_key))?;
+
+    // Each piece of the chain is a line, or a sentence
+    let pieces: Vec<Node> = match node.lines.is_empty() {
+        true => sentences(&node.dialogue)
+            .into_iter()
+            .map(|sentence| Node::new(node_key, sentence))
+            .collect(),
+        false => node
+            .lines
+            .iter()
+            .map(|line| {
+                let mut piece = Node::new(node_key, "");
+                piece.set_lines(vec![line.clone()]);
+                piece
+            })
+            .collect(),
+    };
+    if pieces.len() < 2 {
+        return Ok(vec![node_key.to_owned()]);
+    }
+
+    let mut keys = vec![node_key.to_owned()];
+    for _ in 1..pieces.len() {
+        let key = keygen::unique_key(node_key, |key| {
+            tree.contains_node(key) || keys.iter().any(|taken| taken == key)
+        });
+        keys.push(key);
+    }
+
+    // The node becomes the first of the chain, where it is, and the last of the chain takes its way on
+    let mut pieces = pieces.into_iter();
+    let first = pieces.next().expect("a chain has several pieces");
+    let node = tree.node_mut(node_key).expect("the node exists");
+    let kind = core::mem::replace(&mut node.kind, NodeKind::Choice);
+    let links = core::mem::replace(&mut node.links, vec![Link::new(keys[1].as_str(), dialogue)]);
+    let end = core::mem::replace(&mut node.end, false);
+    node.dialogue = first.dialogue;
+    node.lines = first.lines;
+    let pacing = node.pacing;
+    let mut chain: Vec<Node> = pieces
+        .zip(&keys[1..])
+        .map(|(piece, key)| {
+            let mut piece = piece.with_key(key.as_str());
+            piece.pacing = pacing;
+            piece
+        })
+        .collect();
+    let count = chain.len();
+    for (i, piece) in chain.iter_mut().take(count - 1).enumerate() {
+        piece.links = vec![Link::new(keys[i + 2].as_str(), dialogue)];
+    }
+    let last = chain.last_mut().expect("a chain has several pieces");
+    last.kind = kind;
+    last.links = links;
+    last.end = end;
+    for piece in chain {
+        tree.insert_node(piece);
+    }
+    Ok(keys)
+}
+
+/// Returns the sentences of dialogue, in order. A sentence ends at a line break, or at a `.`, `!`, or `?`, and any closing quotes or brackets after it, which is followed by whitespace or the end of the dialogue. Whitespace around sentences is trimmed, and abbreviations such as `Mr.` are not recognized.
+///
+/// # Arguments
+///
+/// * `text` - A string slice that holds the dialogue.
+///
+/// # Examples
+///
+/// ```
+/// use convo::simplify;
+/// assert_eq!(
+///     vec!["Halt!", "\"Who goes there?!\"", "Speak up"],
+///     simplify::sentences("Halt! \"Who goes there?!\"\nSpeak up")
+/// );
+/// ```
+pub fn sentences(text: &str) -> Vec<&str> {
+    let mut sentences = Vec::new();
+    for line in text.lines() {
+        let mut start = 0;
+        let mut chars = line.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            if !matches!(c, '.' | '!' | '?') {
+                continue;
+            }
+            let mut end = i + c.len_utf8();
+            while let Some(&(j, next)) = chars.peek() {
+                if !matches!(
+                    next,
+                    '.' | '!' | '?' | '"' | '\'' | ')' | ']' | '”' | '’' | '»'
+                ) {
+                    break;
+                }
+                end = j + next.len_utf8();
+                chars.next();
+            }
+            if chars.peek().is_none_or(|(_, next)| next.is_whitespace()) {
+                sentences.push(line[start..end].trim());
+                start = end;
+            }
+        }
+        sentences.push(line[start..].trim());
+    }
+    sentences.retain(|sentence| !sentence.is_empty());
+    sentences
+}
+
+#[cfg(test)]
+#[test]
+fn test_merge_and_split() {
+    use crate::node::Line;
+
+    // one -> two -> three -> (four -> six | five -> seven), where seven is tagged
+    let mut tree = Tree::new();
+    let mut one = Node::new("one", "One.");
+    one.links.push(Link::silent("two"));
+    let mut two = Node::new("two", "Two.");
+    two.links.push(Link::new("three", ""));
+    let mut three = Node::new("three", "Three.");
+    three.links.push(Link::new("four", "Left"));
+    three.links.push(Link::new("five", "Right"));
+    let mut four = Node::new("four", "");
+    four.set_lines(vec![Line::new("guard", "Halt!")]);
+    four.links.push(Link::silent("six"));
+    let mut six = Node::new("six", "");
+    six.set_lines(vec![Line::new("captain", "Let them through.")]);
+    six.end = true;
+    let mut five = Node::new("five", "Five.");
+    five.links.push(Link::silent("seven"));
+    let mut seven = Node::new("seven", "Seven.");
+    seven.tags.push("ending".to_owned());
+    tree.extend_nodes(vec![one, two, three, four, five, six, seven])
+        .unwrap();
+    tree.set_root_key("one").unwrap();
+
+    let original = tree.clone();
+    let merged = merge_chains(&mut tree, " ");
+    assert_eq!(
+        vec![("six", "four"), ("three", "one"), ("two", "one")],
+        merged
+            .iter()
+            .map(|(from, into)| (from.as_str(), into.as_str()))
+            .collect::<Vec<_>>()
+    );
+    let one = tree.node("one").unwrap();
+    assert_eq!("One. Two. Three.", one.dialogue);
+    assert_eq!(2, one.links.len());
+    let four = tree.node("four").unwrap();
+    assert_eq!("guard: Halt!\ncaptain: Let them through.", four.dialogue);
+    assert!(four.end && four.links.is_empty());
+
+    // Tagged nodes are kept apart
+    assert!(tree.contains_node("seven"));
+    assert_eq!(4, tree.len());
+
+    // Unfinished nodes are kept apart from finished ones, so they are still reported as unfinished
+    let mut draft = Tree::new();
+    let mut start = Node::new("start", "Start.");
+    start.links.push(Link::silent("later"));
+    let mut later = Node::new("later", "Later.");
+    later.todo = true;
+    later.end = true;
+    draft.extend_nodes(vec![start, later]).unwrap();
+    draft.set_root_key("start").unwrap();
+    assert!(merge_chains(&mut draft, " ").is_empty());
+    assert!(draft.node("later").unwrap().todo);
+    draft.node_mut("start").unwrap().todo = true;
+    assert_eq!(1, merge_chains(&mut draft, " ").len());
+    assert!(draft.node("start").unwrap().todo);
+
+    // Splitting restores the shape, with generated keys
+    assert_eq!(
+        vec!["one", "one_2", "one_3"],
+        split_sentences(&mut tree, "one", "Continue").unwrap()
+    );
+    assert_eq!(
+        vec!["four", "four_2"],
+        split_sentences(&mut tree, "four", "Continue").unwrap()
+    );
+    assert_eq!(
+        original.node("three").unwrap().links,
+        tree.node("one_3").unwrap().links
+    );
+    assert_eq!(
+        original.node("six").unwrap().lines,
+        tree.node("four_2").unwrap().lines
+    );
+    assert!(tree.node("four_2").unwrap().end);
+    assert_eq!("one", tree.root_key().unwrap());
+    assert_eq!(
+        vec!["seven"],
+        split_sentences(&mut tree, "seven", "Continue").unwrap()
+    );
+    assert!(split_sentences(&mut tree, "missing", "Continue").is_err());
+
+    // Chains split with dialogue are choices, which are not merged again, and empty dialogue is merged
+    assert!(merge_chains(&mut tree, " ").is_empty());
+    let mut tree = Tree::new();
+    tree.insert_node(Node::new("start", "The door creaks. Someone is here!"));
+    split_sentences(&mut tree, "start", "").unwrap();
+    assert_eq!(1, merge_chains(&mut tree, " ").len());
+    assert_eq!(
+        "The door creaks. Someone is here!",
+        tree.node("start").unwrap().dialogue
+    );
+
+    // A single choice with dialogue is kept
+    let mut tree = Tree::new();
+    let mut ask = Node::new("ask", "Want to hear a story?");
+    ask.links.push(Link::new("story", "Tell me more"));
+    tree.extend_nodes(vec![ask, Node::new("story", "Once upon a time.")])
+        .unwrap();
+    tree.set_root_key("ask").unwrap();
+    assert!(merge_chains(&mut tree, " ").is_empty());
+    assert_eq!(
+        Some("Tell me more"),
+        tree.node("ask").unwrap().links[0].dialogue.as_deref()
+    );
+
+    // Cycles are never merged into themselves
+    let mut tree = Tree::new();
+    let mut loop_node = Node::new("loop", "Again.");
+    loop_node.links.push(Link::silent("loop"));
+    tree.insert_node(loop_node);
+    assert!(merge_chains(&mut tree, " ").is_empty());
+}
@@ -0,0 +1,85 @@
+//! Stage directions which cue the engine as dialogue plays, e.g. camera moves and animations, so cutscenes are directed alongside the dialogue they accompany.
+//!
+//! A [`Node`][`crate::Node`] may carry a list of [`StageDirection`]s, read from its optional `stage` section, where each direction is a command and its arguments, e.g. `- camera: [close_up, npc_bob]`. The engine receives them in order with [`Observer::on_stage_direction`][`crate::observer::Observer::on_stage_direction`] when the walker enters the node, or reads them from the node.
+
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+
+use crate::value::Value;
+
+/// A [`StageDirection`] is a command for the engine, with arguments whose meaning is up to the engine, e.g. `camera close_up npc_bob`.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{stage::StageDirection, Value};
+/// let direction = StageDirection::new("animate", vec!["npc_bob".into(), "wave".into(), Value::Int(2)]);
+/// assert_eq!(Some(&Value::Int(2)), direction.arg(2));
+/// assert_eq!("animate npc_bob wave 2", direction.to_string());
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct StageDirection {
+    /// The command, e.g. `camera`.
+    pub command: String,
+
+    /// The arguments of the command, in authored order.
+    pub args: Vec<Value>,
+}
+
+impl StageDirection {
+    /// Returns a [`StageDirection`] with a command and its arguments.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - A string type that holds the command.
+    /// * `args` - The arguments of the command, in order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::stage::StageDirection;
+    /// let direction = StageDirection::new("fade_out", vec![]);
+    /// assert!(direction.args.is_empty());
+    /// ```
+    pub fn new<C>(command: C, args: Vec<Value>) -> StageDirection
+    where
+        C: Into<String>,
+    {
+        StageDirection {
+            command: command.into(),
+            args,
+        }
+    }
+
+    /// Returns an argument of the command, or [`None`] if it has too few arguments.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the argument.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{stage::StageDirection, Value};
+    /// let direction = StageDirection::new("camera", vec!["close_up".into()]);
+    /// assert_eq!(Some(&Value::from("close_up")), direction.arg(0));
+    /// assert_eq!(None, direction.arg(1));
+    /// ```
+    pub fn arg(&self, index: usize) -> Option<&Value> {
+        self.args.get(index)
+    }
+}
+
+impl fmt::Display for StageDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.command)?;
+        for arg in &self.args {
+            match arg {
+                Value::Bool(value) => write!(f, " {}", value)?,
+                Value::Int(value) => write!(f, " {}", value)?,
+                Value::String(value) => write!(f, " {}", value)?,
+            }
+        }
+        Ok(())
+    }
+}
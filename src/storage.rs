@@ -0,0 +1,670 @@
+//! Persistence of walker sessions and coverage to an embedded [sled](https://github.com/spacejam/sled) database, e.g. for chat-bot style servers which must survive restarts.
+//!
+//! A [`Store`] keeps one session and one coverage record per player and tree, named by the host. Sessions hold the position, history and variables of a conversation, so it continues where it left off. Coverage holds the keys of every node a player has entered in a tree, across sessions.
+//!
+//! Records are JSON, so they remain readable with other tools:
+//!
+//! * A session is `{ "current": <key>, "history": [<key>], "variables": { <name>: <bool, int or string> }, "assigned": [<name>], "aborted": <reason or null> }`.
+//! * Coverage is a sorted array of node keys.
+
+use crate::{diff::Migration, error::StorageError, tree::Tree, value::Value, walker::Walker};
+
+use serde_json::{json, Map, Value as Json};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ops::Deref,
+    path::Path,
+};
+
+/// A [`Store`] saves and loads walker sessions and coverage, keyed by player and tree, to an embedded database.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{storage::Store, Link, Node, Tree, Walker};
+/// let mut tree = Tree::new();
+/// let mut start = Node::new("start", "Hello!");
+/// let end = Node::new("end", "Goodbye!");
+/// Link::link(&mut start, &end, "Bye!");
+/// tree.insert_node(start);
+/// tree.insert_node(end);
+/// tree.set_root_key("start").unwrap();
+///
+/// let store = Store::temporary().unwrap();
+/// let mut walker = Walker::new(&tree).unwrap();
+/// walker.choose(0).unwrap();
+/// store.save_session("player-1", "greeting", &walker).unwrap();
+///
+/// let walker = store.load_session("player-1", "greeting", &tree).unwrap().unwrap();
+/// assert_eq!("end", walker.current_key());
+/// assert_eq!(&["start"], walker.history());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Store {
+    db: sled::Db,
+    sessions: sled::Tree,
+    coverage: sled::Tree,
+}
+
+impl Store {
+    /// Try to open a [`Store`] in a directory, creating it if it does not exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - A path to the database directory.
+    ///
+    /// # Errors
+    ///
+    /// * A [`StorageError`] will be returned if the database is unable to be opened, e.g. it is open in another process.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use convo::storage::Store;
+    /// let store = Store::open("sessions.db").unwrap();
+    /// ```
+    pub fn open<P>(path: P) -> Result<Self, StorageError>
+    where
+        P: AsRef<Path>,
+    {
+        Store::with_db(sled::open(path)?)
+    }
+
+    /// Try to open a [`Store`] which is deleted when dropped, e.g. for tests.
+    ///
+    /// # Errors
+    ///
+    /// * A [`StorageError`] will be returned if the database is unable to be created.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::storage::Store;
+    /// let store = Store::temporary().unwrap();
+    /// assert!(store.coverage("player-1", "greeting").unwrap().is_empty());
+    /// ```
+    pub fn temporary() -> Result<Self, StorageError> {
+        Store::with_db(sled::Config::new().temporary(true).open()?)
+    }
+
+    fn with_db(db: sled::Db) -> Result<Self, StorageError> {
+        Ok(Store {
+            sessions: db.open_tree("sessions")?,
+            coverage: db.open_tree("coverage")?,
+            db,
+        })
+    }
+
+    /// Try to save the session of a [`Walker`] for a player and tree, replacing any previous session.
+    ///
+    /// # Arguments
+    ///
+    /// * `player_id` - A string slice that holds the id of the player.
+    /// * `tree_id` - A string slice that holds the name the host gives the tree.
+    /// * `walker` - The [`Walker`] of the conversation.
+    ///
+    /// # Errors
+    ///
+    /// * A [`StorageError`] will be returned if the database fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{storage::Store, Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let store = Store::temporary().unwrap();
+    /// let walker = Walker::new(&tree).unwrap();
+    /// store.save_session("player-1", "greeting", &walker).unwrap();
+    /// ```
+    pub fn save_session<T>(
+        &self,
+        player_id: &str,
+        tree_id: &str,
+        walker: &Walker<T>,
+    ) -> Result<(), StorageError>
+    where
+        T: Deref<Target = Tree>,
+    {
+        let variables: Map<String, Json> = walker
+            .variables()
+            .iter()
+            .map(|(name, value)| (name.clone(), value_to_json(value)))
+            .collect();
+        let session = json!({
+            "current": walker.current_key(),
+            "history": walker.history(),
+            "variables": variables,
+            "assigned": walker.assigned(),
+            "aborted": walker.abort_reason(),
+        });
+        self.sessions.insert(
+            record_key(player_id, tree_id),
+            session.to_string().as_bytes(),
+        )?;
+        Ok(())
+    }
+
+    /// Try to load the session of a player and tree as a [`Walker`] which continues where the session left off, or [`None`] if no session was saved.
+    ///
+    /// The walker has a fresh transcript starting at its current node, and none of the bookmarks, snapshots or settings of the saved walker.
+    ///
+    /// # Arguments
+    ///
+    /// * `player_id` - A string slice that holds the id of the player.
+    /// * `tree_id` - A string slice that holds the name the host gives the tree.
+    /// * `tree` - The [`Tree`] to walk.
+    ///
+    /// # Errors
+    ///
+    /// * A [`StorageError`] will be returned if the database fails, the session is unable to be decoded, or it visited nodes which no longer exist in the tree.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{storage::Store, Node, Tree};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let store = Store::temporary().unwrap();
+    /// assert!(store.load_session("player-1", "greeting", &tree).unwrap().is_none());
+    /// ```
+    pub fn load_session<T>(
+        &self,
+        player_id: &str,
+        tree_id: &str,
+        tree: T,
+    ) -> Result<Option<Walker<T>>, StorageError>
+    where
+        T: Deref<Target = Tree>,
+    {
+        let bytes = match self.sessions.get(record_key(player_id, tree_id))? {
+            Some(bytes) => bytes,
+            None => return Ok(None),
+        };
+        let session: Json = serde_json::from_slice(&bytes).map_err(corrupt)?;
+        let current_key = session["current"]
+            .as_str()
+            .ok_or_else(|| StorageError::Corrupt("Session has no current node".into()))?;
+        let history = strings(&session["history"])?;
+        let assigned = strings(&session["assigned"])?.into_iter().collect();
+        let variables = session["variables"]
+            .as_object()
+            .ok_or_else(|| StorageError::Corrupt("Session variables are not an object".into()))?
+            .iter()
+            .map(|(name, value)| Ok((name.clone(), json_to_value(value)?)))
+            .collect::<Result<BTreeMap<String, Value>, StorageError>>()?;
+        let aborted = session["aborted"].as_str().map(String::from);
+        let walker = Walker::from_parts(
+            tree,
+            current_key.into(),
+            history,
+            variables,
+            assigned,
+            aborted,
+        )?;
+        Ok(Some(walker))
+    }
+
+    /// Try to remove the session of a player and tree, returning whether one was saved.
+    ///
+    /// # Arguments
+    ///
+    /// * `player_id` - A string slice that holds the id of the player.
+    /// * `tree_id` - A string slice that holds the name the host gives the tree.
+    ///
+    /// # Errors
+    ///
+    /// * A [`StorageError`] will be returned if the database fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::storage::Store;
+    /// let store = Store::temporary().unwrap();
+    /// assert!(!store.remove_session("player-1", "greeting").unwrap());
+    /// ```
+    pub fn remove_session(&self, player_id: &str, tree_id: &str) -> Result<bool, StorageError> {
+        Ok(self
+            .sessions
+            .remove(record_key(player_id, tree_id))?
+            .is_some())
+    }
+
+    /// Try to add every node entered by a [`Walker`], as recorded by its [`transcript`][`Walker#method.transcript`], to the coverage of a player and tree. Recording the same walker twice is harmless.
+    ///
+    /// # Arguments
+    ///
+    /// * `player_id` - A string slice that holds the id of the player.
+    /// * `tree_id` - A string slice that holds the name the host gives the tree.
+    /// * `walker` - The [`Walker`] of the conversation.
+    ///
+    /// # Errors
+    ///
+    /// * A [`StorageError`] will be returned if the database fails, or the stored coverage is unable to be decoded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{storage::Store, Link, Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// let mut start = Node::new("start", "Hello!");
+    /// let end = Node::new("end", "Goodbye!");
+    /// Link::link(&mut start, &end, "Bye!");
+    /// tree.insert_node(start);
+    /// tree.insert_node(end);
+    /// tree.set_root_key("start").unwrap();
+    ///
+    /// let store = Store::temporary().unwrap();
+    /// let walker = Walker::new(&tree).unwrap();
+    /// store.record_coverage("player-1", "greeting", &walker).unwrap();
+    /// let mut walker = Walker::new(&tree).unwrap();
+    /// walker.choose(0).unwrap();
+    /// store.record_coverage("player-1", "greeting", &walker).unwrap();
+    /// assert_eq!(2, store.coverage("player-1", "greeting").unwrap().len());
+    /// ```
+    pub fn record_coverage<T>(
+        &self,
+        player_id: &str,
+        tree_id: &str,
+        walker: &Walker<T>,
+    ) -> Result<(), StorageError>
+    where
+        T: Deref<Target = Tree>,
+    {
+        // Concurrent recordings merge, so the update may run several times
+        let mut error = None;
+        self.coverage
+            .fetch_and_update(record_key(player_id, tree_id), |old| {
+                let mut keys = match old.map(decode_coverage).transpose() {
+                    Ok(keys) => keys.unwrap_or_default(),
+                    Err(e) => {
+                        error = Some(e);
+                        return old.map(Vec::from);
+                    }
+                };
+                error = None;
+                keys.extend(walker.transcript().iter().map(|e| e.node_key.clone()));
+                Some(json!(keys).to_string().into_bytes())
+            })?;
+        match error {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    /// Try to return the keys of every node a player has entered in a tree, as recorded with [`record_coverage`][`Store#method.record_coverage`].
+    ///
+    /// # Arguments
+    ///
+    /// * `player_id` - A string slice that holds the id of the player.
+    /// * `tree_id` - A string slice that holds the name the host gives the tree.
+    ///
+    /// # Errors
+    ///
+    /// * A [`StorageError`] will be returned if the database fails, or the stored coverage is unable to be decoded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::storage::Store;
+    /// let store = Store::temporary().unwrap();
+    /// assert!(store.coverage("player-1", "greeting").unwrap().is_empty());
+    /// ```
+    pub fn coverage(
+        &self,
+        player_id: &str,
+        tree_id: &str,
+    ) -> Result<BTreeSet<String>, StorageError> {
+        match self.coverage.get(record_key(player_id, tree_id))? {
+            Some(bytes) => decode_coverage(&bytes),
+            None => Ok(BTreeSet::new()),
+        }
+    }
+
+    /// Try to rename the node keys of every session and coverage record of a tree by a [`Migration`], e.g. after a live game's dialogue is patched, returning the ids of the players whose sessions could not be migrated.
+    ///
+    /// Sessions forget the history before any step back to a removed node, and coverage forgets removed nodes. Sessions whose current node was removed are left untouched, so the host can decide whether to remove them or restart them.
+    ///
+    /// # Arguments
+    ///
+    /// * `tree_id` - A string slice that holds the name the host gives the tree.
+    /// * `migration` - The [`Migration`] from the tree the records were made with to its new version.
+    ///
+    /// # Errors
+    ///
+    /// * A [`StorageError`] will be returned if the database fails, or a record is unable to be decoded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{storage::Store, Migration, Node, Tree, Walker};
+    /// let mut old = Tree::new();
+    /// old.insert_node(Node::new("start", "Hello!"));
+    /// old.set_root_key("start").unwrap();
+    /// old.assign_ids().unwrap();
+    /// let store = Store::temporary().unwrap();
+    /// store.save_session("player-1", "greeting", &Walker::new(&old).unwrap()).unwrap();
+    ///
+    /// let mut new = Tree::new();
+    /// let node = old.node("start").unwrap().clone();
+    /// new.insert_node(node.with_key("hello"));
+    /// new.set_root_key("hello").unwrap();
+    ///
+    /// let stranded = store.migrate("greeting", &Migration::new(&old, &new)).unwrap();
+    /// assert!(stranded.is_empty());
+    /// let walker = store.load_session("player-1", "greeting", &new).unwrap().unwrap();
+    /// assert_eq!("hello", walker.current_key());
+    /// ```
+    pub fn migrate(
+        &self,
+        tree_id: &str,
+        migration: &Migration,
+    ) -> Result<Vec<String>, StorageError> {
+        let mut stranded = Vec::new();
+        for record in self.sessions.iter() {
+            let (key, bytes) = record?;
+            let player_id = match record_tree(&key, tree_id)? {
+                Some(player_id) => player_id,
+                None => continue,
+            };
+            let mut session: Json = serde_json::from_slice(&bytes).map_err(corrupt)?;
+            let current_key = session["current"]
+                .as_str()
+                .ok_or_else(|| StorageError::Corrupt("Session has no current node".into()))?;
+            let history = strings(&session["history"])?;
+            match migration.migrate_keys(current_key, &history) {
+                Some((current_key, history)) => {
+                    session["current"] = json!(current_key);
+                    session["history"] = json!(history);
+                    self.sessions.insert(key, session.to_string().as_bytes())?;
+                }
+                None => stranded.push(player_id),
+            }
+        }
+        for record in self.coverage.iter() {
+            let (key, bytes) = record?;
+            if record_tree(&key, tree_id)?.is_none() {
+                continue;
+            }
+            let old_keys = decode_coverage(&bytes)?;
+            let keys: BTreeSet<&str> = old_keys
+                .iter()
+                .filter_map(|key| migration.key(key))
+                .collect();
+            self.coverage
+                .insert(key, json!(keys).to_string().as_bytes())?;
+        }
+        Ok(stranded)
+    }
+
+    /// Try to write every change to disk, returning once it is durable. Changes are otherwise written in the background.
+    ///
+    /// # Errors
+    ///
+    /// * A [`StorageError`] will be returned if the database fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::storage::Store;
+    /// let store = Store::temporary().unwrap();
+    /// store.flush().unwrap();
+    /// ```
+    pub fn flush(&self) -> Result<(), StorageError> {
+        self.db.flush()?;
+        Ok(())
+    }
+}
+
+// Records are keyed by the JSON array of their ids, so no id can be confused for another
+fn record_key(player_id: &str, tree_id: &str) -> Vec<u8> {
+    json!([player_id, tree_id]).to_string().into_bytes()
+}
+
+// Returns the player id of a record key, if the record belongs to a tree
+fn record_tree(key: &[u8], tree_id: &str) -> Result<Option<String>, StorageError> {
+    let ids: Json = serde_json::from_slice(key).map_err(corrupt)?;
+    match strings(&ids)?.as_slice() {
+        [player_id, record_tree_id] => {
+            Ok(Some(player_id.clone()).filter(|_| record_tree_id == tree_id))
+        }
+        _ => Err(StorageError::Corrupt(format!(
+            "Record key is not a player and tree: {}",
+            ids
+        ))),
+    }
+}
+
+fn decode_coverage(bytes: &[u8]) -> Result<BTreeSet<String>, StorageError> {
+    let keys: Json = serde_json::from_slice(bytes).map_err(corrupt)?;
+    Ok(strings(&keys)?.into_iter().collect())
+}
+
+fn strings(json: &Json) -> Result<Vec<String>, StorageError> {
+    json.as_array()
+        .and_then(|array| {
+            array
+                .iter()
+                .map(|item| item.as_str().map(String::from))
+                .collect()
+        })
+        .ok_or_else(|| StorageError::Corrupt(format!("Expected an array of strings: {}", json)))
+}
+
+fn value_to_json(value: &Value) -> Json {
+    match value {
+        Value::Bool(value) => json!(value),
+        Value::Int(value) => json!(value),
+        Value::String(value) => json!(value),
+    }
+}
+
+fn json_to_value(json: &Json) -> Result<Value, StorageError> {
+    match json {
+        Json::Bool(value) => Ok(Value::Bool(*value)),
+        Json::Number(number) => number.as_i64().map(Value::Int).ok_or_else(|| {
+            StorageError::Corrupt(format!("Variable is not an integer: {}", number))
+        }),
+        Json::String(value) => Ok(Value::String(value.clone())),
+        _ => Err(StorageError::Corrupt(format!(
+            "Variable is not a boolean, integer, or string: {}",
+            json
+        ))),
+    }
+}
+
+fn corrupt(error: serde_json::Error) -> StorageError {
+    StorageError::Corrupt(error.to_string())
+}
+
+#[cfg(test)]
+#[test]
+fn test_sessions() {
+    use crate::{Link, Node};
+
+    // one -> two -> (three | four)
+    let mut tree = Tree::new();
+    let mut one = Node::new("one", "One.");
+    let mut two = Node::new("two", "Two.");
+    let three = Node::new("three", "Three.");
+    let four = Node::new("four", "Four.");
+    Link::link(&mut one, &two, "Continue");
+    one.links[0].set.push(("met".into(), Value::Bool(true)));
+    Link::link(&mut two, &three, "Three");
+    Link::link(&mut two, &four, "Four");
+    for node in [one, two, three, four] {
+        tree.insert_node(node);
+    }
+    tree.set_root_key("one").unwrap();
+
+    let store = Store::temporary().unwrap();
+    let mut walker = Walker::new(&tree).unwrap();
+    walker.choose(0).unwrap();
+    walker
+        .variables_mut()
+        .insert("name".into(), Value::String("Ada \"the\" bard".into()));
+    walker.variables_mut().insert("gold".into(), Value::Int(-3));
+    walker.abort("left");
+    store.save_session("player", "tree", &walker).unwrap();
+
+    // Sessions are keyed by player and tree
+    assert!(store
+        .load_session("tree", "player", &tree)
+        .unwrap()
+        .is_none());
+    let mut loaded = store
+        .load_session("player", "tree", &tree)
+        .unwrap()
+        .unwrap();
+    assert_eq!(walker.variables(), loaded.variables());
+    assert_eq!(Some("left"), loaded.abort_reason());
+    assert_eq!("two", loaded.transcript()[0].node_key);
+
+    // Only variables assigned by links are part of the outcome
+    let mut walker = Walker::new(&tree).unwrap();
+    walker.choose(0).unwrap();
+    store.save_session("player", "tree", &walker).unwrap();
+    loaded = store
+        .load_session("player", "tree", &tree)
+        .unwrap()
+        .unwrap();
+    loaded.choose(1).unwrap();
+    let outcome = loaded.finish().unwrap();
+    assert_eq!(2, outcome.path_length);
+    assert_eq!(vec!["met"], outcome.variables.keys().collect::<Vec<_>>());
+    assert_eq!("two", loaded.step_back().unwrap().key);
+
+    // Sessions which visited removed nodes are rejected
+    let mut edited = tree.clone();
+    edited.remove_node("one").unwrap();
+    assert!(matches!(
+        store.load_session("player", "tree", &edited),
+        Err(StorageError::Validation(_))
+    ));
+
+    assert!(store.remove_session("player", "tree").unwrap());
+    assert!(store
+        .load_session("player", "tree", &tree)
+        .unwrap()
+        .is_none());
+
+    // Corrupt records are reported rather than loaded
+    store
+        .sessions
+        .insert(record_key("player", "tree"), "{}")
+        .unwrap();
+    assert!(matches!(
+        store.load_session("player", "tree", &tree),
+        Err(StorageError::Corrupt(_))
+    ));
+}
+
+#[test]
+fn test_coverage() {
+    use crate::{Link, Node};
+
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "Hello!");
+    let end = Node::new("end", "Goodbye!");
+    Link::link(&mut start, &end, "Bye!");
+    tree.insert_node(start);
+    tree.insert_node(end);
+    tree.set_root_key("start").unwrap();
+
+    // Coverage accumulates across walkers, per player and tree
+    let store = Store::temporary().unwrap();
+    let mut walker = Walker::new(&tree).unwrap();
+    store.record_coverage("player", "tree", &walker).unwrap();
+    assert_eq!(
+        vec!["start"],
+        store
+            .coverage("player", "tree")
+            .unwrap()
+            .into_iter()
+            .collect::<Vec<_>>()
+    );
+    walker.choose(0).unwrap();
+    store.record_coverage("player", "tree", &walker).unwrap();
+    store.record_coverage("player", "tree", &walker).unwrap();
+    assert_eq!(
+        vec!["end", "start"],
+        store
+            .coverage("player", "tree")
+            .unwrap()
+            .into_iter()
+            .collect::<Vec<_>>()
+    );
+    assert!(store.coverage("other", "tree").unwrap().is_empty());
+
+    // Corrupt coverage is reported, and left as it was
+    store
+        .coverage
+        .insert(record_key("other", "tree"), "[1]")
+        .unwrap();
+    assert!(matches!(
+        store.record_coverage("other", "tree", &walker),
+        Err(StorageError::Corrupt(_))
+    ));
+    assert!(matches!(
+        store.coverage("other", "tree"),
+        Err(StorageError::Corrupt(_))
+    ));
+}
+
+#[test]
+fn test_migrate() {
+    use crate::{Link, Node};
+
+    // one -> two
+    let mut old = Tree::new();
+    let mut one = Node::new("one", "One.");
+    let two = Node::new("two", "Two.");
+    Link::link(&mut one, &two, "Continue");
+    old.insert_node(one);
+    old.insert_node(two);
+    old.set_root_key("one").unwrap();
+    old.assign_ids().unwrap();
+
+    let store = Store::temporary().unwrap();
+    let mut walker = Walker::new(&old).unwrap();
+    store.save_session("resting", "tree", &walker).unwrap();
+    walker.choose(0).unwrap();
+    store.save_session("walked", "tree", &walker).unwrap();
+    store.save_session("walked", "other", &walker).unwrap();
+    store.record_coverage("walked", "tree", &walker).unwrap();
+
+    // `one` is removed and `two` is renamed
+    let mut new = Tree::new();
+    let mut second = old.node("two").unwrap().clone();
+    second.key = "second".to_owned();
+    new.insert_node(second);
+    new.set_root_key("second").unwrap();
+    let migration = Migration::new(&old, &new);
+
+    assert_eq!(vec!["resting"], store.migrate("tree", &migration).unwrap());
+    let walker = store.load_session("walked", "tree", &new).unwrap().unwrap();
+    assert_eq!("second", walker.current_key());
+    assert!(walker.history().is_empty());
+    let coverage: Vec<String> = store
+        .coverage("walked", "tree")
+        .unwrap()
+        .into_iter()
+        .collect();
+    assert_eq!(vec!["second"], coverage);
+
+    // Stranded sessions and other trees are untouched
+    assert!(store
+        .load_session("resting", "tree", &old)
+        .unwrap()
+        .is_some());
+    assert_eq!(
+        "two",
+        store
+            .load_session("walked", "other", &old)
+            .unwrap()
+            .unwrap()
+            .current_key()
+    );
+}
@@ -0,0 +1,71 @@
+use alloc::{format, string::String, vec, vec::Vec};
+
+/// Returns the candidate closest to a name by edit distance, if one is close enough to be a likely typo of it, i.e. within a third of the name's length.
+/// Ties are broken alphabetically, so suggestions never depend on the order of candidates.
+pub(crate) fn closest<'c, I>(name: &str, candidates: I) -> Option<&'c str>
+where
+    I: IntoIterator<Item = &'c str>,
+{
+    let limit = core::cmp::max(name.chars().count(), 3) / 3;
+    candidates
+        .into_iter()
+        .filter(|candidate| *candidate != name)
+        .map(|candidate| (distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= limit)
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+/// Returns a suffix for messages about a missing name, suggesting the closest candidate, or an empty string if there is none.
+pub(crate) fn did_you_mean(suggestion: Option<&str>) -> String {
+    match suggestion {
+        Some(suggestion) => format!(", did you mean `{}`?", suggestion),
+        None => String::new(),
+    }
+}
+
+// The edit distance between two strings in characters, where swapping adjacent characters is one edit
+fn distance(a: &str, b: &str) -> usize {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, distance) in distances[0].iter_mut().enumerate() {
+        *distance = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let substitution = distances[i - 1][j - 1] + usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = substitution
+                .min(distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
+    }
+    distances[a.len()][b.len()]
+}
+
+#[cfg(test)]
+#[test]
+fn test_closest() {
+    assert_eq!(3, distance("kitten", "sitting"));
+    assert_eq!(1, distance("strat", "start"));
+    assert_eq!(0, distance("", ""));
+
+    let keys = ["start", "shop", "stop", "farewell"];
+    assert_eq!(Some("start"), closest("strat", keys));
+    assert_eq!(Some("farewell"), closest("farwell", keys));
+
+    // Ties are broken alphabetically
+    assert_eq!(Some("shop"), closest("sxop", keys));
+
+    // Distant names, and the name itself, are never suggested
+    assert_eq!(None, closest("market", keys));
+    assert_eq!(None, closest("shop", ["shop"]));
+    assert_eq!(", did you mean `shop`?", did_you_mean(Some("shop")));
+    assert_eq!("", did_you_mean(None));
+}
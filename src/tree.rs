@@ -1,23 +1,62 @@
+use alloc::{
+    borrow::ToOwned, collections::BTreeMap, format, string::String, sync::Arc, vec, vec::Vec,
+};
 use indexmap::IndexMap;
+#[cfg(feature = "std")]
 use std::path::Path;
+use unicode_normalization::{is_nfc, UnicodeNormalization};
 
 use crate::{
-    error::{ExportError, ImportError, TreeError},
+    availability::Availability,
+    diff::TreeDiff,
+    error::TreeError,
+    index::TreeIndex,
+    link::{Link, LinkOrder, WhenUnmet},
+    lint::{self, Diagnostic},
+    meta::Meta,
+    node::{Node, NodeKind},
+    raw::RawTree,
+    suggest,
+    value::{Type, Value},
+};
+#[cfg(feature = "std")]
+use crate::{
+    error::{ExportError, ImportError},
     exporter,
-    node::Node,
 };
 
+// Without `std`, nodes are indexed with a deterministic hasher
+#[cfg(feature = "std")]
+type NodeHasher = std::collections::hash_map::RandomState;
+#[cfg(not(feature = "std"))]
+type NodeHasher = core::hash::BuildHasherDefault<crate::hash::FnvHasher>;
+
 /// A [`Tree`] is the parent container for a conversation tree. It is a walkable structure which follows the form of a human conversation.
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct Tree {
-    /// The nodes in this conversation tree. Each [`Node`] is uniquely indexable by its [`Node#key`][`Node#structfield.key`].
-    pub nodes: IndexMap<String, Node>,
+    /// The nodes in this conversation tree. Each [`Node`] is uniquely indexable by its [`Node::key`]. Nodes are shared between clones of the tree, and copied on write.
+    pub(crate) nodes: IndexMap<String, Arc<Node>, NodeHasher>,
+
+    /// The key of the root node. Can be [`None`]. If it is [`Some`], it indexes an existing [`Node`] in [`Tree::nodes`] unless set through a [`RawTree`].
+    pub(crate) root_key: Option<String>,
+
+    /// The key of the current node. Can be [`None`]. If it is [`Some`], it indexes an existing [`Node`] in [`Tree::nodes`] unless set through a [`RawTree`].
+    pub(crate) current_key: Option<String>,
+
+    /// The order in which links are presented when walking.
+    link_order: LinkOrder,
+
+    /// The metadata describing this conversation tree.
+    meta: Meta,
 
-    /// The key of the root node. Can be [`None`]. If it is [`Some`], it is guaranteed to index an existing [`Node`] in [`Tree#nodes`][`Tree#structfield.nodes`].
-    root_key: Option<String>,
+    /// When this conversation tree may be offered.
+    availability: Availability,
 
-    /// The key of the current node. Can be [`None`]. If it is [`Some`], it is guaranteed to index an existing [`Node`] in [`Tree#nodes`][`Tree#structfield.nodes`].
-    current_key: Option<String>,
+    /// The declared variables of this conversation tree.
+    variables: BTreeMap<String, Type>,
+
+    /// The named entry points of this conversation tree, mapped to the keys of their nodes.
+    entries: BTreeMap<String, String>,
 }
 
 impl Default for Tree {
@@ -37,12 +76,71 @@ impl Tree {
     /// ```
     pub fn new() -> Self {
         Tree {
-            nodes: IndexMap::<String, Node>::new(),
+            nodes: IndexMap::default(),
             root_key: None,
             current_key: None,
+            link_order: LinkOrder::default(),
+            meta: Meta::default(),
+            availability: Availability::default(),
+            variables: BTreeMap::new(),
+            entries: BTreeMap::new(),
+        }
+    }
+
+    /// Returns a [`Tree`] with no nodes, with space for at least a number of nodes before reallocating, e.g. when generating many nodes procedurally.
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - The number of nodes to allocate space for.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::Tree;
+    /// let tree = Tree::with_capacity(1000);
+    /// assert!(tree.capacity() >= 1000);
+    /// assert!(tree.is_empty());
+    /// ```
+    pub fn with_capacity(capacity: usize) -> Self {
+        Tree {
+            nodes: IndexMap::with_capacity_and_hasher(capacity, NodeHasher::default()),
+            ..Tree::new()
         }
     }
 
+    /// Try to return a [`Tree`] built from [`Node`]s and a root key in one call. The nodes are inserted with [`extend_nodes`][`Tree#method.extend_nodes`], then the root (and current) node is set.
+    ///
+    /// # Arguments
+    ///
+    /// * `root_key` - A string slice that holds the key of the root node.
+    /// * `nodes` - The [`Node`]s of the tree.
+    ///
+    /// # Errors
+    ///
+    /// * A [`TreeError`] will be returned if a node key repeats, if a link targets a node which does not exist, or if the root node does not exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Link, Node, Tree};
+    /// let mut start = Node::new("start", "Hello!");
+    /// let end = Node::new("end", "Goodbye!");
+    /// Link::link(&mut start, &end, "Bye!");
+    /// let tree = Tree::from_nodes("start", vec![start, end]).unwrap();
+    /// assert_eq!("start", tree.current_key().unwrap());
+    ///
+    /// assert!(Tree::from_nodes("missing", vec![Node::new("start", "Hello!")]).is_err());
+    /// ```
+    pub fn from_nodes<I>(root_key: &str, nodes: I) -> Result<Self, TreeError>
+    where
+        I: IntoIterator<Item = Node>,
+    {
+        let mut tree = Tree::new();
+        tree.extend_nodes(nodes)?;
+        tree.set_root_key(root_key)?;
+        Ok(tree)
+    }
+
     /// Try to returns a [`Tree`] which is generated from parsing a string slice.
     ///
     /// # Arguments
@@ -68,6 +166,7 @@ impl Tree {
     ///       - start: Recurse!"#;
     /// let tree = Tree::try_from(source).unwrap();
     /// ```
+    #[cfg(feature = "std")]
     pub fn try_from(source: &str) -> Result<Self, ImportError> {
         crate::importer::source_to_tree(source)
     }
@@ -86,10 +185,11 @@ impl Tree {
     /// let mut tree = Tree::new();
     /// let root_key = "root";
     /// let root_node = Node::new(root_key, "The only node.");
-    /// tree.nodes.insert(root_key.to_owned(), root_node);
+    /// tree.insert_node(root_node);
     /// tree.set_root_key(root_key).unwrap();
     /// assert!(tree.try_export("examples/dialogue_files/export.convo.yml").is_ok());
     /// ```
+    #[cfg(feature = "std")]
     pub fn try_export<P>(&self, path: P) -> Result<(), ExportError>
     where
         P: AsRef<Path>,
@@ -97,7 +197,323 @@ impl Tree {
         exporter::export(self, path)
     }
 
-    /// Returns an [`Option`] which references a copy of the root [`Node#key`][`Node#structfield.key`].
+    /// Returns the [`Diagnostic`]s found by running every lint check over a [`Tree`]. See also: [`lint::lint`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Link, Node, Tree};
+    /// let mut tree = Tree::new();
+    /// let mut node = Node::new("start", "Hello?");
+    /// node.links.push(Link::new("start", "Hello?"));
+    /// tree.insert_node(node);
+    /// assert_eq!(1, tree.lint().len());
+    /// ```
+    pub fn lint(&self) -> Vec<Diagnostic> {
+        lint::lint(self)
+    }
+
+    /// Returns an [`Option`] which references the [`Node`] indexed by a node key.
+    /// This method will return [`None`] if the node does not exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `node_key` - A string slice that holds the key of the node.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Tree, Node};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("x", "Some node."));
+    /// assert_eq!("Some node.", tree.node("x").unwrap().dialogue);
+    /// assert!(tree.node("y").is_none());
+    /// ```
+    pub fn node(&self, node_key: &str) -> Option<&Node> {
+        self.nodes.get(node_key).map(|node| &**node)
+    }
+
+    /// Returns an [`Option`] which mutably references the [`Node`] indexed by a node key.
+    /// This method will return [`None`] if the node does not exist.
+    ///
+    /// The node remains indexed by `node_key`, and its [`Node::key`] cannot be changed through the reference. To re-key a node, [`remove_node`][`Tree#method.remove_node`] it, re-key it with [`Node::with_key`], and [`insert_node`][`Tree#method.insert_node`] it again.
+    ///
+    /// # Arguments
+    ///
+    /// * `node_key` - A string slice that holds the key of the node.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Tree, Node};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("x", "Some node."));
+    /// tree.node_mut("x").unwrap().dialogue = "Edited.".to_owned();
+    /// assert_eq!("Edited.", tree.node("x").unwrap().dialogue);
+    /// ```
+    pub fn node_mut(&mut self, node_key: &str) -> Option<&mut Node> {
+        self.nodes.get_mut(node_key).map(Arc::make_mut)
+    }
+
+    /// Returns whether a node key indexes a [`Node`] in this tree.
+    ///
+    /// # Arguments
+    ///
+    /// * `node_key` - A string slice that holds the key of the node.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Tree, Node};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("x", "Some node."));
+    /// assert!(tree.contains_node("x"));
+    /// assert!(!tree.contains_node("y"));
+    /// ```
+    pub fn contains_node(&self, node_key: &str) -> bool {
+        self.nodes.contains_key(node_key)
+    }
+
+    /// Returns an [`Option`] which references the [`Node`] with a stable id, e.g. one recorded by a save game before the node's key was renamed.
+    /// This method will return [`None`] if no node has the id. Unlike node keys, ids are not indexed, so this method visits every node.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The stable id of the node. See [`Node#id`][`Node#structfield.id`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Tree, Node};
+    /// let mut tree = Tree::new();
+    /// let mut node = Node::new("x", "Some node.");
+    /// node.id = Some(7);
+    /// tree.insert_node(node);
+    /// assert_eq!("x", tree.node_by_id(7).unwrap().key());
+    /// assert!(tree.node_by_id(8).is_none());
+    /// ```
+    pub fn node_by_id(&self, id: u64) -> Option<&Node> {
+        self.nodes().find(|node| node.id == Some(id))
+    }
+
+    /// Assign a stable id to every [`Node`] without one, in the order nodes are stored, counting up from the highest id in use. Imported trees have their ids assigned, so they stay stable as long as the tree is exported with them.
+    ///
+    /// # Errors
+    ///
+    /// * A [`TreeError`] will be returned if two nodes share an id, or if no ids are left to assign. The tree is left unchanged on error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Tree, Node};
+    /// let mut tree = Tree::new();
+    /// let mut node = Node::new("a", "A node.");
+    /// node.id = Some(10);
+    /// tree.insert_node(node);
+    /// tree.insert_node(Node::new("b", "B node."));
+    /// tree.assign_ids().unwrap();
+    /// assert_eq!(Some(11), tree.node("b").unwrap().id);
+    ///
+    /// tree.node_mut("b").unwrap().id = Some(10);
+    /// assert!(tree.assign_ids().is_err());
+    /// ```
+    pub fn assign_ids(&mut self) -> Result<(), TreeError> {
+        let mut ids = BTreeMap::new();
+        for node in self.nodes.values() {
+            if let Some(other) = node.id.and_then(|id| ids.insert(id, &node.key)) {
+                return Err(TreeError::Validation(format!(
+                    "Nodes `{}` and `{}` share the id {}",
+                    other,
+                    node.key,
+                    node.id.unwrap_or_default()
+                )));
+            }
+        }
+
+        let missing = self.nodes.values().filter(|node| node.id.is_none()).count();
+        let mut id = ids.keys().next_back().copied().unwrap_or(0);
+        if id.checked_add(missing as u64).is_none() {
+            return Err(TreeError::Validation(
+                "No ids are left to assign to nodes".into(),
+            ));
+        }
+        for node in self.nodes.values_mut().filter(|node| node.id.is_none()) {
+            id += 1;
+            Arc::make_mut(node).id = Some(id);
+        }
+        Ok(())
+    }
+
+    // Returns the error for a node which does not exist, suggesting the closest existing key
+    pub(crate) fn missing_node(&self, node_key: &str) -> TreeError {
+        let keys = self.nodes.keys().map(String::as_str);
+        TreeError::NodeDNE {
+            key: node_key.to_owned(),
+            suggestion: suggest::closest(node_key, keys).map(ToOwned::to_owned),
+        }
+    }
+
+    /// Insert a [`Node`], indexed by its [`Node::key`]. If a node with the same key exists, it is replaced in place and returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The [`Node`] to insert.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Tree, Node};
+    /// let mut tree = Tree::new();
+    /// assert!(tree.insert_node(Node::new("x", "Some node.")).is_none());
+    /// let old = tree.insert_node(Node::new("x", "Another node.")).unwrap();
+    /// assert_eq!("Some node.", old.dialogue);
+    /// assert_eq!(1, tree.len());
+    /// ```
+    pub fn insert_node(&mut self, node: Node) -> Option<Node> {
+        self.nodes
+            .insert(node.key.clone(), Arc::new(node))
+            .map(Arc::unwrap_or_clone)
+    }
+
+    /// Remove and return the [`Node`] indexed by a node key, preserving the order of the remaining nodes.
+    /// This method will return [`None`] if the node does not exist.
+    ///
+    /// If the removed node was the root node, the root key is cleared. If it was the current node, the current key is rewound to the root key. Entry points to the removed node are removed.
+    ///
+    /// # Arguments
+    ///
+    /// * `node_key` - A string slice that holds the key of the node.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Tree, Node};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("root", "The root."));
+    /// tree.insert_node(Node::new("x", "Some node."));
+    /// tree.set_root_key("root").unwrap();
+    /// tree.set_current_key("x").unwrap();
+    /// assert!(tree.remove_node("x").is_some());
+    /// assert_eq!("root", tree.current_key().unwrap());
+    /// ```
+    pub fn remove_node(&mut self, node_key: &str) -> Option<Node> {
+        let node = self.nodes.shift_remove(node_key)?;
+        if self.root_key.as_deref() == Some(node_key) {
+            self.root_key = None;
+        }
+        if self.current_key.as_deref() == Some(node_key) {
+            self.current_key = self.root_key.clone();
+        }
+        self.entries.retain(|_, entry_key| entry_key != node_key);
+        Some(Arc::unwrap_or_clone(node))
+    }
+
+    /// Returns the number of nodes this tree can hold without reallocating.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::Tree;
+    /// assert_eq!(0, Tree::new().capacity());
+    /// ```
+    pub fn capacity(&self) -> usize {
+        self.nodes.capacity()
+    }
+
+    /// Reserve space for at least a number of additional nodes before reallocating.
+    ///
+    /// # Arguments
+    ///
+    /// * `additional` - The number of additional nodes to allocate space for.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Node, Tree};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.reserve(100);
+    /// assert!(tree.capacity() >= 101);
+    /// ```
+    pub fn reserve(&mut self, additional: usize) {
+        self.nodes.reserve(additional);
+    }
+
+    /// Shrink the storage of this tree's nodes as much as possible, e.g. to trim memory after loading on constrained platforms.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Node, Tree};
+    /// let mut tree = Tree::with_capacity(100);
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.shrink_to_fit();
+    /// assert!(tree.capacity() < 100);
+    /// ```
+    pub fn shrink_to_fit(&mut self) {
+        self.nodes.shrink_to_fit();
+    }
+
+    /// Returns an iterator over the [`Node`]s in this tree, in the order they were inserted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Tree, Node};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("a", "A node."));
+    /// tree.insert_node(Node::new("b", "B node."));
+    /// let keys: Vec<&str> = tree.nodes().map(|node| node.key()).collect();
+    /// assert_eq!(vec!["a", "b"], keys);
+    /// ```
+    pub fn nodes(&self) -> impl DoubleEndedIterator<Item = &Node> + ExactSizeIterator {
+        self.nodes.values().map(|node| &**node)
+    }
+
+    /// Returns an iterator which mutably references the [`Node`]s in this tree, in the order they were inserted. Every node still shared with a clone of this tree is copied, so prefer [`node_mut`][`Tree#method.node_mut`] to edit a few nodes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Tree, Node};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("a", "A node."));
+    /// for node in tree.nodes_mut() {
+    ///     node.dialogue.make_ascii_uppercase();
+    /// }
+    /// assert_eq!("A NODE.", tree.node("a").unwrap().dialogue);
+    /// ```
+    pub fn nodes_mut(&mut self) -> impl DoubleEndedIterator<Item = &mut Node> + ExactSizeIterator {
+        self.nodes.values_mut().map(Arc::make_mut)
+    }
+
+    /// Returns the number of [`Node`]s in this tree.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Tree, Node};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("x", "Some node."));
+    /// assert_eq!(1, tree.len());
+    /// ```
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns whether this tree has no [`Node`]s.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::Tree;
+    /// assert!(Tree::new().is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Returns an [`Option`] which references a copy of the root [`Node::key`].
     /// This method will return [`None`] if the tree has no root set.
     ///
     /// # Examples
@@ -105,7 +521,7 @@ impl Tree {
     /// ```
     /// use convo::{Tree, Node};
     /// let mut tree = Tree::new();
-    /// unsafe { tree.set_root_key_unchecked("root"); }
+    /// tree.raw().set_root_key("root");
     /// assert_eq!("root", tree.root_key().unwrap());
     /// ```
     pub fn root_key(&self) -> Option<&String> {
@@ -122,22 +538,22 @@ impl Tree {
     /// let mut tree = Tree::new();
     /// let root_og = Node::new("root", "The only node.");
     /// let root_copy = root_og.clone();
-    /// tree.nodes.insert("root".to_owned(), root_copy);
+    /// tree.insert_node(root_copy);
     /// tree.set_root_key("root").unwrap();
     /// assert_eq!(&root_og, tree.root_node().unwrap());
     /// ```
     pub fn root_node(&self) -> Option<&Node> {
-        self.nodes.get(self.root_key.as_ref()?)
+        self.node(self.root_key.as_ref()?)
     }
 
     // Sets the root node to a new node defined by a key
     // Also sets current to root node if current is None
 
-    /// Try to set the root node key for a [`Tree`]. If [`Tree#current`][`Tree#structfield.current`] is [`None`], this will automatically be dually initialized to the root key. If you want to set the root node without any [validation checks](https://github.com/simbleau/convo/blob/dev/FORMATTING.md#validation-rules), try [`set_root_key_unchecked`][`Tree#method.set_root_key_unchecked`].
+    /// Try to set the root node key for a [`Tree`]. If [`Tree#current`][`Tree#structfield.current`] is [`None`], this will automatically be dually initialized to the root key. If you want to set the root node without any [validation checks](https://github.com/simbleau/convo/blob/dev/FORMATTING.md#validation-rules), try [`RawTree::set_root_key`] through [`raw`][`Tree#method.raw`].
     ///
     /// # Arguments
     ///
-    /// * `node_key` - A string slice that holds a unique identifier which indexes a [`Node`] in the [`Tree#nodes`][`Tree#structfield.nodes`].
+    /// * `node_key` - A string slice that holds a unique identifier which indexes a [`Node`] in the [`Tree::nodes`].
     ///
     /// # Errors
     ///
@@ -149,13 +565,13 @@ impl Tree {
     /// use convo::{Tree, Node};
     /// let mut tree = Tree::new();
     /// let root_node = Node::new("root", "The only node.");
-    /// tree.nodes.insert("root".to_owned(), root_node);
+    /// tree.insert_node(root_node);
     /// tree.set_root_key("root").unwrap();
     /// ```
     pub fn set_root_key(&mut self, node_key: &str) -> Result<(), TreeError> {
         // Check existence
-        if !self.nodes.contains_key(node_key) {
-            return Err(TreeError::NodeDNE(node_key.to_owned()));
+        if !self.contains_node(node_key) {
+            return Err(self.missing_node(node_key));
         }
 
         self.root_key = Some(node_key.to_owned());
@@ -165,30 +581,39 @@ impl Tree {
         Ok(())
     }
 
-    /// Set the root node key for a [`Tree`]. Unlike [`set_root_key`][`Tree#method.set_root_key`], this method will **not** incur side effects to [`Tree#current`][`Tree#structfield.current`] in any way.
+    /// Try to insert a [`Node`] and set it as the root node in one call, as [`set_root_key`][`Tree#method.set_root_key`] would.
     ///
-    /// # Safety
+    /// # Arguments
     ///
-    /// This function doesn't perform [validation checks](https://github.com/simbleau/convo/blob/dev/FORMATTING.md#validation-rules).
+    /// * `node` - The [`Node`] to insert as the root node.
     ///
-    /// # Arguments
+    /// # Errors
     ///
-    /// * `node_key` - A string slice that holds a unique identifier which indexes a [`Node`] in the [`Tree#nodes`][`Tree#structfield.nodes`].
+    /// * A [`TreeError`] will be returned if a node with the same key already exists in the tree. The tree is left unchanged on error.
     ///
     /// # Examples
     ///
     /// ```
-    /// use convo::{Tree, Node};
+    /// use convo::{Node, Tree};
     /// let mut tree = Tree::new();
-    /// let root_node = Node::new("root", "The only node.");
-    /// tree.nodes.insert("root".to_owned(), root_node);
-    /// unsafe { tree.set_root_key_unchecked("root"); }
+    /// tree.set_root_node(Node::new("root", "The only node.")).unwrap();
+    /// assert_eq!("root", tree.root_key().unwrap());
+    /// assert!(tree.set_root_node(Node::new("root", "Another node.")).is_err());
     /// ```
-    pub unsafe fn set_root_key_unchecked(&mut self, node_key: &str) {
-        self.root_key = Some(node_key.to_owned());
+    pub fn set_root_node(&mut self, node: Node) -> Result<(), TreeError> {
+        if self.contains_node(&node.key) {
+            return Err(TreeError::Validation(format!(
+                "Node `{}` already exists",
+                node.key
+            )));
+        }
+
+        let node_key = node.key.clone();
+        self.insert_node(node);
+        self.set_root_key(&node_key)
     }
 
-    /// Returns an [`Option`] which references a copy of the current [`Node#key`][`Node#structfield.key`].
+    /// Returns an [`Option`] which references a copy of the current [`Node::key`].
     /// This method will return [`None`] if the tree has no current set.
     ///
     /// # Examples
@@ -196,7 +621,7 @@ impl Tree {
     /// ```
     /// use convo::{Tree, Node};
     /// let mut tree = Tree::new();
-    /// unsafe { tree.set_current_key_unchecked("x"); }
+    /// tree.raw().set_current_key("x");
     /// assert_eq!("x", tree.current_key().unwrap());
     /// ```
     pub fn current_key(&self) -> Option<&String> {
@@ -213,19 +638,19 @@ impl Tree {
     /// let mut tree = Tree::new();
     /// let root_og = Node::new("x", "Some node.");
     /// let root_copy = root_og.clone();
-    /// tree.nodes.insert("x".to_owned(), root_copy);
+    /// tree.insert_node(root_copy);
     /// tree.set_current_key("x").unwrap();
     /// assert_eq!(&root_og, tree.current_node().unwrap());
     /// ```
     pub fn current_node(&self) -> Option<&Node> {
-        self.nodes.get(self.current_key.as_ref()?)
+        self.node(self.current_key.as_ref()?)
     }
 
-    /// Try to set the current node key for a [`Tree`]. If you want to set the current node without any [validation checks](https://github.com/simbleau/convo/blob/dev/FORMATTING.md#validation-rules), try [`set_current_key_unchecked`][`Tree#method.set_current_key_unchecked`].
+    /// Try to set the current node key for a [`Tree`]. If you want to set the current node without any [validation checks](https://github.com/simbleau/convo/blob/dev/FORMATTING.md#validation-rules), try [`RawTree::set_current_key`] through [`raw`][`Tree#method.raw`].
     ///
     /// # Arguments
     ///
-    /// * `node_key` - A string slice that holds a unique identifier which indexes a [`Node`] in the [`Tree#nodes`][`Tree#structfield.nodes`].
+    /// * `node_key` - A string slice that holds a unique identifier which indexes a [`Node`] in the [`Tree::nodes`].
     ///
     /// # Errors
     ///
@@ -237,43 +662,652 @@ impl Tree {
     /// use convo::{Tree, Node};
     /// let mut tree = Tree::new();
     /// let current_node = Node::new("x", "Some node.");
-    /// tree.nodes.insert("x".to_owned(), current_node);
+    /// tree.insert_node(current_node);
     /// tree.set_current_key("x").unwrap();
     /// ```
     pub fn set_current_key(&mut self, node_key: &str) -> Result<(), TreeError> {
         // Check existence
-        if !self.nodes.contains_key(node_key) {
-            return Err(TreeError::NodeDNE(node_key.to_owned()));
+        if !self.contains_node(node_key) {
+            return Err(self.missing_node(node_key));
         }
 
         self.current_key = Some(node_key.to_owned());
         Ok(())
     }
 
-    /// Set the current node key for a [`Tree`].
+    /// Returns the [`Node`]s which have at least one [`Link`] to a node key, in the order they are stored. This searches every node, so prefer [`TreeIndex::links_to`] for repeated queries.
+    ///
+    /// # Arguments
     ///
-    /// # Safety
+    /// * `node_key` - A string slice that holds the key of the linked node.
     ///
-    /// This function doesn't perform [validation checks](https://github.com/simbleau/convo/blob/dev/FORMATTING.md#validation-rules).
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Link, Node, Tree};
+    /// let mut tree = Tree::new();
+    /// let mut start = Node::new("start", "Hello!");
+    /// let end = Node::new("end", "Goodbye!");
+    /// Link::link(&mut start, &end, "Bye!");
+    /// tree.insert_node(start);
+    /// tree.insert_node(end);
+    /// assert_eq!("start", tree.links_to("end")[0].key());
+    /// assert!(tree.links_to("start").is_empty());
+    /// ```
+    pub fn links_to(&self, node_key: &str) -> Vec<&Node> {
+        self.nodes()
+            .filter(|node| node.links.iter().any(|link| link.to_key == node_key))
+            .collect()
+    }
+
+    /// Try to insert many [`Node`]s at once, keyed by their [`Node::key`]. Capacity is reserved up front and the batch is validated in a single pass once every node is inserted, so links may reference nodes later in the batch.
     ///
     /// # Arguments
     ///
-    /// * `node_key` - A string slice that holds a unique identifier which indexes a [`Node`] in the [`Tree#nodes`][`Tree#structfield.nodes`].
+    /// * `nodes` - The [`Node`]s to insert.
+    ///
+    /// # Errors
+    ///
+    /// * A [`TreeError`] will be returned if a node key already exists in the tree or repeats within the batch, or if a link of an inserted node targets a node which does not exist. The tree is left unchanged on error.
     ///
     /// # Examples
     ///
     /// ```
-    /// use convo::{Tree, Node};
+    /// use convo::{Link, Node, Tree};
     /// let mut tree = Tree::new();
-    /// let current_node = Node::new("x", "Some node.");
-    /// tree.nodes.insert("x".to_owned(), current_node);
-    /// unsafe { tree.set_current_key_unchecked("x"); }
+    /// let mut start = Node::new("start", "Hello!");
+    /// start.links.push(Link::new("end", "Bye!"));
+    /// let end = Node::new("end", "Goodbye!");
+    /// tree.extend_nodes(vec![start, end]).unwrap();
+    /// assert_eq!(2, tree.len());
+    ///
+    /// let mut lost = Node::new("lost", "Where am I?");
+    /// lost.links.push(Link::new("nowhere", "..."));
+    /// assert!(tree.extend_nodes(vec![lost]).is_err());
+    /// assert_eq!(2, tree.len());
     /// ```
-    pub unsafe fn set_current_key_unchecked(&mut self, node_key: &str) {
-        self.current_key = Some(node_key.to_owned());
+    pub fn extend_nodes<I>(&mut self, nodes: I) -> Result<(), TreeError>
+    where
+        I: IntoIterator<Item = Node>,
+    {
+        let nodes = nodes.into_iter();
+        let len = self.len();
+        self.nodes.reserve(nodes.size_hint().0);
+
+        // Existing nodes are never replaced, so truncating restores the tree
+        for node in nodes {
+            if self.contains_node(&node.key) {
+                let err = TreeError::Validation(format!("Node `{}` already exists", node.key));
+                self.nodes.truncate(len);
+                return Err(err);
+            }
+            self.insert_node(node);
+        }
+
+        let missing = self
+            .nodes
+            .values()
+            .skip(len)
+            .flat_map(|node| &node.links)
+            .find(|link| !self.contains_node(&link.to_key))
+            .map(|link| link.to_key.clone());
+        if let Some(node_key) = missing {
+            let err = self.missing_node(&node_key);
+            self.nodes.truncate(len);
+            return Err(err);
+        }
+
+        Ok(())
     }
 
-    /// Try to rewind the current node key for a [`Tree`] back to the root key by cloning the root key. If you want to rewind the current node without any [validation checks](https://github.com/simbleau/convo/blob/dev/FORMATTING.md#validation-rules), try [`rewind_unchecked`][`Tree#method.rewind_unchecked`].
+    /// Normalizes every node key, and every key linked to, to Unicode Normalization Form C (NFC), so keys which look identical are equal, e.g. `café` written with a combining accent.
+    ///
+    /// # Errors
+    ///
+    /// * A [`TreeError`] will be returned if two node keys are identical once normalized. The tree is left unchanged on error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Link, Node, Tree};
+    /// let mut tree = Tree::new();
+    /// let mut start = Node::new("start", "Hello!");
+    /// start.links.push(Link::new("cafe\u{301}", "Coffee?"));
+    /// tree.insert_node(start);
+    /// tree.insert_node(Node::new("cafe\u{301}", "One espresso."));
+    /// tree.normalize_keys().unwrap();
+    /// assert!(tree.contains_node("caf\u{e9}"));
+    /// assert_eq!("caf\u{e9}", tree.node("start").unwrap().links[0].to_key);
+    ///
+    /// tree.insert_node(Node::new("cafe\u{301}", "Another espresso."));
+    /// assert!(tree.normalize_keys().is_err());
+    /// ```
+    pub fn normalize_keys(&mut self) -> Result<(), TreeError> {
+        let nfc = |key: &str| -> String {
+            match is_nfc(key) {
+                true => key.to_owned(),
+                false => key.nfc().collect(),
+            }
+        };
+
+        // Keys which only differ in normalization would replace each other
+        let mut normalized = BTreeMap::new();
+        for key in self.nodes.keys() {
+            if let Some(other) = normalized.insert(nfc(key), key) {
+                return Err(TreeError::Validation(format!(
+                    "Node keys `{}` and `{}` are identical once normalized",
+                    other, key
+                )));
+            }
+        }
+
+        self.nodes = core::mem::take(&mut self.nodes)
+            .into_iter()
+            .map(|(key, mut node)| {
+                let node_mut = Arc::make_mut(&mut node);
+                node_mut.key = nfc(&key);
+                for link in &mut node_mut.links {
+                    link.to_key = nfc(&link.to_key);
+                }
+                (node.key.clone(), node)
+            })
+            .collect();
+        self.root_key = self.root_key.as_deref().map(nfc);
+        self.current_key = self.current_key.as_deref().map(nfc);
+        for entry_key in self.entries.values_mut() {
+            *entry_key = nfc(entry_key);
+        }
+
+        Ok(())
+    }
+
+    /// Returns a [`TreeDiff`] listing the nodes which were added, changed, or removed in another version of this tree, e.g. one re-imported after its file was saved.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The new version of the tree.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Node, Tree};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// let mut edited = tree.clone();
+    /// edited.node_mut("start").unwrap().dialogue = "Hi!".to_owned();
+    /// assert_eq!(vec!["start"], tree.diff(&edited).changed);
+    /// ```
+    pub fn diff(&self, other: &Tree) -> TreeDiff {
+        let mut diff = TreeDiff::default();
+        for node in other.nodes() {
+            match self.node(&node.key) {
+                None => diff.added.push(node.key.clone()),
+                Some(old) if old != node => diff.changed.push(node.key.clone()),
+                Some(_) => {}
+            }
+        }
+        diff.removed = self
+            .nodes
+            .keys()
+            .filter(|key| !other.contains_node(key))
+            .cloned()
+            .collect();
+        diff
+    }
+
+    /// Update this tree in place to another version of it, replacing only the nodes which were added, changed, or removed, and return the [`TreeDiff`] applied. Nodes take the order of the new version, and its root, link order, metadata, availability, variables, and entry points are taken as well.
+    ///
+    /// The current node is kept if it still exists, or rewound to the root otherwise. Walkers of the old version can continue on the new one with [`Walker::reload`][`crate::Walker::reload`].
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The new version of the tree.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Node, Tree};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.insert_node(Node::new("end", "Goodbye!"));
+    /// tree.set_root_key("start").unwrap();
+    ///
+    /// let mut edited = Tree::new();
+    /// edited.insert_node(Node::new("end", "Farewell!"));
+    /// edited.insert_node(Node::new("start", "Hello!"));
+    /// edited.set_root_key("start").unwrap();
+    ///
+    /// let diff = tree.update(edited.clone());
+    /// assert_eq!(vec!["end"], diff.changed);
+    /// assert_eq!(edited, tree);
+    /// ```
+    pub fn update(&mut self, other: Tree) -> TreeDiff {
+        let diff = self.diff(&other);
+        for key in &diff.removed {
+            self.nodes.shift_remove(key);
+        }
+
+        // Once every node up to an index is in place, the rest of the nodes follow it
+        for (index, (key, node)) in other.nodes.into_iter().enumerate() {
+            if self.nodes.get(&key) != Some(&node) {
+                self.nodes.insert(key.clone(), node);
+            }
+            if let Some(from) = self.nodes.get_index_of(&key).filter(|from| *from != index) {
+                self.nodes.move_index(from, index);
+            }
+        }
+
+        self.root_key = other.root_key;
+        if let Some(key) = &self.current_key {
+            if !self.contains_node(key) {
+                self.current_key = self.root_key.clone();
+            }
+        }
+        self.link_order = other.link_order;
+        self.meta = other.meta;
+        self.availability = other.availability;
+        self.variables = other.variables;
+        self.entries = other.entries;
+        diff
+    }
+
+    /// Returns a stable, human-readable rendering of this tree, e.g. for snapshot tests. Trees which are equal render identically, whatever order their nodes were inserted in.
+    ///
+    /// Nodes are sorted by key, as are tags, variables, entry points, and custom metadata. Links and lines keep their authored order, which changes how a conversation plays. Strings are quoted and escaped, and fields which hold their defaults are left out.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Link, Node, Tree};
+    /// let mut tree = Tree::new();
+    /// let mut start = Node::new("start", "Hello!");
+    /// start.links.push(Link::new("end", "Bye."));
+    /// let mut end = Node::new("end", "Goodbye!\nSee you.");
+    /// end.end = true;
+    /// tree.insert_node(start);
+    /// tree.insert_node(end);
+    /// tree.set_root_key("start").unwrap();
+    /// assert_eq!(
+    ///     "root: \"start\"\nnode \"end\":\n  dialogue: \"Goodbye!\\nSee you.\"\n  end: true\nnode \"start\":\n  dialogue: \"Hello!\"\n  link 1: \"end\": \"Bye.\"\n",
+    ///     tree.to_canonical_string()
+    /// );
+    /// ```
+    pub fn to_canonical_string(&self) -> String {
+        let mut out = String::new();
+        let mut line = |indent: usize, text: String| {
+            out.push_str(&"  ".repeat(indent));
+            out.push_str(&text);
+            out.push('\n');
+        };
+
+        // The tree
+        if let Some(root_key) = &self.root_key {
+            line(0, format!("root: {:?}", root_key));
+        }
+        if self.current_key.is_some() && self.current_key != self.root_key {
+            line(
+                0,
+                format!("current: {:?}", self.current_key.as_deref().unwrap_or("")),
+            );
+        }
+        if self.link_order != LinkOrder::default() {
+            line(0, format!("link_order: {:?}", self.link_order));
+        }
+        let meta = &self.meta;
+        for (name, field) in [
+            ("title", &meta.title),
+            ("author", &meta.author),
+            ("description", &meta.description),
+        ] {
+            if let Some(field) = field {
+                line(0, format!("meta.{}: {:?}", name, field));
+            }
+        }
+        if !meta.tags.is_empty() {
+            line(0, format!("meta.tags: {:?}", sorted(&meta.tags)));
+        }
+        for (name, field) in &meta.custom {
+            line(0, format!("meta.{:?}: {:?}", name, field));
+        }
+        if self.availability != Availability::default() {
+            line(0, format!("availability: {:?}", self.availability));
+        }
+        for (name, ty) in &self.variables {
+            line(0, format!("variable {:?}: {}", name, ty));
+        }
+        for (name, node_key) in &self.entries {
+            line(0, format!("entry {:?}: {:?}", name, node_key));
+        }
+
+        // The nodes, by key
+        let mut nodes: Vec<&Node> = self.nodes().collect();
+        nodes.sort_unstable_by(|a, b| a.key.cmp(&b.key));
+        for node in nodes {
+            line(0, format!("node {:?}:", node.key));
+            if let Some(id) = node.id {
+                line(1, format!("id: {}", id));
+            }
+            if node.kind != NodeKind::default() {
+                line(1, format!("kind: {:?}", node.kind));
+            }
+            match node.lines.is_empty() {
+                true => line(1, format!("dialogue: {:?}", node.dialogue)),
+                false => {
+                    for (i, dialogue_line) in node.lines.iter().enumerate() {
+                        let emotion = match &dialogue_line.emotion {
+                            Some(emotion) => format!(" ({:?})", emotion),
+                            None => String::new(),
+                        };
+                        line(
+                            1,
+                            format!(
+                                "line {}: {:?}: {:?}{}",
+                                i + 1,
+                                dialogue_line.speaker,
+                                dialogue_line.text,
+                                emotion
+                            ),
+                        );
+                    }
+                }
+            }
+            if !node.tags.is_empty() {
+                line(1, format!("tags: {:?}", sorted(&node.tags)));
+            }
+            if node.availability != Availability::default() {
+                line(1, format!("availability: {:?}", node.availability));
+            }
+            if !node.pacing.is_empty() {
+                line(1, format!("pacing: {:?}", node.pacing));
+            }
+            for (i, direction) in node.stage.iter().enumerate() {
+                line(1, format!("stage {}: {}", i + 1, direction));
+            }
+            if node.end {
+                line(1, "end: true".to_owned());
+            }
+            if node.todo {
+                line(1, "todo: true".to_owned());
+            }
+            for (i, link) in node.links.iter().enumerate() {
+                // Silent links are written with null dialogue, as they are in dialogue files
+                let dialogue = match &link.dialogue {
+                    Some(dialogue) => format!("{:?}", dialogue),
+                    None => "~".to_owned(),
+                };
+                line(
+                    1,
+                    format!("link {}: {:?}: {}", i + 1, link.to_key, dialogue),
+                );
+                if let Some(priority) = link.priority {
+                    line(2, format!("priority: {}", priority));
+                }
+                if let Some(weight) = link.weight {
+                    line(2, format!("weight: {}", weight));
+                }
+                if let Some(condition) = &link.condition {
+                    line(2, format!("if: {}", condition));
+                }
+                for (name, value) in &link.set {
+                    let value = match value {
+                        Value::Bool(value) => format!("{}", value),
+                        Value::Int(value) => format!("{}", value),
+                        Value::String(value) => format!("{:?}", value),
+                    };
+                    line(2, format!("set {:?}: {}", name, value));
+                }
+                for (character, amount) in &link.affinity {
+                    line(2, format!("affinity {:?}: {:+}", character, amount));
+                }
+                if let WhenUnmet::Disabled(reason) = &link.when_unmet {
+                    line(2, format!("disabled: {:?}", reason));
+                }
+                if let Some(group) = &link.group {
+                    line(2, format!("group: {:?}", group));
+                }
+                if !link.tags.is_empty() {
+                    line(2, format!("tags: {:?}", sorted(&link.tags)));
+                }
+            }
+        }
+        out
+    }
+
+    /// Returns a [`TreeIndex`] which answers reachability, parent, and path queries for this tree without re-walking it. The index must be rebuilt after the tree changes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Link, Node, Tree};
+    /// let mut tree = Tree::new();
+    /// let mut start = Node::new("start", "Hello!");
+    /// let end = Node::new("end", "Goodbye!");
+    /// Link::link(&mut start, &end, "Bye!");
+    /// tree.insert_node(start);
+    /// tree.insert_node(end);
+    /// tree.set_root_key("start").unwrap();
+    /// let index = tree.build_index();
+    /// assert!(index.is_reachable("end"));
+    /// ```
+    pub fn build_index(&self) -> TreeIndex<'_> {
+        TreeIndex::new(self)
+    }
+
+    /// Returns the [`LinkOrder`] used to present links when walking.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{LinkOrder, Tree};
+    /// let tree = Tree::new();
+    /// assert_eq!(LinkOrder::Authored, tree.link_order());
+    /// ```
+    pub fn link_order(&self) -> LinkOrder {
+        self.link_order
+    }
+
+    /// Set the [`LinkOrder`] used to present links when walking. See also: [`current_links`][`Tree#method.current_links`].
+    ///
+    /// # Arguments
+    ///
+    /// * `order` - A [`LinkOrder`] which determines the order links are presented in.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{LinkOrder, Tree};
+    /// let mut tree = Tree::new();
+    /// tree.set_link_order(LinkOrder::Priority);
+    /// assert_eq!(LinkOrder::Priority, tree.link_order());
+    /// ```
+    pub fn set_link_order(&mut self, order: LinkOrder) {
+        self.link_order = order;
+    }
+
+    /// Returns the [`Meta`] describing this tree.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::Tree;
+    /// let tree = Tree::new();
+    /// assert!(tree.meta().is_empty());
+    /// ```
+    pub fn meta(&self) -> &Meta {
+        &self.meta
+    }
+
+    /// Returns a mutable reference to the [`Meta`] describing this tree.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::Tree;
+    /// let mut tree = Tree::new();
+    /// tree.meta_mut().author = Some("simbleau".into());
+    /// assert_eq!(Some("simbleau"), tree.meta().author.as_deref());
+    /// ```
+    pub fn meta_mut(&mut self) -> &mut Meta {
+        &mut self.meta
+    }
+
+    /// Returns the [`Availability`] of this tree, which decides when the conversation may be offered.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::Tree;
+    /// let tree = Tree::new();
+    /// assert!(tree.availability().is_empty());
+    /// ```
+    pub fn availability(&self) -> &Availability {
+        &self.availability
+    }
+
+    /// Returns a mutable reference to the [`Availability`] of this tree.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{HostState, Tree};
+    /// let mut tree = Tree::new();
+    /// tree.availability_mut().cooldown = Some(600);
+    /// let mut state = HostState { time: 300, ..HostState::default() };
+    /// state.last_used.insert("gossip".into(), 0);
+    /// assert!(!tree.availability().allows(&state, "gossip"));
+    /// ```
+    pub fn availability_mut(&mut self) -> &mut Availability {
+        &mut self.availability
+    }
+
+    /// Returns the declared variables of this tree, mapping names to [`Type`]s. Link conditions and assignments are checked against them by [`lint`][`Tree#method.lint`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::Tree;
+    /// let tree = Tree::new();
+    /// assert!(tree.variables().is_empty());
+    /// ```
+    pub fn variables(&self) -> &BTreeMap<String, Type> {
+        &self.variables
+    }
+
+    /// Returns a mutable reference to the declared variables of this tree.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Tree, Type};
+    /// let mut tree = Tree::new();
+    /// tree.variables_mut().insert("gold".into(), Type::Int);
+    /// assert_eq!(Some(&Type::Int), tree.variables().get("gold"));
+    /// ```
+    pub fn variables_mut(&mut self) -> &mut BTreeMap<String, Type> {
+        &mut self.variables
+    }
+
+    /// Returns the named entry points of this tree, mapped to the keys of their nodes. Walkers start at an entry point with [`Walker::start_at`][`crate::Walker::start_at`], e.g. to open differently depending on the state of the game.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Node, Tree};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("snarl", "What do you want?"));
+    /// tree.set_entry("angry", "snarl").unwrap();
+    /// assert_eq!(Some(&"snarl".to_owned()), tree.entries().get("angry"));
+    /// ```
+    pub fn entries(&self) -> &BTreeMap<String, String> {
+        &self.entries
+    }
+
+    /// Try to set a named entry point of this tree to a node key, replacing any entry point of the same name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - A string slice that holds the name of the entry point.
+    /// * `node_key` - A string slice that holds the key of the node the entry point starts at.
+    ///
+    /// # Errors
+    ///
+    /// * A [`TreeError`] will be returned if the node does not exist in the node map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Node, Tree};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("greet", "Well met!"));
+    /// assert!(tree.set_entry("greeting", "greet").is_ok());
+    /// assert!(tree.set_entry("angry", "snarl").is_err());
+    /// ```
+    pub fn set_entry(&mut self, name: &str, node_key: &str) -> Result<(), TreeError> {
+        if !self.contains_node(node_key) {
+            return Err(self.missing_node(node_key));
+        }
+
+        self.entries.insert(name.to_owned(), node_key.to_owned());
+        Ok(())
+    }
+
+    /// Remove a named entry point of this tree, returning the key of the node it started at.
+    /// This method will return [`None`] if the entry point does not exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - A string slice that holds the name of the entry point.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Node, Tree};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("greet", "Well met!"));
+    /// tree.set_entry("greeting", "greet").unwrap();
+    /// assert_eq!(Some("greet".to_owned()), tree.remove_entry("greeting"));
+    /// assert!(tree.entries().is_empty());
+    /// ```
+    pub fn remove_entry(&mut self, name: &str) -> Option<String> {
+        self.entries.remove(name)
+    }
+
+    // Returns the error for an entry point which does not exist, suggesting the closest existing name
+    pub(crate) fn missing_entry(&self, name: &str) -> TreeError {
+        let names = self.entries.keys().map(String::as_str);
+        TreeError::Validation(format!(
+            "Entry point `{}` does not exist{}",
+            name,
+            suggest::did_you_mean(suggest::closest(name, names))
+        ))
+    }
+
+    /// Returns the [`Link`]s of the current [`Node`], ordered by the tree's [`LinkOrder`].
+    /// This method will return an empty [`Vec`] if the tree has no current node.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Link, LinkOrder, Node, Tree};
+    /// let mut tree = Tree::new();
+    /// let mut node = Node::new("start", "How are you?");
+    /// node.links.push(Link::new("start", "Pardon?"));
+    /// let mut important = Link::new("start", "About that quest...");
+    /// important.priority = Some(10);
+    /// node.links.push(important);
+    /// tree.insert_node(node);
+    /// tree.set_root_key("start").unwrap();
+    /// tree.set_link_order(LinkOrder::Priority);
+    /// assert_eq!("About that quest...", tree.current_links()[0].text());
+    /// ```
+    pub fn current_links(&self) -> Vec<&Link> {
+        match self.current_node() {
+            Some(node) => node.ordered_links(self.link_order),
+            None => vec![],
+        }
+    }
+
+    /// Try to rewind the current node key for a [`Tree`] back to the root key by cloning the root key. If you want to rewind the current node without any [validation checks](https://github.com/simbleau/convo/blob/dev/FORMATTING.md#validation-rules), try [`RawTree::rewind`] through [`raw`][`Tree#method.raw`].
     ///
     /// # Errors
     ///
@@ -286,8 +1320,8 @@ impl Tree {
     /// let mut tree = Tree::new();
     /// let root_node = Node::new("root", "The root.");
     /// let current_node = Node::new("x", "Some node.");
-    /// tree.nodes.insert("root".to_owned(), root_node);
-    /// tree.nodes.insert("x".to_owned(), current_node);
+    /// tree.insert_node(root_node);
+    /// tree.insert_node(current_node);
     /// tree.set_root_key("root").unwrap();
     /// tree.set_current_key("x").unwrap();
     /// tree.rewind().unwrap();
@@ -302,25 +1336,19 @@ impl Tree {
         Ok(())
     }
 
-    /// Rewind the current node key for a [`Tree`] back to the root key by cloning the root key.
-    ///
-    /// # Safety
-    ///
-    /// This function doesn't perform [validation checks](https://github.com/simbleau/convo/blob/dev/FORMATTING.md#validation-rules).
+    /// Returns a [`RawTree`] which edits this tree without [validation checks](https://github.com/simbleau/convo/blob/dev/FORMATTING.md#validation-rules).
     ///
     /// # Examples
     ///
     /// ```
     /// use convo::{Tree, Node};
     /// let mut tree = Tree::new();
-    /// let current_node = Node::new("x", "Some node.");
-    /// tree.nodes.insert("x".to_owned(), current_node);
-    /// tree.set_current_key("x").unwrap();
-    /// unsafe { tree.rewind_unchecked(); }
-    /// assert!(tree.current_key().is_none()); // Because the root was `None`.
+    /// tree.insert_node(Node::new("root", "The only node."));
+    /// tree.raw().set_root_key("root").rewind();
+    /// assert_eq!("root", tree.current_key().unwrap());
     /// ```
-    pub unsafe fn rewind_unchecked(&mut self) {
-        self.current_key = self.root_key.clone();
+    pub fn raw(&mut self) -> RawTree<'_> {
+        RawTree::new(self)
     }
 
     /// Clear the entire tree.
@@ -331,10 +1359,10 @@ impl Tree {
     /// use convo::{Tree, Node};
     /// let mut tree = Tree::new();
     /// let root_node = Node::new("root", "The root.");
-    /// tree.nodes.insert("root".to_owned(), root_node);
+    /// tree.insert_node(root_node);
     /// tree.set_root_key("root").unwrap();
     /// tree.reset();
-    /// assert_eq!(0, tree.nodes.len());
+    /// assert_eq!(0, tree.len());
     /// assert!(tree.root_key().is_none());
     /// assert!(tree.current_key().is_none());
     /// ```
@@ -345,7 +1373,14 @@ impl Tree {
     }
 }
 
-#[cfg(test)]
+// Returns strings in sorted order, for output which does not depend on authored order
+fn sorted(strings: &[String]) -> Vec<&String> {
+    let mut strings: Vec<&String> = strings.iter().collect();
+    strings.sort_unstable();
+    strings
+}
+
+#[cfg(feature = "std")]
 #[test]
 fn test_try_from() {
     let bad_source = "not valid source";
@@ -358,6 +1393,7 @@ fn test_try_from() {
     assert!(Tree::try_from(&good_source).is_ok());
 }
 
+#[cfg(feature = "std")]
 #[test]
 fn test_try_export() {
     let mut tree = Tree::new();
@@ -369,7 +1405,7 @@ fn test_try_export() {
 
     // Qualify the tree
     let root_node = Node::new("root", "The only node.");
-    tree.nodes.insert("root".to_owned(), root_node);
+    tree.insert_node(root_node);
     tree.set_root_key("root").unwrap();
 
     // Should pass because tree is valid
@@ -385,7 +1421,7 @@ fn test_root_key() {
     // Should be none because no root key has been set yet
     assert!(tree.root_key().is_none());
 
-    unsafe { tree.set_root_key_unchecked("root") }
+    tree.raw().set_root_key("root");
 
     // Should be Some
     assert_eq!("root", tree.root_key().unwrap());
@@ -399,12 +1435,12 @@ fn test_root_node() {
     assert!(tree.root_node().is_none());
 
     // Should be None still as the node does not exist in the map
-    unsafe { tree.set_root_key_unchecked("root") }
+    tree.raw().set_root_key("root");
     assert!(tree.root_node().is_none());
 
     // After insertion, it should exist
     let root_node = Node::new("root", "A node.");
-    tree.nodes.insert("root".to_owned(), root_node);
+    tree.insert_node(root_node);
     assert!(tree.root_node().is_some());
 }
 
@@ -417,7 +1453,7 @@ fn test_set_root_key() {
 
     // Should pass because node exists
     let root_node = Node::new("root", "Anode.");
-    tree.nodes.insert("root".to_owned(), root_node);
+    tree.insert_node(root_node);
     assert!(tree.set_root_key("root").is_ok());
 
     // Ensure root key was set
@@ -425,12 +1461,26 @@ fn test_set_root_key() {
 }
 
 #[test]
-fn test_set_root_key_unchecked() {
+fn test_set_root_node() {
     let mut tree = Tree::new();
-    unsafe { tree.set_root_key_unchecked("root") }
 
-    // Ensure root key was set
+    // Should insert the node, and set the root and current keys
+    assert!(tree.set_root_node(Node::new("root", "A node.")).is_ok());
     assert_eq!("root", tree.root_key().unwrap());
+    assert_eq!("root", tree.current_key().unwrap());
+    assert_eq!(1, tree.len());
+
+    // Should fail without replacing the existing node
+    assert!(tree
+        .set_root_node(Node::new("root", "Another node."))
+        .is_err());
+    assert_eq!("A node.", tree.root_node().unwrap().dialogue);
+
+    // Should move the root, but not the current node
+    tree.set_root_node(Node::new("other", "Other node."))
+        .unwrap();
+    assert_eq!("other", tree.root_key().unwrap());
+    assert_eq!("root", tree.current_key().unwrap());
 }
 
 #[test]
@@ -440,7 +1490,7 @@ fn test_current_key() {
     // Should be none because no current key has been set yet
     assert!(tree.current_key().is_none());
 
-    unsafe { tree.set_current_key_unchecked("current") }
+    tree.raw().set_current_key("current");
 
     // Should be Some
     assert_eq!("current", tree.current_key().unwrap());
@@ -454,12 +1504,12 @@ fn test_current_node() {
     assert!(tree.current_node().is_none());
 
     // Should be None still as the node does not exist in the map
-    unsafe { tree.set_current_key_unchecked("current") }
+    tree.raw().set_current_key("current");
     assert!(tree.current_node().is_none());
 
     // After insertion, it should exist
     let current_node = Node::new("current", "A node.");
-    tree.nodes.insert("current".to_owned(), current_node);
+    tree.insert_node(current_node);
     assert!(tree.current_node().is_some());
 }
 
@@ -472,30 +1522,21 @@ fn test_set_current_key() {
 
     // Should pass because node exists
     let current_node = Node::new("current", "A node.");
-    tree.nodes.insert("current".to_owned(), current_node);
+    tree.insert_node(current_node);
     assert!(tree.set_current_key("current").is_ok());
 
     // Ensure current key was set
     assert_eq!("current", tree.current_key().unwrap());
 }
 
-#[test]
-fn test_set_current_key_unchecked() {
-    let mut tree = Tree::new();
-    unsafe { tree.set_current_key_unchecked("current") }
-
-    // Ensure current key was set
-    assert_eq!("current", tree.current_key().unwrap());
-}
-
 #[test]
 fn test_rewind() {
     // Set up tree with a root and additional node
     let mut tree = Tree::new();
     let root_node = Node::new("root", "The root node.");
     let current_node = Node::new("current", "A node.");
-    tree.nodes.insert("root".to_owned(), root_node);
-    tree.nodes.insert("current".to_owned(), current_node);
+    tree.insert_node(root_node);
+    tree.insert_node(current_node);
 
     tree.set_current_key("current").unwrap();
 
@@ -510,34 +1551,258 @@ fn test_rewind() {
 }
 
 #[test]
-fn test_rewind_unchecked() {
+fn test_reset() {
     // Set up tree with a root and additional node
     let mut tree = Tree::new();
     let root_node = Node::new("root", "The root node.");
-    let current_node = Node::new("current", "A node.");
-    tree.nodes.insert("root".to_owned(), root_node);
-    tree.nodes.insert("current".to_owned(), current_node);
+    tree.insert_node(root_node);
+    tree.set_root_key("root").unwrap();
 
-    tree.set_current_key("current").unwrap();
+    tree.reset();
 
-    // Test rewind
-    assert_eq!("current", tree.current_key().unwrap());
-    unsafe { tree.rewind_unchecked() }
+    // Test reset
+    assert_eq!(0, tree.len());
     assert!(tree.root_node().is_none());
+    assert!(tree.current_node().is_none());
 }
 
 #[test]
-fn test_reset() {
-    // Set up tree with a root and additional node
+fn test_current_links() {
     let mut tree = Tree::new();
-    let root_node = Node::new("root", "The root node.");
-    tree.nodes.insert("root".to_owned(), root_node);
+
+    // Should be empty because no current key has been set yet
+    assert!(tree.current_links().is_empty());
+
+    // Set up a node with a prioritized link
+    let mut node = Node::new("current", "A node.");
+    node.links.push(Link::new("current", "First authored."));
+    let mut important = Link::new("current", "Second authored.");
+    important.priority = Some(1);
+    node.links.push(important);
+    tree.insert_node(node);
+    tree.set_current_key("current").unwrap();
+
+    // Authored order by default
+    assert_eq!("First authored.", tree.current_links()[0].text());
+
+    // Priority order when requested
+    tree.set_link_order(LinkOrder::Priority);
+    assert_eq!("Second authored.", tree.current_links()[0].text());
+}
+
+#[test]
+fn test_links_to() {
+    let mut tree = Tree::new();
+    let mut a = Node::new("a", "A node.");
+    let mut b = Node::new("b", "B node.");
+    let c = Node::new("c", "C node.");
+    Link::link(&mut a, &c, "To c.");
+    Link::link(&mut a, &c, "To c, again.");
+    Link::link(&mut b, &c, "To c.");
+    Link::link(&mut b, &a, "To a.");
+    tree.insert_node(a);
+    tree.insert_node(b);
+    tree.insert_node(c);
+
+    // Each linking node is listed once
+    let keys: Vec<&str> = tree
+        .links_to("c")
+        .iter()
+        .map(|node| node.key.as_str())
+        .collect();
+    assert_eq!(vec!["a", "b"], keys);
+    assert_eq!(1, tree.links_to("a").len());
+    assert!(tree.links_to("b").is_empty());
+}
+
+#[test]
+fn test_extend_nodes() {
+    let mut tree = Tree::new();
+    tree.insert_node(Node::new("start", "Hello!"));
+
+    // Links may reference existing nodes and nodes later in the batch
+    let nodes = (0..1000).map(|i| {
+        let mut node = Node::new(format!("n{}", i).as_str(), "Generated.");
+        node.links.push(Link::new("start", "Back."));
+        node.links
+            .push(Link::new(format!("n{}", (i + 1) % 1000).as_str(), "Next."));
+        node
+    });
+    tree.extend_nodes(nodes).unwrap();
+    assert_eq!(1001, tree.len());
+    assert_eq!("n0", tree.nodes.get_index(1).unwrap().0);
+
+    // Duplicate keys leave the tree unchanged
+    let before = tree.clone();
+    let nodes = vec![Node::new("new", "New."), Node::new("n5", "Again.")];
+    assert!(matches!(
+        tree.extend_nodes(nodes),
+        Err(TreeError::Validation(_))
+    ));
+    let nodes = vec![Node::new("new", "New."), Node::new("new", "Again.")];
+    assert!(tree.extend_nodes(nodes).is_err());
+    assert_eq!(before, tree);
+
+    // Missing targets leave the tree unchanged
+    let mut lost = Node::new("lost", "Where am I?");
+    lost.links.push(Link::new("nowhere", "..."));
+    assert!(matches!(
+        tree.extend_nodes(vec![Node::new("new", "New."), lost]),
+        Err(TreeError::NodeDNE { key, suggestion: None }) if key == "nowhere"
+    ));
+    assert_eq!(before, tree);
+
+    // Missing keys suggest the closest existing key
+    assert!(matches!(
+        tree.set_root_key("strat"),
+        Err(TreeError::NodeDNE { suggestion: Some(suggestion), .. }) if suggestion == "start"
+    ));
+}
+
+#[test]
+fn test_remove_node() {
+    let mut tree = Tree::new();
+    for key in ["root", "a", "b"] {
+        tree.insert_node(Node::new(key, "A node."));
+    }
     tree.set_root_key("root").unwrap();
+    tree.set_current_key("b").unwrap();
 
-    tree.reset();
+    // Should preserve the order of the remaining nodes
+    assert_eq!("a", tree.remove_node("a").unwrap().key);
+    assert!(tree.remove_node("a").is_none());
+    let keys: Vec<&str> = tree.nodes().map(|node| node.key.as_str()).collect();
+    assert_eq!(vec!["root", "b"], keys);
 
-    // Test reset
-    assert_eq!(0, tree.nodes.len());
-    assert!(tree.root_node().is_none());
-    assert!(tree.current_node().is_none());
+    // Removing the current node should rewind it
+    tree.remove_node("b").unwrap();
+    assert_eq!("root", tree.current_key().unwrap());
+
+    // Removing the root node should clear both keys
+    tree.remove_node("root").unwrap();
+    assert!(tree.root_key().is_none());
+    assert!(tree.current_key().is_none());
+    assert!(tree.is_empty());
+}
+
+#[test]
+fn test_from_nodes() {
+    let mut start = Node::new("start", "Hello!");
+    let end = Node::new("end", "Goodbye!");
+    Link::link(&mut start, &end, "Bye!");
+
+    // Should set both root and current
+    let tree = Tree::from_nodes("start", vec![start.clone(), end.clone()]).unwrap();
+    assert_eq!(2, tree.len());
+    assert_eq!("start", tree.root_key().unwrap());
+    assert_eq!("start", tree.current_key().unwrap());
+
+    // Should fail when the root does not exist
+    assert!(matches!(
+        Tree::from_nodes("middle", vec![start.clone(), end.clone()]),
+        Err(TreeError::NodeDNE { key, .. }) if key == "middle"
+    ));
+
+    // Should fail on dangling links and repeated keys
+    assert!(Tree::from_nodes("start", vec![start.clone()]).is_err());
+    assert!(Tree::from_nodes("start", vec![start, end.clone(), end]).is_err());
+}
+
+#[test]
+fn test_update() {
+    let mut tree = Tree::new();
+    tree.insert_node(Node::new("a", "A."));
+    tree.insert_node(Node::new("b", "B."));
+    tree.insert_node(Node::new("c", "C."));
+    tree.set_root_key("a").unwrap();
+    tree.set_current_key("c").unwrap();
+
+    // Nodes take the new order, and the current node is rewound when it is removed
+    let mut edited = Tree::new();
+    edited.insert_node(Node::new("d", "D."));
+    edited.insert_node(Node::new("b", "B!"));
+    edited.insert_node(Node::new("a", "A."));
+    edited.set_root_key("b").unwrap();
+    let diff = tree.update(edited);
+    assert_eq!(vec!["d"], diff.added);
+    assert_eq!(vec!["b"], diff.changed);
+    assert_eq!(vec!["c"], diff.removed);
+    let keys: Vec<&str> = tree.nodes().map(|node| node.key.as_str()).collect();
+    assert_eq!(vec!["d", "b", "a"], keys);
+    assert_eq!("B!", tree.node("b").unwrap().dialogue);
+    assert_eq!("b", tree.root_key().unwrap());
+    assert_eq!("b", tree.current_key().unwrap());
+
+    // The current node is kept when it still exists
+    tree.set_current_key("a").unwrap();
+    assert!(tree.update(tree.clone()).is_empty());
+    assert_eq!("a", tree.current_key().unwrap());
+}
+
+#[test]
+fn test_to_canonical_string() {
+    use crate::{condition::Condition, node::Line};
+
+    let mut start = Node::new("start", "");
+    start.set_lines(vec![
+        Line::new("guard", "Halt!"),
+        Line {
+            emotion: Some("tired".into()),
+            ..Line::new("captain", "Let them \"through\".")
+        },
+    ]);
+    start.tags = vec!["b".into(), "a".into()];
+    let mut pass = Link::new("gate", "Thanks.");
+    pass.priority = Some(2);
+    pass.condition = Some(Condition::parse("gold >= 10").unwrap());
+    pass.set.push(("gold".into(), Value::Int(-10)));
+    pass.affinity.push(("guard".into(), 5));
+    pass.when_unmet = WhenUnmet::Disabled("Too poor".into());
+    start.links.push(pass);
+    let mut gate = Node::new("gate", "The gate opens.");
+    gate.end = true;
+
+    let mut tree = Tree::new();
+    tree.insert_node(start.clone());
+    tree.insert_node(gate.clone());
+    tree.set_root_key("start").unwrap();
+    tree.set_entry("gate", "gate").unwrap();
+    tree.variables_mut().insert("gold".into(), Type::Int);
+
+    // Node insertion order and tag order do not matter
+    let mut reordered = Tree::new();
+    start.tags.reverse();
+    reordered.insert_node(gate);
+    reordered.insert_node(start);
+    reordered.set_root_key("start").unwrap();
+    reordered.set_entry("gate", "gate").unwrap();
+    reordered.variables_mut().insert("gold".into(), Type::Int);
+    assert_eq!(tree.to_canonical_string(), reordered.to_canonical_string());
+
+    assert_eq!(
+        r#"root: "start"
+variable "gold": int
+entry "gate": "gate"
+node "gate":
+  dialogue: "The gate opens."
+  end: true
+node "start":
+  line 1: "guard": "Halt!"
+  line 2: "captain": "Let them \"through\"." ("tired")
+  tags: ["a", "b"]
+  link 1: "gate": "Thanks."
+    priority: 2
+    if: gold >= 10
+    set "gold": -10
+    affinity "guard": +5
+    disabled: "Too poor"
+"#,
+        tree.to_canonical_string()
+    );
+
+    // The current node is only shown when it is not the root node
+    tree.set_current_key("gate").unwrap();
+    assert!(tree
+        .to_canonical_string()
+        .starts_with("root: \"start\"\ncurrent: \"gate\"\n"));
 }
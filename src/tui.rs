@@ -0,0 +1,392 @@
+//! A terminal player for walking [`Tree`]s, built on [`ratatui`].
+//!
+//! The player shows the conversation history, the current dialogue, and the available choices. Choices are selected with the arrow keys (or their number) and confirmed with `Enter`. Press `q` or `Esc` to quit.
+
+use crate::{error::TreeError, filter::ContentFilter, tree::Tree, walker::Walker};
+
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout},
+    style::{Style, Stylize},
+    text::{Line, Text},
+    widgets::{Block, List, ListState, Paragraph, Wrap},
+    DefaultTerminal, Frame,
+};
+use std::io;
+
+/// A [`HistoryEntry`] records a [`crate::Node`] which was visited and the choice made there.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct HistoryEntry {
+    /// The dialogue of the visited node.
+    pub dialogue: String,
+
+    /// The dialogue of the link chosen at the visited node.
+    pub choice: String,
+}
+
+/// A [`Player`] plays a conversation in the terminal.
+#[derive(Debug, Clone)]
+pub struct Player<'a> {
+    walker: Walker<&'a Tree>,
+    history: Vec<HistoryEntry>,
+    selected: usize,
+    quit: bool,
+}
+
+impl<'a> Player<'a> {
+    /// Try to return a [`Player`] which starts at the root [`crate::Node`] of a [`Tree`].
+    ///
+    /// # Arguments
+    ///
+    /// * `tree` - A [`Tree`] to play.
+    ///
+    /// # Errors
+    ///
+    /// * A [`TreeError`] will be returned if the tree has no root set or the root node does not exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{importer, tui::Player};
+    /// let tree = importer::import("examples/dialogue_files/ex_1.convo.yml").unwrap();
+    /// let player = Player::new(&tree).unwrap();
+    /// ```
+    pub fn new(tree: &'a Tree) -> Result<Self, TreeError> {
+        Ok(Player {
+            walker: Walker::new(tree)?,
+            history: vec![],
+            selected: 0,
+            quit: false,
+        })
+    }
+
+    /// Returns the [`Walker`] driving this player.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{importer, tui::Player};
+    /// let tree = importer::import("examples/dialogue_files/ex_1.convo.yml").unwrap();
+    /// let player = Player::new(&tree).unwrap();
+    /// assert_eq!("start", player.walker().current_key());
+    /// ```
+    pub fn walker(&self) -> &Walker<&'a Tree> {
+        &self.walker
+    }
+
+    /// Set a [`ContentFilter`] which screens the dialogue displayed by this player. See [`Walker::set_filter`].
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` - The [`ContentFilter`] to apply, replacing any previous filter.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{filter::MaskWords, importer, tui::Player};
+    /// let tree = importer::import("examples/dialogue_files/ex_1.convo.yml").unwrap();
+    /// let mut player = Player::new(&tree).unwrap();
+    /// player.set_filter(MaskWords::new(&["darn"], '*'));
+    /// ```
+    pub fn set_filter<F>(&mut self, filter: F)
+    where
+        F: ContentFilter + 'static,
+    {
+        self.walker.set_filter(filter);
+    }
+
+    /// Returns the [`HistoryEntry`]s of the conversation so far, oldest first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{importer, tui::Player};
+    /// let tree = importer::import("examples/dialogue_files/ex_1.convo.yml").unwrap();
+    /// let player = Player::new(&tree).unwrap();
+    /// assert!(player.history().is_empty());
+    /// ```
+    pub fn history(&self) -> &[HistoryEntry] {
+        &self.history
+    }
+
+    /// Returns the index of the highlighted choice.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{importer, tui::Player};
+    /// let tree = importer::import("examples/dialogue_files/ex_1.convo.yml").unwrap();
+    /// let player = Player::new(&tree).unwrap();
+    /// assert_eq!(0, player.selected());
+    /// ```
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Returns whether the player has quit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{importer, tui::Player};
+    /// use ratatui::crossterm::event::KeyCode;
+    /// let tree = importer::import("examples/dialogue_files/ex_1.convo.yml").unwrap();
+    /// let mut player = Player::new(&tree).unwrap();
+    /// player.handle_key(KeyCode::Char('q')).unwrap();
+    /// assert!(player.has_quit());
+    /// ```
+    pub fn has_quit(&self) -> bool {
+        self.quit
+    }
+
+    /// Try to handle a key press.
+    ///
+    /// # Arguments
+    ///
+    /// * `code` - The [`KeyCode`] which was pressed.
+    ///
+    /// # Errors
+    ///
+    /// * A [`TreeError`] will be returned if the chosen link leads to a node which does not exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{importer, tui::Player};
+    /// use ratatui::crossterm::event::KeyCode;
+    /// let tree = importer::import("examples/dialogue_files/ex_1.convo.yml").unwrap();
+    /// let mut player = Player::new(&tree).unwrap();
+    /// player.handle_key(KeyCode::Down).unwrap();
+    /// player.handle_key(KeyCode::Enter).unwrap();
+    /// assert_eq!("newton", player.walker().current_key());
+    /// ```
+    pub fn handle_key(&mut self, code: KeyCode) -> Result<(), TreeError> {
+        let choices = self.walker.choices().len();
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Up if choices > 0 => self.selected = (self.selected + choices - 1) % choices,
+            KeyCode::Down if choices > 0 => self.selected = (self.selected + 1) % choices,
+            KeyCode::Char(c) if c.is_ascii_digit() => {
+                // Choices are numbered from 1
+                if let Some(index) = c.to_digit(10).and_then(|n| (n as usize).checked_sub(1)) {
+                    if index < choices {
+                        self.selected = index;
+                    }
+                }
+            }
+            KeyCode::Enter if choices > 0 => {
+                let dialogue = self.walker.dialogue().into_owned();
+                let choice = self
+                    .walker
+                    .choice_dialogues()
+                    .swap_remove(self.selected)
+                    .into_owned();
+                self.walker.choose(self.selected)?;
+                self.history.push(HistoryEntry { dialogue, choice });
+                self.selected = 0;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Render the player into a [`Frame`].
+    ///
+    /// # Arguments
+    ///
+    /// * `frame` - The [`Frame`] to render into.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{importer, tui::Player};
+    /// use ratatui::{backend::TestBackend, Terminal};
+    /// let tree = importer::import("examples/dialogue_files/ex_1.convo.yml").unwrap();
+    /// let player = Player::new(&tree).unwrap();
+    /// let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+    /// terminal.draw(|frame| player.render(frame)).unwrap();
+    /// ```
+    pub fn render(&self, frame: &mut Frame) {
+        let [history_area, dialogue_area, choices_area] = Layout::vertical([
+            Constraint::Min(3),
+            Constraint::Length(5),
+            Constraint::Length(self.walker.choices().len().max(1) as u16 + 2),
+        ])
+        .areas(frame.area());
+
+        // History, scrolled to the most recent entries
+        let mut history = Text::default();
+        for entry in &self.history {
+            history.push_line(Line::from(entry.dialogue.as_str()).dim());
+            history.push_line(Line::from(format!("> {}", entry.choice)).dim().italic());
+        }
+        let visible = history_area.height.saturating_sub(2);
+        let scroll = (history.height() as u16).saturating_sub(visible);
+        frame.render_widget(
+            Paragraph::new(history)
+                .block(Block::bordered().title("History"))
+                .scroll((scroll, 0)),
+            history_area,
+        );
+
+        // Current dialogue
+        let node = self.walker.current_node();
+        frame.render_widget(
+            Paragraph::new(self.walker.dialogue())
+                .wrap(Wrap { trim: true })
+                .block(Block::bordered().title(format!("[{}]", node.key))),
+            dialogue_area,
+        );
+
+        // Choices
+        if self.walker.is_finished() {
+            frame.render_widget(
+                Paragraph::new("The end. Press `q` to quit.".italic())
+                    .block(Block::bordered().title("Choices")),
+                choices_area,
+            );
+        } else {
+            let choices = self
+                .walker
+                .choice_dialogues()
+                .iter()
+                .enumerate()
+                .map(|(i, dialogue)| format!("{}. {}", i + 1, dialogue))
+                .collect::<List>()
+                .block(Block::bordered().title("Choices"))
+                .highlight_style(Style::new().reversed())
+                .highlight_symbol("> ");
+            let mut state = ListState::default().with_selected(Some(self.selected));
+            frame.render_stateful_widget(choices, choices_area, &mut state);
+        }
+    }
+
+    /// Try to run the player in a terminal until the player quits.
+    ///
+    /// # Arguments
+    ///
+    /// * `terminal` - The terminal to draw on.
+    ///
+    /// # Errors
+    ///
+    /// * An [`io::Error`] will be returned if the terminal cannot be drawn on or read from.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use convo::{importer, tui::Player};
+    /// let tree = importer::import("examples/dialogue_files/ex_1.convo.yml").unwrap();
+    /// let mut terminal = ratatui::init();
+    /// let result = Player::new(&tree).unwrap().run(&mut terminal);
+    /// ratatui::restore();
+    /// result.unwrap();
+    /// ```
+    pub fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        while !self.quit {
+            terminal.draw(|frame| self.render(frame))?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    self.handle_key(key.code).map_err(tree_error)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Try to play a [`Tree`] in the terminal until the user quits. The terminal is restored afterwards.
+///
+/// # Arguments
+///
+/// * `tree` - A [`Tree`] to play.
+///
+/// # Errors
+///
+/// * An [`io::Error`] will be returned if the tree cannot be walked or the terminal cannot be used.
+///
+/// # Examples
+///
+/// ```no_run
+/// use convo::{importer, tui};
+/// let tree = importer::import("examples/dialogue_files/ex_1.convo.yml").unwrap();
+/// tui::play(&tree).unwrap();
+/// ```
+pub fn play(tree: &Tree) -> io::Result<()> {
+    let mut player = Player::new(tree).map_err(tree_error)?;
+    let mut terminal = ratatui::init();
+    let result = player.run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+fn tree_error(error: TreeError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("{:?}", error))
+}
+
+#[cfg(test)]
+#[test]
+fn test_handle_key() {
+    let tree = crate::importer::import("examples/dialogue_files/ex_1.convo.yml").unwrap();
+    let mut player = Player::new(&tree).unwrap();
+
+    // Selection wraps around
+    player.handle_key(KeyCode::Up).unwrap();
+    assert_eq!(1, player.selected());
+    player.handle_key(KeyCode::Down).unwrap();
+    assert_eq!(0, player.selected());
+
+    // Numbers select choices, starting from 1
+    player.handle_key(KeyCode::Char('2')).unwrap();
+    assert_eq!(1, player.selected());
+    player.handle_key(KeyCode::Char('9')).unwrap();
+    assert_eq!(1, player.selected());
+
+    // Enter confirms the selection and records history
+    player.handle_key(KeyCode::Enter).unwrap();
+    assert_eq!("newton", player.walker().current_key());
+    assert_eq!(0, player.selected());
+    assert_eq!(
+        vec![HistoryEntry {
+            dialogue: "You don't look like you're from around here.".to_owned(),
+            choice: "I came here from Newton.".to_owned(),
+        }],
+        player.history()
+    );
+
+    // Quit
+    assert!(!player.has_quit());
+    player.handle_key(KeyCode::Esc).unwrap();
+    assert!(player.has_quit());
+}
+
+#[test]
+fn test_render() {
+    use ratatui::{backend::TestBackend, Terminal};
+
+    let tree = crate::importer::import("examples/dialogue_files/ex_1.convo.yml").unwrap();
+    let mut player = Player::new(&tree).unwrap();
+    let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+    let screen = |terminal: &Terminal<TestBackend>| -> String {
+        let buffer = terminal.backend().buffer();
+        buffer
+            .content()
+            .chunks(buffer.area.width as usize)
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    // Shows the dialogue and highlights the first choice
+    terminal.draw(|frame| player.render(frame)).unwrap();
+    let text = screen(&terminal);
+    assert!(text.contains("You don't look like you're from around here."));
+    assert!(text.contains("> 1. I've lived here all my life!"));
+
+    // Shows the history and the ending
+    player.handle_key(KeyCode::Enter).unwrap();
+    player.handle_key(KeyCode::Enter).unwrap();
+    terminal.draw(|frame| player.render(frame)).unwrap();
+    let text = screen(&terminal);
+    assert!(text.contains("> I've lived here all my life!"));
+    assert!(text.contains("The end."));
+}
@@ -0,0 +1,164 @@
+use alloc::{string::String, vec::Vec};
+use core::{
+    fmt::{self, Write},
+    str::FromStr,
+};
+
+use crate::{condition, error::TreeError};
+
+/// A [`Value`] is the value of a conversation variable, e.g. a flag set by a quest.
+///
+/// A value is displayed as it is written in a [`Condition`][`crate::Condition`], and parsed from that form with [`str::parse`].
+///
+/// # Examples
+///
+/// ```
+/// use convo::Value;
+/// assert_eq!(Value::Int(10), Value::from(10));
+/// assert_eq!(Value::String("Springville".into()), Value::from("Springville"));
+/// assert_eq!(r#"'say "hi"'"#, Value::from(r#"say "hi""#).to_string());
+/// assert_eq!(Value::from("it's"), r#""it's""#.parse().unwrap());
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum Value {
+    /// A boolean, e.g. a flag.
+    Bool(bool),
+    /// A signed integer, e.g. a counter.
+    Int(i64),
+    /// A string.
+    String(String),
+}
+
+impl From<bool> for Value {
+    fn from(item: bool) -> Self {
+        Value::Bool(item)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(item: i64) -> Self {
+        Value::Int(item)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(item: &str) -> Self {
+        Value::String(item.into())
+    }
+}
+
+impl From<String> for Value {
+    fn from(item: String) -> Self {
+        Value::String(item)
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::Int(value) => write!(f, "{}", value),
+            Value::String(value) => {
+                // Quotes and backslashes are escaped, so strings parse back unchanged
+                let quote = if value.contains('"') && !value.contains('\'') {
+                    '\''
+                } else {
+                    '"'
+                };
+                f.write_char(quote)?;
+                for c in value.chars() {
+                    if c == quote || c == '\\' {
+                        f.write_char('\\')?;
+                    }
+                    f.write_char(c)?;
+                }
+                f.write_char(quote)
+            }
+        }
+    }
+}
+
+impl FromStr for Value {
+    type Err = TreeError;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        condition::parse_value(source)
+    }
+}
+
+/// A [`Type`] is the declared type of a conversation variable.
+///
+/// # Examples
+///
+/// ```
+/// use convo::{Type, Value};
+/// let mood = Type::Enum(vec!["happy".into(), "angry".into()]);
+/// assert!(mood.accepts(&Value::from("happy")));
+/// assert!(!mood.accepts(&Value::from("sad")));
+/// assert!(!Type::Int.accepts(&Value::Bool(true)));
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum Type {
+    /// A boolean, declared as `bool`.
+    Bool,
+    /// A signed integer, declared as `int`.
+    Int,
+    /// A string, declared as `string`.
+    String,
+    /// One of a list of string variants, declared as an array of the variants.
+    Enum(Vec<String>),
+}
+
+impl Type {
+    /// Returns whether a [`Value`] is of this type.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The [`Value`] to check.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Type, Value};
+    /// assert!(Type::String.accepts(&Value::from("Springville")));
+    /// ```
+    pub fn accepts(&self, value: &Value) -> bool {
+        match (self, value) {
+            (Type::Bool, Value::Bool(_)) | (Type::Int, Value::Int(_)) => true,
+            (Type::String, Value::String(_)) => true,
+            (Type::Enum(variants), Value::String(value)) => variants.contains(value),
+            _ => false,
+        }
+    }
+
+    /// Returns the [`Type`] of a [`Value`]. Strings are of type [`Type::String`].
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The [`Value`] to get the type of.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Type, Value};
+    /// assert_eq!(Type::Int, Type::of(&Value::Int(10)));
+    /// ```
+    pub fn of(value: &Value) -> Self {
+        match value {
+            Value::Bool(_) => Type::Bool,
+            Value::Int(_) => Type::Int,
+            Value::String(_) => Type::String,
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Bool => f.write_str("bool"),
+            Type::Int => f.write_str("int"),
+            Type::String => f.write_str("string"),
+            Type::Enum(variants) => write!(f, "[{}]", variants.join(", ")),
+        }
+    }
+}
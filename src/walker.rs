@@ -0,0 +1,3472 @@
+use alloc::{
+    borrow::{Cow, ToOwned},
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    string::String,
+    vec,
+    vec::Vec,
+};
+use core::ops::Deref;
+
+use crate::{
+    affinity,
+    availability::HostState,
+    condition::Host,
+    debugger::{Breakpoints, Debugger},
+    diff::Migration,
+    error::TreeError,
+    filter::{ContentFilter, FilterHandle},
+    inventory::{InventoryHandle, InventoryProvider},
+    link::{Link, WhenUnmet},
+    menu::{self, MenuHandle, MenuProvider},
+    node::{Node, NodeKind},
+    observer::{Evaluation, Journal, Observer, ObserverHandle, TranscriptEntry},
+    resume::ResumePoints,
+    rng::Rng,
+    tree::Tree,
+    value::Value,
+};
+
+/// A [`Walker`] walks a conversation through a [`Tree`]. Unlike [`Tree#current`][`Tree#method.current_key`], a walker never mutates the tree, so many walkers can walk the same tree at once.
+///
+/// A walker holds its tree through any pointer to a [`Tree`], such as a reference (`Walker<&Tree>`) or a shared handle (`Walker<SharedTree>`, see [`SharedTree`][`crate::SharedTree`]) when walkers must be owned or sent across threads.
+///
+/// A walker keeps the values of conversation variables, which decide the [`Condition`][`crate::Condition`]s of links and are assigned by the links it follows.
+#[derive(Debug, Clone)]
+pub struct Walker<T>
+where
+    T: Deref<Target = Tree>,
+{
+    /// The tree being walked.
+    tree: T,
+
+    /// The position and variables of the conversation.
+    state: State,
+
+    /// Saved states, mapped by bookmark name.
+    bookmarks: BTreeMap<String, State>,
+
+    /// The maximum number of variable snapshots kept, if snapshots are taken.
+    snapshot_limit: Option<usize>,
+
+    /// The filter applied to dialogue before it is displayed.
+    filter: Option<FilterHandle>,
+
+    /// The state of the game which decides the [`Availability`][`crate::Availability`] of nodes, if the host supplied one.
+    host_state: Option<HostState>,
+
+    /// The items the player holds, read by conditions, if the host supplied them.
+    inventory: Option<InventoryHandle>,
+
+    /// The entries of menu nodes, if the host supplied them.
+    menu: Option<MenuHandle>,
+
+    /// The choices generated from the entries of the current node, if it is a menu node.
+    offers: Vec<Link>,
+
+    /// The transcript of the conversation, and the observer notified of its events.
+    journal: Journal,
+
+    /// The pseudo-random generator and recent picks of random nodes.
+    dice: Dice,
+
+    /// The keys of nodes with breakpoints, and the debugger called when they are entered.
+    breakpoints: Breakpoints,
+
+    /// The tree name and speaker a [`ResumePoints`] records this conversation under, if it was started with [`Walker::resume_or_start`].
+    resume_key: Option<(String, String)>,
+}
+
+/// The position and variables of a walked conversation.
+#[derive(Debug, Clone)]
+struct State {
+    /// The key of the current node. It is guaranteed to index an existing [`Node`] in the tree.
+    current_key: String,
+
+    /// The keys of the nodes visited before the current node, oldest first.
+    history: Vec<String>,
+
+    /// The values of conversation variables, mapped by name.
+    variables: BTreeMap<String, Value>,
+
+    /// The reason the conversation was aborted, if it was.
+    aborted: Option<String>,
+
+    /// The names of the variables assigned by links followed during the conversation.
+    assigned: BTreeSet<String>,
+
+    /// The variables before each of the most recent steps, oldest first. Snapshots always cover the most recent steps of the history.
+    snapshots: VecDeque<Snapshot>,
+}
+
+/// The variables of a walked conversation before a step, and which of them had been assigned by links.
+#[derive(Debug, Clone)]
+struct Snapshot {
+    variables: BTreeMap<String, Value>,
+    assigned: BTreeSet<String>,
+}
+
+impl<T> Walker<T>
+where
+    T: Deref<Target = Tree>,
+{
+    /// Try to return a [`Walker`] which starts at the root [`Node`] of a [`Tree`].
+    ///
+    /// # Arguments
+    ///
+    /// * `tree` - A [`Tree`] to walk.
+    ///
+    /// # Errors
+    ///
+    /// * A [`TreeError`] will be returned if the tree has no root set or the root node does not exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let walker = Walker::new(&tree).unwrap();
+    /// assert_eq!("start", walker.current_key());
+    /// ```
+    pub fn new(tree: T) -> Result<Self, TreeError> {
+        let root_key = tree
+            .root_key()
+            .ok_or_else(TreeError::RootNotSet)?
+            .to_owned();
+        Walker::start(tree, root_key)
+    }
+
+    /// Try to return a [`Walker`] which starts at a named entry point of a [`Tree`] (see [`Tree::entries`]), rather than its root [`Node`].
+    ///
+    /// # Arguments
+    ///
+    /// * `tree` - A [`Tree`] to walk.
+    /// * `entry_name` - A string slice that holds the name of the entry point.
+    ///
+    /// # Errors
+    ///
+    /// * A [`TreeError`] will be returned if the entry point does not exist, or its node does not exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.insert_node(Node::new("snarl", "What do you want?"));
+    /// tree.set_root_key("start").unwrap();
+    /// tree.set_entry("angry", "snarl").unwrap();
+    /// let walker = Walker::start_at(&tree, "angry").unwrap();
+    /// assert_eq!("snarl", walker.current_key());
+    /// assert!(Walker::start_at(&tree, "happy").is_err());
+    /// ```
+    pub fn start_at(tree: T, entry_name: &str) -> Result<Self, TreeError> {
+        let entry_key = match tree.entries().get(entry_name) {
+            Some(entry_key) => entry_key.to_owned(),
+            None => return Err(tree.missing_entry(entry_name)),
+        };
+        Walker::start(tree, entry_key)
+    }
+
+    fn start(tree: T, start_key: String) -> Result<Self, TreeError> {
+        if !tree.contains_node(&start_key) {
+            return Err(tree.missing_node(&start_key));
+        }
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(node = %start_key, "conversation started");
+
+        let state = State {
+            current_key: start_key,
+            history: Vec::new(),
+            variables: BTreeMap::new(),
+            aborted: None,
+            assigned: BTreeSet::new(),
+            snapshots: VecDeque::new(),
+        };
+        let mut walker = Walker::with_state(tree, state);
+        walker.state.settle(
+            &walker.tree,
+            None,
+            &mut walker.journal,
+            Host::default(),
+            &mut walker.dice,
+            &walker.breakpoints,
+        );
+        Ok(walker)
+    }
+
+    // Returns a walker resting at the current node of a state, with a fresh transcript
+    fn with_state(tree: T, state: State) -> Self {
+        Walker {
+            journal: Journal::new(&state.current_key),
+            tree,
+            state,
+            bookmarks: BTreeMap::new(),
+            snapshot_limit: None,
+            filter: None,
+            host_state: None,
+            inventory: None,
+            menu: None,
+            offers: Vec::new(),
+            dice: Dice::default(),
+            breakpoints: Breakpoints::default(),
+            resume_key: None,
+        }
+    }
+
+    // Returns a walker resting where a saved conversation left off, without passing through automatic nodes again
+    #[cfg(feature = "storage")]
+    pub(crate) fn from_parts(
+        tree: T,
+        current_key: String,
+        history: Vec<String>,
+        variables: BTreeMap<String, Value>,
+        assigned: BTreeSet<String>,
+        aborted: Option<String>,
+    ) -> Result<Self, TreeError> {
+        // Every key must index an existing node, as the history may be rewound
+        if let Some(key) = core::iter::once(&current_key)
+            .chain(&history)
+            .find(|key| !tree.contains_node(key))
+        {
+            return Err(tree.missing_node(key));
+        }
+        let state = State {
+            current_key,
+            history,
+            variables,
+            aborted,
+            assigned,
+            snapshots: VecDeque::new(),
+        };
+        Ok(Walker::with_state(tree, state))
+    }
+
+    /// Try to return a [`Walker`] which continues a conversation at its resume point, or starts it at the root [`Node`] if it has none, or the resume point no longer exists. The walker's outcome can be recorded with [`ResumePoints::record`].
+    ///
+    /// # Arguments
+    ///
+    /// * `tree` - A [`Tree`] to walk.
+    /// * `points` - The [`ResumePoints`] of aborted conversations.
+    /// * `tree_name` - A string slice that holds the name the host gives the tree.
+    /// * `speaker` - A string slice that holds the name of the speaker.
+    ///
+    /// # Errors
+    ///
+    /// * A [`TreeError`] will be returned if the conversation starts at the root [`Node`], and the tree has no root set or the root node does not exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Node, ResumePoints, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.insert_node(Node::new("news", "Where was I?"));
+    /// tree.set_root_key("start").unwrap();
+    /// let mut points = ResumePoints::new();
+    /// points.insert("gossip", "baker", "news");
+    /// let walker = Walker::resume_or_start(&tree, &points, "gossip", "baker").unwrap();
+    /// assert_eq!("news", walker.current_key());
+    /// ```
+    pub fn resume_or_start(
+        tree: T,
+        points: &ResumePoints,
+        tree_name: &str,
+        speaker: &str,
+    ) -> Result<Self, TreeError> {
+        // A resumed conversation starts afresh at its resume point, passing through it if it is automatic
+        let mut walker = match points.get(tree_name, speaker) {
+            Some(node_key) if tree.contains_node(node_key) => {
+                #[cfg(feature = "tracing")]
+                tracing::debug!(node = %node_key, "conversation resumed");
+
+                Walker::start(tree, node_key.to_owned())?
+            }
+            _ => Walker::new(tree)?,
+        };
+        walker.resume_key = Some((tree_name.to_owned(), speaker.to_owned()));
+        Ok(walker)
+    }
+
+    /// Returns the [`Tree`] being walked.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let walker = Walker::new(&tree).unwrap();
+    /// assert_eq!(&tree, walker.tree());
+    /// ```
+    pub fn tree(&self) -> &Tree {
+        &self.tree
+    }
+
+    /// Continue this conversation on another version of its [`Tree`], e.g. one updated with [`Tree::update`] after its file was saved, returning whether it could. The conversation continues only if its current [`Node`] is unchanged in the new version; otherwise the walker is left on its old tree, and should be started again.
+    ///
+    /// Steps back through nodes which no longer exist are forgotten, along with everything before them, and bookmarks of nodes which no longer exist are removed. The entries of a [`NodeKind::Menu`] node are read again, as by [`restock`][`Walker#method.restock`].
+    ///
+    /// # Arguments
+    ///
+    /// * `tree` - The new version of the tree.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Link, Node, SharedTree, Tree};
+    /// let mut tree = Tree::new();
+    /// let mut start = Node::new("start", "Hello!");
+    /// start.links.push(Link::new("end", "Bye!"));
+    /// tree.insert_node(start);
+    /// tree.insert_node(Node::new("end", "Goodbye!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let mut shared = SharedTree::new(tree);
+    /// let mut walker = shared.walker().unwrap();
+    ///
+    /// // Editing another node keeps the conversation going
+    /// let mut edited = (*shared).clone();
+    /// edited.node_mut("end").unwrap().dialogue = "Farewell!".to_owned();
+    /// shared.edit(|tree| tree.update(edited));
+    /// assert!(walker.reload(shared.clone()));
+    /// assert_eq!("Farewell!", walker.choose(0).unwrap().dialogue);
+    ///
+    /// // Editing the current node does not
+    /// shared.edit(|tree| tree.node_mut("end").unwrap().dialogue = "See you!".to_owned());
+    /// assert!(!walker.reload(shared.clone()));
+    /// assert_eq!("Farewell!", walker.current_node().dialogue);
+    /// ```
+    pub fn reload(&mut self, tree: T) -> bool {
+        if tree.node(&self.state.current_key) != Some(self.current_node()) {
+            return false;
+        }
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(node = %self.state.current_key, "conversation reloaded");
+
+        self.state.forget_missing(&tree);
+        self.bookmarks
+            .retain(|_, state| state.forget_missing(&tree));
+        self.tree = tree;
+        self.restock();
+        true
+    }
+
+    /// Continue this conversation on a new version of its [`Tree`] whose nodes may have been renamed or removed, e.g. after a live game's dialogue is patched, returning whether it could. Keys are renamed by a [`Migration`] from the walker's tree to the new version. The conversation continues only if its current [`Node`] still exists; otherwise the walker is left on its old tree, and should be started again.
+    ///
+    /// Steps back through removed nodes are forgotten, along with everything before them, and bookmarks of removed nodes are removed. Breakpoints follow renamed nodes.
+    ///
+    /// # Arguments
+    ///
+    /// * `tree` - The new version of the tree.
+    /// * `migration` - The [`Migration`] from the walker's tree to the new version.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Link, Migration, Node, Tree, Walker};
+    /// let mut old = Tree::new();
+    /// let mut start = Node::new("start", "Hello!");
+    /// start.links.push(Link::new("shop", "Let's trade."));
+    /// old.insert_node(start);
+    /// old.insert_node(Node::new("shop", "What do you need?"));
+    /// old.set_root_key("start").unwrap();
+    /// old.assign_ids().unwrap();
+    /// let mut walker = Walker::new(&old).unwrap();
+    /// walker.choose(0).unwrap();
+    ///
+    /// // The shop was renamed to the store
+    /// let mut new = old.clone();
+    /// let store = new.remove_node("shop").unwrap().with_key("store");
+    /// new.insert_node(store);
+    /// new.node_mut("start").unwrap().links[0].to_key = "store".to_owned();
+    ///
+    /// let migration = Migration::new(&old, &new);
+    /// assert!(walker.migrate(&new, &migration));
+    /// assert_eq!("store", walker.current_key());
+    /// assert_eq!(&["start"], walker.history());
+    /// ```
+    pub fn migrate(&mut self, tree: T, migration: &Migration) -> bool {
+        let mut state = self.state.clone();
+        if !state.migrate(&tree, migration) {
+            return false;
+        }
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(node = %state.current_key, "conversation migrated");
+
+        self.state = state;
+        self.bookmarks
+            .retain(|_, state| state.migrate(&tree, migration));
+        self.breakpoints.keys = core::mem::take(&mut self.breakpoints.keys)
+            .into_iter()
+            .filter_map(|key| Some(migration.key(&key)?.to_owned()))
+            .collect();
+        self.tree = tree;
+        self.restock();
+        true
+    }
+
+    /// Returns the key of the current [`Node`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let walker = Walker::new(&tree).unwrap();
+    /// assert_eq!("start", walker.current_key());
+    /// ```
+    pub fn current_key(&self) -> &str {
+        &self.state.current_key
+    }
+
+    /// Returns the current [`Node`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let walker = Walker::new(&tree).unwrap();
+    /// assert_eq!("Hello!", walker.current_node().dialogue);
+    /// ```
+    pub fn current_node(&self) -> &Node {
+        // The current node is guaranteed to exist, and the tree cannot change while borrowed
+        &self.tree.nodes[&self.state.current_key]
+    }
+
+    /// Returns the keys of the [`Node`]s visited before the current node, oldest first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Link, Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// let mut start = Node::new("start", "Hello!");
+    /// let end = Node::new("end", "Goodbye!");
+    /// Link::link(&mut start, &end, "Bye!");
+    /// tree.insert_node(start);
+    /// tree.insert_node(end);
+    /// tree.set_root_key("start").unwrap();
+    /// let mut walker = Walker::new(&tree).unwrap();
+    /// walker.choose(0).unwrap();
+    /// assert_eq!(["start"], walker.history());
+    /// ```
+    pub fn history(&self) -> &[String] {
+        &self.state.history
+    }
+
+    /// Returns the values of conversation variables, mapped by name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let walker = Walker::new(&tree).unwrap();
+    /// assert!(walker.variables().is_empty());
+    /// ```
+    pub fn variables(&self) -> &BTreeMap<String, Value> {
+        &self.state.variables
+    }
+
+    /// Returns a mutable reference to the values of conversation variables, e.g. to set them from game state before walking.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Node, Tree, Value, Walker};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let mut walker = Walker::new(&tree).unwrap();
+    /// walker.variables_mut().insert("gold".into(), Value::Int(10));
+    /// assert_eq!(Some(&Value::Int(10)), walker.variables().get("gold"));
+    /// ```
+    pub fn variables_mut(&mut self) -> &mut BTreeMap<String, Value> {
+        &mut self.state.variables
+    }
+
+    /// Returns the affinity of a character, which is `0` until it is adjusted. See [`crate::affinity`].
+    ///
+    /// # Arguments
+    ///
+    /// * `character` - A string slice that holds the id of the character.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let walker = Walker::new(&tree).unwrap();
+    /// assert_eq!(0, walker.affinity("npc_bob"));
+    /// ```
+    pub fn affinity(&self, character: &str) -> i64 {
+        affinity::get(&self.state.variables, character)
+    }
+
+    /// Adjust the affinity of a character by an amount, e.g. from a gift given outside of dialogue, and return the new affinity. See [`crate::affinity`].
+    ///
+    /// # Arguments
+    ///
+    /// * `character` - A string slice that holds the id of the character.
+    /// * `amount` - The amount to add, which is negative to lower the affinity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Condition, Link, Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// let mut start = Node::new("start", "Hello!");
+    /// let mut secret = Link::new("start", "Tell me a secret.");
+    /// secret.condition = Some(Condition::parse("affinity(npc_bob) >= 20").unwrap());
+    /// start.links.push(secret);
+    /// tree.insert_node(start);
+    /// tree.set_root_key("start").unwrap();
+    ///
+    /// let mut walker = Walker::new(&tree).unwrap();
+    /// assert!(walker.choices().is_empty());
+    /// assert_eq!(25, walker.adjust("npc_bob", 25));
+    /// assert_eq!(1, walker.choices().len());
+    /// ```
+    pub fn adjust(&mut self, character: &str, amount: i64) -> i64 {
+        affinity::adjust(&mut self.state.variables, character, amount)
+    }
+
+    /// Save the current node, history, and variables under a name, replacing any bookmark of the same name. See [`restore`][`Walker#method.restore`].
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the bookmark.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let mut walker = Walker::new(&tree).unwrap();
+    /// walker.bookmark("before_boss");
+    /// ```
+    pub fn bookmark<N>(&mut self, name: N)
+    where
+        N: Into<String>,
+    {
+        self.bookmarks.insert(name.into(), self.state.clone());
+    }
+
+    /// Return to the current node, history, and variables saved by [`bookmark`][`Walker#method.bookmark`], returning the bookmarked [`Node`], or [`None`] if there is no bookmark of that name. The bookmark is kept, so it can be restored again.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the bookmark.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Link, Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// let mut start = Node::new("start", "Ready?");
+    /// let end = Node::new("end", "You lost!");
+    /// Link::link(&mut start, &end, "Fight!");
+    /// tree.insert_node(start);
+    /// tree.insert_node(end);
+    /// tree.set_root_key("start").unwrap();
+    /// let mut walker = Walker::new(&tree).unwrap();
+    /// walker.bookmark("before_boss");
+    /// walker.choose(0).unwrap();
+    /// assert_eq!("Ready?", walker.restore("before_boss").unwrap().dialogue);
+    /// assert!(walker.history().is_empty());
+    /// assert!(walker.restore("after_boss").is_none());
+    /// ```
+    pub fn restore(&mut self, name: &str) -> Option<&Node> {
+        let state = self.bookmarks.get(name)?.clone();
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(bookmark = %name, node = %state.current_key, "bookmark restored");
+
+        self.state = state;
+        self.restock();
+        let node = &self.tree.nodes[&self.state.current_key];
+        self.journal.enter(node);
+        Some(node)
+    }
+
+    /// Remove a bookmark, returning whether it existed.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the bookmark.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let mut walker = Walker::new(&tree).unwrap();
+    /// walker.bookmark("before_boss");
+    /// assert!(walker.remove_bookmark("before_boss"));
+    /// assert!(walker.restore("before_boss").is_none());
+    /// ```
+    pub fn remove_bookmark(&mut self, name: &str) -> bool {
+        self.bookmarks.remove(name).is_some()
+    }
+
+    /// Take a snapshot of the variables at every step, keeping at most `limit` of the most recent, so [`step_back`][`Walker#method.step_back`] and rewinding restore variables as well as position. Snapshots are off by default, since each one copies every variable.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - The maximum number of snapshots to keep. Older snapshots are dropped first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Link, Node, Tree, Value, Walker};
+    /// let mut tree = Tree::new();
+    /// let mut start = Node::new("start", "Buy a sword?");
+    /// let end = Node::new("end", "Thank you!");
+    /// Link::link(&mut start, &end, "Yes.");
+    /// start.links[0].set.push(("gold".into(), Value::Int(0)));
+    /// tree.insert_node(start);
+    /// tree.insert_node(end);
+    /// tree.set_root_key("start").unwrap();
+    /// let mut walker = Walker::new(&tree).unwrap();
+    /// walker.set_snapshot_limit(16);
+    /// walker.variables_mut().insert("gold".into(), Value::Int(10));
+    /// walker.choose(0).unwrap();
+    /// walker.step_back();
+    /// assert_eq!(Some(&Value::Int(10)), walker.variables().get("gold"));
+    /// ```
+    pub fn set_snapshot_limit(&mut self, limit: usize) {
+        self.snapshot_limit = Some(limit);
+        let snapshots = &mut self.state.snapshots;
+        snapshots.drain(..snapshots.len().saturating_sub(limit));
+    }
+
+    /// Stop taking snapshots of the variables, dropping any which were kept. See [`set_snapshot_limit`][`Walker#method.set_snapshot_limit`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let mut walker = Walker::new(&tree).unwrap();
+    /// walker.set_snapshot_limit(16);
+    /// walker.clear_snapshot_limit();
+    /// ```
+    pub fn clear_snapshot_limit(&mut self) {
+        self.snapshot_limit = None;
+        self.state.snapshots.clear();
+    }
+
+    /// Return to the previous [`Node`] in the [`history`][`Walker#method.history`], returning it, or [`None`] if no node was visited before the current node. Variables are restored if a snapshot of the step was kept, see [`set_snapshot_limit`][`Walker#method.set_snapshot_limit`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Link, Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// let mut start = Node::new("start", "Hello!");
+    /// let end = Node::new("end", "Goodbye!");
+    /// Link::link(&mut start, &end, "Bye!");
+    /// tree.insert_node(start);
+    /// tree.insert_node(end);
+    /// tree.set_root_key("start").unwrap();
+    /// let mut walker = Walker::new(&tree).unwrap();
+    /// walker.choose(0).unwrap();
+    /// assert_eq!("Hello!", walker.step_back().unwrap().dialogue);
+    /// assert!(walker.step_back().is_none());
+    /// ```
+    pub fn step_back(&mut self) -> Option<&Node> {
+        self.rewind_steps(1)
+    }
+
+    /// Return the given number of steps back through the [`history`][`Walker#method.history`], returning the [`Node`] reached, or [`None`] without moving if fewer steps were taken. An aborted conversation is resumed. Variables, and which of them were assigned by links, are restored if a snapshot of every unwound step was kept, see [`set_snapshot_limit`][`Walker#method.set_snapshot_limit`].
+    ///
+    /// # Arguments
+    ///
+    /// * `steps` - The number of steps to unwind. Unwinding 0 steps stays at the current node.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Link, Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// let mut start = Node::new("start", "Hello!");
+    /// let mut middle = Node::new("middle", "How are you?");
+    /// let end = Node::new("end", "Goodbye!");
+    /// Link::link(&mut start, &middle, "Hi!");
+    /// Link::link(&mut middle, &end, "Bye!");
+    /// tree.insert_node(start);
+    /// tree.insert_node(middle);
+    /// tree.insert_node(end);
+    /// tree.set_root_key("start").unwrap();
+    /// let mut walker = Walker::new(&tree).unwrap();
+    /// walker.narrate().for_each(drop);
+    /// assert!(walker.rewind_steps(3).is_none());
+    /// assert_eq!("start", walker.rewind_steps(2).unwrap().key());
+    /// ```
+    pub fn rewind_steps(&mut self, steps: usize) -> Option<&Node> {
+        let len = self.state.history.len();
+        if steps > len {
+            return None;
+        }
+        self.state.aborted = None;
+        if steps > 0 {
+            let mut unwound = self.state.history.split_off(len - steps);
+            self.state.current_key = unwound.swap_remove(0);
+            let snapshots = &mut self.state.snapshots;
+            match snapshots.len().checked_sub(steps) {
+                Some(kept) => {
+                    if let Some(snapshot) = snapshots.drain(kept..).next() {
+                        self.state.variables = snapshot.variables;
+                        self.state.assigned = snapshot.assigned;
+                    }
+                }
+                None => snapshots.clear(),
+            }
+
+            #[cfg(feature = "tracing")]
+            tracing::debug!(steps, node = %self.state.current_key, "rewound");
+
+            self.journal
+                .enter(&self.tree.nodes[&self.state.current_key]);
+            self.restock();
+        }
+        Some(self.current_node())
+    }
+
+    /// Return to the most recent visit of a [`Node`], which is the current node if it matches, returning it, or [`None`] without moving if it was never visited. Variables are restored as by [`rewind_steps`][`Walker#method.rewind_steps`].
+    ///
+    /// # Arguments
+    ///
+    /// * `node_key` - A string slice that holds the key of the node to return to.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Link, Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// let mut start = Node::new("start", "Hello!");
+    /// let mut middle = Node::new("middle", "How are you?");
+    /// let end = Node::new("end", "Goodbye!");
+    /// Link::link(&mut start, &middle, "Hi!");
+    /// Link::link(&mut middle, &end, "Bye!");
+    /// tree.insert_node(start);
+    /// tree.insert_node(middle);
+    /// tree.insert_node(end);
+    /// tree.set_root_key("start").unwrap();
+    /// let mut walker = Walker::new(&tree).unwrap();
+    /// walker.narrate().for_each(drop);
+    /// assert_eq!("How are you?", walker.rewind_to("middle").unwrap().dialogue);
+    /// assert_eq!(["start"], walker.history());
+    /// assert!(walker.rewind_to("end").is_none());
+    /// ```
+    pub fn rewind_to(&mut self, node_key: &str) -> Option<&Node> {
+        let steps = match self.state.history.iter().rposition(|key| key == node_key) {
+            _ if self.state.current_key == node_key => 0,
+            Some(i) => self.state.history.len() - i,
+            None => return None,
+        };
+        self.rewind_steps(steps)
+    }
+
+    /// Returns the [`Link`]s of the current [`Node`] which may be chosen, ordered by the tree's [`LinkOrder`][`crate::LinkOrder`]. Links whose [`Condition`][`crate::Condition`] does not hold for the walker's variables are left out, as are links to nodes whose [`Availability`][`crate::Availability`] does not allow them in the walker's [`HostState`], if one is set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Link, Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// let mut node = Node::new("start", "Hello!");
+    /// node.links.push(Link::new("start", "Hello?"));
+    /// tree.insert_node(node);
+    /// tree.set_root_key("start").unwrap();
+    /// let walker = Walker::new(&tree).unwrap();
+    /// assert_eq!("Hello?", walker.choices()[0].text());
+    /// ```
+    pub fn choices(&self) -> Vec<&Link> {
+        available(
+            &self.tree,
+            self.current_node(),
+            &self.offers,
+            &self.state.variables,
+            self.host(),
+        )
+    }
+
+    /// Returns the links of the current [`Node`] to present, ordered by the tree's [`LinkOrder`][`crate::LinkOrder`]. These are its [`choices`][`Walker#method.choices`], each with its index for [`choose`][`Walker#method.choose`], and the links whose [`Condition`][`crate::Condition`] does not hold but which are [`WhenUnmet::Disabled`], with the reason they cannot be chosen.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Condition, Link, Node, PresentedChoice, Tree, WhenUnmet, Walker};
+    /// let mut tree = Tree::new();
+    /// let mut node = Node::new("start", "What'll it be?");
+    /// let mut sword = Link::new("start", "The sword.");
+    /// sword.condition = Some(Condition::parse("gold >= 10").unwrap());
+    /// sword.when_unmet = WhenUnmet::Disabled("Requires 10 gold".into());
+    /// node.links.extend(vec![sword, Link::new("start", "Nothing.")]);
+    /// tree.insert_node(node);
+    /// tree.set_root_key("start").unwrap();
+    /// let walker = Walker::new(&tree).unwrap();
+    /// let presented = walker.presented_choices();
+    /// assert!(matches!(presented[0], PresentedChoice::Disabled(_, "Requires 10 gold")));
+    /// assert!(matches!(presented[1], PresentedChoice::Enabled(0, _)));
+    /// ```
+    pub fn presented_choices(&self) -> Vec<PresentedChoice<'_>> {
+        let tree = &*self.tree;
+        let mut presented = Vec::new();
+        let mut index = 0;
+        for link in offered(tree, self.current_node(), &self.offers) {
+            if !is_allowed(tree, link, self.host()) {
+                continue;
+            }
+            let holds = holds(link, &self.state.variables, self.host());
+            match (&link.when_unmet, holds) {
+                (_, true) => {
+                    presented.push(PresentedChoice::Enabled(index, link));
+                    index += 1;
+                }
+                (WhenUnmet::Disabled(reason), false) => {
+                    presented.push(PresentedChoice::Disabled(link, reason));
+                }
+                (WhenUnmet::Hidden, false) => {}
+            }
+        }
+        presented
+    }
+
+    /// Returns the [`choices`][`Walker#method.choices`] of the current [`Node`] as a menu, where links with a [`group`][`Link#structfield.group`] are gathered under their heading. Each group appears where its first choice would, and every choice keeps its index for [`choose`][`Walker#method.choose`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Link, MenuItem, Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// let mut node = Node::new("start", "What do you want?");
+    /// let mut town = Link::new("start", "The town.");
+    /// town.group = Some("Ask about...".into());
+    /// let mut king = Link::new("start", "The king.");
+    /// king.group = Some("Ask about...".into());
+    /// node.links.extend(vec![town, Link::new("start", "Nothing."), king]);
+    /// tree.insert_node(node);
+    /// tree.set_root_key("start").unwrap();
+    /// let walker = Walker::new(&tree).unwrap();
+    /// let menu = walker.menu();
+    /// assert_eq!(2, menu.len());
+    /// match &menu[0] {
+    ///     MenuItem::Group(heading, choices) => {
+    ///         assert_eq!("Ask about...", *heading);
+    ///         assert_eq!(vec![0, 2], choices.iter().map(|(i, _)| *i).collect::<Vec<_>>());
+    ///     }
+    ///     MenuItem::Choice(..) => unreachable!(),
+    /// }
+    /// assert!(matches!(menu[1], MenuItem::Choice(1, _)));
+    /// ```
+    pub fn menu(&self) -> Vec<MenuItem<'_>> {
+        let mut menu: Vec<MenuItem<'_>> = Vec::new();
+        for (i, link) in self.choices().into_iter().enumerate() {
+            let heading = match &link.group {
+                Some(heading) => heading.as_str(),
+                None => {
+                    menu.push(MenuItem::Choice(i, link));
+                    continue;
+                }
+            };
+            let group = menu.iter_mut().find_map(|item| match item {
+                MenuItem::Group(group, choices) if *group == heading => Some(choices),
+                _ => None,
+            });
+            match group {
+                Some(choices) => choices.push((i, link)),
+                None => menu.push(MenuItem::Group(heading, vec![(i, link)])),
+            }
+        }
+        menu
+    }
+
+    /// Set the [`HostState`] of the game, so that links to nodes whose [`Availability`][`crate::Availability`] does not allow them are not offered as [`choices`][`Walker#method.choices`]. Cooldowns of nodes are keyed by node key.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The [`HostState`] of the game, replacing any previous state.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{HostState, Link, Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// let mut start = Node::new("start", "Hello!");
+    /// let mut festival = Node::new("festival", "Enjoy the festival!");
+    /// festival.availability.min_day = Some(7);
+    /// Link::link(&mut start, &festival, "What's happening?");
+    /// tree.insert_node(start);
+    /// tree.insert_node(festival);
+    /// tree.set_root_key("start").unwrap();
+    /// let mut walker = Walker::new(&tree).unwrap();
+    /// assert_eq!(1, walker.choices().len());
+    /// walker.set_host_state(HostState { day: 3, ..HostState::default() });
+    /// assert!(walker.choices().is_empty());
+    /// ```
+    pub fn set_host_state(&mut self, state: HostState) {
+        self.host_state = Some(state);
+    }
+
+    /// Returns the [`HostState`] set with [`set_host_state`][`Walker#method.set_host_state`], if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{HostState, Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let mut walker = Walker::new(&tree).unwrap();
+    /// assert!(walker.host_state().is_none());
+    /// walker.set_host_state(HostState::default());
+    /// assert!(walker.host_state().is_some());
+    /// ```
+    pub fn host_state(&self) -> Option<&HostState> {
+        self.host_state.as_ref()
+    }
+
+    /// Returns a mutable reference to the [`HostState`] set with [`set_host_state`][`Walker#method.set_host_state`], if any, e.g. to advance time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{HostState, Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let mut walker = Walker::new(&tree).unwrap();
+    /// walker.set_host_state(HostState::default());
+    /// walker.host_state_mut().unwrap().day += 1;
+    /// assert_eq!(1, walker.host_state().unwrap().day);
+    /// ```
+    pub fn host_state_mut(&mut self) -> Option<&mut HostState> {
+        self.host_state.as_mut()
+    }
+
+    /// Remove the [`HostState`] set with [`set_host_state`][`Walker#method.set_host_state`], if any, so availability is no longer checked.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{HostState, Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let mut walker = Walker::new(&tree).unwrap();
+    /// walker.set_host_state(HostState::default());
+    /// walker.clear_host_state();
+    /// assert!(walker.host_state().is_none());
+    /// ```
+    pub fn clear_host_state(&mut self) {
+        self.host_state = None;
+    }
+
+    /// Set an [`InventoryProvider`] which link conditions read items from, e.g. `item(rusty_key)` or `item(arrows) >= 10`. See [`crate::inventory`]. Clones of this walker share the inventory.
+    ///
+    /// # Arguments
+    ///
+    /// * `inventory` - The [`InventoryProvider`] to read, replacing any previous inventory.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Condition, Link, Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// let mut start = Node::new("start", "A locked door.");
+    /// let mut unlock = Link::new("start", "Unlock it.");
+    /// unlock.condition = Some(Condition::parse("item(rusty_key)").unwrap());
+    /// start.links.push(unlock);
+    /// tree.insert_node(start);
+    /// tree.set_root_key("start").unwrap();
+    ///
+    /// let mut walker = Walker::new(&tree).unwrap();
+    /// assert!(walker.choices().is_empty());
+    /// walker.set_inventory(|item: &str| u32::from(item == "rusty_key"));
+    /// assert_eq!(1, walker.choices().len());
+    /// ```
+    pub fn set_inventory<I>(&mut self, inventory: I)
+    where
+        I: InventoryProvider + 'static,
+    {
+        self.inventory = Some(InventoryHandle::new(inventory));
+    }
+
+    /// Remove the [`InventoryProvider`] set with [`set_inventory`][`Walker#method.set_inventory`], if any, so no items are held.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let mut walker = Walker::new(&tree).unwrap();
+    /// walker.set_inventory(|_: &str| 1);
+    /// walker.clear_inventory();
+    /// ```
+    pub fn clear_inventory(&mut self) {
+        self.inventory = None;
+    }
+
+    /// Set a [`MenuProvider`] which supplies the entries of [`NodeKind::Menu`] nodes, e.g. a shop's stock. Each entry is offered as a copy of the node's first link, before its other links. See [`crate::menu`]. Entries are read as a menu node is entered, and again by [`restock`][`Walker#method.restock`]. Clones of this walker share the provider.
+    ///
+    /// # Arguments
+    ///
+    /// * `menu` - The [`MenuProvider`] to read, replacing any previous provider.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{menu::MenuEntry, Link, Node, NodeKind, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// let mut shop = Node::new("shop", "What'll it be?");
+    /// shop.kind = NodeKind::Menu { source: "shop_stock".into() };
+    /// shop.links.push(Link::new("shop", "Buy"));
+    /// shop.links.push(Link::new("shop", "Nothing, thanks."));
+    /// tree.insert_node(shop);
+    /// tree.set_root_key("shop").unwrap();
+    ///
+    /// let mut walker = Walker::new(&tree).unwrap();
+    /// walker.set_menu(|_: &str| vec![
+    ///     MenuEntry::new("potion", "Potion (5 gold)"),
+    ///     MenuEntry::new("iron_sword", "Iron sword (50 gold)"),
+    /// ]);
+    /// assert_eq!(
+    ///     vec!["Potion (5 gold)", "Iron sword (50 gold)", "Nothing, thanks."],
+    ///     walker.choice_dialogues()
+    /// );
+    /// walker.choose(1).unwrap();
+    /// assert_eq!(Some(&"iron_sword".into()), walker.variables().get("menu.shop_stock"));
+    /// ```
+    pub fn set_menu<M>(&mut self, menu: M)
+    where
+        M: MenuProvider + 'static,
+    {
+        self.menu = Some(MenuHandle::new(menu));
+        self.restock();
+    }
+
+    /// Remove the [`MenuProvider`] set with [`set_menu`][`Walker#method.set_menu`], if any, so menu nodes offer only their other links.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{menu::MenuEntry, Link, Node, NodeKind, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// let mut shop = Node::new("shop", "What'll it be?");
+    /// shop.kind = NodeKind::Menu { source: "shop_stock".into() };
+    /// shop.links.push(Link::new("shop", "Buy"));
+    /// tree.insert_node(shop);
+    /// tree.set_root_key("shop").unwrap();
+    /// let mut walker = Walker::new(&tree).unwrap();
+    /// walker.set_menu(|_: &str| vec![MenuEntry::new("potion", "Potion")]);
+    /// assert_eq!(1, walker.choices().len());
+    /// walker.clear_menu();
+    /// assert!(walker.choices().is_empty());
+    /// ```
+    pub fn clear_menu(&mut self) {
+        self.menu = None;
+        self.restock();
+    }
+
+    /// Read the entries of the current [`Node`] again from the [`MenuProvider`], if it is a [`NodeKind::Menu`] node, e.g. after the game's stock changed while the menu was open.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{menu::MenuEntry, Link, Node, NodeKind, Tree, Walker};
+    /// use std::sync::{Arc, Mutex};
+    /// let mut tree = Tree::new();
+    /// let mut shop = Node::new("shop", "What'll it be?");
+    /// shop.kind = NodeKind::Menu { source: "shop_stock".into() };
+    /// shop.links.push(Link::new("shop", "Buy"));
+    /// tree.insert_node(shop);
+    /// tree.set_root_key("shop").unwrap();
+    ///
+    /// let stock = Arc::new(Mutex::new(vec![MenuEntry::new("potion", "Potion")]));
+    /// let mut walker = Walker::new(&tree).unwrap();
+    /// let shelf = Arc::clone(&stock);
+    /// walker.set_menu(move |_: &str| shelf.lock().unwrap().clone());
+    /// stock.lock().unwrap().clear();
+    /// assert_eq!(1, walker.choices().len());
+    /// walker.restock();
+    /// assert!(walker.choices().is_empty());
+    /// ```
+    pub fn restock(&mut self) {
+        let node = &self.tree.nodes[&self.state.current_key];
+        self.offers = stock(node, self.menu.as_ref());
+    }
+
+    // What conditions read besides conversation variables
+    fn host(&self) -> Host<'_> {
+        host(&self.host_state, &self.inventory)
+    }
+
+    /// Set a [`ContentFilter`] which screens dialogue on the render path, i.e. in [`dialogue`][`Walker#method.dialogue`] and [`choice_dialogues`][`Walker#method.choice_dialogues`]. The tree itself is never altered. Clones of this walker share the filter.
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` - The [`ContentFilter`] to apply, replacing any previous filter.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{filter::MaskWords, Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Darn it!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let mut walker = Walker::new(&tree).unwrap();
+    /// walker.set_filter(MaskWords::new(&["darn"], '*'));
+    /// assert_eq!("**** it!", walker.dialogue());
+    /// assert_eq!("Darn it!", walker.current_node().dialogue);
+    /// ```
+    pub fn set_filter<F>(&mut self, filter: F)
+    where
+        F: ContentFilter + 'static,
+    {
+        self.filter = Some(FilterHandle::new(filter));
+    }
+
+    /// Remove the [`ContentFilter`] set with [`set_filter`][`Walker#method.set_filter`], if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{filter::MaskWords, Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Darn it!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let mut walker = Walker::new(&tree).unwrap();
+    /// walker.set_filter(MaskWords::new(&["darn"], '*'));
+    /// walker.clear_filter();
+    /// assert_eq!("Darn it!", walker.dialogue());
+    /// ```
+    pub fn clear_filter(&mut self) {
+        self.filter = None;
+    }
+
+    /// Returns the dialogue of the current [`Node`] to display, screened by the walker's [`ContentFilter`] if one is set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let walker = Walker::new(&tree).unwrap();
+    /// assert_eq!("Hello!", walker.dialogue());
+    /// ```
+    pub fn dialogue(&self) -> Cow<'_, str> {
+        self.display(&self.current_node().dialogue)
+    }
+
+    /// Returns the speaker and text of each [`Line`][`crate::Line`] of the current [`Node`], to display in order before its choices, screened by the walker's [`ContentFilter`] if one is set. Nodes whose dialogue is not a sequence have no lines, see [`dialogue`][`Walker#method.dialogue`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Line, Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// let mut node = Node::new("start", "");
+    /// node.set_lines(vec![Line::new("guard", "Halt!"), Line::new("captain", "At ease.")]);
+    /// tree.insert_node(node);
+    /// tree.set_root_key("start").unwrap();
+    /// let walker = Walker::new(&tree).unwrap();
+    /// let lines = walker.lines();
+    /// assert_eq!(("guard", "Halt!"), (lines[0].0, &*lines[0].1));
+    /// assert_eq!(2, lines.len());
+    /// ```
+    pub fn lines(&self) -> Vec<(&str, Cow<'_, str>)> {
+        self.current_node()
+            .lines
+            .iter()
+            .map(|line| (line.speaker.as_str(), self.display(&line.text)))
+            .collect()
+    }
+
+    /// Returns the dialogue of each of the [`choices`][`Walker#method.choices`] to display, screened by the walker's [`ContentFilter`] if one is set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Link, Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// let mut node = Node::new("start", "Hello!");
+    /// node.links.push(Link::new("start", "Hello?"));
+    /// tree.insert_node(node);
+    /// tree.set_root_key("start").unwrap();
+    /// let walker = Walker::new(&tree).unwrap();
+    /// assert_eq!(vec!["Hello?"], walker.choice_dialogues());
+    /// ```
+    pub fn choice_dialogues(&self) -> Vec<Cow<'_, str>> {
+        self.choices()
+            .into_iter()
+            .map(|link| self.display(link.text()))
+            .collect()
+    }
+
+    #[cfg(feature = "server")]
+    pub(crate) fn set_filter_handle(&mut self, filter: Option<FilterHandle>) {
+        self.filter = filter;
+    }
+
+    fn display<'s>(&self, text: &'s str) -> Cow<'s, str> {
+        match &self.filter {
+            Some(filter) => filter.apply(text),
+            None => Cow::Borrowed(text),
+        }
+    }
+
+    /// Set an [`Observer`] which is notified of conversation events, replacing any previous observer. Clones of this walker share the observer.
+    ///
+    /// # Arguments
+    ///
+    /// * `observer` - The [`Observer`] to notify.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{observer::Observer, Node, Tree, Walker};
+    ///
+    /// struct Log;
+    /// impl Observer for Log {
+    ///     fn on_node_entered(&self, node: &Node) {
+    ///         println!("Entered {}", node.key());
+    ///     }
+    /// }
+    ///
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let mut walker = Walker::new(&tree).unwrap();
+    /// walker.set_observer(Log);
+    /// ```
+    pub fn set_observer<O>(&mut self, observer: O)
+    where
+        O: Observer + 'static,
+    {
+        self.journal.observer = Some(ObserverHandle::new(observer));
+    }
+
+    /// Remove the [`Observer`] set with [`set_observer`][`Walker#method.set_observer`], if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let mut walker = Walker::new(&tree).unwrap();
+    /// walker.clear_observer();
+    /// ```
+    pub fn clear_observer(&mut self) {
+        self.journal.observer = None;
+    }
+
+    /// Set a [`Debugger`] which is called when the walker enters a [`Node`] with a breakpoint, replacing any previous debugger. Clones of this walker share the debugger.
+    ///
+    /// # Arguments
+    ///
+    /// * `debugger` - The [`Debugger`] to call.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{debugger::{Break, Debugger, Resume}, Node, Tree, Walker};
+    ///
+    /// struct Dump;
+    /// impl Debugger for Dump {
+    ///     fn on_break(&self, context: Break<'_>) -> Resume {
+    ///         println!("Break at {}: {:?}", context.node.key(), context.variables);
+    ///         Resume::Continue
+    ///     }
+    /// }
+    ///
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let mut walker = Walker::new(&tree).unwrap();
+    /// walker.set_debugger(Dump);
+    /// ```
+    pub fn set_debugger<D>(&mut self, debugger: D)
+    where
+        D: Debugger + 'static,
+    {
+        self.breakpoints.set_debugger(debugger);
+    }
+
+    /// Remove the [`Debugger`] set with [`set_debugger`][`Walker#method.set_debugger`], if any. Breakpoints are kept, but nothing is called at them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let mut walker = Walker::new(&tree).unwrap();
+    /// walker.clear_debugger();
+    /// ```
+    pub fn clear_debugger(&mut self) {
+        self.breakpoints.clear_debugger();
+    }
+
+    /// Set a breakpoint on a [`Node`], so the [`Debugger`] is called whenever the walker enters it, by a choice or passing through an automatic node, but not when it returns to it, e.g. with [`step_back`][`Walker#method.step_back`]. The node the walker starts at is entered before breakpoints can be set.
+    ///
+    /// # Arguments
+    ///
+    /// * `node_key` - The key of the node.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let mut walker = Walker::new(&tree).unwrap();
+    /// walker.add_breakpoint("start");
+    /// assert!(walker.breakpoints().any(|key| key == "start"));
+    /// ```
+    pub fn add_breakpoint<K>(&mut self, node_key: K)
+    where
+        K: Into<String>,
+    {
+        self.breakpoints.keys.insert(node_key.into());
+    }
+
+    /// Remove the breakpoint on a [`Node`], returning whether it was set.
+    ///
+    /// # Arguments
+    ///
+    /// * `node_key` - A string slice that holds the key of the node.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let mut walker = Walker::new(&tree).unwrap();
+    /// walker.add_breakpoint("start");
+    /// assert!(walker.remove_breakpoint("start"));
+    /// assert!(!walker.remove_breakpoint("start"));
+    /// ```
+    pub fn remove_breakpoint(&mut self, node_key: &str) -> bool {
+        self.breakpoints.keys.remove(node_key)
+    }
+
+    /// Returns an iterator over the keys of the nodes with breakpoints, in sorted order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let mut walker = Walker::new(&tree).unwrap();
+    /// walker.add_breakpoint("start");
+    /// assert_eq!(vec!["start"], walker.breakpoints().collect::<Vec<_>>());
+    /// ```
+    pub fn breakpoints(&self) -> impl Iterator<Item = &str> {
+        self.breakpoints.keys.iter().map(String::as_str)
+    }
+
+    /// Returns the transcript of the conversation: an entry for every [`Node`] entered, oldest first, starting with the root node. Unlike the [`history`][`Walker#method.history`], the transcript is never unwound, so returning to a node by rewinding or restoring a bookmark adds an entry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Link, Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// let mut start = Node::new("start", "Hello!");
+    /// let end = Node::new("end", "Goodbye!");
+    /// Link::link(&mut start, &end, "Bye!");
+    /// tree.insert_node(start);
+    /// tree.insert_node(end);
+    /// tree.set_root_key("start").unwrap();
+    /// let mut walker = Walker::new(&tree).unwrap();
+    /// walker.choose(0).unwrap();
+    /// walker.abort("combat started");
+    /// let transcript = walker.transcript();
+    /// assert_eq!(Some("Bye!"), transcript[0].choice.as_deref());
+    /// assert_eq!("end", transcript[1].node_key);
+    /// assert_eq!(Some("combat started"), transcript[1].aborted.as_deref());
+    /// ```
+    pub fn transcript(&self) -> &[TranscriptEntry] {
+        &self.journal.transcript
+    }
+
+    /// Enable or disable the trace of conditions evaluated at each step, read with [`condition_trace`][`Walker#method.condition_trace`]. Disabling the trace discards it. Conditions are not traced by default, as every condition of a visited node is recorded along with its inputs.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether conditions are traced.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let mut walker = Walker::new(&tree).unwrap();
+    /// walker.set_condition_trace(true);
+    /// ```
+    pub fn set_condition_trace(&mut self, enabled: bool) {
+        match enabled {
+            true => {
+                self.journal.evaluations.get_or_insert_with(Vec::new);
+            }
+            false => self.journal.evaluations = None,
+        }
+    }
+
+    /// Returns every [`Condition`][`crate::Condition`] evaluated to decide which links the walker could follow since the trace was enabled with [`set_condition_trace`][`Walker#method.set_condition_trace`], oldest first, with the values it tested and whether it held. The trace is empty while disabled.
+    ///
+    /// The conditions of a node's links are evaluated when a choice is made, and when an automatic node is passed through.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Condition, Link, Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// let mut start = Node::new("start", "Hello!");
+    /// start.links.push(Link::new("shop", "Buy a sword."));
+    /// start.links.push(Link::new("end", "Bye!"));
+    /// start.links[0].condition = Some(Condition::parse("gold >= 10").unwrap());
+    /// tree.insert_node(Node::new("shop", "Here you go."));
+    /// tree.insert_node(Node::new("end", "Goodbye!"));
+    /// tree.set_root_node(start).unwrap();
+    /// let mut walker = Walker::new(&tree).unwrap();
+    /// walker.set_condition_trace(true);
+    /// walker.choose(0).unwrap();
+    ///
+    /// // The sword was hidden, as gold was not set
+    /// let evaluation = &walker.condition_trace()[0];
+    /// assert_eq!(("start", 0), (evaluation.node_key.as_str(), evaluation.link_index));
+    /// assert_eq!(Some(&None), evaluation.inputs.get("gold"));
+    /// assert!(!evaluation.holds);
+    /// ```
+    pub fn condition_trace(&self) -> &[Evaluation] {
+        self.journal.evaluations.as_deref().unwrap_or_default()
+    }
+
+    #[cfg(feature = "storage")]
+    pub(crate) fn assigned(&self) -> &BTreeSet<String> {
+        &self.state.assigned
+    }
+
+    pub(crate) fn resume_key(&self) -> Option<(&str, &str)> {
+        self.resume_key
+            .as_ref()
+            .map(|(tree_name, speaker)| (tree_name.as_str(), speaker.as_str()))
+    }
+
+    /// Returns the [`Status`] of the conversation. The conversation is finished only at a [`Node`] which has no links or is an [`end`][`Node#structfield.end`], so a node whose links are all unavailable is [`Status::Blocked`] rather than finished.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Link, Node, Status, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// let mut start = Node::new("start", "Hello!");
+    /// let end = Node::new("end", "Goodbye!");
+    /// Link::link(&mut start, &end, "Bye!");
+    /// tree.insert_node(start);
+    /// tree.insert_node(end);
+    /// tree.set_root_key("start").unwrap();
+    /// let mut walker = Walker::new(&tree).unwrap();
+    /// assert_eq!(Status::Narrating, walker.status());
+    /// walker.choose(0).unwrap();
+    /// assert_eq!(Status::Finished, walker.status());
+    /// ```
+    pub fn status(&self) -> Status {
+        if self.state.aborted.is_some() {
+            return Status::Aborted;
+        }
+        let node = self.current_node();
+        if node.end || node.links.is_empty() {
+            return Status::Finished;
+        }
+        match self.choices().len() {
+            0 => Status::Blocked,
+            1 => Status::Narrating,
+            _ => Status::AwaitingChoice,
+        }
+    }
+
+    /// Abort the conversation, e.g. because combat started. Until it is resumed by [`restore`][`Walker#method.restore`] or rewinding, the walker's [`status`][`Walker#method.status`] is [`Status::Aborted`] and choices cannot be made.
+    ///
+    /// The abort is recorded in the last entry of the [`transcript`][`Walker#method.transcript`], and the [`Observer`] is notified with [`Observer::on_conversation_aborted`].
+    ///
+    /// # Arguments
+    ///
+    /// * `reason` - A string type that holds why the conversation was aborted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Link, Node, Status, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// let mut start = Node::new("start", "Hello!");
+    /// let end = Node::new("end", "Goodbye!");
+    /// Link::link(&mut start, &end, "Bye!");
+    /// tree.insert_node(start);
+    /// tree.insert_node(end);
+    /// tree.set_root_key("start").unwrap();
+    /// let mut walker = Walker::new(&tree).unwrap();
+    /// walker.abort("combat started");
+    /// assert_eq!(Status::Aborted, walker.status());
+    /// assert_eq!(Some("combat started"), walker.abort_reason());
+    /// assert!(walker.choose(0).is_err());
+    /// ```
+    pub fn abort<R>(&mut self, reason: R)
+    where
+        R: Into<String>,
+    {
+        let reason = reason.into();
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(node = %self.state.current_key, reason = %reason, "conversation aborted");
+
+        self.journal.abort(&reason);
+        self.state.aborted = Some(reason);
+    }
+
+    /// Returns the reason the conversation was aborted, or [`None`] if it was not. See [`abort`][`Walker#method.abort`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let walker = Walker::new(&tree).unwrap();
+    /// assert_eq!(None, walker.abort_reason());
+    /// ```
+    pub fn abort_reason(&self) -> Option<&str> {
+        self.state.aborted.as_deref()
+    }
+
+    /// Returns whether the conversation has ended, i.e. its [`status`][`Walker#method.status`] is [`Status::Finished`] because the current [`Node`] has no links or is an [`end`][`Node#structfield.end`]. Aborted and blocked conversations are not finished.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Goodbye!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let walker = Walker::new(&tree).unwrap();
+    /// assert!(walker.is_finished());
+    /// ```
+    pub fn is_finished(&self) -> bool {
+        self.status() == Status::Finished
+    }
+
+    /// Returns the [`Outcome`] of the conversation, or [`None`] if its [`status`][`Walker#method.status`] is not [`Status::Finished`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Link, Node, Tree, Value, Walker};
+    /// let mut tree = Tree::new();
+    /// let mut start = Node::new("start", "Will you help?");
+    /// let mut end = Node::new("end", "Thank you!");
+    /// end.tags.push("ally".into());
+    /// Link::link(&mut start, &end, "Yes.");
+    /// start.links[0].set.push(("helped".into(), Value::Bool(true)));
+    /// tree.insert_node(start);
+    /// tree.insert_node(end);
+    /// tree.set_root_key("start").unwrap();
+    /// let mut walker = Walker::new(&tree).unwrap();
+    /// assert!(walker.finish().is_none());
+    /// walker.choose(0).unwrap();
+    /// let outcome = walker.finish().unwrap();
+    /// assert_eq!("end", outcome.end_key);
+    /// assert_eq!(vec!["ally"], outcome.tags);
+    /// assert_eq!(1, outcome.path_length);
+    /// assert_eq!(Some(&Value::Bool(true)), outcome.variables.get("helped"));
+    /// ```
+    pub fn finish(&self) -> Option<Outcome> {
+        if self.status() != Status::Finished {
+            return None;
+        }
+        let node = self.current_node();
+        let variables = self
+            .state
+            .variables
+            .iter()
+            .filter(|(name, _)| self.state.assigned.contains(*name))
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        Some(Outcome {
+            end_key: node.key.clone(),
+            tags: node.tags.clone(),
+            path_length: self.state.history.len(),
+            variables,
+        })
+    }
+
+    /// Try to follow one of the [`choices`][`Walker#method.choices`] of the current [`Node`], returning the [`Node`] it leads to. The link's assignments are applied to the walker's variables.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the chosen [`Link`] in [`choices`][`Walker#method.choices`].
+    ///
+    /// # Errors
+    ///
+    /// * A [`TreeError`] will be returned if the conversation was aborted, the choice does not exist, or the node it links to does not exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Link, Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// let mut start = Node::new("start", "Hello!");
+    /// let end = Node::new("end", "Goodbye!");
+    /// Link::link(&mut start, &end, "Bye!");
+    /// tree.insert_node(start);
+    /// tree.insert_node(end);
+    /// tree.set_root_key("start").unwrap();
+    /// let mut walker = Walker::new(&tree).unwrap();
+    /// assert_eq!("Goodbye!", walker.choose(0).unwrap().dialogue);
+    /// ```
+    pub fn choose(&mut self, index: usize) -> Result<&Node, TreeError> {
+        if let Some(reason) = &self.state.aborted {
+            return Err(TreeError::Aborted(reason.clone()));
+        }
+        let tree = &*self.tree;
+        let node = &tree.nodes[&self.state.current_key];
+        let host = host(&self.host_state, &self.inventory);
+        self.journal.trace(node, &self.state.variables, host);
+        let link = *available(tree, node, &self.offers, &self.state.variables, host)
+            .get(index)
+            .ok_or(TreeError::LinkDNE(index))?;
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(from = %self.state.current_key, choice = index, to = %link.to_key, "choice made");
+
+        self.state
+            .follow(tree, link, self.snapshot_limit, &mut self.journal)?;
+        let node = self.state.settle(
+            tree,
+            self.snapshot_limit,
+            &mut self.journal,
+            host,
+            &mut self.dice,
+            &self.breakpoints,
+        );
+        self.offers = stock(node, self.menu.as_ref());
+        Ok(node)
+    }
+
+    /// Set the seed of the pseudo-random generator which picks the links of [`NodeKind::Random`] nodes, e.g. from the clock or a saved game, so picks differ between sessions. A random root node is passed through when the walker is created, before a seed can be set.
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - The seed, where equal seeds pick equal links.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// tree.insert_node(Node::new("start", "Hello!"));
+    /// tree.set_root_key("start").unwrap();
+    /// let mut walker = Walker::new(&tree).unwrap();
+    /// walker.set_seed(42);
+    /// ```
+    pub fn set_seed(&mut self, seed: u64) {
+        self.dice.rng = Rng::new(seed);
+    }
+
+    /// Returns an [`Iterator`] narrating the linear segment starting at the current [`Node`].
+    ///
+    /// The iterator yields the current node, then automatically follows nodes with exactly one choice, yielding each node it enters. It ends after yielding a node which requires a choice (more than one choice) or ends the conversation (no choices, or an [`end`][`Node#structfield.end`] node). The walker is left at the last node yielded.
+    ///
+    /// An aborted conversation narrates nothing. Note that a cycle of single-link nodes narrates forever.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use convo::{Link, Node, Tree, Walker};
+    /// let mut tree = Tree::new();
+    /// let mut start = Node::new("start", "Once upon a time...");
+    /// let end = Node::new("end", "The end.");
+    /// Link::link(&mut start, &end, "Continue");
+    /// tree.insert_node(start);
+    /// tree.insert_node(end);
+    /// tree.set_root_key("start").unwrap();
+    /// let mut walker = Walker::new(&tree).unwrap();
+    /// for node in walker.narrate() {
+    ///     println!("{}", node.dialogue);
+    /// }
+    /// assert!(walker.is_finished());
+    /// ```
+    pub fn narrate(&mut self) -> Narrate<'_> {
+        let done = self.state.aborted.is_some();
+        Narrate {
+            tree: &self.tree,
+            state: &mut self.state,
+            snapshot_limit: self.snapshot_limit,
+            host: host(&self.host_state, &self.inventory),
+            menu: self.menu.as_ref(),
+            offers: &mut self.offers,
+            journal: &mut self.journal,
+            dice: &mut self.dice,
+            breakpoints: &self.breakpoints,
+            started: false,
+            done,
+        }
+    }
+}
+
+// Returns what a walker's host supplies to conditions, borrowing only the fields it needs
+fn host<'h>(state: &'h Option<HostState>, inventory: &'h Option<InventoryHandle>) -> Host<'h> {
+    Host {
+        state: state.as_ref(),
+        inventory: inventory.as_ref().map(InventoryHandle::provider),
+    }
+}
+
+impl State {
+    // Renames the keys of the state by a migration, forgetting the history before any step back to a removed node, returning whether the current node exists
+    fn migrate(&mut self, tree: &Tree, migration: &Migration) -> bool {
+        let (current_key, history) = match migration.migrate_keys(&self.current_key, &self.history)
+        {
+            Some(keys) => keys,
+            None => return false,
+        };
+        self.current_key = current_key;
+        self.history = history;
+        let snapshots = &mut self.snapshots;
+        snapshots.drain(..snapshots.len().saturating_sub(self.history.len()));
+        self.forget_missing(tree)
+    }
+
+    // Forgets the history before any step back to a node which does not exist, returning whether the current node exists
+    fn forget_missing(&mut self, tree: &Tree) -> bool {
+        if let Some(index) = self
+            .history
+            .iter()
+            .rposition(|key| !tree.contains_node(key))
+        {
+            self.history.drain(..=index);
+            let snapshots = &mut self.snapshots;
+            snapshots.drain(..snapshots.len().saturating_sub(self.history.len()));
+        }
+        tree.contains_node(&self.current_key)
+    }
+
+    // Follows a link to an existing node, recording history and applying assignments. The caller settles the walker after
+    fn follow(
+        &mut self,
+        tree: &Tree,
+        link: &Link,
+        snapshot_limit: Option<usize>,
+        journal: &mut Journal,
+    ) -> Result<(), TreeError> {
+        let node = tree
+            .nodes
+            .get(&link.to_key)
+            .ok_or_else(|| tree.missing_node(&link.to_key))?;
+        let from_key = core::mem::replace(&mut self.current_key, link.to_key.clone());
+        self.history.push(from_key);
+
+        // Snapshots must cover the most recent steps, so a step without one drops the rest
+        match snapshot_limit {
+            Some(limit) if limit > 0 => {
+                if self.snapshots.len() == limit {
+                    self.snapshots.pop_front();
+                }
+                self.snapshots.push_back(Snapshot {
+                    variables: self.variables.clone(),
+                    assigned: self.assigned.clone(),
+                });
+            }
+            _ => self.snapshots.clear(),
+        }
+        self.assign(link);
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(node = %link.to_key, "node entered");
+
+        journal.follow(link, node);
+        Ok(())
+    }
+
+    // Passes through automatic nodes without recording history, returning the node the walker rests at. Nodes with breakpoints are checked as they are entered
+    fn settle<'t>(
+        &mut self,
+        tree: &'t Tree,
+        snapshot_limit: Option<usize>,
+        journal: &mut Journal,
+        host: Host<'_>,
+        dice: &mut Dice,
+        breakpoints: &Breakpoints,
+    ) -> &'t Node {
+        // Each pass enters a node, so a cycle of automatic nodes rests after visiting every node
+        for _ in 0..tree.len() {
+            let node = &tree.nodes[&self.current_key];
+
+            // A link forced at a breakpoint is followed as a step from a choice node, or passed through otherwise
+            let forced = breakpoints
+                .check(node, &mut self.variables, &self.history)
+                .map(|index| &node.links[index])
+                .filter(|link| tree.contains_node(&link.to_key));
+            let link = match (&node.kind, forced) {
+                (NodeKind::Choice | NodeKind::Menu { .. }, Some(link)) => {
+                    match self.follow(tree, link, snapshot_limit, journal) {
+                        Ok(()) => continue,
+                        Err(_) => break,
+                    }
+                }
+                (_, Some(link)) => Some(link),
+                (NodeKind::Choice | NodeKind::Menu { .. }, None) => break,
+                (NodeKind::Random { avoid_recent }, None) => {
+                    journal.trace(node, &self.variables, host);
+                    let links = available(tree, node, &[], &self.variables, host);
+                    dice.pick(node, *avoid_recent, links)
+                }
+                (NodeKind::Switch { .. }, None) => {
+                    journal.trace(node, &self.variables, host);
+                    let links = available(tree, node, &[], &self.variables, host);
+                    node.links
+                        .iter()
+                        .find(|link| links.iter().any(|l| core::ptr::eq(*l, *link)))
+                }
+            };
+            let (link, to) = match link.and_then(|link| Some((link, tree.nodes.get(&link.to_key)?)))
+            {
+                Some(next) => next,
+                None => break,
+            };
+            self.current_key = link.to_key.clone();
+            self.assign(link);
+
+            #[cfg(feature = "tracing")]
+            tracing::debug!(from = %node.key, to = %link.to_key, "passed through");
+
+            journal.follow(link, to);
+        }
+        &tree.nodes[&self.current_key]
+    }
+
+    fn assign(&mut self, link: &Link) {
+        for (name, value) in &link.set {
+            self.variables.insert(name.clone(), value.clone());
+            self.assigned.insert(name.clone());
+        }
+        for (character, amount) in &link.affinity {
+            affinity::adjust(&mut self.variables, character, *amount);
+            self.assigned.insert(affinity::variable(character));
+        }
+    }
+}
+
+/// The pseudo-random generator and recent picks of random nodes.
+#[derive(Debug, Clone, Default)]
+struct Dice {
+    rng: Rng,
+
+    /// The indices of the links most recently picked from each random node, mapped by node key, oldest first.
+    recent: BTreeMap<String, VecDeque<usize>>,
+}
+
+impl Dice {
+    // Picks one of the available links of a random node by weight, avoiding recent picks while another link can be picked
+    fn pick<'t>(
+        &mut self,
+        node: &'t Node,
+        avoid_recent: usize,
+        links: Vec<&'t Link>,
+    ) -> Option<&'t Link> {
+        let weighted: Vec<(usize, &Link, u64)> = links
+            .into_iter()
+            .filter_map(|link| {
+                let index = node.links.iter().position(|l| core::ptr::eq(l, link))?;
+                let weight = u64::from(link.weight.unwrap_or(1));
+                Some((index, link, weight)).filter(|_| weight > 0)
+            })
+            .collect();
+        let recent = self.recent.entry(node.key.clone()).or_default();
+        let fresh: Vec<(usize, &Link, u64)> = weighted
+            .iter()
+            .copied()
+            .filter(|(index, _, _)| !recent.contains(index))
+            .collect();
+        let candidates = match fresh.is_empty() {
+            true => weighted,
+            false => fresh,
+        };
+
+        let total: u64 = candidates.iter().map(|(_, _, weight)| weight).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut roll = self.rng.below(total);
+        let (index, link, _) = *candidates
+            .iter()
+            .find(|(_, _, weight)| match roll < *weight {
+                true => true,
+                false => {
+                    roll -= weight;
+                    false
+                }
+            })?;
+
+        recent.push_back(index);
+        while recent.len() > avoid_recent {
+            recent.pop_front();
+        }
+        Some(link)
+    }
+}
+
+// Returns the ordered links a node offers, where the template of a menu node is replaced by the links generated from its entries
+fn offered<'t>(tree: &Tree, node: &'t Node, offers: &'t [Link]) -> Vec<&'t Link> {
+    let mut links = node.ordered_links(tree.link_order());
+    if let (NodeKind::Menu { .. }, Some(template)) = (&node.kind, node.links.first()) {
+        if let Some(i) = links.iter().position(|link| core::ptr::eq(*link, template)) {
+            links.splice(i..=i, offers);
+        }
+    }
+    links
+}
+
+// Returns a copy of the template of a menu node for each entry the host supplies, which assigns the entry's id to the menu variable
+fn stock(node: &Node, menu: Option<&MenuHandle>) -> Vec<Link> {
+    let (source, template, menu) = match (&node.kind, node.links.first(), menu) {
+        (NodeKind::Menu { source }, Some(template), Some(menu)) => (source, template, menu),
+        _ => return Vec::new(),
+    };
+    let variable = menu::variable(source);
+    menu.provider()
+        .entries(source)
+        .into_iter()
+        .map(|entry| {
+            let mut link = template.clone();
+            link.dialogue = Some(entry.dialogue);
+            link.set.push((variable.clone(), Value::String(entry.id)));
+            link
+        })
+        .collect()
+}
+
+// Returns the ordered links a node offers whose conditions hold, to nodes which are available
+fn available<'t>(
+    tree: &Tree,
+    node: &'t Node,
+    offers: &'t [Link],
+    variables: &BTreeMap<String, Value>,
+    host: Host<'_>,
+) -> Vec<&'t Link> {
+    let mut links = offered(tree, node, offers);
+    links.retain(|link| is_allowed(tree, link, host) && holds(link, variables, host));
+    links
+}
+
+// Returns whether the condition of a link holds, if it has one, reading the clock and inventory the host supplied
+fn holds(link: &Link, variables: &BTreeMap<String, Value>, host: Host<'_>) -> bool {
+    link.condition
+        .as_ref()
+        .is_none_or(|condition| condition.holds_with(variables, host.state, host.inventory))
+}
+
+// Returns whether the target of a link is available in the host state, if one is set, and neither carries an excluded tag
+fn is_allowed(tree: &Tree, link: &Link, host: Host<'_>) -> bool {
+    let state = match host.state {
+        Some(state) => state,
+        None => return true,
+    };
+    if link
+        .tags
+        .iter()
+        .any(|tag| state.excluded_tags.contains(tag))
+    {
+        return false;
+    }
+    match tree.nodes.get(&link.to_key) {
+        Some(to) => {
+            to.availability.allows(state, &to.key)
+                && !to.tags.iter().any(|tag| state.excluded_tags.contains(tag))
+        }
+        None => true,
+    }
+}
+
+/// A [`PresentedChoice`] is a link presented to the player. See also: [`Walker::presented_choices`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum PresentedChoice<'t> {
+    /// A choice which may be chosen, with its index among the walker's choices.
+    Enabled(usize, &'t Link),
+    /// A link whose condition does not hold, shown with the reason it cannot be chosen.
+    Disabled(&'t Link, &'t str),
+}
+
+/// A [`MenuItem`] is an entry of a categorized choice menu. See also: [`Walker::menu`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum MenuItem<'t> {
+    /// A choice which is not grouped, with its index among the walker's choices.
+    Choice(usize, &'t Link),
+    /// A heading and the choices grouped under it, each with its index among the walker's choices.
+    Group(&'t str, Vec<(usize, &'t Link)>),
+}
+
+/// A [`Status`] is the state of a conversation being walked. See also: [`Walker::status`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Status {
+    /// The current [`Node`] has several choices, one of which must be chosen.
+    AwaitingChoice,
+    /// The current [`Node`] has a single choice, which [`Walker::narrate`] follows automatically.
+    Narrating,
+    /// The current [`Node`] has no links or is an [`end`][`Node#structfield.end`], so the conversation has ended.
+    Finished,
+    /// The current [`Node`] has links, but none of them are available, e.g. because their conditions do not hold, so the conversation cannot go on until they are.
+    Blocked,
+    /// The conversation was interrupted by [`Walker::abort`].
+    Aborted,
+}
+
+/// An [`Outcome`] is the result of a finished conversation, so game code can branch on it. See also: [`Walker::finish`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Outcome {
+    /// The key of the [`Node`] the conversation ended at.
+    pub end_key: String,
+
+    /// The tags of the ending [`Node`].
+    pub tags: Vec<String>,
+
+    /// The number of steps taken to reach the end, i.e. the links followed, besides those followed automatically.
+    pub path_length: usize,
+
+    /// The final values of the variables assigned by links followed during the conversation, mapped by name.
+    pub variables: BTreeMap<String, Value>,
+}
+
+/// A [`Narrate`] is an [`Iterator`] over a linear segment of a conversation. See also: [`Walker::narrate`].
+#[derive(Debug)]
+pub struct Narrate<'w> {
+    tree: &'w Tree,
+    state: &'w mut State,
+    snapshot_limit: Option<usize>,
+    host: Host<'w>,
+    menu: Option<&'w MenuHandle>,
+    offers: &'w mut Vec<Link>,
+    journal: &'w mut Journal,
+    dice: &'w mut Dice,
+    breakpoints: &'w Breakpoints,
+    started: bool,
+    done: bool,
+}
+
+impl<'w> Iterator for Narrate<'w> {
+    type Item = &'w Node;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        // Follow the lone choice of the previously yielded node, if there is one and it is not an ending
+        let tree = self.tree;
+        let node = &tree.nodes[self.state.current_key.as_str()];
+        if self.started {
+            if node.end {
+                self.done = true;
+                return None;
+            }
+            self.journal.trace(node, &self.state.variables, self.host);
+            let links = available(tree, node, self.offers, &self.state.variables, self.host);
+            let followed = match links.as_slice() {
+                [link] => self
+                    .state
+                    .follow(tree, link, self.snapshot_limit, self.journal)
+                    .is_ok(),
+                _ => false,
+            };
+            if !followed {
+                self.done = true;
+                return None;
+            }
+            let next = self.state.settle(
+                tree,
+                self.snapshot_limit,
+                self.journal,
+                self.host,
+                self.dice,
+                self.breakpoints,
+            );
+            *self.offers = stock(next, self.menu);
+            return Some(next);
+        }
+
+        self.started = true;
+        Some(node)
+    }
+}
+
+#[cfg(test)]
+fn linear_tree() -> Tree {
+    // one -> two -> fork -> (three | four), where four ends
+    let mut tree = Tree::new();
+    let mut one = Node::new("one", "One.");
+    let mut two = Node::new("two", "Two.");
+    let mut fork = Node::new("fork", "Fork.");
+    let three = Node::new("three", "Three.");
+    let four = Node::new("four", "Four.");
+    Link::link(&mut one, &two, "Continue");
+    Link::link(&mut two, &fork, "Continue");
+    Link::link(&mut fork, &three, "Go to three");
+    Link::link(&mut fork, &four, "Go to four");
+    for node in [one, two, fork, three, four] {
+        tree.insert_node(node);
+    }
+    tree.set_root_key("one").unwrap();
+    tree
+}
+
+#[test]
+fn test_new() {
+    let mut tree = Tree::new();
+
+    // Should fail because the root is not set
+    assert!(matches!(
+        Walker::new(&tree).unwrap_err(),
+        TreeError::RootNotSet()
+    ));
+
+    // Should fail because the root does not exist
+    tree.raw().set_root_key("root");
+    assert!(matches!(
+        Walker::new(&tree).unwrap_err(),
+        TreeError::NodeDNE { .. }
+    ));
+
+    // Should pass
+    let tree = linear_tree();
+    let walker = Walker::new(&tree).unwrap();
+    assert_eq!("one", walker.current_key());
+}
+
+#[test]
+fn test_choose() {
+    let tree = linear_tree();
+    let mut walker = Walker::new(&tree).unwrap();
+
+    // Should fail because there is only one choice
+    assert!(matches!(
+        walker.choose(1).unwrap_err(),
+        TreeError::LinkDNE(1)
+    ));
+    assert_eq!("one", walker.current_key());
+
+    // Should pass
+    assert_eq!("Two.", walker.choose(0).unwrap().dialogue);
+    assert_eq!("two", walker.current_key());
+    assert!(!walker.is_finished());
+}
+
+#[test]
+fn test_narrate() {
+    let tree = linear_tree();
+    let mut walker = Walker::new(&tree).unwrap();
+
+    // Should stop at the fork
+    let keys: Vec<&str> = walker.narrate().map(|node| node.key.as_str()).collect();
+    assert_eq!(vec!["one", "two", "fork"], keys);
+    assert_eq!("fork", walker.current_key());
+    assert_eq!(2, walker.choices().len());
+
+    // Should only yield the fork because a choice is required
+    assert_eq!(1, walker.narrate().count());
+
+    // Should yield the ending, then stop
+    walker.choose(1).unwrap();
+    let keys: Vec<&str> = walker.narrate().map(|node| node.key.as_str()).collect();
+    assert_eq!(vec!["four"], keys);
+    assert!(walker.is_finished());
+}
+
+#[cfg(all(test, feature = "tracing", feature = "std"))]
+#[derive(Default)]
+struct Recorder {
+    spans: std::sync::Mutex<Vec<String>>,
+    events: std::sync::Mutex<Vec<String>>,
+}
+
+#[cfg(all(test, feature = "tracing", feature = "std"))]
+impl tracing::Subscriber for Recorder {
+    fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+        let mut spans = self.spans.lock().unwrap();
+        spans.push(span.metadata().name().to_owned());
+        tracing::span::Id::from_u64(spans.len() as u64)
+    }
+
+    fn record(&self, _: &tracing::span::Id, _: &tracing::span::Record<'_>) {}
+
+    fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+
+    fn event(&self, event: &tracing::Event<'_>) {
+        struct Message<'m>(&'m mut String);
+        impl tracing::field::Visit for Message<'_> {
+            fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+                if field.name() == "message" {
+                    *self.0 = format!("{:?}", value);
+                }
+            }
+        }
+        let mut message = String::new();
+        event.record(&mut Message(&mut message));
+        self.events.lock().unwrap().push(message);
+    }
+
+    fn enter(&self, _: &tracing::span::Id) {}
+
+    fn exit(&self, _: &tracing::span::Id) {}
+}
+
+#[cfg(all(feature = "tracing", feature = "std"))]
+#[test]
+fn test_tracing() {
+    let recorder = std::sync::Arc::new(Recorder::default());
+    tracing::subscriber::with_default(recorder.clone(), || {
+        let tree = crate::importer::import("examples/dialogue_files/ex_1.convo.yml").unwrap();
+        tree.lint();
+        let mut walker = Walker::new(&tree).unwrap();
+        walker.choose(0).unwrap();
+    });
+
+    let spans = recorder.spans.lock().unwrap();
+    assert_eq!(vec!["import", "source_to_tree", "lint"], *spans);
+    let events = recorder.events.lock().unwrap();
+    assert_eq!(
+        vec![
+            "imported tree",
+            "linted tree",
+            "conversation started",
+            "choice made",
+            "node entered"
+        ],
+        *events
+    );
+}
+
+#[test]
+fn test_filter() {
+    use crate::filter::MaskWords;
+
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "Darn, hello!");
+    let end = Node::new("end", "Goodbye!");
+    Link::link(&mut start, &end, "Darn, bye!");
+    tree.insert_node(start);
+    tree.insert_node(end);
+    tree.set_root_key("start").unwrap();
+
+    let mut walker = Walker::new(&tree).unwrap();
+    walker.set_filter(MaskWords::new(&["darn"], '*'));
+    assert_eq!("****, hello!", walker.dialogue());
+    assert_eq!(vec!["****, bye!"], walker.choice_dialogues());
+
+    // Clean dialogue is borrowed, and the filter follows the walker
+    let mut clone = walker.clone();
+    clone.choose(0).unwrap();
+    assert!(matches!(clone.dialogue(), Cow::Borrowed("Goodbye!")));
+    assert_eq!("Darn, hello!", tree.node("start").unwrap().dialogue);
+}
+
+#[test]
+fn test_variables() {
+    use crate::condition::Condition;
+
+    // start -> (paid -> end | end), where paying needs and spends gold
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "Pay the toll.");
+    let mut paid = Node::new("paid", "Thank you.");
+    let end = Node::new("end", "Goodbye.");
+    Link::link(&mut start, &paid, "Pay.");
+    Link::link(&mut start, &end, "Leave.");
+    Link::link(&mut paid, &end, "Bye.");
+    start.links[0].condition = Some(Condition::parse("gold >= 10").unwrap());
+    start.links[0].set.push(("gold".into(), Value::Int(0)));
+    start.links[0].set.push(("paid".into(), Value::Bool(true)));
+    for node in [start, paid, end] {
+        tree.insert_node(node);
+    }
+    tree.set_root_key("start").unwrap();
+
+    // Choices whose conditions fail are left out
+    let mut walker = Walker::new(&tree).unwrap();
+    assert_eq!(vec!["Leave."], walker.choice_dialogues());
+
+    // Choosing applies assignments, and narrating follows the lone choice
+    walker.variables_mut().insert("gold".into(), Value::Int(10));
+    assert_eq!(vec!["Pay.", "Leave."], walker.choice_dialogues());
+    walker.choose(0).unwrap();
+    assert_eq!(Some(&Value::Int(0)), walker.variables().get("gold"));
+    assert_eq!(Some(&Value::Bool(true)), walker.variables().get("paid"));
+    assert_eq!(2, walker.narrate().count());
+    assert!(walker.is_finished());
+    assert_eq!(["start", "paid"], walker.history());
+}
+
+#[test]
+fn test_bookmarks() {
+    let tree = linear_tree();
+    let mut walker = Walker::new(&tree).unwrap();
+    walker.narrate().for_each(drop);
+    walker.variables_mut().insert("hp".into(), Value::Int(10));
+    walker.bookmark("fork");
+
+    // Restoring returns to the node, history, and variables
+    walker.choose(1).unwrap();
+    walker.variables_mut().insert("hp".into(), Value::Int(0));
+    assert_eq!("Fork.", walker.restore("fork").unwrap().dialogue);
+    assert_eq!("fork", walker.current_key());
+    assert_eq!(["one", "two"], walker.history());
+    assert_eq!(Some(&Value::Int(10)), walker.variables().get("hp"));
+
+    // Bookmarks can be restored repeatedly, replaced, and removed
+    walker.choose(0).unwrap();
+    assert_eq!("fork", walker.restore("fork").unwrap().key);
+    walker.choose(0).unwrap();
+    walker.bookmark("fork");
+    walker.choose(0).unwrap_err();
+    assert_eq!("three", walker.restore("fork").unwrap().key);
+    assert!(walker.remove_bookmark("fork"));
+    assert!(!walker.remove_bookmark("fork"));
+    assert!(walker.restore("fork").is_none());
+    assert_eq!("three", walker.current_key());
+}
+
+#[test]
+fn test_rewind() {
+    let tree = linear_tree();
+    let mut walker = Walker::new(&tree).unwrap();
+    walker.narrate().for_each(drop);
+    walker.choose(1).unwrap();
+    assert_eq!(["one", "two", "fork"], walker.history());
+
+    // Should not move when rewinding too far or to unvisited nodes
+    assert!(walker.rewind_steps(4).is_none());
+    assert!(walker.rewind_to("three").is_none());
+    assert_eq!("four", walker.current_key());
+
+    // Should unwind history
+    assert_eq!("four", walker.rewind_steps(0).unwrap().key);
+    assert_eq!("four", walker.rewind_to("four").unwrap().key);
+    assert_eq!("fork", walker.step_back().unwrap().key);
+    assert_eq!(["one", "two"], walker.history());
+    assert_eq!("one", walker.rewind_to("one").unwrap().key);
+    assert!(walker.history().is_empty());
+    assert!(walker.step_back().is_none());
+
+    // Should rewind to the most recent visit of a cycle: a -> b -> (a | end)
+    let mut tree = Tree::new();
+    let mut a = Node::new("a", "A.");
+    let mut b = Node::new("b", "B.");
+    let end = Node::new("end", "End.");
+    Link::link(&mut a, &b, "To B");
+    Link::link(&mut b, &a, "To A");
+    Link::link(&mut b, &end, "To the end");
+    for node in [a, b, end] {
+        tree.insert_node(node);
+    }
+    tree.set_root_key("a").unwrap();
+    let mut walker = Walker::new(&tree).unwrap();
+    for choice in [0, 0, 0, 1] {
+        walker.choose(choice).unwrap();
+    }
+    assert_eq!("a", walker.rewind_to("a").unwrap().key);
+    assert_eq!(["a", "b"], walker.history());
+}
+
+#[test]
+fn test_snapshots() {
+    // one -> two -> fork -> (three | four), where each step adds to a counter
+    let mut tree = linear_tree();
+    for (i, node) in tree.nodes_mut().enumerate() {
+        for link in &mut node.links {
+            link.set.push(("steps".into(), Value::Int(i as i64 + 1)));
+        }
+    }
+    let steps = |walker: &Walker<&Tree>| walker.variables().get("steps").cloned();
+
+    // Without snapshots, only position is rewound
+    let mut walker = Walker::new(&tree).unwrap();
+    walker.narrate().for_each(drop);
+    walker.step_back();
+    assert_eq!(Some(Value::Int(2)), steps(&walker));
+
+    // With snapshots, variables are rewound too
+    let mut walker = Walker::new(&tree).unwrap();
+    walker.set_snapshot_limit(2);
+    walker.variables_mut().insert("hp".into(), Value::Int(1));
+    walker.narrate().for_each(drop);
+    walker.choose(0).unwrap();
+    assert_eq!(Some(Value::Int(3)), steps(&walker));
+    assert_eq!("fork", walker.step_back().unwrap().key);
+    assert_eq!(Some(Value::Int(2)), steps(&walker));
+    assert_eq!("two", walker.step_back().unwrap().key);
+    assert_eq!(Some(Value::Int(1)), steps(&walker));
+    assert_eq!(Some(&Value::Int(1)), walker.variables().get("hp"));
+
+    // Steps beyond the limit restore position only
+    assert_eq!("one", walker.step_back().unwrap().key);
+    assert_eq!(Some(Value::Int(1)), steps(&walker));
+
+    // Stepping without snapshots drops them, so older steps are never restored out of order
+    walker.narrate().for_each(drop);
+    walker.clear_snapshot_limit();
+    walker.choose(0).unwrap();
+    walker.set_snapshot_limit(2);
+    walker.rewind_steps(2);
+    assert_eq!(Some(Value::Int(3)), steps(&walker));
+
+    // Lowering the limit drops the oldest snapshots
+    let mut walker = Walker::new(&tree).unwrap();
+    walker.set_snapshot_limit(8);
+    walker.narrate().for_each(drop);
+    walker.set_snapshot_limit(1);
+    walker.rewind_steps(2);
+    assert_eq!(Some(Value::Int(2)), steps(&walker));
+}
+
+#[test]
+fn test_rewind_outcome() {
+    // start -> (left | right) -> end, where only the left link assigns `gold`
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "Which way?");
+    let mut left = Node::new("left", "You find gold.");
+    let right = Node::new("right", "You find nothing.");
+    let end = Node::new("end", "The road ends.");
+    Link::link(&mut start, &left, "Left.");
+    Link::link(&mut start, &right, "Right.");
+    start.links[0].set.push(("gold".into(), Value::Int(10)));
+    Link::link(&mut left, &end, "Go on.");
+    tree.extend_nodes(vec![start, left, right, end]).unwrap();
+    tree.set_root_key("start").unwrap();
+
+    // Variables assigned on an unwound path are not part of the outcome
+    let mut walker = Walker::new(&tree).unwrap();
+    walker.set_snapshot_limit(8);
+    walker.variables_mut().insert("gold".into(), Value::Int(5));
+    walker.choose(0).unwrap();
+    walker.rewind_to("start").unwrap();
+    walker.choose(1).unwrap();
+    let outcome = walker.finish().unwrap();
+    assert_eq!("right", outcome.end_key);
+    assert!(outcome.variables.is_empty());
+}
+
+#[test]
+fn test_finish() {
+    let mut tree = linear_tree();
+    tree.node_mut("four")
+        .unwrap()
+        .tags
+        .push("bad_ending".into());
+    tree.node_mut("two").unwrap().links[0]
+        .set
+        .push(("reached_fork".into(), Value::Bool(true)));
+    let mut walker = Walker::new(&tree).unwrap();
+    walker.variables_mut().insert("hp".into(), Value::Int(10));
+
+    // Should only report an outcome once finished
+    walker.narrate().for_each(drop);
+    assert_eq!(None, walker.finish());
+    walker.choose(1).unwrap();
+    let mut variables = BTreeMap::new();
+    variables.insert("reached_fork".into(), Value::Bool(true));
+    assert_eq!(
+        Some(Outcome {
+            end_key: "four".into(),
+            tags: vec!["bad_ending".into()],
+            path_length: 3,
+            variables,
+        }),
+        walker.finish()
+    );
+}
+
+#[test]
+fn test_status() {
+    let tree = linear_tree();
+    let mut walker = Walker::new(&tree).unwrap();
+    assert_eq!(Status::Narrating, walker.status());
+    walker.narrate().for_each(drop);
+    assert_eq!(Status::AwaitingChoice, walker.status());
+    walker.bookmark("fork");
+
+    // Aborting stops choices and narration
+    walker.abort("combat started");
+    assert_eq!(Status::Aborted, walker.status());
+    assert!(matches!(
+        walker.choose(0).unwrap_err(),
+        TreeError::Aborted(reason) if reason == "combat started"
+    ));
+    assert_eq!(0, walker.narrate().count());
+    assert_eq!(None, walker.finish());
+
+    // Restoring or rewinding resumes
+    walker.restore("fork").unwrap();
+    assert_eq!(Status::AwaitingChoice, walker.status());
+    walker.abort("combat started");
+    walker.rewind_steps(0).unwrap();
+    assert_eq!(None, walker.abort_reason());
+    walker.choose(0).unwrap();
+    assert_eq!(Status::Finished, walker.status());
+}
+
+#[test]
+fn test_status_blocked() {
+    use crate::condition::Condition;
+
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "The gate is shut.");
+    let end = Node::new("end", "You pass through.");
+    Link::link(&mut start, &end, "Open it.");
+    start.links[0].condition = Some(Condition::parse("has_key").unwrap());
+    tree.insert_node(start);
+    tree.insert_node(end);
+    tree.set_root_key("start").unwrap();
+
+    // A node whose only link is unavailable is not an ending
+    let mut walker = Walker::new(&tree).unwrap();
+    assert!(walker.choices().is_empty());
+    assert_eq!(Status::Blocked, walker.status());
+    assert!(!walker.is_finished());
+    assert_eq!(None, walker.finish());
+
+    // It goes on once the link is available
+    walker
+        .variables_mut()
+        .insert("has_key".into(), Value::Bool(true));
+    assert_eq!(Status::Narrating, walker.status());
+    walker.choose(0).unwrap();
+    assert_eq!(Status::Finished, walker.status());
+    assert_eq!("end", walker.finish().unwrap().end_key);
+}
+
+#[test]
+fn test_status_end() {
+    // start -> ending -> epilogue, where the ending is marked as one
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "The dragon falls.");
+    let mut ending = Node::new("ending", "The kingdom is saved.");
+    let epilogue = Node::new("epilogue", "Years later...");
+    Link::link(&mut start, &ending, "Continue");
+    Link::link(&mut ending, &epilogue, "Continue");
+    ending.end = true;
+    tree.extend_nodes(vec![start, ending, epilogue]).unwrap();
+    tree.set_root_key("start").unwrap();
+
+    // Narration stops at an ending, even though it has a link
+    let mut walker = Walker::new(&tree).unwrap();
+    let keys: Vec<&str> = walker.narrate().map(|node| node.key.as_str()).collect();
+    assert_eq!(vec!["start", "ending"], keys);
+    assert_eq!(1, walker.choices().len());
+    assert_eq!(Status::Finished, walker.status());
+    assert!(walker.is_finished());
+    assert_eq!("ending", walker.finish().unwrap().end_key);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_observer() {
+    use crate::{observer::Observer, stage::StageDirection};
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Default)]
+    struct Events(Mutex<Vec<String>>);
+    impl Observer for Arc<Events> {
+        fn on_node_entered(&self, node: &Node) {
+            self.0.lock().unwrap().push(format!("entered {}", node.key));
+        }
+
+        fn on_stage_direction(&self, node: &Node, direction: &StageDirection) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("{}: {}", node.key, direction));
+        }
+
+        fn on_conversation_aborted(&self, reason: &str, transcript: &[TranscriptEntry]) {
+            let entries = transcript.len();
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("aborted: {} after {} entries", reason, entries));
+        }
+    }
+
+    let mut tree = linear_tree();
+    tree.node_mut("two").unwrap().stage = vec![
+        StageDirection::new("camera", vec!["close_up".into()]),
+        StageDirection::new("fade_out", vec![]),
+    ];
+    let events = Arc::new(Events::default());
+    let mut walker = Walker::new(&tree).unwrap();
+    walker.set_observer(events.clone());
+    walker.narrate().for_each(drop);
+    walker.choose(0).unwrap();
+    walker.step_back().unwrap();
+    walker.abort("combat started");
+    assert_eq!(
+        vec![
+            "entered two",
+            "two: camera close_up",
+            "two: fade_out",
+            "entered fork",
+            "entered three",
+            "entered fork",
+            "aborted: combat started after 5 entries"
+        ],
+        *events.0.lock().unwrap()
+    );
+
+    // The transcript records choices and the abort, and is never unwound
+    let transcript: Vec<(&str, Option<&str>, Option<&str>)> = walker
+        .transcript()
+        .iter()
+        .map(|entry| {
+            (
+                entry.node_key.as_str(),
+                entry.choice.as_deref(),
+                entry.aborted.as_deref(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        vec![
+            ("one", Some("Continue"), None),
+            ("two", Some("Continue"), None),
+            ("fork", Some("Go to three"), None),
+            ("three", None, None),
+            ("fork", None, Some("combat started")),
+        ],
+        transcript
+    );
+
+    // Without an observer, only the transcript is recorded
+    walker.clear_observer();
+    walker.restore("missing");
+    walker.rewind_to("one").unwrap();
+    assert_eq!(7, events.0.lock().unwrap().len());
+    assert_eq!(6, walker.transcript().len());
+}
+
+#[test]
+fn test_host_state() {
+    // one -> two -> fork -> (three | four), where three is only available on rainy days
+    let mut tree = linear_tree();
+    tree.node_mut("three").unwrap().availability = crate::Availability {
+        cooldown: Some(10),
+        required_tags: vec!["raining".into()],
+        ..crate::Availability::default()
+    };
+    let mut walker = Walker::new(&tree).unwrap();
+    walker.narrate().for_each(drop);
+    assert_eq!("fork", walker.current_key());
+    assert_eq!(2, walker.choices().len());
+
+    // Unavailable nodes are not offered, which may leave a lone choice to narrate
+    walker.set_host_state(HostState::default());
+    assert_eq!(
+        vec!["four"],
+        walker
+            .choices()
+            .iter()
+            .map(|l| &l.to_key)
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(Status::Narrating, walker.status());
+    assert!(matches!(walker.choose(1), Err(TreeError::LinkDNE(1))));
+
+    // Node cooldowns are keyed by node key
+    let state = walker.host_state_mut().unwrap();
+    state.tags.push("raining".into());
+    state.time = 15;
+    state.last_used.insert("three".into(), 10);
+    assert_eq!(1, walker.choices().len());
+    walker.host_state_mut().unwrap().time = 20;
+    assert_eq!(2, walker.choices().len());
+    walker.host_state_mut().unwrap().tags.clear();
+    walker.clear_host_state();
+    assert_eq!(2, walker.choices().len());
+}
+
+#[test]
+fn test_menu() {
+    // fork -> (a | b | c | d), where a and c are grouped, and b is hidden by its condition
+    let mut tree = Tree::new();
+    let mut fork = Node::new("fork", "Fork.");
+    for (key, group) in [
+        ("a", Some("Ask")),
+        ("b", Some("Ask")),
+        ("c", Some("Ask")),
+        ("d", None),
+    ] {
+        let node = Node::new(key, key);
+        Link::link(&mut fork, &node, key);
+        fork.links.last_mut().unwrap().group = group.map(Into::into);
+        tree.insert_node(node);
+    }
+    fork.links[1].condition = Some(crate::Condition::parse("never").unwrap());
+    fork.links[3].priority = Some(1);
+    tree.insert_node(fork);
+    tree.set_root_key("fork").unwrap();
+
+    // Indices follow the choices, and groups appear where their first choice would
+    let mut walker = Walker::new(&tree).unwrap();
+    assert_eq!(
+        vec![
+            MenuItem::Group(
+                "Ask",
+                vec![
+                    (0, &tree.nodes["fork"].links[0]),
+                    (1, &tree.nodes["fork"].links[2])
+                ]
+            ),
+            MenuItem::Choice(2, &tree.nodes["fork"].links[3]),
+        ],
+        walker.menu()
+    );
+    let mut by_priority = tree.clone();
+    by_priority.set_link_order(crate::LinkOrder::Priority);
+    let priority_walker = Walker::new(&by_priority).unwrap();
+    assert!(matches!(priority_walker.menu()[0], MenuItem::Choice(0, _)));
+    assert_eq!("c", walker.choose(1).unwrap().key);
+    assert!(walker.menu().is_empty());
+}
+
+#[test]
+fn test_presented_choices() {
+    use crate::Condition;
+
+    // start -> (a [gold] disabled | b [gold] hidden | c | d), where d is unavailable
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "Start.");
+    for key in ["a", "b", "c", "d"] {
+        let node = Node::new(key, key);
+        Link::link(&mut start, &node, key);
+        tree.insert_node(node);
+    }
+    for link in &mut start.links[..2] {
+        link.condition = Some(Condition::parse("gold").unwrap());
+    }
+    start.links[0].when_unmet = WhenUnmet::Disabled("Needs gold".into());
+    tree.node_mut("d").unwrap().availability.min_day = Some(1);
+    tree.insert_node(start);
+    tree.set_root_key("start").unwrap();
+    let mut walker = Walker::new(&tree).unwrap();
+    walker.set_host_state(HostState::default());
+    let keys = |walker: &Walker<&Tree>| {
+        walker
+            .presented_choices()
+            .iter()
+            .map(|choice| match choice {
+                PresentedChoice::Enabled(i, link) => alloc::format!("{}:{}", i, link.to_key),
+                PresentedChoice::Disabled(link, reason) => {
+                    alloc::format!("{}:{}", link.to_key, reason)
+                }
+            })
+            .collect::<Vec<_>>()
+    };
+
+    // Disabled links are presented, but take no index among the choices
+    assert_eq!(vec!["a:Needs gold", "0:c"], keys(&walker));
+    walker
+        .variables_mut()
+        .insert("gold".into(), Value::Bool(true));
+    assert_eq!(vec!["0:a", "1:b", "2:c"], keys(&walker));
+    assert_eq!("b", walker.choose(1).unwrap().key);
+}
+
+#[test]
+fn test_random() {
+    use crate::NodeKind;
+
+    // start -> roll (random) -> (a | b | never), where a and b lead back to start
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "Start.");
+    let mut roll = Node::new("roll", "");
+    roll.kind = NodeKind::Random { avoid_recent: 1 };
+    Link::link(&mut start, &roll, "Roll");
+    for key in ["a", "b", "never"] {
+        let mut node = Node::new(key, key);
+        Link::link(&mut roll, &node, key);
+        Link::link(&mut node, &start, "Again");
+        tree.insert_node(node);
+    }
+    roll.links[0].set.push(("rolled".into(), Value::Bool(true)));
+    roll.links[2].weight = Some(0);
+    tree.insert_node(start);
+    tree.insert_node(roll);
+    tree.set_root_key("start").unwrap();
+
+    // Random nodes are passed through without entering the history, and never repeat the last pick
+    let mut walker = Walker::new(&tree).unwrap();
+    let mut picks = Vec::new();
+    for _ in 0..10 {
+        let key = walker.choose(0).unwrap().key.clone();
+        assert_eq!(["start"], walker.history());
+        assert_eq!(
+            "roll",
+            walker.transcript()[walker.transcript().len() - 2].node_key
+        );
+        assert_eq!("start", walker.step_back().unwrap().key);
+        picks.push(key);
+    }
+    assert!(picks.windows(2).all(|pair| pair[0] != pair[1]));
+    assert!(!picks.contains(&"never".to_owned()));
+
+    // Links followed automatically assign their variables
+    let mut walker = Walker::new(&tree).unwrap();
+    while walker.choose(0).unwrap().key != "a" {
+        walker.choose(0).unwrap();
+    }
+    assert_eq!(Some(&Value::Bool(true)), walker.variables().get("rolled"));
+
+    // Equal seeds pick equal links
+    let picks = |seed| {
+        let mut walker = Walker::new(&tree).unwrap();
+        walker.set_seed(seed);
+        (0..8)
+            .map(|_| {
+                let key = walker.choose(0).unwrap().key.clone();
+                walker.choose(0).unwrap();
+                key
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(picks(11), picks(11));
+
+    // A random root is passed through, and a random node without available links is rested at
+    let mut tree = tree.clone();
+    tree.set_root_key("roll").unwrap();
+    let walker = Walker::new(&tree).unwrap();
+    assert!(["a", "b"].contains(&walker.current_key()));
+    for link in &mut tree.node_mut("roll").unwrap().links {
+        link.weight = Some(0);
+    }
+    assert_eq!("roll", Walker::new(&tree).unwrap().current_key());
+
+    // A cycle of random nodes rests rather than looping forever
+    let mut tree = Tree::new();
+    let mut ping = Node::new("ping", "");
+    let mut pong = Node::new("pong", "");
+    ping.kind = NodeKind::Random { avoid_recent: 0 };
+    pong.kind = NodeKind::Random { avoid_recent: 0 };
+    Link::link(&mut ping, &pong, "");
+    Link::link(&mut pong, &ping, "");
+    tree.insert_node(ping);
+    tree.insert_node(pong);
+    tree.set_root_key("ping").unwrap();
+    assert_eq!("ping", Walker::new(&tree).unwrap().current_key());
+}
+
+#[test]
+fn test_switch() {
+    use crate::{Condition, NodeKind};
+
+    // start -> route (switch on faction) -> (rebel | empire | neutral)
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "Start.");
+    let mut route = Node::new("route", "");
+    route.kind = NodeKind::Switch {
+        variable: "faction".into(),
+    };
+    Link::link(&mut start, &route, "Enter");
+    for key in ["rebel", "empire", "neutral"] {
+        let node = Node::new(key, key);
+        Link::link(&mut route, &node, "");
+        tree.insert_node(node);
+    }
+    route.links[0].condition = Some(Condition::parse("faction == \"rebels\"").unwrap());
+    route.links[1].condition = Some(Condition::parse("faction == \"empire\"").unwrap());
+    tree.insert_node(start);
+    tree.insert_node(route.clone());
+    tree.set_root_key("start").unwrap();
+
+    // Switches follow the first matching case, or the default, without entering the history
+    for (faction, key) in [
+        ("rebels", "rebel"),
+        ("empire", "empire"),
+        ("pirates", "neutral"),
+    ] {
+        let mut walker = Walker::new(&tree).unwrap();
+        walker
+            .variables_mut()
+            .insert("faction".into(), Value::String(faction.into()));
+        assert_eq!(key, walker.choose(0).unwrap().key);
+        assert_eq!(["start"], walker.history());
+    }
+
+    // A switch without a matching case or default is rested at
+    route.links.pop();
+    tree.insert_node(route);
+    let mut walker = Walker::new(&tree).unwrap();
+    assert_eq!("route", walker.choose(0).unwrap().key);
+}
+
+#[test]
+fn test_breakpoints() {
+    use crate::{
+        debugger::{Break, Debugger, Resume},
+        NodeKind,
+    };
+    use std::sync::{Arc, Mutex};
+
+    // Records each break as the node key and history, and resumes with the queued resumptions
+    #[derive(Default, Clone)]
+    struct Script {
+        breaks: Arc<Mutex<Vec<String>>>,
+        resumes: Arc<Mutex<VecDeque<Resume>>>,
+    }
+    impl Debugger for Script {
+        fn on_break(&self, context: Break<'_>) -> Resume {
+            let entry = format!("{} < {}", context.node.key, context.history.join(" < "));
+            self.breaks.lock().unwrap().push(entry);
+            context
+                .variables
+                .insert("debugged".into(), Value::Bool(true));
+            self.resumes.lock().unwrap().pop_front().unwrap_or_default()
+        }
+    }
+
+    // one -> two -> fork -> (three | four), where four ends
+    let mut tree = linear_tree();
+    let script = Script::default();
+    let mut walker = Walker::new(&tree).unwrap();
+    walker.add_breakpoint("two");
+    walker.add_breakpoint("fork");
+
+    // Without a debugger, breakpoints do nothing
+    assert_eq!("two", walker.choose(0).unwrap().key);
+    assert!(walker.variables().is_empty());
+    assert!(walker.remove_breakpoint("two"));
+    walker.step_back();
+
+    // Continuing changes nothing but the variables the debugger sets
+    walker.set_debugger(script.clone());
+    assert_eq!("two", walker.choose(0).unwrap().key);
+    assert_eq!("fork", walker.choose(0).unwrap().key);
+    assert_eq!(vec!["fork < one < two"], *script.breaks.lock().unwrap());
+    assert_eq!(Some(&Value::Bool(true)), walker.variables().get("debugged"));
+
+    // Returning to a breakpoint does not break
+    assert_eq!("three", walker.choose(0).unwrap().key);
+    assert_eq!("fork", walker.step_back().unwrap().key);
+    assert_eq!(1, script.breaks.lock().unwrap().len());
+
+    // A forced link is followed as a step, even if its condition does not hold
+    tree.node_mut("fork").unwrap().links[1].condition =
+        Some(crate::Condition::parse("never").unwrap());
+    let mut walker = Walker::new(&tree).unwrap();
+    walker.set_debugger(script.clone());
+    walker.add_breakpoint("fork");
+    script.resumes.lock().unwrap().push_back(Resume::Follow(1));
+    assert_eq!("two", walker.choose(0).unwrap().key);
+    assert_eq!("four", walker.choose(0).unwrap().key);
+    assert_eq!(["one", "two", "fork"], walker.history());
+    assert_eq!("fork", walker.step_back().unwrap().key);
+
+    // Links which do not exist continue instead
+    walker.step_back();
+    script.resumes.lock().unwrap().push_back(Resume::Follow(9));
+    assert_eq!("fork", walker.choose(0).unwrap().key);
+
+    // A forced link leaves an automatic node through it instead
+    let mut tree = linear_tree();
+    tree.node_mut("two").unwrap().kind = NodeKind::Random { avoid_recent: 0 };
+    tree.node_mut("two")
+        .unwrap()
+        .links
+        .push(Link::new("four", "Skip"));
+    let mut walker = Walker::new(&tree).unwrap();
+    walker.set_debugger(script.clone());
+    walker.add_breakpoint("two");
+    script.resumes.lock().unwrap().push_back(Resume::Follow(1));
+    assert_eq!("four", walker.choose(0).unwrap().key);
+    assert_eq!(["one"], walker.history());
+}
+
+#[test]
+fn test_condition_trace() {
+    use crate::{Condition, NodeKind};
+
+    // start -> route (switch on gold) -> (rich | poor)
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "Start.");
+    let mut route = Node::new("route", "");
+    route.kind = NodeKind::Switch {
+        variable: "gold".into(),
+    };
+    Link::link(&mut start, &route, "Enter");
+    start.links[0].condition = Some(Condition::parse("!banned").unwrap());
+    for key in ["rich", "poor"] {
+        let node = Node::new(key, key);
+        Link::link(&mut route, &node, "");
+        tree.insert_node(node);
+    }
+    route.links[0].condition = Some(Condition::parse("gold >= 10 && gold < 100").unwrap());
+    tree.insert_node(start);
+    tree.insert_node(route);
+    tree.set_root_key("start").unwrap();
+
+    // Nothing is traced until the trace is enabled
+    let mut walker = Walker::new(&tree).unwrap();
+    walker.variables_mut().insert("gold".into(), Value::Int(5));
+    assert_eq!("poor", walker.choose(0).unwrap().key);
+    assert!(walker.condition_trace().is_empty());
+
+    // Conditions are traced at choices and automatic nodes, with the transcript entry of the visit
+    walker.step_back();
+    walker.set_condition_trace(true);
+    assert_eq!("poor", walker.choose(0).unwrap().key);
+    let trace: Vec<_> = walker
+        .condition_trace()
+        .iter()
+        .map(|e| (e.step, e.node_key.as_str(), e.link_index, e.holds))
+        .collect();
+    assert_eq!(vec![(3, "start", 0, true), (4, "route", 0, false)], trace);
+    assert_eq!("start", walker.transcript()[3].node_key);
+    let inputs = &walker.condition_trace()[1].inputs;
+    assert_eq!(1, inputs.len());
+    assert_eq!(Some(&Some(Value::Int(5))), inputs.get("gold"));
+    assert_eq!(
+        Some(&None),
+        walker.condition_trace()[0].inputs.get("banned")
+    );
+
+    // Disabling the trace discards it
+    walker.set_condition_trace(false);
+    assert!(walker.condition_trace().is_empty());
+}
+
+#[test]
+fn test_reload() {
+    use crate::SharedTree;
+
+    // start -> middle -> end
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "Hello!");
+    start.links.push(Link::new("middle", "Go on."));
+    let mut middle = Node::new("middle", "And then?");
+    middle.links.push(Link::new("end", "Finish."));
+    tree.insert_node(start);
+    tree.insert_node(middle);
+    tree.insert_node(Node::new("end", "The end."));
+    tree.set_root_key("start").unwrap();
+    let mut shared = SharedTree::new(tree);
+
+    let mut walker = shared.walker().unwrap();
+    walker.set_snapshot_limit(4);
+    walker.bookmark("beginning");
+    walker.choose(0).unwrap();
+    walker.bookmark("middle");
+    walker.choose(0).unwrap();
+    assert_eq!(&["start", "middle"], walker.history());
+
+    // Unchanged trees keep everything
+    assert!(walker.reload(shared.clone()));
+    assert_eq!(&["start", "middle"], walker.history());
+
+    // Removing a visited node forgets it and every step before it
+    let mut edited = (*shared).clone();
+    edited.insert_node(Node::new("start", "Welcome!"));
+    edited
+        .node_mut("start")
+        .unwrap()
+        .links
+        .push(Link::new("end", "Skip."));
+    edited.remove_node("middle");
+    edited.set_root_key("start").unwrap();
+    let diff = shared.edit(|tree| tree.update(edited));
+    assert_eq!(vec!["start"], diff.changed);
+    assert_eq!(vec!["middle"], diff.removed);
+    assert!(walker.reload(shared.clone()));
+    assert!(walker.history().is_empty());
+    assert!(walker.step_back().is_none());
+    assert!(walker.restore("middle").is_none());
+    assert_eq!("Welcome!", walker.restore("beginning").unwrap().dialogue);
+
+    // Changing the current node leaves the walker on its old tree
+    shared.edit(|tree| tree.node_mut("start").unwrap().dialogue = "Hi!".to_owned());
+    assert!(!walker.reload(shared.clone()));
+    assert_eq!("Welcome!", walker.current_node().dialogue);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_reload_menu() {
+    use crate::{menu::MenuEntry, SharedTree};
+    use std::sync::{Arc, Mutex};
+
+    // shop -> (entries... | end)
+    let mut tree = Tree::new();
+    let mut shop = Node::new("shop", "What'll it be?");
+    shop.kind = NodeKind::Menu {
+        source: "shop_stock".into(),
+    };
+    shop.links.push(Link::new("end", "Buy"));
+    shop.links.push(Link::new("end", "Nothing, thanks."));
+    tree.insert_node(shop);
+    tree.insert_node(Node::new("end", "Come again!"));
+    tree.set_root_key("shop").unwrap();
+    let mut shared = SharedTree::new(tree);
+
+    let shelf = Arc::new(Mutex::new(vec![MenuEntry::new("potion", "Potion")]));
+    let mut walker = shared.walker().unwrap();
+    let stock = shelf.clone();
+    walker.set_menu(move |_: &str| stock.lock().unwrap().clone());
+    assert_eq!(
+        vec!["Potion", "Nothing, thanks."],
+        walker.choice_dialogues()
+    );
+
+    // Reloading at a menu node offers its entries again
+    shelf.lock().unwrap().push(MenuEntry::new("sword", "Sword"));
+    shared.edit(|tree| tree.node_mut("end").unwrap().dialogue = "Farewell!".to_owned());
+    assert!(walker.reload(shared.clone()));
+    assert_eq!(
+        vec!["Potion", "Sword", "Nothing, thanks."],
+        walker.choice_dialogues()
+    );
+    assert_eq!("Farewell!", walker.choose(1).unwrap().dialogue);
+}
+
+#[test]
+fn test_migrate() {
+    // start -> shop -> end
+    let mut old = Tree::new();
+    let mut start = Node::new("start", "Hello!");
+    start.links.push(Link::new("shop", "Trade."));
+    let mut shop = Node::new("shop", "Buying?");
+    shop.links.push(Link::new("end", "Bye."));
+    old.insert_node(start);
+    old.insert_node(shop);
+    old.insert_node(Node::new("end", "Goodbye!"));
+    old.set_root_key("start").unwrap();
+    old.assign_ids().unwrap();
+
+    // The shop is renamed, and the start is replaced by a node with a new id
+    let mut new = old.clone();
+    let mut store = new.remove_node("shop").unwrap();
+    store.key = "store".to_owned();
+    new.insert_node(store);
+    let mut start = new.remove_node("start").unwrap();
+    start.id = None;
+    start.links[0].to_key = "store".to_owned();
+    new.insert_node(start);
+    new.set_root_key("start").unwrap();
+    new.assign_ids().unwrap();
+    let migration = Migration::new(&old, &new);
+    assert_eq!(
+        vec![("shop", "store")],
+        migration.renamed().collect::<Vec<_>>()
+    );
+    assert_eq!(vec!["start"], migration.removed().collect::<Vec<_>>());
+
+    let mut walker = Walker::new(&old).unwrap();
+    walker.bookmark("beginning");
+    walker.choose(0).unwrap();
+    walker.bookmark("shopping");
+    walker.add_breakpoint("shop");
+    walker.choose(0).unwrap();
+    assert!(walker.migrate(&new, &migration));
+    assert_eq!("end", walker.current_key());
+    assert_eq!(&["store"], walker.history());
+    assert_eq!(vec!["store"], walker.breakpoints().collect::<Vec<_>>());
+    assert!(walker.restore("beginning").is_none());
+    assert_eq!("store", walker.restore("shopping").unwrap().key);
+    assert!(walker.history().is_empty());
+
+    // Walkers resting at removed nodes stay on their old tree
+    let mut walker = Walker::new(&old).unwrap();
+    assert!(!walker.migrate(&new, &migration));
+    assert_eq!(Some(1), walker.current_node().id);
+}
+
+#[test]
+fn test_excluded_tags() {
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "Hello!");
+    let mut spoiler = Link::new("start", "How does it end?");
+    spoiler.tags.push("spoiler".into());
+    start.links.push(spoiler);
+    start.links.push(Link::new("joke", "Tell me a joke."));
+    start.links.push(Link::new("start", "Hello again."));
+    let mut joke = Node::new("joke", "A rude joke.");
+    joke.tags.push("mature".into());
+    tree.insert_node(start);
+    tree.insert_node(joke);
+    tree.set_root_key("start").unwrap();
+
+    let mut walker = Walker::new(&tree).unwrap();
+    assert_eq!(3, walker.choices().len());
+
+    // Links carrying excluded tags, or leading to nodes which do, are hidden
+    walker.set_host_state(HostState {
+        excluded_tags: vec!["mature".into(), "spoiler".into()],
+        ..HostState::default()
+    });
+    let choices: Vec<&str> = walker.choices().iter().map(|link| link.text()).collect();
+    assert_eq!(vec!["Hello again."], choices);
+    assert_eq!(1, walker.presented_choices().len());
+}
+
+#[test]
+fn test_clock_conditions() {
+    use crate::Condition;
+
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "Can I help you?");
+    let mut shop = Link::new("start", "What are you selling?");
+    shop.condition = Some(Condition::parse("hour in 9..17 && day >= 3").unwrap());
+    start.links.push(shop);
+    let mut night = Link::new("start", "Can't sleep?");
+    night.condition = Some(Condition::parse("hour in 22..6").unwrap());
+    start.links.push(night);
+    start.links.push(Link::new("start", "Bye."));
+    tree.insert_node(start);
+    tree.set_root_key("start").unwrap();
+    let dialogue = |walker: &Walker<&Tree>| -> Vec<String> {
+        walker
+            .choices()
+            .iter()
+            .map(|link| link.text().to_owned())
+            .collect()
+    };
+
+    // Without a host state, clock variables are not set
+    let mut walker = Walker::new(&tree).unwrap();
+    walker.set_condition_trace(true);
+    assert_eq!(vec!["Bye."], dialogue(&walker));
+
+    walker.set_host_state(HostState {
+        day: 3,
+        hour: 9,
+        ..HostState::default()
+    });
+    assert_eq!(vec!["What are you selling?", "Bye."], dialogue(&walker));
+    walker.host_state_mut().unwrap().hour = 23;
+    assert_eq!(vec!["Can't sleep?", "Bye."], dialogue(&walker));
+
+    // Traced inputs hold the clock values which were read
+    walker.choose(1).unwrap();
+    let inputs = &walker.condition_trace()[1].inputs;
+    assert_eq!(Some(&Some(Value::Int(23))), inputs.get("hour"));
+    assert!(walker.condition_trace()[1].holds);
+
+    // Conversation variables shadow the clock
+    walker.variables_mut().insert("hour".into(), Value::Int(12));
+    assert_eq!(vec!["What are you selling?", "Bye."], dialogue(&walker));
+}
+
+#[test]
+fn test_affinity() {
+    use crate::Condition;
+
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "Hello!");
+    let mut compliment = Link::new("start", "Nice hat.");
+    compliment.affinity.push(("npc_bob".into(), 15));
+    start.links.push(compliment);
+    let mut secret = Link::new("start", "Tell me a secret.");
+    secret.condition = Some(Condition::parse("affinity(npc_bob) >= 20").unwrap());
+    start.links.push(secret);
+    tree.insert_node(start);
+    tree.set_root_key("start").unwrap();
+
+    let mut walker = Walker::new(&tree).unwrap();
+    assert_eq!(0, walker.affinity("npc_bob"));
+    assert_eq!(1, walker.choices().len());
+
+    // Links adjust affinities as they are followed
+    walker.choose(0).unwrap();
+    assert_eq!(15, walker.affinity("npc_bob"));
+    assert_eq!(1, walker.choices().len());
+    walker.choose(0).unwrap();
+    assert_eq!(30, walker.affinity("npc_bob"));
+    assert_eq!(2, walker.choices().len());
+    assert!(walker.state.assigned.contains("affinity.npc_bob"));
+
+    // Hosts adjust affinities directly
+    assert_eq!(10, walker.adjust("npc_bob", -20));
+    assert_eq!(1, walker.choices().len());
+    assert_eq!(
+        Some(&Value::Int(10)),
+        walker.variables().get("affinity.npc_bob")
+    );
+}
+
+#[test]
+fn test_inventory() {
+    use crate::{Condition, HostState};
+    use std::sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    };
+
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "A locked door.");
+    let mut unlock = Link::new("start", "Unlock it.");
+    unlock.condition = Some(Condition::parse("item(rusty_key)").unwrap());
+    start.links.push(unlock);
+    let mut shoot = Link::new("start", "Shoot the lock.");
+    shoot.condition = Some(Condition::parse("item(arrows) >= 10 && hour < 12").unwrap());
+    start.links.push(shoot);
+    tree.insert_node(start);
+    tree.set_root_key("start").unwrap();
+
+    let arrows = Arc::new(AtomicU32::new(12));
+    let mut walker = Walker::new(&tree).unwrap();
+    assert!(walker.choices().is_empty());
+
+    // The inventory is read as choices are listed, alongside the clock
+    let held = Arc::clone(&arrows);
+    walker.set_inventory(move |item: &str| match item {
+        "rusty_key" => 1,
+        "arrows" => held.load(Ordering::Relaxed),
+        _ => 0,
+    });
+    walker.set_host_state(HostState {
+        hour: 9,
+        ..HostState::default()
+    });
+    assert_eq!(2, walker.choices().len());
+    arrows.store(3, Ordering::Relaxed);
+    assert_eq!(1, walker.choices().len());
+    assert_eq!("Unlock it.", walker.choose(0).unwrap().links[0].text());
+
+    // Clones share the inventory
+    let mut clone = walker.clone();
+    assert_eq!(1, clone.choices().len());
+    clone.clear_inventory();
+    assert!(clone.choices().is_empty());
+    assert_eq!(1, walker.choices().len());
+}
+
+#[test]
+fn test_menu_entries() {
+    use crate::{menu::MenuEntry, Condition};
+    use std::sync::{Arc, Mutex};
+
+    // start -> shop [menu: Buy (template), Leave] -> sold -> shop
+    let mut tree = Tree::new();
+    let mut start = Node::new("start", "Welcome.");
+    let mut shop = Node::new("shop", "What'll it be?");
+    let mut sold = Node::new("sold", "Sold!");
+    let leave = Node::new("leave", "Come again.");
+    shop.kind = NodeKind::Menu {
+        source: "stock".into(),
+    };
+    Link::link(&mut start, &shop, "Let's trade.");
+    Link::link(&mut shop, &sold, "Buy");
+    Link::link(&mut shop, &leave, "Leave.");
+    Link::link(&mut sold, &shop, "Anything else?");
+    shop.links[0].condition = Some(Condition::parse("gold >= 5").unwrap());
+    shop.links[1].priority = Some(1);
+    for node in [start, shop, sold, leave] {
+        tree.insert_node(node);
+    }
+    tree.set_root_key("start").unwrap();
+    tree.set_link_order(crate::LinkOrder::Priority);
+
+    let stock = Arc::new(Mutex::new(vec![
+        MenuEntry::new("potion", "Potion"),
+        MenuEntry::new("sword", "Sword"),
+    ]));
+    let shelf = Arc::clone(&stock);
+    let mut walker = Walker::new(&tree).unwrap();
+    walker.set_menu(move |source: &str| match source {
+        "stock" => shelf.lock().unwrap().clone(),
+        _ => Vec::new(),
+    });
+    walker.variables_mut().insert("gold".into(), Value::Int(10));
+    walker.choose(0).unwrap();
+
+    // Entries take the place of the template, and share its condition
+    assert_eq!(vec!["Leave.", "Potion", "Sword"], walker.choice_dialogues());
+    walker.variables_mut().insert("gold".into(), Value::Int(1));
+    assert_eq!(vec!["Leave."], walker.choice_dialogues());
+    walker.variables_mut().insert("gold".into(), Value::Int(10));
+
+    // Choosing an entry follows the template and assigns the entry's id
+    assert_eq!("Sold!", walker.choose(2).unwrap().dialogue);
+    assert_eq!(
+        Some(&Value::from("sword")),
+        walker.variables().get("menu.stock")
+    );
+    assert!(walker.state.assigned.contains("menu.stock"));
+
+    // Entries are read again as the menu is entered
+    stock.lock().unwrap().remove(1);
+    walker.choose(0).unwrap();
+    assert_eq!(vec!["Leave.", "Potion"], walker.choice_dialogues());
+    stock.lock().unwrap().clear();
+    assert_eq!(2, walker.choices().len());
+    walker.restock();
+    assert_eq!(vec!["Leave."], walker.choice_dialogues());
+
+    // Stepping back to a menu reads its entries again
+    stock
+        .lock()
+        .unwrap()
+        .push(MenuEntry::new("shield", "Shield"));
+    walker.rewind_to("start").unwrap();
+    assert!(walker.offers.is_empty());
+    walker.choose(0).unwrap();
+    assert_eq!(vec!["Leave.", "Shield"], walker.choice_dialogues());
+    walker.choose(1).unwrap();
+    stock
+        .lock()
+        .unwrap()
+        .push(MenuEntry::new("helmet", "Helmet"));
+    walker.step_back().unwrap();
+    assert_eq!(
+        vec!["Leave.", "Shield", "Helmet"],
+        walker.choice_dialogues()
+    );
+
+    // Without a provider, menus offer only their other links
+    walker.clear_menu();
+    assert_eq!(vec!["Leave."], walker.choice_dialogues());
+}
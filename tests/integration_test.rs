@@ -16,8 +16,8 @@ fn test_print() {
     Link::link(&mut node1, &node2, "Sorry, I'm in a hurry!");
 
     // Populate the tree
-    tree.nodes.insert(node1.key.clone(), node1);
-    tree.nodes.insert(node2.key.clone(), node2);
+    tree.insert_node(node1);
+    tree.insert_node(node2);
 
     // Set node1 as root
     tree.set_root_key(root_key).unwrap();
@@ -26,14 +26,16 @@ fn test_print() {
     println!("Node root: [{}]", tree.root_key().unwrap());
 
     // Print them all out
-    for (_, node) in &tree.nodes {
-        println!("Node [{}]: '{}'", node.key, node.dialogue);
+    for node in tree.nodes() {
+        println!("Node [{}]: '{}'", node.key(), node.dialogue);
         for link in &node.links {
-            let linked_search = tree.nodes.get(&link.to_key);
+            let linked_search = tree.node(&link.to_key);
             if let Some(link_node) = linked_search {
                 println!(
                     "Link [{}]->[{}]: '{}'",
-                    node.key, link_node.key, link.dialogue
+                    node.key(),
+                    link_node.key(),
+                    link.text()
                 );
             }
         }